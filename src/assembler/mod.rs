@@ -0,0 +1,200 @@
+use super::*;
+use std::str;
+
+#[cfg(test)]
+mod tests;
+
+/// Collects JSON values from input arriving in arbitrary pieces, e.g. fragmented
+/// WebSocket text frames or reads from a socket, and hands out every value as
+/// soon as its last byte arrives. Values may be separated by whitespace or
+/// nothing at all (`{}{}`), and pieces may split them anywhere, even inside a
+/// UTF-8 character. A value that fails to parse is reported and skipped, so the
+/// values after it still come through. Error positions are byte offsets from
+/// the start of the input.
+pub struct MessageAssembler {
+    options: ParserOptions,
+    buffer: Vec<u8>,
+    // stream offset of `buffer[0]`
+    offset: usize,
+    // bytes of `buffer` already scanned
+    scanned: usize,
+    // start of the value being assembled in `buffer`
+    start: Option<usize>,
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    // the value is a number or literal, which ends at the next delimiter
+    scalar: bool,
+    // the value went over `max_input_bytes` and is dropped as it's read
+    skipping: bool,
+}
+
+impl MessageAssembler {
+    pub fn new() -> MessageAssembler {
+        return MessageAssembler::with_options(&ParserOptions::default());
+    }
+
+    /// `max_input_bytes` of the options limits the size of every value, so a
+    /// peer can't make the assembler buffer without bound.
+    pub fn with_options(options: &ParserOptions) -> MessageAssembler {
+        return MessageAssembler {
+            options: *options,
+            buffer: vec![],
+            offset: 0,
+            scanned: 0,
+            start: None,
+            depth: 0,
+            in_string: false,
+            escaped: false,
+            scalar: false,
+            skipping: false,
+        };
+    }
+
+    /// Adds the next piece of input, returning the values it completed in order.
+    pub fn push(&mut self, fragment: &[u8]) -> Vec<Result<JSONValue, JSONParseError>> {
+        let mut values = vec![];
+        self.buffer.extend_from_slice(fragment);
+        while self.scanned < self.buffer.len() {
+            let i = self.scanned;
+            let b = self.buffer[i];
+            self.scanned += 1;
+            let start = match self.start {
+                Some(start) => start,
+                None => {
+                    if !is_whitespace(b) {
+                        self.begin(i, b, &mut values);
+                    }
+                    continue;
+                }
+            };
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if b == b'\\' {
+                    self.escaped = true;
+                } else if b == b'"' {
+                    self.in_string = false;
+                    if self.depth == 0 {
+                        self.complete(start, i + 1, &mut values);
+                    }
+                }
+            } else if self.scalar {
+                if is_whitespace(b) || b"{}[],:\"".contains(&b) {
+                    // the delimiter may start the next value
+                    self.scanned = i;
+                    self.complete(start, i, &mut values);
+                }
+            } else {
+                match b {
+                    b'"' => self.in_string = true,
+                    b'{' | b'[' => self.depth += 1,
+                    b'}' | b']' => {
+                        self.depth -= 1;
+                        if self.depth == 0 {
+                            self.complete(start, i + 1, &mut values);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            if let (Some(start), Some(limit)) = (self.start, self.options.max_input_bytes) {
+                if !self.skipping && self.scanned - start > limit {
+                    values.push(Err(JSONParseError {
+                        reason: format!(
+                            "Maximum input size in bytes of {} exceeded at position {}",
+                            limit,
+                            self.offset + self.scanned
+                        ),
+                        position: Some(self.offset + self.scanned),
+                        kind: JSONParseErrorKind::LimitExceeded,
+                    }));
+                    self.skipping = true;
+                }
+            }
+        }
+        self.compact();
+        return values;
+    }
+
+    /// Ends the input: returns a value still waiting for its delimiter, like
+    /// a number at the very end, or an error if a value is incomplete.
+    pub fn finish(&mut self) -> Option<Result<JSONValue, JSONParseError>> {
+        let mut values = vec![];
+        if let Some(start) = self.start {
+            let end = self.buffer.len();
+            self.complete(start, end, &mut values);
+        }
+        self.compact();
+        return values.pop();
+    }
+
+    /// Whether part of a value has arrived but not all of it
+    pub fn is_pending(&self) -> bool {
+        return self.start.is_some();
+    }
+
+    fn begin(&mut self, i: usize, b: u8, values: &mut Vec<Result<JSONValue, JSONParseError>>) {
+        self.start = Some(i);
+        match b {
+            b'{' | b'[' => self.depth = 1,
+            b'"' => self.in_string = true,
+            // a stray closing bracket is a value of its own, for the parser to reject
+            b'}' | b']' => self.complete(i, i + 1, values),
+            _ => self.scalar = true,
+        }
+    }
+
+    fn complete(
+        &mut self,
+        start: usize,
+        end: usize,
+        values: &mut Vec<Result<JSONValue, JSONParseError>>,
+    ) {
+        if !self.skipping {
+            let position = self.offset + start;
+            let result = str::from_utf8(&self.buffer[start..end])
+                .map_err(|e| JSONParseError {
+                    reason: "Value is not valid UTF-8".to_owned(),
+                    position: Some(position + e.valid_up_to()),
+                    kind: JSONParseErrorKind::Syntax,
+                })
+                .and_then(|text| {
+                    JSONValue::parse_with(text, &self.options).map_err(|mut err| {
+                        err.position = err.position.map(|p| p + position);
+                        err
+                    })
+                });
+            values.push(result);
+        }
+        self.start = None;
+        self.depth = 0;
+        self.in_string = false;
+        self.escaped = false;
+        self.scalar = false;
+        self.skipping = false;
+    }
+
+    // Drops the bytes no longer needed: everything before the pending value,
+    // and everything of a value that's being skipped
+    fn compact(&mut self) {
+        let keep = match self.start {
+            Some(start) if !self.skipping => start,
+            _ => self.scanned,
+        };
+        self.buffer.drain(..keep);
+        self.offset += keep;
+        self.scanned -= keep;
+        self.start = self.start.map(|start| start.saturating_sub(keep));
+    }
+}
+
+impl Default for MessageAssembler {
+    fn default() -> MessageAssembler {
+        return MessageAssembler::new();
+    }
+}
+
+fn is_whitespace(b: u8) -> bool {
+    return b == b' ' || b == b'\t' || b == b'\n' || b == b'\r';
+}
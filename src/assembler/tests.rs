@@ -0,0 +1,68 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_fragments() {
+    let input = "{\"a\": \"x}\\\"]\"} [1, [2]]{}\n\"é\" true -1.5e3 null";
+    let expected = vec![
+        parse("{\"a\": \"x}\\\"]\"}"),
+        parse("[1, [2]]"),
+        parse("{}"),
+        parse("\"é\""),
+        parse("true"),
+        parse("-1.5e3"),
+    ];
+    // every split point, including ones inside the two-byte é
+    for size in 1..input.len() + 1 {
+        println!("Checking pieces of {}", size);
+        let mut assembler = MessageAssembler::new();
+        let mut values = vec![];
+        for piece in input.as_bytes().chunks(size) {
+            values.extend(assembler.push(piece).into_iter().map(|v| v.unwrap()));
+        }
+        assert_eq!(values, expected);
+        assert!(assembler.is_pending());
+        assert_eq!(assembler.finish().unwrap().unwrap(), JSONValue::JSONNull());
+        assert!(!assembler.is_pending());
+        assert!(assembler.finish().is_none());
+    }
+}
+
+#[test]
+fn test_errors() {
+    let mut assembler = MessageAssembler::new();
+    let results = assembler.push(b"[1,] {\"a\": 1} ] [tru]");
+    let positions: Vec<Option<usize>> = results
+        .iter()
+        .map(|r| r.as_ref().err().and_then(|err| err.position))
+        .collect();
+    assert_eq!(positions, vec![Some(3), None, Some(14), Some(20)]);
+    assert_eq!(*results[1].as_ref().unwrap(), parse("{\"a\": 1}"));
+
+    let results = assembler.push(b" \"\xff\" [");
+    assert_eq!(
+        results[0].as_ref().unwrap_err().reason,
+        "Value is not valid UTF-8"
+    );
+    assert_eq!(results[0].as_ref().unwrap_err().position, Some(23));
+    assert!(assembler.finish().unwrap().is_err());
+
+    let options = ParserOptions {
+        max_input_bytes: Some(8),
+        ..ParserOptions::default()
+    };
+    let mut assembler = MessageAssembler::with_options(&options);
+    let mut results = assembler.push(b"[1, 2, 3, ");
+    results.extend(assembler.push(b"\"]\", 4] [5]"));
+    assert_eq!(results.len(), 2);
+    let err = results[0].as_ref().unwrap_err();
+    assert_eq!(err.kind, JSONParseErrorKind::LimitExceeded);
+    assert_eq!(err.position, Some(9));
+    assert_eq!(*results[1].as_ref().unwrap(), parse("[5]"));
+    assert!(assembler.buffer.is_empty());
+}
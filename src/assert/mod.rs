@@ -0,0 +1,185 @@
+use super::*;
+use pointer::escape_token;
+use serializer::SerializerConfig;
+use std::borrow::Cow;
+
+/// Asserts that two values are equal, comparing `JSONValue`s or JSON text.
+/// On failure the panic message lists every difference with its JSON Pointer
+/// instead of dumping both values.
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert::assert_json_matches(&$actual, &$expected, $crate::assert::Mode::Equal)
+    };
+}
+
+/// Asserts that `actual` contains `expected`: objects may have extra members
+/// and arrays extra trailing items, everything in `expected` must match.
+#[macro_export]
+macro_rules! assert_json_include {
+    ($actual:expr, $expected:expr $(,)?) => {
+        $crate::assert::assert_json_matches(&$actual, &$expected, $crate::assert::Mode::Include)
+    };
+}
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Equal,
+    /// Extra object members and array items in the actual value are fine
+    Include,
+}
+
+/// Either side of a JSON assertion: a value or JSON text.
+pub trait AsJSON {
+    /// Panics if the text isn't valid JSON.
+    fn as_json(&self) -> Cow<'_, JSONValue>;
+}
+
+impl AsJSON for JSONValue {
+    fn as_json(&self) -> Cow<'_, JSONValue> {
+        return self.expand_raw();
+    }
+}
+
+impl AsJSON for str {
+    fn as_json(&self) -> Cow<'_, JSONValue> {
+        match self.parse() {
+            Ok(value) => return Cow::Owned(value),
+            Err(e) => panic!("Invalid JSON in assertion: {}", e),
+        }
+    }
+}
+
+impl AsJSON for String {
+    fn as_json(&self) -> Cow<'_, JSONValue> {
+        return self.as_str().as_json();
+    }
+}
+
+impl<T: AsJSON + ?Sized> AsJSON for &T {
+    fn as_json(&self) -> Cow<'_, JSONValue> {
+        return (**self).as_json();
+    }
+}
+
+/// Differences between the values, one line per path, empty if they match.
+pub fn json_mismatches(actual: &JSONValue, expected: &JSONValue, mode: Mode) -> Vec<String> {
+    return json_mismatches_with(actual, expected, mode, 0.0);
+}
+
+/// Like `json_mismatches`, with numbers matching when they differ by at most `tolerance`.
+pub fn json_mismatches_with(
+    actual: &JSONValue,
+    expected: &JSONValue,
+    mode: Mode,
+    tolerance: f64,
+) -> Vec<String> {
+    let mut comparison = Comparison {
+        mode,
+        tolerance,
+        result: vec![],
+    };
+    comparison.compare(actual, expected, &mut String::new());
+    return comparison.result;
+}
+
+#[track_caller]
+pub fn assert_json_matches<A, E>(actual: &A, expected: &E, mode: Mode)
+where
+    A: AsJSON + ?Sized,
+    E: AsJSON + ?Sized,
+{
+    let mismatches = json_mismatches(&actual.as_json(), &expected.as_json(), mode);
+    if !mismatches.is_empty() {
+        let what = match mode {
+            Mode::Equal => "JSON values are not equal",
+            Mode::Include => "JSON value doesn't include the expected one",
+        };
+        panic!("{}:\n  {}", what, mismatches.join("\n  "));
+    }
+}
+
+struct Comparison {
+    mode: Mode,
+    tolerance: f64,
+    result: Vec<String>,
+}
+
+impl Comparison {
+    fn compare(&mut self, actual: &JSONValue, expected: &JSONValue, path: &mut String) {
+        match (actual, expected) {
+            (JSONValue::JSONObject(actual_map), JSONValue::JSONObject(expected_map)) => {
+                let mut keys: Vec<&String> = expected_map.keys().collect();
+                if self.mode == Mode::Equal {
+                    keys.extend(
+                        actual_map
+                            .keys()
+                            .filter(|key| !expected_map.contains_key(*key)),
+                    );
+                }
+                keys.sort();
+                let base = path.len();
+                for key in keys {
+                    path.push('/');
+                    path.push_str(&escape_token(key));
+                    match (actual_map.get(key), expected_map.get(key)) {
+                        (Some(a), Some(e)) => self.compare(a, e, path),
+                        (None, Some(e)) => {
+                            self.result
+                                .push(format!("{}: missing, expected {}", at(path), show(e)))
+                        }
+                        (Some(a), None) => {
+                            self.result
+                                .push(format!("{}: unexpected {}", at(path), show(a)))
+                        }
+                        (None, None) => (),
+                    }
+                    path.truncate(base);
+                }
+            }
+            (JSONValue::JSONArray(actual_items), JSONValue::JSONArray(expected_items)) => {
+                let length_differs = match self.mode {
+                    Mode::Equal => actual_items.len() != expected_items.len(),
+                    Mode::Include => actual_items.len() < expected_items.len(),
+                };
+                if length_differs {
+                    self.result.push(format!(
+                        "{}: expected {} items, got {}",
+                        at(path),
+                        expected_items.len(),
+                        actual_items.len()
+                    ));
+                }
+                let base = path.len();
+                for (i, (a, e)) in actual_items.iter().zip(expected_items.iter()).enumerate() {
+                    path.push_str(&format!("/{}", i));
+                    self.compare(a, e, path);
+                    path.truncate(base);
+                }
+            }
+            (&JSONValue::JSONNumber(a), &JSONValue::JSONNumber(e))
+                if (a - e).abs() <= self.tolerance => {}
+            _ => {
+                if actual != expected {
+                    self.result.push(format!(
+                        "{}: expected {}, got {}",
+                        at(path),
+                        show(expected),
+                        show(actual)
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn at(path: &str) -> &str {
+    return if path.is_empty() { "(root)" } else { path };
+}
+
+fn show(value: &JSONValue) -> String {
+    return value.to_string_with(&SerializerConfig::compact());
+}
@@ -0,0 +1,76 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_mismatches() {
+    for s in vec![
+        ("1", "1", Mode::Equal, vec![]),
+        ("1", "2", Mode::Equal, vec!["(root): expected 2, got 1"]),
+        (
+            r#"{"a": {"b": [1, 2]}, "c": "x", "d": null}"#,
+            r#"{"a": {"b": [1, 3, 4]}, "c": "x", "e/f": true}"#,
+            Mode::Equal,
+            vec![
+                "/a/b: expected 3 items, got 2",
+                "/a/b/1: expected 3, got 2",
+                "/d: unexpected null",
+                "/e~1f: missing, expected true",
+            ],
+        ),
+        (
+            r#"{"a": {"b": [1, 2, 3], "x": 1}, "c": "x"}"#,
+            r#"{"a": {"b": [1, 2]}}"#,
+            Mode::Include,
+            vec![],
+        ),
+        (
+            r#"{"a": [{"id": 1, "name": "a"}]}"#,
+            r#"{"a": [{"id": 2}, {"id": 3}]}"#,
+            Mode::Include,
+            vec!["/a: expected 2 items, got 1", "/a/0/id: expected 2, got 1"],
+        ),
+        (
+            "[1]",
+            r#"{"a": 1}"#,
+            Mode::Include,
+            vec![r#"(root): expected {"a":1}, got [1]"#],
+        ),
+    ] {
+        println!("Checking {} {}", s.0, s.1);
+        assert_eq!(json_mismatches(&parse(s.0), &parse(s.1), s.2), s.3);
+    }
+}
+
+#[test]
+fn test_macros() {
+    let value = parse(r#"{"id": 7, "tags": ["a", "b"]}"#);
+    assert_json_eq!(value, r#"{"tags": ["a", "b"], "id": 7}"#);
+    assert_json_eq!(&value, value.clone());
+    assert_json_include!(value, r#"{"tags": ["a"]}"#);
+    assert_json_include!(r#"{"a": 1, "b": 2}"#, String::from(r#"{"b": 2}"#),);
+}
+
+#[test]
+#[should_panic(expected = "JSON values are not equal:\n  /id: expected 8, got 7")]
+fn test_assert_json_eq_fails() {
+    assert_json_eq!(parse(r#"{"id": 7}"#), r#"{"id": 8}"#);
+}
+
+#[test]
+#[should_panic(
+    expected = "JSON value doesn't include the expected one:\n  /b: missing, expected 2"
+)]
+fn test_assert_json_include_fails() {
+    assert_json_include!(r#"{"a": 1}"#, r#"{"b": 2}"#);
+}
+
+#[test]
+#[should_panic(expected = "Invalid JSON in assertion")]
+fn test_invalid_text() {
+    assert_json_eq!("{", "{}");
+}
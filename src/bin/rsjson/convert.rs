@@ -0,0 +1,103 @@
+use input::{read_input, render_error, Input};
+use rsjson::ndjson::{parse_ndjson, to_ndjson};
+use rsjson::serializer::SerializerConfig;
+use rsjson::JSONValue;
+use {usage_error, EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+
+const FORMATS: &str = "array, ndjson";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Format {
+    // a single JSON array of records
+    Array,
+    // one record per line
+    Ndjson,
+}
+
+/// `rsjson convert --from FORMAT --to FORMAT [--pretty] [FILE]`:
+/// re-encodes a sequence of records between container formats.
+pub fn run(args: &[String]) -> i32 {
+    let mut from = None;
+    let mut to = None;
+    let mut pretty = false;
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--from" | "--to" => {
+                let format = match args.next().map(|f| parse_format(f)) {
+                    Some(Ok(format)) => format,
+                    Some(Err(message)) => return usage_error(&message),
+                    None => return usage_error(&format!("{} requires a format", arg)),
+                };
+                if arg == "--from" {
+                    from = Some(format);
+                } else {
+                    to = Some(format);
+                }
+            }
+            "--pretty" => pretty = true,
+            _ => paths.push(arg.clone()),
+        }
+    }
+    let (from, to) = match (from, to) {
+        (Some(from), Some(to)) => (from, to),
+        _ => return usage_error("convert requires --from and --to"),
+    };
+    if paths.len() > 1 {
+        return usage_error("convert accepts at most one file");
+    }
+    let input = match read_input(paths.first().map_or("-", |p| p.as_str())) {
+        Ok(input) => input,
+        Err(message) => {
+            eprintln!("{}", message);
+            return EXIT_USAGE;
+        }
+    };
+    let records = match read_records(&input, from) {
+        Ok(records) => records,
+        Err(message) => {
+            eprint!("{}", message);
+            return EXIT_FAILURE;
+        }
+    };
+    match to {
+        Format::Array => {
+            let config = if pretty {
+                SerializerConfig::pretty(2)
+            } else {
+                SerializerConfig::compact()
+            };
+            let array = JSONValue::JSONArray(records.into_iter().map(Box::new).collect());
+            println!("{}", array.to_string_with(&config));
+        }
+        Format::Ndjson => print!("{}", to_ndjson(&records)),
+    }
+    return EXIT_OK;
+}
+
+fn parse_format(name: &str) -> Result<Format, String> {
+    match name {
+        "array" => return Ok(Format::Array),
+        "ndjson" | "jsonl" => return Ok(Format::Ndjson),
+        _ => {
+            return Err(format!(
+                "Unsupported format {} (supported: {})",
+                name, FORMATS
+            ))
+        }
+    }
+}
+
+fn read_records(input: &Input, format: Format) -> Result<Vec<JSONValue>, String> {
+    match format {
+        Format::Ndjson => {
+            return parse_ndjson(&input.text).map_err(|err| render_error(input, &err));
+        }
+        Format::Array => match input.text.parse() {
+            Ok(JSONValue::JSONArray(items)) => return Ok(items.into_iter().map(|i| *i).collect()),
+            Ok(_) => return Err(format!("{}: expected a top-level array\n", input.name)),
+            Err(err) => return Err(render_error(input, &err)),
+        },
+    }
+}
@@ -0,0 +1,103 @@
+use input::{read_input, render_error};
+use rsjson::diff::diff;
+use rsjson::patch::{patch_to_json, PatchOperation};
+use rsjson::serializer::SerializerConfig;
+use rsjson::JSONValue;
+use std::env;
+use std::io::{self, IsTerminal};
+use {usage_error, EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const RESET: &str = "\x1b[0m";
+
+/// `rsjson diff [--patch] [--no-color] A B`: prints the differences between two documents
+/// and fails if there are any, so it can gate scripts like `diff` does.
+pub fn run(args: &[String]) -> i32 {
+    let mut as_patch = false;
+    let mut color = io::stdout().is_terminal() && env::var_os("NO_COLOR").is_none();
+    let mut paths = vec![];
+    for arg in args {
+        match arg.as_str() {
+            "--patch" => as_patch = true,
+            "--no-color" => color = false,
+            _ => paths.push(arg.clone()),
+        }
+    }
+    if paths.len() != 2 {
+        return usage_error("diff requires exactly two files");
+    }
+    let mut documents = vec![];
+    for path in &paths {
+        let input = match read_input(path) {
+            Ok(input) => input,
+            Err(message) => {
+                eprintln!("{}", message);
+                return EXIT_USAGE;
+            }
+        };
+        match input.text.parse::<JSONValue>() {
+            Ok(value) => documents.push(value),
+            Err(err) => {
+                eprint!("{}", render_error(&input, &err));
+                return EXIT_USAGE;
+            }
+        }
+    }
+    let patch = diff(&documents[0], &documents[1]);
+    if as_patch {
+        // sorted keys happen to give the conventional op, path, value order
+        let config = SerializerConfig {
+            sort_keys: true,
+            ..SerializerConfig::pretty(2)
+        };
+        println!("{}", patch_to_json(&patch).to_string_with(&config));
+    } else {
+        for op in &patch {
+            println!("{}", describe(op, &documents[0], color));
+        }
+    }
+    if patch.is_empty() {
+        return EXIT_OK;
+    }
+    return EXIT_FAILURE;
+}
+
+fn describe(op: &PatchOperation, original: &JSONValue, color: bool) -> String {
+    let old = || compact(original.pointer(op.path()));
+    let (marker, line) = match *op {
+        PatchOperation::Add { ref value, .. } => (
+            GREEN,
+            format!("+ {}: {}", display_path(op), compact(Some(value))),
+        ),
+        PatchOperation::Remove { .. } => (RED, format!("- {}: {}", display_path(op), old())),
+        PatchOperation::Replace { ref value, .. } => (
+            YELLOW,
+            format!(
+                "~ {}: {} -> {}",
+                display_path(op),
+                old(),
+                compact(Some(value))
+            ),
+        ),
+        _ => ("", format!("{:?}", op)),
+    };
+    if color {
+        return format!("{}{}{}", marker, line, RESET);
+    }
+    return line;
+}
+
+fn display_path(op: &PatchOperation) -> &str {
+    match op.path() {
+        "" => return "/",
+        path => return path,
+    }
+}
+
+fn compact(value: Option<&JSONValue>) -> String {
+    return value
+        .map(|v| v.to_string_with(&SerializerConfig::compact()))
+        .unwrap_or_default();
+}
@@ -0,0 +1,260 @@
+use query::read_document;
+use rsjson::keypaths::KeyPathIndex;
+use rsjson::pointer::{escape_token, parse_pointer};
+use rsjson::query::Query;
+use rsjson::serializer::SerializerConfig;
+use rsjson::JSONValue;
+use std::io::{self, BufRead, IsTerminal, Write};
+use {usage_error, EXIT_OK};
+
+const HELP: &str = "Commands:
+    ls [PATH]       List the children of the current (or given) value
+    cd PATH         Move to PATH: /absolute, relative/path, .. or a unique key prefix
+    cat [PATH]      Pretty-print the current (or given) value
+    paths [PREFIX]  List the key paths below the current value (or starting with
+                    PREFIX) and the kinds of their values; array items are *
+    pwd             Print the JSON Pointer of the current value
+    q EXPRESSION    Run a jq-style expression against the current value
+    help            Show this message
+    quit            Leave the explorer";
+
+const SUMMARY_WIDTH: usize = 60;
+
+/// `rsjson explore FILE`: an interactive prompt for walking through a document.
+pub fn run(args: &[String]) -> i32 {
+    if args.len() != 1 {
+        return usage_error("explore requires exactly one file");
+    }
+    let document = match read_document(&args[0]) {
+        Ok(document) => document,
+        Err(code) => return code,
+    };
+    let mut explorer = Explorer::new(&document);
+    // the prompt is noise when commands are piped in
+    let interactive = io::stdin().is_terminal();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            print!("{}> ", explorer.pwd());
+            let _ = io::stdout().flush();
+        }
+        let line = match lines.next() {
+            Some(Ok(line)) => line,
+            _ => return EXIT_OK,
+        };
+        match explorer.execute(&line) {
+            Ok(Some(output)) => println!("{}", output),
+            Ok(None) => (),
+            Err(Quit) => return EXIT_OK,
+        }
+    }
+}
+
+pub struct Quit;
+
+/// Navigation state of the explorer, kept separate from terminal I/O.
+pub struct Explorer<'a> {
+    document: &'a JSONValue,
+    // unescaped tokens of the current location
+    location: Vec<String>,
+    paths: KeyPathIndex,
+}
+
+impl<'a> Explorer<'a> {
+    pub fn new(document: &'a JSONValue) -> Explorer<'a> {
+        return Explorer {
+            document,
+            location: vec![],
+            paths: KeyPathIndex::new(document),
+        };
+    }
+
+    pub fn pwd(&self) -> String {
+        if self.location.is_empty() {
+            return "/".to_owned();
+        }
+        return pointer_of(&self.location);
+    }
+
+    /// Runs one command line, returning the text to show, if any.
+    pub fn execute(&mut self, line: &str) -> Result<Option<String>, Quit> {
+        let line = line.trim();
+        let (command, argument) = match line.find(char::is_whitespace) {
+            Some(i) => (&line[..i], line[i..].trim()),
+            None => (line, ""),
+        };
+        let result = match command {
+            "" => return Ok(None),
+            "quit" | "exit" => return Err(Quit),
+            "help" => Ok(HELP.to_owned()),
+            "pwd" => Ok(self.pwd()),
+            "ls" => self.ls(argument),
+            "cat" => self.resolve(argument).map(|tokens| {
+                self.value_at(&tokens)
+                    .to_string_with(&SerializerConfig::pretty(2))
+            }),
+            "cd" => self.resolve(argument).map(|tokens| {
+                self.location = tokens;
+                String::new()
+            }),
+            "q" => self.query(argument),
+            "paths" => Ok(self.list_paths(argument)),
+            _ => Err(format!("Unknown command {}, try help", command)),
+        };
+        match result {
+            Ok(ref output) if output.is_empty() => return Ok(None),
+            Ok(output) => return Ok(Some(output)),
+            Err(message) => return Ok(Some(format!("error: {}", message))),
+        }
+    }
+
+    fn ls(&self, argument: &str) -> Result<String, String> {
+        let tokens = self.resolve(argument)?;
+        let lines: Vec<String> = children(self.value_at(&tokens))
+            .into_iter()
+            .map(|(name, child)| format!("{}\t{}", name, summarize(child)))
+            .collect();
+        if lines.is_empty() {
+            return Ok(summarize(self.value_at(&tokens)));
+        }
+        return Ok(lines.join("\n"));
+    }
+
+    fn list_paths(&self, prefix: &str) -> String {
+        // the location as a key path, with array indices as `*`
+        let mut path = String::new();
+        let mut value = self.document;
+        for token in &self.location {
+            let escaped = format!("/{}", escape_token(token));
+            match *value {
+                JSONValue::JSONArray(_) => path.push_str("/*"),
+                _ => path.push_str(&escaped),
+            }
+            value = value.pointer(&escaped).unwrap_or(value);
+        }
+        let prefix = if prefix.starts_with('/') {
+            prefix.to_owned()
+        } else {
+            format!("{}/{}", path, prefix)
+        };
+        let lines: Vec<String> = self
+            .paths
+            .complete(&prefix)
+            .into_iter()
+            .map(|(path, info)| {
+                let kinds: Vec<&str> = info.kinds.iter().map(|kind| kind.name()).collect();
+                return format!("{}\t{}", path, kinds.join("|"));
+            })
+            .collect();
+        return lines.join("\n");
+    }
+
+    fn query(&self, expression: &str) -> Result<String, String> {
+        let query = Query::compile(expression).map_err(|err| err.reason)?;
+        let outputs = query
+            .run(self.value_at(&self.location))
+            .map_err(|err| err.reason)?;
+        let lines: Vec<String> = outputs
+            .iter()
+            .map(|output| output.to_string_with(&SerializerConfig::compact()))
+            .collect();
+        return Ok(lines.join("\n"));
+    }
+
+    fn value_at(&self, tokens: &[String]) -> &'a JSONValue {
+        // every stored location was resolved against the document
+        return self
+            .document
+            .pointer(&pointer_of(tokens))
+            .unwrap_or(self.document);
+    }
+
+    /// Resolves a path relative to the current location. Each segment may be
+    /// a unique prefix of a key, which is how the explorer completes paths.
+    fn resolve(&self, path: &str) -> Result<Vec<String>, String> {
+        let (mut tokens, segments) = if path.starts_with('/') {
+            (vec![], parse_pointer(path))
+        } else {
+            (self.location.clone(), parse_pointer(&format!("/{}", path)))
+        };
+        let segments = segments.unwrap_or_default();
+        for segment in segments {
+            match segment.as_str() {
+                "" | "." => continue,
+                ".." => {
+                    tokens.pop();
+                    continue;
+                }
+                _ => (),
+            }
+            let names: Vec<String> = children(self.value_at(&tokens))
+                .into_iter()
+                .map(|(name, _)| name)
+                .collect();
+            if names.contains(&segment) {
+                tokens.push(segment);
+                continue;
+            }
+            let candidates: Vec<&String> = names
+                .iter()
+                .filter(|name| name.starts_with(segment.as_str()))
+                .collect();
+            match candidates.len() {
+                0 => return Err(format!("No {} in {}", segment, pointer_of(&tokens))),
+                1 => tokens.push(candidates[0].clone()),
+                _ => {
+                    let candidates: Vec<&str> = candidates.iter().map(|c| c.as_str()).collect();
+                    return Err(format!(
+                        "{} is ambiguous: {}",
+                        segment,
+                        candidates.join(", ")
+                    ));
+                }
+            }
+        }
+        return Ok(tokens);
+    }
+}
+
+fn pointer_of(tokens: &[String]) -> String {
+    return tokens
+        .iter()
+        .map(|token| format!("/{}", escape_token(token)))
+        .collect();
+}
+
+// Object members sorted by key, or array elements by index
+fn children(value: &JSONValue) -> Vec<(String, &JSONValue)> {
+    match *value {
+        JSONValue::JSONObject(ref map) => {
+            let mut members: Vec<(String, &JSONValue)> =
+                map.iter().map(|(k, v)| (k.clone(), &**v)).collect();
+            members.sort_by(|a, b| a.0.cmp(&b.0));
+            return members;
+        }
+        JSONValue::JSONArray(ref items) => {
+            return items
+                .iter()
+                .enumerate()
+                .map(|(i, v)| (i.to_string(), &**v))
+                .collect();
+        }
+        _ => return vec![],
+    }
+}
+
+// One-line description: sizes for containers, truncated compact JSON for scalars
+fn summarize(value: &JSONValue) -> String {
+    match *value {
+        JSONValue::JSONObject(ref map) => return format!("{{{} keys}}", map.len()),
+        JSONValue::JSONArray(ref items) => return format!("[{} items]", items.len()),
+        _ => (),
+    }
+    let text = value.to_string_with(&SerializerConfig::compact());
+    if text.chars().count() <= SUMMARY_WIDTH {
+        return text;
+    }
+    let truncated: String = text.chars().take(SUMMARY_WIDTH).collect();
+    return format!("{}...", truncated);
+}
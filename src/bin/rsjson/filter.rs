@@ -0,0 +1,60 @@
+use rsjson::ndjson::NdjsonReader;
+use rsjson::query::Query;
+use rsjson::serializer::SerializerConfig;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Write};
+use {usage_error, EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+
+/// `rsjson filter EXPRESSION [FILE]`: runs a jq-style expression over every NDJSON record,
+/// reading one line at a time and printing each output as a compact line.
+/// Invalid records are reported and skipped.
+pub fn run(args: &[String]) -> i32 {
+    let (expression, path) = match args {
+        [expression] => (expression, None),
+        [expression, path] => (expression, Some(path)),
+        _ => return usage_error("filter requires an expression and at most one file"),
+    };
+    let query = match Query::compile(expression) {
+        Ok(query) => query,
+        Err(err) => return usage_error(&format!("Invalid expression: {}", err.reason)),
+    };
+    let stdin = io::stdin();
+    let (name, reader): (&str, Box<dyn BufRead>) = match path.map(|p| p.as_str()) {
+        None | Some("-") => ("<stdin>", Box::new(stdin.lock())),
+        Some(path) => match File::open(path) {
+            Ok(file) => (path, Box::new(BufReader::new(file))),
+            Err(e) => {
+                eprintln!("Unable to read {}: {}", path, e);
+                return EXIT_USAGE;
+            }
+        },
+    };
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    let config = SerializerConfig::compact();
+    let mut code = EXIT_OK;
+    for record in NdjsonReader::new(reader) {
+        let outputs = record
+            .map_err(|err| match err.position {
+                Some(position) => format!("{} at byte {}: {}", name, position, err.reason),
+                None => format!("{}: {}", name, err.reason),
+            })
+            .and_then(|value| {
+                query
+                    .run(&value)
+                    .map_err(|err| format!("{}: {}", name, err.reason))
+            });
+        match outputs {
+            Ok(outputs) => {
+                for output in outputs {
+                    let _ = writeln!(out, "{}", output.to_string_with(&config));
+                }
+            }
+            Err(message) => {
+                eprintln!("error: {}", message);
+                code = EXIT_FAILURE;
+            }
+        }
+    }
+    return code;
+}
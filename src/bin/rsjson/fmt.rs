@@ -0,0 +1,100 @@
+use input::{read_inputs, render_error};
+use rsjson::serializer::SerializerConfig;
+use rsjson::stream::reformat;
+use rsjson::JSONValue;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use {usage_error, EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+
+const DEFAULT_INDENT: usize = 2;
+
+/// `rsjson fmt [--indent N] [--minify] [--sort-keys] [-i] [--stream] [FILE...]`:
+/// reformats files to stdout, or rewrites them in place with `-i`.
+/// `--stream` reformats without loading whole files, for ones that don't fit in memory.
+pub fn run(args: &[String]) -> i32 {
+    let mut config = SerializerConfig::pretty(DEFAULT_INDENT);
+    let mut in_place = false;
+    let mut stream = false;
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--indent" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(indent) => config.indent = Some(indent),
+                None => return usage_error("--indent requires a number"),
+            },
+            "--minify" => config.indent = None,
+            "--sort-keys" => config.sort_keys = true,
+            "-i" | "--in-place" => in_place = true,
+            "--stream" => stream = true,
+            _ => paths.push(arg.clone()),
+        }
+    }
+    if in_place && paths.is_empty() {
+        return usage_error("-i requires at least one file");
+    }
+    if in_place && paths.iter().any(|path| path == "-") {
+        return usage_error("-i can't be used with stdin");
+    }
+    if stream {
+        if in_place || config.sort_keys {
+            return usage_error("--stream can't be used with -i or --sort-keys");
+        }
+        return run_stream(&paths, &config);
+    }
+    let inputs = match read_inputs(&paths) {
+        Ok(inputs) => inputs,
+        Err(message) => {
+            eprintln!("{}", message);
+            return EXIT_USAGE;
+        }
+    };
+    let mut code = EXIT_OK;
+    for input in inputs {
+        let value = match input.text.parse::<JSONValue>() {
+            Ok(value) => value,
+            Err(err) => {
+                eprint!("{}", render_error(&input, &err));
+                code = EXIT_FAILURE;
+                continue;
+            }
+        };
+        let output = value.to_string_with(&config) + "\n";
+        if !in_place {
+            print!("{}", output);
+        } else if let Err(e) = fs::write(&input.name, output) {
+            eprintln!("Unable to write {}: {}", input.name, e);
+            code = EXIT_FAILURE;
+        }
+    }
+    return code;
+}
+
+fn run_stream(paths: &[String], config: &SerializerConfig) -> i32 {
+    let stdin = ["-".to_owned()];
+    let paths = if paths.is_empty() { &stdin[..] } else { paths };
+    let stdout = io::stdout();
+    let mut code = EXIT_OK;
+    for path in paths {
+        let reader: Box<dyn Read> = if path == "-" {
+            Box::new(io::stdin())
+        } else {
+            match File::open(path) {
+                Ok(file) => Box::new(file),
+                Err(e) => {
+                    eprintln!("Unable to read {}: {}", path, e);
+                    return EXIT_USAGE;
+                }
+            }
+        };
+        let mut output = BufWriter::new(stdout.lock());
+        let result = reformat(reader, &mut output, config).map(|_| ());
+        let _ = writeln!(output);
+        if let Err(err) = result {
+            let name = if path == "-" { "<stdin>" } else { path };
+            eprintln!("error: {}\n --> {}", err.reason, name);
+            code = EXIT_FAILURE;
+        }
+    }
+    return code;
+}
@@ -0,0 +1,63 @@
+use rsjson::encoding::decode;
+use rsjson::JSONParseError;
+use std::fs;
+use std::io::{self, Read};
+
+const STDIN: &str = "-";
+
+pub struct Input {
+    pub name: String,
+    pub text: String,
+}
+
+/// Reads every file given on the command line. No files or `-` means stdin.
+pub fn read_inputs(paths: &[String]) -> Result<Vec<Input>, String> {
+    if paths.is_empty() {
+        return Ok(vec![read_input(STDIN)?]);
+    }
+    return paths.iter().map(|path| read_input(path)).collect();
+}
+
+/// Reads a file or stdin, decoding UTF-16 and UTF-32 input to text.
+pub fn read_input(path: &str) -> Result<Input, String> {
+    let mut bytes = vec![];
+    let name = if path == STDIN {
+        io::stdin()
+            .read_to_end(&mut bytes)
+            .map_err(|e| format!("Unable to read stdin: {}", e))?;
+        "<stdin>".to_owned()
+    } else {
+        bytes = fs::read(path).map_err(|e| format!("Unable to read {}: {}", path, e))?;
+        path.to_owned()
+    };
+    let text = decode(&bytes).map_err(|e| format!("Unable to read {}: {}", name, e.reason))?;
+    return Ok(Input { name, text });
+}
+
+/// Renders a parse error rustc-style, with the offending line and a caret under the position.
+pub fn render_error(input: &Input, err: &JSONParseError) -> String {
+    let mut result = format!("error: {}\n", err.reason);
+    let (line, column) = match err.line_column(&input.text) {
+        Some(position) => position,
+        None => {
+            result.push_str(&format!(" --> {}\n", input.name));
+            return result;
+        }
+    };
+    let source = input.text.lines().nth(line - 1).unwrap_or("");
+    let gutter = " ".repeat(line.to_string().len());
+    // keep tabs in the caret line so it stays aligned with the source line
+    let padding: String = source
+        .chars()
+        .take(column - 1)
+        .map(|ch| if ch == '\t' { '\t' } else { ' ' })
+        .collect();
+    result.push_str(&format!(
+        "{}--> {}:{}:{}\n",
+        gutter, input.name, line, column
+    ));
+    result.push_str(&format!("{} |\n", gutter));
+    result.push_str(&format!("{} | {}\n", line, source));
+    result.push_str(&format!("{} | {}^\n", gutter, padding));
+    return result;
+}
@@ -0,0 +1,68 @@
+use input::read_inputs;
+use rsjson::line_column;
+use rsjson::lint::{lint, LintConfig, LintRule, Severity};
+use {usage_error, EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+
+/// `rsjson lint [--max-depth N] [--allow RULE] [--warn RULE] [--deny RULE] [FILE...]`:
+/// reports issues as `file:line:column: severity[rule]: message`,
+/// failing if any of them has error severity.
+pub fn run(args: &[String]) -> i32 {
+    let mut config = LintConfig::default();
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let severity = match arg.as_str() {
+            "--allow" => Severity::Off,
+            "--warn" => Severity::Warning,
+            "--deny" => Severity::Error,
+            "--max-depth" => {
+                match args.next().and_then(|n| n.parse().ok()) {
+                    Some(depth) => config.max_depth = depth,
+                    None => return usage_error("--max-depth requires a number"),
+                }
+                continue;
+            }
+            _ => {
+                paths.push(arg.clone());
+                continue;
+            }
+        };
+        match args.next().map(|name| (name, LintRule::from_name(name))) {
+            Some((_, Some(rule))) => {
+                config.severities.insert(rule, severity);
+            }
+            Some((name, None)) => return usage_error(&format!("Unknown rule {}", name)),
+            None => return usage_error(&format!("{} requires a rule name", arg)),
+        }
+    }
+    let inputs = match read_inputs(&paths) {
+        Ok(inputs) => inputs,
+        Err(message) => {
+            eprintln!("{}", message);
+            return EXIT_USAGE;
+        }
+    };
+    let mut code = EXIT_OK;
+    for input in inputs {
+        for issue in lint(&input.text, &config) {
+            let (line, column) = line_column(&input.text, issue.position).unwrap_or((0, 0));
+            let severity = match issue.severity {
+                Severity::Error => {
+                    code = EXIT_FAILURE;
+                    "error"
+                }
+                _ => "warning",
+            };
+            println!(
+                "{}:{}:{}: {}[{}]: {}",
+                input.name,
+                line,
+                column,
+                severity,
+                issue.rule.name(),
+                issue.message
+            );
+        }
+    }
+    return code;
+}
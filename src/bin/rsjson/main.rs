@@ -0,0 +1,82 @@
+#![allow(clippy::needless_return)]
+
+extern crate rsjson;
+
+use std::env;
+use std::process;
+
+mod convert;
+mod diff;
+mod explore;
+mod filter;
+mod fmt;
+mod input;
+mod lint;
+mod query;
+mod schema;
+mod stats;
+mod validate;
+
+#[cfg(test)]
+mod tests;
+
+const EXIT_OK: i32 = 0;
+const EXIT_FAILURE: i32 = 1;
+const EXIT_USAGE: i32 = 2;
+
+const USAGE: &str = "Usage: rsjson <command> [args]
+
+Commands:
+    validate [FILE...]    Check that files (or stdin) contain valid JSON
+    fmt [--indent N] [--minify] [--sort-keys] [-i] [--stream] [FILE...]
+                          Pretty-print or minify files, in place with -i,
+                          without loading them into memory with --stream
+    get [-r] POINTER [FILE]
+                          Print the value at a JSON Pointer, e.g. /items/0/id
+    path [-r] EXPRESSION [FILE]
+                          Print every JSONPath match, e.g. '$.items[*].id'
+    diff [--patch] [--no-color] A B
+                          Show differences, or an RFC 6902 patch with --patch
+    convert --from FORMAT --to FORMAT [--pretty] [FILE]
+                          Convert records between array and ndjson
+    explore FILE          Walk through a document at an interactive prompt
+    filter EXPRESSION [FILE]
+                          Run a jq-style expression over each NDJSON record,
+                          e.g. 'select(.level == \"error\")'
+    lint [--max-depth N] [--allow|--warn|--deny RULE] [FILE...]
+                          Report duplicate keys, lossy numbers, comments, trailing commas,
+                          deep nesting and mixed-type arrays
+    schema check --schema SCHEMA [FILE...]
+                          Validate files against a JSON Schema
+    schema infer [FILE...]
+                          Print a JSON Schema that every file satisfies
+    stats [--top N] [FILE]
+                          Print size, node counts, depth, largest values and key frequency";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let code = match args.first().map(|s| s.as_str()) {
+        Some("validate") => validate::run(&args[1..]),
+        Some("fmt") => fmt::run(&args[1..]),
+        Some("get") => query::run_get(&args[1..]),
+        Some("path") => query::run_path(&args[1..]),
+        Some("diff") => diff::run(&args[1..]),
+        Some("convert") => convert::run(&args[1..]),
+        Some("explore") => explore::run(&args[1..]),
+        Some("filter") => filter::run(&args[1..]),
+        Some("lint") => lint::run(&args[1..]),
+        Some("schema") => schema::run(&args[1..]),
+        Some("stats") => stats::run(&args[1..]),
+        Some("-h") | Some("--help") | None => {
+            println!("{}", USAGE);
+            EXIT_OK
+        }
+        Some(command) => usage_error(&format!("Unknown command {}", command)),
+    };
+    process::exit(code);
+}
+
+fn usage_error(message: &str) -> i32 {
+    eprintln!("{}\n\n{}", message, USAGE);
+    return EXIT_USAGE;
+}
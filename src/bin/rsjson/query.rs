@@ -0,0 +1,92 @@
+use input::{read_input, render_error};
+use rsjson::jsonpath::JSONPath;
+use rsjson::serializer::SerializerConfig;
+use rsjson::JSONValue;
+use {usage_error, EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+
+struct QueryArgs {
+    expression: String,
+    path: String,
+    raw: bool,
+}
+
+/// `rsjson get [-r] POINTER [FILE]`: prints the value at a JSON Pointer, fails if there is none.
+pub fn run_get(args: &[String]) -> i32 {
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(message) => return usage_error(&message),
+    };
+    let document = match read_document(&args.path) {
+        Ok(document) => document,
+        Err(code) => return code,
+    };
+    match document.pointer(&args.expression) {
+        Some(value) => {
+            print_value(value, args.raw);
+            return EXIT_OK;
+        }
+        None => {
+            eprintln!("Nothing found at {}", args.expression);
+            return EXIT_FAILURE;
+        }
+    }
+}
+
+/// `rsjson path [-r] EXPRESSION [FILE]`: prints every JSONPath match on its own line.
+pub fn run_path(args: &[String]) -> i32 {
+    let args = match parse_args(args) {
+        Ok(args) => args,
+        Err(message) => return usage_error(&message),
+    };
+    let path = match JSONPath::compile(&args.expression) {
+        Ok(path) => path,
+        Err(err) => return usage_error(&err.reason),
+    };
+    let document = match read_document(&args.path) {
+        Ok(document) => document,
+        Err(code) => return code,
+    };
+    for value in path.select(&document) {
+        print_value(value, args.raw);
+    }
+    return EXIT_OK;
+}
+
+fn parse_args(args: &[String]) -> Result<QueryArgs, String> {
+    let mut raw = false;
+    let mut positional = vec![];
+    for arg in args {
+        match arg.as_str() {
+            "-r" | "--raw" => raw = true,
+            _ => positional.push(arg.clone()),
+        }
+    }
+    match positional.len() {
+        1 | 2 => (),
+        _ => return Err("Expected an expression and an optional file".to_owned()),
+    }
+    return Ok(QueryArgs {
+        expression: positional[0].clone(),
+        path: positional.get(1).cloned().unwrap_or_else(|| "-".to_owned()),
+        raw,
+    });
+}
+
+pub fn read_document(path: &str) -> Result<JSONValue, i32> {
+    let input = read_input(path).map_err(|message| {
+        eprintln!("{}", message);
+        EXIT_USAGE
+    })?;
+    return input.text.parse().map_err(|err| {
+        eprint!("{}", render_error(&input, &err));
+        EXIT_FAILURE
+    });
+}
+
+// With `raw`, strings are printed without quotes and escapes, like `jq -r`
+fn print_value(value: &JSONValue, raw: bool) {
+    match *value {
+        JSONValue::JSONString(ref s) if raw => println!("{}", s),
+        _ => println!("{}", value.to_string_with(&SerializerConfig::compact())),
+    }
+}
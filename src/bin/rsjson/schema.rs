@@ -0,0 +1,106 @@
+use input::{read_input, read_inputs, render_error, Input};
+use rsjson::schema::{infer_schema, Schema};
+use rsjson::serializer::SerializerConfig;
+use rsjson::JSONValue;
+use {usage_error, EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+
+/// `rsjson schema check --schema SCHEMA [FILE...]` and `rsjson schema infer [FILE...]`.
+pub fn run(args: &[String]) -> i32 {
+    match args.first().map(|s| s.as_str()) {
+        Some("check") => return run_check(&args[1..]),
+        Some("infer") => return run_infer(&args[1..]),
+        Some(command) => return usage_error(&format!("Unknown schema command {}", command)),
+        None => return usage_error("schema requires check or infer"),
+    }
+}
+
+// Prints every violation as `file:/pointer: message`, fails if there were any
+fn run_check(args: &[String]) -> i32 {
+    let mut schema_path = None;
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--schema" => match args.next() {
+                Some(path) => schema_path = Some(path),
+                None => return usage_error("--schema requires a file"),
+            },
+            _ => paths.push(arg.clone()),
+        }
+    }
+    let schema_path = match schema_path {
+        Some(path) => path,
+        None => return usage_error("schema check requires --schema"),
+    };
+    let schema = match read_input(schema_path).and_then(|input| {
+        parse(&input).and_then(|schema| {
+            Schema::compile(&schema)
+                .map_err(|err| format!("{}: invalid schema: {}\n", input.name, err.reason))
+        })
+    }) {
+        Ok(schema) => schema,
+        Err(message) => {
+            eprint!("{}", message);
+            return EXIT_USAGE;
+        }
+    };
+    let inputs = match read_inputs(&paths) {
+        Ok(inputs) => inputs,
+        Err(message) => {
+            eprintln!("{}", message);
+            return EXIT_USAGE;
+        }
+    };
+    let mut code = EXIT_OK;
+    for input in &inputs {
+        let document = match parse(input) {
+            Ok(document) => document,
+            Err(message) => {
+                eprint!("{}", message);
+                code = EXIT_FAILURE;
+                continue;
+            }
+        };
+        for violation in schema.validate(&document) {
+            let path = if violation.path.is_empty() {
+                "/"
+            } else {
+                &violation.path
+            };
+            println!("{}:{}: {}", input.name, path, violation.message);
+            code = EXIT_FAILURE;
+        }
+    }
+    return code;
+}
+
+// Infers one schema that all inputs satisfy
+fn run_infer(paths: &[String]) -> i32 {
+    let inputs = match read_inputs(paths) {
+        Ok(inputs) => inputs,
+        Err(message) => {
+            eprintln!("{}", message);
+            return EXIT_USAGE;
+        }
+    };
+    let mut samples = vec![];
+    for input in &inputs {
+        match parse(input) {
+            Ok(sample) => samples.push(sample),
+            Err(message) => {
+                eprint!("{}", message);
+                return EXIT_FAILURE;
+            }
+        }
+    }
+    let config = SerializerConfig {
+        sort_keys: true,
+        ..SerializerConfig::pretty(2)
+    };
+    println!("{}", infer_schema(&samples).to_string_with(&config));
+    return EXIT_OK;
+}
+
+fn parse(input: &Input) -> Result<JSONValue, String> {
+    return input.text.parse().map_err(|err| render_error(input, &err));
+}
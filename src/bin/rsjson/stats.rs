@@ -0,0 +1,75 @@
+use query::read_document;
+use rsjson::stats::stats;
+use std::fs;
+use {usage_error, EXIT_OK};
+
+const DEFAULT_TOP: usize = 10;
+
+/// `rsjson stats [--top N] [FILE]`: prints size, node counts, depth,
+/// the largest members and arrays, and the most frequent keys.
+pub fn run(args: &[String]) -> i32 {
+    let mut top = DEFAULT_TOP;
+    let mut paths = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--top" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) => top = n,
+                None => return usage_error("--top requires a number"),
+            },
+            _ => paths.push(arg.clone()),
+        }
+    }
+    if paths.len() > 1 {
+        return usage_error("stats accepts at most one file");
+    }
+    let path = paths.first().map_or("-", |p| p.as_str());
+    let document = match read_document(path) {
+        Ok(document) => document,
+        Err(code) => return code,
+    };
+    let stats = stats(&document, top);
+    match fs::metadata(path) {
+        Ok(ref metadata) if path != "-" => println!(
+            "size: {} bytes ({} compact)",
+            metadata.len(),
+            stats.compact_size
+        ),
+        _ => println!("size: {} bytes compact", stats.compact_size),
+    }
+    println!(
+        "nodes: {} (objects {}, arrays {}, strings {}, numbers {}, booleans {}, nulls {})",
+        stats.nodes(),
+        stats.objects,
+        stats.arrays,
+        stats.strings,
+        stats.numbers,
+        stats.booleans,
+        stats.nulls
+    );
+    println!("depth: {}", stats.max_depth);
+    print_table("largest members (bytes)", &stats.largest_members);
+    print_table("largest arrays (items)", &stats.largest_arrays);
+    let keys: Vec<(String, usize)> = stats
+        .top_keys(top)
+        .into_iter()
+        .map(|(key, count)| (key.to_owned(), count))
+        .collect();
+    print_table("key frequency", &keys);
+    return EXIT_OK;
+}
+
+fn print_table(title: &str, rows: &[(String, usize)]) {
+    if rows.is_empty() {
+        return;
+    }
+    println!("{}:", title);
+    let width = rows
+        .iter()
+        .map(|row| row.1.to_string().len())
+        .max()
+        .unwrap_or(0);
+    for &(ref name, count) in rows {
+        println!("  {:>width$}  {}", count, name, width = width);
+    }
+}
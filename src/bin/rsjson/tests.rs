@@ -0,0 +1,92 @@
+use explore::Explorer;
+use fmt;
+use input::{render_error, Input};
+use rsjson::JSONValue;
+use EXIT_USAGE;
+
+fn render(text: &str) -> String {
+    let input = Input {
+        name: "test.json".to_owned(),
+        text: text.to_owned(),
+    };
+    let err = text.parse::<JSONValue>().unwrap_err();
+    return render_error(&input, &err);
+}
+
+#[test]
+fn test_render_error_snippet() {
+    assert_eq!(
+        render("{\n  \"a\": 1,\n}"),
+        "error: Unexpected charachter } at position 12. Expected \"
+ --> test.json:3:1
+  |
+3 | }
+  | ^
+"
+    );
+}
+
+#[test]
+fn test_render_error_keeps_tabs() {
+    assert_eq!(
+        render("[\n\t\tx]"),
+        "error: Unexpected charachter x at position 4
+ --> test.json:2:3
+  |
+2 | \t\tx]
+  | \t\t^
+"
+    );
+}
+
+fn explore(explorer: &mut Explorer, line: &str) -> String {
+    return explorer
+        .execute(line)
+        .unwrap_or_else(|_| panic!("{} should not quit", line))
+        .unwrap_or_default();
+}
+
+#[test]
+fn test_explorer_navigation() {
+    let document: JSONValue = r#"{"users": [{"name": "a", "id": 1}], "usage": {}, "meta/x": 1}"#
+        .parse()
+        .unwrap();
+    let mut explorer = Explorer::new(&document);
+    assert_eq!(
+        explore(&mut explorer, "ls"),
+        "meta/x\t1\nusage\t{0 keys}\nusers\t[1 items]"
+    );
+    assert_eq!(
+        explore(&mut explorer, "cd us"),
+        "error: us is ambiguous: usage, users"
+    );
+    assert_eq!(explore(&mut explorer, "cd use/0"), "");
+    assert_eq!(explorer.pwd(), "/users/0");
+    assert_eq!(explore(&mut explorer, "ls"), "id\t1\nname\t\"a\"");
+    assert_eq!(explore(&mut explorer, "q .id + 1"), "2");
+    assert_eq!(explore(&mut explorer, "cd ../../me"), "");
+    assert_eq!(explorer.pwd(), "/meta~1x");
+    assert_eq!(
+        explore(&mut explorer, "cd /users/1"),
+        "error: No 1 in /users"
+    );
+    assert_eq!(explore(&mut explorer, "cat /users/0/name"), "\"a\"");
+    assert_eq!(explore(&mut explorer, "cd /users/0"), "");
+    assert_eq!(
+        explore(&mut explorer, "paths"),
+        "/users/*/id\tnumber\n/users/*/name\tstring"
+    );
+    assert_eq!(explore(&mut explorer, "paths n"), "/users/*/name\tstring");
+    assert_eq!(explore(&mut explorer, "paths /me"), "/meta~1x\tnumber");
+    assert_eq!(explore(&mut explorer, "paths /none"), "");
+    assert!(explorer.execute("quit").is_err());
+}
+
+#[test]
+fn test_fmt_in_place_rejects_stdin() {
+    for s in [vec!["-i", "-"], vec!["--in-place", "a.json", "-"]] {
+        println!("Checking {:?}", s);
+        let args: Vec<String> = s.into_iter().map(|arg| arg.to_owned()).collect();
+        assert_eq!(fmt::run(&args), EXIT_USAGE);
+    }
+}
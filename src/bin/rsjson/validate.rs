@@ -0,0 +1,22 @@
+use input::{read_inputs, render_error};
+use rsjson::JSONValue;
+use {EXIT_FAILURE, EXIT_OK, EXIT_USAGE};
+
+/// `rsjson validate [FILE...]`: prints every parse error and fails if any input is invalid.
+pub fn run(args: &[String]) -> i32 {
+    let inputs = match read_inputs(args) {
+        Ok(inputs) => inputs,
+        Err(message) => {
+            eprintln!("{}", message);
+            return EXIT_USAGE;
+        }
+    };
+    let mut code = EXIT_OK;
+    for input in inputs {
+        if let Err(err) = input.text.parse::<JSONValue>() {
+            eprint!("{}", render_error(&input, &err));
+            code = EXIT_FAILURE;
+        }
+    }
+    return code;
+}
@@ -0,0 +1,512 @@
+use super::*;
+use convert::{FromJSONValue, JSONConvertError};
+use pointer::{escape_token, parse_pointer};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+// Separates nesting levels in the names of override variables
+const ENV_NESTING: &str = "__";
+const INCLUDE: &str = "$include";
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone)]
+pub struct JSONConfigError {
+    pub reason: String,
+}
+
+impl JSONValue {
+    /// Expands `${VAR}` and `${VAR:-default}` in every string value from the
+    /// environment. `$${` stands for a literal `${`. Keys aren't expanded.
+    /// Fails on the first variable that isn't set and has no default.
+    pub fn expand_env(&mut self) -> Result<(), JSONConfigError> {
+        return self.expand_env_with(&|name| env::var(name).ok());
+    }
+
+    /// Like `expand_env`, with variables looked up through `lookup`.
+    pub fn expand_env_with(
+        &mut self,
+        lookup: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<(), JSONConfigError> {
+        return expand_value(self, lookup, &mut vec![]);
+    }
+}
+
+impl JSONValue {
+    /// Converts the value at `pointer`, failing when there's none. Errors carry
+    /// the full path: `doc.require::<u16>("/server/port")?` fails with
+    /// "Expected u16, got string \"80\" at /server/port".
+    pub fn require<T: FromJSONValue>(&self, pointer: &str) -> Result<T, JSONConvertError> {
+        match self.pointer(pointer) {
+            Some(value) => return T::from_json(value).map_err(|e| e.within_pointer(pointer)),
+            None => {
+                return Err(JSONConvertError {
+                    reason: "Missing required value".to_owned(),
+                    path: pointer.to_owned(),
+                })
+            }
+        }
+    }
+
+    /// Converts the value at `pointer`; `None` when it's missing or `null`.
+    pub fn get_as<T: FromJSONValue>(&self, pointer: &str) -> Result<Option<T>, JSONConvertError> {
+        match self.pointer(pointer) {
+            None | Some(&JSONValue::JSONNull()) => return Ok(None),
+            Some(value) => {
+                return T::from_json(value)
+                    .map(Some)
+                    .map_err(|e| e.within_pointer(pointer))
+            }
+        }
+    }
+
+    /// Converts the value at `pointer`, falling back to `default` when it's missing
+    /// or `null`. A value of the wrong type is still an error.
+    pub fn get_or<T, D>(&self, pointer: &str, default: D) -> Result<T, JSONConvertError>
+    where
+        T: FromJSONValue,
+        D: Into<T>,
+    {
+        return Ok(self.get_as(pointer)?.unwrap_or_else(|| default.into()));
+    }
+}
+
+/// Layered configuration: every layer is deep-merged over the ones loaded before
+/// it, and the stack remembers which layer supplied each value of the result.
+///
+/// `ConfigStack::new().load_file("base.json")?.load_file("prod.json")?.load_env_overrides("APP")`
+#[derive(Debug, Clone)]
+pub struct ConfigStack {
+    layers: Vec<String>,
+    merged: JSONValue,
+    // Layer index for the pointer of every value in `merged`
+    sources: BTreeMap<String, usize>,
+}
+
+impl Default for ConfigStack {
+    fn default() -> ConfigStack {
+        return ConfigStack::new();
+    }
+}
+
+impl ConfigStack {
+    /// Empty stack, its value is an empty object
+    pub fn new() -> ConfigStack {
+        return ConfigStack {
+            layers: vec![],
+            merged: JSONValue::JSONObject(JSONMap::new()),
+            sources: BTreeMap::new(),
+        };
+    }
+
+    /// Adds a layer read from a JSON file, named after the path.
+    pub fn load_file<P: AsRef<Path>>(self, path: P) -> Result<ConfigStack, JSONConfigError> {
+        let path = path.as_ref();
+        let bytes = fs::read(path).map_err(|e| JSONConfigError {
+            reason: format!("Unable to read {}: {}", path.display(), e),
+        })?;
+        let value = JSONValue::from_slice(&bytes).map_err(|e| JSONConfigError {
+            reason: format!("Unable to parse {}: {}", path.display(), e.reason),
+        })?;
+        return Ok(self.load_value(&path.display().to_string(), value));
+    }
+
+    pub fn load_value(mut self, name: &str, value: JSONValue) -> ConfigStack {
+        let layer = self.layers.len();
+        self.layers.push(name.to_owned());
+        merge(
+            &mut self.merged,
+            value,
+            &mut String::new(),
+            layer,
+            &mut self.sources,
+        );
+        return self;
+    }
+
+    /// Adds a layer from the environment variables starting with `prefix` and `_`,
+    /// see `load_env_overrides_from`.
+    pub fn load_env_overrides(self, prefix: &str) -> ConfigStack {
+        return self.load_env_overrides_from(prefix, env::vars());
+    }
+
+    /// Adds a layer from variables named `PREFIX_KEY`, with `__` between nested
+    /// keys: `APP_SERVER__PORT=8080` sets `/server/port`. Keys are lowercased.
+    /// Values that are valid JSON are parsed, the rest are taken as strings.
+    /// The layer is named `env:PREFIX`.
+    pub fn load_env_overrides_from<I>(self, prefix: &str, vars: I) -> ConfigStack
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let mut layer = JSONValue::JSONObject(JSONMap::new());
+        let prefix = format!("{}_", prefix);
+        let mut vars: Vec<(String, String)> = vars
+            .into_iter()
+            .filter(|var| var.0.starts_with(&prefix) && var.0.len() > prefix.len())
+            .collect();
+        // deterministic when one variable overrides a part of another
+        vars.sort();
+        for (name, raw) in vars {
+            let value = raw
+                .parse::<JSONValue>()
+                .unwrap_or(JSONValue::JSONString(raw));
+            let mut current = &mut layer;
+            let keys: Vec<String> = name[prefix.len()..]
+                .split(ENV_NESTING)
+                .map(|key| key.to_lowercase())
+                .collect();
+            for (i, key) in keys.iter().enumerate() {
+                if !matches!(*current, JSONValue::JSONObject(_)) {
+                    *current = JSONValue::JSONObject(JSONMap::new());
+                }
+                let members = match *current {
+                    JSONValue::JSONObject(ref mut members) => members,
+                    _ => unreachable!(),
+                };
+                if i == keys.len() - 1 {
+                    members.insert(key.clone(), Box::new(value.clone()));
+                    break;
+                }
+                if !members.contains_key(key.as_str()) {
+                    members.insert(key.clone(), Box::new(JSONValue::JSONObject(JSONMap::new())));
+                }
+                current = members.get_mut(key.as_str()).unwrap();
+            }
+        }
+        let name = format!("env:{}", &prefix[..prefix.len() - 1]);
+        return self.load_value(&name, layer);
+    }
+
+    /// The merged configuration
+    pub fn value(&self) -> &JSONValue {
+        return &self.merged;
+    }
+
+    pub fn into_value(self) -> JSONValue {
+        return self.merged;
+    }
+
+    /// Names of the layers in the order they were loaded
+    pub fn layers(&self) -> &[String] {
+        return &self.layers;
+    }
+
+    /// Name of the layer that supplied the value at `pointer`, the last one that
+    /// set it or, for objects, the last one that added to it. `None` when the
+    /// pointer doesn't resolve.
+    pub fn source(&self, pointer: &str) -> Option<&str> {
+        let tokens = parse_pointer(pointer)?;
+        let key: String = tokens
+            .iter()
+            .map(|token| format!("/{}", escape_token(token)))
+            .collect();
+        let layer = *self.sources.get(&key)?;
+        return Some(&self.layers[layer]);
+    }
+}
+
+impl JSONValue {
+    /// Merges `overlay` into the value: objects are merged member by member,
+    /// recursively, and everything else, arrays and `null` included, replaces
+    /// what was there before.
+    pub fn deep_merge(&mut self, overlay: JSONValue) {
+        merge(self, overlay, &mut String::new(), 0, &mut BTreeMap::new());
+    }
+}
+
+fn merge(
+    base: &mut JSONValue,
+    overlay: JSONValue,
+    path: &mut String,
+    layer: usize,
+    sources: &mut BTreeMap<String, usize>,
+) {
+    sources.insert(path.clone(), layer);
+    match (base, overlay) {
+        (&mut JSONValue::JSONObject(ref mut members), JSONValue::JSONObject(overlay)) => {
+            for (key, value) in overlay {
+                let len = path.len();
+                path.push('/');
+                path.push_str(&escape_token(&key));
+                match members.get_mut(key.as_str()) {
+                    Some(member) => merge(member, *value, path, layer, sources),
+                    None => {
+                        record(&value, path, layer, sources);
+                        members.insert(key, value);
+                    }
+                }
+                path.truncate(len);
+            }
+        }
+        (base, overlay) => {
+            // drop the sources of everything under the replaced value
+            let prefix = format!("{}/", path);
+            let nested: Vec<String> = sources
+                .range(prefix.clone()..)
+                .take_while(|entry| entry.0.starts_with(&prefix))
+                .map(|entry| entry.0.clone())
+                .collect();
+            for key in nested {
+                sources.remove(&key);
+            }
+            record(&overlay, path, layer, sources);
+            *base = overlay;
+        }
+    }
+}
+
+// Marks the value and everything in it as coming from `layer`
+fn record(
+    value: &JSONValue,
+    path: &mut String,
+    layer: usize,
+    sources: &mut BTreeMap<String, usize>,
+) {
+    sources.insert(path.clone(), layer);
+    let len = path.len();
+    match *value {
+        JSONValue::JSONObject(ref members) => {
+            for (key, member) in members.iter() {
+                path.push('/');
+                path.push_str(&escape_token(key));
+                record(member, path, layer, sources);
+                path.truncate(len);
+            }
+        }
+        JSONValue::JSONArray(ref items) => {
+            for (i, item) in items.iter().enumerate() {
+                path.push('/');
+                path.push_str(&i.to_string());
+                record(item, path, layer, sources);
+                path.truncate(len);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Source of the documents `$include` refers to. Implemented for closures, so
+/// includes can come from memory or an archive; `FileLoader` reads files.
+pub trait IncludeLoader {
+    fn load(&self, path: &Path) -> Result<JSONValue, JSONConfigError>;
+}
+
+impl<F: Fn(&Path) -> Result<JSONValue, JSONConfigError>> IncludeLoader for F {
+    fn load(&self, path: &Path) -> Result<JSONValue, JSONConfigError> {
+        return self(path);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FileLoader;
+
+impl IncludeLoader for FileLoader {
+    fn load(&self, path: &Path) -> Result<JSONValue, JSONConfigError> {
+        let bytes = fs::read(path).map_err(|e| JSONConfigError {
+            reason: format!("Unable to read {}: {}", path.display(), e),
+        })?;
+        return JSONValue::from_slice(&bytes).map_err(|e| JSONConfigError {
+            reason: format!("Unable to parse {}: {}", path.display(), e.reason),
+        });
+    }
+}
+
+/// Reads a JSON file and resolves its `$include`s, see `JSONValue::resolve_includes`.
+pub fn load_with_includes<P: AsRef<Path>>(path: P) -> Result<JSONValue, JSONConfigError> {
+    let path = normalize_path(path.as_ref());
+    let mut value = FileLoader.load(&path)?;
+    let dir = path
+        .parent()
+        .map(|dir| dir.to_path_buf())
+        .unwrap_or_default();
+    resolve(&mut value, &FileLoader, &dir, &mut vec![path], &mut vec![])?;
+    return Ok(value);
+}
+
+impl JSONValue {
+    /// Replaces every `{"$include": "other.json"}` object with the contents of the
+    /// file it names, read from `base_dir` when relative. Included files can include
+    /// others, relative to their own directory; a file including itself, directly
+    /// or not, is an error.
+    pub fn resolve_includes<P: AsRef<Path>>(&mut self, base_dir: P) -> Result<(), JSONConfigError> {
+        return self.resolve_includes_with(base_dir, &FileLoader);
+    }
+
+    /// Like `resolve_includes`, reading the included documents through `loader`.
+    pub fn resolve_includes_with<P: AsRef<Path>>(
+        &mut self,
+        base_dir: P,
+        loader: &dyn IncludeLoader,
+    ) -> Result<(), JSONConfigError> {
+        let dir = normalize_path(base_dir.as_ref());
+        return resolve(self, loader, &dir, &mut vec![], &mut vec![]);
+    }
+}
+
+// Relative includes are read from `dir`; `stack` holds the files being resolved
+fn resolve(
+    value: &mut JSONValue,
+    loader: &dyn IncludeLoader,
+    dir: &Path,
+    stack: &mut Vec<PathBuf>,
+    path: &mut Vec<String>,
+) -> Result<(), JSONConfigError> {
+    let target = match *value {
+        JSONValue::JSONObject(ref members) if members.len() == 1 => match members.get(INCLUDE) {
+            Some(target) => match **target {
+                JSONValue::JSONString(ref target) => Some(target.clone()),
+                _ => {
+                    return Err(JSONConfigError {
+                        reason: format!("{} must be a string at {}", INCLUDE, pointer_of(path)),
+                    })
+                }
+            },
+            None => None,
+        },
+        _ => None,
+    };
+    if let Some(target) = target {
+        let file = normalize_path(&dir.join(&target));
+        if stack.contains(&file) {
+            let chain: Vec<String> = stack
+                .iter()
+                .chain(Some(&file))
+                .map(|p| p.display().to_string())
+                .collect();
+            return Err(JSONConfigError {
+                reason: format!("Include cycle: {}", chain.join(" -> ")),
+            });
+        }
+        let mut included = loader.load(&file).map_err(|e| JSONConfigError {
+            reason: format!("{} (included at {})", e.reason, pointer_of(path)),
+        })?;
+        let included_dir = file
+            .parent()
+            .map(|dir| dir.to_path_buf())
+            .unwrap_or_default();
+        stack.push(file);
+        resolve(&mut included, loader, &included_dir, stack, &mut vec![])?;
+        stack.pop();
+        *value = included;
+        return Ok(());
+    }
+    match *value {
+        JSONValue::JSONArray(ref mut items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                path.push(i.to_string());
+                resolve(item, loader, dir, stack, path)?;
+                path.pop();
+            }
+        }
+        JSONValue::JSONObject(ref mut members) => {
+            for (key, member) in members.iter_mut() {
+                path.push(key.clone());
+                resolve(member, loader, dir, stack, path)?;
+                path.pop();
+            }
+        }
+        _ => (),
+    }
+    return Ok(());
+}
+
+// Removes `.` and resolves `..` without touching the file system, so loaders
+// don't need real files for cycles to be detected
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut result = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                if !result.pop() {
+                    result.push("..");
+                }
+            }
+            _ => result.push(component.as_os_str()),
+        }
+    }
+    return result;
+}
+
+fn expand_value(
+    value: &mut JSONValue,
+    lookup: &dyn Fn(&str) -> Option<String>,
+    path: &mut Vec<String>,
+) -> Result<(), JSONConfigError> {
+    match *value {
+        JSONValue::JSONString(ref mut s) if s.contains('$') => {
+            *s = expand_str(s, lookup).map_err(|reason| JSONConfigError {
+                reason: format!("{} at {}", reason, pointer_of(path)),
+            })?;
+        }
+        JSONValue::JSONArray(ref mut items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                path.push(i.to_string());
+                expand_value(item, lookup, path)?;
+                path.pop();
+            }
+        }
+        JSONValue::JSONObject(ref mut members) => {
+            for (key, member) in members.iter_mut() {
+                path.push(key.clone());
+                expand_value(member, lookup, path)?;
+                path.pop();
+            }
+        }
+        _ => (),
+    }
+    return Ok(());
+}
+
+/// Expands the variables of a single string, as `JSONValue::expand_env_with` does.
+pub fn expand_str(s: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('$') {
+        result.push_str(&rest[..i]);
+        rest = &rest[i..];
+        if rest.starts_with("$${") {
+            result.push_str("${");
+            rest = &rest[3..];
+            continue;
+        }
+        if !rest.starts_with("${") {
+            result.push('$');
+            rest = &rest[1..];
+            continue;
+        }
+        let end = match rest.find('}') {
+            Some(end) => end,
+            None => return Err(format!("Unterminated variable reference in {}", s)),
+        };
+        let reference = &rest[2..end];
+        let (name, default) = match reference.find(":-") {
+            Some(j) => (&reference[..j], Some(&reference[j + 2..])),
+            None => (reference, None),
+        };
+        if name.is_empty() {
+            return Err(format!("Empty variable name in {}", s));
+        }
+        match (lookup(name), default) {
+            (Some(value), _) => result.push_str(&value),
+            (None, Some(default)) => result.push_str(default),
+            (None, None) => return Err(format!("Environment variable {} isn't set", name)),
+        }
+        rest = &rest[end + 1..];
+    }
+    result.push_str(rest);
+    return Ok(result);
+}
+
+fn pointer_of(path: &[String]) -> String {
+    if path.is_empty() {
+        return "(root)".to_owned();
+    }
+    return path
+        .iter()
+        .map(|token| format!("/{}", escape_token(token)))
+        .collect();
+}
@@ -0,0 +1,283 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use std::collections::HashMap;
+
+fn lookup(name: &str) -> Option<String> {
+    match name {
+        "HOST" => return Some("db.local".to_owned()),
+        "PORT" => return Some("5432".to_owned()),
+        "EMPTY" => return Some(String::new()),
+        _ => return None,
+    }
+}
+
+#[test]
+fn test_expand_str() {
+    for s in vec![
+        ("plain", Ok("plain")),
+        ("${HOST}:${PORT}", Ok("db.local:5432")),
+        ("${MISSING:-fallback}", Ok("fallback")),
+        ("${HOST:-fallback}", Ok("db.local")),
+        ("${EMPTY:-fallback}", Ok("")),
+        ("${MISSING:-}", Ok("")),
+        ("$5 and $${HOST}", Ok("$5 and ${HOST}")),
+        ("${MISSING}", Err("Environment variable MISSING isn't set")),
+        ("${HOST", Err("Unterminated variable reference in ${HOST")),
+        ("${:-x}", Err("Empty variable name in ${:-x}")),
+    ] {
+        println!("Checking {}", s.0);
+        let expected = s.1.map(|v| v.to_owned()).map_err(|e| e.to_owned());
+        assert_eq!(expand_str(s.0, &lookup), expected);
+    }
+}
+
+#[test]
+fn test_expand_env() {
+    let mut config: JSONValue =
+        r#"{"db": {"url": "postgres://${HOST}:${PORT}", "pool": 5}, "${HOST}": ["${PORT}"]}"#
+            .parse()
+            .unwrap();
+    config.expand_env_with(&lookup).unwrap();
+    assert_eq!(
+        config,
+        r#"{"db": {"url": "postgres://db.local:5432", "pool": 5}, "${HOST}": ["5432"]}"#
+            .parse()
+            .unwrap()
+    );
+
+    let mut config: JSONValue = r#"{"a/b": [1, "${NOPE}"]}"#.parse().unwrap();
+    let err = config.expand_env_with(&lookup).unwrap_err();
+    assert_eq!(err.reason, "Environment variable NOPE isn't set at /a~1b/1");
+
+    let mut value = JSONValue::JSONString("${PATH}".to_owned());
+    value.expand_env().unwrap();
+    assert_eq!(value, JSONValue::JSONString(env::var("PATH").unwrap()));
+}
+
+#[test]
+fn test_deep_merge() {
+    let mut base: JSONValue = r#"{"a": {"b": 1, "c": [1, 2]}, "d": {"e": 1}, "f": 1}"#
+        .parse()
+        .unwrap();
+    base.deep_merge(
+        r#"{"a": {"c": [3], "x": null}, "d": 5, "g": {"h": 1}}"#
+            .parse()
+            .unwrap(),
+    );
+    assert_eq!(
+        base,
+        r#"{"a": {"b": 1, "c": [3], "x": null}, "d": 5, "f": 1, "g": {"h": 1}}"#
+            .parse()
+            .unwrap()
+    );
+    let mut scalar = JSONValue::JSONNumber(1.0);
+    scalar.deep_merge(JSONValue::JSONBool(true));
+    assert_eq!(scalar, JSONValue::JSONBool(true));
+}
+
+#[test]
+fn test_config_stack() {
+    let dir = env::temp_dir().join(format!("rsjson-config-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let base = dir.join("base.json");
+    fs::write(
+        &base,
+        r#"{"server": {"host": "0.0.0.0", "port": 80, "tls": {"cert": "a.pem"}}, "log": ["stdout"]}"#,
+    )
+    .unwrap();
+    let prod = dir.join("prod.json");
+    fs::write(
+        &prod,
+        r#"{"server": {"port": 443, "tls": false}, "log": {"level": "warn"}}"#,
+    )
+    .unwrap();
+    let vars = vec![
+        ("APP_SERVER__HOST".to_owned(), "example.com".to_owned()),
+        ("APP_DEBUG".to_owned(), "true".to_owned()),
+        ("APP_NAME".to_owned(), "not json".to_owned()),
+        ("OTHER_X".to_owned(), "1".to_owned()),
+        ("APP_".to_owned(), "1".to_owned()),
+    ];
+    let stack = ConfigStack::new()
+        .load_file(&base)
+        .unwrap()
+        .load_file(&prod)
+        .unwrap()
+        .load_env_overrides_from("APP", vars);
+    assert_eq!(
+        *stack.value(),
+        r#"{"server": {"host": "example.com", "port": 443, "tls": false},
+            "log": {"level": "warn"}, "debug": true, "name": "not json"}"#
+            .parse()
+            .unwrap()
+    );
+    let (base, prod) = (base.display().to_string(), prod.display().to_string());
+    assert_eq!(
+        stack.layers(),
+        &[base.clone(), prod.clone(), "env:APP".to_owned()][..]
+    );
+    for s in vec![
+        ("/server/host", Some("env:APP")),
+        ("/server/port", Some(prod.as_str())),
+        ("/server/tls", Some(prod.as_str())),
+        ("/server/tls/cert", None),
+        ("/log", Some(prod.as_str())),
+        ("/log/0", None),
+        ("/debug", Some("env:APP")),
+        ("/server", Some("env:APP")),
+        ("", Some("env:APP")),
+        ("/missing", None),
+        ("invalid", None),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(stack.source(s.0), s.1);
+    }
+
+    let err = ConfigStack::new()
+        .load_file(dir.join("missing.json"))
+        .unwrap_err();
+    assert!(err.reason.starts_with("Unable to read "));
+    let broken = dir.join("broken.json");
+    fs::write(&broken, "{").unwrap();
+    let err = ConfigStack::new().load_file(&broken).unwrap_err();
+    assert!(err
+        .reason
+        .ends_with("broken.json: String ended unexpectedly"));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+fn memory_loader(
+    files: &HashMap<PathBuf, &str>,
+    path: &Path,
+) -> Result<JSONValue, JSONConfigError> {
+    match files.get(path) {
+        Some(text) => return Ok(text.parse().unwrap()),
+        None => {
+            return Err(JSONConfigError {
+                reason: format!("No file {}", path.display()),
+            })
+        }
+    }
+}
+
+#[test]
+fn test_resolve_includes() {
+    let mut files = HashMap::new();
+    files.insert(
+        PathBuf::from("conf/db.json"),
+        r#"{"host": "h", "pool": {"$include": "shared/pool.json"}}"#,
+    );
+    files.insert(
+        PathBuf::from("conf/shared/pool.json"),
+        r#"{"size": 5, "extra": {"$include": "../list.json"}}"#,
+    );
+    files.insert(PathBuf::from("conf/list.json"), "[1, 2]");
+    files.insert(
+        PathBuf::from("conf/loop_a.json"),
+        r#"{"$include": "./loop_b.json"}"#,
+    );
+    files.insert(
+        PathBuf::from("conf/loop_b.json"),
+        r#"[{"$include": "loop_a.json"}]"#,
+    );
+    let loader = |path: &Path| memory_loader(&files, path);
+
+    let mut config: JSONValue = r#"{"db": {"$include": "db.json"}, "list": [{"$include": "./list.json"}], "keep": {"$include": "x", "other": 1}}"#
+        .parse()
+        .unwrap();
+    config.resolve_includes_with("conf", &loader).unwrap();
+    assert_eq!(
+        config,
+        r#"{"db": {"host": "h", "pool": {"size": 5, "extra": [1, 2]}}, "list": [[1, 2]], "keep": {"$include": "x", "other": 1}}"#
+            .parse()
+            .unwrap()
+    );
+
+    for s in vec![
+        (
+            r#"{"a": {"$include": "loop_a.json"}}"#,
+            "Include cycle: conf/loop_a.json -> conf/loop_b.json -> conf/loop_a.json",
+        ),
+        (
+            r#"{"a": [{"$include": "missing.json"}]}"#,
+            "No file conf/missing.json (included at /a/0)",
+        ),
+        (
+            r#"{"a": {"$include": 1}}"#,
+            "$include must be a string at /a",
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        let mut config: JSONValue = s.0.parse().unwrap();
+        let err = config.resolve_includes_with("conf", &loader).unwrap_err();
+        assert_eq!(err.reason, s.1);
+    }
+}
+
+#[test]
+fn test_load_with_includes() {
+    let dir = env::temp_dir().join(format!("rsjson-include-{}", std::process::id()));
+    fs::create_dir_all(dir.join("parts")).unwrap();
+    fs::write(
+        dir.join("main.json"),
+        r#"{"server": {"$include": "parts/server.json"}}"#,
+    )
+    .unwrap();
+    fs::write(dir.join("parts/server.json"), r#"{"port": 80}"#).unwrap();
+    assert_eq!(
+        load_with_includes(dir.join("main.json")).unwrap(),
+        r#"{"server": {"port": 80}}"#.parse().unwrap()
+    );
+    let mut value: JSONValue = r#"[{"$include": "main.json"}]"#.parse().unwrap();
+    value.resolve_includes(&dir).unwrap();
+    assert_eq!(value, r#"[{"server": {"port": 80}}]"#.parse().unwrap());
+    fs::write(
+        dir.join("parts/server.json"),
+        r#"{"$include": "../main.json"}"#,
+    )
+    .unwrap();
+    let err = load_with_includes(dir.join("main.json")).unwrap_err();
+    assert!(err.reason.starts_with("Include cycle: "));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_typed_access() {
+    let config: JSONValue =
+        r#"{"server": {"port": 8080, "host": null, "name": 5, "tags": ["a", 1]}}"#
+            .parse()
+            .unwrap();
+    assert_eq!(config.require::<u16>("/server/port"), Ok(8080));
+    assert_eq!(
+        config.get_or::<String, _>("/server/host", "0.0.0.0"),
+        Ok("0.0.0.0".to_owned())
+    );
+    assert_eq!(
+        config.get_or::<String, _>("/server/missing", "x"),
+        Ok("x".to_owned())
+    );
+    assert_eq!(config.get_or("/server/port", 80u16), Ok(8080));
+    assert_eq!(config.get_as::<u16>("/server/host"), Ok(None));
+    assert_eq!(config.get_as::<u16>("/server/port"), Ok(Some(8080)));
+    for s in vec![
+        (
+            config.require::<u16>("/server/timeout"),
+            "Missing required value at /server/timeout",
+        ),
+        (
+            config.require::<u16>("/server/host"),
+            "Expected u16, got null at /server/host",
+        ),
+        (
+            config.get_or::<String, _>("/server/name", "x").map(|_| 0),
+            "Expected string, got number 5 at /server/name",
+        ),
+        (
+            config.require::<Vec<String>>("/server/tags").map(|_| 0),
+            "Expected string, got number 1 at /server/tags/1",
+        ),
+    ] {
+        assert_eq!(s.0.unwrap_err().to_string(), s.1);
+    }
+}
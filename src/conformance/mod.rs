@@ -0,0 +1,163 @@
+use super::*;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::Path;
+use std::str;
+
+#[cfg(test)]
+mod tests;
+
+// Deep enough for every accepting case, shallow enough that the
+// `n_structure_100000_opening_arrays` style cases can't overflow the stack
+const DEFAULT_MAX_DEPTH: usize = 1024;
+
+/// What the JSONTestSuite file name prefix says about the case
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Expectation {
+    /// `y_`: parsers must accept it
+    Accept,
+    /// `n_`: parsers must reject it
+    Reject,
+    /// `i_`: implementation defined
+    Either,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Accepted,
+    Rejected,
+    Panicked,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaseResult {
+    /// File name of the case
+    pub name: String,
+    pub expectation: Expectation,
+    pub outcome: Outcome,
+}
+
+impl CaseResult {
+    /// Panics always fail, `i_` cases pass with either decision.
+    pub fn passed(&self) -> bool {
+        match (self.expectation, self.outcome) {
+            (_, Outcome::Panicked) => return false,
+            (Expectation::Accept, Outcome::Rejected) => return false,
+            (Expectation::Reject, Outcome::Accepted) => return false,
+            _ => return true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ConformanceReport {
+    /// Results sorted by case name
+    pub cases: Vec<CaseResult>,
+}
+
+impl ConformanceReport {
+    pub fn failures(&self) -> Vec<&CaseResult> {
+        return self.cases.iter().filter(|case| !case.passed()).collect();
+    }
+
+    /// Report as `{"passed": n, "failed": n, "cases": {"y_...": {...}}}`, where
+    /// every case has `expected` (accept, reject, either), `outcome`
+    /// (accepted, rejected, panicked) and `passed`.
+    pub fn to_json(&self) -> JSONValue {
+        let failed = self.failures().len();
+        let mut cases = JSONMap::new();
+        for case in self.cases.iter() {
+            let expected = match case.expectation {
+                Expectation::Accept => "accept",
+                Expectation::Reject => "reject",
+                Expectation::Either => "either",
+            };
+            let outcome = match case.outcome {
+                Outcome::Accepted => "accepted",
+                Outcome::Rejected => "rejected",
+                Outcome::Panicked => "panicked",
+            };
+            let mut result = JSONMap::new();
+            result.insert("expected".to_owned(), Box::new(string(expected)));
+            result.insert("outcome".to_owned(), Box::new(string(outcome)));
+            result.insert(
+                "passed".to_owned(),
+                Box::new(JSONValue::JSONBool(case.passed())),
+            );
+            cases.insert(case.name.clone(), Box::new(JSONValue::JSONObject(result)));
+        }
+        let mut report = JSONMap::new();
+        report.insert(
+            "passed".to_owned(),
+            Box::new(JSONValue::JSONNumber((self.cases.len() - failed) as f64)),
+        );
+        report.insert(
+            "failed".to_owned(),
+            Box::new(JSONValue::JSONNumber(failed as f64)),
+        );
+        report.insert("cases".to_owned(), Box::new(JSONValue::JSONObject(cases)));
+        return JSONValue::JSONObject(report);
+    }
+}
+
+fn string(s: &str) -> JSONValue {
+    return JSONValue::JSONString(s.to_owned());
+}
+
+/// Runs every `y_`, `n_` and `i_` file of a JSONTestSuite `test_parsing` directory
+/// through the parser. Other files are skipped.
+pub fn run_test_suite(dir: &Path) -> io::Result<ConformanceReport> {
+    let options = ParserOptions {
+        max_depth: Some(DEFAULT_MAX_DEPTH),
+        ..ParserOptions::default()
+    };
+    return run_test_suite_with(dir, &options);
+}
+
+pub fn run_test_suite_with(dir: &Path, options: &ParserOptions) -> io::Result<ConformanceReport> {
+    let mut report = ConformanceReport::default();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name.to_owned(),
+            None => continue,
+        };
+        if let Some(expectation) = expectation_of(&name) {
+            let input = fs::read(&path)?;
+            report.cases.push(CaseResult {
+                name,
+                expectation,
+                outcome: check_case(&input, options),
+            });
+        }
+    }
+    report.cases.sort_by(|a, b| a.name.cmp(&b.name));
+    return Ok(report);
+}
+
+pub fn expectation_of(name: &str) -> Option<Expectation> {
+    if name.starts_with("y_") {
+        return Some(Expectation::Accept);
+    }
+    if name.starts_with("n_") {
+        return Some(Expectation::Reject);
+    }
+    if name.starts_with("i_") {
+        return Some(Expectation::Either);
+    }
+    return None;
+}
+
+/// Parses raw case bytes. Input that isn't UTF-8 is rejected.
+pub fn check_case(input: &[u8], options: &ParserOptions) -> Outcome {
+    let text = match str::from_utf8(input) {
+        Ok(text) => text,
+        Err(_) => return Outcome::Rejected,
+    };
+    match panic::catch_unwind(|| JSONValue::parse_with(text, options).is_ok()) {
+        Ok(true) => return Outcome::Accepted,
+        Ok(false) => return Outcome::Rejected,
+        Err(_) => return Outcome::Panicked,
+    }
+}
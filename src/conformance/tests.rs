@@ -0,0 +1,107 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+
+#[test]
+fn test_cases() {
+    let options = ParserOptions::default();
+    for s in vec![
+        (&b"[1]"[..], Outcome::Accepted),
+        (&b"[1,]"[..], Outcome::Rejected),
+        (&b"\"\xff\""[..], Outcome::Rejected),
+    ] {
+        assert_eq!(check_case(s.0, &options), s.1);
+    }
+    for s in vec![
+        ("y_array_empty.json", Some(Expectation::Accept)),
+        ("n_array_comma.json", Some(Expectation::Reject)),
+        ("i_number_huge_exp.json", Some(Expectation::Either)),
+        ("README.md", None),
+    ] {
+        assert_eq!(expectation_of(s.0), s.1);
+    }
+}
+
+#[test]
+fn test_run_test_suite() {
+    let dir = env::temp_dir().join(format!("rsjson-conformance-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for s in vec![
+        ("y_object.json", &b"{\"a\": [1, 2]}"[..]),
+        ("n_trailing_comma.json", &b"[1,]"[..]),
+        ("n_should_fail.json", &b"[2]"[..]),
+        ("i_invalid_utf8.json", &b"[\"\xff\"]"[..]),
+        ("README", &b"not a case"[..]),
+    ] {
+        fs::write(dir.join(s.0), s.1).unwrap();
+    }
+    let report = run_test_suite(&dir);
+    fs::remove_dir_all(&dir).unwrap();
+    let report = report.unwrap();
+
+    let names: Vec<&str> = report.cases.iter().map(|case| case.name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "i_invalid_utf8.json",
+            "n_should_fail.json",
+            "n_trailing_comma.json",
+            "y_object.json"
+        ]
+    );
+    let failures: Vec<&str> = report
+        .failures()
+        .iter()
+        .map(|case| case.name.as_str())
+        .collect();
+    assert_eq!(failures, vec!["n_should_fail.json"]);
+    let json = report.to_json();
+    assert_eq!(json.pointer("/passed"), Some(&JSONValue::JSONNumber(3.0)));
+    assert_eq!(json.pointer("/failed"), Some(&JSONValue::JSONNumber(1.0)));
+    assert_eq!(
+        json.pointer("/cases/n_should_fail.json"),
+        Some(
+            &r#"{"expected": "reject", "outcome": "accepted", "passed": false}"#
+                .parse()
+                .unwrap()
+        )
+    );
+}
+
+/// Runs the corpus at `JSON_TEST_SUITE`, a JSONTestSuite `test_parsing`
+/// directory, or else the sample of its cases in `cases`. Fails on crashes and
+/// on `y_` and `n_` cases decided the wrong way unless `known_failures.txt`
+/// lists them, and on listed cases that pass, so the list stays current.
+#[test]
+fn test_corpus() {
+    let dir = match env::var("JSON_TEST_SUITE") {
+        Ok(dir) => PathBuf::from(dir),
+        Err(_) => Path::new(env!("CARGO_MANIFEST_DIR")).join("src/conformance/cases"),
+    };
+    let report = run_test_suite(&dir).unwrap();
+    println!("{}", report.to_json().to_string_with(&Default::default()));
+    let known: Vec<&str> = include_str!("known_failures.txt")
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+    let unexpected: Vec<&CaseResult> = report
+        .failures()
+        .into_iter()
+        .filter(|case| case.outcome == Outcome::Panicked || !known.contains(&case.name.as_str()))
+        .collect();
+    assert!(unexpected.is_empty(), "{:?}", unexpected);
+    let fixed: Vec<&CaseResult> = report
+        .cases
+        .iter()
+        .filter(|case| case.passed() && known.contains(&case.name.as_str()))
+        .collect();
+    assert!(
+        fixed.is_empty(),
+        "Passing now, remove from known_failures.txt: {:?}",
+        fixed
+    );
+}
@@ -0,0 +1,134 @@
+use super::*;
+use escape::unescape_json_string;
+use std::borrow::Cow;
+
+/// Conversion that may borrow from the value, for decoding many messages
+/// without copying their strings. `&'a str` borrows string values and raw
+/// strings without escapes; `Cow<'a, str>` borrows whenever it can and
+/// unescapes raw strings otherwise. Hooks don't apply, as they produce new
+/// values to borrow from.
+pub trait FromJSONRef<'a>: Sized {
+    fn from_json_ref(value: &'a JSONValue) -> Result<Self, JSONConvertError>;
+}
+
+impl JSONValue {
+    /// Like `convert`, borrowing from the value where `T` allows it, e.g.
+    /// `value.convert_ref::<Vec<&str>>()`.
+    pub fn convert_ref<'a, T: FromJSONRef<'a>>(&'a self) -> Result<T, JSONConvertError> {
+        return T::from_json_ref(self);
+    }
+}
+
+/// Like `convert_at`, for `FromJSONRef` impls of structs with borrowed fields
+pub fn convert_ref_at<'a, T: FromJSONRef<'a>>(
+    value: &'a JSONValue,
+    token: &str,
+) -> Result<T, JSONConvertError> {
+    return T::from_json_ref(value).map_err(|e| e.within(token));
+}
+
+impl<'a> FromJSONRef<'a> for &'a str {
+    fn from_json_ref(value: &'a JSONValue) -> Result<&'a str, JSONConvertError> {
+        match *value {
+            JSONValue::JSONString(ref s) => return Ok(s),
+            JSONValue::JSONRaw(ref raw) => match raw_string(raw) {
+                Some(s) if !s.contains('\\') => return Ok(s),
+                Some(_) => {
+                    return Err(JSONConvertError::unexpected(
+                        "string without escapes",
+                        value,
+                    ))
+                }
+                None => return Err(JSONConvertError::unexpected("string", value)),
+            },
+            ref other => return Err(JSONConvertError::unexpected("string", other)),
+        }
+    }
+}
+
+impl<'a> FromJSONRef<'a> for Cow<'a, str> {
+    fn from_json_ref(value: &'a JSONValue) -> Result<Cow<'a, str>, JSONConvertError> {
+        match *value {
+            JSONValue::JSONString(ref s) => return Ok(Cow::Borrowed(s)),
+            JSONValue::JSONRaw(ref raw) => match raw_string(raw) {
+                // valid JSON, so the escapes are too
+                Some(s) => return Ok(unescape_json_string(s).unwrap()),
+                None => return Err(JSONConvertError::unexpected("string", value)),
+            },
+            ref other => return Err(JSONConvertError::unexpected("string", other)),
+        }
+    }
+}
+
+impl<'a> FromJSONRef<'a> for &'a JSONValue {
+    fn from_json_ref(value: &'a JSONValue) -> Result<&'a JSONValue, JSONConvertError> {
+        return Ok(value);
+    }
+}
+
+/// `null` becomes `None`
+impl<'a, T: FromJSONRef<'a>> FromJSONRef<'a> for Option<T> {
+    fn from_json_ref(value: &'a JSONValue) -> Result<Option<T>, JSONConvertError> {
+        match *value {
+            JSONValue::JSONNull() => return Ok(None),
+            JSONValue::JSONRaw(ref raw) if raw == "null" => return Ok(None),
+            ref other => return T::from_json_ref(other).map(Some),
+        }
+    }
+}
+
+impl<'a, T: FromJSONRef<'a>> FromJSONRef<'a> for Vec<T> {
+    fn from_json_ref(value: &'a JSONValue) -> Result<Vec<T>, JSONConvertError> {
+        match *value {
+            JSONValue::JSONArray(ref items) => {
+                return items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| convert_ref_at(item, &i.to_string()))
+                    .collect()
+            }
+            ref other => return Err(JSONConvertError::unexpected("array", other)),
+        }
+    }
+}
+
+impl<'a, T: FromJSONRef<'a>> FromJSONRef<'a> for HashMap<&'a str, T> {
+    fn from_json_ref(value: &'a JSONValue) -> Result<HashMap<&'a str, T>, JSONConvertError> {
+        match *value {
+            JSONValue::JSONObject(ref members) => {
+                return members
+                    .iter()
+                    .map(|(key, member)| {
+                        return Ok((key.as_str(), convert_ref_at(member, key)?));
+                    })
+                    .collect()
+            }
+            ref other => return Err(JSONConvertError::unexpected("object", other)),
+        }
+    }
+}
+
+// Owned types, converted as usual
+macro_rules! impl_from_json_ref_owned {
+    ($($owned:ty),*) => {
+        $(
+            impl<'a> FromJSONRef<'a> for $owned {
+                fn from_json_ref(value: &'a JSONValue) -> Result<$owned, JSONConvertError> {
+                    return <$owned>::from_json(value);
+                }
+            }
+        )*
+    };
+}
+
+impl_from_json_ref_owned!(
+    bool, String, f32, f64, i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, JSONValue
+);
+
+// The text between the quotes of a raw string
+fn raw_string(raw: &str) -> Option<&str> {
+    if raw.len() >= 2 && raw.starts_with('"') {
+        return Some(&raw[1..raw.len() - 1]);
+    }
+    return None;
+}
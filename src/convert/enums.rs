@@ -0,0 +1,198 @@
+use super::*;
+
+/// How an enum variant is laid out in JSON, following the usual conventions
+/// of JSON APIs. For a variant `Circle` with content `{"r": 1}`:
+/// - `External`: `{"Circle": {"r": 1}}`, unit variants as the bare string `"Circle"`
+/// - `Internal { tag: "type" }`: `{"type": "Circle", "r": 1}`, for object content only
+/// - `Adjacent { tag: "t", content: "c" }`: `{"t": "Circle", "c": {"r": 1}}`
+///
+/// Untagged enums carry no variant name; see `convert_untagged`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EnumRepr {
+    External,
+    Internal {
+        tag: &'static str,
+    },
+    Adjacent {
+        tag: &'static str,
+        content: &'static str,
+    },
+}
+
+/// A variant read from a tagged value, for `FromJSONValue` impls of enums:
+/// match on `name` and convert `content` into the variant's fields.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnumVariant<'a> {
+    pub name: &'a str,
+    /// `None` for unit variants. With `EnumRepr::Internal` this is the whole
+    /// object, tag included.
+    pub content: Option<&'a JSONValue>,
+    // pointer token the content was found under, if any
+    token: Option<&'a str>,
+    // pointer token of the tag
+    tag: Option<&'a str>,
+}
+
+impl EnumRepr {
+    /// Reads the variant name and content of `value`
+    pub fn variant<'a>(&self, value: &'a JSONValue) -> Result<EnumVariant<'a>, JSONConvertError> {
+        let members = match *value {
+            JSONValue::JSONString(ref name) if *self == EnumRepr::External => {
+                return Ok(EnumVariant {
+                    name,
+                    content: None,
+                    token: None,
+                    tag: None,
+                });
+            }
+            JSONValue::JSONObject(ref members) => members,
+            ref other => return Err(JSONConvertError::unexpected("enum variant", other)),
+        };
+        match *self {
+            EnumRepr::External => {
+                let mut iter = members.iter();
+                match (iter.next(), iter.next()) {
+                    (Some((name, content)), None) => {
+                        return Ok(EnumVariant {
+                            name,
+                            content: Some(content),
+                            token: Some(name),
+                            tag: None,
+                        });
+                    }
+                    _ => {
+                        return Err(JSONConvertError::unexpected(
+                            "object with a single key",
+                            value,
+                        ))
+                    }
+                }
+            }
+            EnumRepr::Internal { tag } => {
+                return Ok(EnumVariant {
+                    name: tag_name(members.get(tag).map(|name| &**name), tag)?,
+                    content: Some(value),
+                    token: None,
+                    tag: Some(tag),
+                });
+            }
+            EnumRepr::Adjacent { tag, content } => {
+                return Ok(EnumVariant {
+                    name: tag_name(members.get(tag).map(|name| &**name), tag)?,
+                    content: members.get(content).map(|content| &**content),
+                    token: Some(content),
+                    tag: Some(tag),
+                });
+            }
+        }
+    }
+
+    /// Builds the value of variant `name`, the inverse of `variant`. Internally
+    /// tagged content must be an object, or `None` for unit variants.
+    pub fn tagged(
+        &self,
+        name: &str,
+        content: Option<JSONValue>,
+    ) -> Result<JSONValue, JSONConvertError> {
+        let mut members = JSONMap::new();
+        match (*self, content) {
+            (EnumRepr::External, None) => return Ok(JSONValue::JSONString(name.to_owned())),
+            (EnumRepr::External, Some(content)) => {
+                members.insert(name.to_owned(), Box::new(content));
+            }
+            (EnumRepr::Internal { tag }, content) => {
+                if let Some(content) = content {
+                    match content {
+                        JSONValue::JSONObject(fields) => members = fields,
+                        ref other => {
+                            let err = JSONConvertError::unexpected("object", other);
+                            return Err(err.within(name));
+                        }
+                    }
+                }
+                members.insert(
+                    tag.to_owned(),
+                    Box::new(JSONValue::JSONString(name.to_owned())),
+                );
+            }
+            (EnumRepr::Adjacent { tag, content: key }, content) => {
+                members.insert(
+                    tag.to_owned(),
+                    Box::new(JSONValue::JSONString(name.to_owned())),
+                );
+                if let Some(content) = content {
+                    members.insert(key.to_owned(), Box::new(content));
+                }
+            }
+        }
+        return Ok(JSONValue::JSONObject(members));
+    }
+}
+
+impl<'a> EnumVariant<'a> {
+    /// Converts the content into `T`, with errors pointing into it
+    pub fn convert<T: FromJSONValue>(&self) -> Result<T, JSONConvertError> {
+        let content = match self.content {
+            Some(content) => content,
+            None => {
+                let err =
+                    JSONConvertError::new(format!("Missing content of variant {}", self.name));
+                return Err(self.at_tag(err));
+            }
+        };
+        match self.token {
+            Some(token) => return convert_at(content, token),
+            None => return hooks::convert_hooked(content, false),
+        }
+    }
+
+    /// Error for a variant name the enum doesn't have
+    pub fn unknown(&self, expected: &[&str]) -> JSONConvertError {
+        let err = JSONConvertError::new(format!(
+            "Unknown variant {:?}, expected one of {}",
+            self.name,
+            expected.join(", ")
+        ));
+        return self.at_tag(err);
+    }
+
+    fn at_tag(&self, err: JSONConvertError) -> JSONConvertError {
+        match (self.tag, self.token) {
+            (Some(tag), _) => return err.within(tag),
+            (None, Some(token)) => return err.within(token),
+            (None, None) => return err,
+        }
+    }
+}
+
+/// Conversion of one variant of an untagged enum
+pub type UntaggedVariant<T> = fn(&JSONValue) -> Result<T, JSONConvertError>;
+
+/// Converts an untagged enum by trying `variants` in order; the first that
+/// converts wins. Fails with `name` and the value when none does.
+pub fn convert_untagged<T>(
+    value: &JSONValue,
+    name: &str,
+    variants: &[UntaggedVariant<T>],
+) -> Result<T, JSONConvertError> {
+    for variant in variants {
+        if let Ok(result) = variant(value) {
+            return Ok(result);
+        }
+    }
+    return Err(JSONConvertError::unexpected(
+        &format!("a variant of {}", name),
+        value,
+    ));
+}
+
+fn tag_name<'a>(tag: Option<&'a JSONValue>, key: &str) -> Result<&'a str, JSONConvertError> {
+    let tag = match tag {
+        Some(tag) => tag,
+        None => return Err(JSONConvertError::new(format!("Missing tag {}", key))),
+    };
+    match *tag {
+        JSONValue::JSONString(ref name) => return Ok(name),
+        ref other => return Err(JSONConvertError::unexpected("variant name", other).within(key)),
+    }
+}
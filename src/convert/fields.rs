@@ -0,0 +1,113 @@
+use super::*;
+
+/// What a struct's `FromJSONValue` impl does with keys it has no field for
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum UnknownFields {
+    /// Skip them, for clients that should keep working as the API grows
+    #[default]
+    Ignore,
+    /// Fail on the first one, for strict contracts
+    Deny,
+    /// Keep them, for a catch-all `HashMap<String, JSONValue>` field
+    Collect,
+}
+
+/// Converts field `name` of the object `value`, or the first of `aliases`
+/// present when it's missing, for fields renamed between schema versions.
+/// Errors point at the key that was found.
+pub fn convert_field<T: FromJSONValue>(
+    value: &JSONValue,
+    name: &str,
+    aliases: &[&str],
+) -> Result<T, JSONConvertError> {
+    let value = value.expand_raw();
+    match find_field(&value, name, aliases)? {
+        Some((key, field)) => return convert_at(field, key),
+        None => return Err(JSONConvertError::new(format!("Missing field {}", name))),
+    }
+}
+
+/// Like `convert_field`, with `default` for a missing field. A `null` field
+/// isn't missing: it's converted, so `Option` fields can tell the two apart.
+pub fn convert_field_or<T, F>(
+    value: &JSONValue,
+    name: &str,
+    aliases: &[&str],
+    default: F,
+) -> Result<T, JSONConvertError>
+where
+    T: FromJSONValue,
+    F: FnOnce() -> T,
+{
+    let value = value.expand_raw();
+    match find_field(&value, name, aliases)? {
+        Some((key, field)) => return convert_at(field, key),
+        None => return Ok(default()),
+    }
+}
+
+/// Converts the members of `value` not in `known` as one more object, for a
+/// field whose own fields sit inline in the parent object
+pub fn convert_flattened<T: FromJSONValue>(
+    value: &JSONValue,
+    known: &[&str],
+) -> Result<T, JSONConvertError> {
+    let rest = unknown_fields(value, known, UnknownFields::Collect)?;
+    let members: JSONMap = rest
+        .into_iter()
+        .map(|(key, member)| (key, Box::new(member)))
+        .collect();
+    return hooks::convert_hooked(&JSONValue::JSONObject(members), false);
+}
+
+/// Applies `policy` to the members of `value` that aren't in `known`: the
+/// collected ones with `Collect`, an empty map with `Ignore`, an error
+/// pointing at the member with `Deny`. Fails if `value` isn't an object.
+pub fn unknown_fields(
+    value: &JSONValue,
+    known: &[&str],
+    policy: UnknownFields,
+) -> Result<HashMap<String, JSONValue>, JSONConvertError> {
+    let value = value.expand_raw();
+    let members = match *value {
+        JSONValue::JSONObject(ref members) => members,
+        ref other => return Err(JSONConvertError::unexpected("object", other)),
+    };
+    let mut unknown = HashMap::new();
+    if policy == UnknownFields::Ignore {
+        return Ok(unknown);
+    }
+    for (key, member) in members.iter() {
+        if known.contains(&key.as_str()) {
+            continue;
+        }
+        if policy == UnknownFields::Deny {
+            let reason = format!(
+                "Unknown field {:?}, expected one of {}",
+                key,
+                known.join(", ")
+            );
+            return Err(JSONConvertError::new(reason).within(key));
+        }
+        unknown.insert(key.clone(), (**member).clone());
+    }
+    return Ok(unknown);
+}
+
+// Field `name` or its first alias present, with the key it was found under
+fn find_field<'a>(
+    value: &'a JSONValue,
+    name: &'a str,
+    aliases: &[&'a str],
+) -> Result<Option<(&'a str, &'a JSONValue)>, JSONConvertError> {
+    let members = match *value {
+        JSONValue::JSONObject(ref members) => members,
+        ref other => return Err(JSONConvertError::unexpected("object", other)),
+    };
+    for &key in Some(&name).into_iter().chain(aliases) {
+        if let Some(field) = members.get(key) {
+            return Ok(Some((key, field)));
+        }
+    }
+    return Ok(None);
+}
@@ -0,0 +1,136 @@
+use super::*;
+use pattern::{JSONPatternError, PathPattern};
+use std::any::type_name;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Rewrites a value before it's converted, e.g. `"1,234.56"` into `1234.56`
+pub type ConvertHook = Rc<dyn Fn(&JSONValue) -> Result<JSONValue, JSONConvertError>>;
+
+/// Hooks `JSONValue::convert_with` runs on values before converting them, to
+/// deal with upstream quirks in one place. A value at a path matching an
+/// `on_path` pattern goes through the first such hook; any other value through
+/// the first `on_type` hook for the type it converts into. Hooks see the
+/// elements of `Vec`s, maps and `Option`s, and fields converted with `convert_at`.
+#[derive(Clone, Default)]
+pub struct ConvertHooks {
+    paths: Vec<(PathPattern, ConvertHook)>,
+    types: Vec<(&'static str, ConvertHook)>,
+}
+
+impl ConvertHooks {
+    pub fn new() -> ConvertHooks {
+        return ConvertHooks::default();
+    }
+
+    /// Hook for values at paths matching `pattern`, relative to the converted value
+    pub fn on_path<F>(mut self, pattern: &str, hook: F) -> Result<ConvertHooks, JSONPatternError>
+    where
+        F: Fn(&JSONValue) -> Result<JSONValue, JSONConvertError> + 'static,
+    {
+        self.paths
+            .push((PathPattern::compile(pattern)?, Rc::new(hook)));
+        return Ok(self);
+    }
+
+    /// Hook for values converted into `T`: `hooks.on_type::<f64, _>(parse_money)`
+    pub fn on_type<T: FromJSONValue, F>(mut self, hook: F) -> ConvertHooks
+    where
+        F: Fn(&JSONValue) -> Result<JSONValue, JSONConvertError> + 'static,
+    {
+        self.types.push((type_name::<T>(), Rc::new(hook)));
+        return self;
+    }
+
+    fn find(&self, path: &[String], with_path: bool, target: &str) -> Option<ConvertHook> {
+        if with_path {
+            let by_path = self
+                .paths
+                .iter()
+                .find(|&(pattern, _)| pattern.matches_tokens(path));
+            if let Some((_, hook)) = by_path {
+                return Some(hook.clone());
+            }
+        }
+        let by_type = self.types.iter().find(|&&(name, _)| name == target);
+        return by_type.map(|(_, hook)| hook.clone());
+    }
+}
+
+struct Active {
+    hooks: ConvertHooks,
+    // tokens of the value being converted
+    path: Vec<String>,
+}
+
+thread_local! {
+    static ACTIVE: RefCell<Option<Active>> = const { RefCell::new(None) };
+}
+
+impl JSONValue {
+    /// Like `convert`, with `hooks` rewriting values on the way
+    pub fn convert_with<T: FromJSONValue>(
+        &self,
+        hooks: &ConvertHooks,
+    ) -> Result<T, JSONConvertError> {
+        let active = Active {
+            hooks: hooks.clone(),
+            path: vec![],
+        };
+        let previous = ACTIVE.with(|cell| cell.replace(Some(active)));
+        // restores the previous hooks even if a conversion panics
+        struct Restore(Option<Active>);
+        impl Drop for Restore {
+            fn drop(&mut self) {
+                let previous = self.0.take();
+                ACTIVE.with(|cell| *cell.borrow_mut() = previous);
+            }
+        }
+        let _restore = Restore(previous);
+        return convert_hooked(self, true);
+    }
+}
+
+/// Converts `value`, found under `token` of the value being converted. For
+/// `FromJSONValue` impls of structs: hooks apply to the fields converted
+/// through it, and errors point into the field.
+pub fn convert_at<T: FromJSONValue>(value: &JSONValue, token: &str) -> Result<T, JSONConvertError> {
+    let tracked = ACTIVE.with(|cell| match *cell.borrow_mut() {
+        Some(ref mut active) => {
+            active.path.push(token.to_owned());
+            return true;
+        }
+        None => return false,
+    });
+    if !tracked {
+        return T::from_json(value).map_err(|e| e.within(token));
+    }
+    struct Pop;
+    impl Drop for Pop {
+        fn drop(&mut self) {
+            ACTIVE.with(|cell| {
+                if let Some(ref mut active) = *cell.borrow_mut() {
+                    active.path.pop();
+                }
+            });
+        }
+    }
+    let _pop = Pop;
+    return convert_hooked(value, true).map_err(|e| e.within(token));
+}
+
+// Converts through the hook for the current path or `T`, if any
+pub(super) fn convert_hooked<T: FromJSONValue>(
+    value: &JSONValue,
+    with_path: bool,
+) -> Result<T, JSONConvertError> {
+    let hook = ACTIVE.with(|cell| match *cell.borrow() {
+        Some(ref active) => return active.hooks.find(&active.path, with_path, type_name::<T>()),
+        None => return None,
+    });
+    match hook {
+        // the hook's result isn't hooked again, but its elements are
+        Some(hook) => return T::from_json(&hook(value)?),
+        None => return T::from_json(value),
+    }
+}
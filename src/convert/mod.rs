@@ -0,0 +1,220 @@
+use super::*;
+use pointer::escape_token;
+use serializer::SerializerConfig;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::iter::FromIterator;
+
+#[cfg(test)]
+mod tests;
+
+mod borrowed;
+mod enums;
+mod fields;
+mod hooks;
+mod validate;
+
+pub use self::borrowed::{convert_ref_at, FromJSONRef};
+pub use self::enums::{convert_untagged, EnumRepr, EnumVariant, UntaggedVariant};
+pub use self::fields::{
+    convert_field, convert_field_or, convert_flattened, unknown_fields, UnknownFields,
+};
+pub use self::hooks::{convert_at, ConvertHook, ConvertHooks};
+pub use self::validate::{convert_checked, Check};
+
+// Longer values are cut in error messages
+const MAX_SHOWN_LENGTH: usize = 40;
+
+/// Failed conversion of a value into a Rust type. `path` is the JSON Pointer
+/// of the offending value, relative to the value the conversion started from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JSONConvertError {
+    pub reason: String,
+    pub path: String,
+}
+
+impl JSONConvertError {
+    pub fn new(reason: String) -> JSONConvertError {
+        return JSONConvertError {
+            reason,
+            path: String::new(),
+        };
+    }
+
+    /// Error for a value of the wrong type, e.g. "Expected u16, got string \"80\""
+    pub fn unexpected(expected: &str, actual: &JSONValue) -> JSONConvertError {
+        return JSONConvertError::new(format!("Expected {}, got {}", expected, describe(actual)));
+    }
+
+    /// Prepends a pointer token, as the error bubbles up from a nested value
+    pub fn within(mut self, token: &str) -> JSONConvertError {
+        self.path = format!("/{}{}", escape_token(token), self.path);
+        return self;
+    }
+
+    /// Prepends a whole pointer
+    pub fn within_pointer(mut self, pointer: &str) -> JSONConvertError {
+        self.path = format!("{}{}", pointer, self.path);
+        return self;
+    }
+}
+
+impl fmt::Display for JSONConvertError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            return write!(f, "{}", self.reason);
+        }
+        return write!(f, "{} at {}", self.reason, self.path);
+    }
+}
+
+impl Error for JSONConvertError {}
+
+/// Conversion from a JSON value into a Rust type. Errors point at the
+/// nested value that didn't fit, e.g. `/2/port` for a `Vec` of structs.
+pub trait FromJSONValue: Sized {
+    fn from_json(value: &JSONValue) -> Result<Self, JSONConvertError>;
+}
+
+impl JSONValue {
+    /// Converts the value into `T`, e.g. `value.convert::<Vec<u16>>()`.
+    pub fn convert<T: FromJSONValue>(&self) -> Result<T, JSONConvertError> {
+        return T::from_json(self);
+    }
+}
+
+impl FromJSONValue for JSONValue {
+    fn from_json(value: &JSONValue) -> Result<JSONValue, JSONConvertError> {
+        return Ok(value.expand_raw().into_owned());
+    }
+}
+
+impl FromJSONValue for bool {
+    fn from_json(value: &JSONValue) -> Result<bool, JSONConvertError> {
+        match *value.expand_raw() {
+            JSONValue::JSONBool(b) => return Ok(b),
+            ref other => return Err(JSONConvertError::unexpected("bool", other)),
+        }
+    }
+}
+
+impl FromJSONValue for String {
+    fn from_json(value: &JSONValue) -> Result<String, JSONConvertError> {
+        match *value.expand_raw() {
+            JSONValue::JSONString(ref s) => return Ok(s.clone()),
+            ref other => return Err(JSONConvertError::unexpected("string", other)),
+        }
+    }
+}
+
+impl FromJSONValue for f64 {
+    fn from_json(value: &JSONValue) -> Result<f64, JSONConvertError> {
+        match *value.expand_raw() {
+            JSONValue::JSONNumber(n) => return Ok(n),
+            ref other => return Err(JSONConvertError::unexpected("number", other)),
+        }
+    }
+}
+
+impl FromJSONValue for f32 {
+    fn from_json(value: &JSONValue) -> Result<f32, JSONConvertError> {
+        return f64::from_json(value).map(|n| n as f32);
+    }
+}
+
+macro_rules! impl_from_json_integer {
+    ($($int:ident),*) => {
+        $(
+            impl FromJSONValue for $int {
+                fn from_json(value: &JSONValue) -> Result<$int, JSONConvertError> {
+                    match *value.expand_raw() {
+                        // MAX + 1 is a power of two, so unlike MAX it converts exactly
+                        JSONValue::JSONNumber(n)
+                            if n.fract() == 0.0
+                                && n >= $int::MIN as f64
+                                && n < ($int::MAX / 2 + 1) as f64 * 2.0 =>
+                        {
+                            return Ok(n as $int)
+                        }
+                        ref other => return Err(JSONConvertError::unexpected(stringify!($int), other)),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_from_json_integer!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// `null` becomes `None`
+impl<T: FromJSONValue> FromJSONValue for Option<T> {
+    fn from_json(value: &JSONValue) -> Result<Option<T>, JSONConvertError> {
+        match *value.expand_raw() {
+            JSONValue::JSONNull() => return Ok(None),
+            ref other => return hooks::convert_hooked(other, false).map(Some),
+        }
+    }
+}
+
+impl<T: FromJSONValue> FromJSONValue for Vec<T> {
+    fn from_json(value: &JSONValue) -> Result<Vec<T>, JSONConvertError> {
+        match *value.expand_raw() {
+            JSONValue::JSONArray(ref items) => {
+                return items
+                    .iter()
+                    .enumerate()
+                    .map(|(i, item)| convert_at(item, &i.to_string()))
+                    .collect()
+            }
+            ref other => return Err(JSONConvertError::unexpected("array", other)),
+        }
+    }
+}
+
+impl<T: FromJSONValue> FromJSONValue for HashMap<String, T> {
+    fn from_json(value: &JSONValue) -> Result<HashMap<String, T>, JSONConvertError> {
+        return from_json_object(value);
+    }
+}
+
+impl<T: FromJSONValue> FromJSONValue for BTreeMap<String, T> {
+    fn from_json(value: &JSONValue) -> Result<BTreeMap<String, T>, JSONConvertError> {
+        return from_json_object(value);
+    }
+}
+
+fn from_json_object<T, M>(value: &JSONValue) -> Result<M, JSONConvertError>
+where
+    T: FromJSONValue,
+    M: FromIterator<(String, T)>,
+{
+    match *value.expand_raw() {
+        JSONValue::JSONObject(ref members) => {
+            return members
+                .iter()
+                .map(|(key, member)| {
+                    return Ok((key.clone(), convert_at(member, key)?));
+                })
+                .collect()
+        }
+        ref other => return Err(JSONConvertError::unexpected("object", other)),
+    }
+}
+
+// Type and a short rendering of the value, for error messages
+fn describe(value: &JSONValue) -> String {
+    let kind = match *value {
+        JSONValue::JSONNull() => return "null".to_owned(),
+        JSONValue::JSONBool(_) => "bool",
+        JSONValue::JSONNumber(_) => "number",
+        JSONValue::JSONString(_) => "string",
+        JSONValue::JSONArray(_) => "array",
+        JSONValue::JSONObject(_) => "object",
+        JSONValue::JSONRaw(_) => "raw JSON",
+    };
+    let mut shown = value.to_string_with(&SerializerConfig::compact());
+    if shown.chars().count() > MAX_SHOWN_LENGTH {
+        shown = shown.chars().take(MAX_SHOWN_LENGTH).collect::<String>() + "...";
+    }
+    return format!("{} {}", kind, shown);
+}
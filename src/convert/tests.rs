@@ -0,0 +1,661 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_scalars() {
+    assert_eq!(parse("true").convert::<bool>(), Ok(true));
+    assert_eq!(parse("\"x\"").convert::<String>(), Ok("x".to_owned()));
+    assert_eq!(parse("1.5").convert::<f64>(), Ok(1.5));
+    assert_eq!(parse("1.5").convert::<f32>(), Ok(1.5));
+    assert_eq!(parse("-128").convert::<i8>(), Ok(-128));
+    assert_eq!(parse("65535").convert::<u16>(), Ok(65535));
+    assert_eq!(parse("null").convert::<Option<u8>>(), Ok(None));
+    assert_eq!(parse("7").convert::<Option<u8>>(), Ok(Some(7)));
+    assert_eq!(
+        JSONValue::raw("[1]").unwrap().convert::<Vec<u8>>(),
+        Ok(vec![1])
+    );
+    for s in vec![
+        ("65536", "Expected u16, got number 65536"),
+        ("-1", "Expected u16, got number -1"),
+        ("1.5", "Expected u16, got number 1.5"),
+        ("\"80\"", "Expected u16, got string \"80\""),
+        ("null", "Expected u16, got null"),
+        (
+            "[\"a very long string that gets cut in messages\"]",
+            "Expected u16, got array [\"a very long string that gets cut in me...",
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        let err = parse(s.0).convert::<u16>().unwrap_err();
+        assert_eq!(err.reason, s.1);
+        assert_eq!(err.path, "");
+    }
+    assert_eq!(
+        parse("1").convert::<bool>().unwrap_err().reason,
+        "Expected bool, got number 1"
+    );
+}
+
+#[test]
+fn test_integer_bounds() {
+    assert_eq!(parse("127").convert::<i8>(), Ok(127));
+    assert!(parse("128").convert::<i8>().is_err());
+    assert!(parse("-129").convert::<i8>().is_err());
+    assert_eq!(parse("4294967295").convert::<u32>(), Ok(u32::MAX));
+    assert!(parse("4294967296").convert::<u32>().is_err());
+    assert_eq!(parse("-2147483648").convert::<i32>(), Ok(i32::MIN));
+    assert!(parse("-2147483649").convert::<i32>().is_err());
+    // above 2^53 only some integers are numbers; these are the closest to the bounds
+    assert_eq!(
+        parse("9223372036854774784").convert::<i64>(),
+        Ok(9223372036854774784)
+    );
+    assert!(parse("9223372036854775807").convert::<i64>().is_err());
+    assert!(parse("9223372036854775808").convert::<i64>().is_err());
+    assert_eq!(parse("-9223372036854775808").convert::<i64>(), Ok(i64::MIN));
+    assert!(parse("-9223372036854777856").convert::<i64>().is_err());
+    assert_eq!(
+        parse("18446744073709549568").convert::<u64>(),
+        Ok(18446744073709549568)
+    );
+    assert!(parse("18446744073709551615").convert::<u64>().is_err());
+    assert!(parse("18446744073709551616").convert::<u64>().is_err());
+    assert_eq!(parse("0").convert::<u64>(), Ok(0));
+    assert!(parse("-1").convert::<u64>().is_err());
+}
+
+#[test]
+fn test_nested_paths() {
+    let value = parse(r#"{"a/b": [[1, 2], [3, "x"]]}"#);
+    let err = value
+        .convert::<HashMap<String, Vec<Vec<u8>>>>()
+        .unwrap_err();
+    assert_eq!(err.path, "/a~1b/1/1");
+    assert_eq!(
+        err.to_string(),
+        "Expected u8, got string \"x\" at /a~1b/1/1"
+    );
+    let value = parse(r#"{"b": {"x": 1}, "a": {}}"#);
+    let map = value
+        .convert::<BTreeMap<String, BTreeMap<String, u8>>>()
+        .unwrap();
+    assert_eq!(map["b"]["x"], 1);
+    assert!(map["a"].is_empty());
+    assert_eq!(value.convert::<JSONValue>(), Ok(value.clone()));
+}
+
+#[derive(Debug, PartialEq)]
+struct Item {
+    name: String,
+    price: f64,
+}
+
+impl FromJSONValue for Item {
+    fn from_json(value: &JSONValue) -> Result<Item, JSONConvertError> {
+        let field = |key: &str| match value.pointer(&format!("/{}", key)) {
+            Some(field) => return Ok(field),
+            None => return Err(JSONConvertError::new(format!("Missing {}", key))),
+        };
+        return Ok(Item {
+            name: convert_at(field("name")?, "name")?,
+            price: convert_at(field("price")?, "price")?,
+        });
+    }
+}
+
+fn parse_money(value: &JSONValue) -> Result<JSONValue, JSONConvertError> {
+    match *value {
+        JSONValue::JSONString(ref s) => match s.replace(',', "").parse::<f64>() {
+            Ok(n) => return Ok(JSONValue::JSONNumber(n)),
+            Err(_) => return Err(JSONConvertError::new(format!("Bad amount {}", s))),
+        },
+        ref other => return Ok(other.clone()),
+    }
+}
+
+#[test]
+fn test_hooks() {
+    let value = parse(r#"[{"name": "a", "price": "1,234.56"}, {"name": "b", "price": 2}]"#);
+    let hooks = ConvertHooks::new().on_type::<f64, _>(parse_money);
+    assert_eq!(
+        value.convert_with::<Vec<Item>>(&hooks),
+        Ok(vec![
+            Item {
+                name: "a".to_owned(),
+                price: 1234.56,
+            },
+            Item {
+                name: "b".to_owned(),
+                price: 2.0,
+            },
+        ])
+    );
+    // hooks only apply inside convert_with
+    let err = value.convert::<Vec<Item>>().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Expected number, got string \"1,234.56\" at /0/price"
+    );
+    let bad = parse(r#"{"x": [null, "1.2.3"]}"#);
+    let err = bad
+        .convert_with::<HashMap<String, Vec<Option<f64>>>>(&hooks)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Bad amount 1.2.3 at /x/1");
+
+    // path hooks take precedence over type hooks
+    let hooks = hooks
+        .on_path("/*/name", |value| match *value {
+            JSONValue::JSONString(ref s) => return Ok(JSONValue::JSONString(s.to_uppercase())),
+            ref other => return Ok(other.clone()),
+        })
+        .unwrap()
+        .on_path("/1/price", |_| return Ok(JSONValue::JSONNumber(0.0)))
+        .unwrap();
+    let items = value.convert_with::<Vec<Item>>(&hooks).unwrap();
+    assert_eq!(items[0].name, "A");
+    assert_eq!(items[0].price, 1234.56);
+    assert_eq!(items[1].price, 0.0);
+    assert!(ConvertHooks::new()
+        .on_path("no slash", parse_money)
+        .is_err());
+
+    // a hook converting on its own doesn't disturb the outer paths
+    let hooks = ConvertHooks::new().on_type::<u8, _>(|value| {
+        let inner = ConvertHooks::new().on_type::<f64, _>(parse_money);
+        return Ok(JSONValue::JSONNumber(value.convert_with::<f64>(&inner)?));
+    });
+    let err = parse(r#"{"a": ["1", "x"]}"#)
+        .convert_with::<BTreeMap<String, Vec<u8>>>(&hooks)
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Bad amount x at /a/1");
+}
+
+#[derive(Debug, PartialEq)]
+enum Shape {
+    Circle { r: f64 },
+    Square(f64),
+    Empty,
+}
+
+fn shape(value: &JSONValue, repr: EnumRepr) -> Result<Shape, JSONConvertError> {
+    let variant = repr.variant(value)?;
+    match variant.name {
+        "Circle" => {
+            let r = match variant.content.and_then(|content| content.pointer("/r")) {
+                Some(r) => convert_at(r, "r"),
+                None => Err(JSONConvertError::new("Missing r".to_owned())),
+            };
+            return Ok(Shape::Circle { r: r? });
+        }
+        "Square" => return Ok(Shape::Square(variant.convert()?)),
+        "Empty" => return Ok(Shape::Empty),
+        _ => return Err(variant.unknown(&["Circle", "Square", "Empty"])),
+    }
+}
+
+#[test]
+fn test_enums() {
+    let internal = EnumRepr::Internal { tag: "type" };
+    let adjacent = EnumRepr::Adjacent {
+        tag: "t",
+        content: "c",
+    };
+    for s in vec![
+        (
+            EnumRepr::External,
+            r#"{"Circle": {"r": 1}}"#,
+            Shape::Circle { r: 1.0 },
+        ),
+        (EnumRepr::External, r#"{"Square": 2}"#, Shape::Square(2.0)),
+        (EnumRepr::External, r#""Empty""#, Shape::Empty),
+        (
+            internal,
+            r#"{"type": "Circle", "r": 1}"#,
+            Shape::Circle { r: 1.0 },
+        ),
+        (internal, r#"{"type": "Empty"}"#, Shape::Empty),
+        (
+            adjacent,
+            r#"{"t": "Circle", "c": {"r": 1}}"#,
+            Shape::Circle { r: 1.0 },
+        ),
+        (adjacent, r#"{"c": 2, "t": "Square"}"#, Shape::Square(2.0)),
+        (adjacent, r#"{"t": "Empty"}"#, Shape::Empty),
+    ] {
+        println!("Checking {}", s.1);
+        assert_eq!(shape(&parse(s.1), s.0), Ok(s.2));
+    }
+    for s in vec![
+        (
+            EnumRepr::External,
+            r#"{"Square": "x"}"#,
+            "Expected number, got string \"x\" at /Square",
+        ),
+        (
+            EnumRepr::External,
+            r#""Hexagon""#,
+            "Unknown variant \"Hexagon\", expected one of Circle, Square, Empty",
+        ),
+        (
+            EnumRepr::External,
+            "{}",
+            "Expected object with a single key, got object {}",
+        ),
+        (
+            EnumRepr::External,
+            "1",
+            "Expected enum variant, got number 1",
+        ),
+        (
+            internal,
+            r#"{"type": "Circle", "r": true}"#,
+            "Expected number, got bool true at /r",
+        ),
+        (internal, r#"{"kind": "Circle"}"#, "Missing tag type"),
+        (
+            internal,
+            r#"{"type": 1}"#,
+            "Expected variant name, got number 1 at /type",
+        ),
+        (
+            internal,
+            r#""Empty""#,
+            "Expected enum variant, got string \"Empty\"",
+        ),
+        (
+            adjacent,
+            r#"{"t": "Square", "c": []}"#,
+            "Expected number, got array [] at /c",
+        ),
+        (
+            adjacent,
+            r#"{"t": "Square"}"#,
+            "Missing content of variant Square at /t",
+        ),
+        (
+            adjacent,
+            r#"{"t": "Hexagon"}"#,
+            "Unknown variant \"Hexagon\", expected one of Circle, Square, Empty at /t",
+        ),
+    ] {
+        println!("Checking {}", s.1);
+        assert_eq!(shape(&parse(s.1), s.0).unwrap_err().to_string(), s.2);
+    }
+}
+
+#[test]
+fn test_enums_tagged() {
+    let internal = EnumRepr::Internal { tag: "type" };
+    let adjacent = EnumRepr::Adjacent {
+        tag: "t",
+        content: "c",
+    };
+    let circle = parse(r#"{"r": 1}"#);
+    for s in vec![
+        (
+            EnumRepr::External,
+            Some(circle.clone()),
+            r#"{"Circle": {"r": 1}}"#,
+        ),
+        (EnumRepr::External, None, r#""Empty""#),
+        (
+            internal,
+            Some(circle.clone()),
+            r#"{"r": 1, "type": "Circle"}"#,
+        ),
+        (internal, None, r#"{"type": "Empty"}"#),
+        (
+            adjacent,
+            Some(circle.clone()),
+            r#"{"t": "Circle", "c": {"r": 1}}"#,
+        ),
+        (adjacent, None, r#"{"t": "Empty"}"#),
+    ] {
+        println!("Checking {}", s.2);
+        let (name, expected) = match s.1 {
+            Some(_) => ("Circle", Shape::Circle { r: 1.0 }),
+            None => ("Empty", Shape::Empty),
+        };
+        let tagged = s.0.tagged(name, s.1).unwrap();
+        assert_eq!(tagged, parse(s.2));
+        assert_eq!(shape(&tagged, s.0), Ok(expected));
+    }
+    let err = EnumRepr::Internal { tag: "type" }
+        .tagged("Square", Some(parse("2")))
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Expected object, got number 2 at /Square");
+}
+
+#[test]
+fn test_untagged() {
+    let variants: Vec<UntaggedVariant<Shape>> = vec![
+        |value| return Ok(Shape::Square(value.convert()?)),
+        |value| return shape(value, EnumRepr::External),
+    ];
+    assert_eq!(
+        convert_untagged(&parse("2"), "Shape", &variants),
+        Ok(Shape::Square(2.0))
+    );
+    assert_eq!(
+        convert_untagged(&parse(r#""Empty""#), "Shape", &variants),
+        Ok(Shape::Empty)
+    );
+    assert_eq!(
+        convert_untagged(&parse("[]"), "Shape", &variants)
+            .unwrap_err()
+            .to_string(),
+        "Expected a variant of Shape, got array []"
+    );
+}
+
+#[derive(Debug, PartialEq)]
+struct Message<'a> {
+    topic: &'a str,
+    body: Cow<'a, str>,
+    tags: Vec<&'a str>,
+    id: u32,
+}
+
+impl<'a> FromJSONRef<'a> for Message<'a> {
+    fn from_json_ref(value: &'a JSONValue) -> Result<Message<'a>, JSONConvertError> {
+        let field = |key: &str| match value.pointer(&format!("/{}", key)) {
+            Some(field) => return Ok(field),
+            None => return Err(JSONConvertError::new(format!("Missing {}", key))),
+        };
+        return Ok(Message {
+            topic: convert_ref_at(field("topic")?, "topic")?,
+            body: convert_ref_at(field("body")?, "body")?,
+            tags: convert_ref_at(field("tags")?, "tags")?,
+            id: convert_ref_at(field("id")?, "id")?,
+        });
+    }
+}
+
+#[test]
+fn test_borrowed() {
+    let value = parse(r#"[{"topic": "a", "body": "x\ny", "tags": ["t"], "id": 1}]"#);
+    let messages = value.convert_ref::<Vec<Message>>().unwrap();
+    assert_eq!(messages[0].topic, "a");
+    assert_eq!(messages[0].body, "x\ny");
+    assert_eq!(messages[0].tags, vec!["t"]);
+    assert_eq!(messages[0].id, 1);
+    // the strings point into the value
+    let topic = value.pointer("/0/topic").unwrap();
+    assert!(matches!(messages[0].body, Cow::Borrowed(_)));
+    match *topic {
+        JSONValue::JSONString(ref s) => assert_eq!(s.as_ptr(), messages[0].topic.as_ptr()),
+        _ => panic!("Not a string"),
+    }
+
+    for s in vec![
+        ("\"plain\"", "plain", true),
+        ("\"esc\\u0061ped\"", "escaped", false),
+    ] {
+        println!("Checking {}", s.0);
+        let raw = JSONValue::raw(s.0).unwrap();
+        let cow = raw.convert_ref::<Cow<str>>().unwrap();
+        assert_eq!(cow, s.1);
+        assert_eq!(matches!(cow, Cow::Borrowed(_)), s.2);
+        assert_eq!(raw.convert_ref::<&str>().is_ok(), s.2);
+    }
+    assert_eq!(
+        JSONValue::raw("\"a\\nb\"")
+            .unwrap()
+            .convert_ref::<&str>()
+            .unwrap_err()
+            .reason,
+        "Expected string without escapes, got raw JSON \"a\\nb\""
+    );
+    assert_eq!(
+        JSONValue::raw("null")
+            .unwrap()
+            .convert_ref::<Option<&str>>(),
+        Ok(None)
+    );
+
+    let value = parse(r#"{"a": ["x", null], "b/c": [1]}"#);
+    let err = value
+        .convert_ref::<HashMap<&str, Vec<Option<&str>>>>()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Expected string, got number 1 at /b~1c/0");
+    let err = parse(r#"[{"topic": "a", "body": 1}]"#)
+        .convert_ref::<Vec<Message>>()
+        .unwrap_err();
+    assert_eq!(err.to_string(), "Expected string, got number 1 at /0/body");
+}
+
+const SETTINGS_FIELDS: &[&str] = &["theme", "size"];
+
+#[derive(Debug, PartialEq)]
+struct Settings {
+    theme: String,
+    size: u8,
+    extra: HashMap<String, JSONValue>,
+}
+
+fn settings(value: &JSONValue, policy: UnknownFields) -> Result<Settings, JSONConvertError> {
+    let extra = unknown_fields(value, SETTINGS_FIELDS, policy)?;
+    let field = |key: &str| match value.pointer(&format!("/{}", key)) {
+        Some(field) => return Ok(field),
+        None => return Err(JSONConvertError::new(format!("Missing {}", key))),
+    };
+    return Ok(Settings {
+        theme: convert_at(field("theme")?, "theme")?,
+        size: convert_at(field("size")?, "size")?,
+        extra,
+    });
+}
+
+#[test]
+fn test_unknown_fields() {
+    let value = parse(r#"{"theme": "dark", "size": 2, "font": {"name": "mono"}}"#);
+    let font = parse(r#"{"name": "mono"}"#);
+    for s in vec![
+        (UnknownFields::Ignore, Ok(vec![])),
+        (UnknownFields::Collect, Ok(vec![("font", font.clone())])),
+        (
+            UnknownFields::Deny,
+            Err("Unknown field \"font\", expected one of theme, size at /font"),
+        ),
+    ] {
+        println!("Checking {:?}", s.0);
+        match (settings(&value, s.0), s.1) {
+            (Ok(settings), Ok(extra)) => {
+                assert_eq!(settings.theme, "dark");
+                assert_eq!(settings.size, 2);
+                let expected: HashMap<String, JSONValue> = extra
+                    .into_iter()
+                    .map(|(key, value)| (key.to_owned(), value))
+                    .collect();
+                assert_eq!(settings.extra, expected);
+            }
+            (Err(err), Err(expected)) => assert_eq!(err.to_string(), expected),
+            (result, expected) => panic!("{:?} isn't {:?}", result, expected),
+        }
+    }
+    let known = parse(r#"{"theme": "light", "size": 1}"#);
+    assert!(settings(&known, UnknownFields::Deny)
+        .unwrap()
+        .extra
+        .is_empty());
+    let raw = JSONValue::raw(r#"{"theme": "light", "size": 1, "x": 1}"#).unwrap();
+    assert_eq!(
+        unknown_fields(&raw, SETTINGS_FIELDS, UnknownFields::Collect)
+            .unwrap()
+            .len(),
+        1
+    );
+    assert_eq!(
+        unknown_fields(&parse("[]"), SETTINGS_FIELDS, UnknownFields::Ignore)
+            .unwrap_err()
+            .reason,
+        "Expected object, got array []"
+    );
+}
+
+#[derive(Debug, PartialEq)]
+struct Retry {
+    attempts: u8,
+    delay: f64,
+}
+
+#[derive(Debug, PartialEq)]
+struct Job {
+    name: String,
+    priority: u8,
+    owner: Option<String>,
+    retry: Retry,
+}
+
+impl FromJSONValue for Retry {
+    fn from_json(value: &JSONValue) -> Result<Retry, JSONConvertError> {
+        return Ok(Retry {
+            attempts: convert_field_or(value, "attempts", &[], || return 3)?,
+            delay: convert_field(value, "delay", &["backoff"])?,
+        });
+    }
+}
+
+impl FromJSONValue for Job {
+    fn from_json(value: &JSONValue) -> Result<Job, JSONConvertError> {
+        return Ok(Job {
+            name: convert_field(value, "name", &["title", "label"])?,
+            priority: convert_field_or(value, "priority", &[], || return 5)?,
+            owner: convert_field_or(value, "owner", &[], || return None)?,
+            retry: convert_flattened(value, &["name", "title", "label", "priority", "owner"])?,
+        });
+    }
+}
+
+#[test]
+fn test_field_attributes() {
+    for s in vec![
+        (
+            r#"{"name": "a", "priority": 1, "owner": "me", "attempts": 2, "delay": 0.5}"#,
+            ("a", 1, Some("me"), 2, 0.5),
+        ),
+        (r#"{"title": "b", "backoff": 1}"#, ("b", 5, None, 3, 1.0)),
+        (
+            r#"{"label": "c", "title": "b", "owner": null, "delay": 2}"#,
+            ("b", 5, None, 3, 2.0),
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        let expected = Job {
+            name: (s.1).0.to_owned(),
+            priority: (s.1).1,
+            owner: (s.1).2.map(|owner: &str| owner.to_owned()),
+            retry: Retry {
+                attempts: (s.1).3,
+                delay: (s.1).4,
+            },
+        };
+        assert_eq!(parse(s.0).convert::<Job>(), Ok(expected));
+    }
+    for s in vec![
+        (r#"{"delay": 1}"#, "Missing field name"),
+        (
+            r#"{"title": 1, "delay": 1}"#,
+            "Expected string, got number 1 at /title",
+        ),
+        (
+            r#"{"name": "a", "priority": null, "delay": 1}"#,
+            "Expected u8, got null at /priority",
+        ),
+        (r#"{"name": "a"}"#, "Missing field delay"),
+        (
+            r#"{"name": "a", "backoff": "x"}"#,
+            "Expected number, got string \"x\" at /backoff",
+        ),
+        ("[]", "Expected object, got array []"),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(parse(s.0).convert::<Job>().unwrap_err().to_string(), s.1);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Signup {
+    name: String,
+    email: String,
+    age: u8,
+    code: String,
+    tags: Vec<String>,
+}
+
+fn is_code(s: &str) -> bool {
+    return s.len() == 4 && s.bytes().all(|b| b.is_ascii_uppercase());
+}
+
+impl FromJSONValue for Signup {
+    fn from_json(value: &JSONValue) -> Result<Signup, JSONConvertError> {
+        let field = |key: &str| match value.pointer(&format!("/{}", key)) {
+            Some(field) => return Ok(field),
+            None => return Err(JSONConvertError::new(format!("Missing {}", key))),
+        };
+        return Ok(Signup {
+            name: convert_checked(field("name")?, "name", &[Check::NonEmpty])?,
+            email: convert_checked(field("email")?, "email", &[Check::Format("email")])?,
+            age: convert_checked(field("age")?, "age", &[Check::Min(13.0), Check::Max(120.0)])?,
+            code: convert_checked(
+                field("code")?,
+                "code",
+                &[Check::Matches("[A-Z]{4}", is_code)],
+            )?,
+            tags: convert_checked(field("tags")?, "tags", &[Check::NonEmpty])?,
+        });
+    }
+}
+
+#[test]
+fn test_checks() {
+    let valid = r#"{"name": "a", "email": "a@b.c", "age": 30, "code": "ABCD", "tags": ["x"]}"#;
+    assert_eq!(parse(valid).convert::<Signup>().unwrap().age, 30);
+    for s in vec![
+        (r#"{"name": ""}"#, "Value is empty at /0/name"),
+        (
+            r#"{"email": "nope"}"#,
+            "\"nope\" is not a valid email at /0/email",
+        ),
+        (
+            r#"{"age": 12}"#,
+            "Value 12 is below the minimum 13 at /0/age",
+        ),
+        (
+            r#"{"age": 200}"#,
+            "Value 200 is above the maximum 120 at /0/age",
+        ),
+        (
+            r#"{"age": "30"}"#,
+            "Expected u8, got string \"30\" at /0/age",
+        ),
+        (
+            r#"{"code": "abcd"}"#,
+            "\"abcd\" doesn't match [A-Z]{4} at /0/code",
+        ),
+        (r#"{"tags": []}"#, "Value is empty at /0/tags"),
+    ] {
+        println!("Checking {}", s.0);
+        let mut value = parse(valid);
+        let (key, field) = match parse(s.0) {
+            JSONValue::JSONObject(members) => members.into_iter().next().unwrap(),
+            _ => panic!("Not an object"),
+        };
+        *value.pointer_mut(&format!("/{}", key)).unwrap() = *field;
+        let err = JSONValue::JSONArray(vec![Box::new(value)])
+            .convert::<Vec<Signup>>()
+            .unwrap_err();
+        assert_eq!(err.to_string(), s.1);
+    }
+    let err =
+        convert_checked::<String>(&parse("\"x\""), "f", &[Check::Format("color")]).unwrap_err();
+    assert_eq!(err.to_string(), "Unknown format color at /f");
+}
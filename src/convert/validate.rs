@@ -0,0 +1,78 @@
+use super::*;
+use schema::FormatRegistry;
+
+/// A constraint on a field, checked on its JSON value by `convert_checked`
+/// before it's converted, so parsing and validating take one pass.
+#[derive(Debug, Clone, Copy)]
+pub enum Check {
+    /// Numbers at least this
+    Min(f64),
+    /// Numbers at most this
+    Max(f64),
+    /// Strings, arrays and objects with something in them
+    NonEmpty,
+    /// Strings of a format `FormatRegistry::builtin` knows, e.g. `email`
+    Format(&'static str),
+    /// Strings accepted by the function, described by the name in errors;
+    /// stands in for a regular expression, which the crate has no engine for
+    Matches(&'static str, fn(&str) -> bool),
+}
+
+/// Checks `value` against `checks`, then converts it like `convert_at`.
+/// A failed check is an error pointing at the field, e.g. "Value 120 is above
+/// the maximum 100 at /volume". Checks of other kinds of values pass.
+pub fn convert_checked<T: FromJSONValue>(
+    value: &JSONValue,
+    token: &str,
+    checks: &[Check],
+) -> Result<T, JSONConvertError> {
+    let expanded = value.expand_raw();
+    for check in checks {
+        if let Err(reason) = check.apply(&expanded) {
+            return Err(JSONConvertError::new(reason).within(token));
+        }
+    }
+    return convert_at(value, token);
+}
+
+impl Check {
+    fn apply(&self, value: &JSONValue) -> Result<(), String> {
+        let number = match *value {
+            JSONValue::JSONNumber(n) => Some(n),
+            _ => None,
+        };
+        let string = match *value {
+            JSONValue::JSONString(ref s) => Some(s.as_str()),
+            _ => None,
+        };
+        match (*self, number, string) {
+            (Check::Min(min), Some(n), _) if n < min => {
+                return Err(format!("Value {} is below the minimum {}", n, min));
+            }
+            (Check::Max(max), Some(n), _) if n > max => {
+                return Err(format!("Value {} is above the maximum {}", n, max));
+            }
+            (Check::NonEmpty, _, _) if is_empty(value) => return Err("Value is empty".to_owned()),
+            (Check::Format(format), _, Some(s)) => match FormatRegistry::builtin().check(format, s)
+            {
+                Some(true) => return Ok(()),
+                Some(false) => return Err(format!("{:?} is not a valid {}", s, format)),
+                None => return Err(format!("Unknown format {}", format)),
+            },
+            (Check::Matches(name, matches), _, Some(s)) if !matches(s) => {
+                return Err(format!("{:?} doesn't match {}", s, name));
+            }
+            _ => return Ok(()),
+        }
+    }
+}
+
+// Strings, arrays and objects without anything in them
+fn is_empty(value: &JSONValue) -> bool {
+    match *value {
+        JSONValue::JSONString(ref s) => return s.is_empty(),
+        JSONValue::JSONArray(ref items) => return items.is_empty(),
+        JSONValue::JSONObject(ref members) => return members.is_empty(),
+        _ => return false,
+    }
+}
@@ -0,0 +1,857 @@
+use super::*;
+use patch::PatchOperation;
+use pointer::{parse_index, parse_pointer};
+use serializer::{write_string, SerializerConfig};
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::mem;
+use std::str::CharIndices;
+
+#[cfg(test)]
+mod tests;
+
+const END_OF_ARRAY: &str = "-";
+
+#[derive(Debug, Clone)]
+pub struct JSONCstError {
+    pub reason: String,
+    /// Byte offset in the input for parse errors
+    pub position: Option<usize>,
+}
+
+/// Lossless concrete syntax tree of a JSON or JSONC document. Whitespace and
+/// comments ("trivia") are kept in the nodes next to the tokens they surround and
+/// scalars keep their literal text, so `to_string` reproduces the input byte for byte.
+/// Edits through `set` and `remove` only touch the nodes they change, which keeps
+/// comments, formatting and key order of the rest of a config file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstDocument {
+    /// Trivia before the root value
+    pub leading: String,
+    pub root: CstNode,
+    /// Trivia after the root value
+    pub trailing: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CstNode {
+    /// String, number, boolean or null with its text as written in the input
+    Scalar {
+        raw: String,
+        value: JSONValue,
+    },
+    Array(CstContainer<CstItem>),
+    Object(CstContainer<CstMember>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstContainer<T> {
+    pub entries: Vec<T>,
+    /// Whether the last entry is followed by a comma
+    pub trailing_comma: bool,
+    /// Trivia after the trailing comma, or everything inside an empty container
+    pub closing: String,
+}
+
+/// Array item with the trivia before it and between it and the following `,` or `]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstItem {
+    pub before: String,
+    pub value: CstNode,
+    pub after: String,
+}
+
+/// Object member with the trivia around its key, colon and value
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstMember {
+    pub before: String,
+    pub key: String,
+    /// Key literal as written, quotes and escapes included
+    pub raw_key: String,
+    pub before_colon: String,
+    pub after_colon: String,
+    pub value: CstNode,
+    pub after: String,
+}
+
+// Items and members share the trivia handling of insertion and removal
+trait CstEntry {
+    fn before(&mut self) -> &mut String;
+    fn after(&mut self) -> &mut String;
+}
+
+impl CstEntry for CstItem {
+    fn before(&mut self) -> &mut String {
+        return &mut self.before;
+    }
+
+    fn after(&mut self) -> &mut String {
+        return &mut self.after;
+    }
+}
+
+impl CstEntry for CstMember {
+    fn before(&mut self) -> &mut String {
+        return &mut self.before;
+    }
+
+    fn after(&mut self) -> &mut String {
+        return &mut self.after;
+    }
+}
+
+impl CstDocument {
+    /// Parses JSON, tolerating comments and trailing commas.
+    pub fn parse(input: &str) -> Result<CstDocument, JSONCstError> {
+        let mut parser = CstParser {
+            input,
+            chars: input.char_indices().peekable(),
+        };
+        let leading = parser.trivia()?;
+        let root = parser.value()?;
+        let trailing = parser.trivia()?;
+        if let Some((i, ch)) = parser.chars.next() {
+            return Err(make_positioned_err(
+                format!("Unexpected charachter {} after the value", ch),
+                i,
+            ));
+        }
+        return Ok(CstDocument {
+            leading,
+            root,
+            trailing,
+        });
+    }
+
+    /// The document as a plain value, comments and formatting dropped.
+    pub fn to_value(&self) -> JSONValue {
+        return self.root.to_value();
+    }
+
+    /// Looks up a node by JSON Pointer.
+    pub fn get(&self, pointer: &str) -> Option<&CstNode> {
+        let mut current = &self.root;
+        for token in parse_pointer(pointer)? {
+            current = current.child(&token)?;
+        }
+        return Some(current);
+    }
+
+    fn get_mut(&mut self, tokens: &[String]) -> Option<&mut CstNode> {
+        let mut current = &mut self.root;
+        for token in tokens {
+            current = current.child_mut(token)?;
+        }
+        return Some(current);
+    }
+
+    /// Replaces the node at the pointer, adds a missing object member or appends
+    /// to an array for `-`. New entries copy the indentation of their siblings;
+    /// new values themselves are written compactly.
+    pub fn set(&mut self, pointer: &str, value: &JSONValue) -> Result<(), JSONCstError> {
+        return self.set_node(pointer, CstNode::from_value(value));
+    }
+
+    fn set_node(&mut self, pointer: &str, node: CstNode) -> Result<(), JSONCstError> {
+        let mut tokens = parse_pointer(pointer)
+            .ok_or_else(|| make_err(format!("Invalid pointer {}", pointer)))?;
+        if let Some(existing) = self.get_mut(&tokens) {
+            *existing = node;
+            return Ok(());
+        }
+        let token = tokens
+            .pop()
+            .ok_or_else(|| make_err(format!("Invalid pointer {}", pointer)))?;
+        match self.get_mut(&tokens) {
+            Some(&mut CstNode::Object(ref mut object)) => {
+                let raw_key = quote(&token);
+                let (before_colon, after_colon) = match object.entries.last() {
+                    Some(last) => (last.before_colon.clone(), last.after_colon.clone()),
+                    None => (String::new(), String::new()),
+                };
+                push_entry(
+                    object,
+                    CstMember {
+                        before: String::new(),
+                        key: token,
+                        raw_key,
+                        before_colon,
+                        after_colon,
+                        value: node,
+                        after: String::new(),
+                    },
+                );
+            }
+            Some(&mut CstNode::Array(ref mut array)) if token == END_OF_ARRAY => {
+                push_entry(
+                    array,
+                    CstItem {
+                        before: String::new(),
+                        value: node,
+                        after: String::new(),
+                    },
+                );
+            }
+            Some(&mut CstNode::Array(_)) => {
+                return Err(make_err(format!("Index out of bounds in {}", pointer)))
+            }
+            Some(_) => {
+                return Err(make_err(format!(
+                    "Parent of {} is not a container",
+                    pointer
+                )))
+            }
+            None => return Err(make_err(format!("Parent of {} doesn't exist", pointer))),
+        }
+        return Ok(());
+    }
+
+    /// Removes the node at the pointer together with its comma and the trivia before it.
+    pub fn remove(&mut self, pointer: &str) -> Result<(), JSONCstError> {
+        let mut tokens = parse_pointer(pointer)
+            .ok_or_else(|| make_err(format!("Invalid pointer {}", pointer)))?;
+        let token = tokens
+            .pop()
+            .ok_or_else(|| make_err("Can't remove the whole document".to_owned()))?;
+        let removed = match self.get_mut(&tokens) {
+            Some(&mut CstNode::Object(ref mut object)) => {
+                match object
+                    .entries
+                    .iter()
+                    .rposition(|member| member.key == token)
+                {
+                    Some(i) => {
+                        remove_entry(object, i);
+                        true
+                    }
+                    None => false,
+                }
+            }
+            Some(&mut CstNode::Array(ref mut array)) => match parse_index(&token) {
+                Some(i) if i < array.entries.len() => {
+                    remove_entry(array, i);
+                    true
+                }
+                _ => false,
+            },
+            _ => false,
+        };
+        if !removed {
+            return Err(make_err(format!("Nothing to remove at {}", pointer)));
+        }
+        return Ok(());
+    }
+}
+
+impl CstDocument {
+    /// Applies an RFC 6902 patch, with the semantics of `JSONValue::apply_patch`,
+    /// touching only the nodes the operations change: untouched formatting and
+    /// comments stay as they are, moved and copied values keep theirs, and new
+    /// values are written like `set` writes them. Atomic like `apply_patch`.
+    pub fn apply_patch(&mut self, patch: &[PatchOperation]) -> Result<(), JSONCstError> {
+        let mut result = self.clone();
+        for op in patch {
+            result.apply_operation(op)?;
+        }
+        *self = result;
+        return Ok(());
+    }
+
+    fn apply_operation(&mut self, op: &PatchOperation) -> Result<(), JSONCstError> {
+        match *op {
+            PatchOperation::Add {
+                ref path,
+                ref value,
+            } => return self.add_node(path, CstNode::from_value(value)),
+            PatchOperation::Remove { ref path } => return self.remove(path),
+            PatchOperation::Replace {
+                ref path,
+                ref value,
+            } => {
+                if self.get(path).is_none() {
+                    return Err(make_err(format!("Nothing to replace at {}", path)));
+                }
+                return self.set(path, value);
+            }
+            PatchOperation::Move { ref from, ref path } => {
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(make_err(format!(
+                        "Unable to move {} into its own child",
+                        from
+                    )));
+                }
+                let node = self
+                    .get(from)
+                    .ok_or_else(|| make_err(format!("Nothing to remove at {}", from)))?
+                    .clone();
+                self.remove(from)?;
+                return self.add_node(path, node);
+            }
+            PatchOperation::Copy { ref from, ref path } => {
+                let node = self
+                    .get(from)
+                    .ok_or_else(|| make_err(format!("Nothing to copy at {}", from)))?
+                    .clone();
+                return self.add_node(path, node);
+            }
+            PatchOperation::Test {
+                ref path,
+                ref value,
+            } => {
+                if self.get(path).map(|node| node.to_value()).as_ref() != Some(value) {
+                    return Err(make_err(format!("Test failed at {}", path)));
+                }
+                return Ok(());
+            }
+        }
+    }
+
+    // `add` inserts into arrays where `set` replaces
+    fn add_node(&mut self, pointer: &str, node: CstNode) -> Result<(), JSONCstError> {
+        let mut tokens = parse_pointer(pointer)
+            .ok_or_else(|| make_err(format!("Invalid pointer {}", pointer)))?;
+        let token = match tokens.pop() {
+            Some(token) => token,
+            None => return self.set_node(pointer, node),
+        };
+        if let Some(&mut CstNode::Array(ref mut array)) = self.get_mut(&tokens) {
+            if token != END_OF_ARRAY {
+                let index = parse_index(&token)
+                    .ok_or_else(|| make_err(format!("Invalid index in {}", pointer)))?;
+                let item = CstItem {
+                    before: String::new(),
+                    value: node,
+                    after: String::new(),
+                };
+                match index.cmp(&array.entries.len()) {
+                    Ordering::Less => insert_entry(array, index, item),
+                    Ordering::Equal => push_entry(array, item),
+                    Ordering::Greater => {
+                        return Err(make_err(format!("Index out of bounds in {}", pointer)))
+                    }
+                }
+                return Ok(());
+            }
+        }
+        return self.set_node(pointer, node);
+    }
+}
+
+impl FromStr for CstDocument {
+    type Err = JSONCstError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return CstDocument::parse(s);
+    }
+}
+
+impl fmt::Display for CstDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.leading)?;
+        self.root.write(f)?;
+        return f.write_str(&self.trailing);
+    }
+}
+
+impl CstNode {
+    /// Node without any trivia, written like the compact serializer does.
+    pub fn from_value(value: &JSONValue) -> CstNode {
+        match *value {
+            JSONValue::JSONArray(ref items) => {
+                return CstNode::Array(CstContainer::from_entries(
+                    items
+                        .iter()
+                        .map(|item| CstItem {
+                            before: String::new(),
+                            value: CstNode::from_value(item),
+                            after: String::new(),
+                        })
+                        .collect(),
+                ))
+            }
+            JSONValue::JSONObject(ref members) => {
+                return CstNode::Object(CstContainer::from_entries(
+                    members
+                        .iter()
+                        .map(|(key, member)| CstMember {
+                            before: String::new(),
+                            key: key.clone(),
+                            raw_key: quote(key),
+                            before_colon: String::new(),
+                            after_colon: String::new(),
+                            value: CstNode::from_value(member),
+                            after: String::new(),
+                        })
+                        .collect(),
+                ))
+            }
+            _ => {
+                return CstNode::Scalar {
+                    raw: value.to_string_with(&SerializerConfig::compact()),
+                    value: value.clone(),
+                }
+            }
+        }
+    }
+
+    pub fn to_value(&self) -> JSONValue {
+        match *self {
+            CstNode::Scalar { ref value, .. } => return value.clone(),
+            CstNode::Array(ref array) => {
+                return JSONValue::JSONArray(
+                    array
+                        .entries
+                        .iter()
+                        .map(|item| Box::new(item.value.to_value()))
+                        .collect(),
+                )
+            }
+            CstNode::Object(ref object) => {
+                let mut map = JSONMap::new();
+                for member in object.entries.iter() {
+                    map.insert(member.key.clone(), Box::new(member.value.to_value()));
+                }
+                return JSONValue::JSONObject(map);
+            }
+        }
+    }
+
+    // Duplicate keys resolve to the last member, like they do in the parser
+    fn child(&self, token: &str) -> Option<&CstNode> {
+        match *self {
+            CstNode::Object(ref object) => {
+                let member = object.entries.iter().rev().find(|m| m.key == token)?;
+                return Some(&member.value);
+            }
+            CstNode::Array(ref array) => {
+                return array
+                    .entries
+                    .get(parse_index(token)?)
+                    .map(|item| &item.value)
+            }
+            CstNode::Scalar { .. } => return None,
+        }
+    }
+
+    fn child_mut(&mut self, token: &str) -> Option<&mut CstNode> {
+        match *self {
+            CstNode::Object(ref mut object) => {
+                let member = object.entries.iter_mut().rev().find(|m| m.key == token)?;
+                return Some(&mut member.value);
+            }
+            CstNode::Array(ref mut array) => {
+                return array
+                    .entries
+                    .get_mut(parse_index(token)?)
+                    .map(|item| &mut item.value)
+            }
+            CstNode::Scalar { .. } => return None,
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CstNode::Scalar { ref raw, .. } => return f.write_str(raw),
+            CstNode::Array(ref array) => {
+                f.write_str("[")?;
+                for (i, item) in array.entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    f.write_str(&item.before)?;
+                    item.value.write(f)?;
+                    f.write_str(&item.after)?;
+                }
+                array.write_closing(f)?;
+                return f.write_str("]");
+            }
+            CstNode::Object(ref object) => {
+                f.write_str("{")?;
+                for (i, member) in object.entries.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    f.write_str(&member.before)?;
+                    f.write_str(&member.raw_key)?;
+                    f.write_str(&member.before_colon)?;
+                    f.write_str(":")?;
+                    f.write_str(&member.after_colon)?;
+                    member.value.write(f)?;
+                    f.write_str(&member.after)?;
+                }
+                object.write_closing(f)?;
+                return f.write_str("}");
+            }
+        }
+    }
+}
+
+impl<T> CstContainer<T> {
+    fn from_entries(entries: Vec<T>) -> CstContainer<T> {
+        return CstContainer {
+            entries,
+            trailing_comma: false,
+            closing: String::new(),
+        };
+    }
+
+    fn write_closing(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.trailing_comma {
+            f.write_str(",")?;
+        }
+        return f.write_str(&self.closing);
+    }
+}
+
+// The new entry takes the indentation of the last one, and the trivia
+// before the closing bracket moves after it
+fn push_entry<T: CstEntry>(container: &mut CstContainer<T>, mut entry: T) {
+    if let Some(last) = container.entries.last_mut() {
+        *entry.before() = indentation(last.before());
+        if !container.trailing_comma {
+            *entry.after() = mem::take(last.after());
+        }
+    }
+    container.entries.push(entry);
+}
+
+// The new entry copies the indentation of the one it pushes back, which keeps
+// the trivia before it. A first entry written right after the bracket gets
+// the spacing of the second one, so that `[1, 2]` becomes `[0, 1, 2]`.
+fn insert_entry<T: CstEntry>(container: &mut CstContainer<T>, i: usize, mut entry: T) {
+    *entry.before() = indentation(container.entries[i].before());
+    if i == 0 && container.entries[0].before().is_empty() {
+        let spacing = match container.entries.get_mut(1) {
+            Some(second) => indentation(second.before()),
+            None => " ".to_owned(),
+        };
+        *container.entries[0].before() = spacing;
+    }
+    container.entries.insert(i, entry);
+}
+
+// The trivia before the closing bracket stays in place. A first entry
+// passes its indentation on, so that `[1, 2]` becomes `[2]`, not `[ 2]`.
+// A line comment after the comma is the removed entry's and goes with it,
+// while the one before the removed entry stays with the previous entry.
+fn remove_entry<T: CstEntry>(container: &mut CstContainer<T>, i: usize) {
+    let mut removed = container.entries.remove(i);
+    let kept = match line_comment(removed.before()) {
+        Some(end) if i > 0 => removed.before()[..end].to_owned(),
+        _ => String::new(),
+    };
+    if let Some(next) = container.entries.get_mut(i) {
+        drop_line_comment(next.before());
+        keep_line_comment(next.before(), &kept);
+    } else if container.trailing_comma {
+        drop_line_comment(&mut container.closing);
+        keep_line_comment(&mut container.closing, &kept);
+    } else {
+        drop_line_comment(removed.after());
+        keep_line_comment(removed.after(), &kept);
+        match container.entries.last_mut() {
+            Some(last) => last.after().push_str(removed.after()),
+            None => container.closing = mem::take(removed.after()),
+        }
+    }
+    if i == 0 {
+        if let Some(next) = container.entries.first_mut() {
+            if next.before().trim().is_empty() {
+                *next.before() = indentation(removed.before());
+            }
+        }
+    }
+    if container.entries.is_empty() {
+        container.trailing_comma = false;
+    }
+}
+
+// The end of the trivia's first line, past the newline, if it holds a comment
+fn line_comment(trivia: &str) -> Option<usize> {
+    let end = trivia.find('\n')?;
+    if trivia[..end].contains("//") || trivia[..end].contains("/*") {
+        return Some(end + 1);
+    }
+    return None;
+}
+
+// Drops a line comment that starts the trivia, keeping the newline after it
+fn drop_line_comment(trivia: &mut String) {
+    if let Some(end) = trivia.find('\n') {
+        if trivia[..end].trim_start().starts_with("//") {
+            trivia.drain(..end);
+        }
+    }
+}
+
+// Puts a kept comment line in front of the trivia, sharing its newline
+fn keep_line_comment(trivia: &mut String, kept: &str) {
+    if kept.is_empty() {
+        return;
+    }
+    if trivia.starts_with('\n') {
+        trivia.insert_str(0, &kept[..kept.len() - 1]);
+    } else {
+        trivia.insert_str(0, kept);
+    }
+}
+
+// Whitespace that starts the last line of the trivia, without any comments
+fn indentation(trivia: &str) -> String {
+    match trivia.rfind('\n') {
+        Some(i) if !trivia[i..].contains('/') => return trivia[i..].to_owned(),
+        Some(_) => return "\n".to_owned(),
+        None if !trivia.contains('/') => return trivia.to_owned(),
+        None => return " ".to_owned(),
+    }
+}
+
+fn quote(key: &str) -> String {
+    let mut raw = String::new();
+    write_string(&mut raw, key).unwrap();
+    return raw;
+}
+
+struct CstParser<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+}
+
+impl<'a> CstParser<'a> {
+    fn peek(&mut self) -> Option<(usize, char)> {
+        return self.chars.peek().cloned();
+    }
+
+    fn offset(&mut self) -> usize {
+        return self.peek().map_or(self.input.len(), |(i, _)| i);
+    }
+
+    fn unexpected<T>(&mut self) -> Result<T, JSONCstError> {
+        match self.chars.next() {
+            Some((i, ch)) => {
+                return Err(make_positioned_err(
+                    format!("Unexpected charachter {}", ch),
+                    i,
+                ))
+            }
+            None => {
+                return Err(make_positioned_err(
+                    "String ended unexpectedly".to_owned(),
+                    self.input.len(),
+                ))
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<usize, JSONCstError> {
+        match self.peek() {
+            Some((i, ch)) if ch == expected => {
+                self.chars.next();
+                return Ok(i);
+            }
+            _ => return self.unexpected(),
+        }
+    }
+
+    fn trivia(&mut self) -> Result<String, JSONCstError> {
+        let start = self.offset();
+        while let Some((i, ch)) = self.peek() {
+            match ch {
+                ' ' | '\t' | '\n' | '\r' => {
+                    self.chars.next();
+                }
+                '/' => {
+                    self.chars.next();
+                    self.comment(i)?;
+                }
+                _ => break,
+            }
+        }
+        let end = self.offset();
+        return Ok(self.input[start..end].to_owned());
+    }
+
+    // The leading slash is consumed
+    fn comment(&mut self, start: usize) -> Result<(), JSONCstError> {
+        match self.chars.next() {
+            Some((_, '/')) => {
+                while let Some((_, ch)) = self.peek() {
+                    if ch == '\n' {
+                        break;
+                    }
+                    self.chars.next();
+                }
+            }
+            Some((_, '*')) => {
+                let mut previous = ' ';
+                loop {
+                    match self.chars.next() {
+                        Some((_, '/')) if previous == '*' => break,
+                        Some((_, ch)) => previous = ch,
+                        None => {
+                            return Err(make_positioned_err(
+                                "Unterminated comment".to_owned(),
+                                start,
+                            ))
+                        }
+                    }
+                }
+            }
+            _ => {
+                return Err(make_positioned_err(
+                    "Unexpected charachter /".to_owned(),
+                    start,
+                ))
+            }
+        }
+        return Ok(());
+    }
+
+    fn value(&mut self) -> Result<CstNode, JSONCstError> {
+        match self.peek() {
+            Some((_, '[')) => return Ok(CstNode::Array(self.container(']', CstParser::item)?)),
+            Some((_, '{')) => return Ok(CstNode::Object(self.container('}', CstParser::member)?)),
+            Some((_, '"')) => {
+                let (raw, value) = self.string()?;
+                return Ok(CstNode::Scalar { raw, value });
+            }
+            Some((start, ch)) if ch == '-' || ch.is_ascii_alphanumeric() => {
+                while let Some((_, ch)) = self.peek() {
+                    if !(ch.is_ascii_alphanumeric() || "+-.".contains(ch)) {
+                        break;
+                    }
+                    self.chars.next();
+                }
+                let end = self.offset();
+                let raw = &self.input[start..end];
+                match raw.parse() {
+                    Ok(value) => {
+                        return Ok(CstNode::Scalar {
+                            raw: raw.to_owned(),
+                            value,
+                        })
+                    }
+                    Err(_) => {
+                        return Err(make_positioned_err(
+                            format!("Invalid literal {}", raw),
+                            start,
+                        ))
+                    }
+                }
+            }
+            _ => return self.unexpected(),
+        }
+    }
+
+    fn string(&mut self) -> Result<(String, JSONValue), JSONCstError> {
+        let start = self.expect('"')?;
+        loop {
+            match self.chars.next() {
+                Some((i, '"')) => {
+                    let raw = &self.input[start..=i];
+                    match raw.parse() {
+                        Ok(value) => return Ok((raw.to_owned(), value)),
+                        Err(_) => {
+                            return Err(make_positioned_err(
+                                format!("Invalid string {}", raw),
+                                start,
+                            ))
+                        }
+                    }
+                }
+                Some((_, '\\')) => {
+                    self.chars.next();
+                }
+                Some(_) => (),
+                None => return self.unexpected(),
+            }
+        }
+    }
+
+    fn item(&mut self, before: String) -> Result<CstItem, JSONCstError> {
+        let value = self.value()?;
+        let after = self.trivia()?;
+        return Ok(CstItem {
+            before,
+            value,
+            after,
+        });
+    }
+
+    fn member(&mut self, before: String) -> Result<CstMember, JSONCstError> {
+        let (raw_key, key) = match self.peek() {
+            Some((_, '"')) => match self.string()? {
+                (raw, JSONValue::JSONString(key)) => (raw, key),
+                _ => return self.unexpected(),
+            },
+            _ => return self.unexpected(),
+        };
+        let before_colon = self.trivia()?;
+        self.expect(':')?;
+        let after_colon = self.trivia()?;
+        let value = self.value()?;
+        let after = self.trivia()?;
+        return Ok(CstMember {
+            before,
+            key,
+            raw_key,
+            before_colon,
+            after_colon,
+            value,
+            after,
+        });
+    }
+
+    // The opening bracket is next; `entry` parses an entry given the trivia before it
+    fn container<T, F>(&mut self, end: char, entry: F) -> Result<CstContainer<T>, JSONCstError>
+    where
+        F: Fn(&mut CstParser<'a>, String) -> Result<T, JSONCstError>,
+    {
+        self.chars.next();
+        let mut container = CstContainer {
+            entries: vec![],
+            trailing_comma: false,
+            closing: String::new(),
+        };
+        let mut trivia = self.trivia()?;
+        if self.peek().map(|(_, ch)| ch) == Some(end) {
+            self.chars.next();
+            container.closing = trivia;
+            return Ok(container);
+        }
+        loop {
+            container.entries.push(entry(self, trivia)?);
+            match self.peek() {
+                Some((_, ch)) if ch == end => {
+                    self.chars.next();
+                    return Ok(container);
+                }
+                Some((_, ',')) => {
+                    self.chars.next();
+                    trivia = self.trivia()?;
+                    if self.peek().map(|(_, ch)| ch) == Some(end) {
+                        self.chars.next();
+                        container.trailing_comma = true;
+                        container.closing = trivia;
+                        return Ok(container);
+                    }
+                }
+                _ => return self.unexpected(),
+            }
+        }
+    }
+}
+
+fn make_err(s: String) -> JSONCstError {
+    JSONCstError {
+        reason: s,
+        position: None,
+    }
+}
+
+fn make_positioned_err(s: String, position: usize) -> JSONCstError {
+    JSONCstError {
+        reason: s,
+        position: Some(position),
+    }
+}
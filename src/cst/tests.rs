@@ -0,0 +1,296 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+const CONFIG: &str = r#"// Service configuration
+{
+    "name": "api", // shown in logs
+    /* connection */
+    "db": {
+        "host"  :  "localhost",
+        "port": 5432,
+    },
+    "tags": [ "a",
+              "b" ],
+    "ratio": 1.50e0
+}
+"#;
+
+#[test]
+fn test_round_trip() {
+    for s in vec![
+        CONFIG,
+        "null",
+        "  [ ]  ",
+        "{}",
+        "[1,2,3,]",
+        r#"{"a" : "é\n", "b": [true, false, null, -0.0]}"#,
+        "/* a */ [ /* b */ 1 /* c */ , // d\n 2 ] // e",
+    ] {
+        println!("Checking {}", s);
+        let document = CstDocument::parse(s).unwrap();
+        assert_eq!(document.to_string(), s);
+    }
+}
+
+#[test]
+fn test_to_value() {
+    let document: CstDocument = CONFIG.parse().unwrap();
+    assert_eq!(
+        document.to_value(),
+        r#"{"name": "api", "db": {"host": "localhost", "port": 5432}, "tags": ["a", "b"], "ratio": 1.5}"#
+            .parse()
+            .unwrap()
+    );
+    assert_eq!(
+        document.get("/db/port").map(|node| node.to_value()),
+        Some(JSONValue::JSONNumber(5432.0))
+    );
+    assert_eq!(document.get("/tags/2"), None);
+}
+
+#[test]
+fn test_parse_errors() {
+    for s in vec![
+        ("", "String ended unexpectedly", 0),
+        ("[1 2]", "Unexpected charachter 2", 3),
+        ("{\"a\" 1}", "Unexpected charachter 1", 5),
+        ("[1] x", "Unexpected charachter x after the value", 4),
+        ("[1 /* x", "Unterminated comment", 3),
+        ("[tru]", "Invalid literal tru", 1),
+        ("{1: 2}", "Unexpected charachter 1", 1),
+        ("[,]", "Unexpected charachter ,", 1),
+    ] {
+        println!("Checking {}", s.0);
+        let err = CstDocument::parse(s.0).unwrap_err();
+        assert_eq!(err.reason, s.1);
+        assert_eq!(err.position, Some(s.2));
+    }
+}
+
+#[test]
+fn test_set() {
+    let mut document: CstDocument = CONFIG.parse().unwrap();
+    document
+        .set("/db/host", &JSONValue::JSONString("db.internal".to_owned()))
+        .unwrap();
+    document
+        .set("/db/user", &JSONValue::JSONString("admin".to_owned()))
+        .unwrap();
+    document
+        .set("/tags/-", &JSONValue::JSONString("c".to_owned()))
+        .unwrap();
+    document
+        .set("/debug", &"{\"level\": 2}".parse().unwrap())
+        .unwrap();
+    assert_eq!(
+        document.to_string(),
+        r#"// Service configuration
+{
+    "name": "api", // shown in logs
+    /* connection */
+    "db": {
+        "host"  :  "db.internal",
+        "port": 5432,
+        "user": "admin",
+    },
+    "tags": [ "a",
+              "b",
+              "c" ],
+    "ratio": 1.50e0,
+    "debug": {"level":2}
+}
+"#
+    );
+
+    let mut empty: CstDocument = "{ }".parse().unwrap();
+    empty.set("/a", &JSONValue::JSONBool(true)).unwrap();
+    empty.set("/b", &JSONValue::JSONNull()).unwrap();
+    assert_eq!(empty.to_string(), r#"{"a":true,"b":null }"#);
+
+    for s in vec![
+        ("db", "Invalid pointer db"),
+        ("/tags/5", "Index out of bounds in /tags/5"),
+        ("/name/x", "Parent of /name/x is not a container"),
+        ("/missing/x", "Parent of /missing/x doesn't exist"),
+    ] {
+        println!("Checking {}", s.0);
+        let err = document.set(s.0, &JSONValue::JSONNull()).unwrap_err();
+        assert_eq!(err.reason, s.1);
+    }
+}
+
+#[test]
+fn test_remove() {
+    let mut document: CstDocument = CONFIG.parse().unwrap();
+    document.remove("/db/port").unwrap();
+    document.remove("/tags/0").unwrap();
+    document.remove("/ratio").unwrap();
+    assert_eq!(
+        document.to_string(),
+        r#"// Service configuration
+{
+    "name": "api", // shown in logs
+    /* connection */
+    "db": {
+        "host"  :  "localhost",
+    },
+    "tags": [ "b" ]
+}
+"#
+    );
+    document.remove("/db/host").unwrap();
+    assert_eq!(
+        document.get("/db").unwrap().to_value(),
+        "{}".parse().unwrap()
+    );
+    assert!(document.to_string().contains("\"db\": {\n    }"));
+
+    for s in vec![
+        ("[1, 2, 3]", "/1", "[1, 3]"),
+        ("[1, 2, 3]", "/2", "[1, 2]"),
+        ("[1, 2, 3]", "/0", "[2, 3]"),
+        ("[ 1 ]", "/0", "[ ]"),
+        ("{\"a\": 1, \"b\": 2}", "/b", "{\"a\": 1}"),
+        ("{\"a\": 1, // one\n \"b\": 2}", "/a", "{\"b\": 2}"),
+        (
+            "{\"a\": 1, // keep me\n \"b\": 2, // two\n \"c\": 3}",
+            "/b",
+            "{\"a\": 1, // keep me\n \"c\": 3}",
+        ),
+        (
+            "{\"a\": 1, // keep me\n \"b\": 2, \"c\": 3}",
+            "/b",
+            "{\"a\": 1, // keep me\n \"c\": 3}",
+        ),
+        (
+            "{\"a\": 1, // keep me\n \"b\": 2 // two\n}",
+            "/b",
+            "{\"a\": 1 // keep me\n}",
+        ),
+        (
+            "[1, /* keep me */\n 2, // two\n]",
+            "/1",
+            "[1, /* keep me */\n]",
+        ),
+    ] {
+        println!("Checking {} {}", s.0, s.1);
+        let mut document: CstDocument = s.0.parse().unwrap();
+        document.remove(s.1).unwrap();
+        assert_eq!(document.to_string(), s.2);
+    }
+    for s in vec!["", "/x", "/tags/5", "/name/0"] {
+        println!("Checking {}", s);
+        assert!(document.remove(s).is_err());
+    }
+}
+
+#[test]
+fn test_apply_patch() {
+    let patch = patch::parse_patch(
+        &r#"[
+            {"op": "test", "path": "/name", "value": "api"},
+            {"op": "replace", "path": "/db/port", "value": 6432},
+            {"op": "add", "path": "/tags/1", "value": "a2"},
+            {"op": "add", "path": "/tags/0", "value": "a0"},
+            {"op": "remove", "path": "/ratio"},
+            {"op": "copy", "from": "/db", "path": "/replica"},
+            {"op": "move", "from": "/name", "path": "/service"}
+        ]"#
+        .parse()
+        .unwrap(),
+    )
+    .unwrap();
+    let mut document: CstDocument = CONFIG.parse().unwrap();
+    document.apply_patch(&patch).unwrap();
+    assert_eq!(
+        document.to_string(),
+        r#"// Service configuration
+{
+    /* connection */
+    "db": {
+        "host"  :  "localhost",
+        "port": 6432,
+    },
+    "tags": [ "a0", "a",
+              "a2",
+              "b" ],
+    "replica": {
+        "host"  :  "localhost",
+        "port": 6432,
+    },
+    "service": "api"
+}
+"#
+    );
+    let mut expected = CstDocument::parse(CONFIG).unwrap().to_value();
+    expected.apply_patch(&patch).unwrap();
+    assert_eq!(document.to_value(), expected);
+
+    for s in vec![
+        ("[1, 2]", "/0", "[0, 1, 2]"),
+        ("[1,2]", "/0", "[0,1,2]"),
+        ("[1]", "/0", "[0, 1]"),
+        ("[1, 2]", "/2", "[1, 2, 0]"),
+        ("[\n  1\n]", "/0", "[\n  0,\n  1\n]"),
+        ("{\"a\": [1]}", "", "0"),
+    ] {
+        println!("Checking {} {}", s.0, s.1);
+        let mut document: CstDocument = s.0.parse().unwrap();
+        let add = PatchOperation::Add {
+            path: s.1.to_owned(),
+            value: JSONValue::JSONNumber(0.0),
+        };
+        document.apply_patch(&[add]).unwrap();
+        assert_eq!(document.to_string(), s.2);
+    }
+}
+
+#[test]
+fn test_apply_patch_errors() {
+    let original: CstDocument = CONFIG.parse().unwrap();
+    for s in vec![
+        (
+            r#"{"op": "test", "path": "/name", "value": "web"}"#,
+            "Test failed at /name",
+        ),
+        (
+            r#"{"op": "replace", "path": "/missing", "value": 1}"#,
+            "Nothing to replace at /missing",
+        ),
+        (
+            r#"{"op": "add", "path": "/tags/3", "value": 1}"#,
+            "Index out of bounds in /tags/3",
+        ),
+        (
+            r#"{"op": "add", "path": "/tags/x", "value": 1}"#,
+            "Invalid index in /tags/x",
+        ),
+        (
+            r#"{"op": "move", "from": "/db", "path": "/db/inner"}"#,
+            "Unable to move /db into its own child",
+        ),
+        (
+            r#"{"op": "copy", "from": "/none", "path": "/x"}"#,
+            "Nothing to copy at /none",
+        ),
+        (
+            r#"{"op": "remove", "path": "/none"}"#,
+            "Nothing to remove at /none",
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        let mut patch = patch::parse_patch(&format!("[{}]", s.0).parse().unwrap()).unwrap();
+        // the failing operation comes after one that succeeds
+        patch.insert(
+            0,
+            PatchOperation::Remove {
+                path: "/ratio".to_owned(),
+            },
+        );
+        let mut document = original.clone();
+        let err = document.apply_patch(&patch).unwrap_err();
+        assert_eq!(err.reason, s.1);
+        assert_eq!(document, original);
+    }
+}
@@ -0,0 +1,317 @@
+use super::*;
+use patch::PatchOperation;
+use pointer::escape_token;
+use serializer::{Escaping, SerializerConfig};
+
+#[cfg(test)]
+mod tests;
+
+/// Computes an RFC 6902 patch turning `from` into `to`.
+/// Object members are compared by key (in sorted key order, so output is deterministic),
+/// arrays are compared by index: changed elements are diffed recursively,
+/// extra elements are removed from the end and missing ones appended.
+pub fn diff(from: &JSONValue, to: &JSONValue) -> Vec<PatchOperation> {
+    return diff_with(from, to, &DiffOptions::default());
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiffOptions {
+    /// JSON Pointer, relative to an array item, of the value identifying it,
+    /// e.g. `/id`. When set, arrays are compared by identity instead of by index:
+    /// items missing from `to` are removed, reordered ones moved and new ones
+    /// added, then matching items are diffed. Items without the identity value
+    /// are identified by their whole value.
+    pub array_identity: Option<String>,
+}
+
+/// `diff` with options, e.g. move detection in arrays.
+pub fn diff_with(from: &JSONValue, to: &JSONValue, options: &DiffOptions) -> Vec<PatchOperation> {
+    let mut result = vec![];
+    diff_into(from, to, "", options, &mut result);
+    return result;
+}
+
+fn diff_into(
+    from: &JSONValue,
+    to: &JSONValue,
+    path: &str,
+    options: &DiffOptions,
+    result: &mut Vec<PatchOperation>,
+) {
+    if from == to {
+        return;
+    }
+    match (from, to) {
+        (JSONValue::JSONObject(from_map), JSONValue::JSONObject(to_map)) => {
+            let mut keys: Vec<&String> = from_map.keys().chain(to_map.keys()).collect();
+            keys.sort();
+            keys.dedup();
+            for key in keys {
+                let child = format!("{}/{}", path, escape_token(key));
+                match (from_map.get(key), to_map.get(key)) {
+                    (Some(a), Some(b)) => diff_into(a, b, &child, options, result),
+                    (Some(_), None) => result.push(PatchOperation::Remove { path: child }),
+                    (None, Some(b)) => result.push(PatchOperation::Add {
+                        path: child,
+                        value: (**b).clone(),
+                    }),
+                    (None, None) => (),
+                }
+            }
+        }
+        (JSONValue::JSONArray(from_items), JSONValue::JSONArray(to_items)) => {
+            if let Some(ref identity) = options.array_identity {
+                diff_by_identity(from_items, to_items, identity, path, options, result);
+                return;
+            }
+            let common = from_items.len().min(to_items.len());
+            for i in 0..common {
+                diff_into(
+                    &from_items[i],
+                    &to_items[i],
+                    &format!("{}/{}", path, i),
+                    options,
+                    result,
+                );
+            }
+            // remove from the end, so indices of the remaining elements stay valid
+            for i in (common..from_items.len()).rev() {
+                result.push(PatchOperation::Remove {
+                    path: format!("{}/{}", path, i),
+                });
+            }
+            for (i, item) in to_items.iter().enumerate().skip(common) {
+                result.push(PatchOperation::Add {
+                    path: format!("{}/{}", path, i),
+                    value: (**item).clone(),
+                });
+            }
+        }
+        _ => result.push(PatchOperation::Replace {
+            path: path.to_owned(),
+            value: to.clone(),
+        }),
+    }
+}
+
+// Matches items by identity, then removes, moves and adds items to get the
+// order of `to`. Matched items forming the longest common subsequence of both
+// orders stay in place and only the others move, so the number of moves is minimal.
+fn diff_by_identity(
+    from_items: &[Box<JSONValue>],
+    to_items: &[Box<JSONValue>],
+    identity: &str,
+    path: &str,
+    options: &DiffOptions,
+    result: &mut Vec<PatchOperation>,
+) {
+    let key = |item: &JSONValue| match item.pointer(identity) {
+        Some(id) => (true, id.to_string_with(&SORTED)),
+        None => (false, item.to_string_with(&SORTED)),
+    };
+    // source index in `from_items` of every item of `to_items`, equal keys matched in order
+    let mut unmatched: HashMap<(bool, String), Vec<usize>> = HashMap::new();
+    for (i, item) in from_items.iter().enumerate().rev() {
+        unmatched.entry(key(item)).or_default().push(i);
+    }
+    let sources: Vec<Option<usize>> = to_items
+        .iter()
+        .map(|item| {
+            unmatched
+                .get_mut(&key(item))
+                .and_then(|indices| indices.pop())
+        })
+        .collect();
+    let mut kept = vec![false; from_items.len()];
+    for source in sources.iter().flatten() {
+        kept[*source] = true;
+    }
+    for i in (0..from_items.len()).rev() {
+        if !kept[i] {
+            result.push(PatchOperation::Remove {
+                path: format!("{}/{}", path, i),
+            });
+        }
+    }
+
+    // the array after removals, as indices into `from_items`
+    let mut current: Vec<usize> = (0..from_items.len()).filter(|&i| kept[i]).collect();
+    let target: Vec<usize> = sources.iter().flatten().cloned().collect();
+    let mut rank = vec![0; from_items.len()];
+    for (r, &source) in target.iter().enumerate() {
+        rank[source] = r;
+    }
+    let anchored = longest_increasing(&current.iter().map(|&i| rank[i]).collect::<Vec<_>>());
+    let anchored: Vec<bool> = {
+        let mut flags = vec![false; from_items.len()];
+        for &position in &anchored {
+            flags[current[position]] = true;
+        }
+        flags
+    };
+    // every other item goes right after its predecessor in `to`
+    for (r, &source) in target.iter().enumerate() {
+        if anchored[source] {
+            continue;
+        }
+        let position = current.iter().position(|&i| i == source).unwrap();
+        current.remove(position);
+        let destination = match r {
+            0 => 0,
+            _ => current.iter().position(|&i| i == target[r - 1]).unwrap() + 1,
+        };
+        current.insert(destination, source);
+        if destination != position {
+            result.push(PatchOperation::Move {
+                from: format!("{}/{}", path, position),
+                path: format!("{}/{}", path, destination),
+            });
+        }
+    }
+
+    for (i, source) in sources.iter().enumerate() {
+        if source.is_none() {
+            result.push(PatchOperation::Add {
+                path: format!("{}/{}", path, i),
+                value: (*to_items[i]).clone(),
+            });
+        }
+    }
+    for (i, source) in sources.iter().enumerate() {
+        if let Some(source) = *source {
+            let child = format!("{}/{}", path, i);
+            diff_into(&from_items[source], &to_items[i], &child, options, result);
+        }
+    }
+}
+
+const SORTED: SerializerConfig = SerializerConfig {
+    indent: None,
+    sort_keys: true,
+    escaping: Escaping::Utf8,
+    escape_html: false,
+};
+
+// Positions of a longest strictly increasing subsequence
+fn longest_increasing(values: &[usize]) -> Vec<usize> {
+    // tails[k]: position of the smallest tail of an increasing run of length k + 1
+    let mut tails: Vec<usize> = vec![];
+    let mut previous = vec![None; values.len()];
+    for (i, &value) in values.iter().enumerate() {
+        let k = tails.partition_point(|&t| values[t] < value);
+        if k > 0 {
+            previous[i] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(i);
+        } else {
+            tails[k] = i;
+        }
+    }
+    let mut result = vec![];
+    let mut next = tails.last().cloned();
+    while let Some(i) = next {
+        result.push(i);
+        next = previous[i];
+    }
+    result.reverse();
+    return result;
+}
+
+/// Concurrent changes of the same place in `merge3`. `None` means the value
+/// is missing on that side, e.g. removed by one side and changed by the other.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    /// JSON Pointer of the conflicting value
+    pub path: String,
+    pub base: Option<JSONValue>,
+    pub ours: Option<JSONValue>,
+    pub theirs: Option<JSONValue>,
+}
+
+/// Three-way merge of two documents derived from `base`. A change made on one
+/// side only is taken; objects changed on both sides are merged member by member,
+/// arrays item by item when no side changed their length. Anything else changed
+/// differently on both sides is a conflict. Conflicts are sorted by path.
+pub fn merge3(
+    base: &JSONValue,
+    ours: &JSONValue,
+    theirs: &JSONValue,
+) -> Result<JSONValue, Vec<Conflict>> {
+    let mut conflicts = vec![];
+    let merged = merge_into(Some(base), Some(ours), Some(theirs), "", &mut conflicts);
+    if !conflicts.is_empty() {
+        conflicts.sort_by(|a, b| a.path.cmp(&b.path));
+        return Err(conflicts);
+    }
+    return Ok(merged.expect("the root is present on both sides"));
+}
+
+// The merged value, `None` when it's removed. On conflict records it and keeps ours.
+fn merge_into(
+    base: Option<&JSONValue>,
+    ours: Option<&JSONValue>,
+    theirs: Option<&JSONValue>,
+    path: &str,
+    conflicts: &mut Vec<Conflict>,
+) -> Option<JSONValue> {
+    if ours == theirs || theirs == base {
+        return ours.cloned();
+    }
+    if ours == base {
+        return theirs.cloned();
+    }
+    match (base, ours, theirs) {
+        (
+            Some(JSONValue::JSONObject(base_map)),
+            Some(JSONValue::JSONObject(our_map)),
+            Some(JSONValue::JSONObject(their_map)),
+        ) => {
+            let mut merged = JSONMap::new();
+            let keys = our_map
+                .keys()
+                .chain(their_map.keys().filter(|key| !our_map.contains_key(*key)));
+            for key in keys {
+                let child = format!("{}/{}", path, escape_token(key));
+                let value = merge_into(
+                    base_map.get(key).map(|v| &**v),
+                    our_map.get(key).map(|v| &**v),
+                    their_map.get(key).map(|v| &**v),
+                    &child,
+                    conflicts,
+                );
+                if let Some(value) = value {
+                    merged.insert(key.clone(), Box::new(value));
+                }
+            }
+            return Some(JSONValue::JSONObject(merged));
+        }
+        (
+            Some(JSONValue::JSONArray(base_items)),
+            Some(JSONValue::JSONArray(our_items)),
+            Some(JSONValue::JSONArray(their_items)),
+        ) if base_items.len() == our_items.len() && base_items.len() == their_items.len() => {
+            let mut merged = Vec::with_capacity(base_items.len());
+            for i in 0..base_items.len() {
+                let value = merge_into(
+                    Some(&base_items[i]),
+                    Some(&our_items[i]),
+                    Some(&their_items[i]),
+                    &format!("{}/{}", path, i),
+                    conflicts,
+                );
+                merged.push(Box::new(value.expect("items are present on both sides")));
+            }
+            return Some(JSONValue::JSONArray(merged));
+        }
+        _ => {
+            conflicts.push(Conflict {
+                path: path.to_owned(),
+                base: base.cloned(),
+                ours: ours.cloned(),
+                theirs: theirs.cloned(),
+            });
+            return ours.cloned();
+        }
+    }
+}
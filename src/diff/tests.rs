@@ -0,0 +1,205 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use patch::patch_to_json;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_diff() {
+    for s in vec![
+        ("1", "1", "[]"),
+        ("1", "2", r#"[{"op": "replace", "path": "", "value": 2}]"#),
+        (
+            r#"{"a": 1, "b": 2}"#,
+            r#"{"a": 1, "c": 3}"#,
+            r#"[{"op": "remove", "path": "/b"}, {"op": "add", "path": "/c", "value": 3}]"#,
+        ),
+        (
+            r#"{"a": {"b/c": [1, 2]}}"#,
+            r#"{"a": {"b/c": [1, 3]}}"#,
+            r#"[{"op": "replace", "path": "/a/b~1c/1", "value": 3}]"#,
+        ),
+        (
+            r#"[1, 2, 3, 4]"#,
+            r#"[1, 5]"#,
+            r#"[
+                {"op": "replace", "path": "/1", "value": 5},
+                {"op": "remove", "path": "/3"},
+                {"op": "remove", "path": "/2"}
+            ]"#,
+        ),
+        (
+            r#"[1]"#,
+            r#"[1, {"a": 2}, 3]"#,
+            r#"[{"op": "add", "path": "/1", "value": {"a": 2}}, {"op": "add", "path": "/2", "value": 3}]"#,
+        ),
+        (
+            r#"{"a": [1]}"#,
+            r#"{"a": {"0": 1}}"#,
+            r#"[{"op": "replace", "path": "/a", "value": {"0": 1}}]"#,
+        ),
+    ] {
+        println!("Checking {} -> {}", s.0, s.1);
+        assert_eq!(patch_to_json(&diff(&parse(s.0), &parse(s.1))), parse(s.2));
+    }
+}
+
+#[test]
+fn test_diff_applies() {
+    for s in vec![
+        (
+            r#"{"a": [1, 2, {"b": null}], "c": "x", "d": {"e": true}}"#,
+            r#"{"a": [1, {"b": false}], "c": "y", "f": []}"#,
+        ),
+        (r#"[[1, 2], [3]]"#, r#"[[1], [3, 4], [5]]"#),
+    ] {
+        let (from, to) = (parse(s.0), parse(s.1));
+        let mut patched = from.clone();
+        patched.apply_patch(&diff(&from, &to)).unwrap();
+        assert_eq!(patched, to);
+    }
+}
+
+#[test]
+fn test_merge3() {
+    let base = r#"{"name": "svc", "port": 80, "tags": ["a", "b"], "db": {"host": "x", "pool": 5}, "old": 1}"#;
+    for s in vec![
+        // disjoint changes, additions and removals
+        (
+            r#"{"name": "api", "port": 80, "tags": ["a", "b"], "db": {"host": "y", "pool": 5}, "old": 1}"#,
+            r#"{"name": "svc", "port": 81, "tags": ["a", "c"], "db": {"host": "x", "pool": 6}, "new": 2}"#,
+            r#"{"name": "api", "port": 81, "tags": ["a", "c"], "db": {"host": "y", "pool": 6}, "new": 2}"#,
+        ),
+        // the same change on both sides
+        (
+            r#"{"name": "api", "port": 80, "tags": ["a"], "db": null}"#,
+            r#"{"name": "api", "port": 80, "tags": ["a"], "db": null, "old": 1}"#,
+            r#"{"name": "api", "port": 80, "tags": ["a"], "db": null}"#,
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(
+            merge3(&parse(base), &parse(s.0), &parse(s.1)).unwrap(),
+            parse(s.2)
+        );
+        assert_eq!(
+            merge3(&parse(base), &parse(s.1), &parse(s.0)).unwrap(),
+            parse(s.2)
+        );
+    }
+}
+
+#[test]
+fn test_merge3_conflicts() {
+    let base = parse(r#"{"port": 80, "tags": ["a"], "db": {"host": "x"}, "x/y": 1}"#);
+    let ours = parse(r#"{"port": 81, "tags": ["a", "b"], "db": {"host": "x"}, "x/y": 2}"#);
+    let theirs = parse(r#"{"port": 82, "tags": ["c"], "x/y": 1}"#);
+    let conflicts = merge3(&base, &ours, &theirs).unwrap_err();
+    assert_eq!(
+        conflicts,
+        vec![
+            Conflict {
+                path: "/port".to_owned(),
+                base: Some(parse("80")),
+                ours: Some(parse("81")),
+                theirs: Some(parse("82")),
+            },
+            Conflict {
+                path: "/tags".to_owned(),
+                base: Some(parse(r#"["a"]"#)),
+                ours: Some(parse(r#"["a", "b"]"#)),
+                theirs: Some(parse(r#"["c"]"#)),
+            },
+        ]
+    );
+
+    // removed on one side, changed on the other
+    let ours = parse(r#"{"port": 80, "tags": ["a"], "db": {"host": "y"}, "x/y": 1}"#);
+    let conflicts = merge3(&base, &ours, &theirs).unwrap_err();
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].path, "/db");
+    assert_eq!(conflicts[0].theirs, None);
+
+    let conflicts = merge3(&parse("[1]"), &parse("2"), &parse("\"x\"")).unwrap_err();
+    assert_eq!(conflicts[0].path, "");
+}
+
+#[test]
+fn test_diff_with_identity() {
+    let options = DiffOptions {
+        array_identity: Some("/id".to_owned()),
+    };
+    for s in vec![
+        (
+            r#"[{"id": 1}, {"id": 2}, {"id": 3}]"#,
+            r#"[{"id": 2}, {"id": 3}, {"id": 1}]"#,
+            r#"[{"op": "move", "from": "/0", "path": "/2"}]"#,
+        ),
+        (
+            r#"[{"id": 1}, {"id": 2}, {"id": 3}, {"id": 4}]"#,
+            r#"[{"id": 4}, {"id": 1, "x": true}, {"id": 5}, {"id": 3}]"#,
+            r#"[
+                {"op": "remove", "path": "/1"},
+                {"op": "move", "from": "/2", "path": "/0"},
+                {"op": "add", "path": "/2", "value": {"id": 5}},
+                {"op": "add", "path": "/1/x", "value": true}
+            ]"#,
+        ),
+        (
+            r#"{"list": [{"id": "a", "v": 1}, {"id": "b", "v": 2}]}"#,
+            r#"{"list": [{"id": "b", "v": 2}, {"id": "a", "v": 3}]}"#,
+            r#"[
+                {"op": "move", "from": "/list/0", "path": "/list/1"},
+                {"op": "replace", "path": "/list/1/v", "value": 3}
+            ]"#,
+        ),
+        // items without the identity value match by value
+        (
+            r#"[1, 2, 3]"#,
+            r#"[3, 1, 2]"#,
+            r#"[{"op": "move", "from": "/2", "path": "/0"}]"#,
+        ),
+    ] {
+        println!("Checking {} -> {}", s.0, s.1);
+        let (from, to) = (parse(s.0), parse(s.1));
+        let patch = diff_with(&from, &to, &options);
+        assert_eq!(patch_to_json(&patch), parse(s.2));
+        let mut patched = from.clone();
+        patched.apply_patch(&patch).unwrap();
+        assert_eq!(patched, to);
+    }
+}
+
+#[test]
+fn test_diff_with_identity_applies() {
+    let options = DiffOptions {
+        array_identity: Some("/id".to_owned()),
+    };
+    for s in vec![
+        ("[1, 2, 3, 4, 5, 6]", "[6, 5, 4, 3, 2, 1]"),
+        ("[1, 1, 2, 1]", "[2, 1, 3, 1]"),
+        ("[5, 1, 4, 2, 3]", "[1, 2, 3, 4, 5]"),
+        ("[]", "[1, 2]"),
+        ("[1, 2]", "[]"),
+        (
+            r#"[{"id": 1, "a": [{"id": 7}, {"id": 8}]}, {"id": 2}]"#,
+            r#"[{"id": 2}, {"id": 1, "a": [{"id": 8}, {"id": 7}, {"id": 9}]}]"#,
+        ),
+    ] {
+        println!("Checking {} -> {}", s.0, s.1);
+        let (from, to) = (parse(s.0), parse(s.1));
+        let patch = diff_with(&from, &to, &options);
+        let mut patched = from.clone();
+        patched.apply_patch(&patch).unwrap();
+        assert_eq!(patched, to);
+    }
+    let patch = diff_with(
+        &parse("[5, 1, 2, 3, 4]"),
+        &parse("[1, 2, 3, 4, 5]"),
+        &options,
+    );
+    assert_eq!(patch.len(), 1);
+}
@@ -0,0 +1,245 @@
+use super::*;
+use std::io::Read;
+use std::str;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Utf32Le,
+    Utf32Be,
+}
+
+impl Encoding {
+    fn unit_size(self) -> usize {
+        match self {
+            Encoding::Utf8 => return 1,
+            Encoding::Utf16Le | Encoding::Utf16Be => return 2,
+            Encoding::Utf32Le | Encoding::Utf32Be => return 4,
+        }
+    }
+}
+
+/// Detects the encoding of JSON bytes and the length of their byte order mark.
+/// Without a BOM, the pattern of zero bytes among the first four tells UTF-16
+/// and UTF-32 apart from UTF-8, since JSON text starts with two ASCII characters
+/// (RFC 4627, section 3). Everything else is UTF-8.
+pub fn detect_encoding(bytes: &[u8]) -> (Encoding, usize) {
+    match bytes {
+        [0xEF, 0xBB, 0xBF, ..] => return (Encoding::Utf8, 3),
+        [0x00, 0x00, 0xFE, 0xFF, ..] => return (Encoding::Utf32Be, 4),
+        [0xFF, 0xFE, 0x00, 0x00, ..] => return (Encoding::Utf32Le, 4),
+        [0xFE, 0xFF, ..] => return (Encoding::Utf16Be, 2),
+        [0xFF, 0xFE, ..] => return (Encoding::Utf16Le, 2),
+        [0x00, 0x00, 0x00, _, ..] => return (Encoding::Utf32Be, 0),
+        [_, 0x00, 0x00, 0x00, ..] => return (Encoding::Utf32Le, 0),
+        [0x00, _, ..] => return (Encoding::Utf16Be, 0),
+        [_, 0x00, ..] => return (Encoding::Utf16Le, 0),
+        _ => return (Encoding::Utf8, 0),
+    }
+}
+
+/// Decodes JSON bytes in any of the Unicode encodings into a string, dropping the BOM.
+/// Error positions are byte offsets into `bytes`.
+pub fn decode(bytes: &[u8]) -> Result<String, JSONParseError> {
+    return decode_with(bytes, false);
+}
+
+/// Like `decode`, but replaces invalid sequences with U+FFFD instead of failing.
+pub fn decode_lossy(bytes: &[u8]) -> String {
+    return decode_with(bytes, true).expect("lossy decoding doesn't fail");
+}
+
+fn decode_with(bytes: &[u8], lossy: bool) -> Result<String, JSONParseError> {
+    let (encoding, bom) = detect_encoding(bytes);
+    let mut body = &bytes[bom..];
+    let unit = encoding.unit_size();
+    let mut truncated = false;
+    if !body.len().is_multiple_of(unit) {
+        if !lossy {
+            return Err(make_err(
+                format!("Input length isn't a multiple of {} bytes", unit),
+                bytes.len(),
+            ));
+        }
+        body = &body[..body.len() - body.len() % unit];
+        truncated = true;
+    }
+    let mut result = match encoding {
+        Encoding::Utf8 => {
+            if lossy {
+                String::from_utf8_lossy(body).into_owned()
+            } else {
+                str::from_utf8(body)
+                    .map(|s| s.to_owned())
+                    .map_err(|e| make_err("Invalid UTF-8".to_owned(), bom + e.valid_up_to()))?
+            }
+        }
+        Encoding::Utf16Le | Encoding::Utf16Be => {
+            let units = body.chunks(2).map(|pair| match encoding {
+                Encoding::Utf16Le => u16::from_le_bytes([pair[0], pair[1]]),
+                _ => u16::from_be_bytes([pair[0], pair[1]]),
+            });
+            let mut result = String::with_capacity(body.len() / 2);
+            for (i, ch) in char::decode_utf16(units).enumerate() {
+                match ch {
+                    Ok(ch) => result.push(ch),
+                    Err(_) if lossy => result.push(char::REPLACEMENT_CHARACTER),
+                    Err(_) => {
+                        return Err(make_err(
+                            "Unpaired UTF-16 surrogate".to_owned(),
+                            bom + 2 * i,
+                        ))
+                    }
+                }
+            }
+            result
+        }
+        Encoding::Utf32Le | Encoding::Utf32Be => {
+            let mut result = String::with_capacity(body.len() / 4);
+            for (i, quad) in body.chunks(4).enumerate() {
+                let code = match encoding {
+                    Encoding::Utf32Le => u32::from_le_bytes([quad[0], quad[1], quad[2], quad[3]]),
+                    _ => u32::from_be_bytes([quad[0], quad[1], quad[2], quad[3]]),
+                };
+                match char::from_u32(code) {
+                    Some(ch) => result.push(ch),
+                    None if lossy => result.push(char::REPLACEMENT_CHARACTER),
+                    None => {
+                        return Err(make_err(
+                            "Invalid UTF-32 code point".to_owned(),
+                            bom + 4 * i,
+                        ))
+                    }
+                }
+            }
+            result
+        }
+    };
+    if truncated {
+        result.push(char::REPLACEMENT_CHARACTER);
+    }
+    return Ok(result);
+}
+
+const BASE64URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes bytes as unpadded base64url (RFC 4648, section 5), safe in URLs and
+/// the form JWTs use.
+pub fn base64url_encode(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let buffer = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |buffer, (i, &b)| buffer | (b as u32) << (16 - 8 * i));
+        for i in 0..chunk.len() + 1 {
+            result.push(BASE64URL[(buffer >> (18 - 6 * i) & 63) as usize] as char);
+        }
+    }
+    return result;
+}
+
+/// Decodes base64url (RFC 4648, section 5), with or without padding.
+/// `None` for characters outside the alphabet and impossible lengths.
+pub fn base64url_decode(s: &str) -> Option<Vec<u8>> {
+    let s = s.trim_end_matches('=');
+    if s.len() % 4 == 1 {
+        return None;
+    }
+    let mut result = Vec::with_capacity(s.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for c in s.bytes() {
+        let value = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'-' => 62,
+            b'_' => 63,
+            _ => return None,
+        };
+        buffer = buffer << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    return Some(result);
+}
+
+impl JSONValue {
+    /// Parses bytes in UTF-8, UTF-16 or UTF-32, detected as in `detect_encoding`.
+    /// Parse error positions are byte offsets into the decoded UTF-8 text.
+    pub fn from_slice(bytes: &[u8]) -> Result<JSONValue, JSONParseError> {
+        return JSONValue::from_slice_with(bytes, &ParserOptions::default());
+    }
+
+    pub fn from_slice_with(
+        bytes: &[u8],
+        options: &ParserOptions,
+    ) -> Result<JSONValue, JSONParseError> {
+        return JSONValue::parse_with(&decode(bytes)?, options);
+    }
+
+    /// Parses bytes like `from_slice`, replacing invalid sequences with U+FFFD
+    /// instead of rejecting the document, to recover data from corrupted files.
+    /// Replacements outside of strings still make the document invalid.
+    pub fn from_slice_lossy(bytes: &[u8]) -> Result<JSONValue, JSONParseError> {
+        return JSONValue::parse_with(&decode_lossy(bytes), &ParserOptions::default());
+    }
+
+    /// Reads the whole input and parses it like `from_slice`.
+    pub fn from_reader<R: Read>(reader: R) -> Result<JSONValue, JSONParseError> {
+        return JSONValue::from_reader_with(reader, &ParserOptions::default());
+    }
+
+    /// Like `from_reader`; with `max_input_bytes` set, reading stops after the limit.
+    pub fn from_reader_with<R: Read>(
+        reader: R,
+        options: &ParserOptions,
+    ) -> Result<JSONValue, JSONParseError> {
+        let mut bytes = vec![];
+        let result = match options.max_input_bytes {
+            Some(limit) => reader.take(limit as u64 + 1).read_to_end(&mut bytes),
+            None => {
+                let mut reader = reader;
+                reader.read_to_end(&mut bytes)
+            }
+        };
+        if let Err(e) = result {
+            return Err(JSONParseError {
+                reason: format!("Unable to read input: {}", e),
+                position: Some(bytes.len()),
+                kind: JSONParseErrorKind::Io,
+            });
+        }
+        if let Some(limit) = options.max_input_bytes {
+            if bytes.len() > limit {
+                return Err(JSONParseError {
+                    reason: format!(
+                        "Maximum input size in bytes of {} exceeded at position 0",
+                        limit
+                    ),
+                    position: Some(0),
+                    kind: JSONParseErrorKind::LimitExceeded,
+                });
+            }
+        }
+        return JSONValue::from_slice_with(&bytes, options);
+    }
+}
+
+fn make_err(s: String, position: usize) -> JSONParseError {
+    JSONParseError {
+        reason: s,
+        position: Some(position),
+        kind: JSONParseErrorKind::Syntax,
+    }
+}
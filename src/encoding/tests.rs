@@ -0,0 +1,158 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn utf16(s: &str, little_endian: bool, bom: bool) -> Vec<u8> {
+    let mut units: Vec<u16> = s.encode_utf16().collect();
+    if bom {
+        units.insert(0, 0xFEFF);
+    }
+    return units
+        .iter()
+        .flat_map(|&u| {
+            if little_endian {
+                u.to_le_bytes()
+            } else {
+                u.to_be_bytes()
+            }
+        })
+        .collect();
+}
+
+fn utf32(s: &str, little_endian: bool, bom: bool) -> Vec<u8> {
+    let mut chars: Vec<u32> = s.chars().map(|ch| ch as u32).collect();
+    if bom {
+        chars.insert(0, 0xFEFF);
+    }
+    return chars
+        .iter()
+        .flat_map(|&c| {
+            if little_endian {
+                c.to_le_bytes()
+            } else {
+                c.to_be_bytes()
+            }
+        })
+        .collect();
+}
+
+#[test]
+fn test_detect_and_decode() {
+    let text = r#"{"name": "Zoë 😀"}"#;
+    let mut with_bom = vec![0xEF, 0xBB, 0xBF];
+    with_bom.extend_from_slice(text.as_bytes());
+    for s in vec![
+        (text.as_bytes().to_vec(), Encoding::Utf8, 0),
+        (with_bom, Encoding::Utf8, 3),
+        (utf16(text, true, false), Encoding::Utf16Le, 0),
+        (utf16(text, false, false), Encoding::Utf16Be, 0),
+        (utf16(text, true, true), Encoding::Utf16Le, 2),
+        (utf16(text, false, true), Encoding::Utf16Be, 2),
+        (utf32(text, true, false), Encoding::Utf32Le, 0),
+        (utf32(text, false, false), Encoding::Utf32Be, 0),
+        (utf32(text, true, true), Encoding::Utf32Le, 4),
+        (utf32(text, false, true), Encoding::Utf32Be, 4),
+    ] {
+        println!("Checking {:?} {}", s.1, s.2);
+        assert_eq!(detect_encoding(&s.0), (s.1, s.2));
+        assert_eq!(decode(&s.0).unwrap(), text);
+        assert_eq!(JSONValue::from_slice(&s.0).unwrap(), text.parse().unwrap());
+    }
+    assert_eq!(detect_encoding(b"1"), (Encoding::Utf8, 0));
+    assert_eq!(
+        JSONValue::from_slice(&utf16("7", false, false)).unwrap(),
+        JSONValue::JSONNumber(7.0)
+    );
+}
+
+#[test]
+fn test_decode_errors() {
+    for s in vec![
+        (vec![b'[', b'"', 0xFF, b'"', b']'], "Invalid UTF-8", 2),
+        (vec![0xEF, 0xBB, 0xBF, b'"', 0xC3], "Invalid UTF-8", 4),
+        (
+            vec![b'[', 0, b'1'],
+            "Input length isn't a multiple of 2 bytes",
+            3,
+        ),
+        (
+            vec![b'"', 0, 0x00, 0xD8, b'"', 0],
+            "Unpaired UTF-16 surrogate",
+            2,
+        ),
+        (
+            vec![b'1', 0, 0, 0, 0, 0, 0x11, 0],
+            "Invalid UTF-32 code point",
+            4,
+        ),
+    ] {
+        println!("Checking {}", s.1);
+        let err = JSONValue::from_slice(&s.0).unwrap_err();
+        assert_eq!(err.reason, s.1);
+        assert_eq!(err.position, Some(s.2));
+        assert_eq!(err.kind, JSONParseErrorKind::Syntax);
+    }
+}
+
+#[test]
+fn test_from_slice_lossy() {
+    for s in vec![
+        (vec![b'[', b'"', 0xFF, b'"', b']'], vec!["\u{FFFD}"]),
+        (
+            vec![
+                b'[', b'"', b'a', 0xC3, b'"', b',', b'"', 0xE2, 0x82, b'b', b'"', b']',
+            ],
+            vec!["a\u{FFFD}", "\u{FFFD}b"],
+        ),
+        (b"[\"ok\"]".to_vec(), vec!["ok"]),
+        (
+            vec![b'[', 0, b'"', 0, 0x00, 0xD8, b'"', 0, b']', 0],
+            vec!["\u{FFFD}"],
+        ),
+    ] {
+        println!("Checking {:?}", s.1);
+        let expected = JSONValue::JSONArray(
+            s.1.iter()
+                .map(|v| Box::new(JSONValue::JSONString(v.to_string())))
+                .collect(),
+        );
+        assert_eq!(JSONValue::from_slice_lossy(&s.0).unwrap(), expected);
+    }
+    assert_eq!(decode_lossy(&[b'[', 0, b']']), "[\u{FFFD}");
+    assert!(JSONValue::from_slice_lossy(&[b'[', 0xFF, b']']).is_err());
+}
+
+#[test]
+fn test_from_reader() {
+    let bytes = utf16("[1, 2]", true, true);
+    assert_eq!(
+        JSONValue::from_reader(&bytes[..]).unwrap(),
+        "[1, 2]".parse().unwrap()
+    );
+    let options = ParserOptions {
+        max_input_bytes: Some(4),
+        ..ParserOptions::default()
+    };
+    let err = JSONValue::from_reader_with(&b"[1, 2]"[..], &options).unwrap_err();
+    assert_eq!(err.kind, JSONParseErrorKind::LimitExceeded);
+    assert!(JSONValue::from_reader_with(&b"[1]"[..], &options).is_ok());
+}
+
+#[test]
+fn test_base64url() {
+    for (bytes, encoded) in vec![
+        (&b""[..], ""),
+        (b"f", "Zg"),
+        (b"fo", "Zm8"),
+        (b"foo", "Zm9v"),
+        (b"foob", "Zm9vYg"),
+        (b"\xfb\xff\xbf", "-_-_"),
+    ] {
+        println!("Checking {}", encoded);
+        assert_eq!(base64url_encode(bytes), encoded);
+        assert_eq!(base64url_decode(encoded), Some(bytes.to_vec()));
+    }
+    assert_eq!(base64url_decode("Zm8="), Some(b"fo".to_vec()));
+    assert_eq!(base64url_decode("Zm9vY"), None);
+    assert_eq!(base64url_decode("Zm+v"), None);
+}
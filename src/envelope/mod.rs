@@ -0,0 +1,203 @@
+use super::*;
+use serializer::{write_number, write_string};
+use std::fmt::Write;
+
+#[cfg(test)]
+mod tests;
+
+const PAYLOAD: &str = "payload";
+const SHA256: &str = "sha256";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JSONEnvelopeError {
+    pub reason: String,
+}
+
+impl JSONValue {
+    /// Canonical text of the value: compact, object keys sorted by their UTF-16
+    /// code units as in RFC 8785, raw fragments expanded and numbers written as
+    /// the serializer does, with `-0` as `0`. Values that are equal after parsing
+    /// (`1.0` and `1`, `-0` and `0`, reordered keys) have the same canonical text.
+    pub fn to_canonical_string(&self) -> String {
+        let mut result = String::new();
+        write_canonical(&mut result, self).unwrap();
+        return result;
+    }
+
+    /// SHA-256 of the canonical text, as lowercase hex
+    pub fn canonical_hash(&self) -> String {
+        return to_hex(&sha256(self.to_canonical_string().as_bytes()));
+    }
+
+    /// Wraps the value into `{"payload": ..., "sha256": "..."}` for storage it
+    /// has to survive unchanged. Opened with `open_envelope`.
+    pub fn to_envelope(&self) -> JSONValue {
+        let mut map = JSONMap::new();
+        map.insert(PAYLOAD.to_owned(), Box::new(self.clone()));
+        map.insert(
+            SHA256.to_owned(),
+            Box::new(JSONValue::JSONString(self.canonical_hash())),
+        );
+        return JSONValue::JSONObject(map);
+    }
+}
+
+/// Checks the hash of an envelope made by `to_envelope` and returns its payload.
+/// Only `payload` and `sha256` may be in the envelope. This detects corruption
+/// and tampering by whoever can't also recompute the hash; it's not a signature.
+pub fn open_envelope(envelope: JSONValue) -> Result<JSONValue, JSONEnvelopeError> {
+    let mut map = match envelope {
+        JSONValue::JSONObject(map) => map,
+        JSONValue::JSONRaw(_) => return open_envelope(envelope.expand_raw().into_owned()),
+        _ => return Err(make_err("Expected an envelope object".to_owned())),
+    };
+    let payload = match map.remove(PAYLOAD) {
+        Some(payload) => *payload,
+        None => return Err(make_err(format!("Missing {}", PAYLOAD))),
+    };
+    let expected = match map.remove(SHA256).map(|hash| *hash) {
+        Some(JSONValue::JSONString(hash)) => hash.to_lowercase(),
+        Some(_) => return Err(make_err(format!("Expected {} to be a string", SHA256))),
+        None => return Err(make_err(format!("Missing {}", SHA256))),
+    };
+    if let Some(key) = map.keys().next() {
+        return Err(make_err(format!("Unexpected member {}", key)));
+    }
+    let actual = payload.canonical_hash();
+    if actual != expected {
+        return Err(make_err(format!(
+            "Checksum mismatch: envelope has {}, payload hashes to {}",
+            expected, actual
+        )));
+    }
+    return Ok(payload);
+}
+
+/// Parses an envelope and opens it like `open_envelope`.
+pub fn parse_envelope(input: &str) -> Result<JSONValue, JSONEnvelopeError> {
+    match input.parse::<JSONValue>() {
+        Ok(envelope) => return open_envelope(envelope),
+        Err(err) => return Err(make_err(format!("Invalid JSON: {}", err.reason))),
+    }
+}
+
+fn write_canonical<W: Write>(w: &mut W, value: &JSONValue) -> fmt::Result {
+    match *value {
+        JSONValue::JSONNull() => return w.write_str("null"),
+        JSONValue::JSONBool(b) => return w.write_str(if b { "true" } else { "false" }),
+        // `-0.0 == 0.0`, so `-0` is written as `0`
+        JSONValue::JSONNumber(n) => return write_number(w, if n == 0.0 { 0.0 } else { n }),
+        JSONValue::JSONString(ref s) => return write_string(w, s),
+        JSONValue::JSONRaw(_) => return write_canonical(w, &value.expand_raw()),
+        JSONValue::JSONArray(ref items) => {
+            w.write_char('[')?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_canonical(w, item)?;
+            }
+            return w.write_char(']');
+        }
+        JSONValue::JSONObject(ref map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_by(|a, b| a.encode_utf16().cmp(b.encode_utf16()));
+            w.write_char('{')?;
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_string(w, key)?;
+                w.write_char(':')?;
+                write_canonical(w, &map[key])?;
+            }
+            return w.write_char('}');
+        }
+    }
+}
+
+// SHA-256 round constants
+const K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+/// SHA-256 (FIPS 180-4) of `bytes`
+pub fn sha256(bytes: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    let mut message = bytes.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((bytes.len() as u64) * 8).to_be_bytes());
+    for block in message.chunks(64) {
+        let mut w = [0u32; 64];
+        for i in 0..16 {
+            w[i] = u32::from_be_bytes([
+                block[4 * i],
+                block[4 * i + 1],
+                block[4 * i + 2],
+                block[4 * i + 3],
+            ]);
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let choice = (e & f) ^ (!e & g);
+            let t1 = h
+                .wrapping_add(s1)
+                .wrapping_add(choice)
+                .wrapping_add(K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let majority = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(majority);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (s, v) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *s = s.wrapping_add(v);
+        }
+    }
+    let mut result = [0u8; 32];
+    for (i, s) in state.iter().enumerate() {
+        result[4 * i..4 * i + 4].copy_from_slice(&s.to_be_bytes());
+    }
+    return result;
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    let mut result = String::with_capacity(bytes.len() * 2);
+    for b in bytes {
+        write!(result, "{:02x}", b).unwrap();
+    }
+    return result;
+}
+
+fn make_err(s: String) -> JSONEnvelopeError {
+    JSONEnvelopeError { reason: s }
+}
@@ -0,0 +1,117 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use serializer::SerializerConfig;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_sha256() {
+    for (bytes, hash) in vec![
+        (
+            vec![],
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855",
+        ),
+        (
+            b"abc".to_vec(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        ),
+        (
+            vec![b'a'; 1000],
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3",
+        ),
+    ] {
+        println!("Checking {}", hash);
+        assert_eq!(to_hex(&sha256(&bytes)), hash);
+    }
+}
+
+#[test]
+fn test_canonical() {
+    for (a, b) in vec![
+        (
+            r#"{"b": null, "a": [1, 2.5, "x"]}"#,
+            r#"{"a":[1,2.5,"x"],"b":null}"#,
+        ),
+        (
+            r#"{"z": {"y": 1.0, "x": true}}"#,
+            r#"{"z":{"x":true,"y":1}}"#,
+        ),
+        (r#"[ "é" ]"#, r#"["é"]"#),
+        ("[-0, 0, -0.0]", "[0,0,0]"),
+        // UTF-16 order puts the surrogates of U+1F600 before U+E000
+        (
+            "{\"\u{e000}\": 1, \"\u{1f600}\": 2}",
+            "{\"\u{1f600}\":2,\"\u{e000}\":1}",
+        ),
+    ] {
+        println!("Checking {}", a);
+        assert_eq!(parse(a).to_canonical_string(), b);
+    }
+    assert_eq!(
+        parse(r#"{"b": null, "a": [1, 2.5, "x"]}"#).canonical_hash(),
+        "ebeb83367f9695ff5ceeebf14323134c890fc30c03843f006fdfd18fcb15957f"
+    );
+    let raw = JSONValue::raw(r#"{"b": 1, "a": 2}"#).unwrap();
+    assert_eq!(raw.to_canonical_string(), r#"{"a":2,"b":1}"#);
+}
+
+#[test]
+fn test_envelope() {
+    let value = parse(r#"{"id": 7, "tags": ["a", "b"]}"#);
+    let envelope = value.to_envelope();
+    assert_eq!(
+        envelope.pointer("/sha256"),
+        Some(&JSONValue::JSONString(value.canonical_hash()))
+    );
+    assert_eq!(open_envelope(envelope.clone()).unwrap(), value);
+
+    let text = envelope.to_string_with(&SerializerConfig::pretty(2));
+    assert_eq!(parse_envelope(&text).unwrap(), value);
+    let reordered = format!(
+        r#"{{"sha256": "{}", "payload": {{"tags": ["a", "b"], "id": 7.0}}}}"#,
+        value.canonical_hash().to_uppercase()
+    );
+    assert_eq!(parse_envelope(&reordered).unwrap(), value);
+
+    let hash = value.canonical_hash();
+    for (input, reason) in vec![
+        (
+            format!(
+                r#"{{"payload": {{"id": 8, "tags": ["a", "b"]}}, "sha256": "{}"}}"#,
+                hash
+            ),
+            format!(
+                "Checksum mismatch: envelope has {}, payload hashes to {}",
+                hash,
+                parse(r#"{"id": 8, "tags": ["a", "b"]}"#).canonical_hash()
+            ),
+        ),
+        (
+            r#"{"sha256": "00"}"#.to_owned(),
+            "Missing payload".to_owned(),
+        ),
+        (r#"{"payload": 1}"#.to_owned(), "Missing sha256".to_owned()),
+        (
+            r#"{"payload": 1, "sha256": 1}"#.to_owned(),
+            "Expected sha256 to be a string".to_owned(),
+        ),
+        (
+            format!(
+                r#"{{"payload": {{"id": 7, "tags": ["a", "b"]}}, "sha256": "{}", "x": 1}}"#,
+                hash
+            ),
+            "Unexpected member x".to_owned(),
+        ),
+        ("[]".to_owned(), "Expected an envelope object".to_owned()),
+        (
+            "{".to_owned(),
+            "Invalid JSON: String ended unexpectedly".to_owned(),
+        ),
+    ] {
+        println!("Checking {}", input);
+        assert_eq!(parse_envelope(&input).unwrap_err().reason, reason);
+    }
+}
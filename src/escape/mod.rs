@@ -0,0 +1,230 @@
+use super::*;
+use std::borrow::Cow;
+use std::char;
+use std::fmt::Write;
+
+#[cfg(test)]
+mod tests;
+
+const ONES: u64 = 0x0101_0101_0101_0101;
+const HIGH_BITS: u64 = 0x8080_8080_8080_8080;
+const WORD: usize = 8;
+
+/// Escapes a string for use between the quotes of a JSON string: `"`, `\`
+/// and control characters. Borrows the input when nothing needs escaping.
+///
+/// Both helpers skip over plain text eight bytes at a time, testing a whole
+/// `u64` for quotes, backslashes and control characters at once.
+pub fn escape_json_string(s: &str) -> Cow<'_, str> {
+    let first = match find_escape(s.as_bytes(), 0) {
+        Some(first) => first,
+        None => return Cow::Borrowed(s),
+    };
+    let mut result = String::with_capacity(s.len() + s.len() / 8 + 2);
+    result.push_str(&s[..first]);
+    write_escaped(&mut result, &s[first..]).unwrap();
+    return Cow::Owned(result);
+}
+
+/// Writes `s` escaped like `escape_json_string`, unquoted
+pub fn write_escaped<W: Write>(w: &mut W, s: &str) -> fmt::Result {
+    let bytes = s.as_bytes();
+    let mut written = 0;
+    while let Some(i) = find_escape(bytes, written) {
+        w.write_str(&s[written..i])?;
+        match bytes[i] {
+            b'"' => w.write_str("\\\"")?,
+            b'\\' => w.write_str("\\\\")?,
+            b'\n' => w.write_str("\\n")?,
+            b'\r' => w.write_str("\\r")?,
+            b'\t' => w.write_str("\\t")?,
+            0x08 => w.write_str("\\b")?,
+            0x0c => w.write_str("\\f")?,
+            byte => write!(w, "\\u{:04x}", byte)?,
+        }
+        written = i + 1;
+    }
+    return w.write_str(&s[written..]);
+}
+
+/// Like `write_escaped`, also escaping every non-ASCII character as `\uXXXX`,
+/// as a surrogate pair outside the Basic Multilingual Plane
+pub fn write_escaped_ascii<W: Write>(w: &mut W, s: &str) -> fmt::Result {
+    return write_escaped_chars(w, s, |ch| return !ch.is_ascii());
+}
+
+/// Like `write_escaped`, also escaping the characters `escape` picks as
+/// `\uXXXX`, or surrogate pairs
+pub fn write_escaped_chars<W, F>(w: &mut W, s: &str, escape: F) -> fmt::Result
+where
+    W: Write,
+    F: Fn(char) -> bool,
+{
+    return write_runs(w, s, escape, write_escaped);
+}
+
+/// Writes JSON text with the characters `escape` picks escaped, which is only
+/// valid for characters that can't occur outside strings, i.e. not ASCII
+/// whitespace or punctuation JSON itself uses
+pub fn write_json_escaped_chars<W, F>(w: &mut W, json: &str, escape: F) -> fmt::Result
+where
+    W: Write,
+    F: Fn(char) -> bool,
+{
+    return write_runs(w, json, escape, |w, run| return w.write_str(run));
+}
+
+// Escapes the characters `escape` picks, handing the runs between them to `write_run`
+fn write_runs<W, E, R>(w: &mut W, s: &str, escape: E, write_run: R) -> fmt::Result
+where
+    W: Write,
+    E: Fn(char) -> bool,
+    R: Fn(&mut W, &str) -> fmt::Result,
+{
+    let mut written = 0;
+    for (i, ch) in s.char_indices().filter(|&(_, ch)| escape(ch)) {
+        write_run(w, &s[written..i])?;
+        for unit in ch.encode_utf16(&mut [0; 2]) {
+            write!(w, "\\u{:04x}", unit)?;
+        }
+        written = i + ch.len_utf8();
+    }
+    return write_run(w, &s[written..]);
+}
+
+/// Reverses `escape_json_string`: decodes the escapes of the text between the
+/// quotes of a JSON string, surrogate pairs included. Borrows the input when it
+/// has none. Fails on invalid escapes, lone surrogates and raw control
+/// characters, with the byte offset in `s`.
+pub fn unescape_json_string(s: &str) -> Result<Cow<'_, str>, JSONParseError> {
+    let bytes = s.as_bytes();
+    let mut next = match find_unescape(bytes, 0) {
+        Some(first) => first,
+        None => return Ok(Cow::Borrowed(s)),
+    };
+    let mut result = String::with_capacity(s.len());
+    let mut copied = 0;
+    loop {
+        result.push_str(&s[copied..next]);
+        if bytes[next] != b'\\' {
+            let reason = format!("Unescaped control character at position {}", next);
+            return Err(error(reason, next));
+        }
+        let (ch, length) = unescape_at(s, next)?;
+        result.push(ch);
+        copied = next + length;
+        next = match find_unescape(bytes, copied) {
+            Some(next) => next,
+            None => break,
+        };
+    }
+    result.push_str(&s[copied..]);
+    return Ok(Cow::Owned(result));
+}
+
+// The escape starting at the backslash at `i`, and its length in bytes
+fn unescape_at(s: &str, i: usize) -> Result<(char, usize), JSONParseError> {
+    let bytes = s.as_bytes();
+    let ch = match bytes.get(i + 1) {
+        Some(b'"') => '"',
+        Some(b'\\') => '\\',
+        Some(b'/') => '/',
+        Some(b'b') => '\x08',
+        Some(b'f') => '\x0c',
+        Some(b'n') => '\n',
+        Some(b'r') => '\r',
+        Some(b't') => '\t',
+        Some(b'u') => {
+            let high = hex4(s, i)?;
+            if !(0xD800..0xE000).contains(&high) {
+                return Ok((char::from_u32(high).unwrap(), 6));
+            }
+            let low = match s.get(i + 6..i + 8) {
+                Some("\\u") if high < 0xDC00 => hex4(s, i + 6)?,
+                _ => return Err(invalid_escape(s, i, 6)),
+            };
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(invalid_escape(s, i, 12));
+            }
+            let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+            return Ok((char::from_u32(code).unwrap(), 12));
+        }
+        _ => return Err(invalid_escape(s, i, 2)),
+    };
+    return Ok((ch, 2));
+}
+
+// Code unit of the `\uXXXX` escape at `i`
+fn hex4(s: &str, i: usize) -> Result<u32, JSONParseError> {
+    let digits = s
+        .get(i + 2..i + 6)
+        .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()));
+    match digits {
+        Some(digits) => return Ok(u32::from_str_radix(digits, 16).unwrap()),
+        None => return Err(invalid_escape(s, i, 6)),
+    }
+}
+
+fn invalid_escape(s: &str, i: usize, length: usize) -> JSONParseError {
+    let mut end = (i + length).min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    return error(
+        format!("Invalid escape sequence {} at position {}", &s[i..end], i),
+        i,
+    );
+}
+
+fn error(reason: String, position: usize) -> JSONParseError {
+    return JSONParseError {
+        reason,
+        position: Some(position),
+        kind: JSONParseErrorKind::Syntax,
+    };
+}
+
+// First quote, backslash or control character at or after `from`
+fn find_escape(bytes: &[u8], from: usize) -> Option<usize> {
+    let word_test = |word| has_less(word, 0x20) | has_byte(word, b'"') | has_byte(word, b'\\');
+    return find(bytes, from, word_test, |b| {
+        b < 0x20 || b == b'"' || b == b'\\'
+    });
+}
+
+// First backslash or control character at or after `from`
+fn find_unescape(bytes: &[u8], from: usize) -> Option<usize> {
+    let word_test = |word| has_less(word, 0x20) | has_byte(word, b'\\');
+    return find(bytes, from, word_test, |b| b < 0x20 || b == b'\\');
+}
+
+// Skips whole words `word_test` finds nothing in, then tests bytes one by one
+fn find<W, B>(bytes: &[u8], from: usize, word_test: W, byte_test: B) -> Option<usize>
+where
+    W: Fn(u64) -> u64,
+    B: Fn(u8) -> bool,
+{
+    let mut i = from;
+    while i + WORD <= bytes.len() {
+        let mut word = [0; WORD];
+        word.copy_from_slice(&bytes[i..i + WORD]);
+        if word_test(u64::from_le_bytes(word)) != 0 {
+            break;
+        }
+        i += WORD;
+    }
+    return bytes[i..]
+        .iter()
+        .position(|&b| byte_test(b))
+        .map(|offset| i + offset);
+}
+
+// High bit set in some byte of the result iff a byte of `word` is below `n` (n <= 128).
+// May flag bytes above a true match, so the match is located byte by byte.
+fn has_less(word: u64, n: u8) -> u64 {
+    return word.wrapping_sub(ONES * n as u64) & !word & HIGH_BITS;
+}
+
+fn has_byte(word: u64, byte: u8) -> u64 {
+    return has_less(word ^ (ONES * byte as u64), 1);
+}
@@ -0,0 +1,103 @@
+#![allow(clippy::useless_vec)]
+use super::*;
+
+#[test]
+fn test_escape() {
+    for s in vec![
+        ("", ""),
+        (
+            "plain text longer than a word",
+            "plain text longer than a word",
+        ),
+        ("quote \" here", "quote \\\" here"),
+        ("back\\slash", "back\\\\slash"),
+        ("\n\r\t\x08\x0c\x00\x1f", "\\n\\r\\t\\b\\f\\u0000\\u001f"),
+        ("ünïcödé ✓ 😀 \x7f", "ünïcödé ✓ 😀 \x7f"),
+        ("12345678\"", "12345678\\\""),
+        ("1234567\n", "1234567\\n"),
+    ] {
+        println!("Checking {:?}", s.0);
+        let escaped = escape_json_string(s.0);
+        assert_eq!(escaped, s.1);
+        assert_eq!(matches!(escaped, Cow::Borrowed(_)), s.0 == s.1);
+        assert_eq!(unescape_json_string(&escaped).unwrap(), s.0);
+    }
+}
+
+#[test]
+fn test_unescape() {
+    for s in vec![
+        (
+            "no escapes at all, just text",
+            "no escapes at all, just text",
+        ),
+        ("\\/\\\"\\\\", "/\"\\"),
+        ("\\u0041\\u00e9\\u2713", "Aé✓"),
+        ("\\ud83d\\ude00 smile", "😀 smile"),
+        ("\\uD83D\\uDE00", "😀"),
+        ("mixed ü \\n end", "mixed ü \n end"),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(unescape_json_string(s.0).unwrap(), s.1);
+    }
+    assert!(matches!(
+        unescape_json_string("ü plain"),
+        Ok(Cow::Borrowed(_))
+    ));
+    for s in vec![
+        ("abc\\x", "Invalid escape sequence \\x at position 3", 3),
+        ("\\", "Invalid escape sequence \\ at position 0", 0),
+        ("\\u12", "Invalid escape sequence \\u12 at position 0", 0),
+        (
+            "\\u12g4",
+            "Invalid escape sequence \\u12g4 at position 0",
+            0,
+        ),
+        (
+            "\\ud83d",
+            "Invalid escape sequence \\ud83d at position 0",
+            0,
+        ),
+        (
+            "\\ude00\\ud83d",
+            "Invalid escape sequence \\ude00 at position 0",
+            0,
+        ),
+        (
+            "\\ud83d\\u0041",
+            "Invalid escape sequence \\ud83d\\u0041 at position 0",
+            0,
+        ),
+        ("\\ü", "Invalid escape sequence \\ at position 0", 0),
+        (
+            "long prefix\tx",
+            "Unescaped control character at position 11",
+            11,
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        let err = unescape_json_string(s.0).unwrap_err();
+        assert_eq!(err.reason, s.1);
+        assert_eq!(err.position, Some(s.2));
+    }
+}
+
+#[test]
+fn test_agrees_with_parser() {
+    // every position relative to word boundaries
+    for padding in 0..16 {
+        let prefix = "x".repeat(padding);
+        for special in vec!["\"", "\\", "\n", "\x01", "\x1f", "é", "😀", " "] {
+            let s = format!("{}{}{}", prefix, special, prefix);
+            let escaped = escape_json_string(&s);
+            let mut written = String::new();
+            serializer::write_string(&mut written, &s).unwrap();
+            assert_eq!(written, format!("\"{}\"", escaped));
+            assert_eq!(
+                parser::parse_json(&written).unwrap(),
+                JSONValue::JSONString(s.clone())
+            );
+            assert_eq!(unescape_json_string(&escaped).unwrap(), s);
+        }
+    }
+}
@@ -0,0 +1,240 @@
+use super::*;
+use serializer::SerializerConfig;
+use std::io::{self, BufRead, Read, Write};
+use std::str;
+
+#[cfg(test)]
+mod tests;
+
+const CONTENT_LENGTH: &str = "Content-Length";
+const ERROR_ENDED_UNEXPECTEDLY: &str = "Message ended unexpectedly";
+// Longer header lines are rejected rather than buffered
+const MAX_HEADER_LINE: usize = 8 * 1024;
+
+/// Reads JSON messages framed with `Content-Length` headers, as in the Language
+/// Server Protocol: header lines ending in `\r\n`, an empty line, then exactly
+/// that many bytes of JSON. Other headers, like `Content-Type`, are skipped.
+/// Error positions are byte offsets from the start of the stream. Bodies are
+/// buffered as their bytes arrive, not up front, so a huge `Content-Length`
+/// only costs memory if the peer actually sends that much.
+pub struct FramedReader<R> {
+    reader: R,
+    offset: usize,
+    options: ParserOptions,
+}
+
+impl<R: BufRead> FramedReader<R> {
+    pub fn new(reader: R) -> FramedReader<R> {
+        return FramedReader::with_options(reader, &ParserOptions::default());
+    }
+
+    /// `max_input_bytes` of the options limits every message, checked against
+    /// its `Content-Length` before the body is read.
+    pub fn with_options(reader: R, options: &ParserOptions) -> FramedReader<R> {
+        return FramedReader {
+            reader,
+            offset: 0,
+            options: *options,
+        };
+    }
+
+    // The body of the next message and its offset, `None` at the end of the stream
+    fn read_frame(&mut self) -> Result<Option<(usize, Vec<u8>)>, JSONParseError> {
+        let frame_start = self.offset;
+        let mut length = None;
+        let mut line = String::new();
+        loop {
+            line.clear();
+            let start = self.offset;
+            let read = (&mut self.reader)
+                .take(MAX_HEADER_LINE as u64 + 1)
+                .read_line(&mut line)
+                .map_err(|e| io_err("Unable to read header", e, start))?;
+            self.offset += read;
+            if read > MAX_HEADER_LINE {
+                return Err(JSONParseError {
+                    reason: format!(
+                        "Header line longer than {} bytes at position {}",
+                        MAX_HEADER_LINE, start
+                    ),
+                    position: Some(start),
+                    kind: JSONParseErrorKind::LimitExceeded,
+                });
+            }
+            if read == 0 {
+                if start == frame_start {
+                    return Ok(None);
+                }
+                return Err(make_err(ERROR_ENDED_UNEXPECTEDLY.to_owned(), start));
+            }
+            if !line.ends_with('\n') {
+                return Err(make_err(ERROR_ENDED_UNEXPECTEDLY.to_owned(), self.offset));
+            }
+            let header = line.trim_end_matches('\n').trim_end_matches('\r');
+            if header.is_empty() {
+                break;
+            }
+            let (name, value) = header
+                .split_once(':')
+                .ok_or_else(|| make_err(format!("Invalid header {}", header), start))?;
+            if name.trim().eq_ignore_ascii_case(CONTENT_LENGTH) {
+                let value = value.trim();
+                // `parse` alone would take a leading `+`
+                let digits = !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit());
+                let parsed = value.parse().ok().filter(|_| digits);
+                length = Some(parsed.ok_or_else(|| {
+                    make_err(format!("Invalid {} {}", CONTENT_LENGTH, value), start)
+                })?);
+            }
+        }
+        let length: usize =
+            length.ok_or_else(|| make_err(format!("Missing {}", CONTENT_LENGTH), self.offset))?;
+        let start = self.offset;
+        if let Some(limit) = self.options.max_input_bytes {
+            if length > limit {
+                return Err(JSONParseError {
+                    reason: format!(
+                        "Maximum input size in bytes of {} exceeded at position {}",
+                        limit, start
+                    ),
+                    position: Some(start),
+                    kind: JSONParseErrorKind::LimitExceeded,
+                });
+            }
+        }
+        let mut body = vec![];
+        while body.len() < length {
+            let position = start + body.len();
+            let chunk = self
+                .reader
+                .fill_buf()
+                .map_err(|e| io_err("Unable to read message", e, position))?;
+            if chunk.is_empty() {
+                return Err(make_err(ERROR_ENDED_UNEXPECTEDLY.to_owned(), position));
+            }
+            let read = chunk.len().min(length - body.len());
+            body.extend_from_slice(&chunk[..read]);
+            self.reader.consume(read);
+        }
+        self.offset += length;
+        return Ok(Some((start, body)));
+    }
+
+    fn parse(&self, start: usize, body: &[u8]) -> Result<JSONValue, JSONParseError> {
+        let text = str::from_utf8(body).map_err(|e| {
+            make_err(
+                "Message is not valid UTF-8".to_owned(),
+                start + e.valid_up_to(),
+            )
+        })?;
+        return JSONValue::parse_with(text, &self.options).map_err(|mut err| {
+            err.position = err.position.map(|p| p + start);
+            err
+        });
+    }
+}
+
+impl<R: BufRead> Iterator for FramedReader<R> {
+    type Item = Result<JSONValue, JSONParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.read_frame() {
+            Ok(Some((start, body))) => return Some(self.parse(start, &body)),
+            Ok(None) => return None,
+            Err(err) => return Some(Err(err)),
+        }
+    }
+}
+
+/// Writes JSON messages with `Content-Length` headers, the counterpart of `FramedReader`.
+pub struct FramedWriter<W> {
+    writer: W,
+}
+
+impl<W: Write> FramedWriter<W> {
+    pub fn new(writer: W) -> FramedWriter<W> {
+        return FramedWriter { writer };
+    }
+
+    /// Writes one message as compact JSON and flushes, so the peer sees it at once.
+    pub fn write_message(&mut self, message: &JSONValue) -> io::Result<()> {
+        let body = message.to_string_with(&SerializerConfig::compact());
+        write!(self.writer, "{}: {}\r\n\r\n", CONTENT_LENGTH, body.len())?;
+        self.writer.write_all(body.as_bytes())?;
+        return self.writer.flush();
+    }
+
+    pub fn into_inner(self) -> W {
+        return self.writer;
+    }
+}
+
+/// Runs a request loop over a framed connection, e.g. a language server on
+/// stdin and stdout: every message goes to `handler`, and whatever it returns
+/// is written back. A message whose body isn't valid JSON gets the JSON-RPC
+/// parse error response (code -32700) and the loop goes on; broken framing,
+/// messages over `max_input_bytes` and I/O errors end it. Returns at the end of the input.
+pub fn serve<R, W, F>(
+    reader: R,
+    writer: W,
+    options: &ParserOptions,
+    mut handler: F,
+) -> Result<(), JSONParseError>
+where
+    R: BufRead,
+    W: Write,
+    F: FnMut(JSONValue) -> Option<JSONValue>,
+{
+    let mut reader = FramedReader::with_options(reader, options);
+    let mut writer = FramedWriter::new(writer);
+    while let Some((start, body)) = reader.read_frame()? {
+        let response = match reader.parse(start, &body) {
+            Ok(message) => handler(message),
+            Err(ref err) if err.kind == JSONParseErrorKind::Io => return Err(err.clone()),
+            Err(err) => Some(parse_error_response(&err)),
+        };
+        if let Some(response) = response {
+            writer
+                .write_message(&response)
+                .map_err(|e| io_err("Unable to write message", e, reader.offset))?;
+        }
+    }
+    return Ok(());
+}
+
+// JSON-RPC 2.0 error response for a message that couldn't be parsed
+fn parse_error_response(err: &JSONParseError) -> JSONValue {
+    let mut error = JSONMap::new();
+    error.insert("code".to_owned(), Box::new(JSONValue::JSONNumber(-32700.0)));
+    error.insert(
+        "message".to_owned(),
+        Box::new(JSONValue::JSONString(format!(
+            "Parse error: {}",
+            err.reason
+        ))),
+    );
+    let mut response = JSONMap::new();
+    response.insert(
+        "jsonrpc".to_owned(),
+        Box::new(JSONValue::JSONString("2.0".to_owned())),
+    );
+    response.insert("id".to_owned(), Box::new(JSONValue::JSONNull()));
+    response.insert("error".to_owned(), Box::new(JSONValue::JSONObject(error)));
+    return JSONValue::JSONObject(response);
+}
+
+fn io_err(what: &str, e: io::Error, position: usize) -> JSONParseError {
+    return JSONParseError {
+        reason: format!("{}: {}", what, e),
+        position: Some(position),
+        kind: JSONParseErrorKind::Io,
+    };
+}
+
+fn make_err(s: String, position: usize) -> JSONParseError {
+    JSONParseError {
+        reason: s,
+        position: Some(position),
+        kind: JSONParseErrorKind::Syntax,
+    }
+}
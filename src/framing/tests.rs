@@ -0,0 +1,140 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+fn frame(body: &str) -> String {
+    return format!("Content-Length: {}\r\n\r\n{}", body.len(), body);
+}
+
+#[test]
+fn test_read_messages() {
+    let input = format!(
+        "{}content-length: 7\r\nContent-Type: application/vscode-jsonrpc; charset=utf-8\r\n\r\n[1, 2]\n{}",
+        frame(r#"{"id": 1}"#),
+        frame("\"é\"")
+    );
+    let messages: Vec<JSONValue> = FramedReader::new(input.as_bytes())
+        .map(|message| message.unwrap())
+        .collect();
+    assert_eq!(
+        messages,
+        vec![parse(r#"{"id": 1}"#), parse("[1, 2]"), parse("\"é\"")]
+    );
+    assert_eq!(FramedReader::new(&b""[..]).count(), 0);
+}
+
+#[test]
+fn test_read_errors() {
+    let options = ParserOptions {
+        max_input_bytes: Some(4),
+        ..ParserOptions::default()
+    };
+    for s in vec![
+        (
+            "Content-Length: 3\r\n\r\n[1",
+            "Message ended unexpectedly",
+            23,
+        ),
+        ("Content-Length: 3\r\n", "Message ended unexpectedly", 19),
+        ("Content-Length: 3", "Message ended unexpectedly", 17),
+        ("Content-Type: x\r\n\r\n1", "Missing Content-Length", 19),
+        ("Content-Length: -1\r\n\r\n", "Invalid Content-Length -1", 0),
+        ("Length 3\r\n\r\n", "Invalid header Length 3", 0),
+        (
+            "Content-Length: 5\r\n\r\n[1,2]",
+            "Maximum input size in bytes of 4 exceeded at position 43",
+            21,
+        ),
+        (
+            "Content-Length: 2\r\n\r\n[}",
+            "Unexpected charachter } at position 1",
+            22,
+        ),
+    ] {
+        println!("Checking {:?}", s.0);
+        let input = format!("{}{}", frame("1"), s.0);
+        let mut reader = FramedReader::with_options(input.as_bytes(), &options);
+        assert_eq!(reader.next().unwrap().unwrap(), parse("1"));
+        let err = reader.next().unwrap().unwrap_err();
+        let offset = frame("1").len();
+        assert_eq!(err.reason, s.1);
+        assert_eq!(err.position, Some(s.2 + offset));
+    }
+
+    let input = b"Content-Length: 99999999999\r\n\r\n[1, 2]";
+    let err = FramedReader::new(&input[..]).next().unwrap().unwrap_err();
+    assert_eq!(err.reason, "Message ended unexpectedly");
+    assert_eq!(err.position, Some(37));
+
+    let input = format!("X-Padding: {}\r\n\r\n1", "x".repeat(10000));
+    let err = FramedReader::new(input.as_bytes())
+        .next()
+        .unwrap()
+        .unwrap_err();
+    assert_eq!(
+        err.reason,
+        "Header line longer than 8192 bytes at position 0"
+    );
+    assert_eq!(err.kind, JSONParseErrorKind::LimitExceeded);
+
+    let mut reader = FramedReader::new(&b"Content-Length: 3\r\n\r\n\"\xff\""[..]);
+    let err = reader.next().unwrap().unwrap_err();
+    assert_eq!(err.reason, "Message is not valid UTF-8");
+    assert_eq!(err.position, Some(22));
+}
+
+#[test]
+fn test_serve() {
+    let input = format!(
+        "{}{}{}",
+        frame(r#"{"jsonrpc": "2.0", "id": 1, "method": "ping"}"#),
+        frame(r#"{"jsonrpc": "2.0", "method": "exit"}"#),
+        frame("{"),
+    );
+    let mut output = vec![];
+    let mut methods = vec![];
+    serve(
+        input.as_bytes(),
+        &mut output,
+        &ParserOptions::default(),
+        |message| {
+            methods.push(message.pointer("/method").cloned());
+            let id = message.pointer("/id")?.clone();
+            let mut response = JSONMap::new();
+            response.insert("id".to_owned(), Box::new(id));
+            response.insert("result".to_owned(), Box::new(parse("\"pong\"")));
+            return Some(JSONValue::JSONObject(response));
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        methods,
+        vec![Some(parse("\"ping\"")), Some(parse("\"exit\""))]
+    );
+
+    let responses: Vec<JSONValue> = FramedReader::new(&output[..])
+        .map(|message| message.unwrap())
+        .collect();
+    assert_eq!(responses.len(), 2);
+    assert_eq!(responses[0], parse(r#"{"id": 1, "result": "pong"}"#));
+    assert_eq!(responses[1].pointer("/error/code"), Some(&parse("-32700")));
+    assert_eq!(responses[1].pointer("/id"), Some(&JSONValue::JSONNull()));
+
+    let mut writer = FramedWriter::new(vec![]);
+    writer.write_message(&parse(r#"{"a": "é"}"#)).unwrap();
+    assert_eq!(
+        String::from_utf8(writer.into_inner()).unwrap(),
+        "Content-Length: 10\r\n\r\n{\"a\":\"é\"}"
+    );
+
+    let broken = "Content-Length: x\r\n\r\n";
+    let err = serve(broken.as_bytes(), vec![], &ParserOptions::default(), |m| {
+        Some(m)
+    })
+    .unwrap_err();
+    assert_eq!(err.reason, "Invalid Content-Length x");
+}
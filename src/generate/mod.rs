@@ -0,0 +1,382 @@
+use super::*;
+use schema::Schema;
+
+#[cfg(test)]
+mod tests;
+
+// Generated values that fail validation (e.g. because of `not` or overlapping
+// `oneOf` branches) are thrown away and generated again, up to this many times
+const MAX_ATTEMPTS: usize = 100;
+// Below this depth arrays stay at their minimum size and optional properties
+// are skipped, so recursive `$ref`s terminate
+const MAX_DEPTH: usize = 8;
+const DEFAULT_MAX_ITEMS: usize = 3;
+const DEFAULT_MAX_LENGTH: usize = 8;
+const DEFAULT_NUMBER_RANGE: f64 = 1000.0;
+
+const FIRST_NAMES: [&str; 8] = [
+    "Alice", "Bob", "Carol", "Dave", "Eve", "Frank", "Grace", "Heidi",
+];
+const LAST_NAMES: [&str; 6] = ["Smith", "Jones", "Brown", "Taylor", "Wilson", "Davies"];
+const WORDS: [&str; 8] = [
+    "alpha", "bravo", "delta", "echo", "kilo", "lima", "oscar", "zulu",
+];
+
+#[derive(Debug, Clone)]
+pub struct JSONGenerateError {
+    pub reason: String,
+}
+
+/// Source of randomness for the generators. Implement it over any RNG crate,
+/// or use `SplitMix64` for seeded, reproducible output.
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+
+    /// Uniform in `[0, 1)`
+    fn next_f64(&mut self) -> f64 {
+        return (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+    }
+
+    fn next_bool(&mut self) -> bool {
+        return self.next_u64() >> 63 == 1;
+    }
+
+    /// Uniform in `[low, high]`
+    fn range(&mut self, low: u64, high: u64) -> u64 {
+        if high <= low {
+            return low;
+        }
+        return low + self.next_u64() % (high - low + 1);
+    }
+
+    fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        return &items[self.range(0, items.len() as u64 - 1) as usize];
+    }
+}
+
+/// Small, fast, non-cryptographic generator.
+#[derive(Debug, Clone)]
+pub struct SplitMix64 {
+    state: u64,
+}
+
+impl SplitMix64 {
+    pub fn new(seed: u64) -> SplitMix64 {
+        return SplitMix64 { state: seed };
+    }
+}
+
+impl Rng for SplitMix64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        return z ^ (z >> 31);
+    }
+}
+
+/// Random document valid against the schema. Uses `const`, `enum`, `type`,
+/// numeric and length bounds, `multipleOf`, `items`, `properties`, `required`,
+/// `anyOf`/`oneOf` (one branch), `allOf` (first branch) and local `$ref`s;
+/// everything else is enforced by validating and retrying.
+pub fn generate_from_schema<R: Rng>(
+    schema: &Schema,
+    rng: &mut R,
+) -> Result<JSONValue, JSONGenerateError> {
+    let root = schema.as_json();
+    for _ in 0..MAX_ATTEMPTS {
+        let value = from_schema(root, root, rng, 0)?;
+        if schema.is_valid(&value) {
+            return Ok(value);
+        }
+    }
+    return Err(make_err(format!(
+        "No valid value generated in {} attempts",
+        MAX_ATTEMPTS
+    )));
+}
+
+fn from_schema<R: Rng>(
+    schema: &JSONValue,
+    root: &JSONValue,
+    rng: &mut R,
+    depth: usize,
+) -> Result<JSONValue, JSONGenerateError> {
+    let map = match *schema {
+        JSONValue::JSONObject(ref map) => map,
+        JSONValue::JSONBool(false) => {
+            return Err(make_err("Schema `false` allows no value".to_owned()))
+        }
+        _ => return Ok(JSONValue::JSONNull()),
+    };
+    let keyword = |name: &str| map.get(name).map(|v| &**v);
+
+    if let Some(JSONValue::JSONString(reference)) = keyword("$ref") {
+        let target = reference
+            .strip_prefix('#')
+            .and_then(|pointer| root.pointer(pointer))
+            .ok_or_else(|| make_err(format!("Unresolvable $ref {}", reference)))?;
+        return from_schema(target, root, rng, depth + 1);
+    }
+    if let Some(value) = keyword("const") {
+        return Ok(value.clone());
+    }
+    if let Some(JSONValue::JSONArray(options)) = keyword("enum") {
+        if !options.is_empty() {
+            return Ok((**rng.pick(options)).clone());
+        }
+    }
+    for combinator in ["anyOf", "oneOf"].iter() {
+        if let Some(JSONValue::JSONArray(branches)) = keyword(combinator) {
+            if !branches.is_empty() {
+                let branch = &**rng.pick(&branches[..]);
+                return from_schema(branch, root, rng, depth + 1);
+            }
+        }
+    }
+    if let Some(JSONValue::JSONArray(branches)) = keyword("allOf") {
+        if let Some(first) = branches.first() {
+            return from_schema(first, root, rng, depth + 1);
+        }
+    }
+
+    let number = |name: &str| match keyword(name) {
+        Some(&JSONValue::JSONNumber(n)) => Some(n),
+        _ => None,
+    };
+    let count = |name: &str| number(name).map(|n| n.max(0.0) as usize);
+    let types: Vec<&str> = match keyword("type") {
+        Some(JSONValue::JSONString(name)) => vec![name.as_str()],
+        Some(JSONValue::JSONArray(names)) => names
+            .iter()
+            .filter_map(|name| match **name {
+                JSONValue::JSONString(ref name) => Some(name.as_str()),
+                _ => None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let kind = match types.len() {
+        0 if keyword("properties").is_some() => "object",
+        0 if keyword("items").is_some() => "array",
+        0 if keyword("minLength").is_some() || keyword("maxLength").is_some() => "string",
+        0 if keyword("minimum").is_some() || keyword("maximum").is_some() => "number",
+        0 => "null",
+        _ => *rng.pick(&types),
+    };
+    match kind {
+        "null" => return Ok(JSONValue::JSONNull()),
+        "boolean" => return Ok(JSONValue::JSONBool(rng.next_bool())),
+        "integer" | "number" => {
+            let low = number("minimum")
+                .or_else(|| number("exclusiveMinimum").map(|n| n + 1.0))
+                .unwrap_or(0.0);
+            let high = number("maximum")
+                .or_else(|| number("exclusiveMaximum").map(|n| n - 1.0))
+                .unwrap_or(low + DEFAULT_NUMBER_RANGE);
+            let mut n = low + rng.next_f64() * (high - low).max(0.0);
+            if let Some(step) = number("multipleOf") {
+                n = (n / step).ceil() * step;
+            } else if kind == "integer" {
+                n = n.ceil();
+            }
+            return Ok(JSONValue::JSONNumber(n));
+        }
+        "string" => {
+            let min = count("minLength").unwrap_or(0);
+            let max = count("maxLength")
+                .unwrap_or(min.max(DEFAULT_MAX_LENGTH))
+                .max(min);
+            let length = rng.range(min as u64, max as u64);
+            let text = (0..length)
+                .map(|_| (b'a' + rng.range(0, 25) as u8) as char)
+                .collect();
+            return Ok(JSONValue::JSONString(text));
+        }
+        "array" => {
+            let min = count("minItems").unwrap_or(0);
+            let max = if depth >= MAX_DEPTH {
+                min
+            } else {
+                count("maxItems")
+                    .unwrap_or(min.max(DEFAULT_MAX_ITEMS))
+                    .max(min)
+            };
+            let item_schema = keyword("items")
+                .cloned()
+                .unwrap_or(JSONValue::JSONBool(true));
+            let mut items = vec![];
+            for _ in 0..rng.range(min as u64, max as u64) {
+                items.push(Box::new(from_schema(&item_schema, root, rng, depth + 1)?));
+            }
+            return Ok(JSONValue::JSONArray(items));
+        }
+        "object" => {
+            let required: Vec<&str> = match keyword("required") {
+                Some(JSONValue::JSONArray(names)) => names
+                    .iter()
+                    .filter_map(|name| match **name {
+                        JSONValue::JSONString(ref name) => Some(name.as_str()),
+                        _ => None,
+                    })
+                    .collect(),
+                _ => vec![],
+            };
+            let mut result = JSONMap::new();
+            if let Some(JSONValue::JSONObject(properties)) = keyword("properties") {
+                let mut names: Vec<&String> = properties.keys().collect();
+                names.sort();
+                for name in names {
+                    let optional = !required.contains(&name.as_str());
+                    if optional && (depth >= MAX_DEPTH || rng.next_bool()) {
+                        continue;
+                    }
+                    let value = from_schema(&properties[name], root, rng, depth + 1)?;
+                    result.insert(name.clone(), Box::new(value));
+                }
+            }
+            for name in required {
+                if !result.contains_key(name) {
+                    result.insert(name.to_owned(), Box::new(JSONValue::JSONNull()));
+                }
+            }
+            return Ok(JSONValue::JSONObject(result));
+        }
+        other => return Err(make_err(format!("Unknown type {}", other))),
+    }
+}
+
+/// Fills a template with random data. Strings may contain placeholders:
+/// `{{name}}`, `{{first_name}}`, `{{last_name}}`, `{{word}}`, `{{email}}`, `{{uuid}}`,
+/// `{{bool}}`, `{{int MIN MAX}}`, `{{float MIN MAX}}` and `{{one_of A B ...}}`.
+/// A string that is a single placeholder becomes a value of the placeholder's type,
+/// e.g. `"{{int 1 10}}"` a number; otherwise placeholders are replaced in the text.
+/// `{"$repeat": N, "$item": TEMPLATE}` (or `"$repeat": [MIN, MAX]`) generates an array.
+pub fn generate_from_template<R: Rng>(
+    template: &JSONValue,
+    rng: &mut R,
+) -> Result<JSONValue, JSONGenerateError> {
+    match *template {
+        JSONValue::JSONString(ref text) => return fill(text, rng),
+        JSONValue::JSONArray(ref items) => {
+            let mut result = vec![];
+            for item in items {
+                result.push(Box::new(generate_from_template(item, rng)?));
+            }
+            return Ok(JSONValue::JSONArray(result));
+        }
+        JSONValue::JSONObject(ref map) => {
+            if let (Some(repeat), Some(item)) = (map.get("$repeat"), map.get("$item")) {
+                let (min, max) = match **repeat {
+                    JSONValue::JSONNumber(n) => (n, n),
+                    JSONValue::JSONArray(ref bounds) if bounds.len() == 2 => {
+                        match (&*bounds[0], &*bounds[1]) {
+                            (&JSONValue::JSONNumber(min), &JSONValue::JSONNumber(max)) => {
+                                (min, max)
+                            }
+                            _ => return Err(make_err("Invalid $repeat".to_owned())),
+                        }
+                    }
+                    _ => return Err(make_err("Invalid $repeat".to_owned())),
+                };
+                let mut result = vec![];
+                for _ in 0..rng.range(min.max(0.0) as u64, max.max(0.0) as u64) {
+                    result.push(Box::new(generate_from_template(item, rng)?));
+                }
+                return Ok(JSONValue::JSONArray(result));
+            }
+            // sorted, so that a seed gives the same output whatever the map order
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort();
+            let mut result = JSONMap::new();
+            for key in keys {
+                result.insert(
+                    key.clone(),
+                    Box::new(generate_from_template(&map[key], rng)?),
+                );
+            }
+            return Ok(JSONValue::JSONObject(result));
+        }
+        _ => return Ok(template.clone()),
+    }
+}
+
+fn fill<R: Rng>(text: &str, rng: &mut R) -> Result<JSONValue, JSONGenerateError> {
+    let trimmed = text.trim();
+    if trimmed.starts_with("{{") && trimmed.ends_with("}}") && trimmed.matches("{{").count() == 1 {
+        return placeholder(&trimmed[2..trimmed.len() - 2], rng);
+    }
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let end = rest[start..]
+            .find("}}")
+            .ok_or_else(|| make_err(format!("Unterminated placeholder in {}", text)))?;
+        result.push_str(&rest[..start]);
+        match placeholder(&rest[start + 2..start + end], rng)? {
+            JSONValue::JSONString(s) => result.push_str(&s),
+            value => result.push_str(&value.to_string_with(&Default::default())),
+        }
+        rest = &rest[start + end + 2..];
+    }
+    result.push_str(rest);
+    return Ok(JSONValue::JSONString(result));
+}
+
+fn placeholder<R: Rng>(spec: &str, rng: &mut R) -> Result<JSONValue, JSONGenerateError> {
+    let mut parts = spec.split_whitespace();
+    let name = parts.next().unwrap_or("");
+    let args: Vec<&str> = parts.collect();
+    let bounds = || -> Result<(f64, f64), JSONGenerateError> {
+        match (
+            args.first().map(|a| a.parse()),
+            args.get(1).map(|a| a.parse()),
+        ) {
+            (Some(Ok(low)), Some(Ok(high))) if args.len() == 2 => return Ok((low, high)),
+            _ => return Err(make_err(format!("{} needs numeric MIN and MAX", name))),
+        }
+    };
+    let string = |s: String| Ok(JSONValue::JSONString(s));
+    match name {
+        "first_name" => return string(rng.pick(&FIRST_NAMES).to_string()),
+        "last_name" => return string(rng.pick(&LAST_NAMES).to_string()),
+        "name" => {
+            let first = rng.pick(&FIRST_NAMES);
+            return string(format!("{} {}", first, rng.pick(&LAST_NAMES)));
+        }
+        "word" => return string(rng.pick(&WORDS).to_string()),
+        "email" => {
+            let user = rng.pick(&FIRST_NAMES).to_lowercase();
+            return string(format!("{}{}@example.com", user, rng.range(1, 999)));
+        }
+        "uuid" => {
+            let (a, b) = (rng.next_u64(), rng.next_u64());
+            return string(format!(
+                "{:08x}-{:04x}-4{:03x}-{:x}{:03x}-{:012x}",
+                a >> 32,
+                (a >> 16) & 0xffff,
+                a & 0xfff,
+                8 + (b >> 62),
+                (b >> 48) & 0xfff,
+                b & 0xffff_ffff_ffff
+            ));
+        }
+        "bool" => return Ok(JSONValue::JSONBool(rng.next_bool())),
+        "int" => {
+            let (low, high) = bounds()?;
+            let n = low.ceil() + rng.range(0, (high.floor() - low.ceil()).max(0.0) as u64) as f64;
+            return Ok(JSONValue::JSONNumber(n));
+        }
+        "float" => {
+            let (low, high) = bounds()?;
+            return Ok(JSONValue::JSONNumber(low + rng.next_f64() * (high - low)));
+        }
+        "one_of" if !args.is_empty() => return string(rng.pick(&args).to_string()),
+        _ => return Err(make_err(format!("Unknown placeholder {{{{{}}}}}", spec))),
+    }
+}
+
+fn make_err(s: String) -> JSONGenerateError {
+    JSONGenerateError { reason: s }
+}
@@ -0,0 +1,122 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+#[test]
+fn test_split_mix() {
+    let mut a = SplitMix64::new(42);
+    let mut b = SplitMix64::new(42);
+    let first: Vec<u64> = (0..5).map(|_| a.next_u64()).collect();
+    let second: Vec<u64> = (0..5).map(|_| b.next_u64()).collect();
+    assert_eq!(first, second);
+    for _ in 0..1000 {
+        let f = a.next_f64();
+        assert!((0.0..1.0).contains(&f));
+        let n = a.range(3, 5);
+        assert!((3..=5).contains(&n));
+    }
+    assert_eq!(a.range(7, 7), 7);
+}
+
+#[test]
+fn test_generate_from_schema() {
+    for s in vec![
+        r#"{"type": "integer", "minimum": 5, "maximum": 10}"#,
+        r#"{"type": "number", "exclusiveMinimum": 0, "multipleOf": 0.5}"#,
+        r#"{"type": "number", "minimum": 0, "maximum": 100, "multipleOf": 0.01}"#,
+        r#"{"type": "string", "minLength": 2, "maxLength": 4}"#,
+        r#"{"enum": ["a", "b", 3]}"#,
+        r#"{"const": {"x": 1}}"#,
+        r#"{"type": ["string", "null"]}"#,
+        r#"{"type": "array", "items": {"type": "boolean"}, "minItems": 1, "maxItems": 5, "uniqueItems": true}"#,
+        r##"{
+            "type": "object",
+            "properties": {
+                "id": {"type": "integer", "minimum": 1},
+                "name": {"type": "string", "minLength": 1},
+                "tags": {"type": "array", "items": {"$ref": "#/$defs/tag"}}
+            },
+            "required": ["id", "name"],
+            "additionalProperties": false,
+            "$defs": {"tag": {"enum": ["red", "green"]}}
+        }"##,
+        r#"{"oneOf": [{"type": "string"}, {"type": "integer"}], "not": {"const": ""}}"#,
+        r##"{"$defs": {"node": {"type": "object", "properties": {"children": {"type": "array", "items": {"$ref": "#/$defs/node"}}}}}, "$ref": "#/$defs/node"}"##,
+    ] {
+        println!("Checking {}", s);
+        let schema: Schema = s.parse().unwrap();
+        let mut rng = SplitMix64::new(7);
+        for _ in 0..20 {
+            let value = generate_from_schema(&schema, &mut rng).unwrap();
+            assert!(schema.is_valid(&value), "{:?}", value);
+        }
+    }
+}
+
+#[test]
+fn test_impossible_schema() {
+    let schema: Schema = r#"{"type": "string", "not": {"type": "string"}}"#.parse().unwrap();
+    let err = generate_from_schema(&schema, &mut SplitMix64::new(1)).unwrap_err();
+    assert_eq!(err.reason, "No valid value generated in 100 attempts");
+}
+
+#[test]
+fn test_generate_from_template() {
+    let template: JSONValue = r#"{
+        "users": {"$repeat": [2, 4], "$item": {
+            "id": "{{uuid}}",
+            "name": "{{name}}",
+            "age": "{{int 18 65}}",
+            "score": "{{float 0 1}}",
+            "admin": "{{bool}}",
+            "role": "{{one_of admin viewer}}",
+            "login": "user-{{int 1 9}}",
+            "fixed": [1, null]
+        }}
+    }"#
+    .parse()
+    .unwrap();
+    let mut rng = SplitMix64::new(3);
+    let value = generate_from_template(&template, &mut rng).unwrap();
+    let users = match *value.pointer("/users").unwrap() {
+        JSONValue::JSONArray(ref users) => users.clone(),
+        _ => panic!("users is not an array"),
+    };
+    assert!(users.len() >= 2 && users.len() <= 4);
+    for user in users {
+        match *user.pointer("/id").unwrap() {
+            JSONValue::JSONString(ref id) => {
+                assert_eq!(id.len(), 36);
+                assert_eq!(&id[14..15], "4");
+            }
+            _ => panic!("id is not a string"),
+        }
+        match *user.pointer("/age").unwrap() {
+            JSONValue::JSONNumber(n) => assert!((18.0..=65.0).contains(&n) && n.fract() == 0.0),
+            _ => panic!("age is not a number"),
+        }
+        match *user.pointer("/login").unwrap() {
+            JSONValue::JSONString(ref login) => assert!(login.starts_with("user-")),
+            _ => panic!("login is not a string"),
+        }
+        assert!(matches!(
+            user.pointer("/admin"),
+            Some(&JSONValue::JSONBool(_))
+        ));
+        assert_eq!(user.pointer("/fixed"), Some(&"[1, null]".parse().unwrap()));
+    }
+    let again = generate_from_template(&template, &mut SplitMix64::new(3)).unwrap();
+    assert_eq!(value, again);
+
+    for s in vec![
+        ("\"{{nope}}\"", "Unknown placeholder {{nope}}"),
+        ("\"{{int 1}}\"", "int needs numeric MIN and MAX"),
+        ("\"a {{word\"", "Unterminated placeholder in a {{word"),
+        (r#"{"$repeat": "x", "$item": 1}"#, "Invalid $repeat"),
+    ] {
+        println!("Checking {}", s.0);
+        let template: JSONValue = s.0.parse().unwrap();
+        let err = generate_from_template(&template, &mut rng).unwrap_err();
+        assert_eq!(err.reason, s.1);
+    }
+}
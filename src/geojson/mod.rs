@@ -0,0 +1,357 @@
+use super::*;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone)]
+pub struct JSONGeoError {
+    pub reason: String,
+}
+
+/// Longitude, latitude and optionally altitude (RFC 7946 position)
+pub type Position = Vec<f64>;
+
+/// RFC 7946 geometry. Checked when read: positions have at least two numbers,
+/// line strings at least two positions, and polygon rings at least four with
+/// the last one equal to the first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Geometry {
+    Point(Position),
+    MultiPoint(Vec<Position>),
+    LineString(Vec<Position>),
+    MultiLineString(Vec<Vec<Position>>),
+    /// Exterior ring first, then the holes
+    Polygon(Vec<Vec<Position>>),
+    MultiPolygon(Vec<Vec<Vec<Position>>>),
+    GeometryCollection(Vec<Geometry>),
+}
+
+/// A geometry with properties. `foreign` keeps the other members, like `bbox`,
+/// so they survive a round trip.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Feature {
+    /// A string or a number
+    pub id: Option<JSONValue>,
+    pub geometry: Option<Geometry>,
+    pub properties: Option<JSONMap>,
+    pub foreign: JSONMap,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FeatureCollection {
+    pub features: Vec<Feature>,
+    pub foreign: JSONMap,
+}
+
+/// Any GeoJSON document
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeoJSON {
+    Geometry(Geometry),
+    Feature(Feature),
+    FeatureCollection(FeatureCollection),
+}
+
+impl GeoJSON {
+    /// Reads a document by its `type`. Errors name the offending location as a JSON Pointer.
+    pub fn from_json(value: &JSONValue) -> Result<GeoJSON, JSONGeoError> {
+        match type_of(value, "")? {
+            "Feature" => return Ok(GeoJSON::Feature(read_feature(value, "")?)),
+            "FeatureCollection" => {
+                return Ok(GeoJSON::FeatureCollection(read_collection(value, "")?))
+            }
+            _ => return Ok(GeoJSON::Geometry(read_geometry(value, "")?)),
+        }
+    }
+
+    pub fn to_json(&self) -> JSONValue {
+        match *self {
+            GeoJSON::Geometry(ref geometry) => return geometry.to_json(),
+            GeoJSON::Feature(ref feature) => return feature.to_json(),
+            GeoJSON::FeatureCollection(ref collection) => return collection.to_json(),
+        }
+    }
+}
+
+impl Geometry {
+    pub fn from_json(value: &JSONValue) -> Result<Geometry, JSONGeoError> {
+        return read_geometry(value, "");
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            Geometry::Point(_) => return "Point",
+            Geometry::MultiPoint(_) => return "MultiPoint",
+            Geometry::LineString(_) => return "LineString",
+            Geometry::MultiLineString(_) => return "MultiLineString",
+            Geometry::Polygon(_) => return "Polygon",
+            Geometry::MultiPolygon(_) => return "MultiPolygon",
+            Geometry::GeometryCollection(_) => return "GeometryCollection",
+        }
+    }
+
+    pub fn to_json(&self) -> JSONValue {
+        let mut map = JSONMap::new();
+        map.insert("type".to_owned(), Box::new(string(self.type_name())));
+        let (key, value) = match *self {
+            Geometry::Point(ref p) => ("coordinates", position_json(p)),
+            Geometry::MultiPoint(ref ps) | Geometry::LineString(ref ps) => {
+                ("coordinates", positions_json(ps))
+            }
+            Geometry::MultiLineString(ref lines) | Geometry::Polygon(ref lines) => (
+                "coordinates",
+                array(lines.iter().map(|ps| positions_json(ps))),
+            ),
+            Geometry::MultiPolygon(ref polygons) => (
+                "coordinates",
+                array(
+                    polygons
+                        .iter()
+                        .map(|rings| array(rings.iter().map(|ps| positions_json(ps)))),
+                ),
+            ),
+            Geometry::GeometryCollection(ref geometries) => {
+                ("geometries", array(geometries.iter().map(|g| g.to_json())))
+            }
+        };
+        map.insert(key.to_owned(), Box::new(value));
+        return JSONValue::JSONObject(map);
+    }
+}
+
+impl Feature {
+    pub fn from_json(value: &JSONValue) -> Result<Feature, JSONGeoError> {
+        return read_feature(value, "");
+    }
+
+    /// A property of the feature, `None` if it's missing or there are no properties
+    pub fn property(&self, name: &str) -> Option<&JSONValue> {
+        return self
+            .properties
+            .as_ref()
+            .and_then(|properties| properties.get(name))
+            .map(|value| &**value);
+    }
+
+    pub fn to_json(&self) -> JSONValue {
+        let mut map = self.foreign.clone();
+        map.insert("type".to_owned(), Box::new(string("Feature")));
+        if let Some(ref id) = self.id {
+            map.insert("id".to_owned(), Box::new(id.clone()));
+        }
+        let geometry = match self.geometry {
+            Some(ref geometry) => geometry.to_json(),
+            None => JSONValue::JSONNull(),
+        };
+        map.insert("geometry".to_owned(), Box::new(geometry));
+        let properties = match self.properties {
+            Some(ref properties) => JSONValue::JSONObject(properties.clone()),
+            None => JSONValue::JSONNull(),
+        };
+        map.insert("properties".to_owned(), Box::new(properties));
+        return JSONValue::JSONObject(map);
+    }
+}
+
+impl FeatureCollection {
+    pub fn from_json(value: &JSONValue) -> Result<FeatureCollection, JSONGeoError> {
+        return read_collection(value, "");
+    }
+
+    pub fn to_json(&self) -> JSONValue {
+        let mut map = self.foreign.clone();
+        map.insert("type".to_owned(), Box::new(string("FeatureCollection")));
+        let features = array(self.features.iter().map(|f| f.to_json()));
+        map.insert("features".to_owned(), Box::new(features));
+        return JSONValue::JSONObject(map);
+    }
+}
+
+fn type_of<'a>(value: &'a JSONValue, path: &str) -> Result<&'a str, JSONGeoError> {
+    match member(value, "type", path)? {
+        JSONValue::JSONString(ref name) => return Ok(name),
+        _ => {
+            return Err(make_err(
+                &format!("{}/type", path),
+                "Expected a string".to_owned(),
+            ))
+        }
+    }
+}
+
+fn member<'a>(value: &'a JSONValue, name: &str, path: &str) -> Result<&'a JSONValue, JSONGeoError> {
+    match *value {
+        JSONValue::JSONObject(ref map) => {
+            return map
+                .get(name)
+                .map(|member| &**member)
+                .ok_or_else(|| make_err(path, format!("Missing {}", name)))
+        }
+        _ => return Err(make_err(path, "Expected an object".to_owned())),
+    }
+}
+
+fn read_geometry(value: &JSONValue, path: &str) -> Result<Geometry, JSONGeoError> {
+    let kind = type_of(value, path)?;
+    let key = match kind {
+        "GeometryCollection" => "geometries",
+        "Point" | "MultiPoint" | "LineString" | "MultiLineString" | "Polygon" | "MultiPolygon" => {
+            "coordinates"
+        }
+        _ => return Err(make_err(path, format!("Unknown geometry type {}", kind))),
+    };
+    let coordinates = member(value, key, path)?;
+    let path = &format!("{}/{}", path, key);
+    let line = |value: &JSONValue, path: &str| items(value, path, 2, read_position);
+    let polygon = |value: &JSONValue, path: &str| items(value, path, 0, read_ring);
+    let geometry = match kind {
+        "GeometryCollection" => {
+            Geometry::GeometryCollection(items(coordinates, path, 0, read_geometry)?)
+        }
+        "Point" => Geometry::Point(read_position(coordinates, path)?),
+        "MultiPoint" => Geometry::MultiPoint(items(coordinates, path, 0, read_position)?),
+        "LineString" => Geometry::LineString(line(coordinates, path)?),
+        "MultiLineString" => Geometry::MultiLineString(items(coordinates, path, 0, line)?),
+        "Polygon" => Geometry::Polygon(polygon(coordinates, path)?),
+        _ => Geometry::MultiPolygon(items(coordinates, path, 0, polygon)?),
+    };
+    return Ok(geometry);
+}
+
+fn read_feature(value: &JSONValue, path: &str) -> Result<Feature, JSONGeoError> {
+    let map = match (type_of(value, path)?, value) {
+        ("Feature", JSONValue::JSONObject(map)) => map,
+        (kind, _) => return Err(make_err(path, format!("Expected a Feature, got {}", kind))),
+    };
+    let id = match map.get("id").map(|id| &**id) {
+        Some(id @ JSONValue::JSONString(_)) | Some(id @ JSONValue::JSONNumber(_)) => {
+            Some(id.clone())
+        }
+        Some(_) => {
+            return Err(make_err(
+                &format!("{}/id", path),
+                "Expected a string or a number".to_owned(),
+            ))
+        }
+        None => None,
+    };
+    let geometry = match map.get("geometry").map(|g| &**g) {
+        None | Some(JSONValue::JSONNull()) => None,
+        Some(geometry) => Some(read_geometry(geometry, &format!("{}/geometry", path))?),
+    };
+    let properties = match map.get("properties").map(|p| &**p) {
+        None | Some(JSONValue::JSONNull()) => None,
+        Some(JSONValue::JSONObject(properties)) => Some(properties.clone()),
+        Some(_) => {
+            return Err(make_err(
+                &format!("{}/properties", path),
+                "Expected an object".to_owned(),
+            ))
+        }
+    };
+    return Ok(Feature {
+        id,
+        geometry,
+        properties,
+        foreign: foreign(map, &["type", "id", "geometry", "properties"]),
+    });
+}
+
+fn read_collection(value: &JSONValue, path: &str) -> Result<FeatureCollection, JSONGeoError> {
+    let map = match (type_of(value, path)?, value) {
+        ("FeatureCollection", JSONValue::JSONObject(map)) => map,
+        (kind, _) => {
+            return Err(make_err(
+                path,
+                format!("Expected a FeatureCollection, got {}", kind),
+            ))
+        }
+    };
+    let features_path = format!("{}/features", path);
+    let features = items(
+        member(value, "features", path)?,
+        &features_path,
+        0,
+        read_feature,
+    )?;
+    return Ok(FeatureCollection {
+        features,
+        foreign: foreign(map, &["type", "features"]),
+    });
+}
+
+fn foreign(map: &JSONMap, known: &[&str]) -> JSONMap {
+    let mut foreign = JSONMap::new();
+    for (key, value) in map.iter() {
+        if !known.contains(&key.as_str()) {
+            foreign.insert(key.clone(), value.clone());
+        }
+    }
+    return foreign;
+}
+
+// Reads every item of an array with `read`, requiring at least `min` of them
+fn items<T, F>(value: &JSONValue, path: &str, min: usize, read: F) -> Result<Vec<T>, JSONGeoError>
+where
+    F: Fn(&JSONValue, &str) -> Result<T, JSONGeoError>,
+{
+    let items = match *value {
+        JSONValue::JSONArray(ref items) => items,
+        _ => return Err(make_err(path, "Expected an array".to_owned())),
+    };
+    if items.len() < min {
+        return Err(make_err(
+            path,
+            format!("Expected at least {} items, got {}", min, items.len()),
+        ));
+    }
+    return items
+        .iter()
+        .enumerate()
+        .map(|(i, item)| read(item, &format!("{}/{}", path, i)))
+        .collect();
+}
+
+fn read_position(value: &JSONValue, path: &str) -> Result<Position, JSONGeoError> {
+    return items(value, path, 2, |n, path| match *n {
+        JSONValue::JSONNumber(n) => Ok(n),
+        _ => Err(make_err(path, "Expected a number".to_owned())),
+    });
+}
+
+// Linear ring: closed, with at least four positions
+fn read_ring(value: &JSONValue, path: &str) -> Result<Vec<Position>, JSONGeoError> {
+    let ring = items(value, path, 4, read_position)?;
+    if ring.first() != ring.last() {
+        return Err(make_err(
+            path,
+            "Expected the last position to equal the first".to_owned(),
+        ));
+    }
+    return Ok(ring);
+}
+
+fn position_json(position: &[f64]) -> JSONValue {
+    return array(position.iter().map(|&n| JSONValue::JSONNumber(n)));
+}
+
+fn positions_json(positions: &[Position]) -> JSONValue {
+    return array(positions.iter().map(|p| position_json(p)));
+}
+
+fn array<I: Iterator<Item = JSONValue>>(items: I) -> JSONValue {
+    return JSONValue::JSONArray(items.map(Box::new).collect());
+}
+
+fn string(s: &str) -> JSONValue {
+    return JSONValue::JSONString(s.to_owned());
+}
+
+// Errors at the root of the document go without a location
+fn make_err(path: &str, s: String) -> JSONGeoError {
+    let reason = if path.is_empty() {
+        s
+    } else {
+        format!("{}: {}", path, s)
+    };
+    JSONGeoError { reason }
+}
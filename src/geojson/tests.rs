@@ -0,0 +1,96 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_round_trip() {
+    for s in vec![
+        r#"{"type": "Point", "coordinates": [30.5, 50.45, 179]}"#,
+        r#"{"type": "MultiPoint", "coordinates": [[0, 0], [1, 1]]}"#,
+        r#"{"type": "LineString", "coordinates": [[0, 0], [1, 1]]}"#,
+        r#"{"type": "MultiLineString", "coordinates": [[[0, 0], [1, 1]], [[2, 2], [3, 3]]]}"#,
+        r#"{"type": "Polygon", "coordinates": [[[0, 0], [4, 0], [4, 4], [0, 0]], [[1, 1], [2, 1], [2, 2], [1, 1]]]}"#,
+        r#"{"type": "MultiPolygon", "coordinates": [[[[0, 0], [1, 0], [1, 1], [0, 0]]]]}"#,
+        r#"{"type": "GeometryCollection", "geometries": [{"type": "Point", "coordinates": [1, 2]}]}"#,
+        r#"{"type": "Feature", "id": 7, "geometry": null, "properties": {"name": "x"}}"#,
+        r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 2]}, "properties": null, "title": "kept"}"#,
+        r#"{"type": "FeatureCollection", "bbox": [0, 0, 1, 1], "features": [{"type": "Feature", "id": "a", "geometry": null, "properties": {}}]}"#,
+    ] {
+        println!("Checking {}", s);
+        let value = parse(s);
+        assert_eq!(GeoJSON::from_json(&value).unwrap().to_json(), value);
+    }
+
+    let feature = Feature::from_json(&parse(
+        r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 2]}, "properties": {"name": "x"}}"#,
+    ))
+    .unwrap();
+    assert_eq!(feature.geometry, Some(Geometry::Point(vec![1.0, 2.0])));
+    assert_eq!(feature.property("name"), Some(&parse("\"x\"")));
+    assert_eq!(feature.property("other"), None);
+    assert_eq!(feature.id, None);
+}
+
+#[test]
+fn test_errors() {
+    for s in vec![
+        ("[]", "Expected an object"),
+        (r#"{"coordinates": [1, 2]}"#, "Missing type"),
+        (r#"{"type": "Circle"}"#, "Unknown geometry type Circle"),
+        (r#"{"type": "Point"}"#, "Missing coordinates"),
+        (
+            r#"{"type": "Point", "coordinates": [1]}"#,
+            "/coordinates: Expected at least 2 items, got 1",
+        ),
+        (
+            r#"{"type": "MultiPoint", "coordinates": [[1, 2], [1, "2"]]}"#,
+            "/coordinates/1/1: Expected a number",
+        ),
+        (
+            r#"{"type": "LineString", "coordinates": [[1, 2]]}"#,
+            "/coordinates: Expected at least 2 items, got 1",
+        ),
+        (
+            r#"{"type": "Polygon", "coordinates": [[[0, 0], [1, 0], [1, 1], [0, 1]]]}"#,
+            "/coordinates/0: Expected the last position to equal the first",
+        ),
+        (
+            r#"{"type": "MultiPolygon", "coordinates": [[[[0, 0], [1, 0], [0, 0]]]]}"#,
+            "/coordinates/0/0: Expected at least 4 items, got 3",
+        ),
+        (
+            r#"{"type": "GeometryCollection", "geometries": [{"type": "Feature"}]}"#,
+            "/geometries/0: Unknown geometry type Feature",
+        ),
+        (
+            r#"{"type": "Feature", "id": true, "geometry": null}"#,
+            "/id: Expected a string or a number",
+        ),
+        (
+            r#"{"type": "Feature", "geometry": null, "properties": []}"#,
+            "/properties: Expected an object",
+        ),
+        (
+            r#"{"type": "FeatureCollection", "features": [{"type": "Point", "coordinates": [0, 0]}]}"#,
+            "/features/0: Expected a Feature, got Point",
+        ),
+        (
+            r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "geometry": {"type": "Point", "coordinates": []}}]}"#,
+            "/features/0/geometry/coordinates: Expected at least 2 items, got 0",
+        ),
+        (r#"{"type": "FeatureCollection"}"#, "Missing features"),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(GeoJSON::from_json(&parse(s.0)).unwrap_err().reason, s.1);
+    }
+    assert_eq!(
+        FeatureCollection::from_json(&parse(r#"{"type": "Feature"}"#))
+            .unwrap_err()
+            .reason,
+        "Expected a FeatureCollection, got Feature"
+    );
+}
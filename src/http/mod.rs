@@ -0,0 +1,156 @@
+use super::*;
+use encoding::{decode, detect_encoding, Encoding};
+
+#[cfg(test)]
+mod tests;
+
+pub const JSON_CONTENT_TYPE: &str = "application/json";
+
+pub const STATUS_BAD_REQUEST: u16 = 400;
+pub const STATUS_PAYLOAD_TOO_LARGE: u16 = 413;
+pub const STATUS_UNSUPPORTED_MEDIA_TYPE: u16 = 415;
+
+/// Why a request body was refused, with the HTTP status to answer with.
+/// The building block for web framework extractors: they pass the body and
+/// `Content-Type` to `parse_json_body` and turn a rejection into a response
+/// with `status` and `to_json()` as the body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JSONBodyRejection {
+    pub status: u16,
+    pub reason: String,
+    /// Byte offset of a parse error in the body as received, before decoding
+    pub position: Option<usize>,
+    /// 1-based line and column of a parse error, counted in chars
+    pub line_column: Option<(usize, usize)>,
+}
+
+impl JSONBodyRejection {
+    fn new(status: u16, reason: String) -> JSONBodyRejection {
+        return JSONBodyRejection {
+            status,
+            reason,
+            position: None,
+            line_column: None,
+        };
+    }
+
+    /// Error body: `{"error": "...", "position": 12, "line": 2, "column": 3}`,
+    /// position fields only for parse errors
+    pub fn to_json(&self) -> JSONValue {
+        let mut members = JSONMap::new();
+        members.insert(
+            "error".to_owned(),
+            Box::new(JSONValue::JSONString(self.reason.clone())),
+        );
+        if let Some(position) = self.position {
+            members.insert(
+                "position".to_owned(),
+                Box::new(JSONValue::JSONNumber(position as f64)),
+            );
+        }
+        if let Some((line, column)) = self.line_column {
+            members.insert(
+                "line".to_owned(),
+                Box::new(JSONValue::JSONNumber(line as f64)),
+            );
+            members.insert(
+                "column".to_owned(),
+                Box::new(JSONValue::JSONNumber(column as f64)),
+            );
+        }
+        return JSONValue::JSONObject(members);
+    }
+}
+
+/// Parses a request body with `ParserOptions::untrusted()` limits.
+pub fn parse_json_body(
+    content_type: Option<&str>,
+    body: &[u8],
+) -> Result<JSONValue, JSONBodyRejection> {
+    return parse_json_body_with(content_type, body, &ParserOptions::untrusted());
+}
+
+/// Parses a request body: the content type must be `application/json` or a
+/// `+json` type; bodies over `max_input_bytes` are refused as too large before
+/// parsing, and so are bodies hitting another limit or the allocation budget
+/// while parsed; UTF-16 and UTF-32 bodies are decoded like in `JSONValue::from_slice`.
+pub fn parse_json_body_with(
+    content_type: Option<&str>,
+    body: &[u8],
+    options: &ParserOptions,
+) -> Result<JSONValue, JSONBodyRejection> {
+    match content_type {
+        Some(content_type) if is_json_content_type(content_type) => (),
+        Some(content_type) => {
+            return Err(JSONBodyRejection::new(
+                STATUS_UNSUPPORTED_MEDIA_TYPE,
+                format!("Expected {}, got {}", JSON_CONTENT_TYPE, content_type),
+            ))
+        }
+        None => {
+            return Err(JSONBodyRejection::new(
+                STATUS_UNSUPPORTED_MEDIA_TYPE,
+                format!("Expected {} content type", JSON_CONTENT_TYPE),
+            ))
+        }
+    }
+    if let Some(limit) = options.max_input_bytes {
+        if body.len() > limit {
+            return Err(JSONBodyRejection::new(
+                STATUS_PAYLOAD_TOO_LARGE,
+                format!("Body is larger than {} bytes", limit),
+            ));
+        }
+    }
+    let text = decode(body).map_err(|err| rejection(err, body, None))?;
+    return JSONValue::parse_with(&text, options).map_err(|err| rejection(err, body, Some(&text)));
+}
+
+/// `application/json` or `application/something+json`, parameters ignored
+pub fn is_json_content_type(content_type: &str) -> bool {
+    let media_type = content_type
+        .split(';')
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase();
+    return media_type == JSON_CONTENT_TYPE
+        || (media_type.starts_with("application/") && media_type.ends_with("+json"));
+}
+
+// Decoding errors are already at body offsets, parse errors are at offsets
+// into the decoded text
+fn rejection(err: JSONParseError, body: &[u8], text: Option<&str>) -> JSONBodyRejection {
+    let (position, line_column) = match text {
+        Some(text) => (
+            err.position.and_then(|p| body_offset(body, text, p)),
+            err.line_column(text),
+        ),
+        None => (err.position, None),
+    };
+    // a body that outgrew the limits is too large, not malformed
+    let status = match err.kind {
+        JSONParseErrorKind::LimitExceeded | JSONParseErrorKind::BudgetExceeded => {
+            STATUS_PAYLOAD_TOO_LARGE
+        }
+        _ => STATUS_BAD_REQUEST,
+    };
+    return JSONBodyRejection {
+        status,
+        reason: err.reason,
+        position,
+        line_column,
+    };
+}
+
+// Byte offset in the body of a byte offset in the text decoded from it
+fn body_offset(body: &[u8], text: &str, position: usize) -> Option<usize> {
+    let (encoding, bom) = detect_encoding(body);
+    let before = text.get(..position)?;
+    let offset = match encoding {
+        Encoding::Utf8 => position,
+        Encoding::Utf16Le | Encoding::Utf16Be => before.encode_utf16().count() * 2,
+        Encoding::Utf32Le | Encoding::Utf32Be => before.chars().count() * 4,
+    };
+    return Some(bom + offset);
+}
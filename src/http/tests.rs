@@ -0,0 +1,103 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+#[test]
+fn test_content_types() {
+    for s in vec![
+        ("application/json", true),
+        ("Application/JSON; charset=utf-8", true),
+        ("application/problem+json", true),
+        ("application/merge-patch+json;x=1", true),
+        ("text/json", false),
+        ("text/plain", false),
+        ("", false),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(is_json_content_type(s.0), s.1);
+    }
+}
+
+#[test]
+fn test_parse_json_body() {
+    let json = Some("application/json");
+    assert_eq!(
+        parse_json_body(json, b"{\"a\": [1]}").unwrap(),
+        "{\"a\": [1]}".parse().unwrap()
+    );
+    let err = parse_json_body(json, b"{\n  \"a\": }").unwrap_err();
+    assert_eq!(err.status, STATUS_BAD_REQUEST);
+    assert_eq!(err.position, Some(9));
+    assert_eq!(err.line_column, Some((2, 8)));
+    assert_eq!(
+        err.to_json(),
+        format!(
+            "{{\"error\": \"{}\", \"position\": 9, \"line\": 2, \"column\": 8}}",
+            err.reason
+        )
+        .parse()
+        .unwrap()
+    );
+
+    let err = parse_json_body(Some("text/plain"), b"{}").unwrap_err();
+    assert_eq!(err.status, STATUS_UNSUPPORTED_MEDIA_TYPE);
+    assert_eq!(
+        err.to_json(),
+        "{\"error\": \"Expected application/json, got text/plain\"}"
+            .parse()
+            .unwrap()
+    );
+    assert_eq!(
+        parse_json_body(None, b"{}").unwrap_err().status,
+        STATUS_UNSUPPORTED_MEDIA_TYPE
+    );
+
+    let options = ParserOptions {
+        max_input_bytes: Some(4),
+        max_depth: Some(1),
+        ..ParserOptions::default()
+    };
+    let err = parse_json_body_with(json, b"[1, 2]", &options).unwrap_err();
+    assert_eq!(err.status, STATUS_PAYLOAD_TOO_LARGE);
+    assert_eq!(err.position, None);
+    let err = parse_json_body_with(json, b"[[]]", &options).unwrap_err();
+    assert_eq!(err.status, STATUS_PAYLOAD_TOO_LARGE);
+    assert!(err.reason.starts_with("Maximum nesting depth"));
+    let options = ParserOptions {
+        max_allocated_bytes: Some(16),
+        ..ParserOptions::default()
+    };
+    let err =
+        parse_json_body_with(json, b"[\"abcdefghijklmnopqrstuvwxyz\"]", &options).unwrap_err();
+    assert_eq!(err.status, STATUS_PAYLOAD_TOO_LARGE);
+    let err = parse_json_body(json, &[b'"', 0xFF, b'"']).unwrap_err();
+    assert_eq!((err.status, err.position), (STATUS_BAD_REQUEST, Some(1)));
+
+    // positions are in the body as received, line and column in chars
+    for s in vec![
+        (b"\xEF\xBB\xBF[\"\xC3\xA9\", }".to_vec(), 10, (1, 7)),
+        (utf16le("[\"é\", }"), 12, (1, 7)),
+        (
+            [&[0xFF, 0xFE][..], &utf16le("[\"é\",\n }")].concat(),
+            16,
+            (2, 2),
+        ),
+        (
+            "[\"é\", }"
+                .chars()
+                .flat_map(|c| (c as u32).to_be_bytes())
+                .collect(),
+            24,
+            (1, 7),
+        ),
+    ] {
+        println!("Checking {:?}", s.0);
+        let err = parse_json_body(json, &s.0).unwrap_err();
+        assert_eq!(err.position, Some(s.1));
+        assert_eq!(err.line_column, Some(s.2));
+    }
+}
+
+fn utf16le(text: &str) -> Vec<u8> {
+    return text.encode_utf16().flat_map(|u| u.to_le_bytes()).collect();
+}
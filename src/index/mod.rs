@@ -0,0 +1,108 @@
+use super::*;
+use pattern::PathPattern;
+use pointer::escape_token;
+
+#[cfg(test)]
+mod tests;
+
+/// A document with an index from JSON Pointers to nodes, built once, so each
+/// lookup is a single hash lookup however deep the node is. Worth it when the
+/// same document is queried many times, e.g. by a rules engine.
+/// The index holds a pointer string per node; `with_patterns` limits it to the
+/// nodes that are actually looked up.
+pub struct IndexedDocument<'a> {
+    document: &'a JSONValue,
+    index: HashMap<String, &'a JSONValue>,
+    // every node is indexed, so a miss means there's no such node
+    complete: bool,
+}
+
+impl<'a> IndexedDocument<'a> {
+    /// Indexes every node of the document.
+    pub fn new(document: &'a JSONValue) -> IndexedDocument<'a> {
+        let mut indexed = IndexedDocument {
+            document,
+            index: HashMap::new(),
+            complete: true,
+        };
+        indexed.add(document, &mut vec![], &mut String::new(), &[]);
+        return indexed;
+    }
+
+    /// Indexes only the nodes matching one of the patterns. Other pointers are
+    /// still found, by walking the document.
+    pub fn with_patterns(document: &'a JSONValue, patterns: &[PathPattern]) -> IndexedDocument<'a> {
+        let mut indexed = IndexedDocument {
+            document,
+            index: HashMap::new(),
+            complete: false,
+        };
+        indexed.add(document, &mut vec![], &mut String::new(), patterns);
+        return indexed;
+    }
+
+    /// Looks up a value by RFC 6901 JSON Pointer, like `JSONValue::pointer`.
+    pub fn get(&self, pointer: &str) -> Option<&'a JSONValue> {
+        match self.index.get(pointer) {
+            Some(&value) => return Some(value),
+            None if self.complete => return None,
+            None => return self.document.pointer(pointer),
+        }
+    }
+
+    pub fn contains(&self, pointer: &str) -> bool {
+        return self.get(pointer).is_some();
+    }
+
+    /// Number of indexed nodes
+    pub fn len(&self) -> usize {
+        return self.index.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.index.is_empty();
+    }
+
+    pub fn document(&self) -> &'a JSONValue {
+        return self.document;
+    }
+
+    // Adds `value` and its descendants matching `patterns`, all of them if
+    // there are none. `tokens` is the path of `value`, `pointer` the same escaped.
+    fn add(
+        &mut self,
+        value: &'a JSONValue,
+        tokens: &mut Vec<String>,
+        pointer: &mut String,
+        patterns: &[PathPattern],
+    ) {
+        if !patterns.is_empty() && !patterns.iter().any(|p| p.matches_prefix(tokens)) {
+            return;
+        }
+        if patterns.is_empty() || patterns.iter().any(|p| p.matches_tokens(tokens)) {
+            self.index.insert(pointer.clone(), value);
+        }
+        let len = pointer.len();
+        let mut visit = |token: String, item: &'a JSONValue, indexed: &mut IndexedDocument<'a>| {
+            pointer.push('/');
+            pointer.push_str(&escape_token(&token));
+            tokens.push(token);
+            indexed.add(item, tokens, pointer, patterns);
+            tokens.pop();
+            pointer.truncate(len);
+        };
+        match *value {
+            JSONValue::JSONObject(ref map) => {
+                for (key, item) in map {
+                    visit(key.clone(), item, self);
+                }
+            }
+            JSONValue::JSONArray(ref items) => {
+                for (i, item) in items.iter().enumerate() {
+                    visit(i.to_string(), item, self);
+                }
+            }
+            _ => (),
+        }
+    }
+}
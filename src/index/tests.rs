@@ -0,0 +1,42 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn document() -> JSONValue {
+    return r#"{"user": {"name": "Ann", "roles": ["admin", "dev"], "a/b": {"~": 1}}, "n": null}"#
+        .parse()
+        .unwrap();
+}
+
+#[test]
+fn test_get() {
+    let document = document();
+    let everything = IndexedDocument::new(&document);
+    let selected = IndexedDocument::with_patterns(
+        &document,
+        &[PathPattern::compile("/user/roles/*").unwrap()],
+    );
+    assert_eq!(everything.len(), 9);
+    assert_eq!(selected.len(), 2);
+    for s in vec![
+        "",
+        "/user",
+        "/user/name",
+        "/user/roles/1",
+        "/user/a~1b/~0",
+        "/n",
+        "/user/roles/2",
+        "/user/roles/01",
+        "/missing",
+        "user",
+    ] {
+        println!("Checking {}", s);
+        assert_eq!(everything.get(s), document.pointer(s));
+        assert_eq!(selected.get(s), document.pointer(s));
+        assert_eq!(everything.contains(s), document.pointer(s).is_some());
+    }
+    assert!(std::ptr::eq(
+        everything.get("/user/roles/0").unwrap(),
+        document.pointer("/user/roles/0").unwrap()
+    ));
+}
@@ -0,0 +1,183 @@
+use super::*;
+use pointer::{parse_index, parse_pointer};
+use std::rc::Rc;
+
+#[cfg(test)]
+mod tests;
+
+/// Interned object key, valid within the `Interner` that produced it.
+/// Comparing symbols is an integer comparison.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+/// Stores every distinct key once and hands out `Symbol`s for them.
+#[derive(Debug, Clone, Default)]
+pub struct Interner {
+    names: Vec<Rc<str>>,
+    symbols: HashMap<Rc<str>, Symbol>,
+}
+
+impl Interner {
+    pub fn new() -> Interner {
+        return Interner::default();
+    }
+
+    pub fn intern(&mut self, name: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(name) {
+            return symbol;
+        }
+        let symbol = Symbol(self.names.len() as u32);
+        let name: Rc<str> = Rc::from(name);
+        self.names.push(name.clone());
+        self.symbols.insert(name, symbol);
+        return symbol;
+    }
+
+    /// Symbol of an already interned name, without adding new ones.
+    pub fn lookup(&self, name: &str) -> Option<Symbol> {
+        return self.symbols.get(name).cloned();
+    }
+
+    /// Panics if the symbol comes from another interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        return &self.names[symbol.0 as usize];
+    }
+
+    /// Number of distinct names
+    pub fn len(&self) -> usize {
+        return self.names.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.names.is_empty();
+    }
+}
+
+/// Value with object keys replaced by symbols of the owning `InternedDocument`.
+/// Objects are member lists in the map's iteration order, keys are unique.
+#[derive(Debug, Clone, PartialEq)]
+pub enum InternedValue {
+    JSONNull(),
+    JSONString(String),
+    JSONBool(bool),
+    JSONNumber(f64),
+    JSONObject(Vec<(Symbol, InternedValue)>),
+    JSONArray(Vec<InternedValue>),
+    JSONRaw(String),
+}
+
+/// Document with every key stored once, however many objects repeat it.
+/// Cuts memory of large arrays of similar records; lookups by `&str` resolve
+/// the name once and then compare symbols.
+#[derive(Debug, Clone)]
+pub struct InternedDocument {
+    pub interner: Interner,
+    pub root: InternedValue,
+}
+
+impl InternedDocument {
+    pub fn parse(input: &str) -> Result<InternedDocument, JSONParseError> {
+        return Ok(InternedDocument::from(&input.parse::<JSONValue>()?));
+    }
+
+    pub fn to_value(&self) -> JSONValue {
+        return self.value_of(&self.root);
+    }
+
+    fn value_of(&self, value: &InternedValue) -> JSONValue {
+        match *value {
+            InternedValue::JSONNull() => return JSONValue::JSONNull(),
+            InternedValue::JSONString(ref s) => return JSONValue::JSONString(s.clone()),
+            InternedValue::JSONBool(b) => return JSONValue::JSONBool(b),
+            InternedValue::JSONNumber(n) => return JSONValue::JSONNumber(n),
+            InternedValue::JSONRaw(ref raw) => return JSONValue::JSONRaw(raw.clone()),
+            InternedValue::JSONArray(ref items) => {
+                return JSONValue::JSONArray(
+                    items
+                        .iter()
+                        .map(|item| Box::new(self.value_of(item)))
+                        .collect(),
+                )
+            }
+            InternedValue::JSONObject(ref members) => {
+                let mut map = JSONMap::new();
+                for &(symbol, ref member) in members {
+                    map.insert(
+                        self.interner.resolve(symbol).to_owned(),
+                        Box::new(self.value_of(member)),
+                    );
+                }
+                return JSONValue::JSONObject(map);
+            }
+        }
+    }
+
+    /// Member of an object in this document by name.
+    pub fn get<'a>(&self, object: &'a InternedValue, key: &str) -> Option<&'a InternedValue> {
+        let symbol = self.interner.lookup(key)?;
+        match *object {
+            InternedValue::JSONObject(ref members) => {
+                return members
+                    .iter()
+                    .find(|member| member.0 == symbol)
+                    .map(|member| &member.1)
+            }
+            _ => return None,
+        }
+    }
+
+    /// Looks up a value by JSON Pointer, like `JSONValue::pointer`.
+    pub fn pointer(&self, pointer: &str) -> Option<&InternedValue> {
+        let mut current = &self.root;
+        for token in parse_pointer(pointer)? {
+            current = match *current {
+                InternedValue::JSONObject(_) => self.get(current, &token)?,
+                InternedValue::JSONArray(ref items) => items.get(parse_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        return Some(current);
+    }
+}
+
+impl<'a> From<&'a JSONValue> for InternedDocument {
+    fn from(value: &'a JSONValue) -> InternedDocument {
+        let mut interner = Interner::new();
+        let root = intern_value(&mut interner, value);
+        return InternedDocument { interner, root };
+    }
+}
+
+impl FromStr for InternedDocument {
+    type Err = JSONParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return InternedDocument::parse(s);
+    }
+}
+
+fn intern_value(interner: &mut Interner, value: &JSONValue) -> InternedValue {
+    match *value {
+        JSONValue::JSONNull() => return InternedValue::JSONNull(),
+        JSONValue::JSONString(ref s) => return InternedValue::JSONString(s.clone()),
+        JSONValue::JSONBool(b) => return InternedValue::JSONBool(b),
+        JSONValue::JSONNumber(n) => return InternedValue::JSONNumber(n),
+        JSONValue::JSONRaw(ref raw) => return InternedValue::JSONRaw(raw.clone()),
+        JSONValue::JSONArray(ref items) => {
+            return InternedValue::JSONArray(
+                items
+                    .iter()
+                    .map(|item| intern_value(interner, item))
+                    .collect(),
+            )
+        }
+        JSONValue::JSONObject(ref members) => {
+            return InternedValue::JSONObject(
+                members
+                    .iter()
+                    .map(|(key, member)| (interner.intern(key), intern_value(interner, member)))
+                    .collect(),
+            )
+        }
+    }
+}
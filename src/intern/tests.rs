@@ -0,0 +1,41 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+#[test]
+fn test_interner() {
+    let mut interner = Interner::new();
+    assert!(interner.is_empty());
+    let id = interner.intern("id");
+    let name = interner.intern("name");
+    assert_ne!(id, name);
+    assert_eq!(interner.intern("id"), id);
+    assert_eq!(interner.lookup("name"), Some(name));
+    assert_eq!(interner.lookup("missing"), None);
+    assert_eq!(interner.resolve(name), "name");
+    assert_eq!(interner.len(), 2);
+}
+
+#[test]
+fn test_interned_document() {
+    let input = r#"{"users": [{"id": 1, "name": "a"}, {"id": 2, "name": "b"}, {"id": 3}]}"#;
+    let document: InternedDocument = input.parse().unwrap();
+    assert_eq!(document.interner.len(), 3);
+    assert_eq!(document.to_value(), input.parse().unwrap());
+    for s in vec![
+        (
+            "/users/1/name",
+            Some(InternedValue::JSONString("b".to_owned())),
+        ),
+        ("/users/2/id", Some(InternedValue::JSONNumber(3.0))),
+        ("/users/2/name", None),
+        ("/users/3", None),
+        ("/missing", None),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(document.pointer(s.0).cloned(), s.1);
+    }
+    let users = document.get(&document.root, "users").unwrap();
+    assert_eq!(document.get(users, "id"), None);
+    assert!(InternedDocument::parse("{").is_err());
+}
@@ -0,0 +1,822 @@
+use super::*;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+const CONTEXT: &str = "@context";
+const KEYWORDS: [&str; 11] = [
+    "@context",
+    "@id",
+    "@type",
+    "@value",
+    "@language",
+    "@list",
+    "@set",
+    "@graph",
+    "@index",
+    "@vocab",
+    "@base",
+];
+// Remote contexts loading each other deeper than this are taken as a cycle
+const MAX_REMOTE_CONTEXTS: usize = 32;
+
+#[derive(Debug, Clone)]
+pub struct JSONLDError {
+    pub reason: String,
+}
+
+/// Loads a remote context by its URL, returning the document holding it in `@context`.
+pub type ContextLoader<'a> = Box<dyn FnMut(&str) -> Result<JSONValue, JSONLDError> + 'a>;
+
+/// Expands and compacts JSON-LD documents with the core algorithms of
+/// JSON-LD 1.1. Supported in contexts: terms mapped to IRIs or compact IRIs,
+/// `@vocab`, a default `@language`, type coercion (`@type` of `@id`, `@vocab`
+/// or a datatype IRI) and `@list` and `@set` containers. Relative IRIs are
+/// kept as they are (`@base` is ignored); other container types, reverse
+/// properties, scoped contexts, framing and RDF conversion aren't supported.
+///
+/// Remote contexts, referenced by URL, fail unless the processor was made
+/// `with_loader`; nothing is fetched on its own.
+pub struct JSONLDProcessor<'a> {
+    loader: Option<ContextLoader<'a>>,
+    remote: HashMap<String, JSONValue>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Context {
+    vocab: Option<String>,
+    language: Option<String>,
+    // `None` for terms explicitly mapped to null, which are never expanded
+    terms: BTreeMap<String, Option<Term>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Term {
+    iri: String,
+    // `@id`, `@vocab` or a datatype IRI
+    kind: Option<String>,
+    // `@list` or `@set`
+    container: Option<String>,
+}
+
+impl<'a> JSONLDProcessor<'a> {
+    pub fn new() -> JSONLDProcessor<'a> {
+        return JSONLDProcessor {
+            loader: None,
+            remote: HashMap::new(),
+        };
+    }
+
+    pub fn with_loader<F>(loader: F) -> JSONLDProcessor<'a>
+    where
+        F: FnMut(&str) -> Result<JSONValue, JSONLDError> + 'a,
+    {
+        return JSONLDProcessor {
+            loader: Some(Box::new(loader)),
+            remote: HashMap::new(),
+        };
+    }
+
+    /// Expands a document: every property becomes an absolute IRI, every value
+    /// an array of value or node objects, and the contexts are dropped.
+    pub fn expand(&mut self, document: &JSONValue) -> Result<JSONValue, JSONLDError> {
+        return self.expand_with(document, &JSONValue::JSONNull());
+    }
+
+    /// Expands with `context` applied before the document's own contexts, for
+    /// plain JSON that has none.
+    pub fn expand_with(
+        &mut self,
+        document: &JSONValue,
+        context: &JSONValue,
+    ) -> Result<JSONValue, JSONLDError> {
+        let context = self.process(&Context::default(), inner_context(context), 0)?;
+        let expanded = self.expand_element(&context, None, document)?;
+        let expanded = match expanded {
+            JSONValue::JSONObject(ref map) if map.len() == 1 && map.contains_key("@graph") => {
+                (*map["@graph"]).clone()
+            }
+            other => other,
+        };
+        match expanded {
+            JSONValue::JSONNull() => return Ok(JSONValue::JSONArray(vec![])),
+            JSONValue::JSONArray(_) => return Ok(expanded),
+            other => return Ok(JSONValue::JSONArray(vec![Box::new(other)])),
+        }
+    }
+
+    /// Expands a document, then compacts it against `context`: properties and
+    /// IRIs are shortened to the terms and prefixes of the context, and single
+    /// values are taken out of their arrays. The context goes into `@context`
+    /// of the result; several top-level nodes end up under `@graph`.
+    pub fn compact(
+        &mut self,
+        document: &JSONValue,
+        context: &JSONValue,
+    ) -> Result<JSONValue, JSONLDError> {
+        let expanded = self.expand(document)?;
+        let context_value = inner_context(context);
+        let active = self.process(&Context::default(), context_value, 0)?;
+        let mut result = match compact_element(&active, None, &expanded) {
+            JSONValue::JSONObject(map) => map,
+            JSONValue::JSONArray(items) => {
+                let mut map = JSONMap::new();
+                if !items.is_empty() {
+                    map.insert("@graph".to_owned(), Box::new(JSONValue::JSONArray(items)));
+                }
+                map
+            }
+            _ => JSONMap::new(),
+        };
+        let empty = match *context_value {
+            JSONValue::JSONNull() => true,
+            JSONValue::JSONObject(ref map) => map.is_empty(),
+            JSONValue::JSONArray(ref items) => items.is_empty(),
+            _ => false,
+        };
+        if !empty {
+            result.insert(CONTEXT.to_owned(), Box::new(context_value.clone()));
+        }
+        return Ok(JSONValue::JSONObject(result));
+    }
+
+    // Context processing: applies a local context (object, array, URL or null)
+    fn process(
+        &mut self,
+        active: &Context,
+        local: &JSONValue,
+        depth: usize,
+    ) -> Result<Context, JSONLDError> {
+        match *local {
+            JSONValue::JSONNull() => return Ok(Context::default()),
+            JSONValue::JSONArray(ref items) => {
+                let mut result = active.clone();
+                for item in items {
+                    result = self.process(&result, item, depth)?;
+                }
+                return Ok(result);
+            }
+            JSONValue::JSONString(ref url) => {
+                if depth >= MAX_REMOTE_CONTEXTS {
+                    return Err(make_err(format!("Recursive context inclusion of {}", url)));
+                }
+                let document = self.load(url)?;
+                return self.process(active, inner_context(&document), depth + 1);
+            }
+            JSONValue::JSONObject(ref map) => {
+                let mut result = active.clone();
+                if let Some(vocab) = map.get("@vocab") {
+                    result.vocab = match **vocab {
+                        JSONValue::JSONNull() => None,
+                        JSONValue::JSONString(ref vocab) => {
+                            let mut defining = vec![];
+                            define_prefixes(map, vocab, &mut result, &mut defining)?;
+                            expand_iri(&result, vocab, true).or_else(|| Some(vocab.clone()))
+                        }
+                        _ => return Err(make_err("@vocab must be a string or null".to_owned())),
+                    };
+                }
+                if let Some(language) = map.get("@language") {
+                    result.language = match **language {
+                        JSONValue::JSONNull() => None,
+                        JSONValue::JSONString(ref language) => Some(language.to_lowercase()),
+                        _ => return Err(make_err("@language must be a string or null".to_owned())),
+                    };
+                }
+                let mut terms: Vec<&String> = map.keys().collect();
+                terms.sort();
+                for term in terms {
+                    if !term.starts_with('@') {
+                        define(map, term, &mut result, &mut vec![])?;
+                    }
+                }
+                return Ok(result);
+            }
+            _ => return Err(make_err("Invalid local context".to_owned())),
+        }
+    }
+
+    fn load(&mut self, url: &str) -> Result<JSONValue, JSONLDError> {
+        if let Some(document) = self.remote.get(url) {
+            return Ok(document.clone());
+        }
+        let document = match self.loader {
+            Some(ref mut loader) => loader(url)?,
+            None => {
+                return Err(make_err(format!(
+                    "Remote context {} can't be loaded without a loader",
+                    url
+                )))
+            }
+        };
+        self.remote.insert(url.to_owned(), document.clone());
+        return Ok(document);
+    }
+
+    // Expansion of one element, `None` property meaning the top level
+    fn expand_element(
+        &mut self,
+        active: &Context,
+        property: Option<&str>,
+        element: &JSONValue,
+    ) -> Result<JSONValue, JSONLDError> {
+        let map = match *element {
+            JSONValue::JSONNull() => return Ok(JSONValue::JSONNull()),
+            JSONValue::JSONArray(ref items) => {
+                let list = container_of(active, property) == Some("@list");
+                let mut result = vec![];
+                for item in items {
+                    match self.expand_element(active, property, item)? {
+                        JSONValue::JSONNull() => (),
+                        JSONValue::JSONArray(_) if list => {
+                            return Err(make_err("Lists of lists are not supported".to_owned()))
+                        }
+                        JSONValue::JSONArray(items) => result.extend(items),
+                        item => result.push(Box::new(item)),
+                    }
+                }
+                return Ok(JSONValue::JSONArray(result));
+            }
+            JSONValue::JSONObject(ref map) => map,
+            JSONValue::JSONRaw(_) => {
+                return self.expand_element(active, property, &element.expand_raw())
+            }
+            _ => match property {
+                None | Some("@graph") => return Ok(JSONValue::JSONNull()),
+                Some(property) => return Ok(expand_value(active, property, element)),
+            },
+        };
+        let local;
+        let active = match map.get(CONTEXT) {
+            Some(context) => {
+                local = self.process(active, context, 0)?;
+                &local
+            }
+            None => active,
+        };
+        let mut result = JSONMap::new();
+        let mut keys: Vec<&String> = map.keys().collect();
+        keys.sort();
+        for key in keys {
+            let value = &*map[key];
+            let expanded = match expand_iri(active, key, true) {
+                Some(ref iri) if iri == CONTEXT => continue,
+                Some(iri) => iri,
+                None => continue,
+            };
+            if expanded.starts_with('@') {
+                let value = match expanded.as_str() {
+                    "@id" => match *value {
+                        JSONValue::JSONString(ref id) => {
+                            string(&expand_iri(active, id, false).unwrap_or_else(|| id.clone()))
+                        }
+                        _ => return Err(make_err("@id must be a string".to_owned())),
+                    },
+                    "@type" => expand_types(active, value)?,
+                    "@value" => match *value {
+                        JSONValue::JSONArray(_) | JSONValue::JSONObject(_) => {
+                            return Err(make_err("@value must be a scalar".to_owned()))
+                        }
+                        _ => value.clone(),
+                    },
+                    "@language" => match *value {
+                        JSONValue::JSONString(ref language) => string(&language.to_lowercase()),
+                        _ => return Err(make_err("@language must be a string".to_owned())),
+                    },
+                    "@list" => array(self.expand_element(active, property, value)?),
+                    "@set" => self.expand_element(active, property, value)?,
+                    "@graph" => array(self.expand_element(active, Some("@graph"), value)?),
+                    "@index" => value.clone(),
+                    _ => continue,
+                };
+                result.insert(expanded, Box::new(value));
+                continue;
+            }
+            if !expanded.contains(':') {
+                continue;
+            }
+            let mut value = self.expand_element(active, Some(key), value)?;
+            if value == JSONValue::JSONNull() {
+                continue;
+            }
+            if container_of(active, Some(key)) == Some("@list") && !is_list(&value) {
+                let mut list = JSONMap::new();
+                list.insert("@list".to_owned(), Box::new(array(value)));
+                value = JSONValue::JSONObject(list);
+            }
+            if !result.contains_key(&expanded) {
+                result.insert(expanded.clone(), Box::new(JSONValue::JSONArray(vec![])));
+            }
+            if let Some(JSONValue::JSONArray(values)) = result.get_mut(&expanded).map(|v| &mut **v)
+            {
+                match value {
+                    JSONValue::JSONArray(items) => values.extend(items),
+                    value => values.push(Box::new(value)),
+                }
+            }
+        }
+        if let Some(value) = result.get("@value") {
+            if **value == JSONValue::JSONNull() {
+                return Ok(JSONValue::JSONNull());
+            }
+            if let Some(JSONValue::JSONArray(mut types)) = result.remove("@type").map(|t| *t) {
+                if types.len() != 1 {
+                    return Err(make_err("@type of a value must be a single IRI".to_owned()));
+                }
+                result.insert("@type".to_owned(), types.remove(0));
+            }
+            return Ok(JSONValue::JSONObject(result));
+        }
+        if let Some(set) = result.remove("@set") {
+            return Ok(*set);
+        }
+        let keys: Vec<&str> = result.keys().map(|key| key.as_str()).collect();
+        let top = property.is_none() || property == Some("@graph");
+        if keys == ["@language"] || (top && (keys.is_empty() || keys == ["@id"])) {
+            return Ok(JSONValue::JSONNull());
+        }
+        return Ok(JSONValue::JSONObject(result));
+    }
+}
+
+impl<'a> Default for JSONLDProcessor<'a> {
+    fn default() -> JSONLDProcessor<'a> {
+        return JSONLDProcessor::new();
+    }
+}
+
+// The context in a `{"@context": ...}` document, or the value itself
+fn inner_context(value: &JSONValue) -> &JSONValue {
+    match *value {
+        JSONValue::JSONObject(ref map) if map.contains_key(CONTEXT) => return &map[CONTEXT],
+        _ => return value,
+    }
+}
+
+// Defines the terms of a local context `value` depends on, e.g. the prefix of
+// a compact IRI, before it's expanded
+fn define_prefixes(
+    local: &JSONMap,
+    value: &str,
+    result: &mut Context,
+    defining: &mut Vec<String>,
+) -> Result<(), JSONLDError> {
+    let prefix = match value.split_once(':') {
+        Some((prefix, suffix)) if !suffix.starts_with("//") => prefix,
+        Some(_) => return Ok(()),
+        None => value,
+    };
+    if !prefix.starts_with('@') && local.contains_key(prefix) {
+        define(local, prefix, result, defining)?;
+    }
+    return Ok(());
+}
+
+// Creates a term definition from a local context
+fn define(
+    local: &JSONMap,
+    term: &str,
+    result: &mut Context,
+    defining: &mut Vec<String>,
+) -> Result<(), JSONLDError> {
+    if defining.iter().any(|t| t == term) {
+        return Err(make_err(format!("Cyclic IRI mapping of {}", term)));
+    }
+    defining.push(term.to_owned());
+    let definition = match *local[term] {
+        JSONValue::JSONNull() => None,
+        JSONValue::JSONString(ref id) => {
+            let mut map = JSONMap::new();
+            map.insert("@id".to_owned(), Box::new(string(id)));
+            Some(map)
+        }
+        JSONValue::JSONObject(ref map) => Some(map.clone()),
+        _ => return Err(make_err(format!("Invalid definition of {}", term))),
+    };
+    let definition = match definition {
+        Some(definition) => definition,
+        None => {
+            result.terms.insert(term.to_owned(), None);
+            defining.pop();
+            return Ok(());
+        }
+    };
+    let member = |name: &str| -> Result<Option<&String>, JSONLDError> {
+        match definition.get(name).map(|value| &**value) {
+            None => return Ok(None),
+            Some(JSONValue::JSONString(value)) => return Ok(Some(value)),
+            Some(_) => return Err(make_err(format!("{} of {} must be a string", name, term))),
+        }
+    };
+    if definition.contains_key("@reverse") {
+        return Err(make_err(format!(
+            "Reverse property {} is not supported",
+            term
+        )));
+    }
+    let iri = match member("@id")? {
+        Some(id) if id != term => {
+            define_prefixes(local, id, result, defining)?;
+            expand_iri(result, id, true).unwrap_or_else(|| id.clone())
+        }
+        _ => {
+            if let Some((prefix, _)) = term.split_once(':') {
+                if local.contains_key(prefix) && prefix != term {
+                    define(local, prefix, result, defining)?;
+                }
+            }
+            match expand_iri(result, term, true) {
+                Some(ref iri) if iri.contains(':') => iri.clone(),
+                _ => return Err(make_err(format!("Term {} has no IRI", term))),
+            }
+        }
+    };
+    let kind = match member("@type")? {
+        Some(kind) if kind == "@id" || kind == "@vocab" => Some(kind.clone()),
+        Some(kind) => {
+            define_prefixes(local, kind, result, defining)?;
+            Some(expand_iri(result, kind, true).unwrap_or_else(|| kind.clone()))
+        }
+        None => None,
+    };
+    let container = match member("@container")? {
+        Some(container) if container == "@list" || container == "@set" => Some(container.clone()),
+        Some(container) => {
+            return Err(make_err(format!(
+                "Container {} is not supported",
+                container
+            )))
+        }
+        None => None,
+    };
+    result.terms.insert(
+        term.to_owned(),
+        Some(Term {
+            iri,
+            kind,
+            container,
+        }),
+    );
+    defining.pop();
+    return Ok(());
+}
+
+// IRI expansion. `vocab` for properties and types, which terms and `@vocab`
+// apply to; `None` for terms mapped to null and words no mapping applies to.
+fn expand_iri(active: &Context, value: &str, vocab: bool) -> Option<String> {
+    if value.starts_with('@') {
+        if KEYWORDS.contains(&value) {
+            return Some(value.to_owned());
+        }
+        return None;
+    }
+    if vocab {
+        if let Some(term) = active.terms.get(value) {
+            return term.as_ref().map(|term| term.iri.clone());
+        }
+    }
+    if let Some((prefix, suffix)) = value.split_once(':') {
+        if prefix == "_" || suffix.starts_with("//") {
+            return Some(value.to_owned());
+        }
+        if let Some(Some(term)) = active.terms.get(prefix) {
+            return Some(format!("{}{}", term.iri, suffix));
+        }
+        return Some(value.to_owned());
+    }
+    if vocab {
+        return active
+            .vocab
+            .as_ref()
+            .map(|base| format!("{}{}", base, value));
+    }
+    return Some(value.to_owned());
+}
+
+fn expand_types(active: &Context, value: &JSONValue) -> Result<JSONValue, JSONLDError> {
+    let expand = |value: &JSONValue| match *value {
+        JSONValue::JSONString(ref name) => Ok(string(
+            &expand_iri(active, name, true).unwrap_or_else(|| name.clone()),
+        )),
+        _ => Err(make_err(
+            "@type must be a string or an array of strings".to_owned(),
+        )),
+    };
+    match *value {
+        JSONValue::JSONArray(ref items) => {
+            let types: Result<Vec<Box<JSONValue>>, JSONLDError> = items
+                .iter()
+                .map(|item| expand(item).map(Box::new))
+                .collect();
+            return Ok(JSONValue::JSONArray(types?));
+        }
+        _ => return Ok(JSONValue::JSONArray(vec![Box::new(expand(value)?)])),
+    }
+}
+
+// A scalar under `property` as a value object, or a node reference for
+// properties coerced to `@id` or `@vocab`
+fn expand_value(active: &Context, property: &str, value: &JSONValue) -> JSONValue {
+    let kind = term_of(active, Some(property)).and_then(|term| term.kind.as_ref());
+    let mut result = JSONMap::new();
+    match (kind.map(|kind| kind.as_str()), value) {
+        (Some("@id"), JSONValue::JSONString(id)) | (Some("@vocab"), JSONValue::JSONString(id)) => {
+            let vocab = kind.map(|kind| kind.as_str()) == Some("@vocab");
+            let iri = expand_iri(active, id, vocab).unwrap_or_else(|| id.clone());
+            result.insert("@id".to_owned(), Box::new(string(&iri)));
+            return JSONValue::JSONObject(result);
+        }
+        (Some(datatype), _) if !datatype.starts_with('@') => {
+            result.insert("@type".to_owned(), Box::new(string(datatype)));
+        }
+        (_, JSONValue::JSONString(_)) => {
+            if let Some(ref language) = active.language {
+                result.insert("@language".to_owned(), Box::new(string(language)));
+            }
+        }
+        _ => (),
+    }
+    result.insert("@value".to_owned(), Box::new(value.clone()));
+    return JSONValue::JSONObject(result);
+}
+
+// Compaction of one expanded element, `None` property meaning the top level
+fn compact_element(active: &Context, property: Option<&str>, element: &JSONValue) -> JSONValue {
+    let map = match *element {
+        JSONValue::JSONArray(ref items) => {
+            let mut result: Vec<Box<JSONValue>> = items
+                .iter()
+                .map(|item| compact_element(active, property, item))
+                .filter(|item| *item != JSONValue::JSONNull())
+                .map(Box::new)
+                .collect();
+            let container = container_of(active, property);
+            // a term with a `@list` container holds the single list itself
+            if result.len() == 1 && container != Some("@set") {
+                return *result.remove(0);
+            }
+            return JSONValue::JSONArray(result);
+        }
+        JSONValue::JSONObject(ref map) => map,
+        _ => return element.clone(),
+    };
+    if let Some(value) = compact_value(active, property, map) {
+        return value;
+    }
+    if let Some(list) = map.get("@list") {
+        let items = match compact_element(active, None, list) {
+            JSONValue::JSONArray(items) => JSONValue::JSONArray(items),
+            item => JSONValue::JSONArray(vec![Box::new(item)]),
+        };
+        if container_of(active, property) == Some("@list") {
+            return items;
+        }
+        let mut result = JSONMap::new();
+        result.insert("@list".to_owned(), Box::new(items));
+        return JSONValue::JSONObject(result);
+    }
+    let mut result = JSONMap::new();
+    let mut keys: Vec<&String> = map.keys().collect();
+    keys.sort();
+    for key in keys {
+        let value = &*map[key];
+        match key.as_str() {
+            "@id" => {
+                if let JSONValue::JSONString(ref id) = *value {
+                    result.insert(
+                        key.clone(),
+                        Box::new(string(&compact_iri(active, id, false))),
+                    );
+                }
+            }
+            "@type" => {
+                let compacted = match *value {
+                    JSONValue::JSONString(ref name) => string(&compact_iri(active, name, true)),
+                    JSONValue::JSONArray(ref names) => {
+                        let mut names: Vec<Box<JSONValue>> = names
+                            .iter()
+                            .map(|name| match **name {
+                                JSONValue::JSONString(ref name) => {
+                                    Box::new(string(&compact_iri(active, name, true)))
+                                }
+                                ref other => Box::new(other.clone()),
+                            })
+                            .collect();
+                        if names.len() == 1 {
+                            *names.remove(0)
+                        } else {
+                            JSONValue::JSONArray(names)
+                        }
+                    }
+                    ref other => other.clone(),
+                };
+                result.insert(key.clone(), Box::new(compacted));
+            }
+            "@graph" => {
+                let graph = array(compact_element(active, Some("@graph"), value));
+                result.insert(key.clone(), Box::new(graph));
+            }
+            _ if key.starts_with('@') => {
+                result.insert(key.clone(), Box::new(value.clone()));
+            }
+            _ => {
+                let items = match *value {
+                    JSONValue::JSONArray(ref items) => items.iter().map(|item| &**item).collect(),
+                    ref item => vec![item],
+                };
+                // items go to the term that fits them best, so one property may
+                // end up under several terms
+                let mut grouped: Vec<(String, Vec<&JSONValue>)> = vec![];
+                for item in items {
+                    let term = select_term(active, key, item);
+                    match grouped.iter_mut().find(|group| group.0 == term) {
+                        Some(group) => group.1.push(item),
+                        None => grouped.push((term, vec![item])),
+                    }
+                }
+                for (term, items) in grouped {
+                    let items = JSONValue::JSONArray(
+                        items
+                            .into_iter()
+                            .map(|item| Box::new(item.clone()))
+                            .collect(),
+                    );
+                    let compacted = compact_element(active, Some(&term), &items);
+                    result.insert(term, Box::new(compacted));
+                }
+            }
+        }
+    }
+    return JSONValue::JSONObject(result);
+}
+
+// A value object or node reference as a plain scalar, if the term's
+// definition says what it is
+fn compact_value(active: &Context, property: Option<&str>, map: &JSONMap) -> Option<JSONValue> {
+    let term = term_of(active, property);
+    let kind = term.and_then(|term| term.kind.as_deref());
+    if map.len() == 1 {
+        if let Some(JSONValue::JSONString(id)) = map.get("@id").map(|id| &**id) {
+            match kind {
+                Some("@id") => return Some(string(&compact_iri(active, id, false))),
+                Some("@vocab") => return Some(string(&compact_iri(active, id, true))),
+                _ => return None,
+            }
+        }
+    }
+    let value = map.get("@value")?;
+    let datatype = map.get("@type").map(|t| &**t);
+    let language = map.get("@language").map(|l| &**l);
+    let plain = match (datatype, language) {
+        (Some(JSONValue::JSONString(datatype)), None) => kind == Some(datatype.as_str()),
+        (None, Some(JSONValue::JSONString(language))) => {
+            kind.is_none() && active.language.as_deref() == Some(language.as_str())
+        }
+        (None, None) => {
+            let string = matches!(**value, JSONValue::JSONString(_));
+            kind.is_none_or(|kind| kind.starts_with('@')) && !(string && active.language.is_some())
+        }
+        _ => false,
+    };
+    if plain && map.len() <= 2 {
+        return Some((**value).clone());
+    }
+    if map.len() == 1 {
+        return None;
+    }
+    let mut result = JSONMap::new();
+    for (key, value) in map.iter() {
+        let value = match (key.as_str(), &**value) {
+            ("@type", JSONValue::JSONString(datatype)) => {
+                string(&compact_iri(active, datatype, true))
+            }
+            (_, value) => value.clone(),
+        };
+        result.insert(key.clone(), Box::new(value));
+    }
+    return Some(JSONValue::JSONObject(result));
+}
+
+// The term to compact `iri` to for `item`, preferring one whose type coercion
+// and container fit the item, so it compacts to a plain value
+fn select_term(active: &Context, iri: &str, item: &JSONValue) -> String {
+    let (kind, list) = match *item {
+        JSONValue::JSONObject(ref map) if map.contains_key("@list") => (None, true),
+        JSONValue::JSONObject(ref map) if map.len() == 1 && map.contains_key("@id") => {
+            (Some("@id"), false)
+        }
+        JSONValue::JSONObject(ref map) => match map.get("@type").map(|t| &**t) {
+            Some(JSONValue::JSONString(datatype)) if map.contains_key("@value") => {
+                (Some(datatype.as_str()), false)
+            }
+            _ => (None, false),
+        },
+        _ => (None, false),
+    };
+    let mut candidates: Vec<(&String, &Term)> = active
+        .terms
+        .iter()
+        .filter_map(|(name, term)| term.as_ref().map(|term| (name, term)))
+        .filter(|&(_, term)| term.iri == iri)
+        .collect();
+    candidates.sort_by_key(|&(name, _)| (name.len(), name.clone()));
+    let fits = |term: &Term| {
+        let term_list = term.container.as_deref() == Some("@list");
+        let term_kind = match term.kind.as_deref() {
+            Some("@vocab") => Some("@id"),
+            kind => kind,
+        };
+        return term_list == list && term_kind == kind;
+    };
+    let generic = |term: &Term| term.kind.is_none() && term.container.as_deref() != Some("@list");
+    if let Some(&(name, _)) = candidates
+        .iter()
+        .find(|&&(_, term)| fits(term))
+        .or_else(|| candidates.iter().find(|&&(_, term)| !list && generic(term)))
+    {
+        return name.clone();
+    }
+    return compact_iri(active, iri, true);
+}
+
+// Shortens an IRI to a term (for `vocab` positions only), a `@vocab`-relative
+// word or a compact IRI with the shortest prefix
+fn compact_iri(active: &Context, iri: &str, vocab: bool) -> String {
+    let defined = |name: &str| active.terms.contains_key(name);
+    if vocab {
+        let mut exact: Vec<&String> = active
+            .terms
+            .iter()
+            .filter(|&(_, term)| match *term {
+                Some(ref term) => {
+                    term.iri == iri && term.kind.is_none() && term.container.is_none()
+                }
+                None => false,
+            })
+            .map(|(name, _)| name)
+            .collect();
+        exact.sort_by_key(|name| (name.len(), (*name).clone()));
+        if let Some(name) = exact.first() {
+            return (*name).clone();
+        }
+        if let Some(ref base) = active.vocab {
+            if let Some(suffix) = iri.strip_prefix(base.as_str()) {
+                if !suffix.is_empty() && !suffix.contains(':') && !defined(suffix) {
+                    return suffix.to_owned();
+                }
+            }
+        }
+    }
+    let mut best: Option<String> = None;
+    for (name, term) in active.terms.iter() {
+        let term = match *term {
+            Some(ref term) if !name.contains(':') => term,
+            _ => continue,
+        };
+        let suffix = match iri.strip_prefix(term.iri.as_str()) {
+            Some(suffix) if !suffix.is_empty() => suffix,
+            _ => continue,
+        };
+        let candidate = format!("{}:{}", name, suffix);
+        let better = match best {
+            Some(ref best) => (candidate.len(), &candidate) < (best.len(), best),
+            None => true,
+        };
+        if better && !defined(&candidate) {
+            best = Some(candidate);
+        }
+    }
+    return best.unwrap_or_else(|| iri.to_owned());
+}
+
+fn term_of<'c>(active: &'c Context, property: Option<&str>) -> Option<&'c Term> {
+    return active.terms.get(property?).and_then(|term| term.as_ref());
+}
+
+fn container_of<'c>(active: &'c Context, property: Option<&str>) -> Option<&'c str> {
+    return term_of(active, property).and_then(|term| term.container.as_deref());
+}
+
+fn is_list(value: &JSONValue) -> bool {
+    match *value {
+        JSONValue::JSONObject(ref map) => return map.contains_key("@list"),
+        _ => return false,
+    }
+}
+
+fn array(value: JSONValue) -> JSONValue {
+    match value {
+        JSONValue::JSONArray(_) => return value,
+        JSONValue::JSONNull() => return JSONValue::JSONArray(vec![]),
+        value => return JSONValue::JSONArray(vec![Box::new(value)]),
+    }
+}
+
+fn string(s: &str) -> JSONValue {
+    return JSONValue::JSONString(s.to_owned());
+}
+
+fn make_err(s: String) -> JSONLDError {
+    JSONLDError { reason: s }
+}
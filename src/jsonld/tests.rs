@@ -0,0 +1,162 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+const CONTEXT: &str = r#"{
+    "@vocab": "http://schema.org/",
+    "xsd": "http://www.w3.org/2001/XMLSchema#",
+    "foaf": "http://xmlns.com/foaf/0.1/",
+    "homepage": {"@id": "foaf:homepage", "@type": "@id"},
+    "born": {"@id": "birthDate", "@type": "xsd:date"},
+    "tags": {"@id": "keywords", "@container": "@list"},
+    "ignored": null
+}"#;
+
+#[test]
+fn test_expand() {
+    for (document, expected) in vec![
+        (
+            r#"{"@context": CONTEXT, "@id": "http://ex.org/jane", "@type": "Person", "name": "Jane",
+                "homepage": "http://jane.org/", "born": "1990-01-02", "tags": ["a", "b"], "ignored": 1}"#,
+            r#"[{"@id": "http://ex.org/jane", "@type": ["http://schema.org/Person"],
+                "http://schema.org/name": [{"@value": "Jane"}],
+                "http://xmlns.com/foaf/0.1/homepage": [{"@id": "http://jane.org/"}],
+                "http://schema.org/birthDate": [{"@value": "1990-01-02", "@type": "http://www.w3.org/2001/XMLSchema#date"}],
+                "http://schema.org/keywords": [{"@list": [{"@value": "a"}, {"@value": "b"}]}]}]"#,
+        ),
+        (
+            r#"{"@context": {"@language": "EN", "name": "http://schema.org/name"}, "name": "x", "other": 1}"#,
+            r#"[{"http://schema.org/name": [{"@value": "x", "@language": "en"}]}]"#,
+        ),
+        (
+            r#"{"@context": CONTEXT, "@graph": [{"@id": "a", "name": "A"}, {"@id": "b"}, {"name": null}]}"#,
+            r#"[{"@id": "a", "http://schema.org/name": [{"@value": "A"}]}]"#,
+        ),
+        (r#"{"name": "no context"}"#, r#"[]"#),
+        (
+            r#"{"@context": CONTEXT, "foaf:knows": {"@id": "http://ex.org/bob"}, "http://ex.org/p": [1, {"@value": true}]}"#,
+            r#"[{"http://ex.org/p": [{"@value": 1}, {"@value": true}],
+                "http://xmlns.com/foaf/0.1/knows": [{"@id": "http://ex.org/bob"}]}]"#,
+        ),
+    ] {
+        println!("Checking {}", document);
+        let document = parse(&document.replace("CONTEXT", CONTEXT));
+        let result = JSONLDProcessor::new().expand(&document).unwrap();
+        assert_eq!(result, parse(expected));
+    }
+}
+
+#[test]
+fn test_compact() {
+    for (document, expected) in vec![
+        (
+            r#"[{"@id": "http://ex.org/jane", "@type": ["http://schema.org/Person"],
+                "http://schema.org/name": [{"@value": "Jane"}],
+                "http://xmlns.com/foaf/0.1/homepage": [{"@id": "http://jane.org/"}],
+                "http://xmlns.com/foaf/0.1/nick": [{"@value": "jj"}, {"@value": "jd"}],
+                "http://schema.org/birthDate": [{"@value": "1990-01-02", "@type": "http://www.w3.org/2001/XMLSchema#date"}],
+                "http://schema.org/keywords": [{"@list": [{"@value": "a"}]}]}]"#,
+            r#"{"@id": "http://ex.org/jane", "@type": "Person", "name": "Jane",
+                "homepage": "http://jane.org/", "foaf:nick": ["jj", "jd"], "born": "1990-01-02", "tags": ["a"]}"#,
+        ),
+        (
+            r#"[{"@id": "a", "http://schema.org/birthDate": [{"@value": "soon"}]}, {"@id": "b", "http://ex.org/p": [{"@id": "c"}]}]"#,
+            r#"{"@graph": [{"@id": "a", "birthDate": "soon"}, {"@id": "b", "http://ex.org/p": {"@id": "c"}}]}"#,
+        ),
+    ] {
+        println!("Checking {}", document);
+        let mut result = JSONLDProcessor::new()
+            .compact(&parse(document), &parse(CONTEXT))
+            .unwrap();
+        if let JSONValue::JSONObject(ref mut map) = result {
+            assert_eq!(*map.remove("@context").unwrap(), parse(CONTEXT));
+        }
+        assert_eq!(result, parse(expected));
+        // compaction loses nothing
+        let expanded = JSONLDProcessor::new().expand(&parse(document)).unwrap();
+        let compacted = JSONLDProcessor::new()
+            .compact(&parse(document), &parse(CONTEXT))
+            .unwrap();
+        assert_eq!(JSONLDProcessor::new().expand(&compacted).unwrap(), expanded);
+    }
+}
+
+#[test]
+fn test_remote_context() {
+    let document = parse(r#"{"@context": "http://ex.org/context", "name": "x"}"#);
+    let err = JSONLDProcessor::new().expand(&document).unwrap_err();
+    assert_eq!(
+        err.reason,
+        "Remote context http://ex.org/context can't be loaded without a loader"
+    );
+
+    let mut loads = 0;
+    let mut processor = JSONLDProcessor::with_loader(|url: &str| {
+        loads += 1;
+        match url {
+            "http://ex.org/context" => {
+                return Ok(parse(r#"{"@context": {"@vocab": "http://ex.org/"}}"#))
+            }
+            _ => {
+                return Err(JSONLDError {
+                    reason: format!("No {}", url),
+                })
+            }
+        }
+    });
+    for _ in 0..2 {
+        let result = processor.expand(&document).unwrap();
+        assert_eq!(
+            result,
+            parse(r#"[{"http://ex.org/name": [{"@value": "x"}]}]"#)
+        );
+    }
+    let err = processor
+        .expand(&parse(r#"{"@context": "http://ex.org/other"}"#))
+        .unwrap_err();
+    assert_eq!(err.reason, "No http://ex.org/other");
+    drop(processor);
+    assert_eq!(loads, 2);
+
+    let mut processor = JSONLDProcessor::with_loader(|_: &str| {
+        return Ok(parse(r#"{"@context": "http://ex.org/loop"}"#));
+    });
+    let err = processor
+        .expand(&parse(r#"{"@context": "http://ex.org/loop"}"#))
+        .unwrap_err();
+    assert_eq!(
+        err.reason,
+        "Recursive context inclusion of http://ex.org/loop"
+    );
+}
+
+#[test]
+fn test_errors() {
+    for (context, reason) in vec![
+        (
+            r#"{"a": {"@id": "b:x"}, "b": "a:y"}"#,
+            "Cyclic IRI mapping of a",
+        ),
+        (r#"{"a": {"@type": "@id"}}"#, "Term a has no IRI"),
+        (
+            r#"{"a": {"@id": "http://ex.org/a", "@container": "@index"}}"#,
+            "Container @index is not supported",
+        ),
+        (
+            r#"{"a": {"@reverse": "http://ex.org/a"}}"#,
+            "Reverse property a is not supported",
+        ),
+        (r#"{"@vocab": 1}"#, "@vocab must be a string or null"),
+        (r#"{"a": 1}"#, "Invalid definition of a"),
+        (r#"1"#, "Invalid local context"),
+    ] {
+        println!("Checking {}", context);
+        let document = parse(&format!(r#"{{"@context": {}}}"#, context));
+        let err = JSONLDProcessor::new().expand(&document).unwrap_err();
+        assert_eq!(err.reason, reason);
+    }
+}
@@ -0,0 +1,243 @@
+use super::*;
+use std::iter::Peekable;
+use std::str::Chars;
+
+#[cfg(test)]
+mod tests;
+
+const ROOT: char = '$';
+const CHILD: char = '.';
+const WILDCARD: char = '*';
+const BRACKET_START: char = '[';
+const BRACKET_END: char = ']';
+const SLICE: char = ':';
+
+#[derive(Debug, Clone)]
+pub struct JSONPathError {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Name(String),
+    Index(i64),
+    Slice(Option<i64>, Option<i64>),
+    Wildcard,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Step {
+    // `..` selects from the node and all of its descendants instead of direct children
+    recursive: bool,
+    selector: Selector,
+}
+
+/// Compiled JSONPath expression. Supported subset:
+/// `$`, `.name`, `['name']`, `[0]`, `[-1]`, `[1:3]`, `[*]`, `.*` and recursive descent `..`.
+/// Filters and unions aren't supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JSONPath {
+    steps: Vec<Step>,
+}
+
+impl JSONPath {
+    pub fn compile(expression: &str) -> Result<JSONPath, JSONPathError> {
+        let mut chars = expression.chars().peekable();
+        if chars.next() != Some(ROOT) {
+            return Err(make_err(format!("Path {} must start with $", expression)));
+        }
+        let mut steps = vec![];
+        while let Some(ch) = chars.next() {
+            match ch {
+                CHILD => {
+                    let recursive = chars.peek() == Some(&CHILD);
+                    if recursive {
+                        chars.next();
+                    }
+                    let selector = match chars.peek() {
+                        Some(&BRACKET_START) if recursive => {
+                            chars.next();
+                            parse_bracket(&mut chars)?
+                        }
+                        Some(&WILDCARD) => {
+                            chars.next();
+                            Selector::Wildcard
+                        }
+                        _ => Selector::Name(parse_name(&mut chars)?),
+                    };
+                    steps.push(Step {
+                        recursive,
+                        selector,
+                    });
+                }
+                BRACKET_START => steps.push(Step {
+                    recursive: false,
+                    selector: parse_bracket(&mut chars)?,
+                }),
+                _ => return Err(make_err(format!("Unexpected charachter {} in path", ch))),
+            }
+        }
+        return Ok(JSONPath { steps });
+    }
+
+    /// Returns all matching nodes in document order (object members in map iteration order).
+    pub fn select<'a>(&self, document: &'a JSONValue) -> Vec<&'a JSONValue> {
+        let mut current = vec![document];
+        for step in &self.steps {
+            let mut candidates = vec![];
+            for node in current {
+                if step.recursive {
+                    collect_descendants(node, &mut candidates);
+                } else {
+                    candidates.push(node);
+                }
+            }
+            current = vec![];
+            for node in candidates {
+                apply_selector(&step.selector, node, &mut current);
+            }
+        }
+        return current;
+    }
+}
+
+impl FromStr for JSONPath {
+    type Err = JSONPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return JSONPath::compile(s);
+    }
+}
+
+fn parse_name(chars: &mut Peekable<Chars>) -> Result<String, JSONPathError> {
+    let mut name = String::new();
+    while let Some(&ch) = chars.peek() {
+        if ch == CHILD || ch == BRACKET_START {
+            break;
+        }
+        name.push(ch);
+        chars.next();
+    }
+    if name.is_empty() {
+        return Err(make_err("Empty member name in path".to_owned()));
+    }
+    return Ok(name);
+}
+
+// Parses the inside of `[...]`, the opening bracket is already consumed
+fn parse_bracket(chars: &mut Peekable<Chars>) -> Result<Selector, JSONPathError> {
+    let mut content = String::new();
+    let mut quote = None;
+    loop {
+        let ch = chars
+            .next()
+            .ok_or_else(|| make_err("Unclosed [ in path".to_owned()))?;
+        match quote {
+            Some(q) if ch == q => quote = None,
+            Some(_) => (),
+            None if ch == '\'' || ch == '"' => quote = Some(ch),
+            None if ch == BRACKET_END => break,
+            None => (),
+        }
+        content.push(ch);
+    }
+    let content = content.trim();
+    if content == "*" {
+        return Ok(Selector::Wildcard);
+    }
+    if content.len() >= 2 && (content.starts_with('\'') || content.starts_with('"')) {
+        let (first, last) = (&content[..1], &content[content.len() - 1..]);
+        if first == last {
+            return Ok(Selector::Name(content[1..content.len() - 1].to_owned()));
+        }
+    }
+    if let Some(i) = content.find(SLICE) {
+        let start = parse_bound(&content[..i])?;
+        let end = parse_bound(&content[i + 1..])?;
+        return Ok(Selector::Slice(start, end));
+    }
+    return content
+        .parse()
+        .map(Selector::Index)
+        .map_err(|_| make_err(format!("Invalid selector [{}] in path", content)));
+}
+
+fn parse_bound(s: &str) -> Result<Option<i64>, JSONPathError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Ok(None);
+    }
+    return s
+        .parse()
+        .map(Some)
+        .map_err(|_| make_err(format!("Invalid slice bound {} in path", s)));
+}
+
+fn collect_descendants<'a>(value: &'a JSONValue, result: &mut Vec<&'a JSONValue>) {
+    result.push(value);
+    match *value {
+        JSONValue::JSONObject(ref map) => {
+            for item in map.values() {
+                collect_descendants(item, result);
+            }
+        }
+        JSONValue::JSONArray(ref items) => {
+            for item in items {
+                collect_descendants(item, result);
+            }
+        }
+        _ => (),
+    }
+}
+
+fn apply_selector<'a>(selector: &Selector, value: &'a JSONValue, result: &mut Vec<&'a JSONValue>) {
+    match (selector, value) {
+        (Selector::Name(name), JSONValue::JSONObject(map)) => {
+            if let Some(item) = map.get(name) {
+                result.push(item);
+            }
+        }
+        (Selector::Wildcard, JSONValue::JSONObject(map)) => {
+            result.extend(map.values().map(|item| &**item));
+        }
+        (Selector::Wildcard, JSONValue::JSONArray(items)) => {
+            result.extend(items.iter().map(|item| &**item));
+        }
+        (&Selector::Index(index), JSONValue::JSONArray(items)) => {
+            if let Some(i) = normalize_index(index, items.len()) {
+                if i < items.len() {
+                    result.push(&items[i]);
+                }
+            }
+        }
+        (&Selector::Slice(start, end), JSONValue::JSONArray(items)) => {
+            let len = items.len();
+            let start = start.map_or(0, |i| clamp_index(i, len));
+            let end = end.map_or(len, |i| clamp_index(i, len));
+            if start < end {
+                result.extend(items[start..end].iter().map(|item| &**item));
+            }
+        }
+        _ => (),
+    }
+}
+
+// Negative indices count from the end of the array
+fn normalize_index(index: i64, len: usize) -> Option<usize> {
+    if index >= 0 {
+        return Some(index as usize);
+    }
+    let from_end = index.unsigned_abs() as usize;
+    if from_end > len {
+        return None;
+    }
+    return Some(len - from_end);
+}
+
+fn clamp_index(index: i64, len: usize) -> usize {
+    return normalize_index(index, len).unwrap_or(0).min(len);
+}
+
+fn make_err(s: String) -> JSONPathError {
+    JSONPathError { reason: s }
+}
@@ -0,0 +1,71 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn doc() -> JSONValue {
+    return r#"{
+        "store": {
+            "books": [
+                {"title": "A", "price": 10},
+                {"title": "B", "price": 20},
+                {"title": "C", "price": 30, "tags": ["x"]}
+            ],
+            "name": "shop"
+        },
+        "odd key": 1
+    }"#
+    .parse()
+    .unwrap();
+}
+
+fn select(expression: &str) -> Vec<JSONValue> {
+    let doc = doc();
+    return JSONPath::compile(expression)
+        .unwrap()
+        .select(&doc)
+        .into_iter()
+        .cloned()
+        .collect();
+}
+
+fn strings(values: &[&str]) -> Vec<JSONValue> {
+    return values
+        .iter()
+        .map(|s| JSONValue::JSONString(s.to_string()))
+        .collect();
+}
+
+#[test]
+fn test_select() {
+    for s in vec![
+        ("$.store.name", strings(&["shop"])),
+        ("$['store'][\"name\"]", strings(&["shop"])),
+        ("$.store.books[*].title", strings(&["A", "B", "C"])),
+        ("$.store.books.*.title", strings(&["A", "B", "C"])),
+        ("$.store.books[0].title", strings(&["A"])),
+        ("$.store.books[-1].title", strings(&["C"])),
+        ("$.store.books[1:].title", strings(&["B", "C"])),
+        ("$.store.books[:-1].title", strings(&["A", "B"])),
+        ("$.store.books[5:9].title", strings(&[])),
+        ("$..title", strings(&["A", "B", "C"])),
+        ("$..tags[0]", strings(&["x"])),
+        ("$..['title']", strings(&["A", "B", "C"])),
+        ("$['odd key']", vec![JSONValue::JSONNumber(1.0)]),
+        ("$.missing.title", strings(&[])),
+        ("$.store.books[-4]", strings(&[])),
+    ] {
+        println!("Checking {}", s.0);
+        let mut found = select(s.0);
+        // recursive descent visits object members in map order
+        found.sort_by(|a, b| format!("{:?}", a).cmp(&format!("{:?}", b)));
+        assert_eq!(found, s.1);
+    }
+    assert_eq!(select("$"), vec![doc()]);
+}
+
+#[test]
+fn test_invalid_paths() {
+    for s in vec!["store", "$.", "$.store[", "$.store[abc]", "$[1:x]", "$x"] {
+        JSONPath::compile(s).expect_err(&format!("Path {} should not compile", s));
+    }
+}
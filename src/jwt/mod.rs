@@ -0,0 +1,172 @@
+use super::*;
+use encoding::base64url_decode;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JSONJWTError {
+    pub reason: String,
+}
+
+/// A JWT (RFC 7519) split into its parts, header and claims parsed.
+/// The signature is NOT verified: check `signature` over `signing_input` with
+/// the algorithm from `algorithm()` before trusting any claim. Encrypted
+/// tokens (JWE, five parts) aren't supported. Tokens come from clients, so
+/// header and claims are parsed with `ParserOptions::untrusted()` limits.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnverifiedJWT {
+    pub header: JSONValue,
+    pub claims: JSONValue,
+    pub signature: Vec<u8>,
+    /// `header.payload` as in the token, what the signature is computed over
+    pub signing_input: String,
+}
+
+impl UnverifiedJWT {
+    pub fn decode(token: &str) -> Result<UnverifiedJWT, JSONJWTError> {
+        let parts: Vec<&str> = token.trim().split('.').collect();
+        if parts.len() != 3 {
+            return Err(make_err(format!(
+                "Expected 3 dot-separated parts, got {}",
+                parts.len()
+            )));
+        }
+        let header = decode_part(parts[0], "header")?;
+        let claims = decode_part(parts[1], "claims")?;
+        let signature = match base64url_decode(parts[2]) {
+            Some(signature) => signature,
+            None => return Err(make_err("Invalid base64url in signature".to_owned())),
+        };
+        return Ok(UnverifiedJWT {
+            header,
+            claims,
+            signature,
+            signing_input: format!("{}.{}", parts[0], parts[1]),
+        });
+    }
+
+    /// `alg` of the header, e.g. `HS256`
+    pub fn algorithm(&self) -> Option<&str> {
+        return string_member(&self.header, "alg");
+    }
+
+    /// `kid` of the header
+    pub fn key_id(&self) -> Option<&str> {
+        return string_member(&self.header, "kid");
+    }
+
+    pub fn claim(&self, name: &str) -> Option<&JSONValue> {
+        match self.claims {
+            JSONValue::JSONObject(ref map) => return map.get(name).map(|value| &**value),
+            _ => return None,
+        }
+    }
+
+    pub fn issuer(&self) -> Option<&str> {
+        return string_member(&self.claims, "iss");
+    }
+
+    pub fn subject(&self) -> Option<&str> {
+        return string_member(&self.claims, "sub");
+    }
+
+    pub fn jwt_id(&self) -> Option<&str> {
+        return string_member(&self.claims, "jti");
+    }
+
+    /// `aud`, which is either one string or an array of them
+    pub fn audience(&self) -> Vec<&str> {
+        match self.claim("aud") {
+            Some(JSONValue::JSONString(audience)) => return vec![audience],
+            Some(JSONValue::JSONArray(audiences)) => {
+                return audiences
+                    .iter()
+                    .filter_map(|audience| match **audience {
+                        JSONValue::JSONString(ref audience) => Some(audience.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+            }
+            _ => return vec![],
+        }
+    }
+
+    /// `exp` in seconds since the epoch
+    pub fn expires_at(&self) -> Option<f64> {
+        return self.numeric_date("exp");
+    }
+
+    /// `nbf` in seconds since the epoch
+    pub fn not_before(&self) -> Option<f64> {
+        return self.numeric_date("nbf");
+    }
+
+    /// `iat` in seconds since the epoch
+    pub fn issued_at(&self) -> Option<f64> {
+        return self.numeric_date("iat");
+    }
+
+    /// Whether `now` (seconds since the epoch) is before `exp` and not before
+    /// `nbf`, allowing `leeway` seconds of clock skew either way. Missing
+    /// claims don't limit the time.
+    pub fn is_valid_at(&self, now: f64, leeway: f64) -> bool {
+        if let Some(expires_at) = self.expires_at() {
+            if now >= expires_at + leeway {
+                return false;
+            }
+        }
+        if let Some(not_before) = self.not_before() {
+            if now < not_before - leeway {
+                return false;
+            }
+        }
+        return true;
+    }
+
+    fn numeric_date(&self, name: &str) -> Option<f64> {
+        match self.claim(name) {
+            Some(JSONValue::JSONNumber(seconds)) => return Some(*seconds),
+            _ => return None,
+        }
+    }
+}
+
+// Header and claims must both be JSON objects
+fn decode_part(part: &str, name: &str) -> Result<JSONValue, JSONJWTError> {
+    let bytes = match base64url_decode(part) {
+        Some(bytes) => bytes,
+        None => return Err(make_err(format!("Invalid base64url in {}", name))),
+    };
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(_) => return Err(make_err(format!("Invalid UTF-8 in {}", name))),
+    };
+    let value = match JSONValue::parse_with(&text, &ParserOptions::untrusted()) {
+        Ok(value) => value,
+        Err(err) => {
+            return Err(make_err(format!(
+                "Invalid JSON in {}: {}",
+                name, err.reason
+            )))
+        }
+    };
+    match value {
+        JSONValue::JSONObject(_) => return Ok(value),
+        _ => return Err(make_err(format!("Expected {} to be an object", name))),
+    }
+}
+
+fn string_member<'a>(value: &'a JSONValue, name: &str) -> Option<&'a str> {
+    match *value {
+        JSONValue::JSONObject(ref map) => match map.get(name).map(|value| &**value) {
+            Some(JSONValue::JSONString(value)) => return Some(value),
+            _ => return None,
+        },
+        _ => return None,
+    }
+}
+
+fn make_err(s: String) -> JSONJWTError {
+    JSONJWTError { reason: s }
+}
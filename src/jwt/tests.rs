@@ -0,0 +1,71 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use encoding::base64url_encode;
+
+const TOKEN: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCIsImtpZCI6ImsxIn0.eyJpc3MiOiJhdXRoIiwic3ViIjoiNDIiLCJhdWQiOlsiYXBpIiwid2ViIl0sImV4cCI6MjAwMCwibmJmIjoxMDAwLCJpYXQiOjEwMDAsIm5hbWUiOiJKw7xyZ2VuIn0.AQL_";
+
+#[test]
+fn test_decode() {
+    let jwt = UnverifiedJWT::decode(TOKEN).unwrap();
+    assert_eq!(jwt.algorithm(), Some("HS256"));
+    assert_eq!(jwt.key_id(), Some("k1"));
+    assert_eq!(jwt.issuer(), Some("auth"));
+    assert_eq!(jwt.subject(), Some("42"));
+    assert_eq!(jwt.jwt_id(), None);
+    assert_eq!(jwt.audience(), vec!["api", "web"]);
+    assert_eq!(jwt.expires_at(), Some(2000.0));
+    assert_eq!(jwt.not_before(), Some(1000.0));
+    assert_eq!(jwt.issued_at(), Some(1000.0));
+    assert_eq!(
+        jwt.claim("name"),
+        Some(&JSONValue::JSONString("Jürgen".to_owned()))
+    );
+    assert_eq!(jwt.signature, vec![1, 2, 255]);
+    assert!(TOKEN.starts_with(&format!("{}.", jwt.signing_input)));
+
+    for (now, leeway, valid) in vec![
+        (999.0, 0.0, false),
+        (999.0, 5.0, true),
+        (1000.0, 0.0, true),
+        (1999.0, 0.0, true),
+        (2000.0, 0.0, false),
+        (2004.0, 5.0, true),
+    ] {
+        println!("Checking {} with leeway {}", now, leeway);
+        assert_eq!(jwt.is_valid_at(now, leeway), valid);
+    }
+
+    let jwt = UnverifiedJWT::decode("eyJhbGciOiJub25lIn0.eyJhdWQiOiJhcGkifQ.\n").unwrap();
+    assert_eq!(jwt.algorithm(), Some("none"));
+    assert_eq!(jwt.audience(), vec!["api"]);
+    assert_eq!(jwt.signature, Vec::<u8>::new());
+    assert!(jwt.is_valid_at(0.0, 0.0));
+}
+
+#[test]
+fn test_errors() {
+    for (token, reason) in vec![
+        ("e30.e30", "Expected 3 dot-separated parts, got 2"),
+        (
+            "e30.e30.e30.e30.e30",
+            "Expected 3 dot-separated parts, got 5",
+        ),
+        ("e3+.e30.", "Invalid base64url in header"),
+        ("e30.e.", "Invalid base64url in claims"),
+        ("e30.e30.a!", "Invalid base64url in signature"),
+        ("e30._w.", "Invalid UTF-8 in claims"),
+        ("WzFd.e30.", "Expected header to be an object"),
+        (
+            "e30.eyJhIjo.",
+            "Invalid JSON in claims: Empty string provided",
+        ),
+    ] {
+        println!("Checking {}", token);
+        assert_eq!(UnverifiedJWT::decode(token).unwrap_err().reason, reason);
+    }
+    let header = format!("{}{}", "[".repeat(6000), "]".repeat(6000));
+    let token = format!("{}.e30.", base64url_encode(header.as_bytes()));
+    let reason = UnverifiedJWT::decode(&token).unwrap_err().reason;
+    assert!(reason.starts_with("Invalid JSON in header: Maximum nesting depth"));
+}
@@ -0,0 +1,110 @@
+use super::*;
+use like::{JSONKind, JSONLike};
+use pointer::escape_token;
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+#[cfg(test)]
+mod tests;
+
+const ANY_INDEX: &str = "*";
+
+/// What the index knows about one key path
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyPathInfo {
+    /// Kinds of the values found at the path, in `JSONKind` order
+    pub kinds: Vec<JSONKind>,
+    /// Number of values found at the path
+    pub count: usize,
+}
+
+/// Every key path of a document with the kinds of values found there, sorted
+/// for prefix queries, to back autocompletion in editors and the CLI explorer.
+/// Paths are JSON Pointers with array indices collapsed into `*`, as in
+/// `PathPattern`: `/users/*/name` stands for the names of all users. The root
+/// is the empty path.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct KeyPathIndex {
+    paths: BTreeMap<String, KeyPathInfo>,
+}
+
+impl KeyPathIndex {
+    pub fn new(document: &JSONValue) -> KeyPathIndex {
+        let mut index = KeyPathIndex::default();
+        index.add(document, &mut String::new());
+        return index;
+    }
+
+    pub fn get(&self, path: &str) -> Option<&KeyPathInfo> {
+        return self.paths.get(path);
+    }
+
+    /// Number of distinct paths, the root included
+    pub fn len(&self) -> usize {
+        return self.paths.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.paths.is_empty();
+    }
+
+    /// All paths in sorted order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &KeyPathInfo)> {
+        return self.paths.iter().map(|(path, info)| (path.as_str(), info));
+    }
+
+    /// Paths starting with `prefix`, which doesn't need to end at a token:
+    /// `/users/*/na` finds `/users/*/name`.
+    pub fn complete(&self, prefix: &str) -> Vec<(&str, &KeyPathInfo)> {
+        return self
+            .paths
+            .range::<str, _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|&(path, _)| path.starts_with(prefix))
+            .map(|(path, info)| (path.as_str(), info))
+            .collect();
+    }
+
+    /// Paths one token below `path`, e.g. the keys to offer after `/users/*/`
+    pub fn children(&self, path: &str) -> Vec<(&str, &KeyPathInfo)> {
+        let prefix = format!("{}/", path);
+        let mut result = self.complete(&prefix);
+        result.retain(|&(child, _)| !child[prefix.len()..].contains('/'));
+        return result;
+    }
+
+    fn add(&mut self, value: &JSONValue, path: &mut String) {
+        if !self.paths.contains_key(path.as_str()) {
+            let info = KeyPathInfo {
+                kinds: vec![],
+                count: 0,
+            };
+            self.paths.insert(path.clone(), info);
+        }
+        let kind = value.kind();
+        let info = self.paths.get_mut(path.as_str()).unwrap();
+        info.count += 1;
+        if let Err(i) = info.kinds.binary_search(&kind) {
+            info.kinds.insert(i, kind);
+        }
+        let length = path.len();
+        match *value {
+            JSONValue::JSONObject(ref members) => {
+                for (key, member) in members.iter() {
+                    path.push('/');
+                    path.push_str(&escape_token(key));
+                    self.add(member, path);
+                    path.truncate(length);
+                }
+            }
+            JSONValue::JSONArray(ref items) => {
+                for item in items.iter() {
+                    path.push('/');
+                    path.push_str(ANY_INDEX);
+                    self.add(item, path);
+                    path.truncate(length);
+                }
+            }
+            _ => (),
+        }
+    }
+}
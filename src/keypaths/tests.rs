@@ -0,0 +1,88 @@
+#![allow(clippy::useless_vec)]
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+fn kinds(index: &KeyPathIndex, path: &str) -> Vec<&'static str> {
+    return index
+        .get(path)
+        .unwrap()
+        .kinds
+        .iter()
+        .map(|kind| kind.name())
+        .collect();
+}
+
+#[test]
+fn test_index() {
+    let document = parse(
+        r#"{"users": [{"name": "a", "id": 1, "tags": ["x"]}, {"name": null, "id": 2}],
+            "a/b": {"c~d": true}, "count": 2}"#,
+    );
+    let index = KeyPathIndex::new(&document);
+    let paths: Vec<&str> = index.iter().map(|(path, _)| path).collect();
+    assert_eq!(
+        paths,
+        vec![
+            "",
+            "/a~1b",
+            "/a~1b/c~0d",
+            "/count",
+            "/users",
+            "/users/*",
+            "/users/*/id",
+            "/users/*/name",
+            "/users/*/tags",
+            "/users/*/tags/*",
+        ]
+    );
+    assert_eq!(index.len(), 10);
+    assert_eq!(kinds(&index, ""), vec!["object"]);
+    assert_eq!(kinds(&index, "/users/*/name"), vec!["null", "string"]);
+    assert_eq!(index.get("/users/*").unwrap().count, 2);
+    assert_eq!(index.get("/users/*/tags").unwrap().count, 1);
+    assert_eq!(index.get("/users/0"), None);
+
+    for s in vec![
+        ("/users/*/na", vec!["/users/*/name"]),
+        ("/c", vec!["/count"]),
+        ("/users/*/t", vec!["/users/*/tags", "/users/*/tags/*"]),
+        ("/x", vec![]),
+    ] {
+        println!("Checking {}", s.0);
+        let found: Vec<&str> = index
+            .complete(s.0)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(found, s.1);
+    }
+    for s in vec![
+        ("", vec!["/a~1b", "/count", "/users"]),
+        (
+            "/users/*",
+            vec!["/users/*/id", "/users/*/name", "/users/*/tags"],
+        ),
+        ("/count", vec![]),
+    ] {
+        println!("Checking {}", s.0);
+        let found: Vec<&str> = index
+            .children(s.0)
+            .into_iter()
+            .map(|(path, _)| path)
+            .collect();
+        assert_eq!(found, s.1);
+    }
+}
+
+#[test]
+fn test_scalar_document() {
+    let index = KeyPathIndex::new(&parse("1"));
+    assert_eq!(index.len(), 1);
+    assert_eq!(kinds(&index, ""), vec!["number"]);
+    assert!(index.children("").is_empty());
+    assert!(!KeyPathIndex::new(&parse("[]")).is_empty());
+    assert!(KeyPathIndex::default().is_empty());
+}
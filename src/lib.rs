@@ -1,23 +1,150 @@
+#![allow(clippy::needless_return, clippy::vec_box)]
+
+use std::borrow::Cow;
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
 use std::str::FromStr;
 
+pub mod assembler;
+pub mod assert;
+pub mod config;
+#[cfg(feature = "test_suite")]
+pub mod conformance;
+pub mod convert;
+pub mod cst;
+pub mod diff;
+pub mod encoding;
+pub mod envelope;
+pub mod escape;
+pub mod framing;
+pub mod generate;
+#[cfg(feature = "geojson")]
+pub mod geojson;
+pub mod http;
+pub mod index;
+pub mod intern;
+#[cfg(feature = "jsonld")]
+pub mod jsonld;
+pub mod jsonpath;
+pub mod jwt;
+pub mod keypaths;
+pub mod like;
+pub mod lint;
+pub mod literal;
+pub mod logs;
+pub mod map;
+pub mod multimap;
+pub mod ndjson;
+pub mod normalize;
+pub mod page;
 mod parser;
+pub mod partial;
+pub mod patch;
+pub mod pattern;
+pub mod pointer;
+pub mod pool;
+pub mod presence;
+pub mod query;
+pub mod reference;
+pub mod rename;
+pub mod repair;
+pub mod roundtrip;
+pub mod sample;
+pub mod schema;
+pub mod secret;
+pub mod serializer;
+pub mod shared;
+pub mod snapshot;
+pub mod sniff;
+pub mod stats;
+pub mod store;
+pub mod stream;
+#[cfg(feature = "tracing")]
+pub mod trace;
+pub mod truncate;
+pub mod watch;
+
+pub use parser::{
+    explain_parse, ExplainStep, NumberOverflow, ParseExplanation, ParserOptions, Source, StrSource,
+};
 
-#[derive(Debug, PartialEq)]
+/// Map backing `JSONValue::JSONObject`. A `HashMap` by default; with the
+/// `preserve_order` feature a map iterating in insertion order, so parse -> modify ->
+/// serialize round trips keep the original key order; with the `btree_map` feature
+/// a `BTreeMap`, so keys always iterate sorted. `preserve_order` wins if both are enabled.
+#[cfg(not(any(feature = "preserve_order", feature = "btree_map")))]
+pub type JSONMap = HashMap<String, Box<JSONValue>>;
+#[cfg(feature = "preserve_order")]
+pub type JSONMap = map::OrderedMap<String, Box<JSONValue>>;
+#[cfg(all(feature = "btree_map", not(feature = "preserve_order")))]
+pub type JSONMap = std::collections::BTreeMap<String, Box<JSONValue>>;
+
+/// Values own their contents through `String`, `Vec`, `Box` and `JSONMap`, so they
+/// allocate from the global allocator; route that to a pool or arena with
+/// `#[global_allocator]`, or have the parser take them from a `pool::Alloc` with
+/// `JSONValue::parse_in`, e.g. a `pool::ValuePool` reusing them across parses.
+/// `ParserOptions::max_allocated_bytes` bounds what a parse takes.
+#[derive(Debug, Clone, PartialEq)]
 pub enum JSONValue {
     JSONNull(),
     JSONString(String),
     JSONBool(bool),
     JSONNumber(f64),
-    JSONObject(HashMap<String, Box<JSONValue>>),
+    JSONObject(JSONMap),
     JSONArray(Vec<Box<JSONValue>>),
+    /// Already serialized JSON, written out verbatim by the serializer. Modules that
+    /// look inside values (query, schema, stats) parse it on the fly; others, like
+    /// pointers, diffs and equality, treat it as an opaque leaf. Create with `JSONValue::raw`.
+    JSONRaw(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum JSONParseErrorKind {
+    /// The input is not valid JSON
+    Syntax,
+    /// The input could not be read
+    Io,
+    /// One of the `ParserOptions` limits was hit
+    LimitExceeded,
+    /// The value tree would outgrow `ParserOptions::max_allocated_bytes`
+    BudgetExceeded,
+    /// Parsing took longer than `ParserOptions::timeout`
+    Timeout,
 }
 
 #[derive(Debug, Clone)]
 pub struct JSONParseError {
     pub reason: String,
+    /// Byte offset in the input the error points to, if known
+    pub position: Option<usize>,
+    pub kind: JSONParseErrorKind,
+}
+
+impl JSONParseError {
+    /// Converts the error position into 1-based line and column (counted in chars)
+    /// within the input that produced the error.
+    pub fn line_column(&self, input: &str) -> Option<(usize, usize)> {
+        return line_column(input, self.position?);
+    }
+}
+
+/// Converts a byte offset into 1-based line and column (counted in chars).
+pub fn line_column(input: &str, position: usize) -> Option<(usize, usize)> {
+    let before = input.get(..position)?;
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    return Some((line, before[line_start..].chars().count() + 1));
+}
+
+impl fmt::Display for JSONParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "{}", self.reason);
+    }
 }
 
+impl Error for JSONParseError {}
+
 impl FromStr for JSONValue {
     type Err = JSONParseError;
 
@@ -25,3 +152,65 @@ impl FromStr for JSONValue {
         return parser::parse_json(s);
     }
 }
+
+impl JSONValue {
+    /// Parses with explicit options, e.g. `ParserOptions::untrusted()` for user uploads.
+    pub fn parse_with(input: &str, options: &ParserOptions) -> Result<JSONValue, JSONParseError> {
+        return parser::parse_json_with(input, options);
+    }
+
+    /// Parses a document from any `Source` of chars.
+    pub fn parse_source<S: Source>(
+        source: &mut S,
+        options: &ParserOptions,
+    ) -> Result<JSONValue, JSONParseError> {
+        return parser::parse_source_with(source, options);
+    }
+
+    /// Wraps a pre-serialized fragment to embed it in a document without
+    /// parsing it again on output. The fragment is checked to be valid JSON.
+    pub fn raw(fragment: &str) -> Result<JSONValue, JSONParseError> {
+        fragment.parse::<JSONValue>()?;
+        return Ok(JSONValue::JSONRaw(fragment.trim().to_owned()));
+    }
+
+    /// The value with all `JSONRaw` fragments parsed, borrowed if there are none.
+    /// Fragments that aren't valid JSON become null.
+    pub fn expand_raw(&self) -> Cow<'_, JSONValue> {
+        if !self.contains_raw() {
+            return Cow::Borrowed(self);
+        }
+        match *self {
+            JSONValue::JSONRaw(ref raw) => {
+                return Cow::Owned(raw.parse().unwrap_or(JSONValue::JSONNull()))
+            }
+            JSONValue::JSONArray(ref items) => {
+                return Cow::Owned(JSONValue::JSONArray(
+                    items
+                        .iter()
+                        .map(|item| Box::new(item.expand_raw().into_owned()))
+                        .collect(),
+                ))
+            }
+            JSONValue::JSONObject(ref members) => {
+                let mut map = JSONMap::new();
+                for (key, member) in members.iter() {
+                    map.insert(key.clone(), Box::new(member.expand_raw().into_owned()));
+                }
+                return Cow::Owned(JSONValue::JSONObject(map));
+            }
+            _ => return Cow::Borrowed(self),
+        }
+    }
+
+    fn contains_raw(&self) -> bool {
+        match *self {
+            JSONValue::JSONRaw(_) => return true,
+            JSONValue::JSONArray(ref items) => return items.iter().any(|item| item.contains_raw()),
+            JSONValue::JSONObject(ref members) => {
+                return members.values().any(|member| member.contains_raw())
+            }
+            _ => return false,
+        }
+    }
+}
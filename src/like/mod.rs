@@ -0,0 +1,293 @@
+use super::*;
+use multimap::MultiValue;
+use pointer::{parse_index, parse_pointer};
+use shared::SharedJSONValue;
+
+#[cfg(test)]
+mod tests;
+
+/// The six kinds of JSON values
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum JSONKind {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl JSONKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            JSONKind::Null => return "null",
+            JSONKind::Bool => return "bool",
+            JSONKind::Number => return "number",
+            JSONKind::String => return "string",
+            JSONKind::Array => return "array",
+            JSONKind::Object => return "object",
+        }
+    }
+}
+
+/// Read access shared by JSON value types, so code written against it works
+/// with whichever representation its caller has: `JSONValue`, `SharedJSONValue`
+/// and `MultiValue` here, and value types of other crates that implement it.
+///
+/// `JSONValue::JSONRaw` reports the kind of the JSON it holds and its scalars,
+/// but no members or items; `expand_raw` it to look inside.
+pub trait JSONLike {
+    fn kind(&self) -> JSONKind;
+    /// Member of an object; `None` for missing keys and non-objects
+    fn get(&self, key: &str) -> Option<&Self>;
+    /// Item of an array; `None` when out of bounds and for non-arrays
+    fn get_index(&self, index: usize) -> Option<&Self>;
+    fn as_bool(&self) -> Option<bool>;
+    fn as_f64(&self) -> Option<f64>;
+    fn as_str(&self) -> Option<&str>;
+    /// Object keys in the value's iteration order; none for non-objects
+    fn keys(&self) -> Vec<&str>;
+    /// Items of an array or members of an object, 0 for scalars
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    fn is_null(&self) -> bool {
+        return self.kind() == JSONKind::Null;
+    }
+
+    /// Looks up a value by JSON Pointer, like `JSONValue::pointer`
+    fn pointer(&self, pointer: &str) -> Option<&Self> {
+        let mut current = self;
+        for token in parse_pointer(pointer)? {
+            current = match current.kind() {
+                JSONKind::Object => current.get(&token)?,
+                JSONKind::Array => current.get_index(parse_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        return Some(current);
+    }
+
+    /// Deep copies the value into a `JSONValue`
+    fn to_value(&self) -> JSONValue {
+        match self.kind() {
+            JSONKind::Null => return JSONValue::JSONNull(),
+            JSONKind::Bool => return JSONValue::JSONBool(self.as_bool().unwrap_or_default()),
+            JSONKind::Number => return JSONValue::JSONNumber(self.as_f64().unwrap_or_default()),
+            JSONKind::String => {
+                return JSONValue::JSONString(self.as_str().unwrap_or_default().to_owned())
+            }
+            JSONKind::Array => {
+                let items = (0..self.len()).filter_map(|i| self.get_index(i));
+                return JSONValue::JSONArray(items.map(|item| Box::new(item.to_value())).collect());
+            }
+            JSONKind::Object => {
+                let mut map = JSONMap::new();
+                for key in self.keys() {
+                    if let Some(member) = self.get(key) {
+                        map.insert(key.to_owned(), Box::new(member.to_value()));
+                    }
+                }
+                return JSONValue::JSONObject(map);
+            }
+        }
+    }
+}
+
+impl JSONLike for JSONValue {
+    fn kind(&self) -> JSONKind {
+        match *self {
+            JSONValue::JSONNull() => return JSONKind::Null,
+            JSONValue::JSONBool(_) => return JSONKind::Bool,
+            JSONValue::JSONNumber(_) => return JSONKind::Number,
+            JSONValue::JSONString(_) => return JSONKind::String,
+            JSONValue::JSONArray(_) => return JSONKind::Array,
+            JSONValue::JSONObject(_) => return JSONKind::Object,
+            JSONValue::JSONRaw(_) => return self.expand_raw().kind(),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&JSONValue> {
+        match *self {
+            JSONValue::JSONObject(ref members) => return members.get(key).map(|member| &**member),
+            _ => return None,
+        }
+    }
+
+    fn get_index(&self, index: usize) -> Option<&JSONValue> {
+        match *self {
+            JSONValue::JSONArray(ref items) => return items.get(index).map(|item| &**item),
+            _ => return None,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match *self.expand_raw() {
+            JSONValue::JSONBool(b) => return Some(b),
+            _ => return None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match *self.expand_raw() {
+            JSONValue::JSONNumber(n) => return Some(n),
+            _ => return None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match *self {
+            JSONValue::JSONString(ref s) => return Some(s),
+            _ => return None,
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        match *self {
+            JSONValue::JSONObject(ref members) => {
+                return members.keys().map(|key| key.as_str()).collect()
+            }
+            _ => return vec![],
+        }
+    }
+
+    fn len(&self) -> usize {
+        match *self {
+            JSONValue::JSONObject(ref members) => return members.len(),
+            JSONValue::JSONArray(ref items) => return items.len(),
+            _ => return 0,
+        }
+    }
+
+    fn to_value(&self) -> JSONValue {
+        return self.expand_raw().into_owned();
+    }
+}
+
+impl JSONLike for SharedJSONValue {
+    fn kind(&self) -> JSONKind {
+        match *self {
+            SharedJSONValue::JSONNull() => return JSONKind::Null,
+            SharedJSONValue::JSONBool(_) => return JSONKind::Bool,
+            SharedJSONValue::JSONNumber(_) => return JSONKind::Number,
+            SharedJSONValue::JSONString(_) => return JSONKind::String,
+            SharedJSONValue::JSONArray(_) => return JSONKind::Array,
+            SharedJSONValue::JSONObject(_) => return JSONKind::Object,
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&SharedJSONValue> {
+        return SharedJSONValue::get(self, key);
+    }
+
+    fn get_index(&self, index: usize) -> Option<&SharedJSONValue> {
+        return SharedJSONValue::get_index(self, index);
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match *self {
+            SharedJSONValue::JSONBool(b) => return Some(b),
+            _ => return None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match *self {
+            SharedJSONValue::JSONNumber(n) => return Some(n),
+            _ => return None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match *self {
+            SharedJSONValue::JSONString(ref s) => return Some(s),
+            _ => return None,
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        match *self {
+            SharedJSONValue::JSONObject(ref members) => {
+                return members.keys().map(|key| key.as_str()).collect()
+            }
+            _ => return vec![],
+        }
+    }
+
+    fn len(&self) -> usize {
+        match *self {
+            SharedJSONValue::JSONObject(ref members) => return members.len(),
+            SharedJSONValue::JSONArray(ref items) => return items.len(),
+            _ => return 0,
+        }
+    }
+
+    fn to_value(&self) -> JSONValue {
+        return SharedJSONValue::to_value(self);
+    }
+}
+
+/// Objects act like the parser's: a repeated key counts once and `get` finds
+/// its last value.
+impl JSONLike for MultiValue {
+    fn kind(&self) -> JSONKind {
+        match *self {
+            MultiValue::Scalar(ref value) => return value.kind(),
+            MultiValue::Array(_) => return JSONKind::Array,
+            MultiValue::Object(_) => return JSONKind::Object,
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<&MultiValue> {
+        match *self {
+            MultiValue::Object(ref members) => return members.get(key),
+            _ => return None,
+        }
+    }
+
+    fn get_index(&self, index: usize) -> Option<&MultiValue> {
+        match *self {
+            MultiValue::Array(ref items) => return items.get(index),
+            _ => return None,
+        }
+    }
+
+    fn as_bool(&self) -> Option<bool> {
+        match *self {
+            MultiValue::Scalar(ref value) => return value.as_bool(),
+            _ => return None,
+        }
+    }
+
+    fn as_f64(&self) -> Option<f64> {
+        match *self {
+            MultiValue::Scalar(ref value) => return value.as_f64(),
+            _ => return None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match *self {
+            MultiValue::Scalar(ref value) => return value.as_str(),
+            _ => return None,
+        }
+    }
+
+    fn keys(&self) -> Vec<&str> {
+        match *self {
+            MultiValue::Object(ref members) => return members.keys(),
+            _ => return vec![],
+        }
+    }
+
+    fn len(&self) -> usize {
+        match *self {
+            MultiValue::Object(ref members) => return members.keys().len(),
+            MultiValue::Array(ref items) => return items.len(),
+            _ => return 0,
+        }
+    }
+}
@@ -0,0 +1,90 @@
+#![allow(clippy::useless_vec)]
+use super::*;
+
+const INPUT: &str = r#"{"name": "a", "tags": ["x", "y"], "size": 2, "ok": true, "none": null}"#;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+// Written once against the trait
+fn summary<V: JSONLike>(value: &V) -> String {
+    let tags: Vec<&str> = (0..value.pointer("/tags").map_or(0, |tags| tags.len()))
+        .filter_map(|i| value.pointer(&format!("/tags/{}", i))?.as_str())
+        .collect();
+    return format!(
+        "{} {:?} {} {} {} {:?}",
+        value
+            .get("name")
+            .and_then(|name| name.as_str())
+            .unwrap_or("?"),
+        tags,
+        value
+            .get("size")
+            .and_then(|size| size.as_f64())
+            .unwrap_or(0.0),
+        value.get("ok").and_then(|ok| ok.as_bool()).unwrap_or(false),
+        value.get("none").is_some_and(|none| none.is_null()),
+        value.get_index(0).map(|item| item.kind()),
+    );
+}
+
+#[test]
+fn test_implementations() {
+    let value = parse(INPUT);
+    let expected = "a [\"x\", \"y\"] 2 true true None";
+    assert_eq!(summary(&value), expected);
+    assert_eq!(summary(&SharedJSONValue::from(value.clone())), expected);
+    assert_eq!(summary(&MultiValue::parse(INPUT).unwrap()), expected);
+    assert_eq!(
+        summary(&JSONValue::raw(INPUT).unwrap()),
+        "? [] 0 false false None"
+    );
+
+    for value in vec![
+        value.clone(),
+        parse("[1, [2]]"),
+        parse("\"s\""),
+        parse("null"),
+    ] {
+        println!("Checking {:?}", value);
+        let shared = SharedJSONValue::from(value.clone());
+        let multi = MultiValue::from_value(&value);
+        assert_eq!(shared.kind(), value.kind());
+        assert_eq!(multi.kind(), value.kind());
+        assert_eq!(JSONLike::to_value(&shared), value);
+        assert_eq!(JSONLike::to_value(&multi), value);
+        assert_eq!(shared.len(), value.len());
+        assert_eq!(multi.len(), value.len());
+    }
+}
+
+#[test]
+fn test_kinds() {
+    for s in vec![
+        ("null", JSONKind::Null),
+        ("false", JSONKind::Bool),
+        ("1.5", JSONKind::Number),
+        ("\"\"", JSONKind::String),
+        ("[]", JSONKind::Array),
+        ("{}", JSONKind::Object),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(parse(s.0).kind(), s.1);
+        let raw = JSONValue::raw(s.0).unwrap();
+        assert_eq!(raw.kind(), s.1);
+        assert_eq!(JSONLike::to_value(&raw), parse(s.0));
+    }
+    let raw = JSONValue::raw("[1]").unwrap();
+    assert_eq!(raw.get_index(0), None);
+    assert_eq!(JSONValue::raw("2").unwrap().as_f64(), Some(2.0));
+}
+
+#[test]
+fn test_repeated_keys() {
+    let value = MultiValue::parse(r#"{"a": 1, "b": 2, "a": 3}"#).unwrap();
+    assert_eq!(value.len(), 2);
+    assert_eq!(JSONLike::keys(&value), vec!["a", "b"]);
+    assert_eq!(value.pointer("/a").and_then(|a| a.as_f64()), Some(3.0));
+    assert_eq!(JSONLike::to_value(&value), parse(r#"{"a": 3, "b": 2}"#));
+}
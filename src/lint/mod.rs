@@ -0,0 +1,413 @@
+use super::*;
+use std::collections::HashSet;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[cfg(test)]
+mod tests;
+
+const DEFAULT_MAX_DEPTH: usize = 64;
+// Any decimal with this many significant digits survives a round trip through f64
+const SAFE_SIGNIFICANT_DIGITS: usize = 15;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LintRule {
+    /// Input isn't JSON even with comments and trailing commas tolerated
+    Syntax,
+    DuplicateKey,
+    /// Number literal that doesn't survive conversion to f64 and back
+    PrecisionLoss,
+    Comment,
+    TrailingComma,
+    DeepNesting,
+    /// Array mixing values of different types (nulls are ignored)
+    MixedTypes,
+}
+
+impl LintRule {
+    pub fn all() -> Vec<LintRule> {
+        return vec![
+            LintRule::Syntax,
+            LintRule::DuplicateKey,
+            LintRule::PrecisionLoss,
+            LintRule::Comment,
+            LintRule::TrailingComma,
+            LintRule::DeepNesting,
+            LintRule::MixedTypes,
+        ];
+    }
+
+    pub fn name(&self) -> &'static str {
+        match *self {
+            LintRule::Syntax => return "syntax",
+            LintRule::DuplicateKey => return "duplicate-key",
+            LintRule::PrecisionLoss => return "precision-loss",
+            LintRule::Comment => return "comment",
+            LintRule::TrailingComma => return "trailing-comma",
+            LintRule::DeepNesting => return "deep-nesting",
+            LintRule::MixedTypes => return "mixed-types",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<LintRule> {
+        return LintRule::all().into_iter().find(|rule| rule.name() == name);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintConfig {
+    pub severities: HashMap<LintRule, Severity>,
+    /// Containers nested deeper than this are reported as `DeepNesting`
+    pub max_depth: usize,
+}
+
+impl LintConfig {
+    pub fn severity(&self, rule: LintRule) -> Severity {
+        return self.severities.get(&rule).cloned().unwrap_or(Severity::Off);
+    }
+}
+
+impl Default for LintConfig {
+    /// Constructs that make the input invalid JSON are errors, questionable but valid ones warnings.
+    fn default() -> Self {
+        let mut severities = HashMap::new();
+        severities.insert(LintRule::Syntax, Severity::Error);
+        severities.insert(LintRule::DuplicateKey, Severity::Warning);
+        severities.insert(LintRule::PrecisionLoss, Severity::Warning);
+        severities.insert(LintRule::Comment, Severity::Error);
+        severities.insert(LintRule::TrailingComma, Severity::Error);
+        severities.insert(LintRule::DeepNesting, Severity::Warning);
+        severities.insert(LintRule::MixedTypes, Severity::Warning);
+        return LintConfig {
+            severities,
+            max_depth: DEFAULT_MAX_DEPTH,
+        };
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LintIssue {
+    pub rule: LintRule,
+    pub severity: Severity,
+    /// Byte offset in the input
+    pub position: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Null,
+    Bool,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+/// Checks the input for the configured rules. Comments and trailing commas are tolerated
+/// (and reported), any other syntax error stops linting with a single `Syntax` issue.
+/// Issues are returned in input order, rules configured as `Off` are not reported.
+pub fn lint(input: &str, config: &LintConfig) -> Vec<LintIssue> {
+    let mut linter = Linter {
+        input,
+        chars: input.char_indices().peekable(),
+        config,
+        issues: vec![],
+        depth: 0,
+    };
+    let _ = linter.document();
+    let mut issues = linter.issues;
+    issues.retain(|issue| issue.severity != Severity::Off);
+    issues.sort_by_key(|issue| issue.position);
+    return issues;
+}
+
+struct Linter<'a> {
+    input: &'a str,
+    chars: Peekable<CharIndices<'a>>,
+    config: &'a LintConfig,
+    issues: Vec<LintIssue>,
+    depth: usize,
+}
+
+// Err(()) means a syntax error was already reported and linting should stop
+type Scan<T> = Result<T, ()>;
+
+impl<'a> Linter<'a> {
+    fn document(&mut self) -> Scan<()> {
+        self.skip_trivia()?;
+        self.value()?;
+        self.skip_trivia()?;
+        if let Some((i, ch)) = self.chars.next() {
+            return self.syntax_error(i, format!("Unexpected charachter {} after the value", ch));
+        }
+        return Ok(());
+    }
+
+    fn report(&mut self, rule: LintRule, position: usize, message: String) {
+        self.issues.push(LintIssue {
+            rule,
+            severity: self.config.severity(rule),
+            position,
+            message,
+        });
+    }
+
+    fn syntax_error<T>(&mut self, position: usize, message: String) -> Scan<T> {
+        self.report(LintRule::Syntax, position, message);
+        return Err(());
+    }
+
+    fn unexpected<T>(&mut self) -> Scan<T> {
+        match self.chars.next() {
+            Some((i, ch)) => return self.syntax_error(i, format!("Unexpected charachter {}", ch)),
+            None => {
+                let end = self.input.len();
+                return self.syntax_error(end, "String ended unexpectedly".to_owned());
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<(usize, char)> {
+        return self.chars.peek().cloned();
+    }
+
+    fn expect(&mut self, expected: char) -> Scan<usize> {
+        match self.peek() {
+            Some((i, ch)) if ch == expected => {
+                self.chars.next();
+                return Ok(i);
+            }
+            _ => return self.unexpected(),
+        }
+    }
+
+    fn skip_trivia(&mut self) -> Scan<()> {
+        while let Some((i, ch)) = self.peek() {
+            match ch {
+                ' ' | '\t' | '\n' | '\r' => {
+                    self.chars.next();
+                }
+                '/' => {
+                    self.chars.next();
+                    self.comment(i)?;
+                }
+                _ => return Ok(()),
+            }
+        }
+        return Ok(());
+    }
+
+    // The leading slash is consumed
+    fn comment(&mut self, start: usize) -> Scan<()> {
+        match self.chars.next() {
+            Some((_, '/')) => {
+                for (_, ch) in self.chars.by_ref() {
+                    if ch == '\n' {
+                        break;
+                    }
+                }
+            }
+            Some((_, '*')) => {
+                let mut previous = ' ';
+                loop {
+                    match self.chars.next() {
+                        Some((_, '/')) if previous == '*' => break,
+                        Some((_, ch)) => previous = ch,
+                        None => return self.syntax_error(start, "Unterminated comment".to_owned()),
+                    }
+                }
+            }
+            _ => return self.syntax_error(start, "Unexpected charachter /".to_owned()),
+        }
+        self.report(
+            LintRule::Comment,
+            start,
+            "Comments are not allowed in JSON".to_owned(),
+        );
+        return Ok(());
+    }
+
+    fn value(&mut self) -> Scan<Kind> {
+        match self.peek() {
+            Some((_, '{')) => return self.container(true),
+            Some((_, '[')) => return self.container(false),
+            Some((_, '"')) => {
+                self.string()?;
+                return Ok(Kind::String);
+            }
+            Some((_, 't')) => return self.literal("true", Kind::Bool),
+            Some((_, 'f')) => return self.literal("false", Kind::Bool),
+            Some((_, 'n')) => return self.literal("null", Kind::Null),
+            Some((i, ch)) if ch == '-' || ch.is_ascii_digit() => return self.number(i),
+            _ => return self.unexpected(),
+        }
+    }
+
+    fn literal(&mut self, expected: &str, kind: Kind) -> Scan<Kind> {
+        for ch in expected.chars() {
+            self.expect(ch)?;
+        }
+        return Ok(kind);
+    }
+
+    fn string(&mut self) -> Scan<String> {
+        let start = self.expect('"')?;
+        loop {
+            match self.chars.next() {
+                Some((i, '"')) => {
+                    let raw = &self.input[start..=i];
+                    return match raw.parse() {
+                        Ok(JSONValue::JSONString(s)) => Ok(s),
+                        _ => self.syntax_error(start, format!("Invalid string {}", raw)),
+                    };
+                }
+                Some((_, '\\')) => {
+                    self.chars.next();
+                }
+                Some((i, '\0'..='\x1F')) => {
+                    return self.syntax_error(i, "Control charachter in string".to_owned())
+                }
+                Some(_) => (),
+                None => return self.unexpected(),
+            }
+        }
+    }
+
+    fn number(&mut self, start: usize) -> Scan<Kind> {
+        let mut end = start;
+        while let Some((i, ch)) = self.peek() {
+            if !(ch.is_ascii_digit() || "+-.eE".contains(ch)) {
+                break;
+            }
+            end = i + 1;
+            self.chars.next();
+        }
+        let literal = &self.input[start..end];
+        match literal.parse() {
+            Ok(JSONValue::JSONNumber(n)) => {
+                if loses_precision(literal, n) {
+                    self.report(
+                        LintRule::PrecisionLoss,
+                        start,
+                        format!("Number {} can't be represented exactly as f64", literal),
+                    );
+                }
+                return Ok(Kind::Number);
+            }
+            _ => return self.syntax_error(start, format!("Invalid number {}", literal)),
+        }
+    }
+
+    fn container(&mut self, is_object: bool) -> Scan<Kind> {
+        let (start, end) = if is_object { ('{', '}') } else { ('[', ']') };
+        let position = self.expect(start)?;
+        self.depth += 1;
+        if self.depth == self.config.max_depth + 1 {
+            let message = format!("Nesting is deeper than {} levels", self.config.max_depth);
+            self.report(LintRule::DeepNesting, position, message);
+        }
+        let mut keys = HashSet::new();
+        let mut kinds = vec![];
+        self.skip_trivia()?;
+        if let Some((_, ch)) = self.peek() {
+            if ch == end {
+                self.chars.next();
+                self.depth -= 1;
+                return Ok(if is_object { Kind::Object } else { Kind::Array });
+            }
+        }
+        loop {
+            self.skip_trivia()?;
+            if is_object {
+                let key_position = self.peek().map_or(self.input.len(), |(i, _)| i);
+                let key = self.string()?;
+                if !keys.insert(key.clone()) {
+                    self.report(
+                        LintRule::DuplicateKey,
+                        key_position,
+                        format!("Duplicate key {}", key),
+                    );
+                }
+                self.skip_trivia()?;
+                self.expect(':')?;
+                self.skip_trivia()?;
+            }
+            kinds.push((self.peek().map(|(i, _)| i), self.value()?));
+            self.skip_trivia()?;
+            match self.peek() {
+                Some((_, ch)) if ch == end => {
+                    self.chars.next();
+                    break;
+                }
+                Some((comma, ',')) => {
+                    self.chars.next();
+                    self.skip_trivia()?;
+                    if let Some((_, ch)) = self.peek() {
+                        if ch == end {
+                            self.chars.next();
+                            self.report(
+                                LintRule::TrailingComma,
+                                comma,
+                                "Trailing comma".to_owned(),
+                            );
+                            break;
+                        }
+                    }
+                }
+                _ => return self.unexpected(),
+            }
+        }
+        self.depth -= 1;
+        if !is_object {
+            self.check_mixed_types(position, &kinds);
+            return Ok(Kind::Array);
+        }
+        return Ok(Kind::Object);
+    }
+
+    fn check_mixed_types(&mut self, position: usize, kinds: &[(Option<usize>, Kind)]) {
+        let mut kinds = kinds.iter().filter(|&&(_, kind)| kind != Kind::Null);
+        if let Some(&(_, first)) = kinds.next() {
+            if let Some(&(at, other)) = kinds.find(|&&(_, kind)| kind != first) {
+                let message = format!("Array mixes {:?} and {:?} values", first, other);
+                self.report(LintRule::MixedTypes, at.unwrap_or(position), message);
+            }
+        }
+    }
+}
+
+/// Compares significant digits of the literal with the shortest representation
+/// of the parsed f64. Overflow to infinity and underflow to zero count as loss too.
+pub fn loses_precision(literal: &str, value: f64) -> bool {
+    if !value.is_finite() {
+        return true;
+    }
+    let digits = significant_digits(literal);
+    if digits.is_empty() {
+        return false;
+    }
+    if value == 0.0 {
+        return true;
+    }
+    if digits.len() <= SAFE_SIGNIFICANT_DIGITS {
+        return false;
+    }
+    return digits != significant_digits(&format!("{:e}", value));
+}
+
+fn significant_digits(literal: &str) -> String {
+    let mantissa = literal.split(['e', 'E']).next().unwrap_or("");
+    let digits: String = mantissa.chars().filter(|ch| ch.is_ascii_digit()).collect();
+    return digits
+        .trim_start_matches('0')
+        .trim_end_matches('0')
+        .to_owned();
+}
@@ -0,0 +1,98 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn rules(input: &str) -> Vec<(LintRule, usize)> {
+    return lint(input, &LintConfig::default())
+        .into_iter()
+        .map(|issue| (issue.rule, issue.position))
+        .collect();
+}
+
+#[test]
+fn test_clean_documents() {
+    for s in vec![
+        "{}",
+        "[]",
+        "{\"a\": [1, 2, null], \"b\": {\"c\": \"d\"}}",
+        "[\"x\", null, \"y\"]",
+        "[[1], [\"a\"]]",
+        "0.1",
+        "123456789012345",
+        "9007199254740993e-10",
+        "-0",
+        "\"\\u00e9\\n\"",
+    ] {
+        assert_eq!(rules(s), vec![], "{} should be clean", s);
+    }
+}
+
+#[test]
+fn test_issues() {
+    for s in vec![
+        ("{\"a\": 1, \"a\": 2}", vec![(LintRule::DuplicateKey, 9)]),
+        ("[9007199254740993]", vec![(LintRule::PrecisionLoss, 1)]),
+        (
+            "[0.12345678901234567891]",
+            vec![(LintRule::PrecisionLoss, 1)],
+        ),
+        ("1e400", vec![(LintRule::PrecisionLoss, 0)]),
+        ("1e-400", vec![(LintRule::PrecisionLoss, 0)]),
+        ("// header\n{}", vec![(LintRule::Comment, 0)]),
+        ("[1 /* one */, 2]", vec![(LintRule::Comment, 3)]),
+        ("[1, 2, ]", vec![(LintRule::TrailingComma, 5)]),
+        ("{\"a\": 1,}", vec![(LintRule::TrailingComma, 7)]),
+        ("[1, \"a\", null]", vec![(LintRule::MixedTypes, 4)]),
+        ("[{}, []]", vec![(LintRule::MixedTypes, 5)]),
+        (
+            "[1, ]x",
+            vec![(LintRule::TrailingComma, 2), (LintRule::Syntax, 5)],
+        ),
+        ("{\"a\" 1}", vec![(LintRule::Syntax, 5)]),
+        ("[01]", vec![(LintRule::Syntax, 1)]),
+        ("[\"\\x\"]", vec![(LintRule::Syntax, 1)]),
+        ("[1, 2", vec![(LintRule::Syntax, 5)]),
+        ("[1 /* open", vec![(LintRule::Syntax, 3)]),
+        ("[,]", vec![(LintRule::Syntax, 1)]),
+    ] {
+        assert_eq!(rules(s.0), s.1, "Unexpected issues in {}", s.0);
+    }
+}
+
+#[test]
+fn test_deep_nesting() {
+    let config = LintConfig {
+        max_depth: 2,
+        ..LintConfig::default()
+    };
+    // reported once for every container crossing the limit, not for everything inside it
+    let issues: Vec<(LintRule, usize)> = lint("[[[[1]]], [[2]]]", &config)
+        .into_iter()
+        .map(|issue| (issue.rule, issue.position))
+        .collect();
+    assert_eq!(
+        issues,
+        vec![(LintRule::DeepNesting, 2), (LintRule::DeepNesting, 11)]
+    );
+}
+
+#[test]
+fn test_configurable_severity() {
+    let mut config = LintConfig::default();
+    config.severities.insert(LintRule::Comment, Severity::Off);
+    config
+        .severities
+        .insert(LintRule::MixedTypes, Severity::Error);
+    let issues = lint("// c\n[1, \"a\"]", &config);
+    assert_eq!(issues.len(), 1);
+    assert_eq!(issues[0].rule, LintRule::MixedTypes);
+    assert_eq!(issues[0].severity, Severity::Error);
+}
+
+#[test]
+fn test_rule_names() {
+    for rule in LintRule::all() {
+        assert_eq!(LintRule::from_name(rule.name()), Some(rule));
+    }
+    assert_eq!(LintRule::from_name("unknown"), None);
+}
@@ -0,0 +1,269 @@
+/// Checks a JSON string literal at compile time and evaluates to it as a
+/// `&'static str`, so a typo in a fixture fails the build:
+///
+/// ```compile_fail
+/// # #[macro_use] extern crate rsjson;
+/// # fn main() {
+/// let fixture = json_str!(r#"{"id": 1,}"#);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! json_str {
+    ($text:expr) => {{
+        const TEXT: &str = $text;
+        const _: () = match $crate::literal::validate(TEXT) {
+            Ok(()) => (),
+            Err(err) => panic!("{}", err.reason),
+        };
+        TEXT
+    }};
+}
+
+/// Embeds a JSON file like `include_str!`, checking it at compile time.
+#[macro_export]
+macro_rules! include_json {
+    ($path:expr) => {
+        $crate::json_str!(include_str!($path))
+    };
+}
+
+/// Parsed value of JSON text checked at compile time with `json_str!`,
+/// e.g. `json_value!(include_str!("fixture.json"))`.
+#[macro_export]
+macro_rules! json_value {
+    ($text:expr) => {
+        $crate::json_str!($text)
+            .parse::<$crate::JSONValue>()
+            .expect("JSON checked at compile time")
+    };
+}
+
+#[cfg(test)]
+mod tests;
+
+// Nesting allowed in literals, keeping compile-time recursion shallow
+const MAX_DEPTH: usize = 128;
+
+/// Why `validate` rejected a literal. Plain data, so it can be built in const context.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiteralError {
+    pub reason: &'static str,
+    /// Byte offset in the literal
+    pub position: usize,
+}
+
+const fn fail(reason: &'static str, position: usize) -> Result<usize, LiteralError> {
+    Err(LiteralError { reason, position })
+}
+
+/// Validates JSON text in const context, accepting what the parser accepts with
+/// default options. Behind `json_str!`; only the reason survives into the
+/// compile error, as const panics can't format the position.
+pub const fn validate(text: &str) -> Result<(), LiteralError> {
+    let bytes = text.as_bytes();
+    let end = match value(bytes, skip_whitespace(bytes, 0), 0) {
+        Ok(end) => skip_whitespace(bytes, end),
+        Err(err) => return Err(err),
+    };
+    if end < bytes.len() {
+        return Err(LiteralError {
+            reason: "Invalid JSON literal: unexpected text after the value",
+            position: end,
+        });
+    }
+    return Ok(());
+}
+
+const fn skip_whitespace(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && matches!(bytes[i], b' ' | b'\t' | b'\n' | b'\r') {
+        i += 1;
+    }
+    return i;
+}
+
+// Returns the position right after the value starting at `i`
+const fn value(bytes: &[u8], i: usize, depth: usize) -> Result<usize, LiteralError> {
+    if depth > MAX_DEPTH {
+        return fail("Invalid JSON literal: nested too deeply", i);
+    }
+    if i >= bytes.len() {
+        return fail("Invalid JSON literal: value expected", i);
+    }
+    match bytes[i] {
+        b'{' => return object(bytes, i, depth),
+        b'[' => return array(bytes, i, depth),
+        b'"' => return string(bytes, i),
+        b't' => return keyword(bytes, i, b"true"),
+        b'f' => return keyword(bytes, i, b"false"),
+        b'n' => return keyword(bytes, i, b"null"),
+        b'-' | b'0'..=b'9' => return number(bytes, i),
+        _ => return fail("Invalid JSON literal: unexpected character", i),
+    }
+}
+
+const fn object(bytes: &[u8], start: usize, depth: usize) -> Result<usize, LiteralError> {
+    let mut i = skip_whitespace(bytes, start + 1);
+    if i < bytes.len() && bytes[i] == b'}' {
+        return Ok(i + 1);
+    }
+    loop {
+        if i >= bytes.len() || bytes[i] != b'"' {
+            return fail("Invalid JSON literal: object key expected", i);
+        }
+        i = match string(bytes, i) {
+            Ok(end) => skip_whitespace(bytes, end),
+            Err(err) => return Err(err),
+        };
+        if i >= bytes.len() || bytes[i] != b':' {
+            return fail("Invalid JSON literal: ':' expected", i);
+        }
+        i = match value(bytes, skip_whitespace(bytes, i + 1), depth + 1) {
+            Ok(end) => skip_whitespace(bytes, end),
+            Err(err) => return Err(err),
+        };
+        if i < bytes.len() && bytes[i] == b'}' {
+            return Ok(i + 1);
+        }
+        if i >= bytes.len() || bytes[i] != b',' {
+            return fail("Invalid JSON literal: ',' or '}' expected", i);
+        }
+        i = skip_whitespace(bytes, i + 1);
+    }
+}
+
+const fn array(bytes: &[u8], start: usize, depth: usize) -> Result<usize, LiteralError> {
+    let mut i = skip_whitespace(bytes, start + 1);
+    if i < bytes.len() && bytes[i] == b']' {
+        return Ok(i + 1);
+    }
+    loop {
+        i = match value(bytes, i, depth + 1) {
+            Ok(end) => skip_whitespace(bytes, end),
+            Err(err) => return Err(err),
+        };
+        if i < bytes.len() && bytes[i] == b']' {
+            return Ok(i + 1);
+        }
+        if i >= bytes.len() || bytes[i] != b',' {
+            return fail("Invalid JSON literal: ',' or ']' expected", i);
+        }
+        i = skip_whitespace(bytes, i + 1);
+    }
+}
+
+// The input is a `&str`, so only escapes and control characters need checking
+const fn string(bytes: &[u8], start: usize) -> Result<usize, LiteralError> {
+    let mut i = start + 1;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'"' => return Ok(i + 1),
+            b'\\' => {
+                if i + 1 >= bytes.len() {
+                    break;
+                }
+                match bytes[i + 1] {
+                    b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => i += 2,
+                    b'u' => {
+                        let unit = match hex4(bytes, i + 2) {
+                            Some(unit) => unit,
+                            None => {
+                                return fail("Invalid JSON literal: invalid escape sequence", i)
+                            }
+                        };
+                        // surrogates only come in pairs, high then low
+                        let paired = matches!(unit, 0xD800..=0xDBFF) && low_surrogate(bytes, i + 6);
+                        if matches!(unit, 0xD800..=0xDFFF) && !paired {
+                            return fail("Invalid JSON literal: unpaired surrogate", i);
+                        }
+                        i += if paired { 12 } else { 6 };
+                    }
+                    _ => return fail("Invalid JSON literal: invalid escape sequence", i),
+                }
+            }
+            0x00..=0x1F => return fail("Invalid JSON literal: control character in string", i),
+            _ => i += 1,
+        }
+    }
+    return fail("Invalid JSON literal: unterminated string", start);
+}
+
+// The UTF-16 code unit of the four hex digits at `i`
+const fn hex4(bytes: &[u8], i: usize) -> Option<u32> {
+    let mut unit = 0;
+    let mut j = i;
+    while j < i + 4 {
+        if j >= bytes.len() {
+            return None;
+        }
+        let digit = match bytes[j] {
+            b'0'..=b'9' => bytes[j] - b'0',
+            b'a'..=b'f' => bytes[j] - b'a' + 10,
+            b'A'..=b'F' => bytes[j] - b'A' + 10,
+            _ => return None,
+        };
+        unit = unit * 16 + digit as u32;
+        j += 1;
+    }
+    return Some(unit);
+}
+
+// Whether a `\u` escape of a low surrogate starts at `i`
+const fn low_surrogate(bytes: &[u8], i: usize) -> bool {
+    if i + 1 >= bytes.len() || bytes[i] != b'\\' || bytes[i + 1] != b'u' {
+        return false;
+    }
+    return matches!(hex4(bytes, i + 2), Some(0xDC00..=0xDFFF));
+}
+
+const fn keyword(bytes: &[u8], start: usize, word: &[u8]) -> Result<usize, LiteralError> {
+    let mut k = 0;
+    while k < word.len() {
+        if start + k >= bytes.len() || bytes[start + k] != word[k] {
+            return fail("Invalid JSON literal: unexpected character", start + k);
+        }
+        k += 1;
+    }
+    return Ok(start + word.len());
+}
+
+const fn digits(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && bytes[i].is_ascii_digit() {
+        i += 1;
+    }
+    return i;
+}
+
+const fn number(bytes: &[u8], start: usize) -> Result<usize, LiteralError> {
+    let mut i = start;
+    if bytes[i] == b'-' {
+        i += 1;
+    }
+    if i < bytes.len() && bytes[i] == b'0' {
+        i += 1;
+    } else {
+        let end = digits(bytes, i);
+        if end == i {
+            return fail("Invalid JSON literal: invalid number", start);
+        }
+        i = end;
+    }
+    if i < bytes.len() && bytes[i] == b'.' {
+        let end = digits(bytes, i + 1);
+        if end == i + 1 {
+            return fail("Invalid JSON literal: invalid number", start);
+        }
+        i = end;
+    }
+    if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+        i += 1;
+        if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+            i += 1;
+        }
+        let end = digits(bytes, i);
+        if end == i {
+            return fail("Invalid JSON literal: invalid number", start);
+        }
+        i = end;
+    }
+    return Ok(i);
+}
@@ -0,0 +1,144 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use JSONValue;
+
+#[test]
+fn test_validate() {
+    for s in vec![
+        "null",
+        " true ",
+        "false",
+        "0",
+        "-12.5e+3",
+        "1E2",
+        "\"a\\n\\u00e9\\\"\"",
+        "\"é\"",
+        "[]",
+        "{}",
+        "[1, [2, {\"a\": null}], \"x\"]",
+        "{\"a\": {\"b\": []}, \"c\": 1}\n",
+    ] {
+        println!("Checking {}", s);
+        assert_eq!(validate(s), Ok(()));
+        assert!(s.parse::<JSONValue>().is_ok());
+    }
+}
+
+#[test]
+fn test_validate_errors() {
+    for s in vec![
+        ("", 0, "Invalid JSON literal: value expected"),
+        ("tru", 3, "Invalid JSON literal: unexpected character"),
+        (
+            "01",
+            1,
+            "Invalid JSON literal: unexpected text after the value",
+        ),
+        ("-", 0, "Invalid JSON literal: invalid number"),
+        ("1.", 0, "Invalid JSON literal: invalid number"),
+        ("1e+", 0, "Invalid JSON literal: invalid number"),
+        ("\"abc", 0, "Invalid JSON literal: unterminated string"),
+        (
+            "\"\\x\"",
+            1,
+            "Invalid JSON literal: invalid escape sequence",
+        ),
+        (
+            "\"\\u12\"",
+            1,
+            "Invalid JSON literal: invalid escape sequence",
+        ),
+        ("\"\\ud800\"", 1, "Invalid JSON literal: unpaired surrogate"),
+        (
+            "\"a\\udc00\"",
+            2,
+            "Invalid JSON literal: unpaired surrogate",
+        ),
+        (
+            "\"a\tb\"",
+            2,
+            "Invalid JSON literal: control character in string",
+        ),
+        ("[1,]", 3, "Invalid JSON literal: unexpected character"),
+        ("[1 2]", 3, "Invalid JSON literal: ',' or ']' expected"),
+        (
+            "{\"a\": 1,}",
+            8,
+            "Invalid JSON literal: object key expected",
+        ),
+        ("{\"a\" 1}", 5, "Invalid JSON literal: ':' expected"),
+        ("{a: 1}", 1, "Invalid JSON literal: object key expected"),
+        (
+            "{} {}",
+            3,
+            "Invalid JSON literal: unexpected text after the value",
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(
+            validate(s.0),
+            Err(LiteralError {
+                reason: s.2,
+                position: s.1
+            })
+        );
+        assert!(s.0.parse::<JSONValue>().is_err());
+    }
+    let deep = format!("{}{}", "[".repeat(200), "]".repeat(200));
+    assert_eq!(
+        validate(&deep).unwrap_err().reason,
+        "Invalid JSON literal: nested too deeply"
+    );
+}
+
+// Literals `validate` and the parser must agree on
+const CORPUS: &[&str] = &[
+    "null",
+    " true ",
+    "nul",
+    "-0",
+    "-",
+    "01",
+    "1.5e-3",
+    ".5",
+    "[ ]",
+    "[\n]",
+    "{ }",
+    "{\n}",
+    "{\t\"a\" : 1 }",
+    "[1,]",
+    "{\"a\": 1,}",
+    "\"\\ud834\\udd1e\"",
+    "\"\\ud800\"",
+    "\"\\udc00\"",
+    "\"\\ud800\\u0041\"",
+    "\"\\ud800x\"",
+    "\"\\u00e9\"",
+    "\"\\u00g9\"",
+    "\"a\u{7f}b\"",
+    "\"a\u{1f}b\"",
+    "\u{a0}1",
+    "1 2",
+];
+
+#[test]
+fn test_validate_matches_parser() {
+    for s in CORPUS {
+        println!("Checking {:?}", s);
+        assert_eq!(validate(s).is_ok(), s.parse::<JSONValue>().is_ok());
+    }
+}
+
+#[test]
+fn test_macros() {
+    let text: &'static str = json_str!(r#"{"a": [1, 2]}"#);
+    assert_eq!(text, r#"{"a": [1, 2]}"#);
+    let value = json_value!(r#"{"a": [1, 2]}"#);
+    assert_eq!(value, text.parse().unwrap());
+    assert_eq!(include_json!("fixture.json"), include_str!("fixture.json"));
+    assert_eq!(
+        json_value!(include_str!("fixture.json")),
+        include_str!("fixture.json").parse().unwrap()
+    );
+}
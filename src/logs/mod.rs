@@ -0,0 +1,115 @@
+use super::*;
+use assembler::MessageAssembler;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JSONLogError {
+    pub reason: String,
+}
+
+/// Records taken out of a log envelope, in file order
+pub type LogRecords = std::vec::IntoIter<JSONValue>;
+
+/// Entries of a HAR (HTTP Archive) file, from `log.entries`: one object per
+/// request with its `request`, `response` and `timings`.
+pub fn har_entries(input: &str) -> Result<LogRecords, JSONLogError> {
+    let document = parse(input)?;
+    let log = match member(document, "log")? {
+        log @ JSONValue::JSONObject(_) => log,
+        _ => return Err(make_err("Expected log to be an object".to_owned())),
+    };
+    return records(member(log, "entries")?, "log.entries");
+}
+
+/// Events of a CloudTrail log file, from its `Records`. Digest files, which
+/// have none, are an error.
+pub fn cloudtrail_records(input: &str) -> Result<LogRecords, JSONLogError> {
+    let document = parse(input)?;
+    return records(member(document, "Records")?, "Records");
+}
+
+/// Log events of CloudWatch Logs subscription batches, already decompressed,
+/// from `logEvents`. Batches may follow each other with or without whitespace
+/// in between, as Firehose writes them. Each event gets the `logGroup` and
+/// `logStream` of its batch, so it stands on its own; control messages, which
+/// only check the subscription, yield no events.
+pub fn cloudwatch_events(input: &str) -> Result<LogRecords, JSONLogError> {
+    let mut assembler = MessageAssembler::new();
+    let mut batches = assembler.push(input.as_bytes());
+    batches.extend(assembler.finish());
+    if batches.is_empty() {
+        return Err(make_err("Empty input".to_owned()));
+    }
+    let mut events = vec![];
+    for batch in batches {
+        let batch = match batch {
+            Ok(batch @ JSONValue::JSONObject(_)) => batch,
+            Ok(_) => return Err(make_err("Expected a batch object".to_owned())),
+            Err(err) => return Err(make_err(format!("Invalid JSON: {}", err.reason))),
+        };
+        if let JSONValue::JSONObject(ref map) = batch {
+            if let Some(JSONValue::JSONString(kind)) = map.get("messageType").map(|t| &**t) {
+                if kind == "CONTROL_MESSAGE" {
+                    continue;
+                }
+            }
+        }
+        let source: Vec<(String, JSONValue)> = ["logGroup", "logStream"]
+            .iter()
+            .filter_map(|&name| match batch {
+                JSONValue::JSONObject(ref map) => map
+                    .get(name)
+                    .map(|value| (name.to_owned(), (**value).clone())),
+                _ => None,
+            })
+            .collect();
+        for event in records(member(batch, "logEvents")?, "logEvents")? {
+            let mut event = match event {
+                JSONValue::JSONObject(event) => event,
+                _ => return Err(make_err("Expected log events to be objects".to_owned())),
+            };
+            for (name, value) in &source {
+                if !event.contains_key(name) {
+                    event.insert(name.clone(), Box::new(value.clone()));
+                }
+            }
+            events.push(JSONValue::JSONObject(event));
+        }
+    }
+    return Ok(events.into_iter());
+}
+
+fn parse(input: &str) -> Result<JSONValue, JSONLogError> {
+    match parser::parse_json(input) {
+        Ok(value) => return Ok(value),
+        Err(err) => return Err(make_err(format!("Invalid JSON: {}", err.reason))),
+    }
+}
+
+// Takes member `name` out of an object
+fn member(value: JSONValue, name: &str) -> Result<JSONValue, JSONLogError> {
+    let mut map = match value {
+        JSONValue::JSONObject(map) => map,
+        _ => return Err(make_err("Expected an object".to_owned())),
+    };
+    match map.remove(name) {
+        Some(value) => return Ok(*value),
+        None => return Err(make_err(format!("Missing {}", name))),
+    }
+}
+
+fn records(value: JSONValue, path: &str) -> Result<LogRecords, JSONLogError> {
+    match value {
+        JSONValue::JSONArray(items) => {
+            let items: Vec<JSONValue> = items.into_iter().map(|item| *item).collect();
+            return Ok(items.into_iter());
+        }
+        _ => return Err(make_err(format!("Expected {} to be an array", path))),
+    }
+}
+
+fn make_err(s: String) -> JSONLogError {
+    JSONLogError { reason: s }
+}
@@ -0,0 +1,89 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_records() {
+    let har = r#"{"log": {"version": "1.2", "creator": {"name": "x"}, "entries": [
+        {"request": {"method": "GET", "url": "http://a/"}, "response": {"status": 200}},
+        {"request": {"method": "POST", "url": "http://b/"}, "response": {"status": 500}}
+    ]}}"#;
+    let entries: Vec<JSONValue> = har_entries(har).unwrap().collect();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(
+        entries[1],
+        parse(
+            r#"{"request": {"method": "POST", "url": "http://b/"}, "response": {"status": 500}}"#
+        )
+    );
+
+    let trail = r#"{"Records": [{"eventName": "PutObject"}, {"eventName": "GetObject"}]}"#;
+    let records: Vec<JSONValue> = cloudtrail_records(trail).unwrap().collect();
+    assert_eq!(
+        records,
+        vec![
+            parse(r#"{"eventName": "PutObject"}"#),
+            parse(r#"{"eventName": "GetObject"}"#)
+        ]
+    );
+
+    let batches = r#"{"messageType": "DATA_MESSAGE", "logGroup": "g", "logStream": "s1",
+        "logEvents": [{"id": "1", "timestamp": 10, "message": "a"}, {"id": "2", "timestamp": 11, "message": "b"}]}{"messageType": "CONTROL_MESSAGE", "logGroup": "", "logEvents": [{"id": "", "message": "check"}]}
+        {"messageType": "DATA_MESSAGE", "logGroup": "g", "logStream": "s2", "logEvents": [{"id": "3", "timestamp": 12, "message": "c"}]}"#;
+    let events: Vec<JSONValue> = cloudwatch_events(batches).unwrap().collect();
+    assert_eq!(
+        events,
+        vec![
+            parse(
+                r#"{"id": "1", "timestamp": 10, "message": "a", "logGroup": "g", "logStream": "s1"}"#
+            ),
+            parse(
+                r#"{"id": "2", "timestamp": 11, "message": "b", "logGroup": "g", "logStream": "s1"}"#
+            ),
+            parse(
+                r#"{"id": "3", "timestamp": 12, "message": "c", "logGroup": "g", "logStream": "s2"}"#
+            ),
+        ]
+    );
+}
+
+#[test]
+fn test_errors() {
+    for (result, reason) in vec![
+        (har_entries(r#"{"log": {}}"#), "Missing entries"),
+        (
+            har_entries(r#"{"log": []}"#),
+            "Expected log to be an object",
+        ),
+        (
+            har_entries(r#"{"log": {"entries": {}}}"#),
+            "Expected log.entries to be an array",
+        ),
+        (har_entries(r#"[]"#), "Expected an object"),
+        (
+            har_entries(r#"{"#),
+            "Invalid JSON: String ended unexpectedly",
+        ),
+        (cloudtrail_records(r#"{"logFiles": []}"#), "Missing Records"),
+        (cloudwatch_events(""), "Empty input"),
+        (
+            cloudwatch_events(r#"{"messageType": "DATA_MESSAGE"}"#),
+            "Missing logEvents",
+        ),
+        (
+            cloudwatch_events(r#"{"logEvents": []} 1"#),
+            "Expected a batch object",
+        ),
+        (
+            cloudwatch_events(r#"{"logEvents": [1]}"#),
+            "Expected log events to be objects",
+        ),
+    ] {
+        println!("Checking {}", reason);
+        assert_eq!(result.unwrap_err().reason, reason);
+    }
+}
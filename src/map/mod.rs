@@ -0,0 +1,294 @@
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::ops::Index;
+use std::slice;
+use std::vec;
+
+#[cfg(test)]
+mod tests;
+
+/// The operations the crate needs from an object map, implemented for `HashMap`,
+/// `BTreeMap` and `OrderedMap`. `JSONValue` itself isn't generic over the map,
+/// since a type parameter would spread through every API in the crate; the map
+/// is chosen with the `preserve_order` and `btree_map` features instead (see `JSONMap`).
+/// Code that builds or inspects maps can use this trait to work with any of them.
+pub trait ObjectMap<V>: Default + IntoIterator<Item = (String, V)> {
+    fn insert(&mut self, key: String, value: V) -> Option<V>;
+    fn get(&self, key: &str) -> Option<&V>;
+    fn get_mut(&mut self, key: &str) -> Option<&mut V>;
+    fn remove(&mut self, key: &str) -> Option<V>;
+    fn len(&self) -> usize;
+    /// Keys in the map's iteration order
+    fn keys(&self) -> Vec<&str>;
+
+    fn contains_key(&self, key: &str) -> bool {
+        return self.get(key).is_some();
+    }
+
+    fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+}
+
+macro_rules! impl_object_map {
+    ($map:ident) => {
+        impl<V> ObjectMap<V> for $map<String, V> {
+            fn insert(&mut self, key: String, value: V) -> Option<V> {
+                return $map::insert(self, key, value);
+            }
+
+            fn get(&self, key: &str) -> Option<&V> {
+                return $map::get(self, key);
+            }
+
+            fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+                return $map::get_mut(self, key);
+            }
+
+            fn remove(&mut self, key: &str) -> Option<V> {
+                return $map::remove(self, key);
+            }
+
+            fn len(&self) -> usize {
+                return $map::len(self);
+            }
+
+            fn keys(&self) -> Vec<&str> {
+                return $map::keys(self).map(|key| key.as_str()).collect();
+            }
+        }
+    };
+}
+
+impl_object_map!(HashMap);
+impl_object_map!(BTreeMap);
+impl_object_map!(OrderedMap);
+
+/// Map that iterates in insertion order, with the subset of the `HashMap` API
+/// the crate uses on objects. Backs `JSONObject` with the `preserve_order` feature.
+/// Re-inserting an existing key keeps its original position; `remove` shifts
+/// the following entries, so it's O(n).
+#[derive(Clone)]
+pub struct OrderedMap<K, V> {
+    entries: Vec<(K, V)>,
+    index: HashMap<K, usize>,
+}
+
+impl<K: Hash + Eq + Clone, V> OrderedMap<K, V> {
+    pub fn new() -> OrderedMap<K, V> {
+        return OrderedMap {
+            entries: vec![],
+            index: HashMap::new(),
+        };
+    }
+
+    pub fn with_capacity(capacity: usize) -> OrderedMap<K, V> {
+        return OrderedMap {
+            entries: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+        };
+    }
+
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    /// Entries the map holds without reallocating
+    pub fn capacity(&self) -> usize {
+        return self.entries.capacity().min(self.index.capacity());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(&i) = self.index.get(&key) {
+            return Some(std::mem::replace(&mut self.entries[i].1, value));
+        }
+        self.index.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+        return None;
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let i = *self.index.get(key)?;
+        return Some(&self.entries[i].1);
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let i = *self.index.get(key)?;
+        return Some(&mut self.entries[i].1);
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        return self.index.contains_key(key);
+    }
+
+    /// Removes the key, keeping the order of the remaining entries.
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let i = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(i);
+        for (j, entry) in self.entries.iter().enumerate().skip(i) {
+            self.index.insert(entry.0.clone(), j);
+        }
+        return Some(value);
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        return Iter {
+            inner: self.entries.iter(),
+        };
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        return IterMut {
+            inner: self.entries.iter_mut(),
+        };
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        return self.entries.iter().map(|entry| &entry.0);
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        return self.entries.iter().map(|entry| &entry.1);
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        return self.entries.iter_mut().map(|entry| &mut entry.1);
+    }
+
+    /// Removes all entries in insertion order, keeping the allocations
+    pub fn drain(&mut self) -> vec::Drain<'_, (K, V)> {
+        self.index.clear();
+        return self.entries.drain(..);
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Default for OrderedMap<K, V> {
+    fn default() -> OrderedMap<K, V> {
+        return OrderedMap::new();
+    }
+}
+
+/// Equality ignores order, like it does for `HashMap`
+impl<K: Hash + Eq + Clone, V: PartialEq> PartialEq for OrderedMap<K, V> {
+    fn eq(&self, other: &OrderedMap<K, V>) -> bool {
+        return self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value));
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for OrderedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return f
+            .debug_map()
+            .entries(self.entries.iter().map(|entry| (&entry.0, &entry.1)))
+            .finish();
+    }
+}
+
+impl<K, Q, V> Index<&Q> for OrderedMap<K, V>
+where
+    K: Hash + Eq + Clone + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        return self.get(key).expect("key not found in OrderedMap");
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> FromIterator<(K, V)> for OrderedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> OrderedMap<K, V> {
+        let mut map = OrderedMap::new();
+        map.extend(iter);
+        return map;
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> Extend<(K, V)> for OrderedMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+pub struct Iter<'a, K: 'a, V: 'a> {
+    inner: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.inner.next().map(|entry| (&entry.0, &entry.1));
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        return self.inner.size_hint();
+    }
+}
+
+pub struct IterMut<'a, K: 'a, V: 'a> {
+    inner: slice::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.inner.next().map(|entry| (&entry.0, &mut entry.1));
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, V> IntoIterator for &'a OrderedMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        return self.iter();
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, V> IntoIterator for &'a mut OrderedMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        return self.iter_mut();
+    }
+}
+
+impl<K, V> IntoIterator for OrderedMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> vec::IntoIter<(K, V)> {
+        return self.entries.into_iter();
+    }
+}
@@ -0,0 +1,69 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use std::collections::{BTreeMap, HashMap};
+
+fn keys(map: &OrderedMap<String, i32>) -> Vec<&str> {
+    return map.keys().map(|k| k.as_str()).collect();
+}
+
+#[test]
+fn test_insertion_order() {
+    let mut map: OrderedMap<String, i32> = vec![("b", 1), ("a", 2), ("c", 3)]
+        .into_iter()
+        .map(|(k, v)| (k.to_owned(), v))
+        .collect();
+    assert_eq!(keys(&map), vec!["b", "a", "c"]);
+    assert_eq!(map.insert("a".to_owned(), 4), Some(2));
+    assert_eq!(keys(&map), vec!["b", "a", "c"]);
+    assert_eq!(map["a"], 4);
+    assert_eq!(map.remove("b"), Some(1));
+    assert_eq!(map.remove("b"), None);
+    assert_eq!(keys(&map), vec!["a", "c"]);
+    assert_eq!(map.get("c"), Some(&3));
+    *map.get_mut("c").unwrap() += 1;
+    map.insert("d".to_owned(), 5);
+    let entries: Vec<(String, i32)> = map.into_iter().collect();
+    assert_eq!(
+        entries,
+        vec![
+            ("a".to_owned(), 4),
+            ("c".to_owned(), 4),
+            ("d".to_owned(), 5)
+        ]
+    );
+}
+
+#[test]
+fn test_equality_ignores_order() {
+    let a: OrderedMap<&str, i32> = vec![("x", 1), ("y", 2)].into_iter().collect();
+    let b: OrderedMap<&str, i32> = vec![("y", 2), ("x", 1)].into_iter().collect();
+    let c: OrderedMap<&str, i32> = vec![("y", 2)].into_iter().collect();
+    assert_eq!(a, b);
+    assert_ne!(a, c);
+    assert_eq!(format!("{:?}", a), "{\"x\": 1, \"y\": 2}");
+}
+
+fn exercise<M: ObjectMap<i32>>() -> Vec<String> {
+    let mut map = M::default();
+    assert!(map.is_empty());
+    map.insert("b".to_owned(), 1);
+    map.insert("a".to_owned(), 2);
+    assert_eq!(map.insert("b".to_owned(), 3), Some(1));
+    *map.get_mut("a").unwrap() += 1;
+    assert_eq!(map.get("a"), Some(&3));
+    assert!(map.contains_key("b"));
+    map.insert("c".to_owned(), 4);
+    assert_eq!(map.remove("c"), Some(4));
+    assert_eq!(map.len(), 2);
+    return map.keys().into_iter().map(|k| k.to_owned()).collect();
+}
+
+#[test]
+fn test_object_map_trait() {
+    assert_eq!(exercise::<OrderedMap<String, i32>>(), vec!["b", "a"]);
+    assert_eq!(exercise::<BTreeMap<String, i32>>(), vec!["a", "b"]);
+    let mut keys = exercise::<HashMap<String, i32>>();
+    keys.sort();
+    assert_eq!(keys, vec!["a", "b"]);
+}
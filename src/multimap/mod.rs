@@ -0,0 +1,243 @@
+use super::*;
+use serializer::SerializerConfig;
+use std::io::Read;
+use stream::{Event, EventReader, EventWriter};
+
+#[cfg(test)]
+mod tests;
+
+/// A JSON value whose objects keep every member, repeated keys included, in
+/// input order. RFC 8259 leaves duplicate keys to the implementation, and the
+/// parser keeps only the last one; this keeps them all, for input from systems
+/// that repeat keys on purpose (e.g. one member per `Set-Cookie` header).
+#[derive(Debug, Clone, PartialEq)]
+pub enum MultiValue {
+    /// String, number, boolean or null
+    Scalar(JSONValue),
+    Array(Vec<MultiValue>),
+    Object(MultiMap),
+}
+
+/// Object members in input order, keys possibly repeated
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MultiMap {
+    entries: Vec<(String, MultiValue)>,
+}
+
+impl MultiMap {
+    pub fn new() -> MultiMap {
+        return MultiMap { entries: vec![] };
+    }
+
+    /// Number of members, repeated keys counted every time
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    /// The value of the last member with the key, the one the parser keeps
+    pub fn get(&self, key: &str) -> Option<&MultiValue> {
+        return self.entries.iter().rev().find(|e| e.0 == key).map(|e| &e.1);
+    }
+
+    /// Values of all members with the key, in input order
+    pub fn get_all(&self, key: &str) -> Vec<&MultiValue> {
+        return self
+            .entries
+            .iter()
+            .filter(|e| e.0 == key)
+            .map(|e| &e.1)
+            .collect();
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        return self.entries.iter().any(|e| e.0 == key);
+    }
+
+    /// Distinct keys in the order they first appear
+    pub fn keys(&self) -> Vec<&str> {
+        let mut keys: Vec<&str> = vec![];
+        for (key, _) in &self.entries {
+            if !keys.contains(&key.as_str()) {
+                keys.push(key);
+            }
+        }
+        return keys;
+    }
+
+    /// Keys that appear more than once
+    pub fn duplicate_keys(&self) -> Vec<&str> {
+        return self
+            .keys()
+            .into_iter()
+            .filter(|key| self.entries.iter().filter(|e| e.0 == *key).count() > 1)
+            .collect();
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, (String, MultiValue)> {
+        return self.entries.iter();
+    }
+
+    /// Adds a member at the end, keeping members with the same key
+    pub fn append(&mut self, key: String, value: MultiValue) {
+        self.entries.push((key, value));
+    }
+
+    /// Removes all members with the key, returning their values in order
+    pub fn remove_all(&mut self, key: &str) -> Vec<MultiValue> {
+        let (removed, kept) = std::mem::take(&mut self.entries)
+            .into_iter()
+            .partition(|e| e.0 == key);
+        self.entries = kept;
+        return removed
+            .into_iter()
+            .map(|e: (String, MultiValue)| e.1)
+            .collect();
+    }
+}
+
+impl MultiValue {
+    pub fn parse(input: &str) -> Result<MultiValue, JSONParseError> {
+        return MultiValue::parse_with(input, &ParserOptions::default());
+    }
+
+    /// Honors the options `EventReader` does: `max_input_bytes`, `max_depth`
+    /// and `max_string_length`.
+    pub fn parse_with(input: &str, options: &ParserOptions) -> Result<MultiValue, JSONParseError> {
+        let mut events = EventReader::with_options(input.as_bytes(), options);
+        let first = next(&mut events)?;
+        let value = read(&mut events, first)?;
+        // the reader fails on anything after the value
+        events.next_event()?;
+        return Ok(value);
+    }
+
+    pub fn from_value(value: &JSONValue) -> MultiValue {
+        match *value {
+            JSONValue::JSONArray(ref items) => {
+                return MultiValue::Array(items.iter().map(|v| MultiValue::from_value(v)).collect())
+            }
+            JSONValue::JSONObject(ref members) => {
+                let mut map = MultiMap::new();
+                for (key, member) in members.iter() {
+                    map.append(key.clone(), MultiValue::from_value(member));
+                }
+                return MultiValue::Object(map);
+            }
+            JSONValue::JSONRaw(_) => return MultiValue::from_value(&value.expand_raw()),
+            _ => return MultiValue::Scalar(value.clone()),
+        }
+    }
+
+    /// The value as the parser would have returned it: the last of repeated keys wins.
+    pub fn to_value(&self) -> JSONValue {
+        return self.convert(false);
+    }
+
+    /// Like `to_value`, but repeated keys become one member holding an array of
+    /// all their values, so nothing is lost. A key that's there once keeps its
+    /// value as it is, so `[1, 2]` may have been one member or two.
+    pub fn to_grouped_value(&self) -> JSONValue {
+        return self.convert(true);
+    }
+
+    /// JSON text with every member written, repeated keys included, in order.
+    pub fn to_string_with(&self, config: &SerializerConfig) -> String {
+        let mut writer = EventWriter::new(vec![], config);
+        // events of a complete value written to memory can't fail
+        self.write(&mut writer).unwrap();
+        return String::from_utf8(writer.finish().unwrap()).unwrap();
+    }
+
+    fn convert(&self, grouped: bool) -> JSONValue {
+        match *self {
+            MultiValue::Scalar(ref value) => return value.clone(),
+            MultiValue::Array(ref items) => {
+                return JSONValue::JSONArray(
+                    items
+                        .iter()
+                        .map(|item| Box::new(item.convert(grouped)))
+                        .collect(),
+                )
+            }
+            MultiValue::Object(ref map) => {
+                let mut members = JSONMap::new();
+                for key in map.keys() {
+                    let values = map.get_all(key);
+                    let value = if grouped && values.len() > 1 {
+                        JSONValue::JSONArray(
+                            values
+                                .iter()
+                                .map(|v| Box::new(v.convert(grouped)))
+                                .collect(),
+                        )
+                    } else {
+                        values.last().unwrap().convert(grouped)
+                    };
+                    members.insert(key.to_owned(), Box::new(value));
+                }
+                return JSONValue::JSONObject(members);
+            }
+        }
+    }
+
+    fn write(&self, writer: &mut EventWriter<Vec<u8>>) -> Result<(), stream::JSONStreamError> {
+        match *self {
+            MultiValue::Scalar(ref value) => {
+                let event = match *value {
+                    JSONValue::JSONBool(b) => Event::Bool(b),
+                    JSONValue::JSONNumber(n) => Event::number(n),
+                    JSONValue::JSONString(ref s) => Event::String(s.clone()),
+                    _ => Event::Null,
+                };
+                return writer.write(&event);
+            }
+            MultiValue::Array(ref items) => {
+                writer.write(&Event::StartArray)?;
+                for item in items {
+                    item.write(writer)?;
+                }
+                return writer.write(&Event::EndArray);
+            }
+            MultiValue::Object(ref map) => {
+                writer.write(&Event::StartObject)?;
+                for (key, value) in map.iter() {
+                    writer.write(&Event::Key(key.clone()))?;
+                    value.write(writer)?;
+                }
+                return writer.write(&Event::EndObject);
+            }
+        }
+    }
+}
+
+fn read<R: Read>(events: &mut EventReader<R>, first: Event) -> Result<MultiValue, JSONParseError> {
+    match first {
+        Event::StartArray => {
+            let mut items = vec![];
+            loop {
+                match next(events)? {
+                    Event::EndArray => return Ok(MultiValue::Array(items)),
+                    event => items.push(read(events, event)?),
+                }
+            }
+        }
+        Event::StartObject => {
+            let mut map = MultiMap::new();
+            while let Event::Key(key) = next(events)? {
+                let event = next(events)?;
+                map.append(key, read(events, event)?);
+            }
+            return Ok(MultiValue::Object(map));
+        }
+        event => return Ok(MultiValue::Scalar(events.read_value(event)?)),
+    }
+}
+
+fn next<R: Read>(events: &mut EventReader<R>) -> Result<Event, JSONParseError> {
+    // the reader fails on input ending inside a value
+    return Ok(events.next_event()?.expect("value is incomplete"));
+}
@@ -0,0 +1,79 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use serializer::SerializerConfig;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_parse() {
+    let input = r#"{"cookie": "a=1", "path": "/", "cookie": "b=2", "nested": [{"x": 1, "x": {"y": 2}}], "cookie": "c=3"}"#;
+    let value = MultiValue::parse(input).unwrap();
+    let map = match value {
+        MultiValue::Object(ref map) => map,
+        _ => panic!("Expected an object"),
+    };
+    assert_eq!(map.len(), 5);
+    assert_eq!(map.keys(), vec!["cookie", "path", "nested"]);
+    assert_eq!(map.duplicate_keys(), vec!["cookie"]);
+    assert_eq!(
+        map.get_all("cookie"),
+        vec![
+            &MultiValue::Scalar(parse(r#""a=1""#)),
+            &MultiValue::Scalar(parse(r#""b=2""#)),
+            &MultiValue::Scalar(parse(r#""c=3""#)),
+        ]
+    );
+    assert_eq!(
+        map.get("cookie"),
+        Some(&MultiValue::Scalar(parse(r#""c=3""#)))
+    );
+    assert_eq!(map.get("other"), None);
+    assert!(map.contains_key("path"));
+
+    assert_eq!(value.to_value(), parse(input));
+    assert_eq!(
+        value.to_grouped_value(),
+        parse(
+            r#"{"cookie": ["a=1", "b=2", "c=3"], "path": "/", "nested": [{"x": [1, {"y": 2}]}]}"#
+        )
+    );
+    assert_eq!(
+        value.to_string_with(&SerializerConfig::compact()),
+        input.replace(": ", ":").replace(", ", ",")
+    );
+    assert_eq!(
+        MultiValue::from_value(&parse(r#"{"a": [1, null, true]}"#)).to_value(),
+        parse(r#"{"a": [1, null, true]}"#)
+    );
+
+    let mut map = map.clone();
+    map.append("path".to_owned(), MultiValue::Scalar(parse("null")));
+    let removed = map.remove_all("cookie");
+    assert_eq!(removed.len(), 3);
+    assert_eq!(map.keys(), vec!["path", "nested"]);
+    assert_eq!(map.get("path"), Some(&MultiValue::Scalar(parse("null"))));
+}
+
+#[test]
+fn test_parse_errors() {
+    for (input, reason) in vec![
+        ("", ("String ended unexpectedly", Some(0))),
+        (r#"{"a": 1"#, ("String ended unexpectedly", Some(7))),
+        (
+            r#"{"a": 1} x"#,
+            ("Unexpected charachter x at position 9", Some(9)),
+        ),
+    ] {
+        println!("Checking {}", input);
+        let err = MultiValue::parse(input).unwrap_err();
+        assert_eq!((err.reason.as_str(), err.position), reason);
+    }
+    let options = ParserOptions {
+        max_depth: Some(1),
+        ..ParserOptions::default()
+    };
+    assert!(MultiValue::parse_with("[[1]]", &options).is_err());
+}
@@ -0,0 +1,266 @@
+use super::*;
+use serializer::SerializerConfig;
+use std::collections::BTreeMap;
+use std::io::BufRead;
+use std::panic;
+use std::str;
+use std::sync::{mpsc, Mutex, PoisonError};
+use std::thread;
+
+#[cfg(test)]
+mod tests;
+
+// Lines handed to a worker at once, so channel overhead stays small next to parsing
+const BATCH_LINES: usize = 256;
+
+/// Starts every record of a JSON text sequence
+pub const RECORD_SEPARATOR: char = '\u{1E}';
+
+/// Streams values out of newline-delimited JSON, one line at a time.
+/// Blank lines are skipped. Error positions are byte offsets from the start of the stream,
+/// so `JSONParseError::line_column` works against the whole input. A line that isn't
+/// valid UTF-8 is an error like a malformed one; an I/O error ends the stream.
+pub struct NdjsonReader<R> {
+    reader: R,
+    offset: usize,
+    line: Vec<u8>,
+    done: bool,
+}
+
+impl<R: BufRead> NdjsonReader<R> {
+    pub fn new(reader: R) -> NdjsonReader<R> {
+        return NdjsonReader {
+            reader,
+            offset: 0,
+            line: vec![],
+            done: false,
+        };
+    }
+}
+
+impl<R: BufRead> Iterator for NdjsonReader<R> {
+    type Item = Result<JSONValue, JSONParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            self.line.clear();
+            let read = match self.reader.read_until(b'\n', &mut self.line) {
+                Ok(read) => read,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(JSONParseError {
+                        reason: format!("Unable to read line: {}", e),
+                        position: Some(self.offset),
+                        kind: JSONParseErrorKind::Io,
+                    }));
+                }
+            };
+            if read == 0 {
+                return None;
+            }
+            let line_start = self.offset;
+            self.offset += read;
+            let line = match str::from_utf8(&self.line) {
+                Ok(line) => line,
+                Err(e) => {
+                    return Some(Err(JSONParseError {
+                        reason: "Invalid UTF-8".to_owned(),
+                        position: Some(line_start + e.valid_up_to()),
+                        kind: JSONParseErrorKind::Syntax,
+                    }))
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Some(line.parse().map_err(|mut err: JSONParseError| {
+                err.position = err.position.map(|p| p + line_start);
+                err
+            }));
+        }
+    }
+}
+
+type Batch = Vec<Result<(usize, String), JSONParseError>>;
+type WorkQueue = Mutex<Option<mpsc::Receiver<(usize, Batch)>>>;
+
+// Drops the work queue's receiver when a worker exits, panicking or not, so the
+// reader's sends fail instead of blocking on a queue nobody drains
+struct CloseOnExit<'a>(&'a WorkQueue);
+
+impl<'a> Drop for CloseOnExit<'a> {
+    fn drop(&mut self) {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner).take();
+    }
+}
+
+/// Parses NDJSON lines and applies `map` to them on `threads` worker threads
+/// (all available cores with 0), handing the results to `sink` in input order.
+/// Reading happens on its own thread and only a few batches of lines are in
+/// flight at a time, so memory stays bounded on inputs of any size. Blank lines
+/// are skipped and errors carry positions like `NdjsonReader`'s; reading stops
+/// at the first I/O error. If `map` panics, the remaining work is dropped and
+/// the panic is resumed on the calling thread.
+pub fn par_ndjson<R, T, F, S>(reader: R, threads: usize, map: F, mut sink: S)
+where
+    R: BufRead + Send,
+    T: Send,
+    F: Fn(JSONValue) -> T + Sync,
+    S: FnMut(Result<T, JSONParseError>),
+{
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let (work_tx, work_rx) = mpsc::sync_channel::<(usize, Batch)>(threads * 2);
+    let (done_tx, done_rx) = mpsc::sync_channel(threads * 2);
+    let work_rx: WorkQueue = Mutex::new(Some(work_rx));
+    thread::scope(|scope| {
+        let mut workers = vec![];
+        for _ in 0..threads {
+            let done_tx = done_tx.clone();
+            let (work_rx, map) = (&work_rx, &map);
+            workers.push(scope.spawn(move || {
+                let _close = CloseOnExit(work_rx);
+                loop {
+                    let job = match *work_rx.lock().unwrap() {
+                        Some(ref work_rx) => work_rx.recv(),
+                        None => return,
+                    };
+                    let (index, batch) = match job {
+                        Ok(job) => job,
+                        Err(_) => return,
+                    };
+                    let results: Vec<Result<T, JSONParseError>> = batch
+                        .into_iter()
+                        .map(|line| {
+                            let (offset, line) = line?;
+                            let value = line.parse().map_err(|mut err: JSONParseError| {
+                                err.position = err.position.map(|p| p + offset);
+                                err
+                            })?;
+                            return Ok(map(value));
+                        })
+                        .collect();
+                    if done_tx.send((index, results)).is_err() {
+                        return;
+                    }
+                }
+            }));
+        }
+        drop(done_tx);
+        scope.spawn(move || read_batches(reader, work_tx));
+
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        for (index, results) in done_rx {
+            pending.insert(index, results);
+            while let Some(results) = pending.remove(&next) {
+                results.into_iter().for_each(&mut sink);
+                next += 1;
+            }
+        }
+        for worker in workers {
+            if let Err(payload) = worker.join() {
+                panic::resume_unwind(payload);
+            }
+        }
+    });
+}
+
+fn read_batches<R: BufRead>(mut reader: R, work: mpsc::SyncSender<(usize, Batch)>) {
+    let mut offset = 0;
+    let mut index = 0;
+    let mut batch = vec![];
+    loop {
+        let mut line = String::new();
+        let failed = match reader.read_line(&mut line) {
+            Ok(0) => break,
+            Ok(read) => {
+                if !line.trim().is_empty() {
+                    batch.push(Ok((offset, line)));
+                }
+                offset += read;
+                false
+            }
+            Err(e) => {
+                batch.push(Err(JSONParseError {
+                    reason: format!("Unable to read line: {}", e),
+                    position: Some(offset),
+                    kind: JSONParseErrorKind::Io,
+                }));
+                true
+            }
+        };
+        if failed || batch.len() == BATCH_LINES {
+            if work.send((index, batch)).is_err() {
+                return;
+            }
+            index += 1;
+            batch = vec![];
+        }
+        if failed {
+            return;
+        }
+    }
+    if !batch.is_empty() {
+        let _ = work.send((index, batch));
+    }
+}
+
+/// Parses a whole NDJSON document, failing on the first invalid line.
+pub fn parse_ndjson(input: &str) -> Result<Vec<JSONValue>, JSONParseError> {
+    return NdjsonReader::new(input.as_bytes()).collect();
+}
+
+/// Parses a JSON text sequence (RFC 7464): values each preceded by an ASCII
+/// record separator (0x1E) and usually followed by a newline. Empty records are
+/// skipped; error positions are byte offsets into `input`.
+pub fn parse_json_seq(input: &str) -> Result<Vec<JSONValue>, JSONParseError> {
+    let mut values = vec![];
+    let mut offset = 0;
+    for (i, record) in input.split(RECORD_SEPARATOR).enumerate() {
+        let record_start = offset;
+        offset += record.len() + RECORD_SEPARATOR.len_utf8();
+        if record.trim().is_empty() {
+            continue;
+        }
+        if i == 0 {
+            return Err(JSONParseError {
+                reason: "Expected a record separator at position 0".to_owned(),
+                position: Some(0),
+                kind: JSONParseErrorKind::Syntax,
+            });
+        }
+        let value = record.parse().map_err(|mut err: JSONParseError| {
+            err.position = err.position.map(|p| p + record_start);
+            err
+        })?;
+        values.push(value);
+    }
+    return Ok(values);
+}
+
+/// Writes every value as a JSON text sequence record: separator, compact JSON, newline.
+pub fn to_json_seq(values: &[JSONValue]) -> String {
+    let mut result = String::new();
+    for value in values {
+        result.push(RECORD_SEPARATOR);
+        result.push_str(&value.to_string_with(&SerializerConfig::compact()));
+        result.push('\n');
+    }
+    return result;
+}
+
+/// Writes every value as compact JSON on its own line.
+pub fn to_ndjson(values: &[JSONValue]) -> String {
+    let mut result = String::new();
+    for value in values {
+        result.push_str(&value.to_string_with(&SerializerConfig::compact()));
+        result.push('\n');
+    }
+    return result;
+}
@@ -0,0 +1,151 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use std::io::{self, BufReader, Read};
+
+// Fails every read, like a broken pipe
+struct Broken;
+
+impl Read for Broken {
+    fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+        return Err(io::Error::other("broken"));
+    }
+}
+
+#[test]
+fn test_parse_ndjson() {
+    let values = parse_ndjson("{\"a\": 1}\n\n  [1, 2]\r\n\"x\"").unwrap();
+    assert_eq!(
+        values,
+        vec![
+            "{\"a\": 1}".parse().unwrap(),
+            "[1, 2]".parse().unwrap(),
+            JSONValue::JSONString("x".to_owned()),
+        ]
+    );
+    assert_eq!(parse_ndjson("").unwrap(), vec![]);
+}
+
+#[test]
+fn test_ndjson_error_position() {
+    let input = "{\"a\": 1}\n{\"a\": }\n";
+    let err = parse_ndjson(input).unwrap_err();
+    assert_eq!(err.position, Some(15));
+    assert_eq!(err.line_column(input), Some((2, 7)));
+}
+
+#[test]
+fn test_reader_continues_after_error() {
+    let results: Vec<bool> = NdjsonReader::new("1\nx\n2\n".as_bytes())
+        .map(|r| r.is_ok())
+        .collect();
+    assert_eq!(results, vec![true, false, true]);
+}
+
+#[test]
+fn test_reader_invalid_utf8() {
+    let results: Vec<Result<JSONValue, JSONParseError>> =
+        NdjsonReader::new(&b"1\n\"\xFF\"\n2\n"[..]).collect();
+    assert_eq!(results.len(), 3);
+    let err = results[1].as_ref().unwrap_err();
+    assert_eq!(err.reason, "Invalid UTF-8");
+    assert_eq!(err.position, Some(3));
+    assert_eq!(results[2].as_ref().unwrap(), &JSONValue::JSONNumber(2.0));
+}
+
+#[test]
+fn test_reader_stops_after_io_error() {
+    let mut reader = NdjsonReader::new(BufReader::new(Broken));
+    let err = reader.next().unwrap().unwrap_err();
+    assert_eq!(err.kind, JSONParseErrorKind::Io);
+    assert!(reader.next().is_none());
+}
+
+#[test]
+fn test_to_ndjson() {
+    let values = parse_ndjson("[1, 2]\n{\"a\": \"b\"}").unwrap();
+    assert_eq!(to_ndjson(&values), "[1,2]\n{\"a\":\"b\"}\n");
+}
+
+#[test]
+fn test_par_ndjson() {
+    let mut input = String::new();
+    for i in 0..1000 {
+        input.push_str(&format!("{{\"id\": {}}}\n", i));
+        if i % 100 == 0 {
+            input.push('\n');
+        }
+    }
+    input.push_str("{\"id\": }\n");
+    for threads in vec![0, 1, 4] {
+        println!("Checking {}", threads);
+        let mut results = vec![];
+        par_ndjson(
+            input.as_bytes(),
+            threads,
+            |value| value.pointer("/id").cloned(),
+            |result| results.push(result),
+        );
+        assert_eq!(results.len(), 1001);
+        for (i, result) in results[..1000].iter().enumerate() {
+            assert_eq!(
+                *result.as_ref().unwrap(),
+                Some(JSONValue::JSONNumber(i as f64))
+            );
+        }
+        let err = results[1000].as_ref().unwrap_err();
+        assert_eq!(err.line_column(&input), Some((1011, 8)));
+    }
+    let mut count = 0;
+    par_ndjson("".as_bytes(), 2, |_| (), |_| count += 1);
+    assert_eq!(count, 0);
+}
+
+#[test]
+fn test_par_ndjson_panic() {
+    let input: String = (0..10000).map(|i| format!("{}\n", i)).collect();
+    for threads in vec![1, 4] {
+        println!("Checking {}", threads);
+        let mut count = 0;
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| {
+            par_ndjson(
+                input.as_bytes(),
+                threads,
+                |value| {
+                    if value == JSONValue::JSONNumber(300.0) {
+                        panic!("bad record");
+                    }
+                },
+                |_| count += 1,
+            );
+        }));
+        let payload = result.unwrap_err();
+        assert_eq!(payload.downcast_ref::<&str>(), Some(&"bad record"));
+        assert!(count < 10000);
+    }
+}
+
+#[test]
+fn test_json_seq() {
+    let input = "\u{1E}{\"a\": 1}\n\u{1E}\u{1E}[1, 2]\n\u{1E}\"x\"";
+    let values = parse_json_seq(input).unwrap();
+    assert_eq!(
+        values,
+        vec![
+            "{\"a\": 1}".parse().unwrap(),
+            "[1, 2]".parse().unwrap(),
+            JSONValue::JSONString("x".to_owned()),
+        ]
+    );
+    assert_eq!(
+        to_json_seq(&values),
+        "\u{1E}{\"a\":1}\n\u{1E}[1,2]\n\u{1E}\"x\"\n"
+    );
+    assert_eq!(parse_json_seq("").unwrap(), vec![]);
+
+    let input = "\u{1E}1\n\u{1E}{\"a\": }\n";
+    let err = parse_json_seq(input).unwrap_err();
+    assert_eq!(err.position, Some(10));
+    assert_eq!(&input[10..11], "}");
+    assert_eq!(parse_json_seq("1\n\u{1E}2").unwrap_err().position, Some(0));
+}
@@ -0,0 +1,211 @@
+use super::*;
+use std::cmp::Ordering;
+use std::mem;
+
+// generated by tools/gen_normalize_tables.py, with several rows to a line
+#[rustfmt::skip]
+mod tables;
+#[cfg(test)]
+mod tests;
+
+use self::tables::{COMBINING_CLASSES, COMPOSITIONS, DECOMPOSITIONS};
+
+// Hangul syllables are composed algorithmically (Unicode 14.0, section 3.12)
+const HANGUL_BASE: u32 = 0xAC00;
+const HANGUL_COUNT: u32 = 11172;
+const LEAD_BASE: u32 = 0x1100;
+const LEAD_COUNT: u32 = 19;
+const VOWEL_BASE: u32 = 0x1161;
+const VOWEL_COUNT: u32 = 21;
+const TRAIL_BASE: u32 = 0x11A7;
+const TRAIL_COUNT: u32 = 28;
+const VOWEL_TRAIL_COUNT: u32 = VOWEL_COUNT * TRAIL_COUNT;
+
+/// Which strings `ParserOptions::normalization` and `JSONValue::normalize` bring to NFC
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Normalization {
+    #[default]
+    None,
+    /// Object keys only, so lookups match keys differing just in composition form
+    Keys,
+    /// Object keys and string values
+    KeysAndStrings,
+}
+
+impl Normalization {
+    pub fn keys(self) -> bool {
+        return self != Normalization::None;
+    }
+
+    pub fn strings(self) -> bool {
+        return self == Normalization::KeysAndStrings;
+    }
+}
+
+/// Brings a string to Unicode Normalization Form C, composing decomposed
+/// characters, like the `"e\u{301}"` macOS file systems produce, into `"é"`.
+/// Borrows the input when it's already normalized.
+pub fn nfc(s: &str) -> Cow<'_, str> {
+    if s.is_ascii() {
+        return Cow::Borrowed(s);
+    }
+    let mut chars = vec![];
+    for ch in s.chars() {
+        decompose(ch, &mut chars);
+    }
+    reorder(&mut chars);
+    let composed: String = compose(chars).into_iter().collect();
+    if composed == s {
+        return Cow::Borrowed(s);
+    }
+    return Cow::Owned(composed);
+}
+
+fn decompose(ch: char, result: &mut Vec<char>) {
+    let code = ch as u32;
+    if (HANGUL_BASE..HANGUL_BASE + HANGUL_COUNT).contains(&code) {
+        let index = code - HANGUL_BASE;
+        result.push(to_char(LEAD_BASE + index / VOWEL_TRAIL_COUNT));
+        result.push(to_char(
+            VOWEL_BASE + index % VOWEL_TRAIL_COUNT / TRAIL_COUNT,
+        ));
+        if !index.is_multiple_of(TRAIL_COUNT) {
+            result.push(to_char(TRAIL_BASE + index % TRAIL_COUNT));
+        }
+        return;
+    }
+    match DECOMPOSITIONS.binary_search_by(|entry| entry.0.cmp(&ch)) {
+        Ok(i) => {
+            let (_, first, second) = DECOMPOSITIONS[i];
+            decompose(first, result);
+            if second != '\0' {
+                decompose(second, result);
+            }
+        }
+        Err(_) => result.push(ch),
+    }
+}
+
+// Sorts every run of non-starters by combining class, keeping the order of equal ones
+fn reorder(chars: &mut [char]) {
+    let mut start = 0;
+    while start < chars.len() {
+        if combining_class(chars[start]) == 0 {
+            start += 1;
+            continue;
+        }
+        let mut end = start;
+        while end < chars.len() && combining_class(chars[end]) != 0 {
+            end += 1;
+        }
+        chars[start..end].sort_by_key(|&ch| combining_class(ch));
+        start = end;
+    }
+}
+
+fn compose(chars: Vec<char>) -> Vec<char> {
+    let mut result: Vec<char> = Vec::with_capacity(chars.len());
+    let mut starter: Option<usize> = None;
+    // Combining class of the last char after the starter, None when there's none
+    let mut last_class: Option<u8> = None;
+    for ch in chars {
+        let class = combining_class(ch);
+        if let Some(i) = starter {
+            let blocked = match last_class {
+                None => false,
+                Some(last) => last >= class,
+            };
+            if !blocked {
+                if let Some(composed) = compose_pair(result[i], ch) {
+                    result[i] = composed;
+                    continue;
+                }
+            }
+        }
+        if class == 0 {
+            starter = Some(result.len());
+            last_class = None;
+        } else {
+            last_class = Some(class);
+        }
+        result.push(ch);
+    }
+    return result;
+}
+
+fn compose_pair(first: char, second: char) -> Option<char> {
+    let (a, b) = (first as u32, second as u32);
+    if (LEAD_BASE..LEAD_BASE + LEAD_COUNT).contains(&a)
+        && (VOWEL_BASE..VOWEL_BASE + VOWEL_COUNT).contains(&b)
+    {
+        let index = (a - LEAD_BASE) * VOWEL_TRAIL_COUNT + (b - VOWEL_BASE) * TRAIL_COUNT;
+        return Some(to_char(HANGUL_BASE + index));
+    }
+    if (HANGUL_BASE..HANGUL_BASE + HANGUL_COUNT).contains(&a)
+        && (a - HANGUL_BASE).is_multiple_of(TRAIL_COUNT)
+        && (TRAIL_BASE + 1..TRAIL_BASE + TRAIL_COUNT).contains(&b)
+    {
+        return Some(to_char(a + b - TRAIL_BASE));
+    }
+    return COMPOSITIONS
+        .binary_search_by(|entry| (entry.0, entry.1).cmp(&(first, second)))
+        .ok()
+        .map(|i| COMPOSITIONS[i].2);
+}
+
+fn combining_class(ch: char) -> u8 {
+    if ch.is_ascii() {
+        return 0;
+    }
+    match COMBINING_CLASSES.binary_search_by(|&(start, end, _)| {
+        if end < ch {
+            Ordering::Less
+        } else if start > ch {
+            Ordering::Greater
+        } else {
+            Ordering::Equal
+        }
+    }) {
+        Ok(i) => return COMBINING_CLASSES[i].2,
+        Err(_) => return 0,
+    }
+}
+
+// Only called with code points computed from valid Hangul ranges
+fn to_char(code: u32) -> char {
+    return char::from_u32(code).expect("Hangul code points are valid chars");
+}
+
+impl JSONValue {
+    /// Brings object keys, and string values if asked, to NFC in the whole tree.
+    /// When several keys of an object normalize to the same one, the value of the
+    /// key iterated last is kept. Raw fragments are left as they are.
+    pub fn normalize(&mut self, normalization: Normalization) {
+        if normalization == Normalization::None {
+            return;
+        }
+        match *self {
+            JSONValue::JSONString(ref mut s) if normalization.strings() => {
+                if let Cow::Owned(normalized) = nfc(s) {
+                    *s = normalized;
+                }
+            }
+            JSONValue::JSONArray(ref mut items) => {
+                for item in items.iter_mut() {
+                    item.normalize(normalization);
+                }
+            }
+            JSONValue::JSONObject(ref mut members) => {
+                for (key, mut value) in mem::take(members) {
+                    value.normalize(normalization);
+                    let key = match nfc(&key) {
+                        Cow::Owned(normalized) => normalized,
+                        Cow::Borrowed(_) => key,
+                    };
+                    members.insert(key, value);
+                }
+            }
+            _ => (),
+        }
+    }
+}
@@ -0,0 +1,69 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+#[test]
+fn test_nfc() {
+    for s in vec![
+        ("plain", "plain"),
+        ("e\u{301}", "\u{E9}"),
+        ("\u{E9}", "\u{E9}"),
+        ("Zoe\u{308} Cafe\u{301}", "Zo\u{EB} Caf\u{E9}"),
+        // marks are reordered by combining class before composing
+        ("a\u{323}\u{302}", "\u{1EAD}"),
+        ("a\u{302}\u{323}", "\u{1EAD}"),
+        // singleton decompositions never come back
+        ("\u{212B}", "\u{C5}"),
+        ("\u{2126}", "\u{3A9}"),
+        // a mark with the same class blocks the second one
+        ("a\u{301}\u{301}", "\u{E1}\u{301}"),
+        // composition exclusions stay decomposed
+        ("\u{915}\u{93C}", "\u{915}\u{93C}"),
+        ("\u{958}", "\u{915}\u{93C}"),
+        ("\u{1100}\u{1161}\u{11A8}", "\u{AC01}"),
+        ("\u{AC00}\u{11A8}", "\u{AC01}"),
+        ("\u{D4DB}", "\u{D4DB}"),
+        ("\u{1F600}", "\u{1F600}"),
+    ] {
+        println!("Checking {:?}", s.0);
+        assert_eq!(nfc(s.0), s.1);
+    }
+    assert!(matches!(nfc("Caf\u{E9}"), Cow::Borrowed(_)));
+    assert!(matches!(nfc("Cafe\u{301}"), Cow::Owned(_)));
+}
+
+#[test]
+fn test_normalize() {
+    let text = "{\"Cafe\u{301}\": {\"Zoe\u{308}\": [\"e\u{301}\"]}, \"x\": \"e\u{301}\"}";
+    for s in vec![
+        (
+            Normalization::None,
+            "{\"Cafe\u{301}\": {\"Zoe\u{308}\": [\"e\u{301}\"]}, \"x\": \"e\u{301}\"}",
+        ),
+        (
+            Normalization::Keys,
+            "{\"Caf\u{E9}\": {\"Zo\u{EB}\": [\"e\u{301}\"]}, \"x\": \"e\u{301}\"}",
+        ),
+        (
+            Normalization::KeysAndStrings,
+            "{\"Caf\u{E9}\": {\"Zo\u{EB}\": [\"\u{E9}\"]}, \"x\": \"\u{E9}\"}",
+        ),
+    ] {
+        println!("Checking {:?}", s.0);
+        let expected: JSONValue = s.1.parse().unwrap();
+        let mut value: JSONValue = text.parse().unwrap();
+        value.normalize(s.0);
+        assert_eq!(value, expected);
+        let options = ParserOptions {
+            normalization: s.0,
+            ..ParserOptions::default()
+        };
+        assert_eq!(JSONValue::parse_with(text, &options).unwrap(), expected);
+    }
+    let options = ParserOptions {
+        normalization: Normalization::Keys,
+        ..ParserOptions::default()
+    };
+    let value = JSONValue::parse_with("{\"e\u{301}\": 1, \"\u{E9}\": 2}", &options).unwrap();
+    assert_eq!(value, "{\"\u{E9}\": 2}".parse().unwrap());
+}
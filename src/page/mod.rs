@@ -0,0 +1,177 @@
+use super::*;
+use encoding::{base64url_decode, base64url_encode};
+use query::compare;
+use serializer::SerializerConfig;
+use std::cmp::Ordering;
+
+#[cfg(test)]
+mod tests;
+
+// Longer tokens are refused before decoding; keys of real pages are far shorter
+const MAX_CURSOR_LENGTH: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct JSONPageError {
+    pub reason: String,
+}
+
+/// Where the next page starts. Encoded into an opaque token to hand to clients,
+/// base64url of `{"offset": 20}` or `{"after": <key>}`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Cursor {
+    /// Index of the first item
+    Offset(usize),
+    /// Key of the last item of the previous page, for keyset pagination
+    After(JSONValue),
+}
+
+impl Cursor {
+    pub fn encode(&self) -> String {
+        let mut map = JSONMap::new();
+        match *self {
+            Cursor::Offset(offset) => map.insert(
+                "offset".to_owned(),
+                Box::new(JSONValue::JSONNumber(offset as f64)),
+            ),
+            Cursor::After(ref key) => map.insert("after".to_owned(), Box::new(key.clone())),
+        };
+        let json = JSONValue::JSONObject(map).to_string_with(&SerializerConfig::compact());
+        return base64url_encode(json.as_bytes());
+    }
+
+    /// Reads a token made by `encode`. Tokens come from clients, so they're
+    /// parsed with `ParserOptions::untrusted()` limits, and ones longer than
+    /// 4096 bytes are refused.
+    pub fn decode(token: &str) -> Result<Cursor, JSONPageError> {
+        if token.len() > MAX_CURSOR_LENGTH {
+            return Err(make_err(format!(
+                "Cursor longer than {} bytes",
+                MAX_CURSOR_LENGTH
+            )));
+        }
+        let invalid = || make_err(format!("Invalid cursor {}", token));
+        let json = base64url_decode(token)
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or_else(invalid)?;
+        let mut map = match JSONValue::parse_with(&json, &ParserOptions::untrusted()) {
+            Ok(JSONValue::JSONObject(map)) if map.len() == 1 => map,
+            _ => return Err(invalid()),
+        };
+        if let Some(key) = map.remove("after") {
+            return Ok(Cursor::After(*key));
+        }
+        match map.remove("offset").map(|offset| *offset) {
+            Some(JSONValue::JSONNumber(offset)) if offset >= 0.0 && offset.fract() == 0.0 => {
+                return Ok(Cursor::Offset(offset as usize))
+            }
+            _ => return Err(invalid()),
+        }
+    }
+}
+
+/// A slice of an array with the token for the page after it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page<'a> {
+    pub items: &'a [Box<JSONValue>],
+    /// `None` on the last page
+    pub next: Option<String>,
+    /// Length of the whole array
+    pub total: usize,
+}
+
+impl<'a> Page<'a> {
+    /// `{"items": [...], "next": "..." or null, "total": 42}`
+    pub fn to_json(&self) -> JSONValue {
+        let mut map = JSONMap::new();
+        map.insert(
+            "items".to_owned(),
+            Box::new(JSONValue::JSONArray(self.items.to_vec())),
+        );
+        let next = match self.next {
+            Some(ref next) => JSONValue::JSONString(next.clone()),
+            None => JSONValue::JSONNull(),
+        };
+        map.insert("next".to_owned(), Box::new(next));
+        map.insert(
+            "total".to_owned(),
+            Box::new(JSONValue::JSONNumber(self.total as f64)),
+        );
+        return JSONValue::JSONObject(map);
+    }
+}
+
+impl JSONValue {
+    /// Takes up to `limit` items of an array, starting at `cursor` (a token of a
+    /// previous page, `None` for the first). Offsets shift when items are added
+    /// or removed in between; `page_by_key` doesn't have that problem.
+    pub fn page(&self, cursor: Option<&str>, limit: usize) -> Result<Page<'_>, JSONPageError> {
+        let items = array_items(self)?;
+        let start = match cursor.map(Cursor::decode) {
+            None => 0,
+            Some(Ok(Cursor::Offset(offset))) => offset.min(items.len()),
+            Some(Ok(Cursor::After(_))) => {
+                return Err(make_err("Expected an offset cursor".to_owned()))
+            }
+            Some(Err(err)) => return Err(err),
+        };
+        let end = start.saturating_add(limit).min(items.len());
+        let next = if end < items.len() {
+            Some(Cursor::Offset(end).encode())
+        } else {
+            None
+        };
+        return Ok(Page {
+            items: &items[start..end],
+            next,
+            total: items.len(),
+        });
+    }
+
+    /// Keyset pagination over an array sorted by the value at `pointer` in each
+    /// item (ascending, in the order of `query::compare`; missing keys count as
+    /// null). A page starts after the key of the cursor, so it stays stable when
+    /// items are added or removed before it. Keys should be unique: items sharing
+    /// the last key of a page are skipped.
+    pub fn page_by_key(
+        &self,
+        pointer: &str,
+        cursor: Option<&str>,
+        limit: usize,
+    ) -> Result<Page<'_>, JSONPageError> {
+        let items = array_items(self)?;
+        let null = JSONValue::JSONNull();
+        let key = |item: &'_ JSONValue| item.pointer(pointer).unwrap_or(&null).clone();
+        let start = match cursor.map(Cursor::decode) {
+            None => 0,
+            Some(Ok(Cursor::After(last))) => {
+                items.partition_point(|item| compare(&key(item), &last) != Ordering::Greater)
+            }
+            Some(Ok(Cursor::Offset(_))) => {
+                return Err(make_err("Expected a key cursor".to_owned()))
+            }
+            Some(Err(err)) => return Err(err),
+        };
+        let end = start.saturating_add(limit).min(items.len());
+        let next = if end < items.len() && end > start {
+            Some(Cursor::After(key(&items[end - 1])).encode())
+        } else {
+            None
+        };
+        return Ok(Page {
+            items: &items[start..end],
+            next,
+            total: items.len(),
+        });
+    }
+}
+
+fn array_items(value: &JSONValue) -> Result<&[Box<JSONValue>], JSONPageError> {
+    match *value {
+        JSONValue::JSONArray(ref items) => return Ok(items),
+        _ => return Err(make_err("Only arrays can be paginated".to_owned())),
+    }
+}
+
+fn make_err(s: String) -> JSONPageError {
+    JSONPageError { reason: s }
+}
@@ -0,0 +1,124 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use encoding::base64url_encode;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_cursor() {
+    for cursor in vec![
+        Cursor::Offset(0),
+        Cursor::Offset(20),
+        Cursor::After(parse(r#""2024-01-01""#)),
+        Cursor::After(parse(r#"[3, "x"]"#)),
+        Cursor::After(JSONValue::JSONNull()),
+    ] {
+        println!("Checking {:?}", cursor);
+        let token = cursor.encode();
+        assert!(token
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_'));
+        assert_eq!(Cursor::decode(&token).unwrap(), cursor);
+    }
+    for token in vec![
+        "",
+        "!!",
+        "e30",
+        "eyJvZmZzZXQiOi0xfQ",
+        "eyJvZmZzZXQiOjEuNX0",
+        "WzFd",
+    ] {
+        println!("Checking {}", token);
+        assert_eq!(
+            Cursor::decode(token).unwrap_err().reason,
+            format!("Invalid cursor {}", token)
+        );
+    }
+    let nested = format!("{{\"after\":{}{}}}", "[".repeat(1500), "]".repeat(1500));
+    let token = base64url_encode(nested.as_bytes());
+    assert!(token.len() <= 4096);
+    assert_eq!(
+        Cursor::decode(&token).unwrap_err().reason,
+        format!("Invalid cursor {}", token)
+    );
+    let long = Cursor::After(JSONValue::JSONString("x".repeat(4000))).encode();
+    assert_eq!(
+        Cursor::decode(&long).unwrap_err().reason,
+        "Cursor longer than 4096 bytes"
+    );
+}
+
+#[test]
+fn test_page() {
+    let value = parse("[1, 2, 3, 4, 5]");
+    let mut pages = vec![];
+    let mut cursor = None;
+    loop {
+        let page = value.page(cursor.as_deref(), 2).unwrap();
+        assert_eq!(page.total, 5);
+        pages.push(page.to_json().pointer("/items").unwrap().clone());
+        cursor = page.next;
+        if cursor.is_none() {
+            break;
+        }
+    }
+    assert_eq!(pages, vec![parse("[1, 2]"), parse("[3, 4]"), parse("[5]")]);
+
+    let past = Cursor::Offset(9).encode();
+    let page = value.page(Some(&past), 2).unwrap();
+    assert_eq!(
+        page.to_json(),
+        parse(r#"{"items": [], "next": null, "total": 5}"#)
+    );
+    assert_eq!(
+        value.page(None, 0).unwrap().next,
+        Some(Cursor::Offset(0).encode())
+    );
+
+    let key = Cursor::After(JSONValue::JSONNumber(1.0)).encode();
+    for (value, cursor, reason) in vec![
+        (parse("{}"), None, "Only arrays can be paginated"),
+        (parse("[]"), Some(key.as_str()), "Expected an offset cursor"),
+        (parse("[]"), Some("x"), "Invalid cursor x"),
+    ] {
+        println!("Checking {}", reason);
+        assert_eq!(value.page(cursor, 2).unwrap_err().reason, reason);
+    }
+}
+
+#[test]
+fn test_page_by_key() {
+    let mut value = parse(r#"[{"id": 1}, {"id": 3}, {"id": 5}, {"id": 7}, {"id": 9}]"#);
+    let first = value.page_by_key("/id", None, 2).unwrap();
+    assert_eq!(first.items.len(), 2);
+    let cursor = first.next.unwrap();
+    assert_eq!(
+        Cursor::decode(&cursor).unwrap(),
+        Cursor::After(JSONValue::JSONNumber(3.0))
+    );
+
+    // items added before the cursor don't shift the next page
+    if let JSONValue::JSONArray(ref mut items) = value {
+        items.insert(0, Box::new(parse(r#"{"id": 0}"#)));
+        items.insert(2, Box::new(parse(r#"{"id": 2}"#)));
+    }
+    let second = value.page_by_key("/id", Some(&cursor), 2).unwrap();
+    assert_eq!(
+        second.to_json().pointer("/items").unwrap(),
+        &parse(r#"[{"id": 5}, {"id": 7}]"#)
+    );
+    let third = value.page_by_key("/id", second.next.as_deref(), 2).unwrap();
+    assert_eq!(
+        third.to_json().pointer("/items").unwrap(),
+        &parse(r#"[{"id": 9}]"#)
+    );
+    assert_eq!(third.next, None);
+    assert_eq!(third.total, 7);
+
+    let offset = Cursor::Offset(1).encode();
+    let err = value.page_by_key("/id", Some(&offset), 2).unwrap_err();
+    assert_eq!(err.reason, "Expected a key cursor");
+}
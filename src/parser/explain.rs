@@ -0,0 +1,68 @@
+use super::*;
+
+/// One decision of the parser while reading a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExplainStep {
+    /// Byte offset the decision was made at
+    pub position: usize,
+    /// Nesting depth of arrays and objects at that point
+    pub depth: usize,
+    pub decision: String,
+}
+
+/// What `explain_parse` found: every decision up to the end or the error, and
+/// the result of the parse.
+#[derive(Debug, Clone)]
+pub struct ParseExplanation {
+    pub steps: Vec<ExplainStep>,
+    pub result: Result<JSONValue, JSONParseError>,
+}
+
+/// Parses like `JSONValue::parse_with`, recording every grammar decision with
+/// its position: what each character started, every key, where containers
+/// ended and what was expected where the input went wrong. Meant for figuring
+/// out why almost-JSON doesn't parse; it's much slower than a plain parse.
+pub fn explain_parse(input: &str, options: &ParserOptions) -> ParseExplanation {
+    let mut state = ParseState::new(options);
+    state.explain = Some(vec![]);
+    let result = parse_document(&mut StrSource::new(input), &mut state);
+    return ParseExplanation {
+        steps: state.explain.unwrap_or_default(),
+        result,
+    };
+}
+
+impl ParseExplanation {
+    /// One line per step, `position: decision` indented by depth, then the
+    /// outcome. Errors with a position show the line of `input` they're on
+    /// with a caret under the character.
+    pub fn render(&self, input: &str) -> String {
+        let mut lines = vec![];
+        let width = input.len().to_string().len();
+        for step in &self.steps {
+            lines.push(format!(
+                "{:>width$}: {}{}",
+                step.position,
+                "  ".repeat(step.depth),
+                step.decision,
+                width = width
+            ));
+        }
+        match self.result {
+            Ok(_) => lines.push("ok".to_owned()),
+            Err(ref err) => {
+                lines.push(format!("error: {}", err.reason));
+                if let Some(position) = err.position.filter(|&p| input.is_char_boundary(p)) {
+                    let line_start = input[..position].rfind('\n').map_or(0, |i| i + 1);
+                    let line_end = input[position..]
+                        .find('\n')
+                        .map_or(input.len(), |i| position + i);
+                    let column = input[line_start..position].chars().count();
+                    lines.push(format!("  {}", &input[line_start..line_end]));
+                    lines.push(format!("  {}^", " ".repeat(column)));
+                }
+            }
+        }
+        return lines.join("\n");
+    }
+}
@@ -1,11 +1,16 @@
 use super::*;
+use normalize::{nfc, Normalization};
+use pool::Alloc;
 use std::char;
-use std::iter::Peekable;
-use std::str::CharIndices;
+use std::mem::size_of;
+use std::time::{Duration, Instant};
 
+mod explain;
 #[cfg(test)]
 mod tests;
 
+pub use self::explain::{explain_parse, ExplainStep, ParseExplanation};
+
 const ESCAPE: char = '\\';
 const OBJECT_START: char = '{';
 const OBJECT_END: char = '}';
@@ -27,102 +32,594 @@ const BOOL_FALSE: &str = "false";
 const ESCAPABLE: &str = "\"\\/fnrtb";
 
 const ERROR_ENDED_UNEXPECTEDLY: &str = "String ended unexpectedly";
+// Reading the clock on every step would dominate parsing time
+const STEPS_PER_CLOCK_CHECK: usize = 1024;
+
+/// Limits enforced while parsing, normalization of the parsed strings and
+/// relaxations of RFC 8259. Every limit and relaxation is off by default;
+/// `ParserOptions::untrusted()` is a conservative profile for input from outside.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ParserOptions {
+    /// Maximum input length in bytes, checked before parsing starts
+    pub max_input_bytes: Option<usize>,
+    /// Maximum number of nested arrays and objects
+    pub max_depth: Option<usize>,
+    /// Maximum length of a single string or key in bytes, after unescaping
+    pub max_string_length: Option<usize>,
+    /// Maximum number of items in one array or members in one object, duplicates included
+    pub max_container_entries: Option<usize>,
+    /// Maximum number of distinct keys in one object. Objects are hash maps with
+    /// randomly seeded SipHash, so colliding keys can't be precomputed; this caps
+    /// the size of every map built from the input on top of that.
+    pub max_object_keys: Option<usize>,
+    /// Maximum number of values in the whole document, containers included
+    pub max_nodes: Option<usize>,
+    /// Approximate heap budget for the value tree in bytes: boxed values,
+    /// string and key contents, and container slots. Allocator and hash table
+    /// overhead beyond that isn't counted.
+    pub max_allocated_bytes: Option<usize>,
+    /// Wall-clock limit for the whole parse, checked every few hundred values or string chars
+    pub timeout: Option<Duration>,
+    /// Strings brought to Unicode NFC as they're parsed. Keys that differ only
+    /// in composition form become duplicates, and the last one wins.
+    pub normalization: Normalization,
+    /// Stop after the first value and ignore whatever follows it,
+    /// instead of failing on anything but whitespace
+    pub allow_trailing_data: bool,
+    /// Skip any Unicode whitespace between tokens, not only the
+    /// space, tab, line feed and carriage return RFC 8259 allows
+    pub allow_unicode_whitespace: bool,
+    /// Accept numbers with a dot but no fraction digits, like `1.` and `1.e5`
+    pub allow_empty_fraction: bool,
+    /// Fail unless the document is an object, before parsing any of it. Setting
+    /// this and `require_array_root` together accepts either.
+    pub require_object_root: bool,
+    /// Fail unless the document is an array, before parsing any of it
+    pub require_array_root: bool,
+    /// What numbers beyond the range of `f64`, like `1e400`, become
+    pub number_overflow: NumberOverflow,
+    /// Fail on numbers too small for `f64`, like `1e-400`, instead of reading them as 0
+    pub reject_number_underflow: bool,
+    /// Read `-0`, `-0.0` and the like as 0 instead of negative zero
+    pub normalize_negative_zero: bool,
+}
+
+/// Numbers are converted to the nearest `f64` (ties to even), whatever the
+/// number of digits and independent of the locale. Those beyond its range
+/// round to infinity by default, which the serializer writes as `null`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberOverflow {
+    #[default]
+    Infinity,
+    /// The largest finite `f64` of the same sign
+    Max,
+    Error,
+}
+
+impl ParserOptions {
+    pub fn untrusted() -> ParserOptions {
+        return ParserOptions {
+            max_input_bytes: Some(10 * 1024 * 1024),
+            max_depth: Some(128),
+            max_string_length: Some(1024 * 1024),
+            max_container_entries: Some(100_000),
+            max_object_keys: Some(10_000),
+            max_nodes: Some(1_000_000),
+            max_allocated_bytes: Some(128 * 1024 * 1024),
+            timeout: Some(Duration::from_secs(1)),
+            normalization: Normalization::None,
+            allow_trailing_data: false,
+            allow_unicode_whitespace: false,
+            allow_empty_fraction: false,
+            require_object_root: false,
+            require_array_root: false,
+            number_overflow: NumberOverflow::Infinity,
+            reject_number_underflow: false,
+            normalize_negative_zero: false,
+        };
+    }
+}
+
+/// Characters the parser reads, with their byte offsets. Implement it to parse
+/// from inputs other than `&str` (byte buffers, readers, chunked or mapped
+/// input) with the same parsing core; see `JSONValue::parse_source`.
+pub trait Source {
+    /// Next char and its offset, without consuming it
+    fn peek(&mut self) -> Option<(usize, char)>;
+    fn next(&mut self) -> Option<(usize, char)>;
+    /// Offset of the next char, or the end of the input once it's read
+    fn pos(&self) -> usize;
+    /// Length of the whole input when known upfront, to check
+    /// `ParserOptions::max_input_bytes` before parsing
+    fn total_bytes(&self) -> Option<usize> {
+        return None;
+    }
+}
+
+/// `Source` over a string, what `str::parse` and `JSONValue::parse_with` use
+pub struct StrSource<'a> {
+    input: &'a str,
+    position: usize,
+}
+
+impl<'a> StrSource<'a> {
+    pub fn new(input: &'a str) -> StrSource<'a> {
+        return StrSource { input, position: 0 };
+    }
+}
+
+impl<'a> Source for StrSource<'a> {
+    fn peek(&mut self) -> Option<(usize, char)> {
+        return self.input[self.position..]
+            .chars()
+            .next()
+            .map(|ch| (self.position, ch));
+    }
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        let next = self.peek();
+        if let Some((_, ch)) = next {
+            self.position += ch.len_utf8();
+        }
+        return next;
+    }
+
+    fn pos(&self) -> usize {
+        return self.position;
+    }
+
+    fn total_bytes(&self) -> Option<usize> {
+        return Some(self.input.len());
+    }
+}
+
+struct ParseState<'a> {
+    options: ParserOptions,
+    depth: usize,
+    nodes: usize,
+    allocated: usize,
+    deadline: Option<Instant>,
+    steps: usize,
+    // grammar decisions, recorded by `explain_parse` only
+    explain: Option<Vec<ExplainStep>>,
+    // where containers come from, set by `JSONValue::parse_in`
+    alloc: Option<&'a mut dyn Alloc>,
+}
+
+impl<'a> ParseState<'a> {
+    fn new(options: &ParserOptions) -> ParseState<'a> {
+        return ParseState {
+            options: *options,
+            depth: 0,
+            nodes: 0,
+            allocated: 0,
+            deadline: options.timeout.map(|timeout| Instant::now() + timeout),
+            steps: 0,
+            explain: None,
+            alloc: None,
+        };
+    }
+
+    // The decision is only formatted when explaining
+    fn note<F: FnOnce() -> String>(&mut self, position: usize, decision: F) {
+        if let Some(ref mut steps) = self.explain {
+            steps.push(ExplainStep {
+                position,
+                depth: self.depth,
+                decision: decision(),
+            });
+        }
+    }
+
+    fn take_string(&mut self) -> String {
+        match self.alloc {
+            Some(ref mut alloc) => return alloc.take_string(),
+            None => return String::new(),
+        }
+    }
+
+    fn take_array(&mut self) -> Vec<Box<JSONValue>> {
+        match self.alloc {
+            Some(ref mut alloc) => return alloc.take_array(),
+            None => return vec![],
+        }
+    }
+
+    fn take_map(&mut self) -> JSONMap {
+        match self.alloc {
+            Some(ref mut alloc) => return alloc.take_map(),
+            None => return JSONMap::new(),
+        }
+    }
+
+    fn boxed(&mut self, value: JSONValue) -> Box<JSONValue> {
+        match self.alloc {
+            Some(ref mut alloc) => return alloc.boxed(value),
+            None => return Box::new(value),
+        }
+    }
+
+    fn step(&mut self, position: usize) -> Result<(), JSONParseError> {
+        // sources of unknown length are checked as they're read
+        check_limit(
+            self.options.max_input_bytes,
+            position,
+            "input size in bytes",
+            position,
+        )?;
+        self.steps += 1;
+        if !self.steps.is_multiple_of(STEPS_PER_CLOCK_CHECK) {
+            return Ok(());
+        }
+        match (self.deadline, self.options.timeout) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => {
+                return Err(JSONParseError {
+                    reason: format!(
+                        "Parsing timed out after {:?} at position {}",
+                        timeout, position
+                    ),
+                    position: Some(position),
+                    kind: JSONParseErrorKind::Timeout,
+                });
+            }
+            _ => return Ok(()),
+        }
+    }
+
+    // Fails if `bytes` more would exceed the allocation budget
+    fn check_budget(&self, bytes: usize, position: usize) -> Result<(), JSONParseError> {
+        match self.options.max_allocated_bytes {
+            Some(budget) if self.allocated + bytes > budget => {
+                return Err(JSONParseError {
+                    reason: format!(
+                        "Allocation budget of {} bytes exceeded at position {}",
+                        budget, position
+                    ),
+                    position: Some(position),
+                    kind: JSONParseErrorKind::BudgetExceeded,
+                });
+            }
+            _ => return Ok(()),
+        }
+    }
+
+    fn charge(&mut self, bytes: usize, position: usize) -> Result<(), JSONParseError> {
+        self.check_budget(bytes, position)?;
+        self.allocated += bytes;
+        return Ok(());
+    }
+}
+
+impl<'a> Default for ParseState<'a> {
+    fn default() -> ParseState<'a> {
+        return ParseState::new(&ParserOptions::default());
+    }
+}
 
 pub fn parse_json(input: &str) -> Result<JSONValue, JSONParseError> {
-    let mut chars = input.char_indices().peekable();
-    consume_spaces(&mut chars);
-    let val = parse_value(&mut chars)?;
-    consume_spaces(&mut chars);
+    return parse_json_with(input, &ParserOptions::default());
+}
+
+pub fn parse_json_with(input: &str, options: &ParserOptions) -> Result<JSONValue, JSONParseError> {
+    return parse_source_with(&mut StrSource::new(input), options);
+}
+
+pub fn parse_source_with<S: Source>(
+    source: &mut S,
+    options: &ParserOptions,
+) -> Result<JSONValue, JSONParseError> {
+    return parse_with_state(source, &mut ParseState::new(options));
+}
+
+/// Parses with containers taken from `alloc`, see `JSONValue::parse_in`.
+pub(crate) fn parse_alloc(
+    input: &str,
+    options: &ParserOptions,
+    alloc: &mut dyn Alloc,
+) -> Result<JSONValue, JSONParseError> {
+    let mut state = ParseState::new(options);
+    state.alloc = Some(alloc);
+    return parse_with_state(&mut StrSource::new(input), &mut state);
+}
+
+fn parse_with_state<S: Source>(
+    source: &mut S,
+    state: &mut ParseState,
+) -> Result<JSONValue, JSONParseError> {
+    #[cfg(feature = "tracing")]
+    {
+        if let Some(subscriber) = trace::current() {
+            return parse_traced(source, state, &*subscriber);
+        }
+    }
+    return parse_document(source, state);
+}
+
+#[cfg(feature = "tracing")]
+fn parse_traced<S: Source>(
+    source: &mut S,
+    state: &mut ParseState,
+    subscriber: &dyn trace::Subscriber,
+) -> Result<JSONValue, JSONParseError> {
+    use trace::ParseEvent;
+    let start = source.pos();
+    subscriber.event(&ParseEvent::Start {
+        input_bytes: source.total_bytes().unwrap_or(0),
+    });
+    let started = Instant::now();
+    let result = parse_document(source, state);
+    let error = result.as_ref().err();
+    if let Some(err) = error {
+        match err.kind {
+            JSONParseErrorKind::LimitExceeded
+            | JSONParseErrorKind::BudgetExceeded
+            | JSONParseErrorKind::Timeout => {
+                subscriber.event(&ParseEvent::LimitViolation { error: err });
+            }
+            _ => (),
+        }
+    }
+    subscriber.event(&ParseEvent::End {
+        input_bytes: source.total_bytes().unwrap_or(source.pos() - start),
+        nodes: state.nodes,
+        allocated_bytes: state.allocated,
+        elapsed: started.elapsed(),
+        error,
+    });
+    return result;
+}
+
+fn parse_document<S: Source>(
+    chars: &mut S,
+    state: &mut ParseState,
+) -> Result<JSONValue, JSONParseError> {
+    if let Some(total) = chars.total_bytes() {
+        check_limit(
+            state.options.max_input_bytes,
+            total,
+            "input size in bytes",
+            0,
+        )?;
+    }
+    consume_spaces(chars, state);
+    check_root(chars, state)?;
+    let val = match parse_value(chars, state) {
+        Ok(val) => val,
+        Err(mut err) => {
+            if err.position.is_none() && chars.peek().is_none() {
+                err.position = Some(chars.pos());
+            }
+            return Err(err);
+        }
+    };
+    if state.options.allow_trailing_data {
+        return Ok(val);
+    }
+    consume_spaces(chars, state);
     match chars.next() {
         None => return Ok(val),
         Some(el) => {
             let (i, ch) = el;
+            state.note(i, || format!("expected the end of input, got `{}`", ch));
             return Err(unexpected_character(i, ch));
         }
     }
 }
 
-pub fn parse_value(chars: &mut Peekable<CharIndices>) -> Result<JSONValue, JSONParseError> {
+fn check_root<S: Source>(chars: &mut S, state: &mut ParseState) -> Result<(), JSONParseError> {
+    let options = state.options;
+    let expected = match (options.require_object_root, options.require_array_root) {
+        (false, false) => return Ok(()),
+        (true, false) => "an object",
+        (false, true) => "an array",
+        (true, true) => "an object or an array",
+    };
+    let (position, found) = match chars.peek() {
+        Some((_, '{')) if options.require_object_root => return Ok(()),
+        Some((_, '[')) if options.require_array_root => return Ok(()),
+        Some((i, '{')) => (i, "an object"),
+        Some((i, '[')) => (i, "an array"),
+        Some((i, '"')) => (i, "a string"),
+        Some((i, 't')) | Some((i, 'f')) => (i, "a boolean"),
+        Some((i, 'n')) => (i, "null"),
+        Some((i, ch)) if ch == '-' || ch.is_ascii_digit() => (i, "a number"),
+        // not a value at all, the syntax error is more useful
+        _ => return Ok(()),
+    };
+    state.note(position, || {
+        format!("expected {} at the root, got {}", expected, found)
+    });
+    return Err(make_positioned_err(
+        format!("Expected {} at the root, got {}", expected, found),
+        position,
+    ));
+}
+
+fn parse_value<S: Source>(
+    chars: &mut S,
+    state: &mut ParseState,
+) -> Result<JSONValue, JSONParseError> {
+    state.nodes += 1;
+    state.step(position(chars))?;
+    state.charge(size_of::<JSONValue>(), position(chars))?;
+    check_limit(
+        state.options.max_nodes,
+        state.nodes,
+        "number of values",
+        position(chars),
+    )?;
+    let start = position(chars);
     match next_char(chars) {
         None => return Err(make_err("Empty string provided".to_owned())),
         Some(ch) => match ch {
-            OBJECT_START => return Ok(JSONValue::JSONObject(parse_object(chars)?)),
-            QUOTE => return Ok(JSONValue::JSONString(parse_str(chars)?)),
-            TRUE_START => return Ok(JSONValue::JSONBool(parse_true(chars)?)),
-            FALSE_START => return Ok(JSONValue::JSONBool(parse_false(chars)?)),
-            NULL_START => {
-                parse_null(chars)?;
-                return Ok(JSONValue::JSONNull());
+            OBJECT_START => {
+                state.note(start, || "`{` starts an object".to_owned());
+                enter_container(chars, state)?;
+                let object = parse_object(chars, state)?;
+                state.depth -= 1;
+                return Ok(JSONValue::JSONObject(object));
+            }
+            QUOTE => {
+                let s = parse_str(chars, state)?;
+                state.note(start, || format!("string {}", quote_short(&s)));
+                if state.options.normalization.strings() {
+                    return Ok(JSONValue::JSONString(nfc(&s).into_owned()));
+                }
+                return Ok(JSONValue::JSONString(s));
+            }
+            TRUE_START | FALSE_START | NULL_START => {
+                state.note(start, || format!("`{}` starts a literal", ch));
+                match ch {
+                    TRUE_START => return Ok(JSONValue::JSONBool(parse_true(chars)?)),
+                    FALSE_START => return Ok(JSONValue::JSONBool(parse_false(chars)?)),
+                    _ => {
+                        parse_null(chars)?;
+                        return Ok(JSONValue::JSONNull());
+                    }
+                }
+            }
+            MINUS | '0'..='9' => {
+                let n = parse_num(chars, state)?;
+                state.note(start, || format!("number {}", n));
+                return Ok(JSONValue::JSONNumber(n));
+            }
+            ARRAY_START => {
+                state.note(start, || "`[` starts an array".to_owned());
+                enter_container(chars, state)?;
+                let array = parse_array(chars, state)?;
+                state.depth -= 1;
+                return Ok(JSONValue::JSONArray(array));
             }
-            MINUS => return Ok(JSONValue::JSONNumber(parse_num(chars)?)),
-            '0'...'9' => return Ok(JSONValue::JSONNumber(parse_num(chars)?)),
-            ARRAY_START => return Ok(JSONValue::JSONArray(parse_array(chars)?)),
             _ => {
                 let (i, ch) = chars.next().unwrap();
+                state.note(i, || format!("`{}` can't start a value", ch));
                 return Err(unexpected_character(i, ch));
             }
         },
     };
 }
 
-fn parse_array(chars: &mut Peekable<CharIndices>) -> Result<Vec<Box<JSONValue>>, JSONParseError> {
-    let mut result: Vec<Box<JSONValue>> = vec![];
+fn enter_container<S: Source>(chars: &mut S, state: &mut ParseState) -> Result<(), JSONParseError> {
+    state.depth += 1;
+    return check_limit(
+        state.options.max_depth,
+        state.depth,
+        "nesting depth",
+        position(chars),
+    );
+}
+
+fn parse_array<S: Source>(
+    chars: &mut S,
+    state: &mut ParseState,
+) -> Result<Vec<Box<JSONValue>>, JSONParseError> {
+    let mut result = state.take_array();
     read_known_char(chars, ARRAY_START)?;
-    consume_spaces(chars);
-    match next_char(chars).ok_or(unexpected_eof())? {
-        ARRAY_END => {
-            chars.next();
-            return Ok(result);
-        }
-        _ => (),
+    consume_spaces(chars, state);
+    if next_char(chars).ok_or(unexpected_eof())? == ARRAY_END {
+        state.note(position(chars), || "`]` ends the empty array".to_owned());
+        chars.next();
+        return Ok(result);
     }
     loop {
-        consume_spaces(chars);
-        result.push(Box::new(parse_value(chars)?));
-        consume_spaces(chars);
+        consume_spaces(chars, state);
+        let limit = state.options.max_container_entries;
+        check_limit(
+            limit,
+            result.len() + 1,
+            "number of array items",
+            position(chars),
+        )?;
+        state.charge(size_of::<Box<JSONValue>>(), position(chars))?;
+        let item = parse_value(chars, state)?;
+        result.push(state.boxed(item));
+        consume_spaces(chars, state);
         let (i, ch) = chars.next().ok_or(unexpected_eof())?;
         match ch {
-            ARRAY_END => return Ok(result),
-            COMMA => (),
+            ARRAY_END => {
+                state.note(i, || {
+                    format!("`]` ends the array of {} items", result.len())
+                });
+                return Ok(result);
+            }
+            COMMA => state.note(i, || "`,` expects another item".to_owned()),
             _ => {
+                state.note(i, || {
+                    format!("expected `,` or `]` after an item, got `{}`", ch)
+                });
                 return Err(unexpected_character(i, ch));
             }
         }
     }
 }
 
-fn parse_object(
-    chars: &mut Peekable<CharIndices>,
-) -> Result<HashMap<String, Box<JSONValue>>, JSONParseError> {
-    let mut result: HashMap<String, Box<JSONValue>> = HashMap::new();
+fn parse_object<S: Source>(
+    chars: &mut S,
+    state: &mut ParseState,
+) -> Result<JSONMap, JSONParseError> {
+    let mut result = state.take_map();
     read_known_char(chars, OBJECT_START)?;
-    match next_char(chars).ok_or(unexpected_eof())? {
-        OBJECT_END => {
-            chars.next();
-            return Ok(result);
-        }
-        _ => (),
+    consume_spaces(chars, state);
+    if next_char(chars).ok_or(unexpected_eof())? == OBJECT_END {
+        state.note(position(chars), || "`}` ends the empty object".to_owned());
+        chars.next();
+        return Ok(result);
     }
+    let mut members = 0;
     loop {
-        consume_spaces(chars);
-        let key = parse_str(chars)?;
-        consume_spaces(chars);
+        consume_spaces(chars, state);
+        members += 1;
+        let limit = state.options.max_container_entries;
+        check_limit(limit, members, "number of object members", position(chars))?;
+        let entry_size = size_of::<String>() + size_of::<Box<JSONValue>>();
+        state.charge(entry_size, position(chars))?;
+        let key_position = position(chars);
+        if next_char(chars) != Some(QUOTE) {
+            state.note(key_position, || "expected a quoted key".to_owned());
+        }
+        let mut key = parse_str(chars, state)?;
+        state.note(key_position, || format!("key {}", quote_short(&key)));
+        if state.options.normalization.keys() {
+            key = nfc(&key).into_owned();
+        }
+        if !result.contains_key(&key) {
+            let limit = state.options.max_object_keys;
+            check_limit(
+                limit,
+                result.len() + 1,
+                "number of object keys",
+                key_position,
+            )?;
+        }
+        consume_spaces(chars, state);
+        if next_char(chars) != Some(COLON) {
+            state.note(position(chars), || "expected `:` after the key".to_owned());
+        }
         read_known_char(chars, COLON)?;
-        consume_spaces(chars);
-        let value = parse_value(chars)?;
-        result.insert(key, Box::new(value));
-        consume_spaces(chars);
+        consume_spaces(chars, state);
+        let value = parse_value(chars, state)?;
+        let value = state.boxed(value);
+        result.insert(key, value);
+        consume_spaces(chars, state);
         let (i, ch) = chars.next().ok_or(unexpected_eof())?;
         match ch {
-            OBJECT_END => return Ok(result),
-            COMMA => (),
-            _ => return Err(unexpected_character(i, ch)),
+            OBJECT_END => {
+                state.note(i, || format!("`}}` ends the object of {} members", members));
+                return Ok(result);
+            }
+            COMMA => state.note(i, || "`,` expects another member".to_owned()),
+            _ => {
+                state.note(i, || {
+                    format!("expected `,` or `}}` after a member, got `{}`", ch)
+                });
+                return Err(unexpected_character(i, ch));
+            }
         }
     }
 }
 
-fn parse_const<T>(
-    chars: &mut Peekable<CharIndices>,
+fn parse_const<T, S: Source>(
+    chars: &mut S,
     str_value: &str,
     value: T,
 ) -> Result<T, JSONParseError> {
@@ -135,56 +632,89 @@ fn parse_const<T>(
     return Ok(value);
 }
 
-fn parse_true(chars: &mut Peekable<CharIndices>) -> Result<bool, JSONParseError> {
+fn parse_true<S: Source>(chars: &mut S) -> Result<bool, JSONParseError> {
     return parse_const(chars, BOOL_TRUE, true);
 }
 
-fn parse_false(chars: &mut Peekable<CharIndices>) -> Result<bool, JSONParseError> {
+fn parse_false<S: Source>(chars: &mut S) -> Result<bool, JSONParseError> {
     return parse_const(chars, BOOL_FALSE, false);
 }
 
-fn parse_null(chars: &mut Peekable<CharIndices>) -> Result<(), JSONParseError> {
+fn parse_null<S: Source>(chars: &mut S) -> Result<(), JSONParseError> {
     return parse_const(chars, NULL, ());
 }
 
-fn parse_str(chars: &mut Peekable<CharIndices>) -> Result<String, JSONParseError> {
-    let mut result = String::new();
+fn parse_str<S: Source>(chars: &mut S, state: &mut ParseState) -> Result<String, JSONParseError> {
+    let mut result = state.take_string();
+    let start = position(chars);
     read_known_char(chars, QUOTE)?;
     loop {
+        check_limit(
+            state.options.max_string_length,
+            result.len(),
+            "string length in bytes",
+            start,
+        )?;
+        state.check_budget(result.len(), start)?;
+        state.step(start)?;
         let (i, ch) = chars.next().ok_or(unexpected_eof())?;
         match ch {
-            QUOTE => return Ok(result),
+            QUOTE => {
+                state.charge(result.len(), start)?;
+                return Ok(result);
+            }
             ESCAPE => result.push_str(&read_escape_char(chars)?),
-            '\0'...'\x1F' => return Err(unexpected_character(i, ch)),
+            '\0'..='\x1F' => return Err(unexpected_character(i, ch)),
             _ => result.push(ch),
         }
     }
 }
 
-fn read_escape_char(chars: &mut Peekable<CharIndices>) -> Result<String, JSONParseError> {
+fn read_escape_char<S: Source>(chars: &mut S) -> Result<String, JSONParseError> {
     let mut result = String::new();
     let (i, ch) = chars.next().ok_or(unexpected_eof())?;
     if ESCAPABLE.chars().any(|escapable| escapable == ch) {
         result.push(convert_escaped(ch));
-    } else {
-        if ch == UNICODE_ESCAPE {
-            let mut ord: u32 = 0;
-            let mut seq = "\\u".to_owned();
-            for j in 0..4 {
-                let (i, ch) = chars.next().ok_or(unexpected_eof())?;
+    } else if ch == UNICODE_ESCAPE {
+        let mut seq = "\\u".to_owned();
+        let mut ord = read_hex4(chars, &mut seq, i - 1)?;
+        // a high surrogate must be followed by an escaped low one
+        if (0xD800..0xDC00).contains(&ord) {
+            for expected in [ESCAPE, UNICODE_ESCAPE] {
+                let (_, ch) = chars.next().ok_or(unexpected_eof())?;
                 seq.push(ch);
-                ord = ord * 16 + ch
-                    .to_digit(16)
-                    .ok_or(invalid_escape_sequence(i - j - 2, &seq))?;
+                if ch != expected {
+                    return Err(invalid_escape_sequence(i - 2, &seq));
+                }
             }
-            result.push(char::from_u32(ord).ok_or(invalid_escape_sequence(i - 2, &seq))?)
-        } else {
-            return Err(invalid_escape_sequence(i - 2, &format!("\\{}", ch)));
+            let low = read_hex4(chars, &mut seq, i - 1)?;
+            if !(0xDC00..0xE000).contains(&low) {
+                return Err(invalid_escape_sequence(i - 2, &seq));
+            }
+            ord = 0x10000 + ((ord - 0xD800) << 10) + (low - 0xDC00);
         }
+        result.push(char::from_u32(ord).ok_or(invalid_escape_sequence(i - 2, &seq))?)
+    } else {
+        return Err(invalid_escape_sequence(i - 2, &format!("\\{}", ch)));
     }
     Ok(result)
 }
 
+// Four hex digits of a `\u` escape starting at `start`, appended to `seq`
+fn read_hex4<S: Source>(
+    chars: &mut S,
+    seq: &mut String,
+    start: usize,
+) -> Result<u32, JSONParseError> {
+    let mut ord: u32 = 0;
+    for _ in 0..4 {
+        let (_, ch) = chars.next().ok_or(unexpected_eof())?;
+        seq.push(ch);
+        ord = ord * 16 + ch.to_digit(16).ok_or(invalid_escape_sequence(start, seq))?;
+    }
+    return Ok(ord);
+}
+
 fn convert_escaped(ch: char) -> char {
     match ch {
         't' => '\t',
@@ -196,7 +726,8 @@ fn convert_escaped(ch: char) -> char {
     }
 }
 
-fn parse_num(chars: &mut Peekable<CharIndices>) -> Result<f64, JSONParseError> {
+fn parse_num<S: Source>(chars: &mut S, state: &ParseState) -> Result<f64, JSONParseError> {
+    let start = position(chars);
     let mut num = String::new();
     let ch = next_char(chars).ok_or(unexpected_eof())?;
     if ch == MINUS {
@@ -209,7 +740,7 @@ fn parse_num(chars: &mut Peekable<CharIndices>) -> Result<f64, JSONParseError> {
             num.push(ch);
             chars.next();
         }
-        '1'...'9' => {
+        '1'..='9' => {
             num.push_str(&read_digits(chars)?);
         }
         _ => {
@@ -217,46 +748,65 @@ fn parse_num(chars: &mut Peekable<CharIndices>) -> Result<f64, JSONParseError> {
             return Err(unexpected_character(i, ch));
         }
     }
-    num.push_str(&read_fraction(chars)?);
-    match next_char(chars) {
-        None => (),
-        Some(ch) => {
-            if ch == 'e' || ch == 'E' {
-                chars.next().unwrap();
-                num.push(ch);
-                let ch = next_char(chars).ok_or(unexpected_eof())?;
-                match ch {
-                    MINUS => {
-                        num.push(ch);
-                        chars.next();
-                    }
-                    PLUS => {
-                        chars.next();
-                    }
-                    _ => (),
+    num.push_str(&read_fraction(chars, state)?);
+    if let Some(ch) = next_char(chars) {
+        if ch == 'e' || ch == 'E' {
+            chars.next().unwrap();
+            num.push(ch);
+            let ch = next_char(chars).ok_or(unexpected_eof())?;
+            match ch {
+                MINUS => {
+                    num.push(ch);
+                    chars.next();
+                }
+                PLUS => {
+                    chars.next();
                 }
-                num.push_str(&read_digits(chars)?);
+                _ => (),
             }
+            num.push_str(&read_digits(chars)?);
         }
     }
-    match num.parse() {
-        Ok(n) => return Ok(n),
+    let n: f64 = match num.parse() {
+        Ok(n) => n,
         Err(_) => return Err(make_err(format!("Unable to parse number {}", num))),
+    };
+    let options = &state.options;
+    if n.is_infinite() {
+        match options.number_overflow {
+            NumberOverflow::Infinity => (),
+            NumberOverflow::Max => return Ok(f64::MAX.copysign(n)),
+            NumberOverflow::Error => {
+                let reason = format!("Number {} is out of range at position {}", num, start);
+                return Err(make_positioned_err(reason, start));
+            }
+        }
+    }
+    if n == 0.0 {
+        let mantissa = num.split(['e', 'E']).next().unwrap_or("");
+        if options.reject_number_underflow && mantissa.contains(|ch| ('1'..='9').contains(&ch)) {
+            let reason = format!("Number {} is too small at position {}", num, start);
+            return Err(make_positioned_err(reason, start));
+        }
+        if options.normalize_negative_zero {
+            return Ok(0.0);
+        }
     }
+    return Ok(n);
 }
 
-fn read_digits(chars: &mut Peekable<CharIndices>) -> Result<String, JSONParseError> {
+fn read_digits<S: Source>(chars: &mut S) -> Result<String, JSONParseError> {
     let mut result = String::new();
     loop {
         match next_char(chars) {
             None => {
-                if result.len() > 0 {
+                if !result.is_empty() {
                     return Ok(result);
                 }
                 return Err(unexpected_eof());
             }
             Some(ch) => {
-                if ch.is_digit(10) {
+                if ch.is_ascii_digit() {
                     result.push(ch);
                     chars.next();
                 } else {
@@ -269,21 +819,24 @@ fn read_digits(chars: &mut Peekable<CharIndices>) -> Result<String, JSONParseErr
 }
 
 //Read optional fraction part. It can be empty, but it can't start with number!
-fn read_fraction(chars: &mut Peekable<CharIndices>) -> Result<String, JSONParseError> {
+fn read_fraction<S: Source>(chars: &mut S, state: &ParseState) -> Result<String, JSONParseError> {
     match next_char(chars) {
         None => return Ok(String::new()),
         Some(ch) => {
             match ch {
                 DOT => {
                     chars.next(); //skip dot
-                    let digits = &read_digits(chars)?;
-                    if digits.len() == 0 {
+                    let digits = &match read_digits(chars) {
+                        Err(_) if state.options.allow_empty_fraction => String::new(),
+                        digits => digits?,
+                    };
+                    if digits.is_empty() && !state.options.allow_empty_fraction {
                         let (i, ch) = chars.next().ok_or(unexpected_eof())?;
                         return Err(unexpected_character(i, ch));
                     }
                     return Ok(".".to_owned() + digits);
                 }
-                '0'...'9' => {
+                '0'..='9' => {
                     let (i, ch) = chars.next().unwrap();
                     return Err(unexpected_character(i, ch));
                 }
@@ -293,54 +846,93 @@ fn read_fraction(chars: &mut Peekable<CharIndices>) -> Result<String, JSONParseE
     }
 }
 
-fn read_known_char(
-    chars: &mut Peekable<CharIndices>,
-    expected: char,
-) -> Result<(), JSONParseError> {
+fn read_known_char<S: Source>(chars: &mut S, expected: char) -> Result<(), JSONParseError> {
     let (i, ch) = chars.next().ok_or(unexpected_eof())?;
     if ch != expected {
-        return Err(make_err(format!(
-            "Unexpected charachter {} at position {}. Expected {}",
-            ch, i, expected
-        )));
+        return Err(make_positioned_err(
+            format!(
+                "Unexpected charachter {} at position {}. Expected {}",
+                ch, i, expected
+            ),
+            i,
+        ));
     };
     return Ok(());
 }
 
-fn next_char(chars: &mut Peekable<CharIndices>) -> Option<char> {
+// Offset of the next char, for errors that aren't about a specific char
+fn position<S: Source>(chars: &mut S) -> usize {
+    return chars.pos();
+}
+
+fn check_limit(
+    limit: Option<usize>,
+    value: usize,
+    what: &str,
+    position: usize,
+) -> Result<(), JSONParseError> {
+    match limit {
+        Some(limit) if value > limit => {
+            return Err(JSONParseError {
+                reason: format!(
+                    "Maximum {} of {} exceeded at position {}",
+                    what, limit, position
+                ),
+                position: Some(position),
+                kind: JSONParseErrorKind::LimitExceeded,
+            });
+        }
+        _ => return Ok(()),
+    }
+}
+
+fn next_char<S: Source>(chars: &mut S) -> Option<char> {
     match chars.peek() {
         None => return None,
         Some(el) => {
             let (_, ch) = el;
-            return Some(*ch);
+            return Some(ch);
         }
     }
 }
 
-fn consume_spaces(chars: &mut Peekable<CharIndices>) {
-    loop {
-        match next_char(chars) {
-            None => return,
-            Some(ch) => {
-                if is_whitespace(ch) {
-                    chars.next();
-                } else {
-                    return;
-                }
-            }
+fn consume_spaces<S: Source>(chars: &mut S, state: &ParseState) {
+    while let Some(ch) = next_char(chars) {
+        let unicode = state.options.allow_unicode_whitespace && ch.is_whitespace();
+        if !is_whitespace(ch) && !unicode {
+            return;
         }
+        chars.next();
     }
 }
 
-fn is_whitespace(ch: char) -> bool {
-    match ch {
-        '\x09' | '\x0a' | '\x0d' | '\x20' => true,
-        _ => false,
+// Quoted string for explanations, long ones cut
+fn quote_short(s: &str) -> String {
+    if s.chars().count() <= 40 {
+        return format!("{:?}", s);
     }
+    let start: String = s.chars().take(37).collect();
+    return format!("{:?}...", start);
+}
+
+fn is_whitespace(ch: char) -> bool {
+    matches!(ch, '\x09' | '\x0a' | '\x0d' | '\x20')
 }
 
 fn make_err(s: String) -> JSONParseError {
-    JSONParseError { reason: s }
+    JSONParseError {
+        reason: s,
+        position: None,
+        kind: JSONParseErrorKind::Syntax,
+    }
+}
+
+fn make_positioned_err(s: String, position: usize) -> JSONParseError {
+    JSONParseError {
+        reason: s,
+        position: Some(position),
+        kind: JSONParseErrorKind::Syntax,
+    }
 }
 
 fn unexpected_eof() -> JSONParseError {
@@ -348,15 +940,15 @@ fn unexpected_eof() -> JSONParseError {
 }
 
 fn unexpected_character(position: usize, ch: char) -> JSONParseError {
-    make_err(format!(
-        "Unexpected charachter {} at position {}",
-        ch, position
-    ))
+    make_positioned_err(
+        format!("Unexpected charachter {} at position {}", ch, position),
+        position,
+    )
 }
 
 fn invalid_escape_sequence(position: usize, s: &str) -> JSONParseError {
-    make_err(format!(
-        "Invalid escape sequence {} at position {}",
-        s, position
-    ))
+    make_positioned_err(
+        format!("Invalid escape sequence {} at position {}", s, position),
+        position,
+    )
 }
@@ -1,3 +1,5 @@
+#![allow(clippy::useless_vec, clippy::excessive_precision)]
+
 use super::*;
 
 #[test]
@@ -8,10 +10,14 @@ fn test_valid_string_examples() {
         ("\"asd\\r\\n\\t\"", "asd\r\n\t"),
         ("\"\\u0041\"", "A"),
         ("\"unicode sequence \\uc328\"", "unicode sequence 쌨"),
-        // ("\"\\uD834\\uDd1e\"", "ab"), This corner case is failing for now. I hate utf-16!
+        ("\"\\uD834\\uDd1e\"", "𝄞"),
+        ("\"\\ud83d\\ude00 \\u00e9\"", "😀 é"),
     ] {
         println!("Checking {}", s.0);
-        assert_eq!(parse_str(&mut s.0.char_indices().peekable()).unwrap(), s.1);
+        assert_eq!(
+            parse_str(&mut StrSource::new(s.0), &mut ParseState::default()).unwrap(),
+            s.1
+        );
     }
 }
 
@@ -23,8 +29,12 @@ fn test_invalid_string_examples() {
         ("\"not_closed"),
         ("not opened"),
         ("\"invalid escape \\x \""),
+        ("\"lone \\ud83d surrogate\""),
+        ("\"\\ud83d\\u0041\""),
+        ("\"\\ude00\""),
+        ("\"\\ud83d\\ud\""),
     ] {
-        parse_str(&mut s.char_indices().peekable())
+        parse_str(&mut StrSource::new(s), &mut ParseState::default())
             .expect_err(&format!("Invalid value {} parsed", s));
     }
 }
@@ -32,19 +42,19 @@ fn test_invalid_string_examples() {
 #[test]
 fn valid_parse_bull() {
     for s in vec!["true", "true, ", "true  asdpjmklmo"] {
-        assert!(parse_true(&mut s.char_indices().peekable()).unwrap())
+        assert!(parse_true(&mut StrSource::new(s)).unwrap())
     }
     for s in vec!["false", "false, ", "false  asdpjmklmo"] {
-        assert!(!parse_false(&mut s.char_indices().peekable()).unwrap())
+        assert!(!parse_false(&mut StrSource::new(s)).unwrap())
     }
 }
 
 #[test]
 fn invalid_parse_bull() {
     for s in vec!["True", "False", "TRUE", "0", "1", "asdm"] {
-        parse_true(&mut s.char_indices().peekable())
+        parse_true(&mut StrSource::new(s))
             .expect_err(&format!("Should not be parsed as bool! {}", s));
-        parse_false(&mut s.char_indices().peekable())
+        parse_false(&mut StrSource::new(s))
             .expect_err(&format!("Should not be parsed as bool! {}", s));
     }
 }
@@ -72,7 +82,10 @@ fn test_valid_parse_num() {
         ("1231231239.0121e-5000 asd", 1231231239.0121e-5000),
     ] {
         println!("Checking {}", s.0);
-        assert_eq!(parse_num(&mut s.0.char_indices().peekable()).unwrap(), s.1)
+        assert_eq!(
+            parse_num(&mut StrSource::new(s.0), &ParseState::default()).unwrap(),
+            s.1
+        )
     }
 }
 
@@ -87,7 +100,7 @@ fn test_invalid_parse_num() {
         "123.0Ee123123123",
     ] {
         println!("Checking {}", s);
-        parse_num(&mut s.char_indices().peekable())
+        parse_num(&mut StrSource::new(s), &ParseState::default())
             .expect_err(&format!("Expected to fail while parsing {}", s));
     }
 }
@@ -95,14 +108,14 @@ fn test_invalid_parse_num() {
 #[test]
 fn test_valid_parse_null() {
     for s in vec!["null", "null, ", "null ", "null!"] {
-        parse_null(&mut s.char_indices().peekable()).unwrap();
+        parse_null(&mut StrSource::new(s)).unwrap();
     }
 }
 
 #[test]
 fn invalid_parse_null() {
     for s in vec!["NULL", "!null", "asd", "><>OP"] {
-        parse_null(&mut s.char_indices().peekable())
+        parse_null(&mut StrSource::new(s))
             .expect_err(&format!("Should not be parsed as null! {}", s));
     }
 }
@@ -119,7 +132,7 @@ fn test_invalid_parse_object() {
         "{\"asd\": 1; \"bsd\": 2}",
         "{\"asd\": 1; \"bsd\": \"asdasdad}",
     ] {
-        parse_object(&mut s.char_indices().peekable())
+        parse_object(&mut StrSource::new(s), &mut ParseState::default())
             .expect_err(&format!("Should not be parsed as valid object <{}>", s));
     }
 }
@@ -128,6 +141,8 @@ fn test_invalid_parse_object() {
 fn test_valid_parse_object() {
     for s in vec![
         "{}",
+        "{ }",
+        "{\n}",
         "{\"asd\": 1}",
         "{\"asd\": {\"b\": 1}}",
         "{\"asd\": 17.8e162}",
@@ -135,7 +150,7 @@ fn test_valid_parse_object() {
         "{\"asd\": 1, \"bsd\": \"asdasdasd\"}",
     ] {
         println!("Checking {}", s);
-        parse_object(&mut s.char_indices().peekable()).unwrap();
+        parse_object(&mut StrSource::new(s), &mut ParseState::default()).unwrap();
     }
 }
 
@@ -184,13 +199,13 @@ fn test_valid_parse_array() {
                 Box::new(JSONValue::JSONNull()),
                 Box::new(JSONValue::JSONNumber(1.0)),
                 Box::new(JSONValue::JSONString("1".to_owned())),
-                Box::new(JSONValue::JSONObject(HashMap::new())),
+                Box::new(JSONValue::JSONObject(JSONMap::new())),
             ],
         ),
     ] {
         println!("Checking {}", s.0);
         assert_eq!(
-            parse_array(&mut s.0.char_indices().peekable()).unwrap(),
+            parse_array(&mut StrSource::new(s.0), &mut ParseState::default()).unwrap(),
             s.1
         );
     }
@@ -207,7 +222,453 @@ fn test_invalid_parse_array() {
         "[1, 2, 3 4]",
     ];
     for s in cases {
-        parse_array(&mut s.char_indices().peekable())
+        parse_array(&mut StrSource::new(s), &mut ParseState::default())
             .expect_err(&format!("Should not be parsed as valid array <{}>", s));
     }
 }
+
+#[test]
+fn test_error_positions() {
+    for s in vec![
+        ("{\"a\": 1,}", Some(8), Some((1, 9))),
+        ("[1,\n  2", Some(7), Some((2, 4))),
+        ("[1,\n  x]", Some(6), Some((2, 3))),
+        ("\"\\u00фx\"", Some(1), Some((1, 2))),
+        ("1 2", Some(2), Some((1, 3))),
+        ("[1.]", Some(3), Some((1, 4))),
+    ] {
+        println!("Checking {}", s.0);
+        let err = parse_json(s.0).unwrap_err();
+        assert_eq!(err.position, s.1);
+        assert_eq!(err.line_column(s.0), s.2);
+    }
+}
+
+#[test]
+fn test_limits() {
+    let limits = |f: fn(&mut ParserOptions)| {
+        let mut options = ParserOptions::default();
+        f(&mut options);
+        options
+    };
+    for s in vec![
+        ("[1, 2]", limits(|o| o.max_input_bytes = Some(5)), Some(0)),
+        ("[[1]]", limits(|o| o.max_depth = Some(1)), Some(1)),
+        ("{\"a\": {}}", limits(|o| o.max_depth = Some(1)), Some(6)),
+        (
+            "[\"abc\"]",
+            limits(|o| o.max_string_length = Some(2)),
+            Some(1),
+        ),
+        (
+            "{\"abc\": 1}",
+            limits(|o| o.max_string_length = Some(2)),
+            Some(1),
+        ),
+        (
+            "[1, 2, 3]",
+            limits(|o| o.max_container_entries = Some(2)),
+            Some(7),
+        ),
+        (
+            "{\"a\": 1, \"b\": 2}",
+            limits(|o| o.max_container_entries = Some(1)),
+            Some(9),
+        ),
+        (
+            "{\"a\": 1, \"a\": 2}",
+            limits(|o| o.max_container_entries = Some(1)),
+            Some(9),
+        ),
+        (
+            "{\"a\": 1, \"b\": 2}",
+            limits(|o| o.max_object_keys = Some(1)),
+            Some(9),
+        ),
+        ("[[1], 2]", limits(|o| o.max_nodes = Some(3)), Some(6)),
+    ] {
+        println!("Checking {}", s.0);
+        let err = parse_json_with(s.0, &s.1).unwrap_err();
+        assert_eq!(err.kind, JSONParseErrorKind::LimitExceeded);
+        assert_eq!(err.position, s.2);
+    }
+    for s in vec!["[[1], 2]", "{\"ab\": [\"cd\"]}", "\"\""] {
+        let options = ParserOptions {
+            max_input_bytes: Some(s.len()),
+            max_depth: Some(2),
+            max_string_length: Some(2),
+            max_container_entries: Some(2),
+            max_object_keys: Some(1),
+            max_nodes: Some(4),
+            max_allocated_bytes: None,
+            timeout: None,
+            normalization: Normalization::None,
+            allow_trailing_data: false,
+            allow_unicode_whitespace: false,
+            allow_empty_fraction: false,
+            require_object_root: false,
+            require_array_root: false,
+            number_overflow: NumberOverflow::Infinity,
+            reject_number_underflow: false,
+            normalize_negative_zero: false,
+        };
+        parse_json_with(s, &options).unwrap();
+    }
+    // repeated keys don't add distinct keys
+    let options = limits(|o| o.max_object_keys = Some(1));
+    parse_json_with("{\"a\": 1, \"a\": 2}", &options).unwrap();
+    assert_eq!(
+        parse_json("x").unwrap_err().kind,
+        JSONParseErrorKind::Syntax
+    );
+}
+
+#[test]
+fn test_allocation_budget() {
+    let input = "{\"key\": [\"some text\", 1, null]}";
+    let budget = |bytes| ParserOptions {
+        max_allocated_bytes: Some(bytes),
+        ..ParserOptions::default()
+    };
+    let value_size = size_of::<JSONValue>();
+    let slot_size = size_of::<Box<JSONValue>>();
+    // 5 values, 1 member with a 3 byte key, 3 array slots and 9 bytes of text
+    let needed = 5 * value_size + size_of::<String>() + slot_size + 3 + 3 * slot_size + 9;
+    parse_json_with(input, &budget(needed)).unwrap();
+    let err = parse_json_with(input, &budget(needed - 1)).unwrap_err();
+    assert_eq!(err.kind, JSONParseErrorKind::BudgetExceeded);
+
+    let err =
+        parse_json_with("[\"long string\"]", &budget(2 * value_size + slot_size + 5)).unwrap_err();
+    assert_eq!(err.kind, JSONParseErrorKind::BudgetExceeded);
+    assert_eq!(err.position, Some(1));
+}
+
+#[test]
+fn test_timeout() {
+    let input = format!("[{}1]", "1, ".repeat(5000));
+    let with_timeout = |timeout| ParserOptions {
+        timeout: Some(timeout),
+        ..ParserOptions::default()
+    };
+    let err = parse_json_with(&input, &with_timeout(Duration::from_secs(0))).unwrap_err();
+    assert_eq!(err.kind, JSONParseErrorKind::Timeout);
+    parse_json_with(&input, &with_timeout(Duration::from_secs(60))).unwrap();
+    // short documents finish before the clock is ever read
+    parse_json_with("[1, 2]", &with_timeout(Duration::from_secs(0))).unwrap();
+
+    let long_string = format!("\"{}\"", "a".repeat(5000));
+    let err = parse_json_with(&long_string, &with_timeout(Duration::from_secs(0))).unwrap_err();
+    assert_eq!(err.kind, JSONParseErrorKind::Timeout);
+}
+
+// Input split into chunks, with no length known upfront
+struct ChunkSource {
+    chars: Vec<(usize, char)>,
+    index: usize,
+    len: usize,
+}
+
+impl ChunkSource {
+    fn new(chunks: &[&str]) -> ChunkSource {
+        let text = chunks.concat();
+        return ChunkSource {
+            chars: text.char_indices().collect(),
+            index: 0,
+            len: text.len(),
+        };
+    }
+}
+
+impl Source for ChunkSource {
+    fn peek(&mut self) -> Option<(usize, char)> {
+        return self.chars.get(self.index).cloned();
+    }
+
+    fn next(&mut self) -> Option<(usize, char)> {
+        let next = self.peek();
+        self.index += 1;
+        return next;
+    }
+
+    fn pos(&self) -> usize {
+        return self.chars.get(self.index).map_or(self.len, |&(i, _)| i);
+    }
+}
+
+#[test]
+fn test_custom_source() {
+    let chunks = ["{\"a\": [1, ", "\"é\"], \"b", "\": null}"];
+    let value = parse_source_with(&mut ChunkSource::new(&chunks), &ParserOptions::default());
+    assert_eq!(value.unwrap(), parse_json(&chunks.concat()).unwrap());
+
+    let err = parse_source_with(
+        &mut ChunkSource::new(&["[1, ", "tru"]),
+        &ParserOptions::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err.reason, ERROR_ENDED_UNEXPECTEDLY);
+    assert_eq!(err.position, Some(7));
+
+    let limited = ParserOptions {
+        max_input_bytes: Some(8),
+        ..ParserOptions::default()
+    };
+    let err =
+        parse_source_with(&mut ChunkSource::new(&["[1, 2, ", "3, 4]"]), &limited).unwrap_err();
+    assert_eq!(err.kind, JSONParseErrorKind::LimitExceeded);
+    assert!(parse_source_with(&mut ChunkSource::new(&["[1, 2]"]), &limited).is_ok());
+}
+
+#[test]
+fn test_relaxations() {
+    let strict = ParserOptions::default();
+    let relaxed = |f: fn(&mut ParserOptions)| {
+        let mut options = ParserOptions::default();
+        f(&mut options);
+        options
+    };
+    let trailing = relaxed(|o| o.allow_trailing_data = true);
+    let whitespace = relaxed(|o| o.allow_unicode_whitespace = true);
+    let fraction = relaxed(|o| o.allow_empty_fraction = true);
+    for s in vec![
+        ("{\"a\": 1} {\"b\": 2}", &trailing, "{\"a\": 1}"),
+        ("[1]]", &trailing, "[1]"),
+        ("1 garbage", &trailing, "1"),
+        ("\u{a0}[1,\u{2003}2]\u{3000}", &whitespace, "[1, 2]"),
+        ("{\u{85}\"a\"\u{2028}:\u{a0}1}", &whitespace, "{\"a\": 1}"),
+        ("1.", &fraction, "1"),
+        ("[-1., 2.e1]", &fraction, "[-1, 20]"),
+    ] {
+        println!("Checking {}", s.0);
+        assert!(parse_json_with(s.0, &strict).is_err());
+        assert_eq!(parse_json_with(s.0, s.1).unwrap(), parse_json(s.2).unwrap());
+    }
+    // relaxations don't leak into each other
+    assert!(parse_json_with("1 2", &whitespace).is_err());
+    assert!(parse_json_with("1.", &trailing).is_err());
+    assert!(parse_json_with("\u{a0}1", &fraction).is_err());
+    assert!(parse_json_with(".5", &fraction).is_err());
+}
+
+#[test]
+fn test_explain_parse() {
+    let input = "{\"a\": [1, true,],\n \"b\": null}";
+    let explanation = explain_parse(input, &ParserOptions::default());
+    assert_eq!(
+        explanation.steps[..3],
+        [
+            ExplainStep {
+                position: 0,
+                depth: 0,
+                decision: "`{` starts an object".to_owned()
+            },
+            ExplainStep {
+                position: 1,
+                depth: 1,
+                decision: "key \"a\"".to_owned()
+            },
+            ExplainStep {
+                position: 6,
+                depth: 1,
+                decision: "`[` starts an array".to_owned()
+            },
+        ]
+    );
+    assert_eq!(
+        explanation.render(input),
+        [
+            " 0: `{` starts an object",
+            " 1:   key \"a\"",
+            " 6:   `[` starts an array",
+            " 7:     number 1",
+            " 8:     `,` expects another item",
+            "10:     `t` starts a literal",
+            "14:     `,` expects another item",
+            "15:     `]` can't start a value",
+            "error: Unexpected charachter ] at position 15",
+            "  {\"a\": [1, true,],",
+            "                 ^",
+        ]
+        .join("\n")
+    );
+
+    for (input, last) in vec![
+        (r#"{"a" 1}"#, "expected `:` after the key"),
+        (r#"{a: 1}"#, "expected a quoted key"),
+        (r#"[1 2]"#, "expected `,` or `]` after an item, got `2`"),
+        (
+            r#"{"a": 1 "b": 2}"#,
+            "expected `,` or `}` after a member, got `\"`",
+        ),
+        ("[] x", "expected the end of input, got `x`"),
+        (r#"{"k": "é", "l": {}}"#, "`}` ends the object of 2 members"),
+    ] {
+        println!("Checking {}", input);
+        let explanation = explain_parse(input, &ParserOptions::default());
+        assert_eq!(explanation.steps.last().unwrap().decision, last);
+        let value = input.parse::<JSONValue>();
+        assert_eq!(explanation.result.is_ok(), value.is_ok());
+    }
+    let explanation = explain_parse("[]", &ParserOptions::default());
+    assert_eq!(
+        explanation.render("[]"),
+        "0: `[` starts an array\n1:   `]` ends the empty array\nok"
+    );
+}
+
+#[test]
+fn test_require_root() {
+    let require = |object, array| ParserOptions {
+        require_object_root: object,
+        require_array_root: array,
+        ..ParserOptions::default()
+    };
+    let object = require(true, false);
+    let array = require(false, true);
+    let either = require(true, true);
+    for s in vec![
+        (" {\"a\": 1}", &object, None),
+        ("[1]", &array, None),
+        ("{}", &either, None),
+        ("[]", &either, None),
+        (
+            "42",
+            &object,
+            Some("Expected an object at the root, got a number"),
+        ),
+        (
+            " \"text\"",
+            &object,
+            Some("Expected an object at the root, got a string"),
+        ),
+        (
+            "{}",
+            &array,
+            Some("Expected an array at the root, got an object"),
+        ),
+        (
+            "null",
+            &either,
+            Some("Expected an object or an array at the root, got null"),
+        ),
+        (
+            "true",
+            &array,
+            Some("Expected an array at the root, got a boolean"),
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        match (parse_json_with(s.0, s.1), s.2) {
+            (Ok(_), None) => (),
+            (Err(err), Some(reason)) => {
+                assert_eq!(err.reason, reason);
+                assert_eq!(err.kind, JSONParseErrorKind::Syntax);
+                assert_eq!(err.position, Some(s.0.len() - s.0.trim_start().len()));
+            }
+            (result, _) => panic!("Unexpected {:?}", result),
+        }
+    }
+    // broken input still reports the syntax error
+    assert!(parse_json_with("x", &object)
+        .unwrap_err()
+        .reason
+        .starts_with("Unexpected"));
+    assert!(parse_json_with("{", &object).is_err());
+}
+
+#[test]
+fn test_number_edge_cases() {
+    let default = ParserOptions::default();
+    for s in vec![
+        ("1.7976931348623157e308", f64::MAX),
+        ("1.7976931348623158e308", f64::MAX),
+        ("1.7976931348623159e308", f64::INFINITY),
+        ("-1e309", f64::NEG_INFINITY),
+        ("4.9406564584124654e-324", 5e-324),
+        ("2.4703282292062328e-324", 5e-324),
+        ("2.4703282292062327e-324", 0.0),
+        ("2.2250738585072011e-308", 2.225073858507201e-308),
+        ("2.2250738585072012e-308", f64::MIN_POSITIVE),
+        ("9007199254740993", 9007199254740992.0),
+        (
+            "0.1000000000000000055511151231257827021181583404541015625",
+            0.1,
+        ),
+        ("1e-400", 0.0),
+        ("0e999999", 0.0),
+        ("123456789012345678901234567890e-30", 0.12345678901234568),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(
+            parse_json_with(s.0, &default).unwrap(),
+            JSONValue::JSONNumber(s.1)
+        );
+    }
+    for s in vec!["-0", "-0.0", "-0e10", "-1e-400"] {
+        println!("Checking {}", s);
+        assert_eq!(number_bits(s, &default), (-0.0f64).to_bits());
+    }
+
+    let options = |f: fn(&mut ParserOptions)| {
+        let mut options = ParserOptions::default();
+        f(&mut options);
+        options
+    };
+    let max = options(|o| o.number_overflow = NumberOverflow::Max);
+    let overflow = options(|o| o.number_overflow = NumberOverflow::Error);
+    let underflow = options(|o| o.reject_number_underflow = true);
+    let zero = options(|o| o.normalize_negative_zero = true);
+    assert_eq!(
+        parse_json_with("[1e400, -1e400]", &max).unwrap(),
+        parse_json("[1.7976931348623157e308, -1.7976931348623157e308]").unwrap()
+    );
+    assert_eq!(
+        parse_json_with("1e308", &overflow).unwrap(),
+        JSONValue::JSONNumber(1e308)
+    );
+    let err = parse_json_with("[1, -1e400]", &overflow).unwrap_err();
+    assert_eq!(err.reason, "Number -1e400 is out of range at position 4");
+    assert_eq!(err.position, Some(4));
+    let err = parse_json_with("{\"a\": 1e-400}", &underflow).unwrap_err();
+    assert_eq!(err.reason, "Number 1e-400 is too small at position 6");
+    assert_eq!(err.position, Some(6));
+    for s in vec!["0", "-0.000e-999", "5e-324", "0e999999"] {
+        println!("Checking {}", s);
+        assert!(parse_json_with(s, &underflow).is_ok());
+    }
+    for s in vec!["-0", "-0.0e5", "0"] {
+        println!("Checking {}", s);
+        assert_eq!(number_bits(s, &zero), 0);
+    }
+    assert_eq!(number_bits("-1", &zero), (-1.0f64).to_bits());
+}
+
+fn number_bits(s: &str, options: &ParserOptions) -> u64 {
+    match parse_json_with(s, options).unwrap() {
+        JSONValue::JSONNumber(n) => return n.to_bits(),
+        other => panic!("Unexpected {:?}", other),
+    }
+}
+
+#[test]
+fn test_number_round_trip() {
+    let config = serializer::SerializerConfig::compact();
+    for n in vec![
+        5e-324,
+        -5e-324,
+        1.5e-320,
+        2.225073858507201e-308,
+        f64::MIN_POSITIVE,
+        f64::MAX,
+        f64::MIN,
+        -0.0,
+        0.1 + 0.2,
+        1e21,
+        123456789.123456789,
+    ] {
+        println!("Checking {:?}", n);
+        let text = JSONValue::JSONNumber(n).to_string_with(&config);
+        assert_eq!(number_bits(&text, &ParserOptions::default()), n.to_bits());
+    }
+}
@@ -0,0 +1,87 @@
+/// Declares a companion patch type for a struct: every field becomes an
+/// `Option` that's `None` when the update leaves it alone, with a
+/// `FromJSONValue` impl reading a JSON Merge Patch body (RFC 7396) and an
+/// `apply` method writing the sent fields into the struct. List the fields
+/// the patch may change with their types in the struct:
+///
+/// ```
+/// # #[macro_use] extern crate rsjson;
+/// # fn main() {
+/// struct User {
+///     name: String,
+///     email: Option<String>,
+/// }
+///
+/// json_patch! {
+///     pub struct UserPatch for User {
+///         name: String,
+///         email: Option<String>,
+///     }
+/// }
+///
+/// let mut user = User { name: "a".to_owned(), email: Some("a@b.c".to_owned()) };
+/// let body: rsjson::JSONValue = r#"{"email": null}"#.parse().unwrap();
+/// body.convert::<UserPatch>().unwrap().apply(&mut user);
+/// assert_eq!(user.name, "a");
+/// assert_eq!(user.email, None);
+/// # }
+/// ```
+///
+/// As in merge patches, `null` clears `Option` fields; other fields can't be
+/// null. Fields are replaced as a whole, nested objects included. Keys the
+/// patch has no field for are ignored. Attributes before `struct`, like
+/// derives, go on the patch type, which always derives `Default`.
+#[macro_export]
+macro_rules! json_patch {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $patch:ident for $target:ty {
+            $($field:ident: $ty:ty),* $(,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Default)]
+        $vis struct $patch {
+            $(pub $field: Option<$ty>,)*
+        }
+
+        impl $patch {
+            /// Writes the fields the patch sets into `target`
+            pub fn apply(self, target: &mut $target) {
+                $(
+                    if let Some(value) = self.$field {
+                        target.$field = value;
+                    }
+                )*
+            }
+
+            /// Whether the patch leaves every field alone
+            pub fn is_empty(&self) -> bool {
+                return $(self.$field.is_none() &&)* true;
+            }
+        }
+
+        impl $crate::convert::FromJSONValue for $patch {
+            fn from_json(
+                value: &$crate::JSONValue,
+            ) -> Result<$patch, $crate::convert::JSONConvertError> {
+                let value = value.expand_raw();
+                if let $crate::JSONValue::JSONObject(_) = *value {
+                } else {
+                    return Err($crate::convert::JSONConvertError::unexpected("object", &value));
+                }
+                return Ok($patch {
+                    $(
+                        $field: match value.pointer(concat!("/", stringify!($field))) {
+                            Some(field) => Some($crate::convert::convert_at(field, stringify!($field))?),
+                            None => None,
+                        },
+                    )*
+                });
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests;
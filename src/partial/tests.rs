@@ -0,0 +1,103 @@
+#![allow(clippy::useless_vec)]
+
+use convert::FromJSONValue;
+use JSONValue;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Settings {
+    theme: String,
+    size: u8,
+    font: Option<String>,
+    tags: Vec<String>,
+    internal: bool,
+}
+
+json_patch! {
+    #[derive(Debug, PartialEq)]
+    struct SettingsPatch for Settings {
+        theme: String,
+        size: u8,
+        font: Option<String>,
+        tags: Vec<String>,
+    }
+}
+
+fn settings() -> Settings {
+    return Settings {
+        theme: "dark".to_owned(),
+        size: 2,
+        font: Some("mono".to_owned()),
+        tags: vec!["a".to_owned()],
+        internal: true,
+    };
+}
+
+#[test]
+fn test_apply() {
+    for s in vec![
+        ("{}", settings()),
+        (
+            r#"{"theme": "light", "internal": false}"#,
+            Settings {
+                theme: "light".to_owned(),
+                ..settings()
+            },
+        ),
+        (
+            r#"{"font": null, "tags": []}"#,
+            Settings {
+                font: None,
+                tags: vec![],
+                ..settings()
+            },
+        ),
+        (
+            r#"{"size": 3, "font": "serif"}"#,
+            Settings {
+                size: 3,
+                font: Some("serif".to_owned()),
+                ..settings()
+            },
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        let patch = SettingsPatch::from_json(&parse(s.0)).unwrap();
+        assert_eq!(patch.is_empty(), s.1 == settings());
+        let mut target = settings();
+        patch.apply(&mut target);
+        assert_eq!(target, s.1);
+    }
+    let patch = parse(r#"{"font": null}"#)
+        .convert::<SettingsPatch>()
+        .unwrap();
+    assert_eq!(
+        patch,
+        SettingsPatch {
+            font: Some(None),
+            ..SettingsPatch::default()
+        }
+    );
+}
+
+#[test]
+fn test_errors() {
+    for s in vec![
+        (r#"{"theme": null}"#, "Expected string, got null at /theme"),
+        (r#"{"size": 300}"#, "Expected u8, got number 300 at /size"),
+        (
+            r#"{"tags": [1]}"#,
+            "Expected string, got number 1 at /tags/0",
+        ),
+        ("[]", "Expected object, got array []"),
+    ] {
+        println!("Checking {}", s.0);
+        let err = parse(s.0).convert::<SettingsPatch>().unwrap_err();
+        assert_eq!(err.to_string(), s.1);
+    }
+    let raw = JSONValue::raw(r#"{"size": 5}"#).unwrap();
+    assert_eq!(raw.convert::<SettingsPatch>().unwrap().size, Some(5));
+}
@@ -0,0 +1,422 @@
+use super::*;
+use pointer::{parse_index, parse_pointer};
+
+#[cfg(test)]
+mod tests;
+
+const END_OF_ARRAY: &str = "-";
+
+#[derive(Debug, Clone)]
+pub struct JSONPatchError {
+    pub reason: String,
+}
+
+/// Single RFC 6902 JSON Patch operation. Paths are JSON Pointers.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOperation {
+    Add { path: String, value: JSONValue },
+    Remove { path: String },
+    Replace { path: String, value: JSONValue },
+    Move { from: String, path: String },
+    Copy { from: String, path: String },
+    Test { path: String, value: JSONValue },
+}
+
+impl PatchOperation {
+    pub fn path(&self) -> &str {
+        match *self {
+            PatchOperation::Add { ref path, .. }
+            | PatchOperation::Remove { ref path }
+            | PatchOperation::Replace { ref path, .. }
+            | PatchOperation::Move { ref path, .. }
+            | PatchOperation::Copy { ref path, .. }
+            | PatchOperation::Test { ref path, .. } => return path,
+        }
+    }
+
+    pub fn to_json(&self) -> JSONValue {
+        let mut map = JSONMap::new();
+        let (op, path) = match *self {
+            PatchOperation::Add {
+                ref path,
+                ref value,
+            } => {
+                map.insert("value".to_owned(), Box::new(value.clone()));
+                ("add", path)
+            }
+            PatchOperation::Remove { ref path } => ("remove", path),
+            PatchOperation::Replace {
+                ref path,
+                ref value,
+            } => {
+                map.insert("value".to_owned(), Box::new(value.clone()));
+                ("replace", path)
+            }
+            PatchOperation::Move { ref from, ref path } => {
+                map.insert(
+                    "from".to_owned(),
+                    Box::new(JSONValue::JSONString(from.clone())),
+                );
+                ("move", path)
+            }
+            PatchOperation::Copy { ref from, ref path } => {
+                map.insert(
+                    "from".to_owned(),
+                    Box::new(JSONValue::JSONString(from.clone())),
+                );
+                ("copy", path)
+            }
+            PatchOperation::Test {
+                ref path,
+                ref value,
+            } => {
+                map.insert("value".to_owned(), Box::new(value.clone()));
+                ("test", path)
+            }
+        };
+        map.insert(
+            "op".to_owned(),
+            Box::new(JSONValue::JSONString(op.to_owned())),
+        );
+        map.insert(
+            "path".to_owned(),
+            Box::new(JSONValue::JSONString(path.clone())),
+        );
+        return JSONValue::JSONObject(map);
+    }
+
+    pub fn from_json(value: &JSONValue) -> Result<PatchOperation, JSONPatchError> {
+        let map = match *value {
+            JSONValue::JSONObject(ref map) => map,
+            _ => return Err(make_err("Patch operation must be an object".to_owned())),
+        };
+        let string_member = |name: &str| match map.get(name).map(|v| &**v) {
+            Some(JSONValue::JSONString(s)) => Ok(s.clone()),
+            _ => Err(make_err(format!(
+                "Patch operation requires string {}",
+                name
+            ))),
+        };
+        let value_member = |name: &str| match map.get(name) {
+            Some(v) => Ok((**v).clone()),
+            None => Err(make_err(format!("Patch operation requires {}", name))),
+        };
+        let path = string_member("path")?;
+        match string_member("op")?.as_str() {
+            "add" => {
+                return Ok(PatchOperation::Add {
+                    path,
+                    value: value_member("value")?,
+                })
+            }
+            "remove" => return Ok(PatchOperation::Remove { path }),
+            "replace" => {
+                return Ok(PatchOperation::Replace {
+                    path,
+                    value: value_member("value")?,
+                })
+            }
+            "move" => {
+                return Ok(PatchOperation::Move {
+                    from: string_member("from")?,
+                    path,
+                })
+            }
+            "copy" => {
+                return Ok(PatchOperation::Copy {
+                    from: string_member("from")?,
+                    path,
+                })
+            }
+            "test" => {
+                return Ok(PatchOperation::Test {
+                    path,
+                    value: value_member("value")?,
+                })
+            }
+            op => return Err(make_err(format!("Unknown patch operation {}", op))),
+        }
+    }
+}
+
+/// Reads a patch document: an array of operation objects.
+pub fn parse_patch(value: &JSONValue) -> Result<Vec<PatchOperation>, JSONPatchError> {
+    match *value {
+        JSONValue::JSONArray(ref items) => {
+            return items
+                .iter()
+                .map(|item| PatchOperation::from_json(item))
+                .collect()
+        }
+        _ => return Err(make_err("Patch must be an array".to_owned())),
+    }
+}
+
+pub fn patch_to_json(patch: &[PatchOperation]) -> JSONValue {
+    return JSONValue::JSONArray(patch.iter().map(|op| Box::new(op.to_json())).collect());
+}
+
+impl JSONValue {
+    /// Applies the operations in order. The patch is atomic:
+    /// if any operation fails the value is left untouched.
+    pub fn apply_patch(&mut self, patch: &[PatchOperation]) -> Result<(), JSONPatchError> {
+        let mut result = self.clone();
+        for op in patch {
+            apply_operation(&mut result, op)?;
+        }
+        *self = result;
+        return Ok(());
+    }
+}
+
+fn apply_operation(doc: &mut JSONValue, op: &PatchOperation) -> Result<(), JSONPatchError> {
+    match *op {
+        PatchOperation::Add {
+            ref path,
+            ref value,
+        } => return add_at(doc, path, value.clone()),
+        PatchOperation::Remove { ref path } => return remove_at(doc, path).map(|_| ()),
+        PatchOperation::Replace {
+            ref path,
+            ref value,
+        } => {
+            let target = doc
+                .pointer_mut(path)
+                .ok_or_else(|| make_err(format!("Nothing to replace at {}", path)))?;
+            *target = value.clone();
+            return Ok(());
+        }
+        PatchOperation::Move { ref from, ref path } => {
+            if path.starts_with(&format!("{}/", from)) {
+                return Err(make_err(format!(
+                    "Unable to move {} into its own child",
+                    from
+                )));
+            }
+            let value = remove_at(doc, from)?;
+            return add_at(doc, path, value);
+        }
+        PatchOperation::Copy { ref from, ref path } => {
+            let value = doc
+                .pointer(from)
+                .ok_or_else(|| make_err(format!("Nothing to copy at {}", from)))?
+                .clone();
+            return add_at(doc, path, value);
+        }
+        PatchOperation::Test {
+            ref path,
+            ref value,
+        } => {
+            if doc.pointer(path) != Some(value) {
+                return Err(make_err(format!("Test failed at {}", path)));
+            }
+            return Ok(());
+        }
+    }
+}
+
+/// Patch undoing `patch` on the document it was applied to. Fails if `patch`
+/// doesn't apply to `original`. `test` operations have no inverse and are dropped.
+pub fn invert(
+    patch: &[PatchOperation],
+    original: &JSONValue,
+) -> Result<Vec<PatchOperation>, JSONPatchError> {
+    let mut doc = original.clone();
+    let mut inverse = vec![];
+    for op in patch {
+        // whatever an add, move or copy overwrites
+        let overwritten = match *op {
+            PatchOperation::Add { ref path, .. }
+            | PatchOperation::Move { ref path, .. }
+            | PatchOperation::Copy { ref path, .. } => overwritten(&doc, path),
+            _ => None,
+        };
+        let before = doc.clone();
+        apply_operation(&mut doc, op)?;
+        let mut undo = match *op {
+            PatchOperation::Add { ref path, .. } | PatchOperation::Copy { ref path, .. } => {
+                match overwritten {
+                    Some(old) => vec![PatchOperation::Replace {
+                        path: path.clone(),
+                        value: old,
+                    }],
+                    None => vec![PatchOperation::Remove {
+                        path: resolve_end(&doc, path),
+                    }],
+                }
+            }
+            PatchOperation::Remove { ref path } => vec![PatchOperation::Add {
+                path: path.clone(),
+                value: before
+                    .pointer(path)
+                    .cloned()
+                    .unwrap_or(JSONValue::JSONNull()),
+            }],
+            PatchOperation::Replace { ref path, .. } => vec![PatchOperation::Replace {
+                path: path.clone(),
+                value: before
+                    .pointer(path)
+                    .cloned()
+                    .unwrap_or(JSONValue::JSONNull()),
+            }],
+            // the move replaced an ancestor of `from`, which held the moved value too
+            PatchOperation::Move { ref from, ref path }
+                if from.starts_with(&format!("{}/", path)) =>
+            {
+                vec![PatchOperation::Replace {
+                    path: path.clone(),
+                    value: before
+                        .pointer(path)
+                        .cloned()
+                        .unwrap_or(JSONValue::JSONNull()),
+                }]
+            }
+            PatchOperation::Move { ref from, ref path } => {
+                let mut ops = vec![PatchOperation::Move {
+                    from: resolve_end(&doc, path),
+                    path: from.clone(),
+                }];
+                if let Some(old) = overwritten {
+                    ops.push(PatchOperation::Add {
+                        path: path.clone(),
+                        value: old,
+                    });
+                }
+                ops
+            }
+            PatchOperation::Test { .. } => vec![],
+        };
+        undo.reverse();
+        inverse.extend(undo);
+    }
+    inverse.reverse();
+    return Ok(inverse);
+}
+
+// The value an `add` to `path` replaces rather than inserts before:
+// an existing object member, or the whole document
+fn overwritten(doc: &JSONValue, path: &str) -> Option<JSONValue> {
+    if path.is_empty() {
+        return Some(doc.clone());
+    }
+    let (parent, _) = split_parent(path).ok()?;
+    match doc.pointer(parent) {
+        Some(&JSONValue::JSONObject(_)) => return doc.pointer(path).cloned(),
+        _ => return None,
+    }
+}
+
+// Turns a trailing `-` into the index of the item it appended
+fn resolve_end(doc: &JSONValue, path: &str) -> String {
+    match path.strip_suffix("/-") {
+        Some(parent) => match doc.pointer(parent) {
+            Some(JSONValue::JSONArray(items)) if !items.is_empty() => {
+                return format!("{}/{}", parent, items.len() - 1)
+            }
+            _ => return path.to_owned(),
+        },
+        None => return path.to_owned(),
+    }
+}
+
+/// Single patch with the effect of applying `first` and then `second`.
+/// Adjacent operations on the same path are folded: a replace after an add
+/// or replace keeps only the new value, a remove after a replace only removes.
+pub fn compose(first: &[PatchOperation], second: &[PatchOperation]) -> Vec<PatchOperation> {
+    let mut result: Vec<PatchOperation> = vec![];
+    for op in first.iter().chain(second) {
+        let folded = match (result.last(), op) {
+            (
+                Some(PatchOperation::Add { path: a, .. }),
+                PatchOperation::Replace { path: b, value },
+            ) if a == b && !a.ends_with("/-") => Some(PatchOperation::Add {
+                path: a.clone(),
+                value: value.clone(),
+            }),
+            (
+                Some(PatchOperation::Replace { path: a, .. }),
+                PatchOperation::Replace { path: b, value },
+            ) if a == b => Some(PatchOperation::Replace {
+                path: a.clone(),
+                value: value.clone(),
+            }),
+            (Some(PatchOperation::Replace { path: a, .. }), PatchOperation::Remove { path: b })
+                if a == b =>
+            {
+                Some(PatchOperation::Remove { path: a.clone() })
+            }
+            _ => None,
+        };
+        match folded {
+            Some(folded) => *result.last_mut().unwrap() = folded,
+            None => result.push(op.clone()),
+        }
+    }
+    return result;
+}
+
+// Splits a pointer into the parent pointer and the last unescaped token
+fn split_parent(path: &str) -> Result<(&str, String), JSONPatchError> {
+    let tokens =
+        parse_pointer(path).ok_or_else(|| make_err(format!("Invalid pointer {}", path)))?;
+    let last = tokens
+        .last()
+        .cloned()
+        .ok_or_else(|| make_err("Operation can't target the whole document".to_owned()))?;
+    return Ok((&path[..path.rfind('/').unwrap()], last));
+}
+
+/// Adds a value per RFC 6902 `add` semantics: replaces an object member,
+/// inserts into an array (shifting later elements) or appends for `-`.
+/// The empty pointer replaces the whole document.
+pub fn add_at(doc: &mut JSONValue, path: &str, value: JSONValue) -> Result<(), JSONPatchError> {
+    if path.is_empty() {
+        *doc = value;
+        return Ok(());
+    }
+    let (parent, token) = split_parent(path)?;
+    let parent = doc
+        .pointer_mut(parent)
+        .ok_or_else(|| make_err(format!("Parent of {} doesn't exist", path)))?;
+    match *parent {
+        JSONValue::JSONObject(ref mut map) => {
+            map.insert(token, Box::new(value));
+        }
+        JSONValue::JSONArray(ref mut items) => {
+            let index = if token == END_OF_ARRAY {
+                items.len()
+            } else {
+                parse_index(&token).ok_or_else(|| make_err(format!("Invalid index in {}", path)))?
+            };
+            if index > items.len() {
+                return Err(make_err(format!("Index out of bounds in {}", path)));
+            }
+            items.insert(index, Box::new(value));
+        }
+        _ => return Err(make_err(format!("Parent of {} is not a container", path))),
+    }
+    return Ok(());
+}
+
+/// Removes and returns the value at the pointer.
+pub fn remove_at(doc: &mut JSONValue, path: &str) -> Result<JSONValue, JSONPatchError> {
+    let (parent, token) = split_parent(path)?;
+    let parent = doc
+        .pointer_mut(parent)
+        .ok_or_else(|| make_err(format!("Parent of {} doesn't exist", path)))?;
+    let removed = match *parent {
+        JSONValue::JSONObject(ref mut map) => map.remove(&token),
+        JSONValue::JSONArray(ref mut items) => match parse_index(&token) {
+            Some(i) if i < items.len() => Some(items.remove(i)),
+            _ => None,
+        },
+        _ => None,
+    };
+    return removed
+        .map(|value| *value)
+        .ok_or_else(|| make_err(format!("Nothing to remove at {}", path)));
+}
+
+fn make_err(s: String) -> JSONPatchError {
+    JSONPatchError { reason: s }
+}
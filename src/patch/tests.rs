@@ -0,0 +1,209 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+fn apply(doc: &str, patch: &str) -> Result<JSONValue, JSONPatchError> {
+    let mut doc = parse(doc);
+    doc.apply_patch(&parse_patch(&parse(patch))?)?;
+    return Ok(doc);
+}
+
+#[test]
+fn test_valid_patches() {
+    // Examples from RFC 6902 appendix A
+    for s in vec![
+        (
+            r#"{"foo": "bar"}"#,
+            r#"[{"op": "add", "path": "/baz", "value": "qux"}]"#,
+            r#"{"baz": "qux", "foo": "bar"}"#,
+        ),
+        (
+            r#"{"foo": ["bar", "baz"]}"#,
+            r#"[{"op": "add", "path": "/foo/1", "value": "qux"}]"#,
+            r#"{"foo": ["bar", "qux", "baz"]}"#,
+        ),
+        (
+            r#"{"baz": "qux", "foo": "bar"}"#,
+            r#"[{"op": "remove", "path": "/baz"}]"#,
+            r#"{"foo": "bar"}"#,
+        ),
+        (
+            r#"{"foo": ["bar", "qux", "baz"]}"#,
+            r#"[{"op": "remove", "path": "/foo/1"}]"#,
+            r#"{"foo": ["bar", "baz"]}"#,
+        ),
+        (
+            r#"{"baz": "qux", "foo": "bar"}"#,
+            r#"[{"op": "replace", "path": "/baz", "value": "boo"}]"#,
+            r#"{"baz": "boo", "foo": "bar"}"#,
+        ),
+        (
+            r#"{"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}"#,
+            r#"[{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]"#,
+            r#"{"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}"#,
+        ),
+        (
+            r#"{"foo": ["all", "grass", "cows", "eat"]}"#,
+            r#"[{"op": "move", "from": "/foo/1", "path": "/foo/3"}]"#,
+            r#"{"foo": ["all", "cows", "eat", "grass"]}"#,
+        ),
+        (
+            r#"{"foo": ["bar"]}"#,
+            r#"[{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]"#,
+            r#"{"foo": ["bar", ["abc", "def"]]}"#,
+        ),
+        (
+            r#"{"foo": {"bar": 1}}"#,
+            r#"[{"op": "copy", "from": "/foo", "path": "/baz"}, {"op": "test", "path": "/baz/bar", "value": 1}]"#,
+            r#"{"foo": {"bar": 1}, "baz": {"bar": 1}}"#,
+        ),
+        (
+            r#"{"foo": 1}"#,
+            r#"[{"op": "add", "path": "", "value": [1]}]"#,
+            r#"[1]"#,
+        ),
+    ] {
+        println!("Checking {}", s.1);
+        assert_eq!(apply(s.0, s.1).unwrap(), parse(s.2));
+    }
+}
+
+#[test]
+fn test_invalid_patches() {
+    for s in vec![
+        (r#"{"foo": 1}"#, r#"[{"op": "remove", "path": "/bar"}]"#),
+        (
+            r#"{"foo": 1}"#,
+            r#"[{"op": "replace", "path": "/bar", "value": 1}]"#,
+        ),
+        (
+            r#"{"foo": 1}"#,
+            r#"[{"op": "add", "path": "/bar/baz", "value": 1}]"#,
+        ),
+        (
+            r#"{"foo": [1]}"#,
+            r#"[{"op": "add", "path": "/foo/2", "value": 1}]"#,
+        ),
+        (
+            r#"{"foo": [1]}"#,
+            r#"[{"op": "add", "path": "/foo/01", "value": 1}]"#,
+        ),
+        (
+            r#"{"foo": 1}"#,
+            r#"[{"op": "test", "path": "/foo", "value": "1"}]"#,
+        ),
+        (
+            r#"{"foo": {"a": 1}}"#,
+            r#"[{"op": "move", "from": "/foo", "path": "/foo/a"}]"#,
+        ),
+        (r#"{"foo": 1}"#, r#"[{"op": "launch", "path": "/foo"}]"#),
+        (r#"{"foo": 1}"#, r#"[{"op": "add", "path": "/bar"}]"#),
+        (r#"{"foo": 1}"#, r#"{"op": "remove", "path": "/foo"}"#),
+    ] {
+        apply(s.0, s.1).expect_err(&format!("Patch {} should fail", s.1));
+    }
+}
+
+#[test]
+fn test_patch_is_atomic() {
+    let mut doc = parse(r#"{"foo": 1}"#);
+    let patch = parse_patch(&parse(
+        r#"[{"op": "add", "path": "/bar", "value": 2}, {"op": "remove", "path": "/baz"}]"#,
+    ))
+    .unwrap();
+    doc.apply_patch(&patch).unwrap_err();
+    assert_eq!(doc, parse(r#"{"foo": 1}"#));
+}
+
+#[test]
+fn test_patch_json_roundtrip() {
+    let patch = parse(
+        r#"[
+            {"op": "add", "path": "/a", "value": 1},
+            {"op": "remove", "path": "/b"},
+            {"op": "replace", "path": "/c", "value": null},
+            {"op": "move", "from": "/d", "path": "/e"},
+            {"op": "copy", "from": "/f", "path": "/g"},
+            {"op": "test", "path": "/h", "value": [true]}
+        ]"#,
+    );
+    assert_eq!(patch_to_json(&parse_patch(&patch).unwrap()), patch);
+}
+
+#[test]
+fn test_invert() {
+    let doc = r#"{"a": 1, "b": [1, 2, 3], "c": {"d": "e"}, "f": null}"#;
+    for s in vec![
+        r#"[{"op": "add", "path": "/x", "value": 1}]"#,
+        r#"[{"op": "add", "path": "/a", "value": 2}]"#,
+        r#"[{"op": "add", "path": "/b/1", "value": 9}, {"op": "add", "path": "/b/-", "value": 8}]"#,
+        r#"[{"op": "add", "path": "", "value": [1]}]"#,
+        r#"[{"op": "remove", "path": "/b/0"}, {"op": "remove", "path": "/c"}]"#,
+        r#"[{"op": "replace", "path": "/c/d", "value": [1]}, {"op": "replace", "path": "", "value": 1}]"#,
+        r#"[{"op": "move", "from": "/a", "path": "/c/a"}]"#,
+        r#"[{"op": "move", "from": "/a", "path": "/f"}]"#,
+        r#"[{"op": "move", "from": "/b/0", "path": "/b/-"}]"#,
+        r#"[{"op": "move", "from": "/c/d", "path": "/b/1"}]"#,
+        r#"[{"op": "move", "from": "/c/d", "path": "/c"}]"#,
+        r#"[{"op": "move", "from": "/b/1", "path": ""}]"#,
+        r#"[{"op": "copy", "from": "/c", "path": "/a"}, {"op": "copy", "from": "/a", "path": "/b/0"}]"#,
+        r#"[{"op": "test", "path": "/a", "value": 1}, {"op": "remove", "path": "/a"}]"#,
+    ] {
+        println!("Checking {}", s);
+        let original = parse(doc);
+        let patch = parse_patch(&parse(s)).unwrap();
+        let inverse = invert(&patch, &original).unwrap();
+        let mut value = apply(doc, s).unwrap();
+        value.apply_patch(&inverse).unwrap();
+        assert_eq!(value, original);
+    }
+    let patch = parse_patch(&parse(r#"[{"op": "move", "from": "/c/d", "path": "/c"}]"#)).unwrap();
+    assert_eq!(
+        patch_to_json(&invert(&patch, &parse(doc)).unwrap()),
+        parse(r#"[{"op": "replace", "path": "/c", "value": {"d": "e"}}]"#)
+    );
+    let patch = parse_patch(&parse(r#"[{"op": "remove", "path": "/x"}]"#)).unwrap();
+    assert!(invert(&patch, &parse(doc)).is_err());
+}
+
+#[test]
+fn test_compose() {
+    let doc = r#"{"a": 1, "b": [1, 2]}"#;
+    for s in vec![
+        (
+            r#"[{"op": "add", "path": "/x", "value": 1}]"#,
+            r#"[{"op": "replace", "path": "/x", "value": 2}]"#,
+            r#"[{"op": "add", "path": "/x", "value": 2}]"#,
+        ),
+        (
+            r#"[{"op": "replace", "path": "/a", "value": 2}]"#,
+            r#"[{"op": "replace", "path": "/a", "value": 3}, {"op": "remove", "path": "/a"}]"#,
+            r#"[{"op": "remove", "path": "/a"}]"#,
+        ),
+        (
+            r#"[{"op": "add", "path": "/b/-", "value": 3}]"#,
+            r#"[{"op": "replace", "path": "/b/2", "value": 4}]"#,
+            r#"[{"op": "add", "path": "/b/-", "value": 3}, {"op": "replace", "path": "/b/2", "value": 4}]"#,
+        ),
+        (
+            r#"[{"op": "replace", "path": "/a", "value": 2}]"#,
+            r#"[{"op": "test", "path": "/a", "value": 2}, {"op": "replace", "path": "/a", "value": 3}]"#,
+            r#"[{"op": "replace", "path": "/a", "value": 2}, {"op": "test", "path": "/a", "value": 2}, {"op": "replace", "path": "/a", "value": 3}]"#,
+        ),
+    ] {
+        println!("Checking {} + {}", s.0, s.1);
+        let first = parse_patch(&parse(s.0)).unwrap();
+        let second = parse_patch(&parse(s.1)).unwrap();
+        let composed = compose(&first, &second);
+        assert_eq!(patch_to_json(&composed), parse(s.2));
+        let mut expected = apply(doc, s.0).unwrap();
+        expected.apply_patch(&second).unwrap();
+        let mut value = parse(doc);
+        value.apply_patch(&composed).unwrap();
+        assert_eq!(value, expected);
+    }
+}
@@ -0,0 +1,155 @@
+use super::*;
+use pointer::{escape_token, parse_pointer};
+
+#[cfg(test)]
+mod tests;
+
+const ANY_SEGMENT: &str = "*";
+const ANY_DEPTH: &str = "**";
+
+#[derive(Debug, Clone)]
+pub struct JSONPatternError {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Any,
+    AnyDepth,
+}
+
+/// Pointer-shaped path pattern, e.g. `/users/*/password` or `/**/token`.
+/// `*` matches exactly one segment, `**` matches any number of segments (including none).
+/// Every other segment is a literal pointer token, escaped as in RFC 6901.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPattern {
+    segments: Vec<Segment>,
+}
+
+impl PathPattern {
+    pub fn compile(pattern: &str) -> Result<PathPattern, JSONPatternError> {
+        let tokens = parse_pointer(pattern).ok_or_else(|| JSONPatternError {
+            reason: format!("Pattern {} must be empty or start with /", pattern),
+        })?;
+        let segments = tokens
+            .into_iter()
+            .map(|token| match token.as_str() {
+                ANY_SEGMENT => Segment::Any,
+                ANY_DEPTH => Segment::AnyDepth,
+                _ => Segment::Key(token),
+            })
+            .collect();
+        return Ok(PathPattern { segments });
+    }
+
+    /// Checks a JSON Pointer against the pattern. Invalid pointers never match.
+    pub fn matches(&self, pointer: &str) -> bool {
+        match parse_pointer(pointer) {
+            Some(tokens) => return self.matches_tokens(&tokens),
+            None => return false,
+        }
+    }
+
+    /// Checks already unescaped path tokens against the pattern.
+    pub fn matches_tokens<S: AsRef<str>>(&self, tokens: &[S]) -> bool {
+        return self.states_after(tokens).contains(&self.segments.len());
+    }
+
+    /// Returns true if the path itself or any path below it can match.
+    /// Walkers use it to skip subtrees that can't contain a match.
+    pub fn matches_prefix<S: AsRef<str>>(&self, tokens: &[S]) -> bool {
+        return !self.states_after(tokens).is_empty();
+    }
+
+    /// Collects pointers and values of every node in the document matching the pattern,
+    /// in document order (object keys in map iteration order).
+    pub fn find<'a>(&self, document: &'a JSONValue) -> Vec<(String, &'a JSONValue)> {
+        let mut result = vec![];
+        let mut path = vec![];
+        self.find_into(document, &mut path, &mut result);
+        return result;
+    }
+
+    fn find_into<'a>(
+        &self,
+        value: &'a JSONValue,
+        path: &mut Vec<String>,
+        result: &mut Vec<(String, &'a JSONValue)>,
+    ) {
+        if !self.matches_prefix(path) {
+            return;
+        }
+        if self.matches_tokens(path) {
+            let pointer: String = path
+                .iter()
+                .map(|t| format!("/{}", escape_token(t)))
+                .collect();
+            result.push((pointer, value));
+        }
+        match *value {
+            JSONValue::JSONObject(ref map) => {
+                for (key, item) in map {
+                    path.push(key.clone());
+                    self.find_into(item, path, result);
+                    path.pop();
+                }
+            }
+            JSONValue::JSONArray(ref items) => {
+                for (i, item) in items.iter().enumerate() {
+                    path.push(i.to_string());
+                    self.find_into(item, path, result);
+                    path.pop();
+                }
+            }
+            _ => (),
+        }
+    }
+
+    // Runs the pattern as an NFA: a state is the index of the next segment to match,
+    // `segments.len()` being the accepting state.
+    fn states_after<S: AsRef<str>>(&self, tokens: &[S]) -> Vec<usize> {
+        let mut states = self.closure(vec![0]);
+        for token in tokens {
+            let mut next = vec![];
+            for &state in &states {
+                match self.segments.get(state) {
+                    Some(Segment::Key(key)) if key == token.as_ref() => next.push(state + 1),
+                    Some(Segment::Any) => next.push(state + 1),
+                    Some(Segment::AnyDepth) => next.push(state),
+                    _ => (),
+                }
+            }
+            states = self.closure(next);
+            if states.is_empty() {
+                break;
+            }
+        }
+        return states;
+    }
+
+    // `**` may match zero segments, so it's always possible to skip over it
+    fn closure(&self, mut states: Vec<usize>) -> Vec<usize> {
+        let mut i = 0;
+        while i < states.len() {
+            let state = states[i];
+            if self.segments.get(state) == Some(&Segment::AnyDepth)
+                && !states.contains(&(state + 1))
+            {
+                states.push(state + 1);
+            }
+            i += 1;
+        }
+        states.sort();
+        states.dedup();
+        return states;
+    }
+}
+
+impl FromStr for PathPattern {
+    type Err = JSONPatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return PathPattern::compile(s);
+    }
+}
@@ -0,0 +1,73 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+#[test]
+fn test_pattern_matches() {
+    for s in vec![
+        ("", ""),
+        ("/a/b", "/a/b"),
+        ("/a/*", "/a/b"),
+        ("/a/*/c", "/a/0/c"),
+        ("/**", ""),
+        ("/**", "/a/b/c"),
+        ("/**/token", "/token"),
+        ("/**/token", "/a/b/token"),
+        ("/a/**/c", "/a/c"),
+        ("/a/**/c", "/a/b/b/c"),
+        ("/**/b/**/d", "/a/b/c/d"),
+        ("/a~1b/c~0", "/a~1b/c~0"),
+    ] {
+        let pattern = PathPattern::compile(s.0).unwrap();
+        assert!(pattern.matches(s.1), "{} should match {}", s.0, s.1);
+    }
+}
+
+#[test]
+fn test_pattern_mismatches() {
+    for s in vec![
+        ("", "/a"),
+        ("/a/b", "/a"),
+        ("/a/b", "/a/b/c"),
+        ("/a/*", "/a"),
+        ("/a/*", "/a/b/c"),
+        ("/**/token", "/a/token/b"),
+        ("/a/**/c", "/b/c"),
+        ("/a", "a"),
+    ] {
+        let pattern = PathPattern::compile(s.0).unwrap();
+        assert!(!pattern.matches(s.1), "{} should not match {}", s.0, s.1);
+    }
+}
+
+#[test]
+fn test_invalid_patterns() {
+    for s in vec!["a", "*", "**/a"] {
+        PathPattern::compile(s).expect_err(&format!("Pattern {} should not compile", s));
+    }
+}
+
+#[test]
+fn test_matches_prefix() {
+    let pattern = PathPattern::compile("/users/*/password").unwrap();
+    assert!(pattern.matches_prefix(&["users"]));
+    assert!(pattern.matches_prefix(&["users", "0"]));
+    assert!(!pattern.matches_prefix(&["groups"]));
+    assert!(!pattern.matches_prefix(&["users", "0", "name"]));
+}
+
+#[test]
+fn test_find() {
+    let doc: JSONValue = r#"{"users": [{"password": 1, "name": 2}, {"password": 3}]}"#
+        .parse()
+        .unwrap();
+    let pattern = PathPattern::compile("/users/*/password").unwrap();
+    let found = pattern.find(&doc);
+    assert_eq!(
+        found,
+        vec![
+            ("/users/0/password".to_owned(), &JSONValue::JSONNumber(1.0)),
+            ("/users/1/password".to_owned(), &JSONValue::JSONNumber(3.0)),
+        ]
+    );
+}
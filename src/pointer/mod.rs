@@ -0,0 +1,150 @@
+use super::*;
+
+#[cfg(test)]
+mod tests;
+
+const SEPARATOR: char = '/';
+
+impl JSONValue {
+    /// Looks up a value by RFC 6901 JSON Pointer, e.g. `/definitions/user/0`.
+    /// The empty pointer refers to the whole document.
+    pub fn pointer(&self, pointer: &str) -> Option<&JSONValue> {
+        let mut current = self;
+        for token in parse_pointer(pointer)? {
+            current = match *current {
+                JSONValue::JSONObject(ref map) => map.get(&token)?,
+                JSONValue::JSONArray(ref items) => items.get(parse_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        return Some(current);
+    }
+
+    /// Mutable counterpart of `pointer`.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut JSONValue> {
+        let mut current = self;
+        for token in parse_pointer(pointer)? {
+            current = match *current {
+                JSONValue::JSONObject(ref mut map) => map.get_mut(&token)?,
+                JSONValue::JSONArray(ref mut items) => items.get_mut(parse_index(&token)?)?,
+                _ => return None,
+            };
+        }
+        return Some(current);
+    }
+
+    /// Looks up several keys of an object in one pass over its members, which
+    /// stops once every key is found. Values other than objects have no keys.
+    pub fn get_many(&self, keys: &[&str]) -> Vec<Option<&JSONValue>> {
+        let mut result = vec![None; keys.len()];
+        let members = match *self {
+            JSONValue::JSONObject(ref members) => members,
+            _ => return result,
+        };
+        let mut wanted: HashMap<&str, Vec<usize>> = HashMap::with_capacity(keys.len());
+        for (i, key) in keys.iter().enumerate() {
+            wanted.entry(*key).or_default().push(i);
+        }
+        for (key, value) in members.iter() {
+            if let Some(slots) = wanted.remove(key.as_str()) {
+                for i in slots {
+                    result[i] = Some(&**value);
+                }
+                if wanted.is_empty() {
+                    break;
+                }
+            }
+        }
+        return result;
+    }
+
+    /// Looks up several pointers, resolving the prefixes they share once, so
+    /// `/user/name` and `/user/email` walk to `/user` a single time.
+    /// Invalid pointers find nothing.
+    pub fn pointer_many(&self, pointers: &[&str]) -> Vec<Option<&JSONValue>> {
+        let mut result = vec![None; pointers.len()];
+        let parsed: Vec<Option<Vec<String>>> = pointers.iter().map(|p| parse_pointer(p)).collect();
+        let mut order: Vec<usize> = (0..pointers.len())
+            .filter(|&i| parsed[i].is_some())
+            .collect();
+        order.sort_by(|&a, &b| parsed[a].cmp(&parsed[b]));
+        // values along the previous pointer; `None` past where it stopped resolving
+        let mut path: Vec<Option<&JSONValue>> = vec![Some(self)];
+        let mut previous: &[String] = &[];
+        for i in order {
+            let tokens = parsed[i].as_ref().unwrap();
+            let shared = tokens
+                .iter()
+                .zip(previous)
+                .take_while(|&(a, b)| a == b)
+                .count();
+            path.truncate(shared + 1);
+            for token in &tokens[shared..] {
+                let next = path[path.len() - 1].and_then(|current| match *current {
+                    JSONValue::JSONObject(ref map) => return map.get(token).map(|value| &**value),
+                    JSONValue::JSONArray(ref items) => {
+                        let item = parse_index(token).and_then(|index| items.get(index));
+                        return item.map(|value| &**value);
+                    }
+                    _ => return None,
+                });
+                path.push(next);
+            }
+            result[i] = path[tokens.len()];
+            previous = tokens;
+        }
+        return result;
+    }
+
+    /// Collects the value at `pointer` in every item of an array, like a column
+    /// of a table, with `null` for items that don't have it. Values other than
+    /// arrays have no items. `stream::stream_pluck` does the same over a reader.
+    pub fn pluck(&self, pointer: &str) -> Vec<JSONValue> {
+        match *self {
+            JSONValue::JSONArray(ref items) => {
+                return items
+                    .iter()
+                    .map(|item| {
+                        item.pointer(pointer)
+                            .cloned()
+                            .unwrap_or(JSONValue::JSONNull())
+                    })
+                    .collect()
+            }
+            _ => return vec![],
+        }
+    }
+}
+
+/// Splits a pointer into unescaped reference tokens.
+/// Returns `None` if the pointer is neither empty nor starts with `/`.
+pub fn parse_pointer(pointer: &str) -> Option<Vec<String>> {
+    if pointer.is_empty() {
+        return Some(vec![]);
+    }
+    if !pointer.starts_with(SEPARATOR) {
+        return None;
+    }
+    return Some(pointer[1..].split(SEPARATOR).map(unescape_token).collect());
+}
+
+/// Escapes `~` and `/` so the key can be used as a single pointer token.
+pub fn escape_token(token: &str) -> String {
+    return token.replace('~', "~0").replace('/', "~1");
+}
+
+fn unescape_token(token: &str) -> String {
+    return token.replace("~1", "/").replace("~0", "~");
+}
+
+/// Array indices are plain decimal numbers without leading zeros.
+/// `-` (the element after the last one) never resolves to a value.
+pub fn parse_index(token: &str) -> Option<usize> {
+    if token.is_empty() || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    if !token.chars().all(|ch| ch.is_ascii_digit()) {
+        return None;
+    }
+    return token.parse().ok();
+}
@@ -0,0 +1,110 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn doc() -> JSONValue {
+    return r#"{"a": {"b": [10, 20, {"c/d": true, "e~f": null}]}, "": 1}"#
+        .parse()
+        .unwrap();
+}
+
+#[test]
+fn test_valid_pointers() {
+    let doc = doc();
+    for s in vec![
+        ("/a/b/0", JSONValue::JSONNumber(10.0)),
+        ("/a/b/1", JSONValue::JSONNumber(20.0)),
+        ("/a/b/2/c~1d", JSONValue::JSONBool(true)),
+        ("/a/b/2/e~0f", JSONValue::JSONNull()),
+        ("/", JSONValue::JSONNumber(1.0)),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(doc.pointer(s.0), Some(&s.1));
+    }
+    assert_eq!(doc.pointer(""), Some(&doc));
+}
+
+#[test]
+fn test_invalid_pointers() {
+    let doc = doc();
+    for s in vec![
+        "a", "/b", "/a/b/3", "/a/b/01", "/a/b/-", "/a/b/0/x", "/a/b/x",
+    ] {
+        assert_eq!(doc.pointer(s), None, "Pointer {} should not resolve", s);
+    }
+}
+
+#[test]
+fn test_pointer_mut() {
+    let mut doc = doc();
+    *doc.pointer_mut("/a/b/0").unwrap() = JSONValue::JSONString("x".to_owned());
+    assert_eq!(
+        doc.pointer("/a/b/0"),
+        Some(&JSONValue::JSONString("x".to_owned()))
+    );
+}
+
+#[test]
+fn test_pluck() {
+    let doc: JSONValue = r#"[{"user": {"id": 1}}, {"user": {}}, {"user": {"id": "x"}}, 5]"#
+        .parse()
+        .unwrap();
+    assert_eq!(
+        doc.pluck("/user/id"),
+        vec![
+            JSONValue::JSONNumber(1.0),
+            JSONValue::JSONNull(),
+            JSONValue::JSONString("x".to_owned()),
+            JSONValue::JSONNull(),
+        ]
+    );
+    assert_eq!(doc.pluck("").len(), 4);
+    assert_eq!(doc.pluck("/3")[3], JSONValue::JSONNull());
+    assert_eq!(JSONValue::JSONNumber(1.0).pluck("/a"), vec![]);
+}
+
+#[test]
+fn test_escape_token() {
+    for s in vec![("a/b", "a~1b"), ("a~b", "a~0b"), ("~/", "~0~1")] {
+        assert_eq!(escape_token(s.0), s.1);
+        assert_eq!(unescape_token(s.1), s.0);
+    }
+}
+
+#[test]
+fn test_get_many() {
+    let doc = doc();
+    let found = doc.get_many(&["", "missing", "a", ""]);
+    assert_eq!(found[0], Some(&JSONValue::JSONNumber(1.0)));
+    assert_eq!(found[1], None);
+    assert_eq!(found[2], doc.pointer("/a"));
+    assert_eq!(found[3], found[0]);
+    assert!(doc.get_many(&[]).is_empty());
+    assert_eq!(
+        JSONValue::JSONNull().get_many(&["a", "b"]),
+        vec![None, None]
+    );
+}
+
+#[test]
+fn test_pointer_many() {
+    let doc = doc();
+    let pointers = vec![
+        "/a/b/2/c~1d",
+        "/a/b/0",
+        "",
+        "bad",
+        "/a/x/y",
+        "/a/b/2/e~0f",
+        "/a/b/00",
+        "/a/b/0",
+        "/a/x",
+        "/",
+    ];
+    let found = doc.pointer_many(&pointers);
+    for (pointer, value) in pointers.iter().zip(found) {
+        println!("Checking {}", pointer);
+        assert_eq!(value, doc.pointer(pointer));
+    }
+    assert!(doc.pointer_many(&[]).is_empty());
+}
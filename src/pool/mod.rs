@@ -0,0 +1,228 @@
+use super::*;
+use serializer::{write_value, SerializerConfig};
+use std::mem;
+
+#[cfg(test)]
+mod tests;
+
+// Allocations of each kind a pool keeps by default
+const DEFAULT_LIMIT: usize = 4096;
+
+/// Where the parser gets the strings, arrays, maps and boxes of the values it
+/// builds, for routing them to a pool or arena without a nightly allocator
+/// API; see `JSONValue::parse_in`. Every method defaults to a fresh
+/// allocation, so implementations override only what they manage. The
+/// contents still grow through the global allocator.
+pub trait Alloc {
+    /// An empty string
+    fn take_string(&mut self) -> String {
+        return String::new();
+    }
+
+    /// An empty array
+    fn take_array(&mut self) -> Vec<Box<JSONValue>> {
+        return vec![];
+    }
+
+    /// An empty map
+    fn take_map(&mut self) -> JSONMap {
+        return JSONMap::new();
+    }
+
+    /// `value` in a box
+    fn boxed(&mut self, value: JSONValue) -> Box<JSONValue> {
+        return Box::new(value);
+    }
+}
+
+/// Cleared strings, arrays, maps and boxes for reuse, so hot loops stop
+/// freeing and reallocating them for every request:
+///
+/// ```text
+/// let value = pool.parse(&body)?;
+/// let response = pool.to_string_with(&handle(&value), &config);
+/// value.clear_and_recycle(&mut pool);
+/// ```
+///
+/// Values built from the pool are ordinary values; the ones never recycled
+/// are simply freed. Each kind keeps up to a limit of allocations, capacity
+/// included, so one huge request can pin that much memory.
+#[derive(Debug)]
+pub struct ValuePool {
+    strings: Vec<String>,
+    arrays: Vec<Vec<Box<JSONValue>>>,
+    maps: Vec<JSONMap>,
+    boxes: Vec<Box<JSONValue>>,
+    limit: usize,
+    // values waiting to be taken apart by `recycle`
+    pending: Vec<JSONValue>,
+}
+
+impl Default for ValuePool {
+    fn default() -> ValuePool {
+        return ValuePool::with_limit(DEFAULT_LIMIT);
+    }
+}
+
+impl ValuePool {
+    pub fn new() -> ValuePool {
+        return ValuePool::default();
+    }
+
+    /// Pool keeping at most `limit` allocations of each kind
+    pub fn with_limit(limit: usize) -> ValuePool {
+        return ValuePool {
+            strings: vec![],
+            arrays: vec![],
+            maps: vec![],
+            boxes: vec![],
+            limit,
+            pending: vec![],
+        };
+    }
+
+    /// Allocations the pool holds, of all kinds
+    pub fn len(&self) -> usize {
+        return self.strings.len() + self.arrays.len() + self.maps.len() + self.boxes.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.len() == 0;
+    }
+
+    /// Parses `input` into allocations taken from the pool
+    pub fn parse(&mut self, input: &str) -> Result<JSONValue, JSONParseError> {
+        return self.parse_with(input, &ParserOptions::default());
+    }
+
+    pub fn parse_with(
+        &mut self,
+        input: &str,
+        options: &ParserOptions,
+    ) -> Result<JSONValue, JSONParseError> {
+        return JSONValue::parse_in(input, options, self);
+    }
+
+    /// Serializes `value` into a string taken from the pool; give it back with `recycle_string`
+    pub fn to_string_with(&mut self, value: &JSONValue, config: &SerializerConfig) -> String {
+        let mut result = self.take_string();
+        write_value(&mut result, value, config, 0).unwrap();
+        return result;
+    }
+
+    /// Takes `value` apart, keeping its allocations
+    pub fn recycle(&mut self, value: JSONValue) {
+        let mut pending = mem::take(&mut self.pending);
+        pending.push(value);
+        while let Some(value) = pending.pop() {
+            match value {
+                JSONValue::JSONString(s) | JSONValue::JSONRaw(s) => self.recycle_string(s),
+                JSONValue::JSONArray(mut items) => {
+                    for item in items.drain(..) {
+                        pending.push(self.unbox(item));
+                    }
+                    if self.arrays.len() < self.limit && items.capacity() > 0 {
+                        self.arrays.push(items);
+                    }
+                }
+                JSONValue::JSONObject(mut members) => {
+                    for (key, member) in drain(&mut members) {
+                        self.recycle_string(key);
+                        pending.push(self.unbox(member));
+                    }
+                    if self.maps.len() < self.limit {
+                        self.maps.push(members);
+                    }
+                }
+                _ => (),
+            }
+        }
+        self.pending = pending;
+    }
+
+    pub fn recycle_string(&mut self, mut s: String) {
+        if self.strings.len() < self.limit && s.capacity() > 0 {
+            s.clear();
+            self.strings.push(s);
+        }
+    }
+
+    /// Empty string, reusing a recycled one if there is any
+    pub fn take_string(&mut self) -> String {
+        return self.strings.pop().unwrap_or_default();
+    }
+
+    pub fn take_array(&mut self) -> Vec<Box<JSONValue>> {
+        return self.arrays.pop().unwrap_or_default();
+    }
+
+    pub fn take_map(&mut self) -> JSONMap {
+        return self.maps.pop().unwrap_or_default();
+    }
+
+    /// Boxes `value`, reusing a recycled box if there is any
+    pub fn boxed(&mut self, value: JSONValue) -> Box<JSONValue> {
+        match self.boxes.pop() {
+            Some(mut boxed) => {
+                *boxed = value;
+                return boxed;
+            }
+            None => return Box::new(value),
+        }
+    }
+
+    // Moves the value out, keeping the box
+    fn unbox(&mut self, mut boxed: Box<JSONValue>) -> JSONValue {
+        let value = mem::replace(&mut *boxed, JSONValue::JSONNull());
+        if self.boxes.len() < self.limit {
+            self.boxes.push(boxed);
+        }
+        return value;
+    }
+}
+
+/// Reuses recycled allocations, falling back to fresh ones
+impl Alloc for ValuePool {
+    fn take_string(&mut self) -> String {
+        return ValuePool::take_string(self);
+    }
+
+    fn take_array(&mut self) -> Vec<Box<JSONValue>> {
+        return ValuePool::take_array(self);
+    }
+
+    fn take_map(&mut self) -> JSONMap {
+        return ValuePool::take_map(self);
+    }
+
+    fn boxed(&mut self, value: JSONValue) -> Box<JSONValue> {
+        return ValuePool::boxed(self, value);
+    }
+}
+
+impl JSONValue {
+    /// Parses with the containers of the value taken from `alloc`
+    pub fn parse_in<A: Alloc>(
+        input: &str,
+        options: &ParserOptions,
+        alloc: &mut A,
+    ) -> Result<JSONValue, JSONParseError> {
+        return parser::parse_alloc(input, options, alloc);
+    }
+
+    /// Gives the value's strings, containers and boxes to `pool` for reuse
+    pub fn clear_and_recycle(self, pool: &mut ValuePool) {
+        pool.recycle(self);
+    }
+}
+
+// Empties the map; a `BTreeMap` has no allocation to keep
+#[cfg(not(all(feature = "btree_map", not(feature = "preserve_order"))))]
+fn drain(map: &mut JSONMap) -> impl Iterator<Item = (String, Box<JSONValue>)> + '_ {
+    return map.drain();
+}
+
+#[cfg(all(feature = "btree_map", not(feature = "preserve_order")))]
+fn drain(map: &mut JSONMap) -> impl Iterator<Item = (String, Box<JSONValue>)> + '_ {
+    return mem::take(map).into_iter();
+}
@@ -0,0 +1,102 @@
+#![allow(clippy::useless_vec)]
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_pool() {
+    let mut pool = ValuePool::new();
+    assert!(pool.is_empty());
+    for s in vec![
+        "{\"a\": [1, \"two\", {\"three\": null}], \"b\": \"text\"}",
+        "[[], {}, \"\", [\"x\", \"y\"]]",
+        "\"plain\"",
+        "{\"a\": {\"b\": {\"c\": [1, 2, 3]}}}",
+    ] {
+        println!("Checking {}", s);
+        let value = pool.parse(s).unwrap();
+        assert_eq!(value, parse(s));
+        let config = SerializerConfig::compact();
+        let text = pool.to_string_with(&value, &config);
+        assert_eq!(text, value.to_string_with(&config));
+        pool.recycle_string(text);
+        value.clear_and_recycle(&mut pool);
+        assert!(!pool.is_empty());
+    }
+    assert!(pool.parse("[1, ").is_err());
+    assert_eq!(pool.parse("[1]").unwrap(), parse("[1]"));
+}
+
+#[test]
+fn test_pool_reuse() {
+    let mut pool = ValuePool::new();
+    parse("{\"key\": [\"value\"]}").clear_and_recycle(&mut pool);
+    // two strings, an array, a map and two boxes
+    assert_eq!(pool.len(), 6);
+    let value = pool.parse("{\"other\": [\"item\"]}").unwrap();
+    assert_eq!(pool.len(), 0);
+    value.clear_and_recycle(&mut pool);
+    assert_eq!(pool.len(), 6);
+    let s = pool.take_string();
+    assert!(s.is_empty() && s.capacity() > 0);
+
+    let mut pool = ValuePool::with_limit(1);
+    parse("[\"a\", \"b\", [1], [2]]").clear_and_recycle(&mut pool);
+    assert_eq!(pool.len(), 3);
+    // nothing that didn't allocate is kept
+    let mut pool = ValuePool::new();
+    parse("[]").clear_and_recycle(&mut pool);
+    pool.recycle_string(String::new());
+    assert!(pool.is_empty());
+}
+
+// Counts what the parser takes, handing out fresh allocations
+#[derive(Default)]
+struct Counting {
+    strings: usize,
+    arrays: usize,
+    maps: usize,
+    boxes: usize,
+}
+
+impl Alloc for Counting {
+    fn take_string(&mut self) -> String {
+        self.strings += 1;
+        return String::new();
+    }
+
+    fn take_array(&mut self) -> Vec<Box<JSONValue>> {
+        self.arrays += 1;
+        return vec![];
+    }
+
+    fn take_map(&mut self) -> JSONMap {
+        self.maps += 1;
+        return JSONMap::new();
+    }
+
+    fn boxed(&mut self, value: JSONValue) -> Box<JSONValue> {
+        self.boxes += 1;
+        return Box::new(value);
+    }
+}
+
+#[test]
+fn test_parse_in() {
+    let s = "{\"a\": [1, \"two\"], \"b\": {}}";
+    let mut alloc = Counting::default();
+    let value = JSONValue::parse_in(s, &ParserOptions::default(), &mut alloc).unwrap();
+    assert_eq!(value, parse(s));
+    assert_eq!(
+        (alloc.strings, alloc.arrays, alloc.maps, alloc.boxes),
+        (3, 1, 2, 4)
+    );
+
+    // the defaults allocate afresh
+    struct Fresh;
+    impl Alloc for Fresh {}
+    let value = JSONValue::parse_in(s, &ParserOptions::default(), &mut Fresh).unwrap();
+    assert_eq!(value, parse(s));
+}
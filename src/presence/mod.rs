@@ -0,0 +1,189 @@
+use super::*;
+use pointer::parse_pointer;
+
+#[cfg(test)]
+mod tests;
+
+/// Result of looking up an object member when a missing key and an explicit
+/// `null` mean different things, as in PATCH-style updates: absent means
+/// "leave unchanged", null means "clear".
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Presence<T> {
+    Missing,
+    Null,
+    Value(T),
+}
+
+impl<T> Presence<T> {
+    pub fn is_missing(&self) -> bool {
+        return matches!(*self, Presence::Missing);
+    }
+
+    pub fn is_null(&self) -> bool {
+        return matches!(*self, Presence::Null);
+    }
+
+    /// The value, `None` for both missing and null.
+    pub fn value(self) -> Option<T> {
+        match self {
+            Presence::Value(value) => return Some(value),
+            _ => return None,
+        }
+    }
+
+    pub fn as_ref(&self) -> Presence<&T> {
+        match *self {
+            Presence::Missing => return Presence::Missing,
+            Presence::Null => return Presence::Null,
+            Presence::Value(ref value) => return Presence::Value(value),
+        }
+    }
+
+    pub fn map<U, F: FnOnce(T) -> U>(self, f: F) -> Presence<U> {
+        match self {
+            Presence::Missing => return Presence::Missing,
+            Presence::Null => return Presence::Null,
+            Presence::Value(value) => return Presence::Value(f(value)),
+        }
+    }
+
+    /// Converts the value, keeping missing and null as they are.
+    pub fn and_then<U, E, F>(self, f: F) -> Result<Presence<U>, E>
+    where
+        F: FnOnce(T) -> Result<U, E>,
+    {
+        match self {
+            Presence::Missing => return Ok(Presence::Missing),
+            Presence::Null => return Ok(Presence::Null),
+            Presence::Value(value) => return Ok(Presence::Value(f(value)?)),
+        }
+    }
+
+    /// `None` when missing, `Some(None)` when null.
+    pub fn into_option(self) -> Option<Option<T>> {
+        match self {
+            Presence::Missing => return None,
+            Presence::Null => return Some(None),
+            Presence::Value(value) => return Some(Some(value)),
+        }
+    }
+}
+
+impl<T> Default for Presence<T> {
+    fn default() -> Presence<T> {
+        return Presence::Missing;
+    }
+}
+
+impl<T> From<Option<Option<T>>> for Presence<T> {
+    fn from(option: Option<Option<T>>) -> Presence<T> {
+        match option {
+            None => return Presence::Missing,
+            Some(None) => return Presence::Null,
+            Some(Some(value)) => return Presence::Value(value),
+        }
+    }
+}
+
+impl Presence<JSONValue> {
+    /// Applies the update to `map[key]`: missing keeps the current value,
+    /// null removes the key and a value replaces it.
+    pub fn apply_to(self, map: &mut JSONMap, key: &str) {
+        match self {
+            Presence::Missing => (),
+            Presence::Null => {
+                map.remove(key);
+            }
+            Presence::Value(value) => {
+                map.insert(key.to_owned(), Box::new(value));
+            }
+        }
+    }
+}
+
+/// Which of a struct's fields were present in the input, one bit per field
+/// in declaration order. Lets a `FromJSONValue` impl of a PATCH body tell a
+/// field that was sent from one that got its default; keep it next to the
+/// fields and expose it as e.g. `fn user_patch_presence(&self)`. A `null`
+/// field was sent, so it counts as present.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FieldPresence {
+    bits: u64,
+}
+
+impl FieldPresence {
+    /// Fields a bitmap can track
+    pub const MAX_FIELDS: usize = 64;
+
+    /// Presence of `fields` in `value`; panics with more than `MAX_FIELDS`
+    pub fn of(value: &JSONValue, fields: &[&str]) -> FieldPresence {
+        assert!(fields.len() <= FieldPresence::MAX_FIELDS, "Too many fields");
+        let mut presence = FieldPresence::default();
+        for (i, field) in fields.iter().enumerate() {
+            if !value.presence(field).is_missing() {
+                presence.set(i);
+            }
+        }
+        return presence;
+    }
+
+    pub fn is_present(&self, field: usize) -> bool {
+        return field < FieldPresence::MAX_FIELDS && self.bits & (1 << field) != 0;
+    }
+
+    pub fn set(&mut self, field: usize) {
+        self.bits |= 1 << field;
+    }
+
+    /// Number of present fields
+    pub fn count(&self) -> usize {
+        return self.bits.count_ones() as usize;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.bits == 0;
+    }
+
+    /// Names of the present fields, given the names `of` was called with
+    pub fn present<'a>(&self, fields: &[&'a str]) -> Vec<&'a str> {
+        return fields
+            .iter()
+            .enumerate()
+            .filter(|&(i, _)| self.is_present(i))
+            .map(|(_, &field)| field)
+            .collect();
+    }
+
+    /// The bitmap, bit `i` for field `i`
+    pub fn bits(&self) -> u64 {
+        return self.bits;
+    }
+}
+
+impl JSONValue {
+    /// Looks up an object member, telling a missing key from a `null` one.
+    /// Non-objects have no members, so everything is missing in them.
+    pub fn presence(&self, key: &str) -> Presence<&JSONValue> {
+        match *self {
+            JSONValue::JSONObject(ref map) => match map.get(key).map(|v| &**v) {
+                None => return Presence::Missing,
+                Some(&JSONValue::JSONNull()) => return Presence::Null,
+                Some(value) => return Presence::Value(value),
+            },
+            _ => return Presence::Missing,
+        }
+    }
+
+    /// Like `presence` for a JSON Pointer. Invalid pointers and
+    /// paths through missing or null parents are missing.
+    pub fn presence_at(&self, pointer: &str) -> Presence<&JSONValue> {
+        if parse_pointer(pointer).is_none() {
+            return Presence::Missing;
+        }
+        match self.pointer(pointer) {
+            None => return Presence::Missing,
+            Some(&JSONValue::JSONNull()) => return Presence::Null,
+            Some(value) => return Presence::Value(value),
+        }
+    }
+}
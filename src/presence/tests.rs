@@ -0,0 +1,127 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+#[test]
+fn test_presence() {
+    let value: JSONValue = r#"{"name": "a", "email": null, "tags": [null, 1]}"#
+        .parse()
+        .unwrap();
+    let name = JSONValue::JSONString("a".to_owned());
+    for s in vec![
+        ("name", Presence::Value(&name)),
+        ("email", Presence::Null),
+        ("phone", Presence::Missing),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(value.presence(s.0), s.1);
+    }
+    assert!(JSONValue::JSONNull().presence("a").is_missing());
+
+    for s in vec![
+        ("/tags/0", Presence::Null),
+        ("/tags/1", Presence::Value(JSONValue::JSONNumber(1.0))),
+        ("/tags/2", Presence::Missing),
+        ("/email/x", Presence::Missing),
+        ("tags", Presence::Missing),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(value.presence_at(s.0).map(|v| v.clone()), s.1);
+    }
+}
+
+#[test]
+fn test_conversions() {
+    let present: Presence<i32> = Some(Some(1)).into();
+    assert_eq!(present, Presence::Value(1));
+    assert_eq!(Presence::<i32>::from(Some(None)), Presence::Null);
+    assert_eq!(Presence::<i32>::from(None), Presence::Missing);
+    assert_eq!(present.into_option(), Some(Some(1)));
+    assert_eq!(Presence::<i32>::Null.into_option(), Some(None));
+    assert_eq!(Presence::<i32>::default().into_option(), None);
+    assert_eq!(present.map(|n| n * 2).value(), Some(2));
+    assert_eq!(Presence::<i32>::Null.value(), None);
+    assert!(Presence::<i32>::Null.as_ref().is_null());
+    assert!(Presence::Value("x").and_then(|s| s.parse::<i32>()).is_err());
+    assert_eq!(
+        Presence::<&str>::Null.and_then(|s| s.parse::<i32>()),
+        Ok(Presence::Null)
+    );
+}
+
+#[test]
+fn test_apply_to() {
+    let mut map = JSONMap::new();
+    map.insert("keep".to_owned(), Box::new(JSONValue::JSONBool(true)));
+    map.insert("clear".to_owned(), Box::new(JSONValue::JSONBool(true)));
+    Presence::Missing.apply_to(&mut map, "keep");
+    Presence::Null.apply_to(&mut map, "clear");
+    Presence::Value(JSONValue::JSONNumber(1.0)).apply_to(&mut map, "new");
+    assert_eq!(
+        JSONValue::JSONObject(map),
+        r#"{"keep": true, "new": 1}"#.parse().unwrap()
+    );
+}
+
+const USER_PATCH_FIELDS: &[&str] = &["name", "email", "age"];
+
+#[derive(Debug, Default)]
+struct UserPatch {
+    name: String,
+    email: Option<String>,
+    age: u8,
+    presence: FieldPresence,
+}
+
+impl UserPatch {
+    fn user_patch_presence(&self) -> FieldPresence {
+        return self.presence;
+    }
+}
+
+impl convert::FromJSONValue for UserPatch {
+    fn from_json(value: &JSONValue) -> Result<UserPatch, convert::JSONConvertError> {
+        let mut patch = UserPatch {
+            presence: FieldPresence::of(value, USER_PATCH_FIELDS),
+            ..UserPatch::default()
+        };
+        if let Presence::Value(name) = value.presence("name") {
+            patch.name = convert::convert_at(name, "name")?;
+        }
+        if let Some(email) = value.pointer("/email") {
+            patch.email = convert::convert_at(email, "email")?;
+        }
+        if let Presence::Value(age) = value.presence("age") {
+            patch.age = convert::convert_at(age, "age")?;
+        }
+        return Ok(patch);
+    }
+}
+
+#[test]
+fn test_field_presence() {
+    for s in vec![
+        (r#"{"name": "a", "age": 0}"#, vec!["name", "age"], 0b101),
+        (r#"{"email": null}"#, vec!["email"], 0b010),
+        (r#"{"other": 1}"#, vec![], 0),
+        ("[1]", vec![], 0),
+    ] {
+        println!("Checking {}", s.0);
+        let patch: UserPatch = s.0.parse::<JSONValue>().unwrap().convert().unwrap();
+        let presence = patch.user_patch_presence();
+        assert_eq!(presence.present(USER_PATCH_FIELDS), s.1);
+        assert_eq!(presence.bits(), s.2);
+        assert_eq!(presence.count(), s.1.len());
+        assert_eq!(presence.is_empty(), s.1.is_empty());
+    }
+    // the age was sent as 0, the email was cleared
+    let patch: UserPatch =
+        r#"{"age": 0, "email": null}"#.parse::<JSONValue>().unwrap().convert().unwrap();
+    assert_eq!(patch.age, 0);
+    assert_eq!(patch.email, None);
+    assert_eq!(patch.name, "");
+    assert!(patch.presence.is_present(2));
+    assert!(patch.presence.is_present(1));
+    assert!(!patch.presence.is_present(0));
+    assert!(!patch.presence.is_present(64));
+}
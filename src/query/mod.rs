@@ -0,0 +1,643 @@
+use super::*;
+use std::cmp::Ordering;
+use std::iter::Peekable;
+use std::str::CharIndices;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone)]
+pub struct JSONQueryError {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Dot,
+    Field(String),
+    Ident(String),
+    Literal(JSONValue),
+    Op(&'static str),
+    BracketStart,
+    BracketEnd,
+    ParenStart,
+    ParenEnd,
+    Pipe,
+    Comma,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Identity,
+    Field(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Iterate(Box<Expr>),
+    Literal(JSONValue),
+    Pipe(Box<Expr>, Box<Expr>),
+    Comma(Box<Expr>, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+}
+
+// Longest operators first, so `<=` isn't read as `<`
+const OPERATORS: [&str; 10] = ["==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/"];
+
+/// Compiled jq-style expression. Supported subset:
+/// `.`, `.a.b`, `."key"`, `.[0]`, `.["key"]`, `.[]`, literals, `|`, `,`, parentheses,
+/// comparisons, `+ - * /`, `and`, `or`, and the functions
+/// `select(f)`, `not`, `length`, `keys`, `has(key)`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    expr: Expr,
+}
+
+impl Query {
+    pub fn compile(expression: &str) -> Result<Query, JSONQueryError> {
+        let tokens = tokenize(expression)?;
+        let mut parser = QueryParser { tokens, pos: 0 };
+        let expr = parser.pipe()?;
+        if parser.pos < parser.tokens.len() {
+            return Err(make_err(format!(
+                "Unexpected {:?} in query",
+                parser.tokens[parser.pos]
+            )));
+        }
+        return Ok(Query { expr });
+    }
+
+    /// Runs the query, returning every output it produces (possibly none, e.g. for `select`).
+    pub fn run(&self, input: &JSONValue) -> Result<Vec<JSONValue>, JSONQueryError> {
+        return eval(&self.expr, &input.expand_raw());
+    }
+}
+
+impl FromStr for Query {
+    type Err = JSONQueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        return Query::compile(s);
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, JSONQueryError> {
+    let mut tokens = vec![];
+    let mut chars = expression.char_indices().peekable();
+    while let Some(&(i, ch)) = chars.peek() {
+        match ch {
+            ' ' | '\t' | '\n' | '\r' => {
+                chars.next();
+            }
+            '.' => {
+                chars.next();
+                match chars.peek() {
+                    Some(&(_, ch)) if is_ident_start(ch) => {
+                        tokens.push(Token::Field(read_ident(&mut chars)))
+                    }
+                    Some(&(j, '"')) => {
+                        tokens.push(Token::Field(read_string(expression, &mut chars, j)?))
+                    }
+                    _ => tokens.push(Token::Dot),
+                }
+            }
+            '"' => {
+                let s = read_string(expression, &mut chars, i)?;
+                tokens.push(Token::Literal(JSONValue::JSONString(s)));
+            }
+            '[' | ']' | '(' | ')' | '|' | ',' => {
+                chars.next();
+                tokens.push(match ch {
+                    '[' => Token::BracketStart,
+                    ']' => Token::BracketEnd,
+                    '(' => Token::ParenStart,
+                    ')' => Token::ParenEnd,
+                    '|' => Token::Pipe,
+                    _ => Token::Comma,
+                });
+            }
+            _ if ch.is_ascii_digit() => {
+                let mut end = i;
+                while let Some(&(j, ch)) = chars.peek() {
+                    if !(ch.is_ascii_digit() || ch == '.' || ch == 'e' || ch == 'E') {
+                        break;
+                    }
+                    end = j + ch.len_utf8();
+                    chars.next();
+                }
+                let literal = &expression[i..end];
+                match literal.parse() {
+                    Ok(number) => tokens.push(Token::Literal(number)),
+                    Err(_) => return Err(make_err(format!("Invalid number {}", literal))),
+                }
+            }
+            _ if is_ident_start(ch) => {
+                let ident = read_ident(&mut chars);
+                tokens.push(match ident.as_str() {
+                    "true" => Token::Literal(JSONValue::JSONBool(true)),
+                    "false" => Token::Literal(JSONValue::JSONBool(false)),
+                    "null" => Token::Literal(JSONValue::JSONNull()),
+                    _ => Token::Ident(ident),
+                });
+            }
+            _ => {
+                let op = OPERATORS
+                    .iter()
+                    .find(|op| expression[i..].starts_with(*op))
+                    .ok_or_else(|| make_err(format!("Unexpected charachter {} in query", ch)))?;
+                for _ in 0..op.len() {
+                    chars.next();
+                }
+                tokens.push(Token::Op(op));
+            }
+        }
+    }
+    return Ok(tokens);
+}
+
+fn is_ident_start(ch: char) -> bool {
+    return ch.is_alphabetic() || ch == '_';
+}
+
+fn read_ident(chars: &mut Peekable<CharIndices>) -> String {
+    let mut ident = String::new();
+    while let Some(&(_, ch)) = chars.peek() {
+        if !(ch.is_alphanumeric() || ch == '_') {
+            break;
+        }
+        ident.push(ch);
+        chars.next();
+    }
+    return ident;
+}
+
+// Reads a JSON string literal starting at `start`
+fn read_string(
+    expression: &str,
+    chars: &mut Peekable<CharIndices>,
+    start: usize,
+) -> Result<String, JSONQueryError> {
+    chars.next();
+    let mut escaped = false;
+    for (i, ch) in chars.by_ref() {
+        if ch == '"' && !escaped {
+            match expression[start..=i].parse() {
+                Ok(JSONValue::JSONString(s)) => return Ok(s),
+                _ => break,
+            }
+        }
+        escaped = ch == '\\' && !escaped;
+    }
+    return Err(make_err(format!("Invalid string at {}", start)));
+}
+
+struct QueryParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl QueryParser {
+    fn peek(&self) -> Option<&Token> {
+        return self.tokens.get(self.pos);
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        return token;
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), JSONQueryError> {
+        match self.next() {
+            Some(ref token) if *token == expected => return Ok(()),
+            token => {
+                return Err(make_err(format!(
+                    "Expected {:?}, got {:?}",
+                    expected, token
+                )))
+            }
+        }
+    }
+
+    fn is_ident(&self, name: &str) -> bool {
+        return self.peek() == Some(&Token::Ident(name.to_owned()));
+    }
+
+    fn pipe(&mut self) -> Result<Expr, JSONQueryError> {
+        let mut left = self.comma()?;
+        while self.peek() == Some(&Token::Pipe) {
+            self.next();
+            left = Expr::Pipe(Box::new(left), Box::new(self.comma()?));
+        }
+        return Ok(left);
+    }
+
+    fn comma(&mut self) -> Result<Expr, JSONQueryError> {
+        let mut left = self.or()?;
+        while self.peek() == Some(&Token::Comma) {
+            self.next();
+            left = Expr::Comma(Box::new(left), Box::new(self.or()?));
+        }
+        return Ok(left);
+    }
+
+    fn or(&mut self) -> Result<Expr, JSONQueryError> {
+        let mut left = self.and()?;
+        while self.is_ident("or") {
+            self.next();
+            left = Expr::Binary("or", Box::new(left), Box::new(self.and()?));
+        }
+        return Ok(left);
+    }
+
+    fn and(&mut self) -> Result<Expr, JSONQueryError> {
+        let mut left = self.comparison()?;
+        while self.is_ident("and") {
+            self.next();
+            left = Expr::Binary("and", Box::new(left), Box::new(self.comparison()?));
+        }
+        return Ok(left);
+    }
+
+    fn comparison(&mut self) -> Result<Expr, JSONQueryError> {
+        let left = self.additive()?;
+        if let Some(&Token::Op(op)) = self.peek() {
+            if ["==", "!=", "<", "<=", ">", ">="].contains(&op) {
+                self.next();
+                return Ok(Expr::Binary(op, Box::new(left), Box::new(self.additive()?)));
+            }
+        }
+        return Ok(left);
+    }
+
+    fn additive(&mut self) -> Result<Expr, JSONQueryError> {
+        let mut left = self.multiplicative()?;
+        while let Some(&Token::Op(op)) = self.peek() {
+            if op != "+" && op != "-" {
+                break;
+            }
+            self.next();
+            left = Expr::Binary(op, Box::new(left), Box::new(self.multiplicative()?));
+        }
+        return Ok(left);
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, JSONQueryError> {
+        let mut left = self.postfix()?;
+        while let Some(&Token::Op(op)) = self.peek() {
+            if op != "*" && op != "/" {
+                break;
+            }
+            self.next();
+            left = Expr::Binary(op, Box::new(left), Box::new(self.postfix()?));
+        }
+        return Ok(left);
+    }
+
+    fn postfix(&mut self) -> Result<Expr, JSONQueryError> {
+        let mut expr = self.primary()?;
+        loop {
+            match self.peek() {
+                Some(&Token::Field(_)) => {
+                    if let Some(Token::Field(name)) = self.next() {
+                        expr = Expr::Field(Box::new(expr), name);
+                    }
+                }
+                Some(&Token::Dot)
+                    if self.tokens.get(self.pos + 1) == Some(&Token::BracketStart) =>
+                {
+                    self.next();
+                }
+                Some(&Token::BracketStart) => {
+                    self.next();
+                    if self.peek() == Some(&Token::BracketEnd) {
+                        self.next();
+                        expr = Expr::Iterate(Box::new(expr));
+                    } else {
+                        let index = self.pipe()?;
+                        self.expect(Token::BracketEnd)?;
+                        expr = Expr::Index(Box::new(expr), Box::new(index));
+                    }
+                }
+                _ => return Ok(expr),
+            }
+        }
+    }
+
+    fn primary(&mut self) -> Result<Expr, JSONQueryError> {
+        match self.next() {
+            Some(Token::Dot) => return Ok(Expr::Identity),
+            Some(Token::Field(name)) => return Ok(Expr::Field(Box::new(Expr::Identity), name)),
+            Some(Token::Literal(value)) => return Ok(Expr::Literal(value)),
+            Some(Token::Op("-")) => {
+                let operand = self.postfix()?;
+                let zero = Expr::Literal(JSONValue::JSONNumber(0.0));
+                return Ok(Expr::Binary("-", Box::new(zero), Box::new(operand)));
+            }
+            Some(Token::ParenStart) => {
+                let expr = self.pipe()?;
+                self.expect(Token::ParenEnd)?;
+                return Ok(expr);
+            }
+            Some(Token::Ident(name)) => {
+                let mut args = vec![];
+                if self.peek() == Some(&Token::ParenStart) {
+                    self.next();
+                    args.push(self.pipe()?);
+                    self.expect(Token::ParenEnd)?;
+                }
+                return Ok(Expr::Call(name, args));
+            }
+            None => return Err(make_err("Unexpected end of query".to_owned())),
+            Some(token) => return Err(make_err(format!("Unexpected {:?} in query", token))),
+        }
+    }
+}
+
+fn eval(expr: &Expr, input: &JSONValue) -> Result<Vec<JSONValue>, JSONQueryError> {
+    match *expr {
+        Expr::Identity => return Ok(vec![input.clone()]),
+        Expr::Literal(ref value) => return Ok(vec![value.clone()]),
+        Expr::Field(ref target, ref name) => {
+            let key = JSONValue::JSONString(name.clone());
+            return flat_map(eval(target, input)?, |value| Ok(vec![index(&value, &key)?]));
+        }
+        Expr::Index(ref target, ref index_expr) => {
+            let targets = eval(target, input)?;
+            let keys = eval(index_expr, input)?;
+            let mut result = vec![];
+            for value in &targets {
+                for key in &keys {
+                    result.push(index(value, key)?);
+                }
+            }
+            return Ok(result);
+        }
+        Expr::Iterate(ref target) => {
+            return flat_map(eval(target, input)?, |value| match value {
+                JSONValue::JSONArray(items) => Ok(items.into_iter().map(|i| *i).collect()),
+                JSONValue::JSONObject(map) => {
+                    // sorted by key, so the output doesn't depend on hash order
+                    let mut entries: Vec<(String, Box<JSONValue>)> = map.into_iter().collect();
+                    entries.sort_by(|a, b| a.0.cmp(&b.0));
+                    Ok(entries.into_iter().map(|(_, v)| *v).collect())
+                }
+                other => Err(make_err(format!(
+                    "Cannot iterate over {}",
+                    type_name(&other)
+                ))),
+            });
+        }
+        Expr::Pipe(ref left, ref right) => {
+            return flat_map(eval(left, input)?, |value| eval(right, &value));
+        }
+        Expr::Comma(ref left, ref right) => {
+            let mut result = eval(left, input)?;
+            result.extend(eval(right, input)?);
+            return Ok(result);
+        }
+        Expr::Binary(op, ref left, ref right) => {
+            let mut result = vec![];
+            for a in eval(left, input)? {
+                if op == "and" && !is_truthy(&a) || op == "or" && is_truthy(&a) {
+                    result.push(JSONValue::JSONBool(op == "or"));
+                    continue;
+                }
+                for b in eval(right, input)? {
+                    result.push(binary(op, &a, &b)?);
+                }
+            }
+            return Ok(result);
+        }
+        Expr::Call(ref name, ref args) => return call(name, args, input),
+    }
+}
+
+fn flat_map<F>(values: Vec<JSONValue>, mut f: F) -> Result<Vec<JSONValue>, JSONQueryError>
+where
+    F: FnMut(JSONValue) -> Result<Vec<JSONValue>, JSONQueryError>,
+{
+    let mut result = vec![];
+    for value in values {
+        result.extend(f(value)?);
+    }
+    return Ok(result);
+}
+
+fn index(value: &JSONValue, key: &JSONValue) -> Result<JSONValue, JSONQueryError> {
+    match (value, key) {
+        (&JSONValue::JSONNull(), _) => return Ok(JSONValue::JSONNull()),
+        (JSONValue::JSONObject(map), JSONValue::JSONString(name)) => {
+            return Ok(map
+                .get(name)
+                .map_or(JSONValue::JSONNull(), |v| (**v).clone()));
+        }
+        (JSONValue::JSONArray(items), &JSONValue::JSONNumber(n)) => {
+            let i = if n < 0.0 { items.len() as f64 + n } else { n };
+            if i < 0.0 || i.fract() != 0.0 {
+                return Ok(JSONValue::JSONNull());
+            }
+            return Ok(items
+                .get(i as usize)
+                .map_or(JSONValue::JSONNull(), |v| (**v).clone()));
+        }
+        _ => {
+            return Err(make_err(format!(
+                "Cannot index {} with {}",
+                type_name(value),
+                type_name(key)
+            )))
+        }
+    }
+}
+
+fn binary(op: &str, a: &JSONValue, b: &JSONValue) -> Result<JSONValue, JSONQueryError> {
+    let ordering = compare(a, b);
+    let result = match op {
+        "==" => JSONValue::JSONBool(ordering == Ordering::Equal),
+        "!=" => JSONValue::JSONBool(ordering != Ordering::Equal),
+        "<" => JSONValue::JSONBool(ordering == Ordering::Less),
+        "<=" => JSONValue::JSONBool(ordering != Ordering::Greater),
+        ">" => JSONValue::JSONBool(ordering == Ordering::Greater),
+        ">=" => JSONValue::JSONBool(ordering != Ordering::Less),
+        "and" | "or" => JSONValue::JSONBool(is_truthy(b)),
+        _ => return arithmetic(op, a, b),
+    };
+    return Ok(result);
+}
+
+fn arithmetic(op: &str, a: &JSONValue, b: &JSONValue) -> Result<JSONValue, JSONQueryError> {
+    match (op, a, b) {
+        (_, &JSONValue::JSONNumber(x), &JSONValue::JSONNumber(y)) => {
+            let n = match op {
+                "+" => x + y,
+                "-" => x - y,
+                "*" => x * y,
+                _ if y == 0.0 => return Err(make_err("Division by zero".to_owned())),
+                _ => x / y,
+            };
+            return Ok(JSONValue::JSONNumber(n));
+        }
+        ("+", &JSONValue::JSONNull(), other) | ("+", other, &JSONValue::JSONNull()) => {
+            return Ok(other.clone());
+        }
+        ("+", JSONValue::JSONString(x), JSONValue::JSONString(y)) => {
+            return Ok(JSONValue::JSONString(format!("{}{}", x, y)));
+        }
+        ("+", JSONValue::JSONArray(x), JSONValue::JSONArray(y)) => {
+            return Ok(JSONValue::JSONArray(
+                x.iter().chain(y.iter()).cloned().collect(),
+            ));
+        }
+        _ => {
+            return Err(make_err(format!(
+                "Cannot apply {} to {} and {}",
+                op,
+                type_name(a),
+                type_name(b)
+            )))
+        }
+    }
+}
+
+fn call(name: &str, args: &[Expr], input: &JSONValue) -> Result<Vec<JSONValue>, JSONQueryError> {
+    match (name, args.len()) {
+        ("select", 1) => {
+            for value in eval(&args[0], input)? {
+                if is_truthy(&value) {
+                    return Ok(vec![input.clone()]);
+                }
+            }
+            return Ok(vec![]);
+        }
+        ("has", 1) => {
+            return flat_map(eval(&args[0], input)?, |key| {
+                let found = match (input, &key) {
+                    (JSONValue::JSONObject(map), JSONValue::JSONString(name)) => {
+                        map.contains_key(name)
+                    }
+                    (JSONValue::JSONArray(items), &JSONValue::JSONNumber(n)) => {
+                        n >= 0.0 && (n as usize) < items.len()
+                    }
+                    _ => {
+                        return Err(make_err(format!(
+                            "Cannot check whether {} has a key",
+                            type_name(input)
+                        )))
+                    }
+                };
+                Ok(vec![JSONValue::JSONBool(found)])
+            });
+        }
+        ("not", 0) => return Ok(vec![JSONValue::JSONBool(!is_truthy(input))]),
+        ("length", 0) => {
+            let length = match *input {
+                JSONValue::JSONNull() => 0.0,
+                JSONValue::JSONNumber(n) => n.abs(),
+                JSONValue::JSONString(ref s) => s.chars().count() as f64,
+                JSONValue::JSONArray(ref items) => items.len() as f64,
+                JSONValue::JSONObject(ref map) => map.len() as f64,
+                JSONValue::JSONBool(_) => return Err(make_err("Boolean has no length".to_owned())),
+                JSONValue::JSONRaw(_) => return Err(make_err("Raw JSON has no length".to_owned())),
+            };
+            return Ok(vec![JSONValue::JSONNumber(length)]);
+        }
+        ("keys", 0) => match *input {
+            JSONValue::JSONObject(ref map) => {
+                let mut keys: Vec<&String> = map.keys().collect();
+                keys.sort();
+                let keys = keys
+                    .into_iter()
+                    .map(|k| Box::new(JSONValue::JSONString(k.clone())))
+                    .collect();
+                return Ok(vec![JSONValue::JSONArray(keys)]);
+            }
+            JSONValue::JSONArray(ref items) => {
+                let keys = (0..items.len())
+                    .map(|i| Box::new(JSONValue::JSONNumber(i as f64)))
+                    .collect();
+                return Ok(vec![JSONValue::JSONArray(keys)]);
+            }
+            _ => return Err(make_err(format!("{} has no keys", type_name(input)))),
+        },
+        _ => {
+            return Err(make_err(format!(
+                "Unknown function {}/{}",
+                name,
+                args.len()
+            )))
+        }
+    }
+}
+
+/// Only `false` and `null` are falsy, as in jq.
+fn is_truthy(value: &JSONValue) -> bool {
+    match *value {
+        JSONValue::JSONNull() | JSONValue::JSONBool(false) => return false,
+        _ => return true,
+    }
+}
+
+fn type_rank(value: &JSONValue) -> u8 {
+    match *value {
+        JSONValue::JSONNull() => return 0,
+        JSONValue::JSONBool(false) => return 1,
+        JSONValue::JSONBool(true) => return 2,
+        JSONValue::JSONNumber(_) => return 3,
+        JSONValue::JSONString(_) => return 4,
+        JSONValue::JSONArray(_) => return 5,
+        JSONValue::JSONObject(_) => return 6,
+        JSONValue::JSONRaw(_) => return 7,
+    }
+}
+
+/// Total order over values, as in jq: null < false < true < numbers < strings < arrays < objects.
+/// Objects compare by sorted key sets first, then by values in key order.
+pub fn compare(a: &JSONValue, b: &JSONValue) -> Ordering {
+    match (a, b) {
+        (&JSONValue::JSONNumber(x), &JSONValue::JSONNumber(y)) => {
+            return x.partial_cmp(&y).unwrap_or(Ordering::Equal);
+        }
+        (JSONValue::JSONString(x), JSONValue::JSONString(y)) => return x.cmp(y),
+        (JSONValue::JSONArray(x), JSONValue::JSONArray(y)) => {
+            for (a, b) in x.iter().zip(y.iter()) {
+                let ordering = compare(a, b);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            return x.len().cmp(&y.len());
+        }
+        (JSONValue::JSONObject(x), JSONValue::JSONObject(y)) => {
+            let mut x_keys: Vec<&String> = x.keys().collect();
+            let mut y_keys: Vec<&String> = y.keys().collect();
+            x_keys.sort();
+            y_keys.sort();
+            if x_keys != y_keys {
+                return x_keys.cmp(&y_keys);
+            }
+            for key in x_keys {
+                let ordering = compare(&x[key], &y[key]);
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            return Ordering::Equal;
+        }
+        _ => return type_rank(a).cmp(&type_rank(b)),
+    }
+}
+
+fn type_name(value: &JSONValue) -> &'static str {
+    match *value {
+        JSONValue::JSONNull() => return "null",
+        JSONValue::JSONBool(_) => return "boolean",
+        JSONValue::JSONNumber(_) => return "number",
+        JSONValue::JSONString(_) => return "string",
+        JSONValue::JSONArray(_) => return "array",
+        JSONValue::JSONObject(_) => return "object",
+        JSONValue::JSONRaw(_) => return "raw JSON",
+    }
+}
+
+fn make_err(s: String) -> JSONQueryError {
+    JSONQueryError { reason: s }
+}
@@ -0,0 +1,113 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+fn run(query: &str, input: &str) -> Vec<JSONValue> {
+    return Query::compile(query).unwrap().run(&parse(input)).unwrap();
+}
+
+#[test]
+fn test_paths() {
+    let input = r#"{"a": {"b": [1, 2, {"c": "x"}]}, "odd key": true}"#;
+    for s in vec![
+        (".", vec![input]),
+        (".a.b[0]", vec!["1"]),
+        (".a.b[-1].c", vec!["\"x\""]),
+        (".a[\"b\"][1]", vec!["2"]),
+        (".[\"odd key\"]", vec!["true"]),
+        (".\"odd key\"", vec!["true"]),
+        (".a.b[]", vec!["1", "2", r#"{"c": "x"}"#]),
+        (".a.b[5]", vec!["null"]),
+        (".missing.deeper", vec!["null"]),
+        (".a.b | length", vec!["3"]),
+        (".a.b[0], .a.b[1]", vec!["1", "2"]),
+        ("keys", vec![r#"["a", "odd key"]"#]),
+    ] {
+        println!("Checking {}", s.0);
+        let expected: Vec<JSONValue> = s.1.into_iter().map(parse).collect();
+        assert_eq!(run(s.0, input), expected);
+    }
+}
+
+#[test]
+fn test_expressions() {
+    for s in vec![
+        ("1 + 2 * 3", "null", "7"),
+        ("(1 + 2) * 3", "null", "9"),
+        ("-.a + 1", r#"{"a": 3}"#, "-2"),
+        (".a + .b", r#"{"a": "x", "b": "y"}"#, "\"xy\""),
+        (".a + .b", r#"{"a": [1], "b": [2]}"#, "[1, 2]"),
+        (".a == 1", r#"{"a": 1}"#, "true"),
+        (".a != \"1\"", r#"{"a": 1}"#, "true"),
+        (".a < .b", r#"{"a": null, "b": false}"#, "true"),
+        (".a >= 2 and .a <= 3", r#"{"a": 2}"#, "true"),
+        (".a > 2 or .b", r#"{"a": 2}"#, "false"),
+        (".a | not", r#"{"a": null}"#, "true"),
+        ("has(\"a\")", r#"{"a": null}"#, "true"),
+        ("has(1)", "[0]", "false"),
+        (
+            ".a == .b",
+            r#"{"a": [1, {"c": 2}], "b": [1, {"c": 2}]}"#,
+            "true",
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(run(s.0, s.1), vec![parse(s.2)]);
+    }
+}
+
+#[test]
+fn test_raw_input() {
+    let mut map = JSONMap::new();
+    map.insert(
+        "a".to_owned(),
+        Box::new(JSONValue::raw(r#"{"b": [1, 2]}"#).unwrap()),
+    );
+    let query = Query::compile(".a.b | length").unwrap();
+    assert_eq!(
+        query.run(&JSONValue::JSONObject(map)).unwrap(),
+        vec![JSONValue::JSONNumber(2.0)]
+    );
+}
+
+#[test]
+fn test_select() {
+    let query = Query::compile("select(.level == \"error\") | .msg").unwrap();
+    let error = parse(r#"{"level": "error", "msg": "boom"}"#);
+    let info = parse(r#"{"level": "info", "msg": "ok"}"#);
+    assert_eq!(query.run(&error).unwrap(), vec![parse("\"boom\"")]);
+    assert_eq!(query.run(&info).unwrap(), vec![]);
+    assert_eq!(
+        run(".[] | select(. > 1)", "[1, 2, 3]"),
+        vec![parse("2"), parse("3")]
+    );
+}
+
+#[test]
+fn test_invalid_queries() {
+    for s in vec!["", ".a |", "select(", ".a[", "1 +", ".a ]", "$", "\"open"] {
+        Query::compile(s).expect_err(&format!("Query {} should not compile", s));
+    }
+}
+
+#[test]
+fn test_runtime_errors() {
+    for s in vec![
+        (".a", "[1]"),
+        (".[0]", "{}"),
+        (".[]", "1"),
+        ("1 / 0", "null"),
+        ("\"a\" - 1", "null"),
+        ("true | length", "null"),
+        ("unknown", "null"),
+    ] {
+        Query::compile(s.0)
+            .unwrap()
+            .run(&parse(s.1))
+            .expect_err(&format!("Query {} should fail on {}", s.0, s.1));
+    }
+}
@@ -0,0 +1,177 @@
+use super::*;
+
+#[cfg(test)]
+mod tests;
+
+const REF_KEY: &str = "$ref";
+const FRAGMENT_START: char = '#';
+const ROOT_DOCUMENT: &str = "";
+
+#[derive(Debug, Clone)]
+pub struct JSONRefError {
+    pub reason: String,
+}
+
+/// Loads an external document by the URI part of a reference (everything before `#`).
+/// The URI is passed verbatim, so relative paths are interpreted by the loader.
+pub type RefLoader<'a> = Box<dyn FnMut(&str) -> Result<JSONValue, JSONRefError> + 'a>;
+
+/// Replaces `{"$ref": "..."}` objects with the values they point to.
+/// Internal references (`#/definitions/user`) are resolved against the document itself,
+/// external ones (`common.json#/definitions/id`) are fetched through the loader
+/// and cached for the lifetime of the resolver. Each target is resolved once and
+/// copied wherever it's referenced.
+pub struct RefResolver<'a> {
+    loader: Option<RefLoader<'a>>,
+    documents: HashMap<String, JSONValue>,
+    // resolved targets by `uri#pointer`
+    resolved: HashMap<String, JSONValue>,
+}
+
+impl<'a> RefResolver<'a> {
+    pub fn new() -> RefResolver<'a> {
+        return RefResolver {
+            loader: None,
+            documents: HashMap::new(),
+            resolved: HashMap::new(),
+        };
+    }
+
+    pub fn with_loader<F>(loader: F) -> RefResolver<'a>
+    where
+        F: FnMut(&str) -> Result<JSONValue, JSONRefError> + 'a,
+    {
+        return RefResolver {
+            loader: Some(Box::new(loader)),
+            documents: HashMap::new(),
+            resolved: HashMap::new(),
+        };
+    }
+
+    /// Returns a fully dereferenced copy of the document.
+    /// Fails on dangling or cyclic references, since a cycle can't be represented in a tree.
+    pub fn resolve(&mut self, document: &JSONValue) -> Result<JSONValue, JSONRefError> {
+        self.documents
+            .insert(ROOT_DOCUMENT.to_owned(), document.clone());
+        // targets in the previous root document are stale
+        self.resolved.clear();
+        return self.resolve_value(document, ROOT_DOCUMENT, &mut vec![]);
+    }
+
+    fn resolve_value(
+        &mut self,
+        value: &JSONValue,
+        base: &str,
+        stack: &mut Vec<String>,
+    ) -> Result<JSONValue, JSONRefError> {
+        match *value {
+            JSONValue::JSONObject(ref map) => {
+                if let Some(reference) = map.get(REF_KEY) {
+                    if let JSONValue::JSONString(ref reference) = **reference {
+                        return self.resolve_ref(reference, base, stack);
+                    }
+                }
+                let mut result = JSONMap::new();
+                for (key, item) in map {
+                    result.insert(
+                        key.clone(),
+                        Box::new(self.resolve_value(item, base, stack)?),
+                    );
+                }
+                return Ok(JSONValue::JSONObject(result));
+            }
+            JSONValue::JSONArray(ref items) => {
+                let mut result = Vec::with_capacity(items.len());
+                for item in items {
+                    result.push(Box::new(self.resolve_value(item, base, stack)?));
+                }
+                return Ok(JSONValue::JSONArray(result));
+            }
+            _ => return Ok(value.clone()),
+        }
+    }
+
+    fn resolve_ref(
+        &mut self,
+        reference: &str,
+        base: &str,
+        stack: &mut Vec<String>,
+    ) -> Result<JSONValue, JSONRefError> {
+        let (uri, fragment) = match reference.find(FRAGMENT_START) {
+            Some(i) => (&reference[..i], &reference[i + 1..]),
+            None => (reference, ""),
+        };
+        let uri = if uri.is_empty() { base } else { uri };
+        let pointer = percent_decode(fragment)
+            .ok_or_else(|| make_err(format!("Invalid reference {}", reference)))?;
+        let key = format!("{}#{}", uri, pointer);
+        if let Some(resolved) = self.resolved.get(&key) {
+            return Ok(resolved.clone());
+        }
+        if stack.contains(&key) {
+            return Err(make_err(format!("Cyclic reference {}", reference)));
+        }
+        self.load(uri)?;
+        let target = self.documents[uri]
+            .pointer(&pointer)
+            .ok_or_else(|| make_err(format!("Unresolvable reference {}", reference)))?
+            .clone();
+        stack.push(key);
+        let result = self.resolve_value(&target, uri, stack);
+        let key = stack.pop().unwrap();
+        if let Ok(ref resolved) = result {
+            self.resolved.insert(key, resolved.clone());
+        }
+        return result;
+    }
+
+    fn load(&mut self, uri: &str) -> Result<(), JSONRefError> {
+        if self.documents.contains_key(uri) {
+            return Ok(());
+        }
+        let document = match self.loader {
+            Some(ref mut loader) => loader(uri)?,
+            None => {
+                return Err(make_err(format!(
+                    "External reference {} requires a loader",
+                    uri
+                )))
+            }
+        };
+        self.documents.insert(uri.to_owned(), document);
+        return Ok(());
+    }
+}
+
+impl<'a> Default for RefResolver<'a> {
+    fn default() -> Self {
+        return RefResolver::new();
+    }
+}
+
+/// Resolves internal references only. See `RefResolver` for external ones.
+pub fn resolve_refs(document: &JSONValue) -> Result<JSONValue, JSONRefError> {
+    return RefResolver::new().resolve(document);
+}
+
+// URI fragments may percent-encode pointer characters, e.g. `#/definitions/a%20b`
+fn percent_decode(s: &str) -> Option<String> {
+    let bytes = s.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = s.get(i + 1..i + 3)?;
+            result.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            result.push(bytes[i]);
+            i += 1;
+        }
+    }
+    return String::from_utf8(result).ok();
+}
+
+fn make_err(s: String) -> JSONRefError {
+    JSONRefError { reason: s }
+}
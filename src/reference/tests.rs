@@ -0,0 +1,89 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_resolve_internal_refs() {
+    for s in vec![
+        (
+            r##"{"definitions": {"id": {"type": "integer"}}, "a": {"$ref": "#/definitions/id"}}"##,
+            r##"{"definitions": {"id": {"type": "integer"}}, "a": {"type": "integer"}}"##,
+        ),
+        (
+            r##"{"d": {"x": {"$ref": "#/d/y"}, "y": [1, 2]}, "a": [{"$ref": "#/d/x"}]}"##,
+            r##"{"d": {"x": [1, 2], "y": [1, 2]}, "a": [[1, 2]]}"##,
+        ),
+        (
+            r##"{"d": {"a b": 1}, "a": {"$ref": "#/d/a%20b"}}"##,
+            r##"{"d": {"a b": 1}, "a": 1}"##,
+        ),
+        (r##"{"a": {"$ref": 1}}"##, r##"{"a": {"$ref": 1}}"##),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(resolve_refs(&parse(s.0)).unwrap(), parse(s.1));
+    }
+}
+
+#[test]
+fn test_invalid_refs() {
+    for s in vec![
+        r##"{"a": {"$ref": "#/missing"}}"##,
+        r##"{"a": {"$ref": "#/a"}}"##,
+        r##"{"d": {"x": {"items": {"$ref": "#/d/x"}}}}"##,
+        r##"{"d": {"x": {"$ref": "#/d/y"}, "y": {"$ref": "#/d/x"}}}"##,
+        r##"{"a": {"$ref": "other.json#/a"}}"##,
+        r##"{"a": {"$ref": "#/a%zz"}}"##,
+    ] {
+        resolve_refs(&parse(s)).expect_err(&format!("Should not be resolved {}", s));
+    }
+}
+
+#[test]
+fn test_external_refs() {
+    let mut loaded = vec![];
+    let result = {
+        let mut resolver = RefResolver::with_loader(|uri: &str| {
+            loaded.push(uri.to_owned());
+            match uri {
+                "common.json" => Ok(parse(r##"{"id": {"$ref": "#/int"}, "int": 42}"##)),
+                _ => Err(JSONRefError {
+                    reason: format!("Unknown document {}", uri),
+                }),
+            }
+        });
+        resolver.resolve(&parse(
+            r##"{"a": {"$ref": "common.json#/id"}, "b": {"$ref": "common.json#/int"}}"##,
+        ))
+    };
+    assert_eq!(result.unwrap(), parse(r##"{"a": 42, "b": 42}"##));
+    assert_eq!(loaded, vec!["common.json"]);
+}
+
+#[test]
+fn test_shared_refs() {
+    // every definition references the previous one twice
+    let mut definitions = vec!["\"d0\": 1".to_owned()];
+    for i in 1..=16 {
+        definitions.push(format!(
+            r##""d{}": [{{"$ref": "#/d/d{}"}}, {{"$ref": "#/d/d{}"}}]"##,
+            i,
+            i - 1,
+            i - 1
+        ));
+    }
+    let document = parse(&format!(
+        r##"{{"d": {{{}}}, "a": {{"$ref": "#/d/d16"}}}}"##,
+        definitions.join(", ")
+    ));
+    let resolved = resolve_refs(&document).unwrap();
+    let mut leaf = resolved.pointer("/a").unwrap();
+    for _ in 0..16 {
+        leaf = leaf.pointer("/1").unwrap();
+    }
+    assert_eq!(*leaf, JSONValue::JSONNumber(1.0));
+    assert_eq!(resolved.pointer("/a"), resolved.pointer("/d/d16"));
+}
@@ -0,0 +1,105 @@
+use super::*;
+use pattern::PathPattern;
+use std::mem;
+
+#[cfg(test)]
+mod tests;
+
+impl JSONValue {
+    /// Renames the keys of every object in the tree with `rename`, e.g.
+    /// `value.rename_keys(to_snake_case)`. When several keys of an object get the
+    /// same name, the value of the key iterated last is kept. Raw fragments are
+    /// expanded, so their keys are renamed too.
+    pub fn rename_keys<F: FnMut(&str) -> String>(&mut self, rename: F) {
+        self.rename_keys_except(&[], rename);
+    }
+
+    /// Like `rename_keys`, but members at paths matching one of `excludes` keep
+    /// their key and everything below them is left alone, e.g. `/metadata` for
+    /// free-form maps whose keys are data. Paths use the keys before renaming.
+    pub fn rename_keys_except<F: FnMut(&str) -> String>(
+        &mut self,
+        excludes: &[PathPattern],
+        mut rename: F,
+    ) {
+        rename_in(self, excludes, &mut rename, &mut vec![]);
+    }
+}
+
+fn rename_in<F: FnMut(&str) -> String>(
+    value: &mut JSONValue,
+    excludes: &[PathPattern],
+    rename: &mut F,
+    path: &mut Vec<String>,
+) {
+    if let JSONValue::JSONRaw(_) = *value {
+        *value = value.expand_raw().into_owned();
+    }
+    match *value {
+        JSONValue::JSONArray(ref mut items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                path.push(i.to_string());
+                if !excludes.iter().any(|p| p.matches_tokens(path)) {
+                    rename_in(item, excludes, rename, path);
+                }
+                path.pop();
+            }
+        }
+        JSONValue::JSONObject(ref mut members) => {
+            for (key, mut member) in mem::take(members) {
+                path.push(key);
+                let key = if excludes.iter().any(|p| p.matches_tokens(path)) {
+                    path.pop().unwrap()
+                } else {
+                    rename_in(&mut member, excludes, rename, path);
+                    let key = path.pop().unwrap();
+                    rename(&key)
+                };
+                members.insert(key, member);
+            }
+        }
+        _ => (),
+    }
+}
+
+/// `userId`, `UserID` and `HTTPServer` to `user_id`, `user_id` and
+/// `http_server`. Underscores and digits are kept as they are.
+pub fn to_snake_case(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    let mut result = String::with_capacity(key.len() + 4);
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            // a word starts after a lowercase letter or digit, or at the last
+            // capital of an acronym followed by lowercase letters
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_lower)
+            {
+                result.push('_');
+            }
+        }
+        result.extend(c.to_lowercase());
+    }
+    return result;
+}
+
+/// `user_id` to `userId`. Leading underscores are kept (`_id` stays `_id`),
+/// the case of the rest is left as it is.
+pub fn to_camel_case(key: &str) -> String {
+    let trimmed = key.trim_start_matches('_');
+    let mut result = String::with_capacity(key.len());
+    result.push_str(&key[..key.len() - trimmed.len()]);
+    for (i, word) in trimmed.split('_').enumerate() {
+        let mut chars = word.chars();
+        match chars.next() {
+            Some(first) if i > 0 => {
+                result.extend(first.to_uppercase());
+                result.push_str(chars.as_str());
+            }
+            _ => result.push_str(word),
+        }
+    }
+    return result;
+}
@@ -0,0 +1,72 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_case_conversion() {
+    for (camel, snake) in vec![
+        ("userId", "user_id"),
+        ("user", "user"),
+        ("HTTPServer", "http_server"),
+        ("parseHTTPResponse", "parse_http_response"),
+        ("version2Name", "version2_name"),
+        ("_id", "_id"),
+        ("already_snake", "already_snake"),
+        ("", ""),
+    ] {
+        println!("Checking {} and {}", camel, snake);
+        assert_eq!(to_snake_case(camel), snake);
+    }
+    for (snake, camel) in vec![
+        ("user_id", "userId"),
+        ("user", "user"),
+        ("http_server", "httpServer"),
+        ("_private_key", "_privateKey"),
+        ("a__b", "aB"),
+        ("trailing_", "trailing"),
+        ("ünter_ß", "ünterSS"),
+        ("", ""),
+    ] {
+        println!("Checking {} and {}", snake, camel);
+        assert_eq!(to_camel_case(snake), camel);
+    }
+}
+
+#[test]
+fn test_rename_keys() {
+    let mut value = parse(
+        r#"{"userId": 1, "addressLines": [{"postCode": "x"}], "metadata": {"someKey": {"innerKey": 1}},
+            "items": [{"itemId": 1, "extraData": {"keepMe": 1}}], "name": "firstName"}"#,
+    );
+    value.rename_keys(to_snake_case);
+    assert_eq!(
+        value,
+        parse(
+            r#"{"user_id": 1, "address_lines": [{"post_code": "x"}], "metadata": {"some_key": {"inner_key": 1}},
+                "items": [{"item_id": 1, "extra_data": {"keep_me": 1}}], "name": "firstName"}"#
+        )
+    );
+
+    let excludes = vec![
+        PathPattern::compile("/metadata").unwrap(),
+        PathPattern::compile("/items/*/extra_data").unwrap(),
+    ];
+    value.rename_keys_except(&excludes, to_camel_case);
+    assert_eq!(
+        value,
+        parse(
+            r#"{"userId": 1, "addressLines": [{"postCode": "x"}], "metadata": {"some_key": {"inner_key": 1}},
+                "items": [{"itemId": 1, "extra_data": {"keep_me": 1}}], "name": "firstName"}"#
+        )
+    );
+
+    let mut value = JSONValue::JSONArray(vec![Box::new(
+        JSONValue::raw(r#"{"a_b": 1, "aB": 2}"#).unwrap(),
+    )]);
+    value.rename_keys(|key| key.to_uppercase());
+    assert_eq!(value, parse(r#"[{"A_B": 1, "AB": 2}]"#));
+}
@@ -0,0 +1,488 @@
+use super::*;
+
+#[cfg(test)]
+mod tests;
+
+// Deeper nesting is copied as it is instead of being repaired
+const MAX_DEPTH: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FixKind {
+    /// Markdown code fence around the document, as LLMs write them
+    RemovedCodeFence,
+    RemovedComment,
+    RemovedTrailingComma,
+    /// Comma with nothing before it, like the second of `[1,,2]`
+    RemovedComma,
+    AddedComma,
+    AddedColon,
+    /// Bracket or brace the input ended without, or that a mismatched one stood for
+    AddedClosing,
+    /// Character that can't be anywhere at its position
+    RemovedCharacter,
+    /// Anything after the first value
+    RemovedTrailingData,
+    /// Key that isn't a string, like `{a: 1}`
+    QuotedKey,
+    /// Bare word that isn't a literal, like `[hello]`
+    QuotedString,
+    /// Single-quoted string made double-quoted
+    ReplacedQuotes,
+    /// `True`, `None`, `undefined`, `NaN` and similar, made JSON literals
+    ReplacedLiteral,
+    /// Number written in a way JSON doesn't allow, like `+1`, `.5`, `1.` or `01`
+    FixedNumber,
+    /// Raw control character or invalid escape inside a string
+    EscapedCharacter,
+    /// String the input ended inside of
+    ClosedString,
+    /// `null` in place of a value the input ended without
+    AddedValue,
+}
+
+/// A change `repair` made
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fix {
+    pub kind: FixKind,
+    /// Byte offset in the input
+    pub position: usize,
+    pub description: String,
+}
+
+/// Turns almost-JSON into JSON, conservatively, the way jsonrepair does: strips
+/// comments, trailing commas and code fences, adds missing commas, colons,
+/// quotes and closing brackets, and fixes quotes, literals and numbers of other
+/// languages. Returns the repaired text with every change made; valid JSON comes
+/// back unchanged, formatting included. The result isn't guaranteed to parse:
+/// input too broken to guess about (e.g. empty) is passed through.
+pub fn repair(input: &str) -> (String, Vec<Fix>) {
+    let mut repairer = Repairer {
+        chars: input.char_indices().collect(),
+        i: 0,
+        end: input.len(),
+        out: String::with_capacity(input.len() + 16),
+        fixes: vec![],
+        stack: vec![],
+    };
+    repairer.document(input);
+    let mut fixes = repairer.fixes;
+    fixes.sort_by_key(|fix| fix.position);
+    return (repairer.out, fixes);
+}
+
+struct Repairer {
+    chars: Vec<(usize, char)>,
+    i: usize,
+    // byte length of the input, less a closing code fence
+    end: usize,
+    out: String,
+    fixes: Vec<Fix>,
+    // closing characters of the open containers
+    stack: Vec<char>,
+}
+
+impl Repairer {
+    fn document(&mut self, input: &str) {
+        let trimmed = input.trim_start();
+        if trimmed.starts_with("```") {
+            let fence = input.len() - trimmed.len();
+            let line_end = input[fence..]
+                .find('\n')
+                .map_or(input.len(), |i| fence + i + 1);
+            while self.position() < line_end {
+                self.i += 1;
+            }
+            let body = input[line_end..].trim_end();
+            if body.ends_with("```") {
+                self.end = line_end + body.len() - 3;
+            }
+            self.fix(
+                FixKind::RemovedCodeFence,
+                fence,
+                "Removed code fence".to_owned(),
+            );
+        }
+        self.trivia();
+        if self.peek().is_none() {
+            return;
+        }
+        self.value();
+        self.trivia();
+        if let Some(position) = self.peek().map(|_| self.position()) {
+            let message = "Removed data after the value".to_owned();
+            self.fix(FixKind::RemovedTrailingData, position, message);
+            // whitespace after the value is kept, the rest dropped
+            let kept = self.out.trim_end().len();
+            self.out.truncate(kept);
+            self.i = self.chars.len();
+        }
+    }
+
+    fn value(&mut self) {
+        if self.stack.len() >= MAX_DEPTH {
+            let rest: String = self.chars[self.i..].iter().map(|&(_, ch)| ch).collect();
+            self.out.push_str(&rest);
+            self.i = self.chars.len();
+            return;
+        }
+        match self.peek() {
+            Some('{') => self.container('{', '}'),
+            Some('[') => self.container('[', ']'),
+            Some('"') => self.string('"'),
+            Some('\'') => {
+                let message = "Replaced single quotes with double quotes".to_owned();
+                self.fix(FixKind::ReplacedQuotes, self.position(), message);
+                self.string('\'');
+            }
+            Some(ch) if starts_number(ch) => self.number(),
+            Some(ch) if is_word(ch) => self.word(),
+            _ => {
+                let message = "Added null for a missing value".to_owned();
+                self.fix(FixKind::AddedValue, self.position(), message);
+                self.out.push_str("null");
+            }
+        }
+    }
+
+    fn container(&mut self, open: char, close: char) {
+        let object = open == '{';
+        self.out.push(open);
+        self.i += 1;
+        self.stack.push(close);
+        // output offsets: after the last value, and of a comma following it
+        let mut value_end: Option<usize> = None;
+        let mut comma: Option<usize> = None;
+        loop {
+            self.trivia();
+            let position = self.position();
+            match self.peek() {
+                None => {
+                    self.drop_trailing_comma(&mut comma, position);
+                    let message = format!("Added missing {}", close);
+                    self.fix(FixKind::AddedClosing, position, message);
+                    self.out.push(close);
+                    break;
+                }
+                Some(ch) if ch == close => {
+                    self.drop_trailing_comma(&mut comma, position);
+                    self.out.push(close);
+                    self.i += 1;
+                    break;
+                }
+                Some(ch) if ch == '}' || ch == ']' => {
+                    if self.stack.contains(&ch) {
+                        // closes an outer container, so this one ends here
+                        self.drop_trailing_comma(&mut comma, position);
+                        let message = format!("Added missing {} before {}", close, ch);
+                        self.fix(FixKind::AddedClosing, position, message);
+                        self.out.push(close);
+                        break;
+                    }
+                    self.skip_char(ch);
+                }
+                Some(',') => {
+                    if value_end.is_some() && comma.is_none() {
+                        comma = Some(self.out.len());
+                        self.out.push(',');
+                        self.i += 1;
+                    } else {
+                        let message = "Removed comma without a value before it".to_owned();
+                        self.fix(FixKind::RemovedComma, position, message);
+                        self.i += 1;
+                    }
+                }
+                Some(ch) if ch == ':' || (object && !starts_key(ch)) => self.skip_char(ch),
+                Some(ch) if !object && !starts_value(ch) => self.skip_char(ch),
+                Some(_) => {
+                    if let (Some(end), None) = (value_end, comma) {
+                        self.fix(
+                            FixKind::AddedComma,
+                            position,
+                            "Added missing comma".to_owned(),
+                        );
+                        self.out.insert(end, ',');
+                    }
+                    if object {
+                        self.key();
+                        let key_end = self.out.len();
+                        self.trivia();
+                        if self.peek() == Some(':') {
+                            self.out.push(':');
+                            self.i += 1;
+                        } else {
+                            let message = "Added missing colon".to_owned();
+                            self.fix(FixKind::AddedColon, self.position(), message);
+                            self.out.insert(key_end, ':');
+                        }
+                        self.trivia();
+                        match self.peek() {
+                            Some(ch) if ch == ',' || ch == '}' || ch == ']' => {
+                                let message = "Added null for a missing value".to_owned();
+                                self.fix(FixKind::AddedValue, self.position(), message);
+                                self.out.push_str("null");
+                            }
+                            _ => self.value(),
+                        }
+                    } else {
+                        self.value();
+                    }
+                    value_end = Some(self.out.len());
+                    comma = None;
+                }
+            }
+        }
+        self.stack.pop();
+    }
+
+    fn drop_trailing_comma(&mut self, comma: &mut Option<usize>, position: usize) {
+        if let Some(at) = comma.take() {
+            self.out.remove(at);
+            let message = "Removed trailing comma".to_owned();
+            self.fix(FixKind::RemovedTrailingComma, position, message);
+        }
+    }
+
+    fn key(&mut self) {
+        match self.peek() {
+            Some('"') => self.string('"'),
+            Some('\'') => {
+                let message = "Replaced single quotes with double quotes".to_owned();
+                self.fix(FixKind::ReplacedQuotes, self.position(), message);
+                self.string('\'');
+            }
+            _ => {
+                let position = self.position();
+                let word = self.take_while(|ch| is_word(ch) || ch == '-' || ch == '.');
+                let message = format!("Quoted key {}", word);
+                self.fix(FixKind::QuotedKey, position, message);
+                push_quoted(&mut self.out, &word);
+            }
+        }
+    }
+
+    // A string in `quote`, written double-quoted
+    fn string(&mut self, quote: char) {
+        self.out.push('"');
+        self.i += 1;
+        loop {
+            let (position, ch) = match self.chars.get(self.i) {
+                Some(&(position, ch)) if position < self.end => (position, ch),
+                _ => {
+                    let message = "Added missing closing quote".to_owned();
+                    self.fix(FixKind::ClosedString, self.position(), message);
+                    self.out.push('"');
+                    return;
+                }
+            };
+            self.i += 1;
+            match ch {
+                _ if ch == quote => {
+                    self.out.push('"');
+                    return;
+                }
+                '"' => self.out.push_str("\\\""),
+                '\\' => match self.peek() {
+                    Some(next) if "\"\\/bfnrt".contains(next) => {
+                        self.out.push('\\');
+                        self.out.push(next);
+                        self.i += 1;
+                    }
+                    Some('u') if self.is_unicode_escape() => self.out.push('\\'),
+                    Some('\'') if quote == '\'' => {
+                        self.out.push('\'');
+                        self.i += 1;
+                    }
+                    _ => {
+                        let message = "Escaped a backslash".to_owned();
+                        self.fix(FixKind::EscapedCharacter, position, message);
+                        self.out.push_str("\\\\");
+                    }
+                },
+                '\0'..='\x1F' => {
+                    let message = format!("Escaped control character {:?}", ch);
+                    self.fix(FixKind::EscapedCharacter, position, message);
+                    match ch {
+                        '\n' => self.out.push_str("\\n"),
+                        '\r' => self.out.push_str("\\r"),
+                        '\t' => self.out.push_str("\\t"),
+                        _ => self.out.push_str(&format!("\\u{:04x}", ch as u32)),
+                    }
+                }
+                _ => self.out.push(ch),
+            }
+        }
+    }
+
+    fn is_unicode_escape(&self) -> bool {
+        let digits = self.chars[self.i + 1..].iter().take(4);
+        return digits.filter(|&&(_, ch)| ch.is_ascii_hexdigit()).count() == 4;
+    }
+
+    fn number(&mut self) {
+        let position = self.position();
+        let token = self.take_while(|ch| ch.is_ascii_alphanumeric() || "+-.".contains(ch));
+        if is_number(&token) {
+            self.out.push_str(&token);
+            return;
+        }
+        let mut fixed = token.trim_start_matches('+').to_owned();
+        let negative = fixed.starts_with('-');
+        if negative {
+            fixed.remove(0);
+        }
+        let integer_end = fixed
+            .find(|ch: char| !ch.is_ascii_digit())
+            .unwrap_or(fixed.len());
+        let digits = fixed[..integer_end].trim_start_matches('0');
+        let digits = if digits.is_empty() { "0" } else { digits };
+        fixed = format!("{}{}", digits, &fixed[integer_end..]);
+        if fixed.ends_with('.') {
+            fixed.pop();
+        }
+        fixed = fixed.replace(".e", ".0e").replace(".E", ".0E");
+        if negative {
+            fixed.insert(0, '-');
+        }
+        if is_number(&fixed) {
+            let message = format!("Rewrote number {} as {}", token, fixed);
+            self.fix(FixKind::FixedNumber, position, message);
+            self.out.push_str(&fixed);
+        } else {
+            let message = format!("Quoted {}", token);
+            self.fix(FixKind::QuotedString, position, message);
+            push_quoted(&mut self.out, &token);
+        }
+    }
+
+    fn word(&mut self) {
+        let position = self.position();
+        let word = self.take_while(is_word);
+        let literal = match word.as_str() {
+            "true" | "false" | "null" => {
+                self.out.push_str(&word);
+                return;
+            }
+            "True" | "TRUE" => "true",
+            "False" | "FALSE" => "false",
+            "None" | "NULL" | "Null" | "nil" | "undefined" | "NaN" | "Infinity" => "null",
+            _ => {
+                // words up to the next delimiter, like `[hello world]`
+                let rest = self.take_while(|ch| !",:]}\n".contains(ch));
+                let text = format!("{}{}", word, rest).trim_end().to_owned();
+                let message = format!("Quoted {}", text);
+                self.fix(FixKind::QuotedString, position, message);
+                push_quoted(&mut self.out, &text);
+                let trailing = rest.len() - rest.trim_end().len();
+                self.out.push_str(&rest[rest.len() - trailing..]);
+                return;
+            }
+        };
+        let message = format!("Replaced {} with {}", word, literal);
+        self.fix(FixKind::ReplacedLiteral, position, message);
+        self.out.push_str(literal);
+    }
+
+    // Copies whitespace, drops comments
+    fn trivia(&mut self) {
+        loop {
+            match self.peek() {
+                Some(ch) if ch.is_whitespace() => {
+                    self.out.push(ch);
+                    self.i += 1;
+                }
+                Some('/') if self.peek_at(1) == Some('/') => {
+                    let message = "Removed comment".to_owned();
+                    self.fix(FixKind::RemovedComment, self.position(), message);
+                    self.take_while(|ch| ch != '\n');
+                }
+                Some('/') if self.peek_at(1) == Some('*') => {
+                    let message = "Removed comment".to_owned();
+                    self.fix(FixKind::RemovedComment, self.position(), message);
+                    self.i += 2;
+                    while self.peek().is_some()
+                        && !(self.peek() == Some('*') && self.peek_at(1) == Some('/'))
+                    {
+                        self.i += 1;
+                    }
+                    self.i = (self.i + 2).min(self.chars.len());
+                }
+                Some('#') => {
+                    let message = "Removed comment".to_owned();
+                    self.fix(FixKind::RemovedComment, self.position(), message);
+                    self.take_while(|ch| ch != '\n');
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn skip_char(&mut self, ch: char) {
+        let message = format!("Removed unexpected {}", ch);
+        self.fix(FixKind::RemovedCharacter, self.position(), message);
+        self.i += 1;
+    }
+
+    fn take_while<F: Fn(char) -> bool>(&mut self, accept: F) -> String {
+        let mut result = String::new();
+        while let Some(ch) = self.peek() {
+            if !accept(ch) {
+                break;
+            }
+            result.push(ch);
+            self.i += 1;
+        }
+        return result;
+    }
+
+    fn peek(&self) -> Option<char> {
+        return self.peek_at(0);
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        match self.chars.get(self.i + offset) {
+            Some(&(position, ch)) if position < self.end => return Some(ch),
+            _ => return None,
+        }
+    }
+
+    // Byte offset of the next character in the input
+    fn position(&self) -> usize {
+        return self
+            .chars
+            .get(self.i)
+            .map_or(self.end, |&(position, _)| position.min(self.end));
+    }
+
+    fn fix(&mut self, kind: FixKind, position: usize, description: String) {
+        self.fixes.push(Fix {
+            kind,
+            position,
+            description,
+        });
+    }
+}
+
+fn is_word(ch: char) -> bool {
+    return ch.is_alphanumeric() || ch == '_' || ch == '$';
+}
+
+fn starts_key(ch: char) -> bool {
+    return ch == '"' || ch == '\'' || is_word(ch);
+}
+
+fn starts_number(ch: char) -> bool {
+    return ch == '-' || ch == '+' || ch == '.' || ch.is_ascii_digit();
+}
+
+// `value` consumes it rather than adding `null` in its place
+fn starts_value(ch: char) -> bool {
+    return matches!(ch, '{' | '[' | '"' | '\'') || starts_number(ch) || is_word(ch);
+}
+
+fn is_number(token: &str) -> bool {
+    return matches!(parser::parse_json(token), Ok(JSONValue::JSONNumber(_)));
+}
+
+fn push_quoted(out: &mut String, s: &str) {
+    serializer::write_string(out, s).unwrap();
+}
@@ -0,0 +1,155 @@
+#![allow(clippy::useless_vec)]
+use super::*;
+use generate::{Rng, SplitMix64};
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_repair() {
+    for (input, expected, kinds) in vec![
+        ("{\"a\": [1, 2]}", "{\"a\": [1, 2]}", vec![]),
+        ("[1, 2,]", "[1, 2]", vec![FixKind::RemovedTrailingComma]),
+        ("[1,,2]", "[1,2]", vec![FixKind::RemovedComma]),
+        ("[1 2]", "[1, 2]", vec![FixKind::AddedComma]),
+        ("{a: 1}", "{\"a\": 1}", vec![FixKind::QuotedKey]),
+        (
+            "{'a': 'it\\'s \"x\"'}",
+            "{\"a\": \"it's \\\"x\\\"\"}",
+            vec![FixKind::ReplacedQuotes, FixKind::ReplacedQuotes],
+        ),
+        ("{\"a\" 1}", "{\"a\": 1}", vec![FixKind::AddedColon]),
+        (
+            "{\"a\": [1, {\"b\": 2",
+            "{\"a\": [1, {\"b\": 2}]}",
+            vec![
+                FixKind::AddedClosing,
+                FixKind::AddedClosing,
+                FixKind::AddedClosing,
+            ],
+        ),
+        (
+            "[1, 2}",
+            "[1, 2]",
+            vec![FixKind::RemovedCharacter, FixKind::AddedClosing],
+        ),
+        ("{\"a\": [1}", "{\"a\": [1]}", vec![FixKind::AddedClosing]),
+        (
+            "{\"a\": \"b",
+            "{\"a\": \"b\"}",
+            vec![FixKind::ClosedString, FixKind::AddedClosing],
+        ),
+        ("{\"a\":}", "{\"a\":null}", vec![FixKind::AddedValue]),
+        (
+            "[True, None, undefined]",
+            "[true, null, null]",
+            vec![
+                FixKind::ReplacedLiteral,
+                FixKind::ReplacedLiteral,
+                FixKind::ReplacedLiteral,
+            ],
+        ),
+        (
+            "[+1, .5, 1., 007, -01.e2]",
+            "[1, 0.5, 1, 7, -1.0e2]",
+            vec![FixKind::FixedNumber; 5],
+        ),
+        (
+            "[hello world, 0x1F]",
+            "[\"hello world\", \"0x1F\"]",
+            vec![FixKind::QuotedString, FixKind::QuotedString],
+        ),
+        (
+            "\"a\tb\\d\"",
+            "\"a\\tb\\\\d\"",
+            vec![FixKind::EscapedCharacter, FixKind::EscapedCharacter],
+        ),
+        (
+            "// header\n{\"a\": 1 /* one */, # two\n\"b\": 2}",
+            "\n{\"a\": 1 , \n\"b\": 2}",
+            vec![FixKind::RemovedComment; 3],
+        ),
+        (
+            "```json\n{\"a\": 1}\n```\n",
+            "{\"a\": 1}\n",
+            vec![FixKind::RemovedCodeFence],
+        ),
+        (
+            "{\"a\": 1} trailing words",
+            "{\"a\": 1}",
+            vec![FixKind::RemovedTrailingData],
+        ),
+    ] {
+        println!("Checking {}", input);
+        let (repaired, fixes) = repair(input);
+        assert_eq!(repaired, expected);
+        assert_eq!(fixes.iter().map(|fix| fix.kind).collect::<Vec<_>>(), kinds);
+        parse(&repaired);
+    }
+}
+
+#[test]
+fn test_repair_positions() {
+    let (repaired, fixes) = repair("{a: 1, b: [1, 2,], c 3");
+    assert_eq!(
+        parse(&repaired),
+        parse("{\"a\": 1, \"b\": [1, 2], \"c\": 3}")
+    );
+    let found: Vec<(FixKind, usize)> = fixes.iter().map(|fix| (fix.kind, fix.position)).collect();
+    assert_eq!(
+        found,
+        vec![
+            (FixKind::QuotedKey, 1),
+            (FixKind::QuotedKey, 7),
+            (FixKind::RemovedTrailingComma, 16),
+            (FixKind::QuotedKey, 19),
+            (FixKind::AddedColon, 21),
+            (FixKind::AddedClosing, 22),
+        ]
+    );
+    assert_eq!(fixes[0].description, "Quoted key a");
+}
+
+#[test]
+fn test_repair_stray_characters() {
+    for (input, expected) in vec![
+        ("[@]", "[]"),
+        ("[1, @]", "[1 ]"),
+        ("[/]", "[]"),
+        ("[1 @ 2]", "[1,  2]"),
+    ] {
+        println!("Checking {}", input);
+        let (repaired, fixes) = repair(input);
+        assert_eq!(repaired, expected);
+        assert!(fixes
+            .iter()
+            .any(|fix| fix.kind == FixKind::RemovedCharacter));
+    }
+}
+
+#[test]
+fn test_repair_terminates() {
+    let alphabet: Vec<char> = "{}[]\"',:@/#*-+.0123456789eEtruflsnNaI \n\t\\x`é"
+        .chars()
+        .collect();
+    let mut rng = SplitMix64::new(7);
+    for _ in 0..2000 {
+        let len = (rng.next_u64() % 40) as usize;
+        let input: String = (0..len)
+            .map(|_| alphabet[(rng.next_u64() % alphabet.len() as u64) as usize])
+            .collect();
+        let (repaired, _) = repair(&input);
+        // every char is copied, dropped or stands for a few added ones
+        assert!(repaired.len() <= input.len() * 8 + 16, "{:?}", input);
+    }
+}
+
+#[test]
+fn test_repair_hopeless() {
+    for input in vec!["", "   ", "// only a comment"] {
+        println!("Checking {:?}", input);
+        let (repaired, _) = repair(input);
+        assert!(repaired.parse::<JSONValue>().is_err());
+    }
+}
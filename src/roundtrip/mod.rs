@@ -0,0 +1,200 @@
+use super::*;
+use cst::{CstDocument, CstMember, CstNode};
+use lint::loses_precision;
+use pointer::escape_token;
+use serializer::SerializerConfig;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DifferenceKind {
+    /// Number literal whose value doesn't survive conversion to f64
+    Precision,
+    /// Same number written differently, e.g. `1.0` as `1`
+    NumberFormat,
+    /// Same string or key with different escaping, e.g. `é` as `é`
+    Escape,
+    /// Object keys written in another order
+    KeyOrder,
+    /// Key repeated in an object; only the last value survives parsing
+    DuplicateKey,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundTripDifference {
+    pub kind: DifferenceKind,
+    /// JSON Pointer of the value
+    pub path: String,
+    /// Text in the input, or a description for key order and duplicates
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RoundTripReport {
+    /// The re-serialized input
+    pub output: String,
+    /// Every difference between the input and the output, in document order
+    pub differences: Vec<RoundTripDifference>,
+}
+
+impl RoundTripReport {
+    /// Whether the output holds exactly the data of the input. Formatting,
+    /// escaping and key order may still differ.
+    pub fn is_lossless(&self) -> bool {
+        return !self.differences.iter().any(|difference| {
+            difference.kind == DifferenceKind::Precision
+                || difference.kind == DifferenceKind::DuplicateKey
+        });
+    }
+}
+
+/// Parses the input, serializes it compactly and compares the two texts value by value.
+pub fn verify_roundtrip(input: &str) -> Result<RoundTripReport, JSONParseError> {
+    return verify_roundtrip_with(input, &SerializerConfig::compact());
+}
+
+pub fn verify_roundtrip_with(
+    input: &str,
+    config: &SerializerConfig,
+) -> Result<RoundTripReport, JSONParseError> {
+    let output = input.parse::<JSONValue>()?.to_string_with(config);
+    let before = CstDocument::parse(input).map_err(|e| JSONParseError {
+        reason: e.reason,
+        position: e.position,
+        kind: JSONParseErrorKind::Syntax,
+    })?;
+    let after = CstDocument::parse(&output).expect("serializer output is valid JSON");
+    let mut differences = vec![];
+    compare(
+        &before.root,
+        &after.root,
+        &mut String::new(),
+        &mut differences,
+    );
+    return Ok(RoundTripReport {
+        output,
+        differences,
+    });
+}
+
+fn compare(
+    before: &CstNode,
+    after: &CstNode,
+    path: &mut String,
+    differences: &mut Vec<RoundTripDifference>,
+) {
+    match (before, after) {
+        (
+            CstNode::Scalar {
+                raw: ref raw_before,
+                ref value,
+            },
+            CstNode::Scalar {
+                raw: ref raw_after, ..
+            },
+        ) => {
+            if raw_before == raw_after {
+                return;
+            }
+            let kind = match *value {
+                JSONValue::JSONNumber(n) if loses_precision(raw_before, n) => {
+                    DifferenceKind::Precision
+                }
+                JSONValue::JSONNumber(_) => DifferenceKind::NumberFormat,
+                _ => DifferenceKind::Escape,
+            };
+            report(differences, kind, path, raw_before, raw_after);
+        }
+        (CstNode::Array(before), CstNode::Array(after)) => {
+            let base = path.len();
+            for (i, (a, b)) in before.entries.iter().zip(after.entries.iter()).enumerate() {
+                path.push_str(&format!("/{}", i));
+                compare(&a.value, &b.value, path, differences);
+                path.truncate(base);
+            }
+        }
+        (CstNode::Object(before), CstNode::Object(after)) => {
+            let mut keys_before: Vec<&str> = vec![];
+            for member in before.entries.iter() {
+                if keys_before.contains(&member.key.as_str()) {
+                    let child = format!("{}/{}", path, escape_token(&member.key));
+                    report(
+                        differences,
+                        DifferenceKind::DuplicateKey,
+                        &child,
+                        &member.raw_key,
+                        "last value kept",
+                    );
+                } else {
+                    keys_before.push(&member.key);
+                }
+            }
+            let keys_after: Vec<&str> = after.entries.iter().map(|m| m.key.as_str()).collect();
+            if keys_before != keys_after {
+                report(
+                    differences,
+                    DifferenceKind::KeyOrder,
+                    path,
+                    &keys_before.join(", "),
+                    &keys_after.join(", "),
+                );
+            }
+            let base = path.len();
+            for key in keys_before {
+                let (a, b) = (
+                    last_member(&before.entries, key),
+                    last_member(&after.entries, key),
+                );
+                if let (Some(a), Some(b)) = (a, b) {
+                    path.push('/');
+                    path.push_str(&escape_token(key));
+                    if a.raw_key != b.raw_key {
+                        report(
+                            differences,
+                            DifferenceKind::Escape,
+                            path,
+                            &a.raw_key,
+                            &b.raw_key,
+                        );
+                    }
+                    compare(&a.value, &b.value, path, differences);
+                    path.truncate(base);
+                }
+            }
+        }
+        // the serializer keeps the structure, so this only happens to values it can't write
+        _ => {
+            let config = SerializerConfig::compact();
+            let before = before.to_value().to_string_with(&config);
+            let after = after.to_value().to_string_with(&config);
+            report(
+                differences,
+                DifferenceKind::Precision,
+                path,
+                &before,
+                &after,
+            );
+        }
+    }
+}
+
+fn report(
+    differences: &mut Vec<RoundTripDifference>,
+    kind: DifferenceKind,
+    path: &str,
+    before: &str,
+    after: &str,
+) {
+    differences.push(RoundTripDifference {
+        kind,
+        path: path.to_owned(),
+        before: before.to_owned(),
+        after: after.to_owned(),
+    });
+}
+
+fn last_member<'a>(members: &'a [CstMember], key: &str) -> Option<&'a CstMember> {
+    return members.iter().rev().find(|member| member.key == key);
+}
@@ -0,0 +1,73 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn sorted() -> SerializerConfig {
+    return SerializerConfig {
+        sort_keys: true,
+        ..SerializerConfig::default()
+    };
+}
+
+fn difference(kind: DifferenceKind, path: &str, before: &str, after: &str) -> RoundTripDifference {
+    return RoundTripDifference {
+        kind,
+        path: path.to_owned(),
+        before: before.to_owned(),
+        after: after.to_owned(),
+    };
+}
+
+#[test]
+fn test_lossless() {
+    for s in vec![
+        r#"{"a":[1,2.5,"x"],"b":null}"#,
+        r#"{"a": [1, true],   "b": {}}"#,
+        "[]",
+    ] {
+        println!("Checking {}", s);
+        let report = verify_roundtrip_with(s, &sorted()).unwrap();
+        assert!(report.is_lossless());
+        assert!(report.differences.is_empty());
+    }
+}
+
+#[test]
+fn test_differences() {
+    let input =
+        r#"{"b": 1.0, "a": ["\u00e9", 12345678901234567890], "c\/": 1e400, "d": 1, "d": 2}"#;
+    let report = verify_roundtrip_with(input, &sorted()).unwrap();
+    assert_eq!(
+        report.output,
+        r#"{"a":["é",1.2345678901234567e19],"b":1,"c/":null,"d":2}"#
+    );
+    assert_eq!(
+        report.differences,
+        vec![
+            difference(
+                DifferenceKind::DuplicateKey,
+                "/d",
+                "\"d\"",
+                "last value kept"
+            ),
+            difference(DifferenceKind::KeyOrder, "", "b, a, c/, d", "a, b, c/, d"),
+            difference(DifferenceKind::NumberFormat, "/b", "1.0", "1"),
+            difference(DifferenceKind::Escape, "/a/0", "\"\\u00e9\"", "\"é\""),
+            difference(
+                DifferenceKind::Precision,
+                "/a/1",
+                "12345678901234567890",
+                "1.2345678901234567e19"
+            ),
+            difference(DifferenceKind::Escape, "/c~1", "\"c\\/\"", "\"c/\""),
+            difference(DifferenceKind::Precision, "/c~1", "1e400", "null"),
+        ]
+    );
+    assert!(!report.is_lossless());
+}
+
+#[test]
+fn test_invalid_input() {
+    let err = verify_roundtrip("[1,").unwrap_err();
+    assert_eq!(err.kind, JSONParseErrorKind::Syntax);
+}
@@ -0,0 +1,68 @@
+use super::*;
+use generate::{Rng, SplitMix64};
+
+#[cfg(test)]
+mod tests;
+
+impl JSONValue {
+    /// `n` members of an object, picked uniformly at random but the same for the
+    /// same object and `seed`, whatever the map's iteration order. The members
+    /// keep their order. Other values are returned as they are.
+    pub fn sample_fields(&self, n: usize, seed: u64) -> JSONValue {
+        let members = match *self {
+            JSONValue::JSONObject(ref members) => members,
+            JSONValue::JSONRaw(_) => return self.expand_raw().sample_fields(n, seed),
+            _ => return self.clone(),
+        };
+        let mut keys: Vec<&String> = members.keys().collect();
+        keys.sort();
+        let picked: Vec<&String> = pick(keys.len(), n, seed)
+            .into_iter()
+            .map(|i| keys[i])
+            .collect();
+        let mut result = JSONMap::new();
+        for (key, member) in members.iter() {
+            if picked.contains(&key) {
+                result.insert(key.clone(), member.clone());
+            }
+        }
+        return JSONValue::JSONObject(result);
+    }
+
+    /// `n` items of an array, picked uniformly at random but the same for the
+    /// same array and `seed`. The items keep their order. Other values are
+    /// returned as they are.
+    pub fn sample_array(&self, n: usize, seed: u64) -> JSONValue {
+        match *self {
+            JSONValue::JSONArray(ref items) => {
+                let picked = pick(items.len(), n, seed);
+                return JSONValue::JSONArray(
+                    picked.into_iter().map(|i| items[i].clone()).collect(),
+                );
+            }
+            JSONValue::JSONRaw(_) => return self.expand_raw().sample_array(n, seed),
+            _ => return self.clone(),
+        }
+    }
+}
+
+// `n` of the indices below `len` in ascending order, by selection sampling
+// (Knuth's algorithm S): every index is taken with the probability of filling
+// the remaining slots from the remaining indices.
+fn pick(len: usize, n: usize, seed: u64) -> Vec<usize> {
+    if n >= len {
+        return (0..len).collect();
+    }
+    let mut rng = SplitMix64::new(seed);
+    let mut picked = Vec::with_capacity(n);
+    for i in 0..len {
+        let left = (n - picked.len()) as f64;
+        if rng.next_f64() * ((len - i) as f64) < left {
+            picked.push(i);
+            if picked.len() == n {
+                break;
+            }
+        }
+    }
+    return picked;
+}
@@ -0,0 +1,78 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_pick() {
+    for (len, n) in vec![(0, 3), (5, 0), (5, 5), (5, 9), (100, 10), (1000, 999)] {
+        println!("Checking {} of {}", n, len);
+        for seed in 0..20 {
+            let picked = pick(len, n, seed);
+            assert_eq!(picked.len(), n.min(len));
+            assert!(picked.windows(2).all(|w| w[0] < w[1]));
+            assert!(picked.iter().all(|&i| i < len));
+            assert_eq!(pick(len, n, seed), picked);
+        }
+    }
+    // every index gets picked about as often
+    let mut counts = vec![0; 10];
+    for seed in 0..10000 {
+        for i in pick(10, 3, seed) {
+            counts[i] += 1;
+        }
+    }
+    assert!(
+        counts.iter().all(|&count| (2700..3300).contains(&count)),
+        "{:?}",
+        counts
+    );
+}
+
+#[test]
+fn test_sample() {
+    let items: Vec<String> = (0..50).map(|i| i.to_string()).collect();
+    let array = parse(&format!("[{}]", items.join(",")));
+    let sample = array.sample_array(5, 42);
+    assert_eq!(sample, array.sample_array(5, 42));
+    assert_ne!(sample, array.sample_array(5, 43));
+    match sample {
+        JSONValue::JSONArray(ref items) => {
+            assert_eq!(items.len(), 5);
+            let numbers: Vec<f64> = items
+                .iter()
+                .map(|item| match **item {
+                    JSONValue::JSONNumber(n) => n,
+                    _ => panic!("Expected a number"),
+                })
+                .collect();
+            assert!(numbers.windows(2).all(|w| w[0] < w[1]));
+        }
+        _ => panic!("Expected an array"),
+    }
+    assert_eq!(array.sample_array(100, 1), array);
+
+    let members: Vec<String> = (0..50).map(|i| format!(r#""k{}": {}"#, i, i)).collect();
+    let object = parse(&format!("{{{}}}", members.join(",")));
+    let sample = object.sample_fields(3, 7);
+    // the same members whatever order the object was built in
+    let mut reversed = members.clone();
+    reversed.reverse();
+    let reversed = parse(&format!("{{{}}}", reversed.join(",")));
+    assert_eq!(reversed.sample_fields(3, 7), sample);
+    match sample {
+        JSONValue::JSONObject(ref map) => {
+            assert_eq!(map.len(), 3);
+            for (key, value) in map.iter() {
+                assert_eq!(object.pointer(&format!("/{}", key)), Some(&**value));
+            }
+        }
+        _ => panic!("Expected an object"),
+    }
+
+    assert_eq!(parse("[1]").sample_fields(1, 0), parse("[1]"));
+    assert_eq!(parse(r#""x""#).sample_array(1, 0), parse(r#""x""#));
+}
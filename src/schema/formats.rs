@@ -0,0 +1,307 @@
+// Built-in `format` validators and the registry for user-defined ones
+
+use super::*;
+use pattern::PathPattern;
+use std::fmt;
+use std::sync::Arc;
+
+const BUILTIN: [&str; 5] = ["date-time", "date", "email", "uri", "uuid"];
+
+// Canonical form of a valid string, `None` for an invalid one
+type Normalizer = Arc<dyn Fn(&str) -> Option<String> + Send + Sync>;
+
+/// Named string formats, e.g. `"phone"` or `"country-code"`, for the `format`
+/// keyword of schemas given to `Schema::with_formats` and for `validate_formats`
+/// passes over documents. A validator only accepts or rejects a string, a
+/// normalizer also returns its canonical form, used by `normalize_formats`.
+#[derive(Clone, Default)]
+pub struct FormatRegistry {
+    formats: BTreeMap<String, Normalizer>,
+}
+
+impl FormatRegistry {
+    /// An empty registry
+    pub fn new() -> FormatRegistry {
+        return FormatRegistry::default();
+    }
+
+    /// A registry with the formats OpenAPI schemas check: `date-time`, `date`,
+    /// `email`, `uri` and `uuid`
+    pub fn builtin() -> FormatRegistry {
+        let mut registry = FormatRegistry::new();
+        for &name in BUILTIN.iter() {
+            registry.register(name, move |s| check_builtin(name, s) == Some(true));
+        }
+        return registry;
+    }
+
+    /// Adds a format, replacing any registered under the same name.
+    pub fn register<F>(&mut self, name: &str, validator: F)
+    where
+        F: Fn(&str) -> bool + Send + Sync + 'static,
+    {
+        self.register_normalizer(name, move |s| {
+            if validator(s) {
+                return Some(s.to_owned());
+            }
+            return None;
+        });
+    }
+
+    /// Adds a format whose valid strings have a canonical form, e.g. phone numbers
+    /// without spaces. `normalizer` returns `None` for invalid strings.
+    pub fn register_normalizer<F>(&mut self, name: &str, normalizer: F)
+    where
+        F: Fn(&str) -> Option<String> + Send + Sync + 'static,
+    {
+        self.formats.insert(name.to_owned(), Arc::new(normalizer));
+    }
+
+    /// Names of the registered formats, sorted
+    pub fn names(&self) -> Vec<&str> {
+        return self.formats.keys().map(|name| name.as_str()).collect();
+    }
+
+    /// Checks a string against a format, `None` for unknown formats.
+    pub fn check(&self, format: &str, s: &str) -> Option<bool> {
+        return self
+            .normalize(format, s)
+            .map(|normalized| normalized.is_some());
+    }
+
+    /// Canonical form of a string, `Some(None)` if it is invalid and `None`
+    /// for unknown formats.
+    pub fn normalize(&self, format: &str, s: &str) -> Option<Option<String>> {
+        return self.formats.get(format).map(|normalizer| normalizer(s));
+    }
+
+    /// Checks the values at paths matching each pattern against its format.
+    /// Values that aren't strings and formats that aren't registered are reported too.
+    pub fn validate_formats(
+        &self,
+        document: &JSONValue,
+        rules: &[(PathPattern, &str)],
+    ) -> Vec<SchemaViolation> {
+        return self.apply(document, rules).0;
+    }
+
+    /// Like `validate_formats`, and also replaces valid strings with their canonical form.
+    pub fn normalize_formats(
+        &self,
+        document: &mut JSONValue,
+        rules: &[(PathPattern, &str)],
+    ) -> Vec<SchemaViolation> {
+        let (violations, normalized) = self.apply(document, rules);
+        for (pointer, s) in normalized {
+            if let Some(value) = document.pointer_mut(&pointer) {
+                *value = JSONValue::JSONString(s);
+            }
+        }
+        return violations;
+    }
+
+    // Violations and canonical forms of the strings that have changed, by pointer
+    fn apply(
+        &self,
+        document: &JSONValue,
+        rules: &[(PathPattern, &str)],
+    ) -> (Vec<SchemaViolation>, Vec<(String, String)>) {
+        let mut violations = vec![];
+        let mut normalized = vec![];
+        for &(ref pattern, format) in rules {
+            for (pointer, value) in pattern.find(document) {
+                let message = match (value, self.formats.get(format)) {
+                    (_, None) => format!("Unknown format {}", format),
+                    (JSONValue::JSONString(s), Some(normalizer)) => match normalizer(s) {
+                        Some(canonical) => {
+                            if canonical != *s {
+                                normalized.push((pointer, canonical));
+                            }
+                            continue;
+                        }
+                        None => format!("Expected a valid {}", format),
+                    },
+                    (value, Some(_)) => format!("Expected string, got {}", type_name(value)),
+                };
+                violations.push(SchemaViolation {
+                    path: pointer,
+                    message,
+                    position: None,
+                });
+            }
+        }
+        return (violations, normalized);
+    }
+}
+
+impl fmt::Debug for FormatRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return f.debug_set().entries(self.formats.keys()).finish();
+    }
+}
+
+impl PartialEq for FormatRegistry {
+    // Same names with the same functions behind them
+    fn eq(&self, other: &FormatRegistry) -> bool {
+        return self.formats.len() == other.formats.len()
+            && self
+                .formats
+                .iter()
+                .zip(other.formats.iter())
+                .all(|(a, b)| a.0 == b.0 && Arc::ptr_eq(a.1, b.1));
+    }
+}
+
+/// Checks a string against a known format, `None` for unknown formats.
+pub fn check_builtin(format: &str, s: &str) -> Option<bool> {
+    match format {
+        "date-time" => return Some(is_date_time(s)),
+        "date" => return Some(is_date(s)),
+        "email" => return Some(is_email(s)),
+        "uri" => return Some(is_uri(s)),
+        "uuid" => return Some(is_uuid(s)),
+        _ => return None,
+    }
+}
+
+fn digits(s: &str) -> Option<u32> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    return s.parse().ok();
+}
+
+// RFC 3339 full-date: YYYY-MM-DD
+fn is_date(s: &str) -> bool {
+    let b = s.as_bytes();
+    if b.len() != 10 || b[4] != b'-' || b[7] != b'-' {
+        return false;
+    }
+    let (year, month, day) = match (digits(&s[..4]), digits(&s[5..7]), digits(&s[8..])) {
+        (Some(year), Some(month), Some(day)) => (year, month, day),
+        _ => return false,
+    };
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    return day >= 1 && day <= days;
+}
+
+// RFC 3339 date-time: full-date "T" HH:MM:SS[.frac] ("Z" / ("+" / "-") HH:MM)
+fn is_date_time(s: &str) -> bool {
+    if s.len() < 20 || !s.is_char_boundary(10) || !is_date(&s[..10]) {
+        return false;
+    }
+    let rest = &s[10..];
+    if !rest.starts_with(['T', 't']) {
+        return false;
+    }
+    let time = &rest[1..];
+    if time.len() < 9 || !time.is_char_boundary(8) || !is_time(&time[..8], 60) {
+        return false;
+    }
+    let mut offset = &time[8..];
+    if let Some(fraction) = offset.strip_prefix('.') {
+        let end = fraction
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(fraction.len());
+        if end == 0 {
+            return false;
+        }
+        offset = &fraction[end..];
+    }
+    if offset == "Z" || offset == "z" {
+        return true;
+    }
+    return offset.len() == 6
+        && offset.starts_with(['+', '-'])
+        && offset.as_bytes()[3] == b':'
+        && digits(&offset[1..3]).is_some_and(|h| h < 24)
+        && digits(&offset[4..]).is_some_and(|m| m < 60);
+}
+
+// HH:MM:SS, seconds up to `max_second` to allow leap seconds
+fn is_time(s: &str, max_second: u32) -> bool {
+    let b = s.as_bytes();
+    if b.len() != 8 || b[2] != b':' || b[5] != b':' {
+        return false;
+    }
+    return digits(&s[..2]).is_some_and(|h| h < 24)
+        && digits(&s[3..5]).is_some_and(|m| m < 60)
+        && digits(&s[6..]).is_some_and(|sec| sec <= max_second);
+}
+
+// local-part@domain with an unquoted local part (RFC 5321 dot-string)
+fn is_email(s: &str) -> bool {
+    let (local, domain) = match s.rsplit_once('@') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let atext = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c);
+    let local_ok = !local.is_empty()
+        && local.len() <= 64
+        && local
+            .split('.')
+            .all(|part| !part.is_empty() && part.chars().all(atext));
+    return local_ok && is_hostname(domain);
+}
+
+fn is_hostname(s: &str) -> bool {
+    return !s.is_empty()
+        && s.len() <= 253
+        && s.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+}
+
+// Absolute URI (RFC 3986): scheme ":" and a rest without spaces, controls or
+// broken percent-encodings
+fn is_uri(s: &str) -> bool {
+    let (scheme, rest) = match s.split_once(':') {
+        Some(parts) => parts,
+        None => return false,
+    };
+    let scheme_ok = scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+        && scheme
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '-' || c == '.');
+    if !scheme_ok {
+        return false;
+    }
+    let bytes = rest.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = bytes.get(i + 1..i + 3);
+                if !hex.is_some_and(|hex| hex.iter().all(|b| b.is_ascii_hexdigit())) {
+                    return false;
+                }
+                i += 3;
+            }
+            b' ' | b'"' | b'<' | b'>' | b'\\' | b'^' | b'`' | b'{' | b'|' | b'}' => return false,
+            b if b >= 0x80 || b.is_ascii_control() => return false,
+            _ => i += 1,
+        }
+    }
+    return true;
+}
+
+// 8-4-4-4-12 hex digits
+fn is_uuid(s: &str) -> bool {
+    let b = s.as_bytes();
+    return b.len() == 36
+        && b.iter().enumerate().all(|(i, &c)| match i {
+            8 | 13 | 18 | 23 => c == b'-',
+            _ => c.is_ascii_hexdigit(),
+        });
+}
@@ -0,0 +1,839 @@
+use super::*;
+use pointer::escape_token;
+use serializer::SerializerConfig;
+use std::collections::{BTreeMap, BTreeSet};
+use std::mem;
+
+mod formats;
+mod streaming;
+#[cfg(test)]
+mod tests;
+mod types;
+
+use self::formats::check_builtin;
+pub use self::formats::FormatRegistry;
+pub use self::types::{schema_document, ObjectSchema, ToJSONSchema};
+
+const TYPES: [&str; 7] = [
+    "null", "boolean", "integer", "number", "string", "array", "object",
+];
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(Debug, Clone)]
+pub struct JSONSchemaError {
+    pub reason: String,
+}
+
+/// A single failed constraint. `path` is a JSON Pointer into the validated document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub message: String,
+    /// Byte offset of the offending value in the input, set by `validate_stream`
+    pub position: Option<usize>,
+}
+
+/// A checked JSON Schema. Supported keywords:
+/// `type`, `enum`, `const`, `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum`,
+/// `multipleOf`, `minLength`, `maxLength`, `items`, `minItems`, `maxItems`, `uniqueItems`,
+/// `properties`, `required`, `additionalProperties`, `minProperties`, `maxProperties`,
+/// `allOf`, `anyOf`, `oneOf`, `not` and local `$ref`s (`#/$defs/...`).
+/// Other keywords are ignored, as the specification requires.
+///
+/// Schemas compiled with `compile_openapi` follow OpenAPI 3.1 on top of that:
+/// `nullable: true` also accepts null (the 3.0 way of saying it), a `discriminator`
+/// picks the one schema to check an object against by its property, and
+/// `format` is asserted for `date-time`, `date`, `email`, `uri` and `uuid`.
+/// Formats of a registry given to `with_formats` are asserted in any schema.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schema {
+    root: JSONValue,
+    // pointer to the schema to validate against inside `root`
+    entry: String,
+    openapi: bool,
+    formats: Option<FormatRegistry>,
+}
+
+impl Schema {
+    /// Checks that every known keyword has a value of the right shape
+    /// and every `$ref` points somewhere inside the schema.
+    pub fn compile(schema: &JSONValue) -> Result<Schema, JSONSchemaError> {
+        check_schema(schema, schema, "")?;
+        return Ok(Schema {
+            root: schema.clone(),
+            entry: String::new(),
+            openapi: false,
+            formats: None,
+        });
+    }
+
+    /// Compiles the schema at `pointer` in an OpenAPI document, e.g.
+    /// `/components/schemas/Pet`, with OpenAPI validation rules.
+    /// `$ref`s resolve against the whole document.
+    pub fn compile_openapi(document: &JSONValue, pointer: &str) -> Result<Schema, JSONSchemaError> {
+        let schema = document
+            .pointer(pointer)
+            .ok_or_else(|| make_err(format!("No schema at {}", pointer)))?;
+        check_schema(schema, document, pointer)?;
+        return Ok(Schema {
+            root: document.clone(),
+            entry: pointer.to_owned(),
+            openapi: true,
+            formats: None,
+        });
+    }
+
+    /// Asserts `format` for the formats in `formats`, which take precedence over
+    /// the built-in ones of OpenAPI schemas. Other formats stay annotations.
+    pub fn with_formats(mut self, formats: FormatRegistry) -> Schema {
+        self.formats = Some(formats);
+        return self;
+    }
+
+    pub fn validate(&self, value: &JSONValue) -> Vec<SchemaViolation> {
+        let mut validator = Validator {
+            root: &self.root,
+            openapi: self.openapi,
+            formats: self.formats.as_ref(),
+            violations: vec![],
+            refs: vec![],
+        };
+        validator.validate(self.schema(), value, &mut vec![]);
+        return validator.violations;
+    }
+
+    pub fn is_valid(&self, value: &JSONValue) -> bool {
+        return self.validate(value).is_empty();
+    }
+
+    /// The schema document, the whole OpenAPI document for `compile_openapi`.
+    pub fn as_json(&self) -> &JSONValue {
+        return &self.root;
+    }
+
+    fn schema(&self) -> &JSONValue {
+        return self.root.pointer(&self.entry).expect("checked by compile");
+    }
+
+    /// Converts values in place to the types the schema expects, for input from
+    /// HTML forms and other loosely typed sources: numeric strings to numbers,
+    /// `"true"` and `"false"` to booleans, `""` and `"null"` to null, numbers and
+    /// booleans to strings, and single values to one-element arrays. Follows `type`,
+    /// `properties`, `additionalProperties`, `items`, `allOf` and `$ref`; values
+    /// that can't be converted are left for `validate` to report.
+    pub fn coerce(&self, value: &mut JSONValue) {
+        let mut coercer = Coercer {
+            root: &self.root,
+            refs: vec![],
+        };
+        coercer.coerce(self.schema(), value, &mut vec![]);
+    }
+}
+
+impl FromStr for Schema {
+    type Err = JSONSchemaError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let schema = s
+            .parse()
+            .map_err(|err: JSONParseError| make_err(format!("Invalid JSON: {}", err.reason)))?;
+        return Schema::compile(&schema);
+    }
+}
+
+fn check_schema(schema: &JSONValue, root: &JSONValue, path: &str) -> Result<(), JSONSchemaError> {
+    let map = match *schema {
+        JSONValue::JSONBool(_) => return Ok(()),
+        JSONValue::JSONObject(ref map) => map,
+        _ => {
+            return Err(make_err(format!(
+                "{}: schema must be an object or a boolean",
+                path
+            )))
+        }
+    };
+    for (keyword, value) in map {
+        let path = format!("{}/{}", path, escape_token(keyword));
+        let valid = match (keyword.as_str(), &**value) {
+            ("type", JSONValue::JSONString(name)) => TYPES.contains(&name.as_str()),
+            ("type", JSONValue::JSONArray(names)) => names.iter().all(|name| match **name {
+                JSONValue::JSONString(ref name) => TYPES.contains(&name.as_str()),
+                _ => false,
+            }),
+            ("type", _) => false,
+            ("enum", value) => matches!(value, JSONValue::JSONArray(_)),
+            ("minimum", value)
+            | ("maximum", value)
+            | ("exclusiveMinimum", value)
+            | ("exclusiveMaximum", value) => matches!(value, JSONValue::JSONNumber(_)),
+            ("multipleOf", &JSONValue::JSONNumber(n)) => n > 0.0,
+            ("multipleOf", _) => false,
+            ("minLength", value)
+            | ("maxLength", value)
+            | ("minItems", value)
+            | ("maxItems", value)
+            | ("minProperties", value)
+            | ("maxProperties", value) => as_count(value).is_some(),
+            ("uniqueItems", value) => matches!(value, JSONValue::JSONBool(_)),
+            ("required", JSONValue::JSONArray(names)) => names
+                .iter()
+                .all(|name| matches!(**name, JSONValue::JSONString(_))),
+            ("required", _) => false,
+            ("items", value) | ("additionalProperties", value) | ("not", value) => {
+                check_schema(value, root, &path)?;
+                true
+            }
+            ("properties", JSONValue::JSONObject(schemas))
+            | ("$defs", JSONValue::JSONObject(schemas))
+            | ("definitions", JSONValue::JSONObject(schemas)) => {
+                for (name, schema) in schemas {
+                    check_schema(schema, root, &format!("{}/{}", path, escape_token(name)))?;
+                }
+                true
+            }
+            ("properties", _) | ("$defs", _) | ("definitions", _) => false,
+            ("allOf", JSONValue::JSONArray(schemas))
+            | ("anyOf", JSONValue::JSONArray(schemas))
+            | ("oneOf", JSONValue::JSONArray(schemas)) => {
+                for (i, schema) in schemas.iter().enumerate() {
+                    check_schema(schema, root, &format!("{}/{}", path, i))?;
+                }
+                !schemas.is_empty()
+            }
+            ("allOf", _) | ("anyOf", _) | ("oneOf", _) => false,
+            ("format", value) => matches!(value, JSONValue::JSONString(_)),
+            ("nullable", value) => matches!(value, JSONValue::JSONBool(_)),
+            ("discriminator", JSONValue::JSONObject(discriminator)) => {
+                matches!(
+                    discriminator.get("propertyName").map(|name| &**name),
+                    Some(JSONValue::JSONString(_))
+                )
+            }
+            ("discriminator", _) => false,
+            ("$ref", JSONValue::JSONString(reference)) => resolve_ref(root, reference).is_some(),
+            ("$ref", _) => false,
+            _ => true,
+        };
+        if !valid {
+            return Err(make_err(format!("{}: invalid value for {}", path, keyword)));
+        }
+    }
+    return Ok(());
+}
+
+// Only references into the schema itself are supported
+fn resolve_ref<'a>(root: &'a JSONValue, reference: &str) -> Option<&'a JSONValue> {
+    if !reference.starts_with('#') {
+        return None;
+    }
+    return root.pointer(&reference[1..]);
+}
+
+fn as_count(value: &JSONValue) -> Option<usize> {
+    match *value {
+        JSONValue::JSONNumber(n) if n >= 0.0 && n.fract() == 0.0 => return Some(n as usize),
+        _ => return None,
+    }
+}
+
+struct Validator<'a> {
+    root: &'a JSONValue,
+    openapi: bool,
+    formats: Option<&'a FormatRegistry>,
+    violations: Vec<SchemaViolation>,
+    // `$ref`s being expanded with the instance location they were applied to,
+    // a repeat means the schema loops without consuming any input
+    refs: Vec<(&'a str, String)>,
+}
+
+impl<'a> Validator<'a> {
+    fn report(&mut self, path: &[String], message: String) {
+        self.violations.push(SchemaViolation {
+            path: pointer_of(path),
+            message,
+            position: None,
+        });
+    }
+
+    // Validates against a subschema without reporting, e.g. for `anyOf` branches
+    fn violations_of(
+        &mut self,
+        schema: &'a JSONValue,
+        value: &JSONValue,
+        path: &mut Vec<String>,
+    ) -> Vec<SchemaViolation> {
+        let saved = mem::take(&mut self.violations);
+        self.validate(schema, value, path);
+        return mem::replace(&mut self.violations, saved);
+    }
+
+    fn validate(&mut self, schema: &'a JSONValue, value: &JSONValue, path: &mut Vec<String>) {
+        if let JSONValue::JSONRaw(_) = *value {
+            return self.validate(schema, &value.expand_raw(), path);
+        }
+        let map = match *schema {
+            JSONValue::JSONObject(ref map) => map,
+            JSONValue::JSONBool(false) => {
+                return self.report(path, "No value is allowed here".to_owned());
+            }
+            _ => return,
+        };
+        let keyword = |name: &str| map.get(name).map(|v| &**v);
+
+        if let Some(JSONValue::JSONString(reference)) = keyword("$ref") {
+            self.validate_ref(reference, value, path);
+        }
+        let nullable = self.openapi && keyword("nullable") == Some(&JSONValue::JSONBool(true));
+        if let Some(expected) =
+            keyword("type").filter(|_| !(nullable && *value == JSONValue::JSONNull()))
+        {
+            let names = type_names(expected);
+            if !names.iter().any(|name| has_type(value, name)) {
+                let message = format!("Expected {}, got {}", names.join(" or "), type_name(value));
+                self.report(path, message);
+            }
+        }
+        if let Some(JSONValue::JSONArray(allowed)) = keyword("enum") {
+            if !allowed.iter().any(|item| **item == *value) {
+                self.report(path, "Value is not one of the allowed values".to_owned());
+            }
+        }
+        if let Some(expected) = keyword("const") {
+            if *expected != *value {
+                self.report(path, "Value does not match the constant".to_owned());
+            }
+        }
+        match *value {
+            JSONValue::JSONNumber(n) => self.validate_number(&keyword, n, path),
+            JSONValue::JSONString(ref s) => {
+                if let Some(JSONValue::JSONString(format)) = keyword("format") {
+                    let valid = match self.formats.and_then(|f| f.check(format, s)) {
+                        None if self.openapi => check_builtin(format, s),
+                        valid => valid,
+                    };
+                    if valid == Some(false) {
+                        self.report(path, format!("Expected a valid {}", format));
+                    }
+                }
+                let length = s.chars().count();
+                if let Some(min) = keyword("minLength").and_then(as_count) {
+                    if length < min {
+                        let message =
+                            format!("Expected at least {} characters, got {}", min, length);
+                        self.report(path, message);
+                    }
+                }
+                if let Some(max) = keyword("maxLength").and_then(as_count) {
+                    if length > max {
+                        let message =
+                            format!("Expected at most {} characters, got {}", max, length);
+                        self.report(path, message);
+                    }
+                }
+            }
+            JSONValue::JSONArray(ref items) => self.validate_array(&keyword, items, path),
+            JSONValue::JSONObject(ref members) => self.validate_object(&keyword, members, path),
+            _ => (),
+        }
+        if let Some(JSONValue::JSONArray(schemas)) = keyword("allOf") {
+            for schema in schemas {
+                self.validate(schema, value, path);
+            }
+        }
+        let discriminator = match (keyword("discriminator"), value) {
+            (Some(JSONValue::JSONObject(discriminator)), JSONValue::JSONObject(members))
+                if self.openapi =>
+            {
+                self.validate_discriminated(discriminator, members, value, path);
+                true
+            }
+            _ => false,
+        };
+        if let Some(JSONValue::JSONArray(schemas)) = keyword("anyOf").filter(|_| !discriminator) {
+            if !schemas
+                .iter()
+                .any(|schema| self.violations_of(schema, value, path).is_empty())
+            {
+                self.report(
+                    path,
+                    "Value does not match any of the schemas in anyOf".to_owned(),
+                );
+            }
+        }
+        if let Some(JSONValue::JSONArray(schemas)) = keyword("oneOf").filter(|_| !discriminator) {
+            let matching = schemas
+                .iter()
+                .filter(|schema| self.violations_of(schema, value, path).is_empty())
+                .count();
+            if matching != 1 {
+                let message = format!(
+                    "Expected exactly one schema in oneOf to match, {} did",
+                    matching
+                );
+                self.report(path, message);
+            }
+        }
+        if let Some(schema) = keyword("not") {
+            if self.violations_of(schema, value, path).is_empty() {
+                self.report(path, "Value must not match the schema in not".to_owned());
+            }
+        }
+    }
+
+    // OpenAPI discriminator: the property value names the schema through `mapping`
+    // or as a schema under `#/components/schemas/`, replacing `oneOf`/`anyOf`
+    fn validate_discriminated(
+        &mut self,
+        discriminator: &'a JSONMap,
+        members: &JSONMap,
+        value: &JSONValue,
+        path: &mut Vec<String>,
+    ) {
+        let property = match discriminator.get("propertyName").map(|name| &**name) {
+            Some(JSONValue::JSONString(property)) => property,
+            _ => return,
+        };
+        let name = match members.get(property).map(|name| &**name) {
+            Some(JSONValue::JSONString(name)) => name,
+            Some(_) => {
+                return self.report(path, format!("Discriminator {} must be a string", property));
+            }
+            None => {
+                return self.report(path, format!("Missing discriminator property {}", property));
+            }
+        };
+        let mapped = match discriminator.get("mapping").map(|mapping| &**mapping) {
+            Some(JSONValue::JSONObject(mapping)) => match mapping.get(name).map(|r| &**r) {
+                Some(JSONValue::JSONString(reference)) => Some(reference.as_str()),
+                _ => None,
+            },
+            _ => None,
+        };
+        if let Some(reference) = mapped {
+            return self.validate_ref(reference, value, path);
+        }
+        let root = self.root;
+        let implicit = format!("#/components/schemas/{}", escape_token(name));
+        match resolve_ref(root, &implicit) {
+            Some(schema) => self.validate(schema, value, path),
+            None => self.report(path, format!("Unknown {} {}", property, name)),
+        }
+    }
+
+    fn validate_ref(&mut self, reference: &'a str, value: &JSONValue, path: &mut Vec<String>) {
+        let key = (reference, pointer_of(path));
+        if self.refs.contains(&key) {
+            return self.report(path, format!("Circular $ref {}", reference));
+        }
+        let root = self.root;
+        match resolve_ref(root, reference) {
+            Some(schema) => {
+                self.refs.push(key);
+                self.validate(schema, value, path);
+                self.refs.pop();
+            }
+            None => self.report(path, format!("Unresolvable $ref {}", reference)),
+        }
+    }
+
+    fn validate_number<F>(&mut self, keyword: &F, n: f64, path: &[String])
+    where
+        F: Fn(&str) -> Option<&'a JSONValue>,
+    {
+        let bound = |name: &str| match keyword(name) {
+            Some(&JSONValue::JSONNumber(bound)) => Some(bound),
+            _ => None,
+        };
+        if let Some(min) = bound("minimum") {
+            if n < min {
+                self.report(path, format!("Expected at least {}, got {}", min, n));
+            }
+        }
+        if let Some(max) = bound("maximum") {
+            if n > max {
+                self.report(path, format!("Expected at most {}, got {}", max, n));
+            }
+        }
+        if let Some(min) = bound("exclusiveMinimum") {
+            if n <= min {
+                self.report(path, format!("Expected more than {}, got {}", min, n));
+            }
+        }
+        if let Some(max) = bound("exclusiveMaximum") {
+            if n >= max {
+                self.report(path, format!("Expected less than {}, got {}", max, n));
+            }
+        }
+        // The quotient of decimals like 0.07 / 0.01 is only close to whole
+        if let Some(factor) = bound("multipleOf") {
+            let q = n / factor;
+            if factor > 0.0 && (q - q.round()).abs() > q.abs() * f64::EPSILON * 4.0 {
+                self.report(
+                    path,
+                    format!("Expected a multiple of {}, got {}", factor, n),
+                );
+            }
+        }
+    }
+
+    // `minItems`/`maxItems` or `minProperties`/`maxProperties`, by `noun`
+    fn validate_count<F>(&mut self, keyword: &F, noun: &str, count: usize, path: &[String])
+    where
+        F: Fn(&str) -> Option<&'a JSONValue>,
+    {
+        let lower = noun.to_lowercase();
+        if let Some(min) = keyword(&format!("min{}", noun)).and_then(as_count) {
+            if count < min {
+                let message = format!("Expected at least {} {}, got {}", min, lower, count);
+                self.report(path, message);
+            }
+        }
+        if let Some(max) = keyword(&format!("max{}", noun)).and_then(as_count) {
+            if count > max {
+                let message = format!("Expected at most {} {}, got {}", max, lower, count);
+                self.report(path, message);
+            }
+        }
+    }
+
+    fn validate_array<F>(&mut self, keyword: &F, items: &[Box<JSONValue>], path: &mut Vec<String>)
+    where
+        F: Fn(&str) -> Option<&'a JSONValue>,
+    {
+        self.validate_count(keyword, "Items", items.len(), path);
+        if let Some(&JSONValue::JSONBool(true)) = keyword("uniqueItems") {
+            for i in 1..items.len() {
+                if items[..i].contains(&items[i]) {
+                    path.push(i.to_string());
+                    self.report(path, "Duplicate array item".to_owned());
+                    path.pop();
+                }
+            }
+        }
+        if let Some(schema) = keyword("items") {
+            for (i, item) in items.iter().enumerate() {
+                path.push(i.to_string());
+                self.validate(schema, item, path);
+                path.pop();
+            }
+        }
+    }
+
+    fn validate_object<F>(&mut self, keyword: &F, members: &JSONMap, path: &mut Vec<String>)
+    where
+        F: Fn(&str) -> Option<&'a JSONValue>,
+    {
+        self.validate_count(keyword, "Properties", members.len(), path);
+        if let Some(JSONValue::JSONArray(required)) = keyword("required") {
+            for name in required {
+                if let JSONValue::JSONString(ref name) = **name {
+                    if !members.contains_key(name) {
+                        self.report(path, format!("Missing required property {}", name));
+                    }
+                }
+            }
+        }
+        let properties = match keyword("properties") {
+            Some(JSONValue::JSONObject(properties)) => Some(properties),
+            _ => None,
+        };
+        let additional = keyword("additionalProperties");
+        // sorted, so violations come out in a stable order
+        let mut names: Vec<&String> = members.keys().collect();
+        names.sort();
+        for name in names {
+            let schema = match properties.and_then(|p| p.get(name)) {
+                Some(schema) => &**schema,
+                None => match additional {
+                    Some(schema) => schema,
+                    None => continue,
+                },
+            };
+            path.push(name.clone());
+            if let JSONValue::JSONBool(false) = *schema {
+                self.report(path, format!("Unexpected property {}", name));
+            } else {
+                self.validate(schema, &members[name], path);
+            }
+            path.pop();
+        }
+    }
+}
+
+struct Coercer<'a> {
+    root: &'a JSONValue,
+    // same loop guard as in `Validator`
+    refs: Vec<(&'a str, String)>,
+}
+
+impl<'a> Coercer<'a> {
+    fn coerce(&mut self, schema: &'a JSONValue, value: &mut JSONValue, path: &mut Vec<String>) {
+        if let JSONValue::JSONRaw(_) = *value {
+            *value = value.expand_raw().into_owned();
+        }
+        let map = match *schema {
+            JSONValue::JSONObject(ref map) => map,
+            _ => return,
+        };
+        let keyword = |name: &str| map.get(name).map(|v| &**v);
+
+        if let Some(JSONValue::JSONString(reference)) = keyword("$ref") {
+            let key = (reference.as_str(), pointer_of(path));
+            if !self.refs.contains(&key) {
+                if let Some(schema) = resolve_ref(self.root, reference) {
+                    self.refs.push(key);
+                    self.coerce(schema, value, path);
+                    self.refs.pop();
+                }
+            }
+        }
+        if let Some(JSONValue::JSONArray(schemas)) = keyword("allOf") {
+            for schema in schemas {
+                self.coerce(schema, value, path);
+            }
+        }
+        match keyword("type") {
+            Some(JSONValue::JSONString(name)) => coerce_to(value, &[name.as_str()]),
+            Some(JSONValue::JSONArray(names)) => {
+                let names: Vec<&str> = names
+                    .iter()
+                    .filter_map(|name| match **name {
+                        JSONValue::JSONString(ref name) => Some(name.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                coerce_to(value, &names);
+            }
+            _ => (),
+        }
+        match *value {
+            JSONValue::JSONArray(ref mut items) => {
+                if let Some(schema) = keyword("items") {
+                    for (i, item) in items.iter_mut().enumerate() {
+                        path.push(i.to_string());
+                        self.coerce(schema, item, path);
+                        path.pop();
+                    }
+                }
+            }
+            JSONValue::JSONObject(ref mut members) => {
+                let properties = match keyword("properties") {
+                    Some(JSONValue::JSONObject(properties)) => Some(properties),
+                    _ => None,
+                };
+                for (name, member) in members.iter_mut() {
+                    let schema = match properties.and_then(|p| p.get(name)) {
+                        Some(schema) => &**schema,
+                        None => match keyword("additionalProperties") {
+                            Some(schema) => schema,
+                            None => continue,
+                        },
+                    };
+                    path.push(name.clone());
+                    self.coerce(schema, member, path);
+                    path.pop();
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+// Converts to the first of the types the value can take, unless it has one already
+fn coerce_to(value: &mut JSONValue, names: &[&str]) {
+    if names.iter().any(|name| has_type(value, name)) {
+        return;
+    }
+    for name in names {
+        let coerced = match (*name, &*value) {
+            ("integer", JSONValue::JSONString(s)) | ("number", JSONValue::JSONString(s)) => {
+                match s.trim().parse::<JSONValue>() {
+                    Ok(JSONValue::JSONNumber(n)) if *name == "number" || n.fract() == 0.0 => {
+                        Some(JSONValue::JSONNumber(n))
+                    }
+                    _ => None,
+                }
+            }
+            ("boolean", JSONValue::JSONString(s)) => match s.trim() {
+                "true" => Some(JSONValue::JSONBool(true)),
+                "false" => Some(JSONValue::JSONBool(false)),
+                _ => None,
+            },
+            ("null", JSONValue::JSONString(s)) if s.is_empty() || s == "null" => {
+                Some(JSONValue::JSONNull())
+            }
+            ("string", JSONValue::JSONNumber(_)) | ("string", JSONValue::JSONBool(_)) => Some(
+                JSONValue::JSONString(value.to_string_with(&SerializerConfig::compact())),
+            ),
+            ("array", _) => Some(JSONValue::JSONArray(vec![Box::new(value.clone())])),
+            _ => None,
+        };
+        if let Some(coerced) = coerced {
+            *value = coerced;
+            return;
+        }
+    }
+}
+
+// Type names listed by a `type` keyword
+fn type_names(expected: &JSONValue) -> Vec<&str> {
+    match *expected {
+        JSONValue::JSONString(ref name) => return vec![name.as_str()],
+        JSONValue::JSONArray(ref names) => {
+            return names
+                .iter()
+                .filter_map(|name| match **name {
+                    JSONValue::JSONString(ref name) => Some(name.as_str()),
+                    _ => None,
+                })
+                .collect()
+        }
+        _ => return vec![],
+    }
+}
+
+fn has_type(value: &JSONValue, name: &str) -> bool {
+    match (name, value) {
+        ("integer", &JSONValue::JSONNumber(n)) => return n.fract() == 0.0,
+        _ => {
+            return type_name(value) == name || (name == "number" && type_name(value) == "integer")
+        }
+    }
+}
+
+// The most specific schema type of a value
+fn type_name(value: &JSONValue) -> &'static str {
+    match *value {
+        JSONValue::JSONNull() => return "null",
+        JSONValue::JSONBool(_) => return "boolean",
+        JSONValue::JSONNumber(n) if n.fract() == 0.0 => return "integer",
+        JSONValue::JSONNumber(_) => return "number",
+        JSONValue::JSONString(_) => return "string",
+        JSONValue::JSONArray(_) => return "array",
+        JSONValue::JSONObject(_) => return "object",
+        JSONValue::JSONRaw(_) => return "raw JSON",
+    }
+}
+
+fn pointer_of(path: &[String]) -> String {
+    return path
+        .iter()
+        .map(|token| format!("/{}", escape_token(token)))
+        .collect();
+}
+
+// Everything seen at one location across all samples
+#[derive(Default)]
+struct Shape {
+    samples: usize,
+    types: BTreeSet<&'static str>,
+    objects: usize,
+    properties: BTreeMap<String, Shape>,
+    items: Option<Box<Shape>>,
+}
+
+impl Shape {
+    fn add(&mut self, value: &JSONValue) {
+        if let JSONValue::JSONRaw(_) = *value {
+            return self.add(&value.expand_raw());
+        }
+        self.samples += 1;
+        self.types.insert(type_name(value));
+        match *value {
+            JSONValue::JSONObject(ref members) => {
+                self.objects += 1;
+                for (name, member) in members {
+                    self.properties.entry(name.clone()).or_default().add(member);
+                }
+            }
+            JSONValue::JSONArray(ref items) => {
+                let shape = self.items.get_or_insert_with(Default::default);
+                for item in items {
+                    shape.add(item);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn to_schema(&self) -> JSONMap {
+        let mut schema = JSONMap::new();
+        let mut types: Vec<&str> = TYPES
+            .iter()
+            .cloned()
+            .filter(|name| self.types.contains(name))
+            .collect();
+        if types.contains(&"number") {
+            types.retain(|name| *name != "integer");
+        }
+        match types.len() {
+            0 => (),
+            1 => {
+                schema.insert("type".to_owned(), Box::new(string(types[0])));
+            }
+            _ => {
+                let types = types
+                    .into_iter()
+                    .map(|name| Box::new(string(name)))
+                    .collect();
+                schema.insert("type".to_owned(), Box::new(JSONValue::JSONArray(types)));
+            }
+        }
+        if self.objects > 0 {
+            let mut properties = JSONMap::new();
+            let mut required = vec![];
+            for (name, shape) in &self.properties {
+                properties.insert(
+                    name.clone(),
+                    Box::new(JSONValue::JSONObject(shape.to_schema())),
+                );
+                // a key occurs at most once per object, so this means "present in all of them"
+                if shape.samples == self.objects {
+                    required.push(Box::new(string(name)));
+                }
+            }
+            schema.insert(
+                "properties".to_owned(),
+                Box::new(JSONValue::JSONObject(properties)),
+            );
+            if !required.is_empty() {
+                schema.insert(
+                    "required".to_owned(),
+                    Box::new(JSONValue::JSONArray(required)),
+                );
+            }
+        }
+        if let Some(ref items) = self.items {
+            if items.samples > 0 {
+                schema.insert(
+                    "items".to_owned(),
+                    Box::new(JSONValue::JSONObject(items.to_schema())),
+                );
+            }
+        }
+        return schema;
+    }
+}
+
+fn string(s: &str) -> JSONValue {
+    return JSONValue::JSONString(s.to_owned());
+}
+
+/// Infers the narrowest schema every sample satisfies: types are unioned,
+/// properties present in every object become `required`, array items share one schema.
+pub fn infer_schema<'a, I>(samples: I) -> JSONValue
+where
+    I: IntoIterator<Item = &'a JSONValue>,
+{
+    let mut shape = Shape::default();
+    for sample in samples {
+        shape.add(sample);
+    }
+    let mut schema = shape.to_schema();
+    schema.insert("$schema".to_owned(), Box::new(string(DRAFT)));
+    return JSONValue::JSONObject(schema);
+}
+
+fn make_err(s: String) -> JSONSchemaError {
+    JSONSchemaError { reason: s }
+}
@@ -0,0 +1,261 @@
+use super::*;
+use std::io::Read;
+use stream::{Event, EventReader};
+
+// Keywords that need the whole value at hand, which is then built in memory
+const WHOLE_VALUE: [&str; 7] = [
+    "enum",
+    "const",
+    "uniqueItems",
+    "anyOf",
+    "oneOf",
+    "not",
+    "discriminator",
+];
+
+impl Schema {
+    /// Validates the document read by `events` without building it in memory, so
+    /// inputs larger than memory can be checked. Arrays and objects are walked
+    /// event by event; only scalars and values under keywords that need the whole
+    /// value (`enum`, `const`, `uniqueItems`, `anyOf`, `oneOf`, `not` and the
+    /// OpenAPI `discriminator`) are built, one at a time. Finds the same violations
+    /// as `validate`, in document order, each with the byte offset of the value it
+    /// was found in. Stops at the first parse error.
+    pub fn validate_stream<R: Read>(
+        &self,
+        events: &mut EventReader<R>,
+    ) -> Result<Vec<SchemaViolation>, JSONParseError> {
+        let mut walker = Walker {
+            validator: Validator {
+                root: &self.root,
+                openapi: self.openapi,
+                formats: self.formats.as_ref(),
+                violations: vec![],
+                refs: vec![],
+            },
+            events,
+            path: vec![],
+        };
+        while let Some(first) = walker.events.next_event()? {
+            walker.value(vec![self.schema()], first)?;
+        }
+        return Ok(walker.validator.violations);
+    }
+}
+
+struct Walker<'a, 'r, R: 'r> {
+    validator: Validator<'a>,
+    events: &'r mut EventReader<R>,
+    path: Vec<String>,
+}
+
+impl<'a, 'r, R: Read> Walker<'a, 'r, R> {
+    // Validates the value starting with `first` against all of `schemas`
+    fn value(&mut self, schemas: Vec<&'a JSONValue>, first: Event) -> Result<(), JSONParseError> {
+        if schemas.is_empty() {
+            return self.events.skip_value(&first);
+        }
+        let position = self.events.event_position();
+        let start = self.validator.violations.len();
+        let mut flat = vec![];
+        let streamed = match first {
+            Event::StartArray | Event::StartObject => {
+                self.flatten(&schemas, &mut vec![], &mut flat)
+            }
+            _ => false,
+        };
+        if streamed {
+            self.check_type(&flat, &first);
+            if first == Event::StartArray {
+                self.array(&flat)?;
+            } else {
+                self.object(&flat)?;
+            }
+        } else {
+            let value = self.events.read_value(first)?;
+            for schema in schemas {
+                self.validator.validate(schema, &value, &mut self.path);
+            }
+        }
+        for violation in &mut self.validator.violations[start..] {
+            violation.position = violation.position.or(Some(position));
+        }
+        return Ok(());
+    }
+
+    // Collects `schemas` with their `$ref`s and `allOf`s expanded into `flat`.
+    // Returns false if the value has to be built instead: for keywords in
+    // `WHOLE_VALUE` and for broken or circular `$ref`s, left for `validate` to report.
+    fn flatten(
+        &self,
+        schemas: &[&'a JSONValue],
+        refs: &mut Vec<&'a str>,
+        flat: &mut Vec<&'a JSONValue>,
+    ) -> bool {
+        for &schema in schemas {
+            flat.push(schema);
+            let map = match *schema {
+                JSONValue::JSONObject(ref map) => map,
+                _ => continue,
+            };
+            if WHOLE_VALUE.iter().any(|&name| map.contains_key(name)) {
+                return false;
+            }
+            if let Some(JSONValue::JSONString(reference)) = map.get("$ref").map(|r| &**r) {
+                if refs.contains(&reference.as_str()) {
+                    return false;
+                }
+                let target = match resolve_ref(self.validator.root, reference) {
+                    Some(target) => target,
+                    None => return false,
+                };
+                refs.push(reference);
+                let expanded = self.flatten(&[target], refs, flat);
+                refs.pop();
+                if !expanded {
+                    return false;
+                }
+            }
+            if let Some(JSONValue::JSONArray(all)) = map.get("allOf").map(|all| &**all) {
+                let all: Vec<&JSONValue> = all.iter().map(|schema| &**schema).collect();
+                if !self.flatten(&all, refs, flat) {
+                    return false;
+                }
+            }
+        }
+        return true;
+    }
+
+    fn check_type(&mut self, flat: &[&'a JSONValue], first: &Event) {
+        let kind = if *first == Event::StartArray {
+            "array"
+        } else {
+            "object"
+        };
+        for &schema in flat {
+            let expected = match *schema {
+                JSONValue::JSONBool(false) => {
+                    let message = "No value is allowed here".to_owned();
+                    self.validator.report(&self.path, message);
+                    continue;
+                }
+                JSONValue::JSONObject(ref map) => match map.get("type") {
+                    Some(expected) => expected,
+                    None => continue,
+                },
+                _ => continue,
+            };
+            let names = type_names(expected);
+            if !names.contains(&kind) {
+                let message = format!("Expected {}, got {}", names.join(" or "), kind);
+                self.validator.report(&self.path, message);
+            }
+        }
+    }
+
+    fn array(&mut self, flat: &[&'a JSONValue]) -> Result<(), JSONParseError> {
+        let items: Vec<&'a JSONValue> = flat
+            .iter()
+            .filter_map(|schema| keyword_of(schema, "items"))
+            .collect();
+        let mut count = 0;
+        loop {
+            match self.next()? {
+                Event::EndArray => break,
+                event => {
+                    self.path.push(count.to_string());
+                    self.value(items.clone(), event)?;
+                    self.path.pop();
+                    count += 1;
+                }
+            }
+        }
+        for &schema in flat {
+            let keyword = |name: &str| keyword_of(schema, name);
+            self.validator
+                .validate_count(&keyword, "Items", count, &self.path);
+        }
+        return Ok(());
+    }
+
+    fn object(&mut self, flat: &[&'a JSONValue]) -> Result<(), JSONParseError> {
+        let mut required: Vec<&'a str> = vec![];
+        for &schema in flat {
+            if let Some(JSONValue::JSONArray(names)) = keyword_of(schema, "required") {
+                for name in names {
+                    if let JSONValue::JSONString(ref name) = **name {
+                        required.push(name);
+                    }
+                }
+            }
+        }
+        // only the required names are remembered, not every key
+        let mut seen = BTreeSet::new();
+        let mut count = 0;
+        while let Event::Key(name) = self.next()? {
+            // `Unexpected property` points at the key
+            let position = self.events.event_position();
+            let start = self.validator.violations.len();
+            if required.contains(&name.as_str()) {
+                seen.insert(name.clone());
+            }
+            self.path.push(name);
+            let mut schemas = vec![];
+            for &schema in flat {
+                let name = self.path.last().unwrap();
+                let properties = match keyword_of(schema, "properties") {
+                    Some(JSONValue::JSONObject(properties)) => Some(properties),
+                    _ => None,
+                };
+                let member = match properties.and_then(|p| p.get(name)) {
+                    Some(member) => &**member,
+                    None => match keyword_of(schema, "additionalProperties") {
+                        Some(member) => member,
+                        None => continue,
+                    },
+                };
+                if let JSONValue::JSONBool(false) = *member {
+                    let message = format!("Unexpected property {}", name);
+                    self.validator.report(&self.path, message);
+                } else {
+                    schemas.push(member);
+                }
+            }
+            let event = self.next()?;
+            self.value(schemas, event)?;
+            for violation in &mut self.validator.violations[start..] {
+                violation.position = violation.position.or(Some(position));
+            }
+            self.path.pop();
+            count += 1;
+        }
+        for &schema in flat {
+            let keyword = |name: &str| keyword_of(schema, name);
+            self.validator
+                .validate_count(&keyword, "Properties", count, &self.path);
+            if let Some(JSONValue::JSONArray(names)) = keyword("required") {
+                for name in names {
+                    if let JSONValue::JSONString(ref name) = **name {
+                        if !seen.contains(name) {
+                            let message = format!("Missing required property {}", name);
+                            self.validator.report(&self.path, message);
+                        }
+                    }
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    fn next(&mut self) -> Result<Event, JSONParseError> {
+        // the reader fails on input ending inside a value, so there's always one
+        return Ok(self.events.next_event()?.expect("value is incomplete"));
+    }
+}
+
+fn keyword_of<'a>(schema: &'a JSONValue, name: &str) -> Option<&'a JSONValue> {
+    match *schema {
+        JSONValue::JSONObject(ref map) => return map.get(name).map(|value| &**value),
+        _ => return None,
+    }
+}
@@ -0,0 +1,641 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use pattern::PathPattern;
+use stream::EventReader;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+fn violations(schema: &str, value: &str) -> Vec<(String, String)> {
+    let schema: Schema = schema.parse().unwrap();
+    return schema
+        .validate(&parse(value))
+        .into_iter()
+        .map(|v| (v.path, v.message))
+        .collect();
+}
+
+#[test]
+fn test_valid_documents() {
+    for s in vec![
+        ("true", "[1, {}]"),
+        ("{}", "null"),
+        (r#"{"type": "integer"}"#, "3"),
+        (r#"{"type": "number"}"#, "3"),
+        (r#"{"type": ["string", "null"]}"#, "null"),
+        (r#"{"enum": [1, "a", [true]]}"#, "[true]"),
+        (r#"{"const": {"a": 1}}"#, r#"{"a": 1}"#),
+        (r#"{"minimum": 1, "exclusiveMaximum": 2}"#, "1.5"),
+        (r#"{"multipleOf": 0.5}"#, "2.5"),
+        (r#"{"multipleOf": 0.01}"#, "0.07"),
+        (r#"{"multipleOf": 0.01}"#, "19.99"),
+        (r#"{"multipleOf": 0.01}"#, "1234567.89"),
+        (r#"{"multipleOf": 0.1}"#, "0.3"),
+        (r#"{"minLength": 2, "maxLength": 2}"#, "\"é!\""),
+        (
+            r#"{"items": {"type": "string"}, "maxItems": 2}"#,
+            "[\"a\", \"b\"]",
+        ),
+        (r#"{"uniqueItems": true}"#, "[1, [1], {\"a\": 1}]"),
+        (
+            r#"{"properties": {"a": {"type": "string"}}, "required": ["a"], "additionalProperties": false}"#,
+            r#"{"a": "x"}"#,
+        ),
+        (r#"{"anyOf": [{"type": "string"}, {"minimum": 5}]}"#, "7"),
+        (
+            r#"{"oneOf": [{"type": "string"}, {"type": "integer"}]}"#,
+            "\"x\"",
+        ),
+        (r#"{"not": {"type": "null"}}"#, "0"),
+        (
+            r##"{"$defs": {"id": {"type": "integer"}}, "items": {"$ref": "#/$defs/id"}}"##,
+            "[1, 2]",
+        ),
+        (
+            r##"{"properties": {"next": {"$ref": "#"}}, "type": "object"}"##,
+            r#"{"next": {"next": {}}}"#,
+        ),
+    ] {
+        assert_eq!(violations(s.0, s.1), vec![], "{} should match {}", s.1, s.0);
+    }
+}
+
+#[test]
+fn test_violations() {
+    for s in vec![
+        ("false", "1", vec![("", "No value is allowed here")]),
+        (
+            r#"{"type": "integer"}"#,
+            "1.5",
+            vec![("", "Expected integer, got number")],
+        ),
+        (
+            r#"{"type": ["string", "null"]}"#,
+            "{}",
+            vec![("", "Expected string or null, got object")],
+        ),
+        (
+            r#"{"enum": [1, 2]}"#,
+            "3",
+            vec![("", "Value is not one of the allowed values")],
+        ),
+        (
+            r#"{"maximum": 2}"#,
+            "3",
+            vec![("", "Expected at most 2, got 3")],
+        ),
+        (
+            r#"{"exclusiveMinimum": 2}"#,
+            "2",
+            vec![("", "Expected more than 2, got 2")],
+        ),
+        (
+            r#"{"multipleOf": 2}"#,
+            "3",
+            vec![("", "Expected a multiple of 2, got 3")],
+        ),
+        (
+            r#"{"multipleOf": 0.01}"#,
+            "19.995",
+            vec![("", "Expected a multiple of 0.01, got 19.995")],
+        ),
+        (
+            r#"{"minLength": 2}"#,
+            "\"a\"",
+            vec![("", "Expected at least 2 characters, got 1")],
+        ),
+        (
+            r#"{"items": {"type": "string"}}"#,
+            "[\"a\", 1]",
+            vec![("/1", "Expected string, got integer")],
+        ),
+        (
+            r#"{"uniqueItems": true}"#,
+            "[1, 2, 1]",
+            vec![("/2", "Duplicate array item")],
+        ),
+        (
+            r#"{"properties": {"a/b": {"type": "string"}}, "required": ["c"]}"#,
+            r#"{"a/b": 1}"#,
+            vec![
+                ("", "Missing required property c"),
+                ("/a~1b", "Expected string, got integer"),
+            ],
+        ),
+        (
+            r#"{"properties": {"a": true}, "additionalProperties": false}"#,
+            r#"{"a": 1, "b": 2}"#,
+            vec![("/b", "Unexpected property b")],
+        ),
+        (
+            r#"{"additionalProperties": {"type": "integer"}}"#,
+            r#"{"a": "x"}"#,
+            vec![("/a", "Expected integer, got string")],
+        ),
+        (
+            r#"{"anyOf": [{"type": "string"}, {"minimum": 5}]}"#,
+            "3",
+            vec![("", "Value does not match any of the schemas in anyOf")],
+        ),
+        (
+            r#"{"oneOf": [{"type": "integer"}, {"minimum": 5}]}"#,
+            "7",
+            vec![("", "Expected exactly one schema in oneOf to match, 2 did")],
+        ),
+        (
+            r#"{"allOf": [{"type": "integer"}, {"minimum": 5}]}"#,
+            "1.5",
+            vec![
+                ("", "Expected integer, got number"),
+                ("", "Expected at least 5, got 1.5"),
+            ],
+        ),
+        (
+            r#"{"not": {"type": "null"}}"#,
+            "null",
+            vec![("", "Value must not match the schema in not")],
+        ),
+        (
+            r##"{"$defs": {"a": {"$ref": "#/$defs/b"}, "b": {"$ref": "#/$defs/a"}}, "$ref": "#/$defs/a"}"##,
+            "1",
+            vec![("", "Circular $ref #/$defs/a")],
+        ),
+    ] {
+        let expected: Vec<(String, String)> =
+            s.2.into_iter()
+                .map(|(path, message)| (path.to_owned(), message.to_owned()))
+                .collect();
+        assert_eq!(
+            violations(s.0, s.1),
+            expected,
+            "Validating {} against {}",
+            s.1,
+            s.0
+        );
+    }
+}
+
+#[test]
+fn test_invalid_schemas() {
+    for s in vec![
+        "1",
+        r#"{"type": "float"}"#,
+        r#"{"type": ["string", 1]}"#,
+        r#"{"minLength": -1}"#,
+        r#"{"multipleOf": 0}"#,
+        r#"{"required": "a"}"#,
+        r#"{"properties": {"a": 1}}"#,
+        r#"{"anyOf": []}"#,
+        r#"{"items": {"not": "x"}}"#,
+        r##"{"$ref": "#/missing"}"##,
+        r#"{"$ref": "other.json"}"#,
+    ] {
+        s.parse::<Schema>()
+            .expect_err(&format!("{} should not compile", s));
+    }
+}
+
+#[test]
+fn test_infer_schema() {
+    let samples = vec![
+        parse(r#"{"id": 1, "tags": ["a"], "score": 1, "extra": null}"#),
+        parse(r#"{"id": 2, "tags": [], "score": 1.5}"#),
+    ];
+    let schema = infer_schema(&samples);
+    assert_eq!(
+        schema,
+        parse(
+            r#"{
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "tags": {"type": "array", "items": {"type": "string"}},
+                    "score": {"type": "number"},
+                    "extra": {"type": "null"}
+                },
+                "required": ["id", "score", "tags"]
+            }"#
+        )
+    );
+    let compiled = Schema::compile(&schema).unwrap();
+    for sample in &samples {
+        assert!(compiled.is_valid(sample));
+    }
+    assert_eq!(
+        infer_schema(&[parse("1"), parse("\"a\"")]),
+        parse(
+            r#"{"$schema": "https://json-schema.org/draft/2020-12/schema", "type": ["integer", "string"]}"#
+        )
+    );
+}
+
+#[test]
+fn test_coerce() {
+    let schema: Schema = r##"{
+        "properties": {
+            "age": {"type": "integer"},
+            "price": {"type": "number"},
+            "agree": {"type": "boolean"},
+            "note": {"type": ["null", "integer"]},
+            "zip": {"type": "string"},
+            "tags": {"type": "array", "items": {"type": "integer"}},
+            "nested": {"$ref": "#/$defs/point"},
+            "either": {"type": ["boolean", "number"]}
+        },
+        "additionalProperties": {"type": "number"},
+        "$defs": {"point": {"properties": {"x": {"type": "number"}}}}
+    }"##
+    .parse()
+    .unwrap();
+    for s in vec![
+        (
+            r#"{"age": "42", "price": " 9.5 ", "agree": "true", "note": "", "zip": 1234}"#,
+            r#"{"age": 42, "price": 9.5, "agree": true, "note": null, "zip": "1234"}"#,
+        ),
+        (
+            r#"{"tags": "7", "nested": {"x": "-1e3"}, "extra": "3", "either": "1"}"#,
+            r#"{"tags": [7], "nested": {"x": -1000}, "extra": 3, "either": 1}"#,
+        ),
+        (
+            r#"{"tags": ["1", "2"], "note": "5", "zip": true, "either": "false"}"#,
+            r#"{"tags": [1, 2], "note": 5, "zip": "true", "either": false}"#,
+        ),
+        // left for validation to report
+        (
+            r#"{"age": "4.5", "price": "abc", "agree": "yes", "zip": null}"#,
+            r#"{"age": "4.5", "price": "abc", "agree": "yes", "zip": null}"#,
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        let mut value = parse(s.0);
+        schema.coerce(&mut value);
+        assert_eq!(value, parse(s.1));
+    }
+    let mut value = parse(r#"{"age": "4.5"}"#);
+    schema.coerce(&mut value);
+    assert!(!schema.is_valid(&value));
+}
+
+#[test]
+fn test_formats() {
+    for s in vec![
+        ("date-time", "2024-02-29T23:59:60Z", true),
+        ("date-time", "2024-01-01t00:00:00.123+05:30", true),
+        ("date-time", "2023-02-29T00:00:00Z", false),
+        ("date-time", "2024-01-01T24:00:00Z", false),
+        ("date-time", "2024-01-01T00:00:00", false),
+        ("date-time", "2024-01-01T00:00:00.Z", false),
+        ("date-time", "2024-01-01 00:00:00Z", false),
+        ("date", "2000-02-29", true),
+        ("date", "1900-02-29", false),
+        ("date", "2024-13-01", false),
+        ("email", "first.last+tag@mail.example.com", true),
+        ("email", "a@localhost", true),
+        ("email", "no-at.example.com", false),
+        ("email", "a..b@example.com", false),
+        ("email", "a@-example.com", false),
+        ("email", "a b@example.com", false),
+        ("uri", "https://example.com/a%20b?q=1#top", true),
+        ("uri", "urn:isbn:0451450523", true),
+        ("uri", "/relative/path", false),
+        ("uri", "http://exa mple.com", false),
+        ("uri", "http://example.com/%zz", false),
+        ("uuid", "123e4567-e89b-12d3-a456-426614174000", true),
+        ("uuid", "123e4567e89b12d3a456426614174000", false),
+        ("uuid", "123e4567-e89b-12d3-a456-42661417400g", false),
+    ] {
+        println!("Checking {} {}", s.0, s.1);
+        assert_eq!(check_builtin(s.0, s.1), Some(s.2));
+    }
+    assert_eq!(check_builtin("phone", "123"), None);
+}
+
+fn registry() -> FormatRegistry {
+    let mut registry = FormatRegistry::builtin();
+    registry.register("country-code", |s| {
+        s.len() == 2 && s.bytes().all(|b| b.is_ascii_uppercase())
+    });
+    registry.register_normalizer("phone", |s| {
+        let digits: String = s.chars().filter(|c| !" -()".contains(*c)).collect();
+        let valid = digits.starts_with('+')
+            && digits.len() > 4
+            && digits[1..].bytes().all(|b| b.is_ascii_digit());
+        return if valid { Some(digits) } else { None };
+    });
+    return registry;
+}
+
+#[test]
+fn test_format_registry() {
+    let registry = registry();
+    assert_eq!(
+        registry.names(),
+        vec![
+            "country-code",
+            "date",
+            "date-time",
+            "email",
+            "phone",
+            "uri",
+            "uuid"
+        ]
+    );
+    assert_eq!(registry.check("country-code", "DE"), Some(true));
+    assert_eq!(registry.check("country-code", "de"), Some(false));
+    assert_eq!(registry.check("uuid", "1"), Some(false));
+    assert_eq!(registry.check("isbn", "1"), None);
+    assert_eq!(
+        registry.normalize("phone", "+1 (415) 555-0100"),
+        Some(Some("+14155550100".to_owned()))
+    );
+    assert_eq!(registry.normalize("phone", "555-0100"), Some(None));
+    assert_eq!(registry.clone(), registry);
+    assert!(FormatRegistry::builtin() != FormatRegistry::builtin());
+
+    for s in vec![
+        (r#"{"format": "country-code"}"#, r#""FR""#, vec![]),
+        (
+            r#"{"format": "country-code"}"#,
+            r#""France""#,
+            vec![("", "Expected a valid country-code")],
+        ),
+        (
+            r#"{"items": {"format": "phone"}}"#,
+            r#"["+49 30 1234567", "12", 12]"#,
+            vec![("/1", "Expected a valid phone")],
+        ),
+        (r#"{"format": "isbn"}"#, r#""x""#, vec![]),
+        (
+            r#"{"format": "email"}"#,
+            r#""nobody""#,
+            vec![("", "Expected a valid email")],
+        ),
+    ] {
+        println!("Checking {} {}", s.0, s.1);
+        let schema =
+            s.0.parse::<Schema>()
+                .unwrap()
+                .with_formats(registry.clone());
+        let found: Vec<(String, String)> = schema
+            .validate(&parse(s.1))
+            .into_iter()
+            .map(|v| (v.path, v.message))
+            .collect();
+        let expected: Vec<(String, String)> =
+            s.2.iter()
+                .map(|e| (e.0.to_owned(), e.1.to_owned()))
+                .collect();
+        assert_eq!(found, expected);
+    }
+    // without a registry `format` is only an annotation
+    assert_eq!(violations(r#"{"format": "email"}"#, r#""nobody""#), vec![]);
+}
+
+#[test]
+fn test_validate_formats() {
+    let registry = registry();
+    let rules = vec![
+        (PathPattern::compile("/users/*/phone").unwrap(), "phone"),
+        (
+            PathPattern::compile("/users/*/country").unwrap(),
+            "country-code",
+        ),
+        (PathPattern::compile("/id").unwrap(), "isbn"),
+    ];
+    let mut document = parse(
+        r#"{"id": "1", "users": [
+            {"phone": "+44 20 7946 0958", "country": "GB"},
+            {"phone": "none", "country": 44}
+        ]}"#,
+    );
+    let expected = vec![
+        ("/users/1/phone", "Expected a valid phone"),
+        ("/users/1/country", "Expected string, got integer"),
+        ("/id", "Unknown format isbn"),
+    ];
+    for violations in vec![
+        registry.validate_formats(&document, &rules),
+        registry.normalize_formats(&mut document, &rules),
+    ] {
+        let found: Vec<(&str, &str)> = violations
+            .iter()
+            .map(|v| (v.path.as_str(), v.message.as_str()))
+            .collect();
+        assert_eq!(found, expected);
+    }
+    assert_eq!(
+        document.pointer("/users/0/phone"),
+        Some(&JSONValue::JSONString("+442079460958".to_owned()))
+    );
+    assert_eq!(
+        document.pointer("/users/1/phone"),
+        Some(&JSONValue::JSONString("none".to_owned()))
+    );
+}
+
+#[test]
+fn test_openapi() {
+    let document = parse(
+        r##"{
+        "openapi": "3.1.0",
+        "components": {"schemas": {
+            "Pet": {
+                "oneOf": [{"$ref": "#/components/schemas/Cat"}, {"$ref": "#/components/schemas/Dog"}],
+                "discriminator": {"propertyName": "kind", "mapping": {"doggo": "#/components/schemas/Dog"}}
+            },
+            "Cat": {"properties": {"kind": {"type": "string"}, "lives": {"type": "integer"}}},
+            "Dog": {"properties": {"kind": {"type": "string"}, "bark": {"type": "boolean"}}},
+            "Owner": {
+                "properties": {
+                    "email": {"type": "string", "format": "email"},
+                    "id": {"type": "string", "format": "uuid"},
+                    "nickname": {"type": "string", "nullable": true},
+                    "pets": {"items": {"$ref": "#/components/schemas/Pet"}}
+                }
+            }
+        }}
+    }"##,
+    );
+    let schema = Schema::compile_openapi(&document, "/components/schemas/Owner").unwrap();
+    assert!(schema.is_valid(&parse(
+        r#"{"email": "a@b.io", "nickname": null, "pets": [{"kind": "Cat", "lives": 9}, {"kind": "doggo", "bark": true}]}"#
+    )));
+    let found: Vec<(String, String)> = schema
+        .validate(&parse(
+            r#"{"email": "nope", "id": "1", "pets": [{"kind": "Cat", "lives": "x"}, {"kind": "Fish"}, {"bark": true}, {"kind": 1}]}"#,
+        ))
+        .into_iter()
+        .map(|v| (v.path, v.message))
+        .collect();
+    let expected: Vec<(String, String)> = vec![
+        ("/email", "Expected a valid email"),
+        ("/id", "Expected a valid uuid"),
+        ("/pets/0/lives", "Expected integer, got string"),
+        ("/pets/1", "Unknown kind Fish"),
+        ("/pets/2", "Missing discriminator property kind"),
+        ("/pets/3", "Discriminator kind must be a string"),
+    ]
+    .into_iter()
+    .map(|(path, message)| (path.to_owned(), message.to_owned()))
+    .collect();
+    assert_eq!(found, expected);
+
+    // references point into the document, not the schema itself
+    let plain = Schema::compile(document.pointer("/components/schemas/Owner").unwrap());
+    assert!(plain.is_err());
+    // plain JSON Schema ignores the OpenAPI keywords
+    let plain: Schema = r#"{"type": "string", "format": "email", "nullable": true}"#
+        .parse()
+        .unwrap();
+    assert!(plain.is_valid(&parse("\"nope\"")));
+    assert!(!plain.is_valid(&parse("null")));
+
+    // OpenAPI schemas share the numeric checks
+    let money =
+        parse(r#"{"components": {"schemas": {"Price": {"type": "number", "multipleOf": 0.01}}}}"#);
+    let price = Schema::compile_openapi(&money, "/components/schemas/Price").unwrap();
+    assert!(price.is_valid(&parse("19.99")));
+    assert!(!price.is_valid(&parse("19.999")));
+
+    assert!(Schema::compile_openapi(&document, "/components/schemas/Nope").is_err());
+    let broken = parse(r#"{"components": {"schemas": {"A": {"discriminator": {}}}}}"#);
+    assert!(Schema::compile_openapi(&broken, "/components/schemas/A").is_err());
+}
+
+#[test]
+fn test_validate_stream() {
+    for s in vec![
+        (
+            r#"{"items": {"type": "string", "minLength": 2}, "maxItems": 1}"#,
+            r#"["ab", "c", 3]"#,
+            vec![
+                ("/1", "Expected at least 2 characters, got 1", 7),
+                ("/2", "Expected string, got integer", 12),
+                ("", "Expected at most 1 items, got 3", 0),
+            ],
+        ),
+        (
+            r##"{"$defs": {"p": {"required": ["id"], "properties": {"id": {"type": "integer"}}}},
+                "type": "array", "items": {"allOf": [{"$ref": "#/$defs/p"}, {"additionalProperties": {"type": "string"}}]}}"##,
+            "[{\"id\": 1}, {\"id\": \"x\", \"n\": 2}, {}]",
+            vec![
+                ("/0/id", "Expected string, got integer", 8),
+                ("/1/id", "Expected integer, got string", 19),
+                ("/1/n", "Expected string, got integer", 29),
+                ("/2", "Missing required property id", 33),
+            ],
+        ),
+        (
+            r#"{"properties": {"a": {"enum": [[1]]}}, "additionalProperties": false}"#,
+            r#" {"a": [2], "b": {"c": []}}"#,
+            vec![
+                ("/a", "Value is not one of the allowed values", 7),
+                ("/b", "Unexpected property b", 12),
+            ],
+        ),
+        (
+            r#"{"type": "object", "minProperties": 1}"#,
+            "[]",
+            vec![("", "Expected object, got array", 0)],
+        ),
+        (
+            r#"{"items": false}"#,
+            "[[]]",
+            vec![("/0", "No value is allowed here", 1)],
+        ),
+    ] {
+        println!("Checking {}", s.1);
+        let schema: Schema = s.0.parse().unwrap();
+        let mut events = EventReader::new(s.1.as_bytes());
+        let streamed: Vec<(String, String, Option<usize>)> = schema
+            .validate_stream(&mut events)
+            .unwrap()
+            .into_iter()
+            .map(|v| (v.path, v.message, v.position))
+            .collect();
+        let expected: Vec<(String, String, Option<usize>)> =
+            s.2.iter()
+                .map(|e| (e.0.to_owned(), e.1.to_owned(), Some(e.2)))
+                .collect();
+        assert_eq!(streamed, expected);
+
+        // same findings as on the parsed document
+        let mut streamed: Vec<(String, String)> =
+            streamed.into_iter().map(|v| (v.0, v.1)).collect();
+        let mut built = violations(s.0, s.1);
+        streamed.sort();
+        built.sort();
+        assert_eq!(streamed, built);
+    }
+
+    let schema: Schema = r#"{"items": {"type": "string"}}"#.parse().unwrap();
+    let err = schema
+        .validate_stream(&mut EventReader::new(&b"[\"a\", 1"[..]))
+        .unwrap_err();
+    assert_eq!(err.position, Some(7));
+    let err = schema
+        .validate_stream(&mut EventReader::new(&b"[] []"[..]))
+        .unwrap_err();
+    assert_eq!(err.position, Some(3));
+}
+
+struct Order {
+    _id: u32,
+    _items: Vec<String>,
+    _note: Option<String>,
+    _extra: BTreeMap<String, f64>,
+}
+
+impl ToJSONSchema for Order {
+    fn json_schema() -> JSONValue {
+        return ObjectSchema::new()
+            .field::<u32>("id")
+            .field::<Vec<String>>("items")
+            .optional::<Option<String>>("note")
+            .optional::<BTreeMap<String, f64>>("extra")
+            .deny_unknown()
+            .build();
+    }
+}
+
+#[test]
+fn test_type_schemas() {
+    let expected = parse(
+        r#"{"$schema": "https://json-schema.org/draft/2020-12/schema", "type": "object",
+            "properties": {
+                "id": {"type": "integer", "minimum": 0, "maximum": 4294967295},
+                "items": {"type": "array", "items": {"type": "string"}},
+                "note": {"anyOf": [{"type": "string"}, {"type": "null"}]},
+                "extra": {"type": "object", "additionalProperties": {"type": "number"}}
+            },
+            "required": ["id", "items"],
+            "additionalProperties": false}"#,
+    );
+    let document = schema_document::<Order>();
+    assert_eq!(document, expected);
+
+    let schema = Schema::compile(&document).unwrap();
+    for s in vec![
+        (r#"{"id": 1, "items": []}"#, true),
+        (
+            r#"{"id": 1, "items": ["a"], "note": null, "extra": {"x": 1.5}}"#,
+            true,
+        ),
+        (r#"{"id": -1, "items": []}"#, false),
+        (r#"{"id": 1.5, "items": []}"#, false),
+        (r#"{"id": 1}"#, false),
+        (r#"{"id": 1, "items": [], "note": 2}"#, false),
+        (r#"{"id": 1, "items": [], "other": 2}"#, false),
+    ] {
+        println!("Checking {}", s.0);
+        assert_eq!(schema.is_valid(&parse(s.0)), s.1);
+    }
+    assert_eq!(<JSONValue as ToJSONSchema>::json_schema(), parse("{}"));
+    assert_eq!(
+        schema_document::<bool>().pointer("/type"),
+        Some(&JSONValue::JSONString("boolean".to_owned()))
+    );
+}
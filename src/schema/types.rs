@@ -0,0 +1,166 @@
+use super::*;
+
+/// JSON Schema of what a type's `FromJSONValue` impl accepts, so servers can
+/// publish the schemas of their payloads instead of maintaining them by hand.
+/// Structs describe their fields with `ObjectSchema`.
+pub trait ToJSONSchema {
+    fn json_schema() -> JSONValue;
+}
+
+/// Builds the schema of a struct, one field at a time
+#[derive(Debug, Clone, Default)]
+pub struct ObjectSchema {
+    properties: JSONMap,
+    required: Vec<Box<JSONValue>>,
+    deny_unknown: bool,
+}
+
+impl ObjectSchema {
+    pub fn new() -> ObjectSchema {
+        return ObjectSchema::default();
+    }
+
+    /// A field that must be present
+    pub fn field<T: ToJSONSchema>(mut self, name: &str) -> ObjectSchema {
+        self.required.push(Box::new(string(name)));
+        return self.optional::<T>(name);
+    }
+
+    /// A field that may be missing, e.g. one with a default
+    pub fn optional<T: ToJSONSchema>(mut self, name: &str) -> ObjectSchema {
+        self.properties
+            .insert(name.to_owned(), Box::new(T::json_schema()));
+        return self;
+    }
+
+    /// Reject keys without a field, like `UnknownFields::Deny`
+    pub fn deny_unknown(mut self) -> ObjectSchema {
+        self.deny_unknown = true;
+        return self;
+    }
+
+    pub fn build(self) -> JSONValue {
+        let mut schema = schema_of_type("object");
+        schema.insert(
+            "properties".to_owned(),
+            Box::new(JSONValue::JSONObject(self.properties)),
+        );
+        if !self.required.is_empty() {
+            schema.insert(
+                "required".to_owned(),
+                Box::new(JSONValue::JSONArray(self.required)),
+            );
+        }
+        if self.deny_unknown {
+            schema.insert(
+                "additionalProperties".to_owned(),
+                Box::new(JSONValue::JSONBool(false)),
+            );
+        }
+        return JSONValue::JSONObject(schema);
+    }
+}
+
+/// The whole schema document of `T`, with `$schema`
+pub fn schema_document<T: ToJSONSchema>() -> JSONValue {
+    let mut schema = T::json_schema();
+    if let JSONValue::JSONObject(ref mut members) = schema {
+        members.insert("$schema".to_owned(), Box::new(string(DRAFT)));
+    }
+    return schema;
+}
+
+impl ToJSONSchema for JSONValue {
+    fn json_schema() -> JSONValue {
+        return JSONValue::JSONObject(JSONMap::new());
+    }
+}
+
+impl ToJSONSchema for bool {
+    fn json_schema() -> JSONValue {
+        return JSONValue::JSONObject(schema_of_type("boolean"));
+    }
+}
+
+impl ToJSONSchema for String {
+    fn json_schema() -> JSONValue {
+        return JSONValue::JSONObject(schema_of_type("string"));
+    }
+}
+
+impl ToJSONSchema for f64 {
+    fn json_schema() -> JSONValue {
+        return JSONValue::JSONObject(schema_of_type("number"));
+    }
+}
+
+impl ToJSONSchema for f32 {
+    fn json_schema() -> JSONValue {
+        return f64::json_schema();
+    }
+}
+
+macro_rules! impl_integer_schema {
+    ($($int:ident),*) => {
+        $(
+            impl ToJSONSchema for $int {
+                fn json_schema() -> JSONValue {
+                    let mut schema = schema_of_type("integer");
+                    schema.insert("minimum".to_owned(), Box::new(JSONValue::JSONNumber($int::MIN as f64)));
+                    schema.insert("maximum".to_owned(), Box::new(JSONValue::JSONNumber($int::MAX as f64)));
+                    return JSONValue::JSONObject(schema);
+                }
+            }
+        )*
+    };
+}
+
+impl_integer_schema!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize);
+
+/// `T` or `null`
+impl<T: ToJSONSchema> ToJSONSchema for Option<T> {
+    fn json_schema() -> JSONValue {
+        let variants = vec![
+            Box::new(T::json_schema()),
+            Box::new(JSONValue::JSONObject(schema_of_type("null"))),
+        ];
+        let mut schema = JSONMap::new();
+        schema.insert("anyOf".to_owned(), Box::new(JSONValue::JSONArray(variants)));
+        return JSONValue::JSONObject(schema);
+    }
+}
+
+impl<T: ToJSONSchema> ToJSONSchema for Vec<T> {
+    fn json_schema() -> JSONValue {
+        let mut schema = schema_of_type("array");
+        schema.insert("items".to_owned(), Box::new(T::json_schema()));
+        return JSONValue::JSONObject(schema);
+    }
+}
+
+impl<T: ToJSONSchema> ToJSONSchema for HashMap<String, T> {
+    fn json_schema() -> JSONValue {
+        return map_schema::<T>();
+    }
+}
+
+impl<T: ToJSONSchema> ToJSONSchema for BTreeMap<String, T> {
+    fn json_schema() -> JSONValue {
+        return map_schema::<T>();
+    }
+}
+
+fn map_schema<T: ToJSONSchema>() -> JSONValue {
+    let mut schema = schema_of_type("object");
+    schema.insert(
+        "additionalProperties".to_owned(),
+        Box::new(T::json_schema()),
+    );
+    return JSONValue::JSONObject(schema);
+}
+
+fn schema_of_type(name: &str) -> JSONMap {
+    let mut schema = JSONMap::new();
+    schema.insert("type".to_owned(), Box::new(string(name)));
+    return schema;
+}
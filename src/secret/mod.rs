@@ -0,0 +1,107 @@
+use super::*;
+use pattern::PathPattern;
+use serializer::{write_string, write_value, SerializerConfig};
+use std::hint::black_box;
+
+mod scrub;
+#[cfg(test)]
+mod tests;
+
+pub use self::scrub::{Masking, PIIKind, Scrubber};
+
+const REDACTED: &str = "[REDACTED]";
+
+impl JSONValue {
+    /// Compares a string value with a secret without leaking, through timing,
+    /// how much of it matched. Only the length of `expected` affects the running time.
+    /// Non-string values never match.
+    pub fn as_str_ct_eq(&self, expected: &str) -> bool {
+        match *self {
+            JSONValue::JSONString(ref s) => return ct_eq(s.as_bytes(), expected.as_bytes()),
+            _ => return false,
+        }
+    }
+
+    /// Wraps the value so that `Display` and `Debug` print compact JSON
+    /// with everything at the given paths replaced by `"[REDACTED]"`.
+    pub fn redacted<'a>(&'a self, paths: &'a [PathPattern]) -> Redacted<'a> {
+        return Redacted { value: self, paths };
+    }
+}
+
+/// Constant-time byte comparison. The loop always runs over all of `expected`,
+/// so the time depends on its length only, not on the position of the first mismatch.
+pub fn ct_eq(actual: &[u8], expected: &[u8]) -> bool {
+    let mut diff = (actual.len() != expected.len()) as u8;
+    for (i, b) in expected.iter().enumerate() {
+        let a = actual.get(i).cloned().unwrap_or(0);
+        diff |= black_box(a ^ b);
+    }
+    return diff == 0;
+}
+
+/// Value formatter that never prints what's under redact-marked paths,
+/// safe to pass to loggers. Created by `JSONValue::redacted`.
+pub struct Redacted<'a> {
+    value: &'a JSONValue,
+    paths: &'a [PathPattern],
+}
+
+impl<'a> Redacted<'a> {
+    fn write(
+        &self,
+        f: &mut fmt::Formatter,
+        value: &JSONValue,
+        path: &mut Vec<String>,
+    ) -> fmt::Result {
+        if self.paths.iter().any(|p| p.matches_tokens(path)) {
+            return write_string(f, REDACTED);
+        }
+        if !self.paths.iter().any(|p| p.matches_prefix(path)) {
+            return write_value(f, value, &SerializerConfig::compact(), 0);
+        }
+        match *value {
+            // a fragment written verbatim could contain redacted paths
+            JSONValue::JSONRaw(_) => return self.write(f, &value.expand_raw(), path),
+            JSONValue::JSONArray(ref items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    path.push(i.to_string());
+                    self.write(f, item, path)?;
+                    path.pop();
+                }
+                return f.write_str("]");
+            }
+            JSONValue::JSONObject(ref members) => {
+                f.write_str("{")?;
+                for (i, (key, member)) in members.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write_string(f, key)?;
+                    f.write_str(":")?;
+                    path.push(key.clone());
+                    self.write(f, member, path)?;
+                    path.pop();
+                }
+                return f.write_str("}");
+            }
+            _ => return write_value(f, value, &SerializerConfig::compact(), 0),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Redacted<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return self.write(f, self.value, &mut vec![]);
+    }
+}
+
+impl<'a> fmt::Debug for Redacted<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return fmt::Display::fmt(self, f);
+    }
+}
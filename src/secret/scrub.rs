@@ -0,0 +1,309 @@
+use super::*;
+use std::ops::Range;
+
+/// What the scrubber looks for in string values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PIIKind {
+    /// `name@example.com`
+    Email,
+    /// 10 to 15 digits, optionally grouped with spaces, dashes or parentheses,
+    /// or 8 and more starting with `+`
+    Phone,
+    /// 13 to 19 digits passing the Luhn check, optionally grouped
+    CardNumber,
+    /// 20 and more letters, digits and `_-+/=.` mixing lowercase, uppercase and
+    /// digits, as API keys, session tokens and JWTs do
+    Token,
+}
+
+impl PIIKind {
+    fn label(self) -> &'static str {
+        match self {
+            PIIKind::Email => return "[EMAIL]",
+            PIIKind::Phone => return "[PHONE]",
+            PIIKind::CardNumber => return "[CARD]",
+            PIIKind::Token => return "[TOKEN]",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Masking {
+    /// Replaces each match with a label such as `[EMAIL]`, keeping the rest
+    Label,
+    /// Replaces the whole string with `"[REDACTED]"` if anything matches
+    Redact,
+}
+
+/// Masks likely personal data in string values, for sanitizing logs. Detection
+/// is heuristic: matches are found by shape, so expect some false positives
+/// (e.g. long numeric IDs taken for phone numbers) and misses. Object keys are
+/// never changed. Paths given `with_paths` are redacted whatever they hold, as
+/// `JSONValue::redacted` does.
+#[derive(Debug, Clone)]
+pub struct Scrubber {
+    kinds: Vec<PIIKind>,
+    masking: Masking,
+    paths: Vec<PathPattern>,
+}
+
+impl Scrubber {
+    /// Looks for all kinds, masking with labels
+    pub fn new() -> Scrubber {
+        return Scrubber::only(&[
+            PIIKind::Email,
+            PIIKind::CardNumber,
+            PIIKind::Token,
+            PIIKind::Phone,
+        ]);
+    }
+
+    pub fn only(kinds: &[PIIKind]) -> Scrubber {
+        return Scrubber {
+            kinds: kinds.to_vec(),
+            masking: Masking::Label,
+            paths: vec![],
+        };
+    }
+
+    pub fn with_masking(mut self, masking: Masking) -> Scrubber {
+        self.masking = masking;
+        return self;
+    }
+
+    pub fn with_paths(mut self, paths: Vec<PathPattern>) -> Scrubber {
+        self.paths = paths;
+        return self;
+    }
+
+    /// Matches in `s` as byte ranges, in order. Where matches overlap, emails
+    /// win over card numbers, card numbers over tokens and tokens over phones.
+    pub fn find(&self, s: &str) -> Vec<(PIIKind, Range<usize>)> {
+        let b = s.as_bytes();
+        let mut found: Vec<(PIIKind, Range<usize>)> = vec![];
+        for kind in [
+            PIIKind::Email,
+            PIIKind::CardNumber,
+            PIIKind::Token,
+            PIIKind::Phone,
+        ] {
+            if !self.kinds.contains(&kind) {
+                continue;
+            }
+            let ranges = match kind {
+                PIIKind::Email => find_emails(b),
+                PIIKind::CardNumber => find_cards(b),
+                PIIKind::Token => find_tokens(b),
+                PIIKind::Phone => find_phones(b),
+            };
+            for range in ranges {
+                let overlaps = found
+                    .iter()
+                    .any(|(_, other)| range.start < other.end && other.start < range.end);
+                if !overlaps {
+                    found.push((kind, range));
+                }
+            }
+        }
+        found.sort_by_key(|(_, range)| range.start);
+        return found;
+    }
+
+    pub fn scrub_str<'s>(&self, s: &'s str) -> Cow<'s, str> {
+        let found = self.find(s);
+        if found.is_empty() {
+            return Cow::Borrowed(s);
+        }
+        if self.masking == Masking::Redact {
+            return Cow::Owned(REDACTED.to_owned());
+        }
+        let mut result = String::with_capacity(s.len());
+        let mut last = 0;
+        for (kind, range) in found {
+            result.push_str(&s[last..range.start]);
+            result.push_str(kind.label());
+            last = range.end;
+        }
+        result.push_str(&s[last..]);
+        return Cow::Owned(result);
+    }
+
+    /// Scrubs every string value in the tree. Raw fragments are expanded.
+    pub fn scrub(&self, value: &mut JSONValue) {
+        self.scrub_in(value, &mut vec![]);
+    }
+
+    fn scrub_in(&self, value: &mut JSONValue, path: &mut Vec<String>) {
+        if self.paths.iter().any(|p| p.matches_tokens(path)) {
+            *value = JSONValue::JSONString(REDACTED.to_owned());
+            return;
+        }
+        if let JSONValue::JSONRaw(_) = *value {
+            *value = value.expand_raw().into_owned();
+        }
+        match *value {
+            JSONValue::JSONString(ref mut s) => {
+                if let Cow::Owned(scrubbed) = self.scrub_str(s) {
+                    *s = scrubbed;
+                }
+            }
+            JSONValue::JSONArray(ref mut items) => {
+                for (i, item) in items.iter_mut().enumerate() {
+                    path.push(i.to_string());
+                    self.scrub_in(item, path);
+                    path.pop();
+                }
+            }
+            JSONValue::JSONObject(ref mut members) => {
+                for (key, member) in members.iter_mut() {
+                    path.push(key.clone());
+                    self.scrub_in(member, path);
+                    path.pop();
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+impl Default for Scrubber {
+    fn default() -> Scrubber {
+        return Scrubber::new();
+    }
+}
+
+fn find_emails(b: &[u8]) -> Vec<Range<usize>> {
+    let local = |c: u8| c.is_ascii_alphanumeric() || b"._%+-".contains(&c);
+    let domain = |c: u8| c.is_ascii_alphanumeric() || c == b'.' || c == b'-';
+    let mut result = vec![];
+    for at in 0..b.len() {
+        if b[at] != b'@' {
+            continue;
+        }
+        let mut start = at;
+        while start > 0 && local(b[start - 1]) {
+            start -= 1;
+        }
+        while start < at && b[start] == b'.' {
+            start += 1;
+        }
+        let mut end = at + 1;
+        while end < b.len() && domain(b[end]) {
+            end += 1;
+        }
+        while end > at + 1 && (b[end - 1] == b'.' || b[end - 1] == b'-') {
+            end -= 1;
+        }
+        let host = &b[at + 1..end];
+        let tld = host.rsplit(|&c| c == b'.').next().unwrap_or(&[]);
+        let valid_host = host.contains(&b'.')
+            && host[0] != b'.'
+            && tld.len() >= 2
+            && tld.iter().all(|c| c.is_ascii_alphabetic());
+        if start < at && valid_host {
+            result.push(start..end);
+        }
+    }
+    return result;
+}
+
+// Runs of digits grouped by single `separators`, not touching letters or
+// digits on either side, as (range, digit count)
+fn digit_runs(b: &[u8], separators: &[u8], start_chars: &[u8]) -> Vec<(Range<usize>, usize)> {
+    let mut result = vec![];
+    let mut i = 0;
+    while i < b.len() {
+        let starts = b[i].is_ascii_digit() || start_chars.contains(&b[i]);
+        let boundary = i == 0 || !(b[i - 1].is_ascii_alphanumeric() || b[i - 1] == b'+');
+        if !starts || !boundary {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        let mut end = i;
+        let mut digits = 0;
+        let mut j = i;
+        while j < b.len() {
+            let c = b[j];
+            if c.is_ascii_digit() {
+                digits += 1;
+                end = j + 1;
+            } else if !(separators.contains(&c) || (j == start && start_chars.contains(&c))) {
+                break;
+            }
+            j += 1;
+        }
+        i = j.max(i + 1);
+        if end < b.len() && b[end].is_ascii_alphanumeric() {
+            continue;
+        }
+        // closing parentheses trailing the digits belong to the group
+        while end < b.len() && b[end] == b')' && b[start..end].contains(&b'(') {
+            end += 1;
+        }
+        if digits > 0 {
+            result.push((start..end, digits));
+        }
+    }
+    return result;
+}
+
+fn find_cards(b: &[u8]) -> Vec<Range<usize>> {
+    return digit_runs(b, b" -", b"")
+        .into_iter()
+        .filter(|(range, digits)| (13..=19).contains(digits) && luhn(&b[range.clone()]))
+        .map(|(range, _)| range)
+        .collect();
+}
+
+fn luhn(b: &[u8]) -> bool {
+    let mut sum = 0;
+    let digits = b.iter().rev().filter(|c| c.is_ascii_digit());
+    for (i, c) in digits.enumerate() {
+        let mut digit = (c - b'0') as u32;
+        if i % 2 == 1 {
+            digit *= 2;
+            if digit > 9 {
+                digit -= 9;
+            }
+        }
+        sum += digit;
+    }
+    return sum % 10 == 0;
+}
+
+fn find_phones(b: &[u8]) -> Vec<Range<usize>> {
+    return digit_runs(b, b" -()", b"+(")
+        .into_iter()
+        .filter(|(range, digits)| {
+            let international = b[range.start] == b'+' && *digits >= 8;
+            return (international || *digits >= 10) && *digits <= 15;
+        })
+        .map(|(range, _)| range)
+        .collect();
+}
+
+fn find_tokens(b: &[u8]) -> Vec<Range<usize>> {
+    let token = |c: u8| c.is_ascii_alphanumeric() || b"_-+/=.".contains(&c);
+    let mut result = vec![];
+    let mut i = 0;
+    while i < b.len() {
+        if !token(b[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < b.len() && token(b[i]) {
+            i += 1;
+        }
+        let run = &b[start..i];
+        if run.len() >= 20
+            && run.iter().any(|c| c.is_ascii_lowercase())
+            && run.iter().any(|c| c.is_ascii_uppercase())
+            && run.iter().any(|c| c.is_ascii_digit())
+        {
+            result.push(start..i);
+        }
+    }
+    return result;
+}
@@ -0,0 +1,138 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+#[test]
+fn test_ct_eq() {
+    for s in vec![
+        ("secret", "secret", true),
+        ("", "", true),
+        ("secret", "secreT", false),
+        ("secret", "secre", false),
+        ("secre", "secret", false),
+        ("", "x", false),
+        ("é", "é", true),
+    ] {
+        assert_eq!(
+            ct_eq(s.0.as_bytes(), s.1.as_bytes()),
+            s.2,
+            "{} vs {}",
+            s.0,
+            s.1
+        );
+        let value = JSONValue::JSONString(s.0.to_owned());
+        assert_eq!(value.as_str_ct_eq(s.1), s.2);
+    }
+    assert!(!JSONValue::JSONNull().as_str_ct_eq("null"));
+    assert!(!JSONValue::JSONNumber(1.0).as_str_ct_eq("1"));
+}
+
+#[test]
+fn test_redacted() {
+    let value: JSONValue =
+        r#"{"auth": {"token": "abc", "user": "bob"}, "cards": [{"pan": "4111"}, {"pan": 4222}]}"#
+            .parse()
+            .unwrap();
+    let paths = vec![
+        PathPattern::compile("/auth/token").unwrap(),
+        PathPattern::compile("/cards/*/pan").unwrap(),
+    ];
+    let text = format!("{}", value.redacted(&paths));
+    assert!(!text.contains("abc"));
+    assert!(!text.contains("4111"));
+    assert!(!text.contains("4222"));
+    let parsed: JSONValue = text.parse().unwrap();
+    assert_eq!(
+        parsed,
+        r#"{"auth": {"token": "[REDACTED]", "user": "bob"}, "cards": [{"pan": "[REDACTED]"}, {"pan": "[REDACTED]"}]}"#
+            .parse()
+            .unwrap()
+    );
+    assert_eq!(format!("{:?}", value.redacted(&paths)), text);
+
+    let mut map = JSONMap::new();
+    map.insert(
+        "auth".to_owned(),
+        Box::new(JSONValue::raw(r#"{"token": "abc"}"#).unwrap()),
+    );
+    let text = format!("{}", JSONValue::JSONObject(map).redacted(&paths));
+    assert_eq!(text, r#"{"auth":{"token":"[REDACTED]"}}"#);
+
+    let everything = vec![PathPattern::compile("").unwrap()];
+    assert_eq!(format!("{}", value.redacted(&everything)), "\"[REDACTED]\"");
+}
+
+#[test]
+fn test_scrub_str() {
+    let scrubber = Scrubber::new();
+    for (s, expected) in vec![
+        ("mail john.doe+x@mail.example.com.", "mail [EMAIL]."),
+        ("a@b, x@y.z, @example.com", "a@b, x@y.z, @example.com"),
+        ("card 4111 1111 1111 1111 ok", "card [CARD] ok"),
+        ("card 4111-1111-1111-1112", "card 4111-1111-1111-1112"),
+        (
+            "call (555) 123-4567 or +44 20 7946 0958",
+            "call [PHONE] or [PHONE]",
+        ),
+        (
+            "on 2023-01-01 at 12:00, order 1234567",
+            "on 2023-01-01 at 12:00, order 1234567",
+        ),
+        (
+            "Bearer eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxIn0.Ab3_x",
+            "Bearer [TOKEN]",
+        ),
+        (
+            "id 0f8fad5b-d9cb-469f-a165-70867728950e",
+            "id 0f8fad5b-d9cb-469f-a165-70867728950e",
+        ),
+        ("5555555555554444", "[CARD]"),
+        ("x4111111111111111", "x4111111111111111"),
+        ("Ünïcödé a@example.org", "Ünïcödé [EMAIL]"),
+    ] {
+        println!("Checking {}", s);
+        assert_eq!(scrubber.scrub_str(s), expected);
+    }
+
+    let scrubber = Scrubber::only(&[PIIKind::Phone]);
+    assert_eq!(scrubber.scrub_str("15555550100"), "[PHONE]");
+    assert_eq!(scrubber.scrub_str("a@example.org"), "a@example.org");
+    assert_eq!(
+        scrubber.find("tel +1 555 0100 999"),
+        vec![(PIIKind::Phone, 4..19)]
+    );
+}
+
+#[test]
+fn test_scrub() {
+    let mut value: JSONValue =
+        r#"{"user": {"email": "bob@example.com", "note": "call 555-010-0999"},
+        "password": "hunter2", "items": ["plain", 4111111111111111, "4111111111111111"]}"#
+            .parse()
+            .unwrap();
+    let mut redacted = value.clone();
+    let scrubber = Scrubber::new().with_paths(vec![PathPattern::compile("/password").unwrap()]);
+    scrubber.scrub(&mut value);
+    assert_eq!(
+        value,
+        r#"{"user": {"email": "[EMAIL]", "note": "call [PHONE]"},
+            "password": "[REDACTED]", "items": ["plain", 4111111111111111, "[CARD]"]}"#
+            .parse()
+            .unwrap()
+    );
+
+    Scrubber::new()
+        .with_masking(Masking::Redact)
+        .scrub(&mut redacted);
+    assert_eq!(
+        redacted,
+        r#"{"user": {"email": "[REDACTED]", "note": "[REDACTED]"},
+            "password": "hunter2", "items": ["plain", 4111111111111111, "[REDACTED]"]}"#
+            .parse()
+            .unwrap()
+    );
+
+    let mut value = JSONValue::raw(r#"{"to": "a@example.org"}"#).unwrap();
+    Scrubber::new().scrub(&mut value);
+    assert_eq!(value, r#"{"to": "[EMAIL]"}"#.parse().unwrap());
+}
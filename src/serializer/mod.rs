@@ -0,0 +1,241 @@
+use super::*;
+use escape::{write_escaped, write_escaped_chars, write_json_escaped_chars};
+use std::fmt::Write;
+use std::io;
+
+#[cfg(test)]
+mod tests;
+
+const NULL: &str = "null";
+const BOOL_TRUE: &str = "true";
+const BOOL_FALSE: &str = "false";
+
+// Escaped with `escape_html`
+const HTML_UNSAFE: &[char] = &['<', '>', '&', '\u{2028}', '\u{2029}'];
+
+// Output is handed to `io::Write` in chunks of about this size
+const WRITE_BUFFER_SIZE: usize = 8 * 1024;
+
+// Integral values below this are written without exponent or fraction
+const MAX_PLAIN_INTEGER: f64 = 1e17;
+
+/// Output options of the serializer. The default is compact output
+/// with object keys in map iteration order.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SerializerConfig {
+    /// Number of spaces per nesting level. `None` means everything on a single line.
+    pub indent: Option<usize>,
+    /// Write object keys in lexicographic order, which makes output deterministic.
+    pub sort_keys: bool,
+    pub escaping: Escaping,
+    /// Escape `<`, `>`, `&`, U+2028 and U+2029 as `\uXXXX` too, so the output
+    /// can be embedded in a `<script>` tag: it can't close the tag or start a
+    /// comment, and older JavaScript engines don't take the two separators for
+    /// line breaks in string literals.
+    pub escape_html: bool,
+}
+
+/// How strings are escaped. Quotes, backslashes and control characters always are.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Escaping {
+    /// Other characters are written as UTF-8
+    #[default]
+    Utf8,
+    /// Non-ASCII characters are escaped as `\uXXXX` too, surrogate pairs for
+    /// those beyond the BMP, for consumers that only handle ASCII. Applies to
+    /// raw fragments as well.
+    Ascii,
+}
+
+impl SerializerConfig {
+    pub fn compact() -> SerializerConfig {
+        return SerializerConfig::default();
+    }
+
+    pub fn pretty(indent: usize) -> SerializerConfig {
+        return SerializerConfig {
+            indent: Some(indent),
+            ..SerializerConfig::default()
+        };
+    }
+
+    // Whether `ch` is escaped in strings beyond what JSON requires
+    fn escapes(&self, ch: char) -> bool {
+        return (self.escaping == Escaping::Ascii && !ch.is_ascii())
+            || (self.escape_html && HTML_UNSAFE.contains(&ch));
+    }
+
+    fn escapes_any(&self) -> bool {
+        return self.escaping == Escaping::Ascii || self.escape_html;
+    }
+}
+
+impl JSONValue {
+    /// Serializes the value to JSON text. Numbers that JSON can't represent
+    /// (NaN and infinities) are written as `null`.
+    pub fn to_string_with(&self, config: &SerializerConfig) -> String {
+        let mut result = String::new();
+        write_value(&mut result, self, config, 0).unwrap();
+        return result;
+    }
+}
+
+impl JSONValue {
+    /// Writes the value as compact JSON to `w` as it goes, through a small
+    /// buffer, without building the whole text in memory first.
+    pub fn write_to<W: io::Write>(&self, w: W) -> io::Result<()> {
+        return self.write_to_with(w, &SerializerConfig::compact());
+    }
+
+    /// Like `write_to`, pretty-printed with `indent` spaces per level
+    pub fn write_pretty_to<W: io::Write>(&self, w: W, indent: usize) -> io::Result<()> {
+        return self.write_to_with(w, &SerializerConfig::pretty(indent));
+    }
+
+    pub fn write_to_with<W: io::Write>(&self, w: W, config: &SerializerConfig) -> io::Result<()> {
+        let mut adapter = IoAdapter {
+            inner: w,
+            buffer: String::with_capacity(WRITE_BUFFER_SIZE),
+            error: None,
+        };
+        if write_value(&mut adapter, self, config, 0).is_err() {
+            // without an I/O error, the failure came from formatting
+            return Err(adapter
+                .error
+                .take()
+                .unwrap_or_else(|| io::Error::other("Unable to format value")));
+        }
+        adapter.inner.write_all(adapter.buffer.as_bytes())?;
+        return adapter.inner.flush();
+    }
+}
+
+// Buffers the serializer's output for an `io::Write`, keeping the first error.
+// Strings too long to buffer go straight to the writer.
+struct IoAdapter<W> {
+    inner: W,
+    buffer: String,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> Write for IoAdapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        if self.buffer.len() + s.len() < WRITE_BUFFER_SIZE {
+            self.buffer.push_str(s);
+            return Ok(());
+        }
+        let mut result = self.inner.write_all(self.buffer.as_bytes());
+        self.buffer.clear();
+        if s.len() < WRITE_BUFFER_SIZE {
+            self.buffer.push_str(s);
+        } else if result.is_ok() {
+            result = self.inner.write_all(s.as_bytes());
+        }
+        if let Err(e) = result {
+            self.error = Some(e);
+            return Err(fmt::Error);
+        }
+        return Ok(());
+    }
+}
+
+/// Compact JSON text, so `value.to_string()` round-trips through `parse`
+impl fmt::Display for JSONValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write_value(f, self, &SerializerConfig::compact(), 0);
+    }
+}
+
+pub fn write_value<W: Write>(
+    w: &mut W,
+    value: &JSONValue,
+    config: &SerializerConfig,
+    depth: usize,
+) -> fmt::Result {
+    match *value {
+        JSONValue::JSONNull() => return w.write_str(NULL),
+        JSONValue::JSONBool(b) => return w.write_str(if b { BOOL_TRUE } else { BOOL_FALSE }),
+        JSONValue::JSONNumber(n) => return write_number(w, n),
+        JSONValue::JSONString(ref s) => return write_string_with(w, s, config),
+        JSONValue::JSONRaw(ref raw) if config.escapes_any() => {
+            return write_json_escaped_chars(w, raw, |ch| return config.escapes(ch))
+        }
+        JSONValue::JSONRaw(ref raw) => return w.write_str(raw),
+        JSONValue::JSONArray(ref items) => {
+            if items.is_empty() {
+                return w.write_str("[]");
+            }
+            w.write_char('[')?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_newline(w, config, depth + 1)?;
+                write_value(w, item, config, depth + 1)?;
+            }
+            write_newline(w, config, depth)?;
+            return w.write_char(']');
+        }
+        JSONValue::JSONObject(ref map) => {
+            if map.is_empty() {
+                return w.write_str("{}");
+            }
+            let mut keys: Vec<&String> = map.keys().collect();
+            if config.sort_keys {
+                keys.sort();
+            }
+            w.write_char('{')?;
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    w.write_char(',')?;
+                }
+                write_newline(w, config, depth + 1)?;
+                write_string_with(w, key, config)?;
+                w.write_str(if config.indent.is_some() { ": " } else { ":" })?;
+                write_value(w, &map[key], config, depth + 1)?;
+            }
+            write_newline(w, config, depth)?;
+            return w.write_char('}');
+        }
+    }
+}
+
+fn write_newline<W: Write>(w: &mut W, config: &SerializerConfig, depth: usize) -> fmt::Result {
+    if let Some(indent) = config.indent {
+        w.write_char('\n')?;
+        for _ in 0..indent * depth {
+            w.write_char(' ')?;
+        }
+    }
+    return Ok(());
+}
+
+/// Writes the shortest text that parses back to the same `f64`, subnormals
+/// included, independent of the locale. Negative zero is written as `-0`.
+pub fn write_number<W: Write>(w: &mut W, n: f64) -> fmt::Result {
+    if !n.is_finite() {
+        return w.write_str(NULL);
+    }
+    if n.fract() == 0.0 && n.abs() < MAX_PLAIN_INTEGER {
+        return write!(w, "{}", n);
+    }
+    // Debug formatting is the shortest representation that parses back to the same f64,
+    // and switches to exponent notation for very large and very small numbers
+    return write!(w, "{:?}", n);
+}
+
+pub fn write_string<W: Write>(w: &mut W, s: &str) -> fmt::Result {
+    w.write_char('"')?;
+    write_escaped(w, s)?;
+    return w.write_char('"');
+}
+
+/// Like `write_string`, escaped as `config` asks
+pub fn write_string_with<W: Write>(w: &mut W, s: &str, config: &SerializerConfig) -> fmt::Result {
+    if !config.escapes_any() {
+        return write_string(w, s);
+    }
+    w.write_char('"')?;
+    write_escaped_chars(w, s, |ch| return config.escapes(ch))?;
+    return w.write_char('"');
+}
@@ -0,0 +1,287 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_compact_output() {
+    let config = SerializerConfig {
+        sort_keys: true,
+        ..SerializerConfig::compact()
+    };
+    for s in vec![
+        ("null", "null"),
+        ("true", "true"),
+        (" [ ]", "[]"),
+        ("{}", "{}"),
+        ("[1, 2.5, -3]", "[1,2.5,-3]"),
+        (
+            "{\"b\": [true], \"a\": {\"c\": null}}",
+            "{\"a\":{\"c\":null},\"b\":[true]}",
+        ),
+        ("\"a\\\"b\\\\c\\/d\"", "\"a\\\"b\\\\c/d\""),
+        ("\"\\n\\r\\t\\b\\f\\u0001\"", "\"\\n\\r\\t\\b\\f\\u0001\""),
+        ("\"юникод\"", "\"юникод\""),
+    ] {
+        assert_eq!(parse(s.0).to_string_with(&config), s.1);
+    }
+}
+
+#[test]
+fn test_numbers() {
+    for s in vec![
+        (0.0, "0"),
+        (-0.0, "-0"),
+        (42.0, "42"),
+        (0.1, "0.1"),
+        (-1.5, "-1.5"),
+        (1e17, "1e17"),
+        (1.5e300, "1.5e300"),
+        (1e-7, "1e-7"),
+        (f64::NAN, "null"),
+        (f64::INFINITY, "null"),
+    ] {
+        assert_eq!(
+            JSONValue::JSONNumber(s.0).to_string_with(&SerializerConfig::compact()),
+            s.1
+        );
+    }
+}
+
+#[test]
+fn test_pretty_output() {
+    let config = SerializerConfig {
+        sort_keys: true,
+        ..SerializerConfig::pretty(2)
+    };
+    assert_eq!(
+        parse("{\"b\": [1, {}], \"a\": {\"c\": []}}").to_string_with(&config),
+        "{
+  \"a\": {
+    \"c\": []
+  },
+  \"b\": [
+    1,
+    {}
+  ]
+}"
+    );
+}
+
+#[test]
+fn test_roundtrip() {
+    for s in vec![
+        "{\"a\": [1, 2, {\"b\": \"\\u0000\\u001f\"}], \"c\": -12.5e-10}",
+        "[0.30000000000000004, 1e300, 5e-324, 123456789012345680000]",
+        "\"\\\"quoted\\\" \\\\ back\"",
+    ] {
+        let value = parse(s);
+        for config in vec![SerializerConfig::compact(), SerializerConfig::pretty(4)] {
+            assert_eq!(parse(&value.to_string_with(&config)), value);
+        }
+        assert_eq!(parse(&value.to_string()), value);
+    }
+}
+
+#[test]
+fn test_display() {
+    for s in vec![
+        ("[1, true, null, \"\\u00e9\\n\"]", "[1,true,null,\"é\\n\"]"),
+        ("{\"a\": {}}", "{\"a\":{}}"),
+        ("-0.5", "-0.5"),
+    ] {
+        println!("Checking {}", s.0);
+        let value = parse(s.0);
+        assert_eq!(value.to_string(), s.1);
+        assert_eq!(
+            format!("{}", value),
+            value.to_string_with(&SerializerConfig::compact())
+        );
+    }
+    assert_eq!(JSONValue::JSONNumber(f64::NAN).to_string(), "null");
+}
+
+#[test]
+fn test_raw() {
+    let mut items = vec![Box::new(JSONValue::raw(" {\"cached\" :  [1,2]} ").unwrap())];
+    items.push(Box::new(JSONValue::JSONNumber(3.0)));
+    let value = JSONValue::JSONArray(items);
+    assert_eq!(
+        value.to_string_with(&SerializerConfig::compact()),
+        "[{\"cached\" :  [1,2]},3]"
+    );
+    assert_eq!(
+        value.to_string_with(&SerializerConfig::pretty(2)),
+        "[\n  {\"cached\" :  [1,2]},\n  3\n]"
+    );
+    assert_eq!(*value.expand_raw(), parse("[{\"cached\": [1, 2]}, 3]"));
+    assert!(JSONValue::raw("{\"a\": }").is_err());
+
+    let plain = parse("[1]");
+    assert!(match plain.expand_raw() {
+        Cow::Borrowed(borrowed) => std::ptr::eq(borrowed, &plain),
+        Cow::Owned(_) => false,
+    });
+    assert_eq!(
+        *JSONValue::JSONRaw("nope".to_owned()).expand_raw(),
+        JSONValue::JSONNull()
+    );
+}
+
+#[cfg(feature = "preserve_order")]
+#[test]
+fn test_preserve_order() {
+    let input = "{\"z\":1,\"a\":{\"y\":2,\"b\":3},\"m\":[]}";
+    let mut value = parse(input);
+    assert_eq!(value.to_string_with(&SerializerConfig::compact()), input);
+    if let JSONValue::JSONObject(ref mut map) = value {
+        map.remove("z");
+        map.insert("c".to_owned(), Box::new(JSONValue::JSONNull()));
+    }
+    assert_eq!(
+        value.to_string_with(&SerializerConfig::compact()),
+        "{\"a\":{\"y\":2,\"b\":3},\"m\":[],\"c\":null}"
+    );
+}
+
+#[cfg(all(feature = "btree_map", not(feature = "preserve_order")))]
+#[test]
+fn test_btree_map_sorted_keys() {
+    let value = parse("{\"z\": 1, \"a\": {\"y\": 2, \"b\": 3}, \"m\": []}");
+    assert_eq!(
+        value.to_string_with(&SerializerConfig::compact()),
+        "{\"a\":{\"b\":3,\"y\":2},\"m\":[],\"z\":1}"
+    );
+}
+
+// Records the size of each write, failing after `limit` bytes
+struct Recorder {
+    written: Vec<u8>,
+    largest: usize,
+    limit: usize,
+}
+
+impl io::Write for Recorder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written.len() + buf.len() > self.limit {
+            return Err(io::Error::other("disk full"));
+        }
+        self.largest = self.largest.max(buf.len());
+        self.written.extend_from_slice(buf);
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+#[test]
+fn test_write_to() {
+    let items: Vec<String> = (0..5000)
+        .map(|i| format!("{{\"id\": {}, \"name\": \"item {}\"}}", i, i))
+        .collect();
+    let value = parse(&format!("[{}]", items.join(",")));
+    for config in vec![SerializerConfig::compact(), SerializerConfig::pretty(2)] {
+        println!("Checking {:?}", config);
+        let mut recorder = Recorder {
+            written: vec![],
+            largest: 0,
+            limit: usize::MAX,
+        };
+        value.write_to_with(&mut recorder, &config).unwrap();
+        let expected = value.to_string_with(&config);
+        assert_eq!(String::from_utf8(recorder.written).unwrap(), expected);
+        assert!(expected.len() > 100_000);
+        assert!(recorder.largest < 10_000);
+    }
+    let raw = format!("\"{}\"", "x".repeat(100_000));
+    let value = JSONValue::JSONArray(vec![Box::new(JSONValue::JSONRaw(raw.clone()))]);
+    let mut recorder = Recorder {
+        written: vec![],
+        largest: 0,
+        limit: usize::MAX,
+    };
+    value.write_to(&mut recorder).unwrap();
+    assert_eq!(recorder.written, format!("[{}]", raw).into_bytes());
+    // written as it is, not copied into the buffer after `[`
+    assert_eq!(recorder.largest, raw.len());
+    let mut pretty = vec![];
+    parse("[1]").write_pretty_to(&mut pretty, 4).unwrap();
+    assert_eq!(pretty, b"[\n    1\n]");
+    let mut compact = vec![];
+    parse("{\"a\": [true]}").write_to(&mut compact).unwrap();
+    assert_eq!(compact, b"{\"a\":[true]}");
+
+    for limit in vec![0, 20_000] {
+        let mut recorder = Recorder {
+            written: vec![],
+            largest: 0,
+            limit,
+        };
+        let err = value.write_to(&mut recorder).unwrap_err();
+        assert_eq!(err.to_string(), "disk full");
+    }
+}
+
+#[test]
+fn test_ascii_escaping() {
+    let config = SerializerConfig {
+        escaping: Escaping::Ascii,
+        ..SerializerConfig::compact()
+    };
+    for s in vec![
+        ("\"plain\\n\"", "\"plain\\n\""),
+        (
+            "\"юникод\"",
+            "\"\\u044e\\u043d\\u0438\\u043a\\u043e\\u0434\"",
+        ),
+        ("\"a😀b\"", "\"a\\ud83d\\ude00b\""),
+        (
+            "{\"ключ\": \"é\\\"\"}",
+            "{\"\\u043a\\u043b\\u044e\\u0447\":\"\\u00e9\\\"\"}",
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        let value = parse(s.0);
+        let written = value.to_string_with(&config);
+        assert_eq!(written, s.1);
+        assert!(written.is_ascii());
+        assert_eq!(parse(&written), value);
+    }
+    let raw = JSONValue::JSONArray(vec![Box::new(JSONValue::raw("{\"ü\": \"✓\"}").unwrap())]);
+    assert_eq!(raw.to_string_with(&config), "[{\"\\u00fc\": \"\\u2713\"}]");
+    assert_eq!(raw.to_string(), "[{\"ü\": \"✓\"}]");
+}
+
+#[test]
+fn test_html_escaping() {
+    let config = SerializerConfig {
+        escape_html: true,
+        ..SerializerConfig::compact()
+    };
+    for s in vec![
+        (
+            "\"</script><!-- a & b\"",
+            "\"\\u003c/script\\u003e\\u003c!-- a \\u0026 b\"",
+        ),
+        ("\"line\\u2028para\\u2029é\"", "\"line\\u2028para\\u2029é\""),
+        ("{\"<k>\": [1]}", "{\"\\u003ck\\u003e\":[1]}"),
+    ] {
+        println!("Checking {}", s.0);
+        let value = parse(s.0);
+        let written = value.to_string_with(&config);
+        assert_eq!(written, s.1);
+        assert_eq!(parse(&written), value);
+    }
+    let raw = JSONValue::raw("[\"<b>\", 1]").unwrap();
+    assert_eq!(raw.to_string_with(&config), "[\"\\u003cb\\u003e\", 1]");
+    let both = SerializerConfig {
+        escaping: Escaping::Ascii,
+        ..config
+    };
+    assert_eq!(parse("\"é&\"").to_string_with(&both), "\"\\u00e9\\u0026\"");
+}
@@ -0,0 +1,438 @@
+use super::*;
+use patch::{JSONPatchError, PatchOperation};
+use pointer::{parse_index, parse_pointer};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+#[cfg(test)]
+mod tests;
+
+const END_OF_ARRAY: &str = "-";
+
+/// Object map of `SharedJSONValue`, picked by the same features as `JSONMap`.
+#[cfg(not(any(feature = "preserve_order", feature = "btree_map")))]
+pub type SharedMap = HashMap<String, SharedJSONValue>;
+#[cfg(feature = "preserve_order")]
+pub type SharedMap = map::OrderedMap<String, SharedJSONValue>;
+#[cfg(all(feature = "btree_map", not(feature = "preserve_order")))]
+pub type SharedMap = std::collections::BTreeMap<String, SharedJSONValue>;
+
+/// Counterpart of `JSONValue` with strings and containers behind `Arc`,
+/// so cloning is O(1) whatever the size of the document and clones can be sent
+/// to other threads. Convert with `From<JSONValue>` and `to_value`.
+///
+/// Mutation is copy-on-write: `pointer_mut`, `set` and `remove` copy only the
+/// containers on the path to the target that are shared with other clones,
+/// everything else stays shared. A derived variant of a large base document
+/// costs as much as the path it changes.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SharedJSONValue {
+    JSONNull(),
+    JSONString(Arc<str>),
+    JSONBool(bool),
+    JSONNumber(f64),
+    JSONObject(Arc<SharedMap>),
+    JSONArray(Arc<Vec<SharedJSONValue>>),
+}
+
+impl SharedJSONValue {
+    /// Deep copies the value back into an owned `JSONValue`.
+    pub fn to_value(&self) -> JSONValue {
+        match *self {
+            SharedJSONValue::JSONNull() => return JSONValue::JSONNull(),
+            SharedJSONValue::JSONString(ref s) => return JSONValue::JSONString(s.to_string()),
+            SharedJSONValue::JSONBool(b) => return JSONValue::JSONBool(b),
+            SharedJSONValue::JSONNumber(n) => return JSONValue::JSONNumber(n),
+            SharedJSONValue::JSONObject(ref members) => {
+                let mut map = JSONMap::new();
+                for (key, member) in members.iter() {
+                    map.insert(key.clone(), Box::new(member.to_value()));
+                }
+                return JSONValue::JSONObject(map);
+            }
+            SharedJSONValue::JSONArray(ref items) => {
+                return JSONValue::JSONArray(
+                    items.iter().map(|item| Box::new(item.to_value())).collect(),
+                )
+            }
+        }
+    }
+
+    /// Member of an object, `None` for missing keys and non-objects.
+    pub fn get(&self, key: &str) -> Option<&SharedJSONValue> {
+        match *self {
+            SharedJSONValue::JSONObject(ref members) => return members.get(key),
+            _ => return None,
+        }
+    }
+
+    /// Item of an array, `None` when out of bounds and for non-arrays.
+    pub fn get_index(&self, index: usize) -> Option<&SharedJSONValue> {
+        match *self {
+            SharedJSONValue::JSONArray(ref items) => return items.get(index),
+            _ => return None,
+        }
+    }
+
+    /// Looks up a value by JSON Pointer, like `JSONValue::pointer`.
+    pub fn pointer(&self, pointer: &str) -> Option<&SharedJSONValue> {
+        return self.tokens(&parse_pointer(pointer)?);
+    }
+
+    /// Mutable access by JSON Pointer. Containers on the path that are shared
+    /// with other clones are copied (shallowly) first, so they never see the change.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut SharedJSONValue> {
+        let tokens = parse_pointer(pointer)?;
+        self.tokens(&tokens)?;
+        return self.tokens_mut(&tokens);
+    }
+
+    fn tokens(&self, tokens: &[String]) -> Option<&SharedJSONValue> {
+        let mut current = self;
+        for token in tokens {
+            current = match *current {
+                SharedJSONValue::JSONObject(ref map) => map.get(token)?,
+                SharedJSONValue::JSONArray(ref items) => items.get(parse_index(token)?)?,
+                _ => return None,
+            };
+        }
+        return Some(current);
+    }
+
+    // Callers check the path with `tokens` first, so that a missing
+    // target doesn't copy the containers leading to it
+    fn tokens_mut(&mut self, tokens: &[String]) -> Option<&mut SharedJSONValue> {
+        let mut current = self;
+        for token in tokens {
+            current = match *current {
+                SharedJSONValue::JSONObject(ref mut map) => Arc::make_mut(map).get_mut(token)?,
+                SharedJSONValue::JSONArray(ref mut items) => {
+                    Arc::make_mut(items).get_mut(parse_index(token)?)?
+                }
+                _ => return None,
+            };
+        }
+        return Some(current);
+    }
+
+    /// Sets an object member or replaces an array item, copying only the path to it.
+    /// `-` appends to an array; the empty pointer replaces the whole value.
+    pub fn set(&mut self, path: &str, value: SharedJSONValue) -> Result<(), JSONPatchError> {
+        let mut tokens =
+            parse_pointer(path).ok_or_else(|| make_err(format!("Invalid pointer {}", path)))?;
+        let token = match tokens.pop() {
+            Some(token) => token,
+            None => {
+                *self = value;
+                return Ok(());
+            }
+        };
+        let index = match self.tokens(&tokens) {
+            Some(&SharedJSONValue::JSONObject(_)) => None,
+            Some(SharedJSONValue::JSONArray(items)) => {
+                if token == END_OF_ARRAY {
+                    Some(items.len())
+                } else {
+                    match parse_index(&token) {
+                        Some(i) if i < items.len() => Some(i),
+                        _ => return Err(make_err(format!("Index out of bounds in {}", path))),
+                    }
+                }
+            }
+            Some(_) => return Err(make_err(format!("Parent of {} is not a container", path))),
+            None => return Err(make_err(format!("Parent of {} doesn't exist", path))),
+        };
+        match self.tokens_mut(&tokens) {
+            Some(SharedJSONValue::JSONObject(map)) => {
+                Arc::make_mut(map).insert(token, value);
+            }
+            Some(SharedJSONValue::JSONArray(items)) => {
+                let items = Arc::make_mut(items);
+                match index {
+                    Some(i) if i < items.len() => items[i] = value,
+                    _ => items.push(value),
+                }
+            }
+            _ => unreachable!("checked above"),
+        }
+        return Ok(());
+    }
+
+    /// Removes and returns the value at the pointer, copying only the path to it.
+    pub fn remove(&mut self, path: &str) -> Result<SharedJSONValue, JSONPatchError> {
+        let tokens =
+            parse_pointer(path).ok_or_else(|| make_err(format!("Invalid pointer {}", path)))?;
+        let (token, parent) = tokens
+            .split_last()
+            .ok_or_else(|| make_err("Can't remove the whole document".to_owned()))?;
+        if self.tokens(&tokens).is_none() {
+            return Err(make_err(format!("Nothing to remove at {}", path)));
+        }
+        match self.tokens_mut(parent) {
+            Some(SharedJSONValue::JSONObject(map)) => {
+                return Ok(Arc::make_mut(map).remove(token).unwrap());
+            }
+            Some(SharedJSONValue::JSONArray(items)) => {
+                let i = parse_index(token).unwrap();
+                return Ok(Arc::make_mut(items).remove(i));
+            }
+            _ => unreachable!("checked above"),
+        }
+    }
+
+    /// Applies an RFC 6902 patch like `JSONValue::apply_patch`, atomically,
+    /// copying only the paths the operations change.
+    pub fn apply_patch(&mut self, patch: &[PatchOperation]) -> Result<(), JSONPatchError> {
+        let mut result = self.clone();
+        for op in patch {
+            result.apply_operation(op)?;
+        }
+        *self = result;
+        return Ok(());
+    }
+
+    fn apply_operation(&mut self, op: &PatchOperation) -> Result<(), JSONPatchError> {
+        match *op {
+            PatchOperation::Add {
+                ref path,
+                ref value,
+            } => return self.add(path, SharedJSONValue::from(value)),
+            PatchOperation::Remove { ref path } => return self.remove(path).map(|_| ()),
+            PatchOperation::Replace {
+                ref path,
+                ref value,
+            } => {
+                let target = self
+                    .pointer_mut(path)
+                    .ok_or_else(|| make_err(format!("Nothing to replace at {}", path)))?;
+                *target = SharedJSONValue::from(value);
+                return Ok(());
+            }
+            PatchOperation::Move { ref from, ref path } => {
+                if path.starts_with(&format!("{}/", from)) {
+                    return Err(make_err(format!(
+                        "Unable to move {} into its own child",
+                        from
+                    )));
+                }
+                let value = self.remove(from)?;
+                return self.add(path, value);
+            }
+            PatchOperation::Copy { ref from, ref path } => {
+                let value = self
+                    .pointer(from)
+                    .ok_or_else(|| make_err(format!("Nothing to copy at {}", from)))?
+                    .clone();
+                return self.add(path, value);
+            }
+            PatchOperation::Test {
+                ref path,
+                ref value,
+            } => {
+                if self.pointer(path) != Some(&SharedJSONValue::from(value)) {
+                    return Err(make_err(format!("Test failed at {}", path)));
+                }
+                return Ok(());
+            }
+        }
+    }
+
+    // RFC 6902 `add`: unlike `set`, inserts into arrays instead of replacing
+    fn add(&mut self, path: &str, value: SharedJSONValue) -> Result<(), JSONPatchError> {
+        let mut tokens =
+            parse_pointer(path).ok_or_else(|| make_err(format!("Invalid pointer {}", path)))?;
+        let token = match tokens.pop() {
+            Some(token) => token,
+            None => {
+                *self = value;
+                return Ok(());
+            }
+        };
+        if let Some(SharedJSONValue::JSONArray(items)) = self.tokens(&tokens) {
+            let index = if token == END_OF_ARRAY {
+                items.len()
+            } else {
+                parse_index(&token).ok_or_else(|| make_err(format!("Invalid index in {}", path)))?
+            };
+            if index > items.len() {
+                return Err(make_err(format!("Index out of bounds in {}", path)));
+            }
+            if let Some(SharedJSONValue::JSONArray(items)) = self.tokens_mut(&tokens) {
+                Arc::make_mut(items).insert(index, value);
+            }
+            return Ok(());
+        }
+        return self.set(path, value);
+    }
+
+    /// Whether both values point to the same allocation, i.e. one is a clone
+    /// of the other. Always false for null, booleans and numbers.
+    pub fn ptr_eq(a: &SharedJSONValue, b: &SharedJSONValue) -> bool {
+        match (a, b) {
+            (SharedJSONValue::JSONString(a), SharedJSONValue::JSONString(b)) => {
+                return Arc::ptr_eq(a, b)
+            }
+            (SharedJSONValue::JSONObject(a), SharedJSONValue::JSONObject(b)) => {
+                return Arc::ptr_eq(a, b)
+            }
+            (SharedJSONValue::JSONArray(a), SharedJSONValue::JSONArray(b)) => {
+                return Arc::ptr_eq(a, b)
+            }
+            _ => return false,
+        }
+    }
+
+    /// Copy of the value where identical strings and containers share one
+    /// allocation, found by structural hashing. Shrinks documents with repeated
+    /// blocks, e.g. `SharedJSONValue::from(value).dedup_subtrees()`; equality
+    /// and serialization are unaffected. Objects are identical regardless of key
+    /// order, except with `preserve_order`, where key order is serialized too.
+    pub fn dedup_subtrees(&self) -> SharedJSONValue {
+        return dedup(self, &mut HashMap::new()).0;
+    }
+}
+
+// Canonical copy of the value and its structural hash. Children are made
+// canonical first, so candidates with the same hash only need their children
+// compared by pointer.
+fn dedup(
+    value: &SharedJSONValue,
+    seen: &mut HashMap<u64, Vec<SharedJSONValue>>,
+) -> (SharedJSONValue, u64) {
+    let mut hasher = DefaultHasher::new();
+    let value = match *value {
+        SharedJSONValue::JSONNull() => {
+            0u8.hash(&mut hasher);
+            return (value.clone(), hasher.finish());
+        }
+        SharedJSONValue::JSONBool(b) => {
+            1u8.hash(&mut hasher);
+            b.hash(&mut hasher);
+            return (value.clone(), hasher.finish());
+        }
+        SharedJSONValue::JSONNumber(n) => {
+            2u8.hash(&mut hasher);
+            // 0.0 and -0.0 are equal, so they must hash the same
+            let n = if n == 0.0 { 0.0 } else { n };
+            n.to_bits().hash(&mut hasher);
+            return (value.clone(), hasher.finish());
+        }
+        SharedJSONValue::JSONString(ref s) => {
+            3u8.hash(&mut hasher);
+            s.hash(&mut hasher);
+            value.clone()
+        }
+        SharedJSONValue::JSONArray(ref items) => {
+            4u8.hash(&mut hasher);
+            items.len().hash(&mut hasher);
+            let mut canonical = Vec::with_capacity(items.len());
+            for item in items.iter() {
+                let (item, hash) = dedup(item, seen);
+                hash.hash(&mut hasher);
+                canonical.push(item);
+            }
+            SharedJSONValue::JSONArray(Arc::new(canonical))
+        }
+        SharedJSONValue::JSONObject(ref members) => {
+            // members are summed, so the iteration order doesn't matter unless
+            // it's preserved, in which case each member's position is hashed too
+            let mut combined: u64 = 0;
+            let mut canonical = SharedMap::new();
+            for (i, (key, member)) in members.iter().enumerate() {
+                let (member, hash) = dedup(member, seen);
+                let mut member_hasher = DefaultHasher::new();
+                if cfg!(feature = "preserve_order") {
+                    i.hash(&mut member_hasher);
+                }
+                key.hash(&mut member_hasher);
+                hash.hash(&mut member_hasher);
+                combined = combined.wrapping_add(member_hasher.finish());
+                canonical.insert(key.clone(), member);
+            }
+            5u8.hash(&mut hasher);
+            members.len().hash(&mut hasher);
+            combined.hash(&mut hasher);
+            SharedJSONValue::JSONObject(Arc::new(canonical))
+        }
+    };
+    let hash = hasher.finish();
+    let candidates = seen.entry(hash).or_default();
+    for candidate in candidates.iter() {
+        if shallow_eq(candidate, &value) {
+            return (candidate.clone(), hash);
+        }
+    }
+    candidates.push(value.clone());
+    return (value, hash);
+}
+
+// Equality of values whose children are canonical
+fn shallow_eq(a: &SharedJSONValue, b: &SharedJSONValue) -> bool {
+    match (a, b) {
+        (SharedJSONValue::JSONArray(a), SharedJSONValue::JSONArray(b)) => {
+            return a.len() == b.len() && a.iter().zip(b.iter()).all(|(a, b)| child_eq(a, b));
+        }
+        (SharedJSONValue::JSONObject(a), SharedJSONValue::JSONObject(b)) => {
+            return a.len() == b.len()
+                && (!cfg!(feature = "preserve_order") || a.keys().eq(b.keys()))
+                && a.iter()
+                    .all(|(key, a)| b.get(key).is_some_and(|b| child_eq(a, b)));
+        }
+        _ => return a == b,
+    }
+}
+
+fn child_eq(a: &SharedJSONValue, b: &SharedJSONValue) -> bool {
+    match *a {
+        SharedJSONValue::JSONString(_)
+        | SharedJSONValue::JSONArray(_)
+        | SharedJSONValue::JSONObject(_) => return SharedJSONValue::ptr_eq(a, b),
+        _ => return a == b,
+    }
+}
+
+impl<'a> From<&'a JSONValue> for SharedJSONValue {
+    fn from(value: &'a JSONValue) -> SharedJSONValue {
+        match *value {
+            JSONValue::JSONNull() => return SharedJSONValue::JSONNull(),
+            JSONValue::JSONString(ref s) => {
+                return SharedJSONValue::JSONString(Arc::from(s.as_str()))
+            }
+            JSONValue::JSONBool(b) => return SharedJSONValue::JSONBool(b),
+            JSONValue::JSONNumber(n) => return SharedJSONValue::JSONNumber(n),
+            JSONValue::JSONRaw(_) => return SharedJSONValue::from(&*value.expand_raw()),
+            JSONValue::JSONObject(ref members) => {
+                let mut map = SharedMap::new();
+                for (key, member) in members.iter() {
+                    map.insert(key.clone(), SharedJSONValue::from(&**member));
+                }
+                return SharedJSONValue::JSONObject(Arc::new(map));
+            }
+            JSONValue::JSONArray(ref items) => {
+                return SharedJSONValue::JSONArray(Arc::new(
+                    items
+                        .iter()
+                        .map(|item| SharedJSONValue::from(&**item))
+                        .collect(),
+                ))
+            }
+        }
+    }
+}
+
+impl From<JSONValue> for SharedJSONValue {
+    fn from(value: JSONValue) -> SharedJSONValue {
+        return SharedJSONValue::from(&value);
+    }
+}
+
+impl<'a> From<&'a SharedJSONValue> for JSONValue {
+    fn from(value: &'a SharedJSONValue) -> JSONValue {
+        return value.to_value();
+    }
+}
+
+fn make_err(s: String) -> JSONPatchError {
+    JSONPatchError { reason: s }
+}
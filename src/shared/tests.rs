@@ -0,0 +1,214 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use std::thread;
+
+#[test]
+fn test_round_trip() {
+    for s in vec![
+        "null",
+        "true",
+        "1.5",
+        r#""text""#,
+        "[]",
+        r#"{"a": [1, {"b": null}], "c": "d"}"#,
+    ] {
+        println!("Checking {}", s);
+        let value: JSONValue = s.parse().unwrap();
+        let shared = SharedJSONValue::from(&value);
+        assert_eq!(shared.to_value(), value);
+        assert_eq!(JSONValue::from(&shared), value);
+    }
+}
+
+#[test]
+fn test_cheap_clone() {
+    let value: JSONValue = r#"{"a": [1, {"b": "c"}]}"#.parse().unwrap();
+    let shared = SharedJSONValue::from(value);
+    let copy = shared.clone();
+    assert!(SharedJSONValue::ptr_eq(&shared, &copy));
+    assert!(SharedJSONValue::ptr_eq(
+        shared.get("a").unwrap(),
+        copy.get("a").unwrap()
+    ));
+    assert!(!SharedJSONValue::ptr_eq(
+        &shared,
+        &SharedJSONValue::from(shared.to_value())
+    ));
+
+    let handle = thread::spawn(move || {
+        return copy
+            .get("a")
+            .unwrap()
+            .get_index(1)
+            .unwrap()
+            .get("b")
+            .cloned();
+    });
+    assert_eq!(
+        handle.join().unwrap(),
+        Some(SharedJSONValue::JSONString(Arc::from("c")))
+    );
+    assert_eq!(shared.get("missing"), None);
+    assert_eq!(shared.get_index(0), None);
+}
+
+#[test]
+fn test_copy_on_write() {
+    let base = SharedJSONValue::from(
+        r#"{"db": {"host": "localhost", "port": 5432}, "features": ["a", "b"], "big": {"x": [1, 2, 3]}}"#
+            .parse::<JSONValue>()
+            .unwrap(),
+    );
+    let mut tenant = base.clone();
+    tenant
+        .set(
+            "/db/host",
+            SharedJSONValue::JSONString(Arc::from("tenant.db")),
+        )
+        .unwrap();
+    tenant
+        .set("/features/-", SharedJSONValue::JSONString(Arc::from("c")))
+        .unwrap();
+    assert_eq!(
+        tenant.remove("/db/port").unwrap(),
+        SharedJSONValue::JSONNumber(5432.0)
+    );
+
+    assert_eq!(
+        base.to_value(),
+        r#"{"db": {"host": "localhost", "port": 5432}, "features": ["a", "b"], "big": {"x": [1, 2, 3]}}"#
+            .parse()
+            .unwrap()
+    );
+    assert_eq!(
+        tenant.to_value(),
+        r#"{"db": {"host": "tenant.db"}, "features": ["a", "b", "c"], "big": {"x": [1, 2, 3]}}"#
+            .parse()
+            .unwrap()
+    );
+    assert!(SharedJSONValue::ptr_eq(
+        base.get("big").unwrap(),
+        tenant.get("big").unwrap()
+    ));
+    assert!(!SharedJSONValue::ptr_eq(
+        base.get("db").unwrap(),
+        tenant.get("db").unwrap()
+    ));
+
+    let copy = tenant.clone();
+    *tenant.pointer_mut("/features/0").unwrap() = SharedJSONValue::JSONNull();
+    assert_eq!(
+        tenant.pointer("/features/0"),
+        Some(&SharedJSONValue::JSONNull())
+    );
+    assert_eq!(
+        copy.pointer("/features/0"),
+        Some(&SharedJSONValue::JSONString(Arc::from("a")))
+    );
+}
+
+#[test]
+fn test_copy_on_write_errors() {
+    let mut value = SharedJSONValue::from(r#"{"a": [1], "s": "x"}"#.parse::<JSONValue>().unwrap());
+    let before = value.clone();
+    for s in vec![
+        ("a", "Invalid pointer a"),
+        ("/missing/b", "Parent of /missing/b doesn't exist"),
+        ("/a/1", "Index out of bounds in /a/1"),
+        ("/s/0", "Parent of /s/0 is not a container"),
+    ] {
+        println!("Checking {}", s.0);
+        let err = value.set(s.0, SharedJSONValue::JSONNull()).unwrap_err();
+        assert_eq!(err.reason, s.1);
+    }
+    for s in vec!["", "/b", "/a/1", "/a/x", "/s/0"] {
+        println!("Checking {}", s);
+        assert!(value.remove(s).is_err());
+    }
+    assert!(SharedJSONValue::ptr_eq(&value, &before));
+    value.set("", SharedJSONValue::JSONBool(true)).unwrap();
+    assert_eq!(value, SharedJSONValue::JSONBool(true));
+}
+
+#[test]
+fn test_apply_patch() {
+    let document: JSONValue = r#"{"a": [1, 2], "b": {"c": "x"}, "big": {"x": [1]}}"#
+        .parse()
+        .unwrap();
+    for s in vec![
+        r#"[{"op": "add", "path": "/a/1", "value": 9}, {"op": "add", "path": "/a/-", "value": [3]}]"#,
+        r#"[{"op": "remove", "path": "/a/0"}, {"op": "replace", "path": "/b/c", "value": {"d": 1}}]"#,
+        r#"[{"op": "move", "from": "/b/c", "path": "/a/0"}, {"op": "copy", "from": "/a", "path": "/e"}]"#,
+        r#"[{"op": "test", "path": "/b", "value": {"c": "x"}}, {"op": "add", "path": "", "value": 1}]"#,
+        r#"[{"op": "add", "path": "/a/3", "value": 0}]"#,
+        r#"[{"op": "remove", "path": "/a/0"}, {"op": "test", "path": "/a/0", "value": 1}]"#,
+        r#"[{"op": "move", "from": "/b", "path": "/b/c/d"}]"#,
+        r#"[{"op": "replace", "path": "/z", "value": 0}]"#,
+    ] {
+        println!("Checking {}", s);
+        let patch = patch::parse_patch(&s.parse().unwrap()).unwrap();
+        let mut expected = document.clone();
+        let expected = expected.apply_patch(&patch).map(|_| expected);
+        let original = SharedJSONValue::from(&document);
+        let mut shared = original.clone();
+        match (shared.apply_patch(&patch), expected) {
+            (Ok(()), Ok(expected)) => {
+                assert_eq!(shared.to_value(), expected);
+                if let Some(big) = shared.get("big") {
+                    assert!(SharedJSONValue::ptr_eq(big, original.get("big").unwrap()));
+                }
+            }
+            (Err(err), Err(expected)) => {
+                assert_eq!(err.reason, expected.reason);
+                assert!(SharedJSONValue::ptr_eq(&shared, &original));
+            }
+            (result, expected) => panic!("{:?} vs {:?}", result, expected),
+        }
+    }
+}
+
+#[test]
+fn test_dedup_subtrees() {
+    let value: JSONValue = r#"{
+        "a": {"retry": {"count": 3, "delay": [1, 2]}, "name": "svc"},
+        "b": {"name": "svc", "retry": {"delay": [1, 2], "count": 3}},
+        "c": {"retry": {"count": 4, "delay": [1, 2]}, "name": "svc"},
+        "zeros": [0.0, -0.0]
+    }"#
+    .parse()
+    .unwrap();
+    let shared = SharedJSONValue::from(&value);
+    let deduped = shared.dedup_subtrees();
+    assert_eq!(deduped, shared);
+    assert_eq!(deduped.to_value(), value);
+    let at = |p: &str| deduped.pointer(p).unwrap();
+    // with preserve_order the key order is serialized, so it must match too
+    assert_eq!(
+        SharedJSONValue::ptr_eq(at("/a"), at("/b")),
+        !cfg!(feature = "preserve_order")
+    );
+    #[cfg(feature = "preserve_order")]
+    assert_eq!(deduped.to_value().to_string(), value.to_string());
+    assert!(!SharedJSONValue::ptr_eq(at("/a"), at("/c")));
+    assert!(!SharedJSONValue::ptr_eq(at("/a/retry"), at("/c/retry")));
+    assert!(SharedJSONValue::ptr_eq(
+        at("/a/retry/delay"),
+        at("/c/retry/delay")
+    ));
+    assert!(SharedJSONValue::ptr_eq(at("/a/name"), at("/c/name")));
+    assert!(!SharedJSONValue::ptr_eq(
+        shared.pointer("/a").unwrap(),
+        shared.pointer("/b").unwrap()
+    ));
+
+    // shared subtrees are still copied on write
+    let mut changed = deduped.clone();
+    changed
+        .set("/a/retry/count", SharedJSONValue::JSONNumber(5.0))
+        .unwrap();
+    assert_eq!(
+        changed.pointer("/b/retry/count"),
+        Some(&SharedJSONValue::JSONNumber(3.0))
+    );
+}
@@ -0,0 +1,94 @@
+use super::*;
+use assert::{json_mismatches_with, Mode};
+use serializer::SerializerConfig;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+#[cfg(test)]
+mod tests;
+
+/// Set to anything to make `assert_snapshot` rewrite snapshot files
+/// with the actual values instead of comparing.
+pub const UPDATE_ENV: &str = "RSJSON_UPDATE_SNAPSHOTS";
+
+#[derive(Debug, Clone)]
+pub struct JSONSnapshotError {
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SnapshotOptions {
+    /// Numbers within this distance of the expected ones match
+    pub float_tolerance: f64,
+    /// Indentation of written snapshot files
+    pub indent: usize,
+    /// Write keys sorted, so snapshot files don't change with map iteration order
+    pub sort_keys: bool,
+    /// Rewrite the file instead of comparing. `UPDATE_ENV` turns this on too.
+    pub update: bool,
+}
+
+impl Default for SnapshotOptions {
+    fn default() -> SnapshotOptions {
+        return SnapshotOptions {
+            float_tolerance: 0.0,
+            indent: 2,
+            sort_keys: true,
+            update: false,
+        };
+    }
+}
+
+/// Compares the value with the JSON stored in the file, or writes the file
+/// in update mode. A missing file is an error unless updating.
+pub fn check_snapshot(
+    path: &Path,
+    actual: &JSONValue,
+    options: &SnapshotOptions,
+) -> Result<(), JSONSnapshotError> {
+    if options.update || env::var_os(UPDATE_ENV).is_some() {
+        let config = SerializerConfig {
+            indent: Some(options.indent),
+            sort_keys: options.sort_keys,
+            ..SerializerConfig::default()
+        };
+        let mut text = actual.to_string_with(&config);
+        text.push('\n');
+        return fs::write(path, text)
+            .map_err(|e| make_err(format!("Can't write snapshot {}: {}", path.display(), e)));
+    }
+    let text = fs::read_to_string(path).map_err(|e| {
+        make_err(format!(
+            "Can't read snapshot {}: {}. Set {} to create it",
+            path.display(),
+            e,
+            UPDATE_ENV
+        ))
+    })?;
+    let expected: JSONValue = text
+        .parse()
+        .map_err(|e| make_err(format!("Invalid snapshot {}: {}", path.display(), e)))?;
+    let mismatches = json_mismatches_with(actual, &expected, Mode::Equal, options.float_tolerance);
+    if !mismatches.is_empty() {
+        return Err(make_err(format!(
+            "Snapshot {} doesn't match:\n  {}\nSet {} to update it",
+            path.display(),
+            mismatches.join("\n  "),
+            UPDATE_ENV
+        )));
+    }
+    return Ok(());
+}
+
+/// Panicking version of `check_snapshot` for tests.
+#[track_caller]
+pub fn assert_snapshot<P: AsRef<Path>>(path: P, actual: &JSONValue, options: &SnapshotOptions) {
+    if let Err(e) = check_snapshot(path.as_ref(), actual, options) {
+        panic!("{}", e.reason);
+    }
+}
+
+fn make_err(s: String) -> JSONSnapshotError {
+    JSONSnapshotError { reason: s }
+}
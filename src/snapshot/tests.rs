@@ -0,0 +1,57 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use std::process;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_snapshot() {
+    let dir = env::temp_dir().join(format!("rsjson-snapshot-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("value.json");
+    let value = parse(r#"{"b": [1, 0.1], "a": null}"#);
+
+    let missing = check_snapshot(&path, &value, &SnapshotOptions::default()).unwrap_err();
+    assert!(missing.reason.starts_with("Can't read snapshot"));
+
+    let update = SnapshotOptions {
+        update: true,
+        ..SnapshotOptions::default()
+    };
+    check_snapshot(&path, &value, &update).unwrap();
+    let written = fs::read_to_string(&path).unwrap();
+
+    let close = parse(r#"{"b": [1, 0.1000001], "a": null}"#);
+    let tolerant = SnapshotOptions {
+        float_tolerance: 1e-6,
+        ..SnapshotOptions::default()
+    };
+    let strict = check_snapshot(&path, &close, &SnapshotOptions::default());
+    let loose = check_snapshot(&path, &close, &tolerant);
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(
+        written,
+        "{\n  \"a\": null,\n  \"b\": [\n    1,\n    0.1\n  ]\n}\n"
+    );
+    assert!(strict
+        .unwrap_err()
+        .reason
+        .contains("/b/1: expected 0.1, got 0.1000001"));
+    loose.unwrap();
+}
+
+#[test]
+fn test_assert_snapshot() {
+    let path = env::temp_dir().join(format!("rsjson-snapshot-{}.json", process::id()));
+    fs::write(&path, "[1]").unwrap();
+    assert_snapshot(&path, &parse("[1]"), &SnapshotOptions::default());
+    let result = std::panic::catch_unwind(|| {
+        assert_snapshot(&path, &parse("[2]"), &SnapshotOptions::default());
+    });
+    fs::remove_file(&path).unwrap();
+    assert!(result.is_err());
+}
@@ -0,0 +1,68 @@
+use super::*;
+use encoding::{decode, decode_lossy};
+use ndjson::{parse_json_seq, parse_ndjson, RECORD_SEPARATOR};
+
+#[cfg(test)]
+mod tests;
+
+// Bytes looked at by `detect_format`, enough for the first line of most NDJSON
+const SNIFF_BYTES: usize = 64 * 1024;
+
+/// Input format told by `detect_format`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Detected {
+    /// A single JSON value
+    Json,
+    /// Newline-delimited JSON, several values one per line
+    Ndjson,
+    /// JSON text sequence (RFC 7464), records starting with 0x1E
+    JsonSeq,
+    /// Empty input, or something that doesn't start like JSON text.
+    /// Binary encodings such as CBOR and MessagePack end up here,
+    /// since the crate has no decoders for them.
+    Unknown,
+}
+
+/// Tells the format of an ingestion body from its first bytes, in any of the
+/// encodings `encoding::decode` handles. Input with a complete JSON value on the
+/// first line followed by more lines is NDJSON; single-value NDJSON is
+/// indistinguishable from JSON and is reported as `Json`.
+pub fn detect_format(bytes: &[u8]) -> Detected {
+    let head = decode_lossy(&bytes[..bytes.len().min(SNIFF_BYTES)]);
+    let text = head.trim_start();
+    match text.chars().next() {
+        Some(RECORD_SEPARATOR) => return Detected::JsonSeq,
+        Some('{') | Some('[') | Some('"') | Some('-') | Some('t') | Some('f') | Some('n') => (),
+        Some(ch) if ch.is_ascii_digit() => (),
+        _ => return Detected::Unknown,
+    }
+    let (first, rest) = match text.find('\n') {
+        Some(end) => (&text[..end], &text[end..]),
+        None => return Detected::Json,
+    };
+    if rest.trim().is_empty() || first.parse::<JSONValue>().is_err() {
+        return Detected::Json;
+    }
+    return Detected::Ndjson;
+}
+
+/// Detects the format of `bytes` and parses them accordingly, a JSON value
+/// coming out as a single-element list. Error positions are byte offsets
+/// into the decoded text.
+pub fn parse_detected(bytes: &[u8]) -> Result<(Detected, Vec<JSONValue>), JSONParseError> {
+    let detected = detect_format(bytes);
+    if detected == Detected::Unknown {
+        return Err(JSONParseError {
+            reason: "Unrecognized input format".to_owned(),
+            position: Some(0),
+            kind: JSONParseErrorKind::Syntax,
+        });
+    }
+    let text = decode(bytes)?;
+    let values = match detected {
+        Detected::Ndjson => parse_ndjson(&text)?,
+        Detected::JsonSeq => parse_json_seq(&text)?,
+        _ => vec![text.parse()?],
+    };
+    return Ok((detected, values));
+}
@@ -0,0 +1,60 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+#[test]
+fn test_detect_format() {
+    for s in vec![
+        ("{\"a\": 1}", Detected::Json),
+        ("{\n  \"a\": 1\n}\n", Detected::Json),
+        ("[1,\n2]", Detected::Json),
+        ("  42\n\n", Detected::Json),
+        ("{\"a\": 1}\n{\"a\": 2}\n", Detected::Ndjson),
+        ("\n1\r\n2", Detected::Ndjson),
+        ("\u{1E}{\"a\": 1}\n", Detected::JsonSeq),
+        ("\n\u{1E}1\n", Detected::JsonSeq),
+        ("", Detected::Unknown),
+        ("  ", Detected::Unknown),
+        ("<xml/>", Detected::Unknown),
+        ("a=1&b=2", Detected::Unknown),
+    ] {
+        println!("Checking {:?}", s.0);
+        assert_eq!(detect_format(s.0.as_bytes()), s.1);
+    }
+    // CBOR map {"a": 1} and MessagePack map {"a": 1}
+    assert_eq!(detect_format(&[0xA1, 0x61, 0x61, 0x01]), Detected::Unknown);
+    assert_eq!(detect_format(&[0x81, 0xA1, 0x61, 0x01]), Detected::Unknown);
+    // UTF-16LE with a BOM
+    assert_eq!(
+        detect_format(&[0xFF, 0xFE, b'1', 0, b'\n', 0, b'2', 0]),
+        Detected::Ndjson
+    );
+}
+
+#[test]
+fn test_parse_detected() {
+    let (detected, values) = parse_detected(b"{\"a\": [1]}").unwrap();
+    assert_eq!(detected, Detected::Json);
+    assert_eq!(values, vec!["{\"a\": [1]}".parse().unwrap()]);
+
+    let (detected, values) = parse_detected(b"1\n\"x\"\n").unwrap();
+    assert_eq!(detected, Detected::Ndjson);
+    assert_eq!(
+        values,
+        vec![
+            JSONValue::JSONNumber(1.0),
+            JSONValue::JSONString("x".to_owned())
+        ]
+    );
+
+    let (detected, values) = parse_detected(b" \x1E[]\n\x1Enull\n").unwrap();
+    assert_eq!(detected, Detected::JsonSeq);
+    assert_eq!(
+        values,
+        vec![JSONValue::JSONArray(vec![]), JSONValue::JSONNull()]
+    );
+
+    assert_eq!(parse_detected(b"1\n[\n").unwrap_err().position, Some(4));
+    let err = parse_detected(b"<a/>").unwrap_err();
+    assert_eq!(err.reason, "Unrecognized input format");
+}
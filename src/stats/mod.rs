@@ -0,0 +1,197 @@
+use super::*;
+use pointer::escape_token;
+use serializer::{write_number, write_string};
+use std::mem::size_of;
+
+#[cfg(test)]
+mod tests;
+
+/// Shape and size profile of a document.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DocumentStats {
+    pub nulls: usize,
+    pub booleans: usize,
+    pub numbers: usize,
+    pub strings: usize,
+    pub arrays: usize,
+    pub objects: usize,
+    /// Nesting depth in containers: 0 for a scalar, 1 for `[]`
+    pub max_depth: usize,
+    /// Length of the compact serialization, in bytes
+    pub compact_size: usize,
+    /// Object members with the largest compact size as `(pointer, bytes)`, largest first
+    pub largest_members: Vec<(String, usize)>,
+    /// Arrays with the most items as `(pointer, length)`, largest first
+    pub largest_arrays: Vec<(String, usize)>,
+    /// How many times every key occurs across all objects
+    pub key_frequency: HashMap<String, usize>,
+}
+
+impl DocumentStats {
+    pub fn nodes(&self) -> usize {
+        return self.nulls
+            + self.booleans
+            + self.numbers
+            + self.strings
+            + self.arrays
+            + self.objects;
+    }
+
+    /// The `n` most frequent keys, ties broken alphabetically.
+    pub fn top_keys(&self, n: usize) -> Vec<(&str, usize)> {
+        let mut keys: Vec<(&str, usize)> = self
+            .key_frequency
+            .iter()
+            .map(|(key, count)| (key.as_str(), *count))
+            .collect();
+        keys.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        keys.truncate(n);
+        return keys;
+    }
+}
+
+impl JSONValue {
+    /// Approximate memory taken by the value tree in bytes: the value itself,
+    /// string and key capacities, container slots and map tables. Allocator
+    /// bookkeeping isn't counted. Meant for weighting cache entries.
+    pub fn deep_size_of(&self) -> usize {
+        return size_of::<JSONValue>() + heap_size(self);
+    }
+}
+
+fn heap_size(value: &JSONValue) -> usize {
+    match *value {
+        JSONValue::JSONString(ref s) | JSONValue::JSONRaw(ref s) => return s.capacity(),
+        JSONValue::JSONArray(ref items) => {
+            let slots = items.capacity() * size_of::<Box<JSONValue>>();
+            return slots + items.iter().map(|item| item.deep_size_of()).sum::<usize>();
+        }
+        JSONValue::JSONObject(ref members) => {
+            let contents: usize = members
+                .iter()
+                .map(|(key, member)| key.capacity() + member.deep_size_of())
+                .sum();
+            return map_table_size(members) + contents;
+        }
+        _ => return 0,
+    }
+}
+
+// A slot and a control byte per bucket, with buckets kept at most 7/8 full
+#[cfg(not(any(feature = "preserve_order", feature = "btree_map")))]
+fn map_table_size(members: &JSONMap) -> usize {
+    let buckets = members.capacity() * 8 / 7;
+    return buckets * (size_of::<(String, Box<JSONValue>)>() + 1);
+}
+
+// The entry vector, plus an index table holding a copy of every key
+#[cfg(feature = "preserve_order")]
+fn map_table_size(members: &JSONMap) -> usize {
+    let entries = members.capacity() * size_of::<(String, Box<JSONValue>)>();
+    let buckets = members.capacity() * 8 / 7;
+    let index = buckets * (size_of::<(String, usize)>() + 1);
+    let key_copies: usize = members.keys().map(|key| key.len()).sum();
+    return entries + index + key_copies;
+}
+
+// B-tree nodes are about two thirds full on average
+#[cfg(all(feature = "btree_map", not(feature = "preserve_order")))]
+fn map_table_size(members: &JSONMap) -> usize {
+    return members.len() * size_of::<(String, Box<JSONValue>)>() * 3 / 2;
+}
+
+/// Collects statistics in a single pass, keeping the `top` largest members and arrays.
+pub fn stats(value: &JSONValue, top: usize) -> DocumentStats {
+    let mut collector = Collector {
+        stats: DocumentStats::default(),
+        top,
+    };
+    collector.stats.compact_size = collector.visit(value, &mut String::new(), 0);
+    return collector.stats;
+}
+
+struct Collector {
+    stats: DocumentStats,
+    top: usize,
+}
+
+impl Collector {
+    // Returns the compact size of the value
+    fn visit(&mut self, value: &JSONValue, pointer: &mut String, depth: usize) -> usize {
+        match *value {
+            JSONValue::JSONNull() => {
+                self.stats.nulls += 1;
+                return "null".len();
+            }
+            JSONValue::JSONBool(b) => {
+                self.stats.booleans += 1;
+                return if b { "true".len() } else { "false".len() };
+            }
+            JSONValue::JSONNumber(n) => {
+                self.stats.numbers += 1;
+                let mut text = String::new();
+                let _ = write_number(&mut text, n);
+                return text.len();
+            }
+            JSONValue::JSONString(ref s) => {
+                self.stats.strings += 1;
+                return quoted_len(s);
+            }
+            JSONValue::JSONRaw(_) => return self.visit(&value.expand_raw(), pointer, depth),
+            JSONValue::JSONArray(ref items) => {
+                self.stats.arrays += 1;
+                self.stats.max_depth = self.stats.max_depth.max(depth + 1);
+                let mut size = 2 + items.len().saturating_sub(1);
+                let base = pointer.len();
+                for (i, item) in items.iter().enumerate() {
+                    pointer.push_str(&format!("/{}", i));
+                    size += self.visit(item, pointer, depth + 1);
+                    pointer.truncate(base);
+                }
+                let top = self.top;
+                insert_top(&mut self.stats.largest_arrays, top, pointer, items.len());
+                return size;
+            }
+            JSONValue::JSONObject(ref members) => {
+                self.stats.objects += 1;
+                self.stats.max_depth = self.stats.max_depth.max(depth + 1);
+                let mut size = 2 + members.len().saturating_sub(1);
+                let base = pointer.len();
+                // sorted, so ties in the top lists don't depend on hash order
+                let mut members: Vec<(&String, &Box<JSONValue>)> = members.iter().collect();
+                members.sort_by(|a, b| a.0.cmp(b.0));
+                for (key, member) in members {
+                    *self.stats.key_frequency.entry(key.clone()).or_insert(0) += 1;
+                    pointer.push('/');
+                    pointer.push_str(&escape_token(key));
+                    let member_size = self.visit(member, pointer, depth + 1);
+                    let top = self.top;
+                    insert_top(&mut self.stats.largest_members, top, pointer, member_size);
+                    pointer.truncate(base);
+                    size += quoted_len(key) + 1 + member_size;
+                }
+                return size;
+            }
+        }
+    }
+}
+
+fn quoted_len(s: &str) -> usize {
+    let mut text = String::new();
+    let _ = write_string(&mut text, s);
+    return text.len();
+}
+
+// Keeps `list` sorted by size, largest first, and no longer than `top`.
+// Equal sizes keep the order they were seen in.
+fn insert_top(list: &mut Vec<(String, usize)>, top: usize, pointer: &str, size: usize) {
+    if list.len() == top && list.last().is_none_or(|last| last.1 >= size) {
+        return;
+    }
+    let position = list
+        .iter()
+        .position(|entry| entry.1 < size)
+        .unwrap_or(list.len());
+    list.insert(position, (pointer.to_owned(), size));
+    list.truncate(top);
+}
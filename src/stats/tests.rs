@@ -0,0 +1,78 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+#[test]
+fn test_stats() {
+    let input = r#"{"users": [{"id": 1, "name": "a"}, {"id": 2, "name": null}], "ok": true, "tags": ["x\n"]}"#;
+    let value: JSONValue = input.parse().unwrap();
+    let stats = stats(&value, 2);
+    assert_eq!(
+        (
+            stats.objects,
+            stats.arrays,
+            stats.strings,
+            stats.numbers,
+            stats.booleans,
+            stats.nulls
+        ),
+        (3, 2, 2, 2, 1, 1)
+    );
+    assert_eq!(stats.nodes(), 11);
+    assert_eq!(stats.max_depth, 3);
+    assert_eq!(
+        stats.compact_size,
+        value.to_string_with(&Default::default()).len()
+    );
+    assert_eq!(
+        stats.largest_members,
+        vec![("/users".to_owned(), 42), ("/tags".to_owned(), 7)]
+    );
+    assert_eq!(
+        stats.largest_arrays,
+        vec![("/users".to_owned(), 2), ("/tags".to_owned(), 1)]
+    );
+    assert_eq!(stats.top_keys(3), vec![("id", 2), ("name", 2), ("ok", 1)]);
+}
+
+#[test]
+fn test_scalar_stats() {
+    for s in vec![
+        ("null", 4),
+        ("-1.5", 4),
+        ("\"é\\\"\"", 6),
+        ("[]", 2),
+        ("{}", 2),
+    ] {
+        let stats = stats(&s.0.parse().unwrap(), 5);
+        assert_eq!(stats.compact_size, s.1, "Size of {}", s.0);
+        assert_eq!(stats.nodes(), 1);
+    }
+}
+
+#[test]
+fn test_deep_size_of() {
+    let value_size = size_of::<JSONValue>();
+    assert_eq!(JSONValue::JSONNull().deep_size_of(), value_size);
+    assert_eq!(JSONValue::JSONNumber(1.5).deep_size_of(), value_size);
+    let s = String::with_capacity(100);
+    assert_eq!(JSONValue::JSONString(s).deep_size_of(), value_size + 100);
+
+    let mut items = Vec::with_capacity(4);
+    items.push(Box::new(JSONValue::JSONString("abc".to_owned())));
+    items.push(Box::new(JSONValue::JSONBool(true)));
+    let array = JSONValue::JSONArray(items);
+    let slots = 4 * size_of::<Box<JSONValue>>();
+    assert_eq!(
+        array.deep_size_of(),
+        value_size + slots + 2 * value_size + 3
+    );
+
+    let object: JSONValue = r#"{"key": "value", "nested": [1, 2]}"#.parse().unwrap();
+    let members = value_size + 3 + 5 + value_size + 6 + value_size;
+    let nested = 2 * (size_of::<Box<JSONValue>>() + value_size);
+    assert!(object.deep_size_of() >= value_size + members + nested);
+
+    let empty: JSONValue = "{}".parse().unwrap();
+    assert!(object.deep_size_of() > empty.deep_size_of() + members);
+}
@@ -0,0 +1,151 @@
+use super::*;
+use patch::PatchOperation;
+use shared::SharedJSONValue;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone)]
+pub struct JSONStoreError {
+    pub reason: String,
+}
+
+/// One change of a document in a `DocumentStore`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Revision {
+    /// 1 for the version a document was created with, then one up per change
+    pub version: u64,
+    /// What changed; an `add` of the whole document for `insert`
+    pub patch: Vec<PatchOperation>,
+    /// The document after the change
+    pub snapshot: SharedJSONValue,
+}
+
+/// Named documents with their change history, e.g. for collaborative editing
+/// or audit logs. Documents are `SharedJSONValue`s, so a snapshot is a cheap
+/// clone and every revision shares the unchanged parts with the others.
+#[derive(Debug, Clone, Default)]
+pub struct DocumentStore {
+    documents: BTreeMap<String, Vec<Revision>>,
+}
+
+impl DocumentStore {
+    pub fn new() -> DocumentStore {
+        return DocumentStore::default();
+    }
+
+    /// Creates a document or replaces its contents, keeping the history.
+    /// Returns the new version.
+    pub fn insert(&mut self, name: &str, document: JSONValue) -> u64 {
+        let snapshot = SharedJSONValue::from(&document);
+        let patch = vec![PatchOperation::Add {
+            path: String::new(),
+            value: document,
+        }];
+        let history = self.documents.entry(name.to_owned()).or_default();
+        return push(history, patch, snapshot);
+    }
+
+    /// Removes a document with its history, returning its last version.
+    pub fn remove(&mut self, name: &str) -> Option<SharedJSONValue> {
+        let history = self.documents.remove(name)?;
+        return history.last().map(|revision| revision.snapshot.clone());
+    }
+
+    /// Names of the documents, sorted
+    pub fn names(&self) -> Vec<&str> {
+        return self.documents.keys().map(|name| name.as_str()).collect();
+    }
+
+    /// Current contents of a document
+    pub fn get(&self, name: &str) -> Option<&SharedJSONValue> {
+        return self.last(name).map(|revision| &revision.snapshot);
+    }
+
+    /// A copy of the current contents that later changes don't affect, in O(1)
+    pub fn snapshot(&self, name: &str) -> Option<SharedJSONValue> {
+        return self.get(name).cloned();
+    }
+
+    pub fn version(&self, name: &str) -> Option<u64> {
+        return self.last(name).map(|revision| revision.version);
+    }
+
+    /// Contents of a document at a version, if it is still in the history
+    pub fn at_version(&self, name: &str, version: u64) -> Option<&SharedJSONValue> {
+        return self
+            .history(name)?
+            .iter()
+            .find(|revision| revision.version == version)
+            .map(|revision| &revision.snapshot);
+    }
+
+    /// Revisions of a document, oldest first
+    pub fn history(&self, name: &str) -> Option<&[Revision]> {
+        return self.documents.get(name).map(|history| history.as_slice());
+    }
+
+    /// Applies a patch atomically and records it. Returns the new version.
+    pub fn apply_patch(
+        &mut self,
+        name: &str,
+        patch: &[PatchOperation],
+    ) -> Result<u64, JSONStoreError> {
+        let history = self
+            .documents
+            .get_mut(name)
+            .ok_or_else(|| make_err(format!("No document {}", name)))?;
+        let mut snapshot = history.last().expect("never empty").snapshot.clone();
+        snapshot
+            .apply_patch(patch)
+            .map_err(|err| make_err(format!("Unable to patch {}: {}", name, err.reason)))?;
+        return Ok(push(history, patch.to_vec(), snapshot));
+    }
+
+    /// Like `apply_patch`, but only if the document is still at `version`, the one
+    /// the patch was made against. Concurrent editors that lose the race get an
+    /// error and can rebase their change on the newer version.
+    pub fn apply_patch_at(
+        &mut self,
+        name: &str,
+        version: u64,
+        patch: &[PatchOperation],
+    ) -> Result<u64, JSONStoreError> {
+        match self.version(name) {
+            Some(current) if current != version => {
+                return Err(make_err(format!(
+                    "{} is at version {}, not {}",
+                    name, current, version
+                )));
+            }
+            _ => return self.apply_patch(name, patch),
+        }
+    }
+
+    /// Drops all but the last `keep` revisions of a document (at least one is kept).
+    pub fn truncate_history(&mut self, name: &str, keep: usize) {
+        if let Some(history) = self.documents.get_mut(name) {
+            let drop = history.len().saturating_sub(keep.max(1));
+            history.drain(..drop);
+        }
+    }
+
+    fn last(&self, name: &str) -> Option<&Revision> {
+        return self.documents.get(name).and_then(|history| history.last());
+    }
+}
+
+fn push(history: &mut Vec<Revision>, patch: Vec<PatchOperation>, snapshot: SharedJSONValue) -> u64 {
+    let version = history.last().map_or(1, |revision| revision.version + 1);
+    history.push(Revision {
+        version,
+        patch,
+        snapshot,
+    });
+    return version;
+}
+
+fn make_err(s: String) -> JSONStoreError {
+    JSONStoreError { reason: s }
+}
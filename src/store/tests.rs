@@ -0,0 +1,110 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+fn replace(path: &str, value: &str) -> Vec<PatchOperation> {
+    return vec![PatchOperation::Replace {
+        path: path.to_owned(),
+        value: parse(value),
+    }];
+}
+
+#[test]
+fn test_history() {
+    let mut store = DocumentStore::new();
+    assert_eq!(
+        store.insert("doc", parse(r#"{"title": "a", "body": {"text": "hello"}}"#)),
+        1
+    );
+    assert_eq!(store.insert("other", parse("[]")), 1);
+    let before = store.snapshot("doc").unwrap();
+    assert_eq!(
+        store
+            .apply_patch("doc", &replace("/title", "\"b\""))
+            .unwrap(),
+        2
+    );
+    assert_eq!(store.version("doc"), Some(2));
+    assert_eq!(store.names(), vec!["doc", "other"]);
+
+    // snapshots aren't affected by later changes and share what didn't change
+    assert_eq!(
+        before.to_value(),
+        parse(r#"{"title": "a", "body": {"text": "hello"}}"#)
+    );
+    let current = store.get("doc").unwrap();
+    assert_eq!(
+        current.to_value(),
+        parse(r#"{"title": "b", "body": {"text": "hello"}}"#)
+    );
+    assert!(SharedJSONValue::ptr_eq(
+        before.get("body").unwrap(),
+        current.get("body").unwrap()
+    ));
+    assert_eq!(store.at_version("doc", 1), Some(&before));
+
+    let history = store.history("doc").unwrap();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[1].patch, replace("/title", "\"b\""));
+    assert_eq!(
+        history[0].patch,
+        vec![PatchOperation::Add {
+            path: String::new(),
+            value: parse(r#"{"title": "a", "body": {"text": "hello"}}"#),
+        }]
+    );
+
+    assert_eq!(store.insert("doc", parse("null")), 3);
+    store.truncate_history("doc", 2);
+    assert_eq!(store.at_version("doc", 1), None);
+    assert_eq!(store.history("doc").unwrap().len(), 2);
+    store.truncate_history("doc", 0);
+    assert_eq!(store.history("doc").unwrap().len(), 1);
+    assert_eq!(store.version("doc"), Some(3));
+
+    assert_eq!(
+        store.remove("other"),
+        Some(SharedJSONValue::from(parse("[]")))
+    );
+    assert_eq!(store.remove("other"), None);
+    assert_eq!(store.get("other"), None);
+}
+
+#[test]
+fn test_errors() {
+    let mut store = DocumentStore::new();
+    store.insert("doc", parse(r#"{"n": 1}"#));
+    for s in vec![
+        ("missing", None, replace("/n", "2"), "No document missing"),
+        (
+            "doc",
+            None,
+            replace("/m", "2"),
+            "Unable to patch doc: Nothing to replace at /m",
+        ),
+        (
+            "doc",
+            Some(2),
+            replace("/n", "2"),
+            "doc is at version 1, not 2",
+        ),
+    ] {
+        println!("Checking {}", s.3);
+        let err = match s.1 {
+            Some(version) => store.apply_patch_at(s.0, version, &s.2),
+            None => store.apply_patch(s.0, &s.2),
+        }
+        .unwrap_err();
+        assert_eq!(err.reason, s.3);
+    }
+    assert_eq!(store.version("doc"), Some(1));
+    assert_eq!(
+        store.apply_patch_at("doc", 1, &replace("/n", "2")).unwrap(),
+        2
+    );
+    assert_eq!(store.get("doc").unwrap().to_value(), parse(r#"{"n": 2}"#));
+}
@@ -0,0 +1,1213 @@
+use super::*;
+use convert::{convert_at, FromJSONValue, JSONConvertError};
+use generate::Rng;
+use ndjson::NdjsonReader;
+use pointer::parse_pointer;
+use serializer::{write_string, SerializerConfig};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::str;
+
+#[cfg(test)]
+mod tests;
+
+const BUFFER_SIZE: usize = 8 * 1024;
+const ERROR_ENDED_UNEXPECTEDLY: &str = "String ended unexpectedly";
+
+#[derive(Debug, Clone)]
+pub struct JSONStreamError {
+    pub reason: String,
+    /// Byte offset in the input for parse errors
+    pub position: Option<usize>,
+}
+
+impl From<JSONParseError> for JSONStreamError {
+    fn from(err: JSONParseError) -> JSONStreamError {
+        return JSONStreamError {
+            reason: err.reason,
+            position: err.position,
+        };
+    }
+}
+
+/// One token of a JSON document as seen by `EventReader` and `EventWriter`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    /// Key of the object member whose value comes next
+    Key(String),
+    String(String),
+    /// Number literal as written in the input, so it's copied without losing precision
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+impl Event {
+    /// Number event for a float, written the way the serializer writes numbers
+    pub fn number(n: f64) -> Event {
+        return Event::Number(
+            JSONValue::JSONNumber(n).to_string_with(&SerializerConfig::compact()),
+        );
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    FirstItem,
+    NextItem,
+    FirstKey,
+    Key,
+    Colon,
+    NextMember,
+    End,
+}
+
+#[derive(Debug)]
+struct Frame {
+    object: bool,
+    count: usize,
+}
+
+/// Pull parser producing the events of one JSON document from a reader, in
+/// constant memory apart from the nesting stack and the current string or number.
+/// Input is read in chunks and must be UTF-8. Error positions are byte offsets.
+pub struct EventReader<R> {
+    reader: R,
+    buffer: Vec<u8>,
+    start: usize,
+    end: usize,
+    offset: usize,
+    options: ParserOptions,
+    expect: Expect,
+    frames: Vec<Frame>,
+    path: Vec<String>,
+    // Offset of the token of the last event
+    token_start: usize,
+    // Path tokens to drop before the next event, once the previous value is complete
+    pending_pop: bool,
+}
+
+impl<R: Read> EventReader<R> {
+    pub fn new(reader: R) -> EventReader<R> {
+        return EventReader::with_options(reader, &ParserOptions::default());
+    }
+
+    /// Honors `max_input_bytes`, `max_depth` and `max_string_length` of the options
+    pub fn with_options(reader: R, options: &ParserOptions) -> EventReader<R> {
+        return EventReader {
+            reader,
+            buffer: vec![0; BUFFER_SIZE],
+            start: 0,
+            end: 0,
+            offset: 0,
+            options: *options,
+            expect: Expect::Value,
+            frames: vec![],
+            path: vec![],
+            token_start: 0,
+            pending_pop: false,
+        };
+    }
+
+    /// Byte offset of the next unread byte
+    pub fn position(&self) -> usize {
+        return self.offset;
+    }
+
+    /// Byte offset where the token of the last event starts, e.g. the `{` of
+    /// `StartObject` or the opening quote of a string
+    pub fn event_position(&self) -> usize {
+        return self.token_start;
+    }
+
+    /// Pointer tokens of the last event: the member for `Key`, the container for
+    /// start and end events and the value itself for scalars.
+    pub fn path(&self) -> &[String] {
+        return &self.path;
+    }
+
+    /// Nesting depth after the last event
+    pub fn depth(&self) -> usize {
+        return self.frames.len();
+    }
+
+    /// Returns the next event, or `None` after the end of the document.
+    pub fn next_event(&mut self) -> Result<Option<Event>, JSONParseError> {
+        if self.pending_pop {
+            self.path.pop();
+            self.pending_pop = false;
+        }
+        loop {
+            self.skip_whitespace()?;
+            self.token_start = self.offset;
+            let next = self.peek()?;
+            match (self.expect, next) {
+                (Expect::End, None) => return Ok(None),
+                (Expect::End, Some(_)) => return Err(self.unexpected()?),
+                (_, None) => return Err(unexpected_eof(self.offset)),
+                (Expect::FirstItem, Some(b']')) | (Expect::NextItem, Some(b']')) => {
+                    self.advance();
+                    return Ok(Some(self.close(false)));
+                }
+                (Expect::FirstKey, Some(b'}')) | (Expect::NextMember, Some(b'}')) => {
+                    self.advance();
+                    return Ok(Some(self.close(true)));
+                }
+                (Expect::NextItem, Some(b',')) => {
+                    self.advance();
+                    self.expect = Expect::Value;
+                }
+                (Expect::NextMember, Some(b',')) => {
+                    self.advance();
+                    self.expect = Expect::Key;
+                }
+                (Expect::Colon, Some(b':')) => {
+                    self.advance();
+                    self.expect = Expect::Value;
+                }
+                (Expect::Value, Some(_)) | (Expect::FirstItem, Some(_)) => {
+                    return self.value().map(Some);
+                }
+                (Expect::FirstKey, Some(b'"')) | (Expect::Key, Some(b'"')) => {
+                    let key = self.string()?;
+                    self.path.push(key.clone());
+                    self.expect = Expect::Colon;
+                    return Ok(Some(Event::Key(key)));
+                }
+                _ => return Err(self.unexpected()?),
+            }
+        }
+    }
+
+    /// Reads the rest of the value whose start event was just returned:
+    /// the whole container after a start event, the member value after a key.
+    /// Does nothing after a scalar or an end event.
+    pub fn skip_value(&mut self, last: &Event) -> Result<(), JSONParseError> {
+        let mut depth = match *last {
+            Event::StartObject | Event::StartArray => 1,
+            Event::Key(_) => 0,
+            _ => return Ok(()),
+        };
+        loop {
+            match self.next_event()? {
+                Some(Event::StartObject) | Some(Event::StartArray) => depth += 1,
+                Some(Event::EndObject) | Some(Event::EndArray) => depth -= 1,
+                Some(_) => (),
+                None => return Err(unexpected_eof(self.offset)),
+            }
+            if depth == 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Builds the value that starts with `first`, the event just returned,
+    /// reading the rest of it from the input.
+    pub fn read_value(&mut self, first: Event) -> Result<JSONValue, JSONParseError> {
+        match first {
+            Event::Null => return Ok(JSONValue::JSONNull()),
+            Event::Bool(b) => return Ok(JSONValue::JSONBool(b)),
+            // literals are checked while reading, so they always parse
+            Event::Number(n) => return Ok(JSONValue::JSONNumber(n.parse().unwrap())),
+            Event::String(s) => return Ok(JSONValue::JSONString(s)),
+            Event::StartArray => {
+                let mut items = vec![];
+                loop {
+                    match self.next_in_value()? {
+                        Event::EndArray => return Ok(JSONValue::JSONArray(items)),
+                        event => items.push(Box::new(self.read_value(event)?)),
+                    }
+                }
+            }
+            Event::StartObject => {
+                let mut members = JSONMap::new();
+                loop {
+                    match self.next_in_value()? {
+                        Event::Key(key) => {
+                            let event = self.next_in_value()?;
+                            members.insert(key, Box::new(self.read_value(event)?));
+                        }
+                        _ => return Ok(JSONValue::JSONObject(members)),
+                    }
+                }
+            }
+            _ => {
+                return Err(make_err(
+                    format!("Expected a value, got {:?}", first),
+                    self.offset,
+                ))
+            }
+        }
+    }
+
+    fn next_in_value(&mut self) -> Result<Event, JSONParseError> {
+        return self
+            .next_event()?
+            .ok_or_else(|| unexpected_eof(self.offset));
+    }
+
+    fn value(&mut self) -> Result<Event, JSONParseError> {
+        if let Some(frame) = self.frames.last_mut() {
+            if !frame.object {
+                self.path.push(frame.count.to_string());
+                frame.count += 1;
+            }
+        }
+        let event = match self.peek()? {
+            Some(b'{') | Some(b'[') => {
+                let object = self.advance() == b'{';
+                self.frames.push(Frame { object, count: 0 });
+                check_limit(
+                    self.options.max_depth,
+                    self.frames.len(),
+                    "nesting depth",
+                    self.offset - 1,
+                )?;
+                self.expect = if object {
+                    Expect::FirstKey
+                } else {
+                    Expect::FirstItem
+                };
+                return Ok(if object {
+                    Event::StartObject
+                } else {
+                    Event::StartArray
+                });
+            }
+            Some(b'"') => Event::String(self.string()?),
+            Some(b't') => self.literal("true", Event::Bool(true))?,
+            Some(b'f') => self.literal("false", Event::Bool(false))?,
+            Some(b'n') => self.literal("null", Event::Null)?,
+            Some(b'-') | Some(b'0'..=b'9') => Event::Number(self.number()?),
+            _ => return Err(self.unexpected()?),
+        };
+        self.complete_value();
+        return Ok(event);
+    }
+
+    fn close(&mut self, object: bool) -> Event {
+        self.frames.pop();
+        self.complete_value();
+        return if object {
+            Event::EndObject
+        } else {
+            Event::EndArray
+        };
+    }
+
+    fn complete_value(&mut self) {
+        match self.frames.last() {
+            None => self.expect = Expect::End,
+            Some(frame) => {
+                self.expect = if frame.object {
+                    Expect::NextMember
+                } else {
+                    Expect::NextItem
+                };
+                self.pending_pop = true;
+            }
+        }
+    }
+
+    fn literal(&mut self, text: &str, event: Event) -> Result<Event, JSONParseError> {
+        for &expected in text.as_bytes() {
+            match self.peek()? {
+                Some(b) if b == expected => {
+                    self.advance();
+                }
+                Some(_) => return Err(self.unexpected()?),
+                None => return Err(unexpected_eof(self.offset)),
+            }
+        }
+        return Ok(event);
+    }
+
+    fn number(&mut self) -> Result<String, JSONParseError> {
+        let mut result = String::new();
+        if self.peek()? == Some(b'-') {
+            result.push(self.advance() as char);
+        }
+        match self.peek()? {
+            Some(b'0') => result.push(self.advance() as char),
+            Some(b'1'..=b'9') => self.digits(&mut result)?,
+            Some(_) => return Err(self.unexpected()?),
+            None => return Err(unexpected_eof(self.offset)),
+        }
+        if self.peek()? == Some(b'.') {
+            result.push(self.advance() as char);
+            self.digits(&mut result)?;
+        }
+        if let Some(b'e') | Some(b'E') = self.peek()? {
+            result.push(self.advance() as char);
+            if let Some(b'+') | Some(b'-') = self.peek()? {
+                result.push(self.advance() as char);
+            }
+            self.digits(&mut result)?;
+        }
+        return Ok(result);
+    }
+
+    // At least one digit
+    fn digits(&mut self, result: &mut String) -> Result<(), JSONParseError> {
+        match self.peek()? {
+            Some(b'0'..=b'9') => (),
+            Some(_) => return Err(self.unexpected()?),
+            None => return Err(unexpected_eof(self.offset)),
+        }
+        while let Some(b'0'..=b'9') = self.peek()? {
+            result.push(self.advance() as char);
+        }
+        return Ok(());
+    }
+
+    fn string(&mut self) -> Result<String, JSONParseError> {
+        let start = self.offset;
+        self.advance();
+        let mut bytes = vec![];
+        loop {
+            check_limit(
+                self.options.max_string_length,
+                bytes.len(),
+                "string length in bytes",
+                start,
+            )?;
+            let position = self.offset;
+            match self.peek()? {
+                None => return Err(unexpected_eof(self.offset)),
+                Some(b'"') => {
+                    self.advance();
+                    break;
+                }
+                Some(b'\\') => {
+                    self.advance();
+                    let ch = self.escape(position)?;
+                    let mut encoded = [0; 4];
+                    bytes.extend_from_slice(ch.encode_utf8(&mut encoded).as_bytes());
+                }
+                Some(0x00..=0x1F) => return Err(self.unexpected()?),
+                Some(_) => bytes.push(self.advance()),
+            }
+        }
+        match String::from_utf8(bytes) {
+            Ok(s) => return Ok(s),
+            Err(e) => {
+                return Err(make_err(
+                    "Invalid UTF-8".to_owned(),
+                    start + 1 + e.utf8_error().valid_up_to(),
+                ))
+            }
+        }
+    }
+
+    // Called after the backslash at `position`
+    fn escape(&mut self, position: usize) -> Result<char, JSONParseError> {
+        let b = match self.peek()? {
+            Some(b) => self.advance_with(b),
+            None => return Err(unexpected_eof(self.offset)),
+        };
+        match b {
+            b'"' | b'\\' | b'/' => return Ok(b as char),
+            b'b' => return Ok('\x08'),
+            b'f' => return Ok('\x0c'),
+            b'n' => return Ok('\n'),
+            b'r' => return Ok('\r'),
+            b't' => return Ok('\t'),
+            b'u' => (),
+            _ => {
+                return Err(invalid_escape_sequence(
+                    position,
+                    &format!("\\{}", b as char),
+                ))
+            }
+        }
+        let high = self.hex(position)?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return char::from_u32(high)
+                .ok_or_else(|| invalid_escape_sequence(position, &format!("\\u{:04x}", high)));
+        }
+        // a high surrogate must be followed by an escaped low one
+        let low_position = self.offset;
+        if self.peek()? == Some(b'\\') {
+            self.advance();
+            if self.peek()? == Some(b'u') {
+                self.advance();
+                let low = self.hex(low_position)?;
+                if (0xDC00..0xE000).contains(&low) {
+                    let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                    return char::from_u32(code).ok_or_else(|| {
+                        invalid_escape_sequence(position, &format!("\\u{:04x}", high))
+                    });
+                }
+            }
+        }
+        return Err(invalid_escape_sequence(
+            position,
+            &format!("\\u{:04x}", high),
+        ));
+    }
+
+    fn hex(&mut self, position: usize) -> Result<u32, JSONParseError> {
+        let mut seq = "\\u".to_owned();
+        let mut code = 0;
+        for _ in 0..4 {
+            let b = match self.peek()? {
+                Some(b) => self.advance_with(b),
+                None => return Err(unexpected_eof(self.offset)),
+            };
+            seq.push(b as char);
+            match (b as char).to_digit(16) {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(invalid_escape_sequence(position, &seq)),
+            }
+        }
+        return Ok(code);
+    }
+
+    fn skip_whitespace(&mut self) -> Result<(), JSONParseError> {
+        while let Some(b' ') | Some(b'\t') | Some(b'\n') | Some(b'\r') = self.peek()? {
+            self.advance();
+        }
+        return Ok(());
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, JSONParseError> {
+        if self.start == self.end {
+            self.fill()?;
+        }
+        if self.start == self.end {
+            return Ok(None);
+        }
+        return Ok(Some(self.buffer[self.start]));
+    }
+
+    // Only called after `peek` returned a byte
+    fn advance(&mut self) -> u8 {
+        let b = self.buffer[self.start];
+        self.start += 1;
+        self.offset += 1;
+        return b;
+    }
+
+    fn advance_with(&mut self, b: u8) -> u8 {
+        self.advance();
+        return b;
+    }
+
+    fn fill(&mut self) -> Result<(), JSONParseError> {
+        loop {
+            match self.reader.read(&mut self.buffer) {
+                Ok(read) => {
+                    self.start = 0;
+                    self.end = read;
+                    break;
+                }
+                Err(ref e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => {
+                    return Err(JSONParseError {
+                        reason: format!("Unable to read input: {}", e),
+                        position: Some(self.offset),
+                        kind: JSONParseErrorKind::Io,
+                    })
+                }
+            }
+        }
+        return check_limit(
+            self.options.max_input_bytes,
+            self.offset + self.end,
+            "input size in bytes",
+            0,
+        );
+    }
+
+    // Error for the char at the current position, decoded for the message
+    fn unexpected(&mut self) -> Result<JSONParseError, JSONParseError> {
+        let position = self.offset;
+        let first = match self.peek()? {
+            Some(b) => b,
+            None => return Ok(unexpected_eof(position)),
+        };
+        let mut bytes = vec![self.advance()];
+        let len = match first {
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            0xF0..=0xF7 => 4,
+            _ => 1,
+        };
+        while bytes.len() < len {
+            match self.peek()? {
+                Some(b @ 0x80..=0xBF) => bytes.push(self.advance_with(b)),
+                _ => break,
+            }
+        }
+        let ch = match str::from_utf8(&bytes) {
+            Ok(s) => s.chars().next().unwrap_or(char::REPLACEMENT_CHARACTER),
+            Err(_) => char::REPLACEMENT_CHARACTER,
+        };
+        return Ok(make_err(
+            format!("Unexpected charachter {} at position {}", ch, position),
+            position,
+        ));
+    }
+}
+
+impl<R: Read> Iterator for EventReader<R> {
+    type Item = Result<Event, JSONParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        return self.next_event().transpose();
+    }
+}
+
+/// Writes events as JSON text, checking that they form a single valid document.
+/// Output goes straight to the writer piece by piece, so wrap unbuffered
+/// writers like files in a `BufWriter`. `sort_keys` of the config is ignored,
+/// since members are written as they come.
+pub struct EventWriter<W> {
+    writer: W,
+    indent: Option<usize>,
+    frames: Vec<Frame>,
+    after_key: bool,
+    done: bool,
+    buffer: String,
+}
+
+impl<W: Write> EventWriter<W> {
+    pub fn new(writer: W, config: &SerializerConfig) -> EventWriter<W> {
+        return EventWriter {
+            writer,
+            indent: config.indent,
+            frames: vec![],
+            after_key: false,
+            done: false,
+            buffer: String::new(),
+        };
+    }
+
+    pub fn write(&mut self, event: &Event) -> Result<(), JSONStreamError> {
+        self.buffer.clear();
+        match *event {
+            Event::Key(ref key) => {
+                match self.frames.last() {
+                    Some(frame) if frame.object && !self.after_key => (),
+                    _ => return Err(make_stream_err(format!("Unexpected key {}", key))),
+                }
+                self.separate();
+                write_string(&mut self.buffer, key).unwrap();
+                self.buffer
+                    .push_str(if self.indent.is_some() { ": " } else { ":" });
+                self.after_key = true;
+            }
+            Event::EndObject | Event::EndArray => {
+                let object = *event == Event::EndObject;
+                match self.frames.last() {
+                    Some(frame) if frame.object == object && !self.after_key => (),
+                    _ => return Err(make_stream_err(format!("Unexpected {:?}", event))),
+                }
+                let frame = self.frames.pop().unwrap();
+                if frame.count > 0 {
+                    self.newline(self.frames.len());
+                }
+                self.buffer.push(if object { '}' } else { ']' });
+                self.done = self.frames.is_empty();
+            }
+            _ => {
+                if self.done {
+                    return Err(make_stream_err(format!(
+                        "Unexpected {:?} after the end of the document",
+                        event
+                    )));
+                }
+                match self.frames.last() {
+                    Some(frame) if frame.object && !self.after_key => {
+                        return Err(make_stream_err(format!(
+                            "Unexpected {:?} in place of a key",
+                            event
+                        )))
+                    }
+                    Some(frame) if !frame.object => self.separate(),
+                    _ => (),
+                }
+                self.after_key = false;
+                match *event {
+                    Event::StartObject => {
+                        self.buffer.push('{');
+                        self.frames.push(Frame {
+                            object: true,
+                            count: 0,
+                        });
+                    }
+                    Event::StartArray => {
+                        self.buffer.push('[');
+                        self.frames.push(Frame {
+                            object: false,
+                            count: 0,
+                        });
+                    }
+                    Event::String(ref s) => write_string(&mut self.buffer, s).unwrap(),
+                    Event::Number(ref n) => {
+                        if !is_number(n) {
+                            return Err(make_stream_err(format!("Invalid number {}", n)));
+                        }
+                        self.buffer.push_str(n);
+                    }
+                    Event::Bool(b) => self.buffer.push_str(if b { "true" } else { "false" }),
+                    Event::Null => self.buffer.push_str("null"),
+                    _ => (),
+                }
+                self.done = self.frames.is_empty();
+            }
+        }
+        return self
+            .writer
+            .write_all(self.buffer.as_bytes())
+            .map_err(write_err);
+    }
+
+    /// Checks that the document is complete, flushes and returns the writer.
+    pub fn finish(mut self) -> Result<W, JSONStreamError> {
+        if !self.done {
+            return Err(make_stream_err("Document is incomplete".to_owned()));
+        }
+        self.writer.flush().map_err(write_err)?;
+        return Ok(self.writer);
+    }
+
+    // Comma and line break before a member or an item
+    fn separate(&mut self) {
+        let depth = self.frames.len();
+        let frame = self.frames.last_mut().unwrap();
+        if frame.count > 0 {
+            self.buffer.push(',');
+        }
+        frame.count += 1;
+        self.newline(depth);
+    }
+
+    fn newline(&mut self, depth: usize) {
+        if let Some(indent) = self.indent {
+            self.buffer.push('\n');
+            for _ in 0..indent * depth {
+                self.buffer.push(' ');
+            }
+        }
+    }
+}
+
+/// What `transcode` does with an event
+#[derive(Debug, Clone, PartialEq)]
+pub enum Action {
+    /// Write the event unchanged
+    Keep,
+    /// Leave the event out. On a key the whole member is left out, on a start
+    /// event the whole container.
+    Drop,
+    /// Write these events instead. On a start event they replace the whole
+    /// container; on a key only the key, so a single `Key` renames the member.
+    Replace(Vec<Event>),
+}
+
+/// Copies a document from `reader` to `writer` event by event, without building
+/// it in memory. `f` gets the path (as pointer tokens) and every event, apart from
+/// those inside dropped or replaced values, and decides what gets written.
+/// Replacing an end event can add members or items before it.
+pub fn transcode<R, W, F>(
+    reader: R,
+    writer: W,
+    config: &SerializerConfig,
+    mut f: F,
+) -> Result<W, JSONStreamError>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(&[String], &Event) -> Action,
+{
+    let mut events = EventReader::new(reader);
+    let mut output = EventWriter::new(writer, config);
+    while let Some(event) = events.next_event()? {
+        match f(events.path(), &event) {
+            Action::Keep => output.write(&event)?,
+            Action::Drop => events.skip_value(&event)?,
+            Action::Replace(replacement) => {
+                if let Event::StartObject | Event::StartArray = event {
+                    events.skip_value(&event)?;
+                }
+                for event in &replacement {
+                    output.write(event)?;
+                }
+            }
+        }
+    }
+    return output.finish();
+}
+
+/// Re-indents or minifies a document of any size in constant memory, following
+/// `indent` of the config. Keys keep their order and numbers their literal text.
+pub fn reformat<R: Read, W: Write>(
+    reader: R,
+    writer: W,
+    config: &SerializerConfig,
+) -> Result<W, JSONStreamError> {
+    return transcode(reader, writer, config, |_, _| Action::Keep);
+}
+
+/// Splits a document holding a top-level array into arrays of at most `chunk_size`
+/// items each, streaming every item to the writer `open` returns for the chunk
+/// index (0, 1, ...). Returns the number of chunks; an empty array gives none.
+pub fn split_array<R, W, F>(
+    reader: R,
+    chunk_size: usize,
+    config: &SerializerConfig,
+    mut open: F,
+) -> Result<usize, JSONStreamError>
+where
+    R: Read,
+    W: Write,
+    F: FnMut(usize) -> io::Result<W>,
+{
+    if chunk_size == 0 {
+        return Err(make_stream_err("Chunk size must be positive".to_owned()));
+    }
+    let mut events = EventReader::new(reader);
+    expect_array(&mut events)?;
+    let mut chunks = 0;
+    let mut chunk: Option<EventWriter<W>> = None;
+    let mut items = 0;
+    loop {
+        let event = events.next_event()?.unwrap();
+        if event == Event::EndArray {
+            break;
+        }
+        if chunk.is_none() {
+            let mut writer = EventWriter::new(open(chunks).map_err(write_err)?, config);
+            writer.write(&Event::StartArray)?;
+            chunk = Some(writer);
+            chunks += 1;
+        }
+        let writer = chunk.as_mut().unwrap();
+        copy_value(&mut events, event, writer)?;
+        items += 1;
+        if items % chunk_size == 0 {
+            let mut writer = chunk.take().unwrap();
+            writer.write(&Event::EndArray)?;
+            writer.finish()?;
+        }
+    }
+    if let Some(mut writer) = chunk {
+        writer.write(&Event::EndArray)?;
+        writer.finish()?;
+    }
+    // nothing but whitespace may follow the array
+    events.next_event()?;
+    return Ok(chunks);
+}
+
+/// Joins the items of top-level arrays from several readers into one array,
+/// the reverse of `split_array`.
+pub fn concat_arrays<R, I, W>(
+    readers: I,
+    writer: W,
+    config: &SerializerConfig,
+) -> Result<W, JSONStreamError>
+where
+    R: Read,
+    I: IntoIterator<Item = R>,
+    W: Write,
+{
+    let mut output = EventWriter::new(writer, config);
+    output.write(&Event::StartArray)?;
+    for reader in readers {
+        let mut events = EventReader::new(reader);
+        expect_array(&mut events)?;
+        loop {
+            let event = events.next_event()?.unwrap();
+            if event == Event::EndArray {
+                break;
+            }
+            copy_value(&mut events, event, &mut output)?;
+        }
+        events.next_event()?;
+    }
+    output.write(&Event::EndArray)?;
+    return output.finish();
+}
+
+fn expect_array<R: Read>(events: &mut EventReader<R>) -> Result<(), JSONStreamError> {
+    match events.next_event()? {
+        Some(Event::StartArray) => return Ok(()),
+        _ => {
+            return Err(JSONStreamError {
+                reason: "Expected a top-level array".to_owned(),
+                position: Some(0),
+            })
+        }
+    }
+}
+
+// Writes the value starting with `first`, reading the rest of it from `events`
+fn copy_value<R: Read, W: Write>(
+    events: &mut EventReader<R>,
+    first: Event,
+    writer: &mut EventWriter<W>,
+) -> Result<(), JSONStreamError> {
+    let mut depth = 0;
+    let mut event = first;
+    loop {
+        match event {
+            Event::StartObject | Event::StartArray => depth += 1,
+            Event::EndObject | Event::EndArray => depth -= 1,
+            _ => (),
+        }
+        writer.write(&event)?;
+        if depth == 0 {
+            return Ok(());
+        }
+        event = events.next_event()?.unwrap();
+    }
+}
+
+/// Iterates over the items of a top-level array, building one item at a time,
+/// so arrays far larger than memory can be processed. Stops after the first error.
+pub struct ArrayItems<R> {
+    events: EventReader<R>,
+    started: bool,
+    done: bool,
+}
+
+impl<R: Read> ArrayItems<R> {
+    pub fn new(reader: R) -> ArrayItems<R> {
+        return ArrayItems {
+            events: EventReader::new(reader),
+            started: false,
+            done: false,
+        };
+    }
+
+    fn next_item(&mut self) -> Result<Option<JSONValue>, JSONParseError> {
+        if !self.started {
+            self.started = true;
+            if self.events.next_event()? != Some(Event::StartArray) {
+                return Err(make_err("Expected a top-level array".to_owned(), 0));
+            }
+        }
+        match self.events.next_in_value()? {
+            Event::EndArray => {
+                // nothing but whitespace may follow the array
+                self.events.next_event()?;
+                return Ok(None);
+            }
+            event => return self.events.read_value(event).map(Some),
+        }
+    }
+}
+
+impl<R: Read> Iterator for ArrayItems<R> {
+    type Item = Result<JSONValue, JSONParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = self.next_item();
+        if let Ok(None) | Err(_) = item {
+            self.done = true;
+        }
+        return item.transpose();
+    }
+}
+
+// Records of either a top-level array or NDJSON
+enum Records<R> {
+    Array(Box<ArrayItems<R>>),
+    Lines(NdjsonReader<R>),
+}
+
+impl<R: BufRead> Iterator for Records<R> {
+    type Item = Result<JSONValue, JSONParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match *self {
+            Records::Array(ref mut items) => return items.next(),
+            Records::Lines(ref mut lines) => return lines.next(),
+        }
+    }
+}
+
+// Input starting with `[` is a top-level array, anything else NDJSON
+fn records<R: Read>(reader: R) -> Result<Records<BufReader<R>>, JSONParseError> {
+    let mut reader = BufReader::new(reader);
+    loop {
+        let (whitespace, array) = {
+            let buffer = reader.fill_buf().map_err(|e| JSONParseError {
+                reason: format!("Unable to read input: {}", e),
+                position: None,
+                kind: JSONParseErrorKind::Io,
+            })?;
+            let whitespace = buffer
+                .iter()
+                .take_while(|b| b.is_ascii_whitespace())
+                .count();
+            if buffer.is_empty() || whitespace < buffer.len() {
+                (0, buffer.get(whitespace) == Some(&b'['))
+            } else {
+                (whitespace, false)
+            }
+        };
+        if whitespace == 0 {
+            if array {
+                return Ok(Records::Array(Box::new(ArrayItems::new(reader))));
+            }
+            return Ok(Records::Lines(NdjsonReader::new(reader)));
+        }
+        reader.consume(whitespace);
+    }
+}
+
+/// Parses the first `n` records of a top-level array or of NDJSON, reading no
+/// further than needed. Input starting with `[` is taken as an array, so NDJSON
+/// whose first record is an array should go through `NdjsonReader` instead.
+pub fn stream_head<R: Read>(reader: R, n: usize) -> Result<Vec<JSONValue>, JSONParseError> {
+    return records(reader)?.take(n).collect();
+}
+
+/// Picks `n` records, uniformly at random, from a top-level array or NDJSON
+/// (detected like in `stream_head`) in a single pass, keeping only the sample in
+/// memory (reservoir sampling). Records keep their order in the input.
+pub fn stream_sample<R: Read, G: Rng>(
+    reader: R,
+    n: usize,
+    rng: &mut G,
+) -> Result<Vec<JSONValue>, JSONParseError> {
+    let mut sample: Vec<(usize, JSONValue)> = Vec::with_capacity(n);
+    for (i, record) in records(reader)?.enumerate() {
+        let record = record?;
+        if sample.len() < n {
+            sample.push((i, record));
+            continue;
+        }
+        let j = rng.range(0, i as u64) as usize;
+        if j < n {
+            sample[j] = (i, record);
+        }
+    }
+    sample.sort_by_key(|entry| entry.0);
+    return Ok(sample.into_iter().map(|entry| entry.1).collect());
+}
+
+/// Collects the value at `pointer` in every record of a top-level array or of
+/// NDJSON (detected like in `stream_head`), with `null` for records that don't
+/// have it. Only one record is in memory at a time.
+pub fn stream_pluck<R: Read>(reader: R, pointer: &str) -> Result<Vec<JSONValue>, JSONParseError> {
+    let mut result = vec![];
+    for record in records(reader)? {
+        let record = record?;
+        result.push(
+            record
+                .pointer(pointer)
+                .cloned()
+                .unwrap_or(JSONValue::JSONNull()),
+        );
+    }
+    return Ok(result);
+}
+
+/// Converts the items of the array at `pointer` into `T` one at a time and
+/// hands them to `on_item`, so a huge array field never has to be in memory
+/// as a whole. Returns the rest of the document with that array left empty,
+/// for converting the other fields. Fails if there is no array at `pointer`,
+/// and with the path into the item when one doesn't convert.
+pub fn stream_array_field<R, T, F>(
+    reader: R,
+    pointer: &str,
+    mut on_item: F,
+) -> Result<JSONValue, JSONStreamError>
+where
+    R: Read,
+    T: FromJSONValue,
+    F: FnMut(T) -> Result<(), JSONConvertError>,
+{
+    let target = match parse_pointer(pointer) {
+        Some(target) => target,
+        None => return Err(stream_error(format!("Invalid pointer {}", pointer), None)),
+    };
+    let mut field = ArrayField {
+        events: EventReader::new(reader),
+        pointer,
+        target,
+        found: false,
+    };
+    let first = field.events.next_in_value()?;
+    let document = field.read(first, &mut on_item)?;
+    field.events.next_event()?;
+    if !field.found {
+        return Err(stream_error(
+            format!("Expected an array at {}", pointer),
+            None,
+        ));
+    }
+    return Ok(document);
+}
+
+struct ArrayField<'a, R> {
+    events: EventReader<R>,
+    pointer: &'a str,
+    target: Vec<String>,
+    found: bool,
+}
+
+impl<'a, R: Read> ArrayField<'a, R> {
+    // Like `EventReader::read_value`, streaming the target array
+    fn read<T, F>(&mut self, first: Event, on_item: &mut F) -> Result<JSONValue, JSONStreamError>
+    where
+        T: FromJSONValue,
+        F: FnMut(T) -> Result<(), JSONConvertError>,
+    {
+        if !self.target.starts_with(self.events.path()) {
+            return Ok(self.events.read_value(first)?);
+        }
+        match first {
+            Event::StartArray if self.events.path() == &self.target[..] => {
+                self.found = true;
+                loop {
+                    let event = self.events.next_in_value()?;
+                    if event == Event::EndArray {
+                        return Ok(JSONValue::JSONArray(vec![]));
+                    }
+                    let position = self.events.event_position();
+                    let index = self.events.path().last().unwrap().clone();
+                    let item = self.events.read_value(event)?;
+                    let err = match convert_at(&item, &index) {
+                        Ok(item) => match on_item(item) {
+                            Ok(()) => continue,
+                            Err(err) => err.within(&index),
+                        },
+                        Err(err) => err,
+                    };
+                    let reason = err.within_pointer(self.pointer).to_string();
+                    return Err(stream_error(reason, Some(position)));
+                }
+            }
+            Event::StartArray => {
+                let mut items = vec![];
+                loop {
+                    match self.events.next_in_value()? {
+                        Event::EndArray => return Ok(JSONValue::JSONArray(items)),
+                        event => items.push(Box::new(self.read(event, on_item)?)),
+                    }
+                }
+            }
+            Event::StartObject => {
+                let mut members = JSONMap::new();
+                loop {
+                    match self.events.next_in_value()? {
+                        Event::Key(key) => {
+                            let event = self.events.next_in_value()?;
+                            members.insert(key, Box::new(self.read(event, on_item)?));
+                        }
+                        _ => return Ok(JSONValue::JSONObject(members)),
+                    }
+                }
+            }
+            first => return Ok(self.events.read_value(first)?),
+        }
+    }
+}
+
+fn stream_error(reason: String, position: Option<usize>) -> JSONStreamError {
+    return JSONStreamError { reason, position };
+}
+
+// Number literal as JSON defines it
+fn is_number(s: &str) -> bool {
+    let mut bytes = s.bytes().peekable();
+    if bytes.peek() == Some(&b'-') {
+        bytes.next();
+    }
+    let digits = |bytes: &mut std::iter::Peekable<str::Bytes>| {
+        let mut count = 0;
+        while let Some(b'0'..=b'9') = bytes.peek() {
+            bytes.next();
+            count += 1;
+        }
+        count
+    };
+    match bytes.peek() {
+        Some(b'0') => {
+            bytes.next();
+        }
+        Some(b'1'..=b'9') => {
+            digits(&mut bytes);
+        }
+        _ => return false,
+    }
+    if bytes.peek() == Some(&b'.') {
+        bytes.next();
+        if digits(&mut bytes) == 0 {
+            return false;
+        }
+    }
+    if let Some(b'e') | Some(b'E') = bytes.peek() {
+        bytes.next();
+        if let Some(b'+') | Some(b'-') = bytes.peek() {
+            bytes.next();
+        }
+        if digits(&mut bytes) == 0 {
+            return false;
+        }
+    }
+    return bytes.next().is_none();
+}
+
+fn check_limit(
+    limit: Option<usize>,
+    value: usize,
+    what: &str,
+    position: usize,
+) -> Result<(), JSONParseError> {
+    match limit {
+        Some(limit) if value > limit => {
+            return Err(JSONParseError {
+                reason: format!(
+                    "Maximum {} of {} exceeded at position {}",
+                    what, limit, position
+                ),
+                position: Some(position),
+                kind: JSONParseErrorKind::LimitExceeded,
+            });
+        }
+        _ => return Ok(()),
+    }
+}
+
+fn write_err(e: io::Error) -> JSONStreamError {
+    return make_stream_err(format!("Unable to write output: {}", e));
+}
+
+fn make_stream_err(s: String) -> JSONStreamError {
+    JSONStreamError {
+        reason: s,
+        position: None,
+    }
+}
+
+fn make_err(s: String, position: usize) -> JSONParseError {
+    JSONParseError {
+        reason: s,
+        position: Some(position),
+        kind: JSONParseErrorKind::Syntax,
+    }
+}
+
+fn unexpected_eof(position: usize) -> JSONParseError {
+    return make_err(ERROR_ENDED_UNEXPECTEDLY.to_owned(), position);
+}
+
+fn invalid_escape_sequence(position: usize, s: &str) -> JSONParseError {
+    return make_err(
+        format!("Invalid escape sequence {} at position {}", s, position),
+        position,
+    );
+}
@@ -0,0 +1,526 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use generate::SplitMix64;
+use std::cell::RefCell;
+
+// Hands out one byte per read to exercise buffer refills
+struct Trickle<'a>(&'a [u8]);
+
+impl<'a> Read for Trickle<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.0.is_empty() || buf.is_empty() {
+            return Ok(0);
+        }
+        buf[0] = self.0[0];
+        self.0 = &self.0[1..];
+        return Ok(1);
+    }
+}
+
+fn events(input: &str) -> Result<Vec<(String, Event)>, JSONParseError> {
+    let mut reader = EventReader::new(Trickle(input.as_bytes()));
+    let mut result = vec![];
+    while let Some(event) = reader.next_event()? {
+        result.push((format!("/{}", reader.path().join("/")), event));
+    }
+    return Ok(result);
+}
+
+fn transcode_str<F: FnMut(&[String], &Event) -> Action>(
+    input: &str,
+    config: &SerializerConfig,
+    f: F,
+) -> Result<String, JSONStreamError> {
+    let output = transcode(input.as_bytes(), vec![], config, f)?;
+    return Ok(String::from_utf8(output).unwrap());
+}
+
+#[test]
+fn test_event_reader() {
+    let input =
+        r#" {"a": [1, -2.5E+3, "x\u00e9\ud83d\ude00\n"], "b": {"c": null, "d": {}}, "e": true} "#;
+    let expected = vec![
+        ("/", Event::StartObject),
+        ("/a", Event::Key("a".to_owned())),
+        ("/a", Event::StartArray),
+        ("/a/0", Event::Number("1".to_owned())),
+        ("/a/1", Event::Number("-2.5E+3".to_owned())),
+        ("/a/2", Event::String("xé😀\n".to_owned())),
+        ("/a", Event::EndArray),
+        ("/b", Event::Key("b".to_owned())),
+        ("/b", Event::StartObject),
+        ("/b/c", Event::Key("c".to_owned())),
+        ("/b/c", Event::Null),
+        ("/b/d", Event::Key("d".to_owned())),
+        ("/b/d", Event::StartObject),
+        ("/b/d", Event::EndObject),
+        ("/b", Event::EndObject),
+        ("/e", Event::Key("e".to_owned())),
+        ("/e", Event::Bool(true)),
+        ("/", Event::EndObject),
+    ];
+    let actual = events(input).unwrap();
+    assert_eq!(actual.len(), expected.len());
+    for (actual, expected) in actual.iter().zip(expected.iter()) {
+        assert_eq!((actual.0.as_str(), &actual.1), (expected.0, &expected.1));
+    }
+    assert_eq!(
+        events("[[], 0]").unwrap(),
+        vec![
+            ("/".to_owned(), Event::StartArray),
+            ("/0".to_owned(), Event::StartArray),
+            ("/0".to_owned(), Event::EndArray),
+            ("/1".to_owned(), Event::Number("0".to_owned())),
+            ("/".to_owned(), Event::EndArray),
+        ]
+    );
+    assert_eq!(
+        events("\"é\"").unwrap(),
+        vec![("/".to_owned(), Event::String("é".to_owned()))]
+    );
+}
+
+#[test]
+fn test_event_reader_errors() {
+    for s in vec![
+        ("", "String ended unexpectedly", 0),
+        ("[1,]", "Unexpected charachter ] at position 3", 3),
+        ("[1 2]", "Unexpected charachter 2 at position 3", 3),
+        ("{\"a\" 1}", "Unexpected charachter 1 at position 5", 5),
+        ("{1: 2}", "Unexpected charachter 1 at position 1", 1),
+        ("01", "Unexpected charachter 1 at position 1", 1),
+        ("1.", "String ended unexpectedly", 2),
+        ("-x", "Unexpected charachter x at position 1", 1),
+        ("tru", "String ended unexpectedly", 3),
+        ("nul1", "Unexpected charachter 1 at position 3", 3),
+        ("[1] é", "Unexpected charachter é at position 4", 4),
+        ("\"a\\x\"", "Invalid escape sequence \\x at position 2", 2),
+        (
+            "\"\\u12g4\"",
+            "Invalid escape sequence \\u12g at position 1",
+            1,
+        ),
+        (
+            "\"\\ud800\"",
+            "Invalid escape sequence \\ud800 at position 1",
+            1,
+        ),
+        ("\"a\nb\"", "Unexpected charachter \n at position 2", 2),
+        ("[\"a\"", "String ended unexpectedly", 4),
+    ] {
+        println!("Checking {}", s.0);
+        let err = events(s.0).unwrap_err();
+        assert_eq!(err.reason, s.1);
+        assert_eq!(err.position, Some(s.2));
+    }
+    let err = events_of(&[b'"', b'a', 0xFF, b'"']).unwrap_err();
+    assert_eq!(
+        (err.reason.as_str(), err.position),
+        ("Invalid UTF-8", Some(2))
+    );
+    let options = ParserOptions {
+        max_depth: Some(2),
+        max_string_length: Some(3),
+        ..ParserOptions::default()
+    };
+    for s in vec![
+        ("[[1]]", true),
+        ("[[[1]]]", false),
+        ("[\"abc\"]", true),
+        ("\"abcd\"", false),
+    ] {
+        println!("Checking {}", s.0);
+        let result: Result<Vec<Event>, JSONParseError> =
+            EventReader::with_options(s.0.as_bytes(), &options).collect();
+        assert_eq!(result.is_ok(), s.1);
+    }
+}
+
+fn events_of(bytes: &[u8]) -> Result<Vec<Event>, JSONParseError> {
+    return EventReader::new(bytes).collect();
+}
+
+#[test]
+fn test_event_writer() {
+    let input = r#"{"a": [1, 2.5, "x\"y"], "b": {}, "c": [], "d": {"e": null, "f": [true]}}"#;
+    let value: JSONValue = input.parse().unwrap();
+    for config in vec![SerializerConfig::compact(), SerializerConfig::pretty(2)] {
+        println!("Checking {:?}", config);
+        let mut writer = EventWriter::new(vec![], &config);
+        for event in EventReader::new(input.as_bytes()) {
+            writer.write(&event.unwrap()).unwrap();
+        }
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        let expected = value.to_string_with(&config);
+        #[cfg(not(any(feature = "preserve_order", feature = "btree_map")))]
+        assert_eq!(output.parse::<JSONValue>().unwrap(), value);
+        #[cfg(any(feature = "preserve_order", feature = "btree_map"))]
+        assert_eq!(output, expected);
+        assert_eq!(output.len(), expected.len());
+    }
+
+    for s in vec![
+        (vec![Event::Key("a".to_owned())], "Unexpected key a"),
+        (
+            vec![Event::StartArray, Event::EndObject],
+            "Unexpected EndObject",
+        ),
+        (
+            vec![Event::StartObject, Event::Null],
+            "Unexpected Null in place of a key",
+        ),
+        (
+            vec![Event::Null, Event::Null],
+            "Unexpected Null after the end of the document",
+        ),
+        (vec![Event::Number("01".to_owned())], "Invalid number 01"),
+        (vec![Event::StartArray], "Document is incomplete"),
+        (vec![], "Document is incomplete"),
+    ] {
+        println!("Checking {}", s.1);
+        let mut writer = EventWriter::new(vec![], &SerializerConfig::compact());
+        let err =
+            s.0.iter()
+                .map(|event| writer.write(event))
+                .find(|result| result.is_err())
+                .unwrap_or_else(|| writer.finish().map(|_| ()))
+                .unwrap_err();
+        assert_eq!(err.reason, s.1);
+    }
+    assert_eq!(Event::number(2.0), Event::Number("2".to_owned()));
+    assert_eq!(Event::number(0.5), Event::Number("0.5".to_owned()));
+}
+
+#[test]
+fn test_transcode() {
+    let input = r#"[{"id": 12345678901234567890, "password": {"hash": "x"}, "name": "a"}, {"id": 2, "name": "b", "password": "y"}]"#;
+    let compact = SerializerConfig::compact();
+    let keep = transcode_str(input, &compact, |_, _| Action::Keep).unwrap();
+    assert_eq!(
+        keep,
+        r#"[{"id":12345678901234567890,"password":{"hash":"x"},"name":"a"},{"id":2,"name":"b","password":"y"}]"#
+    );
+
+    let stripped = transcode_str(input, &compact, |path, event| match *event {
+        Event::Key(ref key) if key == "password" && path.len() == 2 => Action::Drop,
+        _ => Action::Keep,
+    })
+    .unwrap();
+    assert_eq!(
+        stripped,
+        r#"[{"id":12345678901234567890,"name":"a"},{"id":2,"name":"b"}]"#
+    );
+
+    let changed = transcode_str(input, &compact, |path, event| match *event {
+        Event::Key(ref key) if key == "name" => {
+            Action::Replace(vec![Event::Key("login".to_owned())])
+        }
+        Event::String(ref s) if path.last().map(|k| k.as_str()) == Some("name") => {
+            Action::Replace(vec![Event::String(s.to_uppercase())])
+        }
+        Event::StartObject if path.last().map(|k| k.as_str()) == Some("password") => {
+            Action::Replace(vec![Event::Null])
+        }
+        Event::EndObject if path.len() == 1 => Action::Replace(vec![
+            Event::Key("seen".to_owned()),
+            Event::Bool(true),
+            Event::EndObject,
+        ]),
+        _ => Action::Keep,
+    })
+    .unwrap();
+    assert_eq!(
+        changed,
+        r#"[{"id":12345678901234567890,"password":null,"login":"A","seen":true},{"id":2,"login":"B","password":"y","seen":true}]"#
+    );
+
+    let pretty = transcode_str("[1, {\"a\": []}]", &SerializerConfig::pretty(2), |_, _| {
+        Action::Keep
+    })
+    .unwrap();
+    assert_eq!(pretty, "[\n  1,\n  {\n    \"a\": []\n  }\n]");
+
+    let err = transcode_str("[1, }", &compact, |_, _| Action::Keep).unwrap_err();
+    assert_eq!(err.position, Some(4));
+    let err = transcode_str("[1]", &compact, |_, _| Action::Replace(vec![])).unwrap_err();
+    assert_eq!(err.reason, "Document is incomplete");
+}
+
+#[test]
+fn test_reformat() {
+    let input = "{\"b\": [1.50, {}, [2]], \"a\": \"\\u00e9\"}";
+    for s in vec![
+        (
+            SerializerConfig::compact(),
+            "{\"b\":[1.50,{},[2]],\"a\":\"\u{e9}\"}",
+        ),
+        (
+            SerializerConfig::pretty(2),
+            "{\n  \"b\": [\n    1.50,\n    {},\n    [\n      2\n    ]\n  ],\n  \"a\": \"\u{e9}\"\n}",
+        ),
+    ] {
+        println!("Checking {:?}", s.0);
+        let output = reformat(Trickle(input.as_bytes()), vec![], &s.0).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), s.1);
+    }
+    let err = reformat("[1, 2".as_bytes(), vec![], &SerializerConfig::compact()).unwrap_err();
+    assert_eq!(err.reason, "String ended unexpectedly");
+}
+
+#[test]
+fn test_split_and_concat() {
+    let input = r#"[1, {"a": [2, 3]}, "x", [], null]"#;
+    for s in vec![
+        (
+            1,
+            vec!["[1]", r#"[{"a":[2,3]}]"#, r#"["x"]"#, "[[]]", "[null]"],
+        ),
+        (2, vec![r#"[1,{"a":[2,3]}]"#, r#"["x",[]]"#, "[null]"]),
+        (5, vec![r#"[1,{"a":[2,3]},"x",[],null]"#]),
+        (10, vec![r#"[1,{"a":[2,3]},"x",[],null]"#]),
+    ] {
+        println!("Checking {}", s.0);
+        let outputs = split_to_strings(input, s.0);
+        assert_eq!(outputs, s.1);
+        let joined = concat_arrays(
+            outputs.iter().map(|chunk| chunk.as_bytes()),
+            vec![],
+            &SerializerConfig::compact(),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(joined).unwrap(),
+            r#"[1,{"a":[2,3]},"x",[],null]"#
+        );
+    }
+    assert_eq!(split_to_strings("[]", 2), Vec::<String>::new());
+    let empty = concat_arrays(Vec::<&[u8]>::new(), vec![], &SerializerConfig::compact());
+    assert_eq!(empty.unwrap(), b"[]");
+
+    for s in vec![
+        ("{}", "Expected a top-level array"),
+        ("[1, 2", "String ended unexpectedly"),
+        ("[1] 2", "Unexpected charachter 2 at position 4"),
+    ] {
+        println!("Checking {}", s.0);
+        let err = split_array(s.0.as_bytes(), 1, &SerializerConfig::compact(), |_| {
+            Ok(vec![])
+        })
+        .unwrap_err();
+        assert_eq!(err.reason, s.1);
+        let err =
+            concat_arrays(vec![s.0.as_bytes()], vec![], &SerializerConfig::compact()).unwrap_err();
+        assert_eq!(err.reason, s.1);
+    }
+}
+
+fn split_to_strings(input: &str, chunk_size: usize) -> Vec<String> {
+    let outputs = RefCell::new(vec![]);
+    let count = split_array(
+        input.as_bytes(),
+        chunk_size,
+        &SerializerConfig::compact(),
+        |_| {
+            outputs.borrow_mut().push(vec![]);
+            Ok(Chunk(&outputs))
+        },
+    )
+    .unwrap();
+    assert_eq!(count, outputs.borrow().len());
+    return outputs
+        .into_inner()
+        .into_iter()
+        .map(|bytes| String::from_utf8(bytes).unwrap())
+        .collect();
+}
+
+// Appends to the last of the shared buffers
+struct Chunk<'a>(&'a RefCell<Vec<Vec<u8>>>);
+
+impl<'a> Write for Chunk<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0
+            .borrow_mut()
+            .last_mut()
+            .unwrap()
+            .extend_from_slice(buf);
+        return Ok(buf.len());
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        return Ok(());
+    }
+}
+
+#[test]
+fn test_array_items() {
+    let items: Vec<JSONValue> = ArrayItems::new(Trickle(b"[1, {\"a\": [true, null]}, \"x\"] "))
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(
+        JSONValue::JSONArray(items.into_iter().map(Box::new).collect()),
+        "[1, {\"a\": [true, null]}, \"x\"]".parse().unwrap()
+    );
+    for s in vec![
+        ("{}", vec![Err("Expected a top-level array".to_owned())]),
+        ("[]", vec![]),
+        (
+            "[1, x]",
+            vec![
+                Ok(1.0),
+                Err("Unexpected charachter x at position 4".to_owned()),
+            ],
+        ),
+        (
+            "[1] 2",
+            vec![
+                Ok(1.0),
+                Err("Unexpected charachter 2 at position 4".to_owned()),
+            ],
+        ),
+    ] {
+        println!("Checking {}", s.0);
+        let results: Vec<Result<f64, String>> = ArrayItems::new(s.0.as_bytes())
+            .map(|item| match item {
+                Ok(JSONValue::JSONNumber(n)) => Ok(n),
+                Ok(other) => panic!("unexpected {:?}", other),
+                Err(err) => Err(err.reason),
+            })
+            .collect();
+        assert_eq!(results, s.1);
+    }
+}
+
+#[test]
+fn test_stream_head_and_sample() {
+    let array = format!(
+        "  [{}]",
+        (0..100)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    let lines = (0..100).map(|i| format!("{}\n", i)).collect::<String>();
+    let numbers = |values: Vec<JSONValue>| -> Vec<usize> {
+        return values
+            .iter()
+            .map(|value| match *value {
+                JSONValue::JSONNumber(n) => n as usize,
+                _ => panic!("unexpected {:?}", value),
+            })
+            .collect();
+    };
+    for input in vec![&array, &lines] {
+        println!("Checking {}", &input[..5]);
+        let head = stream_head(Trickle(input.as_bytes()), 3).unwrap();
+        assert_eq!(numbers(head), vec![0, 1, 2]);
+        let all = stream_head(input.as_bytes(), 1000).unwrap();
+        assert_eq!(numbers(all), (0..100).collect::<Vec<_>>());
+
+        let sample = numbers(stream_sample(input.as_bytes(), 10, &mut SplitMix64::new(7)).unwrap());
+        assert_eq!(sample.len(), 10);
+        assert!(sample.windows(2).all(|pair| pair[0] < pair[1]));
+        let again = numbers(stream_sample(input.as_bytes(), 10, &mut SplitMix64::new(7)).unwrap());
+        assert_eq!(sample, again);
+        let everything = stream_sample(input.as_bytes(), 200, &mut SplitMix64::new(7)).unwrap();
+        assert_eq!(numbers(everything), (0..100).collect::<Vec<_>>());
+    }
+    // head stops before the broken tail, sampling reads all of it
+    assert_eq!(stream_head("[1, 2, x".as_bytes(), 2).unwrap().len(), 2);
+    assert!(stream_sample("[1, 2, x".as_bytes(), 2, &mut SplitMix64::new(1)).is_err());
+    assert_eq!(stream_head("".as_bytes(), 2).unwrap(), vec![]);
+}
+
+#[test]
+fn test_stream_pluck() {
+    let array = r#"[{"user": {"id": 1}}, {"user": {}}, {"user": {"id": [2]}}]"#;
+    let lines = "{\"user\": {\"id\": 1}}\n{\"user\": {}}\n{\"user\": {\"id\": [2]}}\n";
+    for input in vec![array, lines] {
+        println!("Checking {}", input);
+        let expected: JSONValue = "[1, null, [2]]".parse().unwrap();
+        let plucked = stream_pluck(Trickle(input.as_bytes()), "/user/id").unwrap();
+        assert_eq!(
+            JSONValue::JSONArray(plucked.into_iter().map(Box::new).collect()),
+            expected
+        );
+        let doc: JSONValue = array.parse().unwrap();
+        assert_eq!(
+            doc.pluck("/user/id"),
+            stream_pluck(input.as_bytes(), "/user/id").unwrap()
+        );
+    }
+    assert!(stream_pluck("[{}, x]".as_bytes(), "/a").is_err());
+}
+
+#[derive(Debug, PartialEq)]
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+impl FromJSONValue for Point {
+    fn from_json(value: &JSONValue) -> Result<Point, JSONConvertError> {
+        let field = |key: &str| match value.pointer(&format!("/{}", key)) {
+            Some(field) => return convert_at(field, key),
+            None => return Err(JSONConvertError::new(format!("Missing {}", key))),
+        };
+        return Ok(Point {
+            x: field("x")?,
+            y: field("y")?,
+        });
+    }
+}
+
+#[test]
+fn test_stream_array_field() {
+    let input =
+        r#"{"name": "path", "data": {"points": [{"x": 1, "y": 2}, {"x": 3, "y": 4}], "n": 2}}"#;
+    let mut points = vec![];
+    let rest = stream_array_field(Trickle(input.as_bytes()), "/data/points", |point: Point| {
+        points.push(point);
+        return Ok(());
+    })
+    .unwrap();
+    assert_eq!(points, vec![Point { x: 1, y: 2 }, Point { x: 3, y: 4 }]);
+    assert_eq!(
+        rest,
+        r#"{"name": "path", "data": {"points": [], "n": 2}}"#
+            .parse()
+            .unwrap()
+    );
+    let mut sum = 0;
+    stream_array_field("[[1, 2], [3, 4]]".as_bytes(), "/1", |n: u32| {
+        sum += n;
+        return Ok(());
+    })
+    .unwrap();
+    assert_eq!(sum, 7);
+
+    for s in vec![
+        (
+            r#"{"points": [{"x": 1, "y": 2}, {"x": 1, "y": "2"}]}"#,
+            "Expected i32, got string \"2\" at /points/1/y",
+            Some(30),
+        ),
+        (
+            r#"{"points": [{"x": 1, "y": 2}, {"x": -1, "y": 0}]}"#,
+            "Negative x at /points/1",
+            Some(30),
+        ),
+        (r#"{"points": {}}"#, "Expected an array at /points", None),
+        (r#"{"other": []}"#, "Expected an array at /points", None),
+        ("[", "String ended unexpectedly", Some(1)),
+    ] {
+        println!("Checking {}", s.0);
+        let err = stream_array_field(s.0.as_bytes(), "/points", |point: Point| {
+            if point.x < 0 {
+                return Err(JSONConvertError::new("Negative x".to_owned()));
+            }
+            return Ok(());
+        })
+        .unwrap_err();
+        assert_eq!(err.reason, s.1);
+        assert_eq!(err.position, s.2);
+    }
+}
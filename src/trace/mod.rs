@@ -0,0 +1,86 @@
+use super::*;
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+#[cfg(test)]
+mod tests;
+
+/// What the parser reports to the active `Subscriber`
+#[derive(Debug, Clone, Copy)]
+pub enum ParseEvent<'a> {
+    Start {
+        input_bytes: usize,
+    },
+    /// One of the `ParserOptions` limits was hit; followed by `End` with the same error
+    LimitViolation {
+        error: &'a JSONParseError,
+    },
+    End {
+        input_bytes: usize,
+        /// Values parsed, containers included, up to the error if there was one
+        nodes: usize,
+        /// Heap estimate of the value tree, as counted for `max_allocated_bytes`
+        allocated_bytes: usize,
+        elapsed: Duration,
+        error: Option<&'a JSONParseError>,
+    },
+}
+
+/// Receives parse events, e.g. to forward them to the `tracing` or `log`
+/// crates or to metrics. Implemented for closures.
+pub trait Subscriber: Send + Sync {
+    fn event(&self, event: &ParseEvent);
+}
+
+impl<F: Fn(&ParseEvent) + Send + Sync> Subscriber for F {
+    fn event(&self, event: &ParseEvent) {
+        self(event);
+    }
+}
+
+static GLOBAL: RwLock<Option<Arc<dyn Subscriber>>> = RwLock::new(None);
+// Skips the lock on every parse while no subscriber was ever installed
+static GLOBAL_SET: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static SCOPED: RefCell<Option<Arc<dyn Subscriber>>> = const { RefCell::new(None) };
+}
+
+/// Installs the subscriber for parses on all threads, replacing the previous one.
+pub fn set_subscriber<S: Subscriber + 'static>(subscriber: S) {
+    *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(subscriber));
+    GLOBAL_SET.store(true, Ordering::Release);
+}
+
+pub fn clear_subscriber() {
+    *GLOBAL.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+/// Runs `f` with `subscriber` receiving the events of parses on this thread
+/// instead of the global one.
+pub fn with_subscriber<S: Subscriber + 'static, T, F: FnOnce() -> T>(subscriber: S, f: F) -> T {
+    let previous = SCOPED.with(|scoped| scoped.replace(Some(Arc::new(subscriber))));
+    // restores the previous subscriber even if `f` panics
+    struct Restore(Option<Arc<dyn Subscriber>>);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            SCOPED.with(|scoped| *scoped.borrow_mut() = previous);
+        }
+    }
+    let _restore = Restore(previous);
+    return f();
+}
+
+/// The subscriber parses on this thread report to, if any
+pub(crate) fn current() -> Option<Arc<dyn Subscriber>> {
+    if let Some(subscriber) = SCOPED.with(|scoped| scoped.borrow().clone()) {
+        return Some(subscriber);
+    }
+    if !GLOBAL_SET.load(Ordering::Acquire) {
+        return None;
+    }
+    return GLOBAL.read().unwrap_or_else(|e| e.into_inner()).clone();
+}
@@ -0,0 +1,107 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use std::sync::Mutex;
+
+// Events flattened to owned data, so the closure can keep them
+#[derive(Debug, PartialEq)]
+enum Seen {
+    Start(usize),
+    Limit(JSONParseErrorKind),
+    End(usize, usize, bool),
+}
+
+fn collect<F: FnOnce()>(f: F) -> Vec<Seen> {
+    let seen = Arc::new(Mutex::new(vec![]));
+    let sink = seen.clone();
+    with_subscriber(
+        move |event: &ParseEvent| {
+            let event = match *event {
+                ParseEvent::Start { input_bytes } => Seen::Start(input_bytes),
+                ParseEvent::LimitViolation { error } => Seen::Limit(error.kind),
+                ParseEvent::End {
+                    input_bytes,
+                    nodes,
+                    error,
+                    ..
+                } => Seen::End(input_bytes, nodes, error.is_some()),
+            };
+            sink.lock().unwrap().push(event);
+        },
+        f,
+    );
+    let seen = seen.lock().unwrap().drain(..).collect();
+    return seen;
+}
+
+#[test]
+fn test_parse_events() {
+    let seen = collect(|| {
+        "[1, {\"a\": null}]".parse::<JSONValue>().unwrap();
+        "[1,".parse::<JSONValue>().unwrap_err();
+    });
+    assert_eq!(
+        seen,
+        vec![
+            Seen::Start(16),
+            Seen::End(16, 4, false),
+            Seen::Start(3),
+            Seen::End(3, 3, true),
+        ]
+    );
+}
+
+#[test]
+fn test_limit_events() {
+    let options = ParserOptions {
+        max_depth: Some(1),
+        ..ParserOptions::default()
+    };
+    let seen = collect(|| {
+        JSONValue::parse_with("[[1]]", &options).unwrap_err();
+    });
+    assert_eq!(
+        seen,
+        vec![
+            Seen::Start(5),
+            Seen::Limit(JSONParseErrorKind::LimitExceeded),
+            Seen::End(5, 2, true),
+        ]
+    );
+    // the scoped subscriber is gone afterwards
+    assert_eq!(collect(|| ()), vec![]);
+}
+
+#[test]
+fn test_allocated_bytes_reported() {
+    let allocated = Arc::new(Mutex::new(0));
+    let sink = allocated.clone();
+    with_subscriber(
+        move |event: &ParseEvent| {
+            if let ParseEvent::End {
+                allocated_bytes, ..
+            } = *event
+            {
+                *sink.lock().unwrap() = allocated_bytes;
+            }
+        },
+        || "[\"abc\"]".parse::<JSONValue>().unwrap(),
+    );
+    assert!(*allocated.lock().unwrap() > 0);
+}
+
+#[test]
+fn test_global_subscriber() {
+    let starts = Arc::new(Mutex::new(0));
+    let sink = starts.clone();
+    set_subscriber(move |event: &ParseEvent| {
+        if let ParseEvent::Start { .. } = *event {
+            *sink.lock().unwrap() += 1;
+        }
+    });
+    // other tests may parse meanwhile, so only a lower bound holds
+    "1".parse::<JSONValue>().unwrap();
+    clear_subscriber();
+    assert!(*starts.lock().unwrap() >= 1);
+    assert!(current().is_none());
+}
@@ -0,0 +1,122 @@
+use super::*;
+use serializer::SerializerConfig;
+
+#[cfg(test)]
+mod tests;
+
+const ELLIPSIS: &str = "…";
+// Limits halve down to these while the value doesn't fit the budget
+const MIN_STRING_CHARS: usize = 8;
+const MIN_ENTRIES: usize = 1;
+
+/// How `truncate_for_log` shortens a value before it starts cutting deeper to
+/// meet the byte budget.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TruncatePolicy {
+    /// Strings longer than this many chars are cut, ending in `…(+N chars)`
+    pub max_string_chars: usize,
+    /// Arrays keep this many items, followed by `"…(+N items)"`
+    pub max_array_items: usize,
+    /// Objects keep this many members, the first in map iteration order,
+    /// plus `"…": "(+N members)"`
+    pub max_object_members: usize,
+    /// Arrays and objects nested deeper are replaced with
+    /// `"…(array of N items)"` or `"…(object of N members)"`
+    pub max_depth: usize,
+}
+
+impl Default for TruncatePolicy {
+    fn default() -> TruncatePolicy {
+        return TruncatePolicy {
+            max_string_chars: 256,
+            max_array_items: 20,
+            max_object_members: 50,
+            max_depth: 8,
+        };
+    }
+}
+
+impl JSONValue {
+    /// A summary of the value for logs whose compact JSON text is at most
+    /// `max_bytes` long: the value shortened by `policy`, then with the limits
+    /// halved until it fits. Elided parts are replaced by `…` markers saying how
+    /// much is missing, so the result is still valid JSON but not the same
+    /// structure. If even the smallest summary doesn't fit, the result is the
+    /// single string `"…(N bytes)"` with the size of the whole value.
+    pub fn truncate_for_log(&self, max_bytes: usize, policy: &TruncatePolicy) -> JSONValue {
+        let mut policy = *policy;
+        loop {
+            let truncated = truncate(self, &policy, 0);
+            if size_of(&truncated) <= max_bytes {
+                return truncated;
+            }
+            let smaller = TruncatePolicy {
+                max_string_chars: (policy.max_string_chars / 2).max(MIN_STRING_CHARS),
+                max_array_items: (policy.max_array_items / 2).max(MIN_ENTRIES),
+                max_object_members: (policy.max_object_members / 2).max(MIN_ENTRIES),
+                max_depth: policy.max_depth.saturating_sub(1),
+            };
+            if smaller == policy {
+                break;
+            }
+            policy = smaller;
+        }
+        return JSONValue::JSONString(format!("{}({} bytes)", ELLIPSIS, size_of(self)));
+    }
+}
+
+fn truncate(value: &JSONValue, policy: &TruncatePolicy, depth: usize) -> JSONValue {
+    match *value {
+        JSONValue::JSONString(ref s) => {
+            let chars = s.chars().count();
+            if chars <= policy.max_string_chars {
+                return value.clone();
+            }
+            let kept: String = s.chars().take(policy.max_string_chars).collect();
+            let elided = chars - policy.max_string_chars;
+            return JSONValue::JSONString(format!("{}{}(+{} chars)", kept, ELLIPSIS, elided));
+        }
+        JSONValue::JSONRaw(_) => return truncate(&value.expand_raw(), policy, depth),
+        JSONValue::JSONArray(ref items) => {
+            if depth >= policy.max_depth && !items.is_empty() {
+                return marker(format!("array of {} items", items.len()));
+            }
+            let mut result: Vec<Box<JSONValue>> = items
+                .iter()
+                .take(policy.max_array_items)
+                .map(|item| Box::new(truncate(item, policy, depth + 1)))
+                .collect();
+            if items.len() > policy.max_array_items {
+                let elided = items.len() - policy.max_array_items;
+                result.push(Box::new(marker(format!("+{} items", elided))));
+            }
+            return JSONValue::JSONArray(result);
+        }
+        JSONValue::JSONObject(ref members) => {
+            if depth >= policy.max_depth && !members.is_empty() {
+                return marker(format!("object of {} members", members.len()));
+            }
+            let mut result = JSONMap::new();
+            for (key, member) in members.iter().take(policy.max_object_members) {
+                result.insert(key.clone(), Box::new(truncate(member, policy, depth + 1)));
+            }
+            if members.len() > policy.max_object_members {
+                let elided = members.len() - policy.max_object_members;
+                result.insert(
+                    ELLIPSIS.to_owned(),
+                    Box::new(JSONValue::JSONString(format!("(+{} members)", elided))),
+                );
+            }
+            return JSONValue::JSONObject(result);
+        }
+        _ => return value.clone(),
+    }
+}
+
+fn marker(s: String) -> JSONValue {
+    return JSONValue::JSONString(format!("{}({})", ELLIPSIS, s));
+}
+
+fn size_of(value: &JSONValue) -> usize {
+    return value.to_string_with(&SerializerConfig::compact()).len();
+}
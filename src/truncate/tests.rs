@@ -0,0 +1,72 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use serializer::SerializerConfig;
+
+fn parse(s: &str) -> JSONValue {
+    return s.parse().unwrap();
+}
+
+#[test]
+fn test_policy() {
+    let policy = TruncatePolicy {
+        max_string_chars: 3,
+        max_array_items: 2,
+        max_object_members: 1,
+        max_depth: 2,
+    };
+    for (value, expected) in vec![
+        (r#""abc""#, r#""abc""#),
+        (r#""abcdé""#, r#""abc…(+2 chars)""#),
+        (r#""ééééé""#, r#""ééé…(+2 chars)""#),
+        ("[1, 2, 3, 4]", r#"[1, 2, "…(+2 items)"]"#),
+        (r#"{"a": {"b": 1}}"#, r#"{"a": {"b": 1}}"#),
+        (
+            r#"{"a": {"b": {"c": 1}}}"#,
+            r#"{"a": {"b": "…(object of 1 members)"}}"#,
+        ),
+        (
+            "[[[1, 2]], [[]], {}]",
+            r#"[["…(array of 2 items)"], [[]], "…(+1 items)"]"#,
+        ),
+        (r#"{"a": 1, "b": 1}"#, r#"{"a": 1, "…": "(+1 members)"}"#),
+        ("null", "null"),
+    ] {
+        println!("Checking {}", value);
+        let mut truncated = parse(value).truncate_for_log(usize::MAX, &policy);
+        if let JSONValue::JSONObject(ref mut map) = truncated {
+            // which member is kept depends on the map
+            if map.contains_key("b") && map.len() == 2 {
+                let member = map.remove("b").unwrap();
+                map.insert("a".to_owned(), member);
+            }
+        }
+        assert_eq!(truncated, parse(expected));
+    }
+}
+
+#[test]
+fn test_budget() {
+    let items: Vec<String> = (0..100)
+        .map(|i| format!(r#"{{"id": {}, "text": "{}"}}"#, i, "x".repeat(500)))
+        .collect();
+    let value = parse(&format!("[{}]", items.join(", ")));
+    for max_bytes in vec![100000, 4096, 1024, 200, 60] {
+        println!("Checking {}", max_bytes);
+        let truncated = value.truncate_for_log(max_bytes, &TruncatePolicy::default());
+        let text = truncated.to_string_with(&SerializerConfig::compact());
+        assert!(text.len() <= max_bytes, "{} is over {}", text, max_bytes);
+        assert!(text.contains(" items)\""));
+    }
+    let truncated = value.truncate_for_log(1024, &TruncatePolicy::default());
+    assert_eq!(
+        truncated.pointer("/0/id"),
+        Some(&JSONValue::JSONNumber(0.0))
+    );
+
+    let size = value.to_string_with(&SerializerConfig::compact()).len();
+    assert_eq!(
+        value.truncate_for_log(10, &TruncatePolicy::default()),
+        JSONValue::JSONString(format!("…({} bytes)", size))
+    );
+}
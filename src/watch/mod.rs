@@ -0,0 +1,180 @@
+use super::*;
+use patch::{JSONPatchError, PatchOperation};
+use pattern::PathPattern;
+use pointer::parse_pointer;
+use std::collections::BTreeMap;
+
+#[cfg(test)]
+mod tests;
+
+type Callback = Box<dyn FnMut(&JSONValue, &[String])>;
+
+/// Handle of a watcher, for `WatchedDocument::unwatch`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchId(usize);
+
+struct Derived {
+    pattern: PathPattern,
+    compute: Box<dyn Fn(&JSONValue) -> JSONValue>,
+    value: JSONValue,
+}
+
+/// A document that runs callbacks and recomputes derived values when a change
+/// touches the paths they depend on, e.g. to reload the parts of an application
+/// affected by a configuration change. A change at a path touches watchers of
+/// that path, of its ancestors (their value changed too) and of its descendants
+/// (they may be gone or replaced). Adding or removing array items touches the
+/// whole array, since later items move.
+pub struct WatchedDocument {
+    document: JSONValue,
+    watchers: Vec<(WatchId, PathPattern, Callback)>,
+    derived: BTreeMap<String, Derived>,
+    next_id: usize,
+}
+
+impl WatchedDocument {
+    pub fn new(document: JSONValue) -> WatchedDocument {
+        return WatchedDocument {
+            document,
+            watchers: vec![],
+            derived: BTreeMap::new(),
+            next_id: 0,
+        };
+    }
+
+    pub fn document(&self) -> &JSONValue {
+        return &self.document;
+    }
+
+    pub fn into_inner(self) -> JSONValue {
+        return self.document;
+    }
+
+    /// Calls `callback` after every change touching a path matching `pattern`,
+    /// with the changed document and the touched pointers.
+    pub fn watch<F>(&mut self, pattern: PathPattern, callback: F) -> WatchId
+    where
+        F: FnMut(&JSONValue, &[String]) + 'static,
+    {
+        let id = WatchId(self.next_id);
+        self.next_id += 1;
+        self.watchers.push((id, pattern, Box::new(callback)));
+        return id;
+    }
+
+    /// Removes a watcher. Returns false if it was already removed.
+    pub fn unwatch(&mut self, id: WatchId) -> bool {
+        let len = self.watchers.len();
+        self.watchers.retain(|watcher| watcher.0 != id);
+        return self.watchers.len() != len;
+    }
+
+    /// Adds a value computed from the document, recomputed whenever a change
+    /// touches a path matching `pattern`. Replaces a derived value of the same name.
+    pub fn derive<F>(&mut self, name: &str, pattern: PathPattern, compute: F)
+    where
+        F: Fn(&JSONValue) -> JSONValue + 'static,
+    {
+        let value = compute(&self.document);
+        let derived = Derived {
+            pattern,
+            compute: Box::new(compute),
+            value,
+        };
+        self.derived.insert(name.to_owned(), derived);
+    }
+
+    /// Current value of a derived value
+    pub fn derived(&self, name: &str) -> Option<&JSONValue> {
+        return self.derived.get(name).map(|derived| &derived.value);
+    }
+
+    /// Sets an object member or replaces an array item; `-` appends to an array
+    /// and the empty pointer replaces the whole document.
+    pub fn set_pointer(&mut self, pointer: &str, value: JSONValue) -> Result<(), JSONPatchError> {
+        let path = pointer.to_owned();
+        let op = if self.document.pointer(pointer).is_some() {
+            PatchOperation::Replace { path, value }
+        } else {
+            PatchOperation::Add { path, value }
+        };
+        return self.apply_patch(&[op]);
+    }
+
+    /// Removes and returns the value at the pointer.
+    pub fn remove_pointer(&mut self, pointer: &str) -> Result<JSONValue, JSONPatchError> {
+        let removed = self.document.pointer(pointer).cloned();
+        let op = PatchOperation::Remove {
+            path: pointer.to_owned(),
+        };
+        self.apply_patch(&[op])?;
+        return Ok(removed.expect("removed by the patch"));
+    }
+
+    /// Applies the patch atomically, like `JSONValue::apply_patch`, then
+    /// recomputes the touched derived values and runs the touched watchers.
+    pub fn apply_patch(&mut self, patch: &[PatchOperation]) -> Result<(), JSONPatchError> {
+        let mut result = self.document.clone();
+        let mut touched = vec![];
+        for op in patch {
+            match *op {
+                PatchOperation::Add { ref path, .. } | PatchOperation::Copy { ref path, .. } => {
+                    touched.push(resized(&result, path));
+                }
+                PatchOperation::Remove { ref path } => touched.push(resized(&result, path)),
+                PatchOperation::Replace { ref path, .. } => touched.push(path.clone()),
+                PatchOperation::Move { ref from, ref path } => {
+                    touched.push(resized(&result, from));
+                    touched.push(resized(&result, path));
+                }
+                PatchOperation::Test { .. } => (),
+            }
+            result.apply_patch(std::slice::from_ref(op))?;
+        }
+        self.document = result;
+        touched.sort();
+        touched.dedup();
+        self.notify(&touched);
+        return Ok(());
+    }
+
+    fn notify(&mut self, touched: &[String]) {
+        let tokens: Vec<Vec<String>> = touched
+            .iter()
+            .map(|pointer| parse_pointer(pointer).unwrap_or_default())
+            .collect();
+        for derived in self.derived.values_mut() {
+            if tokens.iter().any(|t| overlaps(&derived.pattern, t)) {
+                derived.value = (derived.compute)(&self.document);
+            }
+        }
+        for &mut (_, ref pattern, ref mut callback) in &mut self.watchers {
+            let matching: Vec<String> = touched
+                .iter()
+                .zip(&tokens)
+                .filter(|&(_, t)| overlaps(pattern, t))
+                .map(|(pointer, _)| pointer.clone())
+                .collect();
+            if !matching.is_empty() {
+                callback(&self.document, &matching);
+            }
+        }
+    }
+}
+
+// The path touched by adding or removing at `path`: the parent for array items
+fn resized(document: &JSONValue, path: &str) -> String {
+    if let Some(i) = path.rfind('/') {
+        if let Some(&JSONValue::JSONArray(_)) = document.pointer(&path[..i]) {
+            return path[..i].to_owned();
+        }
+    }
+    return path.to_owned();
+}
+
+// Whether a change at `tokens` touches a path matching the pattern: the path
+// itself, one below it or one above it
+fn overlaps(pattern: &PathPattern, tokens: &[String]) -> bool {
+    return pattern.matches_prefix(tokens)
+        || (0..tokens.len()).any(|len| pattern.matches_tokens(&tokens[..len]));
+}
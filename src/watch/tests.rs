@@ -0,0 +1,108 @@
+#![allow(clippy::useless_vec)]
+
+use super::*;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn pattern(s: &str) -> PathPattern {
+    return PathPattern::compile(s).unwrap();
+}
+
+fn config() -> WatchedDocument {
+    let document = r#"{"db": {"host": "a", "port": 1}, "workers": [1, 2, 3], "name": "x"}"#
+        .parse()
+        .unwrap();
+    return WatchedDocument::new(document);
+}
+
+#[test]
+fn test_watch() {
+    for s in vec![
+        ("/db/host", "/db/host", vec!["/db/host"]),
+        ("/db/host", "/db", vec!["/db"]),
+        ("/db", "/db/port", vec!["/db/port"]),
+        ("/db/host", "/db/port", vec![]),
+        ("/db/host", "", vec![""]),
+        ("/workers/2", "/workers/0", vec![]),
+        ("/workers/2", "/workers/-", vec!["/workers"]),
+        ("/**/port", "/db/port", vec!["/db/port"]),
+        ("/name", "/missing", vec![]),
+    ] {
+        println!("Checking {} on {}", s.0, s.1);
+        let mut document = config();
+        let calls = Rc::new(RefCell::new(vec![]));
+        let seen = calls.clone();
+        document.watch(pattern(s.0), move |_, touched| {
+            seen.borrow_mut().push(touched.to_vec());
+        });
+        document.set_pointer(s.1, JSONValue::JSONNull()).unwrap();
+        let expected: Vec<Vec<String>> = if s.2.is_empty() {
+            vec![]
+        } else {
+            vec![s.2.iter().map(|p| p.to_string()).collect()]
+        };
+        assert_eq!(*calls.borrow(), expected);
+    }
+}
+
+#[test]
+fn test_patch_and_derive() {
+    let mut document = config();
+    document.derive("url", pattern("/db"), |doc| {
+        let host = match doc.pointer("/db/host") {
+            Some(JSONValue::JSONString(host)) => host.clone(),
+            _ => String::new(),
+        };
+        return JSONValue::JSONString(format!("db://{}", host));
+    });
+    let calls = Rc::new(RefCell::new(0));
+    let seen = calls.clone();
+    let id = document.watch(pattern("/workers/0"), move |doc, _| {
+        assert_eq!(doc.pointer("/workers/0"), Some(&JSONValue::JSONNumber(2.0)));
+        *seen.borrow_mut() += 1;
+    });
+    assert_eq!(
+        document.derived("url"),
+        Some(&JSONValue::JSONString("db://a".to_owned()))
+    );
+
+    let patch = vec![
+        PatchOperation::Replace {
+            path: "/db/host".to_owned(),
+            value: JSONValue::JSONString("b".to_owned()),
+        },
+        PatchOperation::Remove {
+            path: "/workers/0".to_owned(),
+        },
+    ];
+    document.apply_patch(&patch).unwrap();
+    assert_eq!(
+        document.derived("url"),
+        Some(&JSONValue::JSONString("db://b".to_owned()))
+    );
+    assert_eq!(*calls.borrow(), 1);
+
+    // failed patches change nothing and notify no one
+    let failing = vec![
+        PatchOperation::Remove {
+            path: "/workers/0".to_owned(),
+        },
+        PatchOperation::Remove {
+            path: "/missing".to_owned(),
+        },
+    ];
+    assert!(document.apply_patch(&failing).is_err());
+    assert_eq!(*calls.borrow(), 1);
+
+    assert_eq!(
+        document.remove_pointer("/workers/1").unwrap(),
+        JSONValue::JSONNumber(3.0)
+    );
+    assert_eq!(*calls.borrow(), 2);
+    assert!(document.unwatch(id));
+    assert!(!document.unwatch(id));
+    document.remove_pointer("/workers/0").unwrap();
+    assert_eq!(*calls.borrow(), 2);
+    assert!(document.remove_pointer("/workers/0").is_err());
+    assert_eq!(document.derived("missing"), None);
+}
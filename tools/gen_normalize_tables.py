@@ -0,0 +1,122 @@
+#!/usr/bin/env python3
+"""Generates src/normalize/tables.rs from the Unicode character database.
+
+Run from the repository root with a Python whose unicodedata module is built
+from UCD 14.0.0 (Python 3.11):
+
+    python3 tools/gen_normalize_tables.py > src/normalize/tables.rs
+"""
+
+import sys
+import unicodedata
+
+UCD_VERSION = "14.0.0"
+COMMAND = "python3 tools/gen_normalize_tables.py > src/normalize/tables.rs"
+
+# Rows are packed onto lines up to this many columns, indentation included
+WIDTH = 96
+INDENT = "    "
+
+# Hangul syllables decompose algorithmically, see src/normalize/mod.rs
+HANGUL = range(0xAC00, 0xD7A4)
+
+
+def char(cp):
+    return "'\\0'" if cp == 0 else "'\\u{%X}'" % cp
+
+
+def decompositions():
+    rows = []
+    for cp in range(sys.maxunicode + 1):
+        mapping = unicodedata.decomposition(chr(cp))
+        # compatibility mappings start with a <tag>
+        if not mapping or mapping.startswith("<") or cp in HANGUL:
+            continue
+        parts = [int(part, 16) for part in mapping.split()]
+        rows.append((cp, parts[0], parts[1] if len(parts) > 1 else 0))
+    return rows
+
+
+def combining_classes():
+    rows = []
+    for cp in range(sys.maxunicode + 1):
+        ccc = unicodedata.combining(chr(cp))
+        if ccc == 0:
+            continue
+        if rows and rows[-1][1] == cp - 1 and rows[-1][2] == ccc:
+            rows[-1] = (rows[-1][0], cp, ccc)
+        else:
+            rows.append((cp, cp, ccc))
+    return rows
+
+
+def compositions(decomposed):
+    # a pair decomposition is a primary composite unless NFC leaves it apart,
+    # which covers CompositionExclusions.txt and non-starter decompositions
+    rows = []
+    for cp, first, second in decomposed:
+        if second and unicodedata.normalize("NFC", chr(first) + chr(second)) == chr(cp):
+            rows.append((first, second, cp))
+    return sorted(rows)
+
+
+def table(doc, name, kind, rows, render):
+    lines = ["/// " + doc, "pub const %s: &[%s] = &[" % (name, kind)]
+    line = ""
+    for row in rows:
+        item = render(row) + ","
+        if line and len(line) + 1 + len(item) > WIDTH:
+            lines.append(line)
+            line = ""
+        line = line + " " + item if line else INDENT + item
+    if line:
+        lines.append(line)
+    lines.append("];")
+    return "\n".join(lines)
+
+
+def main():
+    if unicodedata.unidata_version != UCD_VERSION:
+        sys.exit(
+            "unicodedata has UCD %s, expected %s"
+            % (unicodedata.unidata_version, UCD_VERSION)
+        )
+    decomposed = decompositions()
+    triple = lambda row: "(%s, %s, %s)" % tuple(char(cp) for cp in row)
+    ranged = lambda row: "(%s, %s, %d)" % (char(row[0]), char(row[1]), row[2])
+    print("// Generated from the Unicode %s character database by" % UCD_VERSION)
+    print("// `%s`; don't edit by hand." % COMMAND)
+    print()
+    print(
+        table(
+            "Canonical decompositions, one step each; the second char is `'\\0'` for singletons.",
+            "DECOMPOSITIONS",
+            "(char, char, char)",
+            decomposed,
+            triple,
+        )
+    )
+    print()
+    print(
+        table(
+            "Canonical combining classes of non-starters, as inclusive ranges.",
+            "COMBINING_CLASSES",
+            "(char, char, u8)",
+            combining_classes(),
+            ranged,
+        )
+    )
+    print()
+    print(
+        table(
+            "Primary composites, sorted by the pair they compose from.",
+            "COMPOSITIONS",
+            "(char, char, char)",
+            compositions(decomposed),
+            triple,
+        )
+    )
+
+
+if __name__ == "__main__":
+    main()